@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -10,6 +11,9 @@ use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::config_types::Verbosity;
 use codex_protocol::protocol::AskForApproval;
+use codex_protocol::protocol::BudgetDecision;
+use codex_protocol::protocol::BudgetKind;
+use codex_protocol::protocol::ClientCapabilities;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::FileChange;
 use codex_protocol::protocol::ReviewDecision;
@@ -93,11 +97,22 @@ client_request_definitions! {
         params: ListConversationsParams,
         response: ListConversationsResponse,
     },
+    /// Search the text of recorded Codex conversations.
+    SearchConversations {
+        params: SearchConversationsParams,
+        response: SearchConversationsResponse,
+    },
     /// Resume a recorded Codex conversation from a rollout file.
     ResumeConversation {
         params: ResumeConversationParams,
         response: ResumeConversationResponse,
     },
+    /// Start a new conversation that shares the history of a recorded one up
+    /// to a given turn.
+    ForkConversation {
+        params: ForkConversationParams,
+        response: ForkConversationResponse,
+    },
     ArchiveConversation {
         params: ArchiveConversationParams,
         response: ArchiveConversationResponse,
@@ -150,6 +165,10 @@ client_request_definitions! {
         params: #[ts(type = "undefined")] #[serde(skip_serializing_if = "Option::is_none")] Option<()>,
         response: GetUserSavedConfigResponse,
     },
+    GetConfigSchema {
+        params: #[ts(type = "undefined")] #[serde(skip_serializing_if = "Option::is_none")] Option<()>,
+        response: GetConfigSchemaResponse,
+    },
     SetDefaultModel {
         params: SetDefaultModelParams,
         response: SetDefaultModelResponse,
@@ -235,6 +254,11 @@ pub struct NewConversationParams {
     /// Whether to include the apply patch tool in the conversation.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub include_apply_patch_tool: Option<bool>,
+
+    /// What the client can present to the user (images, diffs, approval
+    /// prompts). Omitted capabilities are assumed to be supported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_capabilities: Option<ClientCapabilities>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -266,6 +290,9 @@ pub struct ListConversationsParams {
     /// Opaque pagination cursor returned by a previous call.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<String>,
+    /// Only list conversations whose tags include all of these.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -277,6 +304,12 @@ pub struct ConversationSummary {
     /// RFC3339 timestamp string for the session start, if available.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<String>,
+    /// Generated or user-set session title, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Tags attached to the session.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -289,6 +322,48 @@ pub struct ListConversationsResponse {
     pub next_cursor: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchConversationsParams {
+    /// Words to look for, e.g. "where I fixed the migration deadlock".
+    pub query: String,
+    /// Optional maximum number of conversations; defaults to a reasonable
+    /// server-side value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchConversationsResponse {
+    /// Matching conversations, best match first.
+    pub items: Vec<ConversationSearchHit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSearchHit {
+    pub conversation_id: ConversationId,
+    /// Absolute path to the rollout file, for `resumeConversation`.
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// RFC3339 timestamp string for the session start.
+    pub timestamp: String,
+    /// Best matching turns, best first.
+    pub excerpts: Vec<ConversationExcerpt>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationExcerpt {
+    /// 0-based index of the user turn. Pass `turn + 1` to
+    /// `forkConversation` to keep the history through this turn.
+    pub turn: usize,
+    /// Text around the match.
+    pub text: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct ResumeConversationParams {
@@ -299,6 +374,30 @@ pub struct ResumeConversationParams {
     pub overrides: Option<NewConversationParams>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationParams {
+    /// Absolute path to the rollout JSONL file of the conversation to fork.
+    pub path: PathBuf,
+    /// Number of user turns to keep (0-based index of the first turn that is
+    /// dropped). When omitted, the entire history is kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn: Option<usize>,
+    /// Optional overrides to apply when spawning the forked session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overrides: Option<NewConversationParams>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ForkConversationResponse {
+    pub conversation_id: ConversationId,
+    pub model: String,
+    pub rollout_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_messages: Option<Vec<EventMsg>>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct AddConversationSubscriptionResponse {
@@ -442,6 +541,13 @@ pub struct GetUserSavedConfigResponse {
     pub config: UserSavedConfig,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct GetConfigSchemaResponse {
+    /// JSON Schema (draft-07) of `config.toml`.
+    pub schema: serde_json::Value,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 pub struct SetDefaultModelParams {
@@ -536,6 +642,11 @@ pub struct SandboxSettings {
 pub struct SendUserMessageParams {
     pub conversation_id: ConversationId,
     pub items: Vec<InputItem>,
+    /// When a turn is already running, run this message as its own turn once
+    /// that turn (and any message queued before it) finishes, instead of
+    /// adding it to the running turn.
+    #[serde(default)]
+    pub queue: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -667,6 +778,8 @@ server_request_definitions! {
     ApplyPatchApproval,
     /// Request to exec a command.
     ExecCommandApproval,
+    /// Ask whether a turn that reached the session budget should continue.
+    SessionBudgetExceeded,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
@@ -709,6 +822,22 @@ pub struct ApplyPatchApprovalResponse {
     pub decision: ReviewDecision,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBudgetExceededParams {
+    pub conversation_id: ConversationId,
+    pub budget: BudgetKind,
+    /// The configured limit, in the budget's unit.
+    pub limit: f64,
+    /// Usage counted against the budget so far.
+    pub used: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct SessionBudgetExceededResponse {
+    pub decision: BudgetDecision,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(rename_all = "camelCase")]
@@ -843,6 +972,7 @@ mod tests {
                 base_instructions: None,
                 include_plan_tool: None,
                 include_apply_patch_tool: None,
+                client_capabilities: None,
             },
         };
         assert_eq!(
@@ -11,13 +11,18 @@ use codex_app_server_protocol::ArchiveConversationParams;
 use codex_app_server_protocol::ArchiveConversationResponse;
 use codex_app_server_protocol::AuthStatusChangeNotification;
 use codex_app_server_protocol::ClientRequest;
+use codex_app_server_protocol::ConversationExcerpt;
+use codex_app_server_protocol::ConversationSearchHit;
 use codex_app_server_protocol::ConversationSummary;
 use codex_app_server_protocol::ExecCommandApprovalParams;
 use codex_app_server_protocol::ExecCommandApprovalResponse;
 use codex_app_server_protocol::ExecOneOffCommandParams;
 use codex_app_server_protocol::ExecOneOffCommandResponse;
+use codex_app_server_protocol::ForkConversationParams;
+use codex_app_server_protocol::ForkConversationResponse;
 use codex_app_server_protocol::FuzzyFileSearchParams;
 use codex_app_server_protocol::FuzzyFileSearchResponse;
+use codex_app_server_protocol::GetConfigSchemaResponse;
 use codex_app_server_protocol::GetUserAgentResponse;
 use codex_app_server_protocol::GetUserSavedConfigResponse;
 use codex_app_server_protocol::GitDiffToRemoteResponse;
@@ -38,12 +43,16 @@ use codex_app_server_protocol::RemoveConversationSubscriptionResponse;
 use codex_app_server_protocol::RequestId;
 use codex_app_server_protocol::Result as JsonRpcResult;
 use codex_app_server_protocol::ResumeConversationParams;
+use codex_app_server_protocol::SearchConversationsParams;
+use codex_app_server_protocol::SearchConversationsResponse;
 use codex_app_server_protocol::SendUserMessageParams;
 use codex_app_server_protocol::SendUserMessageResponse;
 use codex_app_server_protocol::SendUserTurnParams;
 use codex_app_server_protocol::SendUserTurnResponse;
 use codex_app_server_protocol::ServerNotification;
 use codex_app_server_protocol::ServerRequestPayload;
+use codex_app_server_protocol::SessionBudgetExceededParams;
+use codex_app_server_protocol::SessionBudgetExceededResponse;
 use codex_app_server_protocol::SessionConfiguredNotification;
 use codex_app_server_protocol::SetDefaultModelParams;
 use codex_app_server_protocol::SetDefaultModelResponse;
@@ -68,18 +77,23 @@ use codex_core::config::load_config_as_toml;
 use codex_core::config_edit::CONFIG_KEY_EFFORT;
 use codex_core::config_edit::CONFIG_KEY_MODEL;
 use codex_core::config_edit::persist_overrides_and_clear_if_none;
+use codex_core::config_schema::config_schema;
 use codex_core::default_client::get_codex_user_agent;
 use codex_core::exec::ExecParams;
 use codex_core::exec_env::create_env;
 use codex_core::get_platform_sandbox;
 use codex_core::git_info::git_diff_to_remote;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalBatchRequestEvent;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::BudgetExceededEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem as CoreInputItem;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SessionConfiguredEvent;
 use codex_login::ServerOptions as LoginServerOptions;
 use codex_login::ShutdownHandle;
 use codex_login::run_login_server;
@@ -89,6 +103,7 @@ use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::InputMessageKind;
 use codex_protocol::protocol::USER_MESSAGE_BEGIN;
 use codex_utils_json_to_toml::json_to_toml;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::path::PathBuf;
@@ -167,9 +182,15 @@ impl CodexMessageProcessor {
             ClientRequest::ListConversations { request_id, params } => {
                 self.handle_list_conversations(request_id, params).await;
             }
+            ClientRequest::SearchConversations { request_id, params } => {
+                self.handle_search_conversations(request_id, params).await;
+            }
             ClientRequest::ResumeConversation { request_id, params } => {
                 self.handle_resume_conversation(request_id, params).await;
             }
+            ClientRequest::ForkConversation { request_id, params } => {
+                self.handle_fork_conversation(request_id, params).await;
+            }
             ClientRequest::ArchiveConversation { request_id, params } => {
                 self.archive_conversation(request_id, params).await;
             }
@@ -218,6 +239,15 @@ impl CodexMessageProcessor {
             } => {
                 self.get_user_saved_config(request_id).await;
             }
+            ClientRequest::GetConfigSchema {
+                request_id,
+                params: _,
+            } => {
+                let response = GetConfigSchemaResponse {
+                    schema: config_schema(),
+                };
+                self.outgoing.send_response(request_id, response).await;
+            }
             ClientRequest::SetDefaultModel { request_id, params } => {
                 self.set_default_model(request_id, params).await;
             }
@@ -705,11 +735,14 @@ impl CodexMessageProcessor {
         };
         let cursor_ref = cursor_obj.as_ref();
 
-        let page = match RolloutRecorder::list_conversations(
+        let required_tags = params.tags.unwrap_or_default();
+
+        let page = match RolloutRecorder::list_conversations_with_tags(
             &self.config.codex_home,
             page_size,
             cursor_ref,
             INTERACTIVE_SESSION_SOURCES,
+            &required_tags,
         )
         .await
         {
@@ -728,7 +761,7 @@ impl CodexMessageProcessor {
         let items = page
             .items
             .into_iter()
-            .filter_map(|it| extract_conversation_summary(it.path, &it.head))
+            .filter_map(|it| extract_conversation_summary(it.path, &it.head, it.title, it.tags))
             .collect();
 
         // Encode next_cursor as a plain string
@@ -744,6 +777,53 @@ impl CodexMessageProcessor {
         self.outgoing.send_response(request_id, response).await;
     }
 
+    async fn handle_search_conversations(
+        &self,
+        request_id: RequestId,
+        params: SearchConversationsParams,
+    ) {
+        let limit = params.limit.unwrap_or(20);
+        let hits = match RolloutRecorder::search_conversations(
+            &self.config.codex_home,
+            &params.query,
+            limit,
+            INTERACTIVE_SESSION_SOURCES,
+        )
+        .await
+        {
+            Ok(hits) => hits,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("failed to search conversations: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        let items = hits
+            .into_iter()
+            .map(|hit| ConversationSearchHit {
+                conversation_id: hit.conversation_id,
+                path: hit.path,
+                title: hit.title,
+                timestamp: hit.timestamp,
+                excerpts: hit
+                    .excerpts
+                    .into_iter()
+                    .map(|excerpt| ConversationExcerpt {
+                        turn: excerpt.turn,
+                        text: excerpt.text,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let response = SearchConversationsResponse { items };
+        self.outgoing.send_response(request_id, response).await;
+    }
+
     async fn handle_resume_conversation(
         &self,
         request_id: RequestId,
@@ -783,37 +863,14 @@ impl CodexMessageProcessor {
                 session_configured,
                 ..
             }) => {
-                self.outgoing
-                    .send_server_notification(ServerNotification::SessionConfigured(
-                        SessionConfiguredNotification {
-                            session_id: session_configured.session_id,
-                            model: session_configured.model.clone(),
-                            reasoning_effort: session_configured.reasoning_effort,
-                            history_log_id: session_configured.history_log_id,
-                            history_entry_count: session_configured.history_entry_count,
-                            initial_messages: session_configured.initial_messages.clone(),
-                            rollout_path: session_configured.rollout_path.clone(),
-                        },
-                    ))
+                self.send_session_configured_notification(&session_configured)
                     .await;
-                let initial_messages = session_configured.initial_messages.map(|msgs| {
-                    msgs.into_iter()
-                        .filter(|event| {
-                            // Don't send non-plain user messages (like user instructions
-                            // or environment context) back so they don't get rendered.
-                            if let EventMsg::UserMessage(user_message) = event {
-                                return matches!(user_message.kind, Some(InputMessageKind::Plain));
-                            }
-                            true
-                        })
-                        .collect()
-                });
 
                 // Reply with conversation id + model and initial messages (when present)
                 let response = codex_app_server_protocol::ResumeConversationResponse {
                     conversation_id,
                     model: session_configured.model.clone(),
-                    initial_messages,
+                    initial_messages: plain_initial_messages(session_configured.initial_messages),
                 };
                 self.outgoing.send_response(request_id, response).await;
             }
@@ -828,6 +885,94 @@ impl CodexMessageProcessor {
         }
     }
 
+    async fn handle_fork_conversation(
+        &self,
+        request_id: RequestId,
+        params: ForkConversationParams,
+    ) {
+        let ForkConversationParams {
+            path,
+            turn,
+            overrides,
+        } = params;
+        let config = match overrides {
+            Some(overrides) => {
+                derive_config_from_params(overrides, self.codex_linux_sandbox_exe.clone())
+            }
+            None => Ok(self.config.as_ref().clone()),
+        };
+        let config = match config {
+            Ok(cfg) => cfg,
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message: format!("error deriving config: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+                return;
+            }
+        };
+
+        let forked = match turn {
+            Some(turn) => {
+                self.conversation_manager
+                    .fork_conversation(turn, config, path)
+                    .await
+            }
+            None => {
+                self.conversation_manager
+                    .fork_conversation_from_latest(config, path)
+                    .await
+            }
+        };
+        match forked {
+            Ok(NewConversation {
+                conversation_id,
+                session_configured,
+                ..
+            }) => {
+                self.send_session_configured_notification(&session_configured)
+                    .await;
+
+                let response = ForkConversationResponse {
+                    conversation_id,
+                    model: session_configured.model.clone(),
+                    rollout_path: session_configured.rollout_path.clone(),
+                    initial_messages: plain_initial_messages(session_configured.initial_messages),
+                };
+                self.outgoing.send_response(request_id, response).await;
+            }
+            Err(err) => {
+                let error = JSONRPCErrorError {
+                    code: INTERNAL_ERROR_CODE,
+                    message: format!("error forking conversation: {err}"),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    async fn send_session_configured_notification(
+        &self,
+        session_configured: &SessionConfiguredEvent,
+    ) {
+        self.outgoing
+            .send_server_notification(ServerNotification::SessionConfigured(
+                SessionConfiguredNotification {
+                    session_id: session_configured.session_id,
+                    model: session_configured.model.clone(),
+                    reasoning_effort: session_configured.reasoning_effort,
+                    history_log_id: session_configured.history_log_id,
+                    history_entry_count: session_configured.history_entry_count,
+                    initial_messages: session_configured.initial_messages.clone(),
+                    rollout_path: session_configured.rollout_path.clone(),
+                },
+            ))
+            .await;
+    }
+
     async fn archive_conversation(&self, request_id: RequestId, params: ArchiveConversationParams) {
         let ArchiveConversationParams {
             conversation_id,
@@ -967,6 +1112,7 @@ impl CodexMessageProcessor {
         let SendUserMessageParams {
             conversation_id,
             items,
+            queue,
         } = params;
         let Ok(conversation) = self
             .conversation_manager
@@ -992,11 +1138,16 @@ impl CodexMessageProcessor {
             .collect();
 
         // Submit user input to the conversation.
-        let _ = conversation
-            .submit(Op::UserInput {
+        let op = if queue {
+            Op::QueueUserInput {
                 items: mapped_items,
-            })
-            .await;
+            }
+        } else {
+            Op::UserInput {
+                items: mapped_items,
+            }
+        };
+        let _ = conversation.submit(op).await;
 
         // Acknowledge with an empty result.
         self.outgoing
@@ -1300,6 +1451,51 @@ async fn apply_bespoke_event_handling(
                 on_exec_approval_response(event_id, rx, conversation).await;
             });
         }
+        // Surface each batch item as its own approval request; answering with
+        // the item's call_id resolves that entry of the batch.
+        EventMsg::ApprovalBatchRequest(ApprovalBatchRequestEvent { items }) => {
+            for ExecApprovalRequestEvent {
+                call_id,
+                command,
+                cwd,
+                reason,
+            } in items
+            {
+                let params = ExecCommandApprovalParams {
+                    conversation_id,
+                    call_id: call_id.clone(),
+                    command,
+                    cwd,
+                    reason,
+                };
+                let rx = outgoing
+                    .send_request(ServerRequestPayload::ExecCommandApproval(params))
+                    .await;
+
+                let conversation = conversation.clone();
+                tokio::spawn(async move {
+                    on_exec_approval_response(call_id, rx, conversation).await;
+                });
+            }
+        }
+        EventMsg::BudgetExceeded(BudgetExceededEvent {
+            budget,
+            limit,
+            used,
+        }) => {
+            let params = SessionBudgetExceededParams {
+                conversation_id,
+                budget,
+                limit,
+                used,
+            };
+            let rx = outgoing
+                .send_request(ServerRequestPayload::SessionBudgetExceeded(params))
+                .await;
+            tokio::spawn(async move {
+                on_budget_exceeded_response(event_id, rx, conversation).await;
+            });
+        }
         // If this is a TurnAborted, reply to any pending interrupt requests.
         EventMsg::TurnAborted(turn_aborted_event) => {
             let pending = {
@@ -1320,6 +1516,21 @@ async fn apply_bespoke_event_handling(
     }
 }
 
+/// Drop non-plain user messages (like user instructions or environment
+/// context) from replayed history so clients don't render them.
+fn plain_initial_messages(initial_messages: Option<Vec<EventMsg>>) -> Option<Vec<EventMsg>> {
+    initial_messages.map(|msgs| {
+        msgs.into_iter()
+            .filter(|event| {
+                if let EventMsg::UserMessage(user_message) = event {
+                    return matches!(user_message.kind, Some(InputMessageKind::Plain));
+                }
+                true
+            })
+            .collect()
+    })
+}
+
 fn derive_config_from_params(
     params: NewConversationParams,
     codex_linux_sandbox_exe: Option<PathBuf>,
@@ -1334,6 +1545,7 @@ fn derive_config_from_params(
         base_instructions,
         include_plan_tool,
         include_apply_patch_tool,
+        client_capabilities,
     } = params;
     let overrides = ConfigOverrides {
         model,
@@ -1350,6 +1562,7 @@ fn derive_config_from_params(
         include_view_image_tool: None,
         show_raw_agent_reasoning: None,
         tools_web_search_request: None,
+        client_capabilities,
     };
 
     let cli_overrides = cli_overrides
@@ -1439,9 +1652,45 @@ async fn on_exec_approval_response(
     }
 }
 
+async fn on_budget_exceeded_response(
+    event_id: String,
+    receiver: oneshot::Receiver<JsonRpcResult>,
+    conversation: Arc<CodexConversation>,
+) {
+    let response = receiver.await;
+    let value = match response {
+        Ok(value) => value,
+        Err(err) => {
+            error!("request failed: {err:?}");
+            return;
+        }
+    };
+
+    // A response we cannot read stops the turn.
+    let response =
+        serde_json::from_value::<SessionBudgetExceededResponse>(value).unwrap_or_else(|err| {
+            error!("failed to deserialize SessionBudgetExceededResponse: {err}");
+            SessionBudgetExceededResponse {
+                decision: BudgetDecision::Stop,
+            }
+        });
+
+    if let Err(err) = conversation
+        .submit(Op::BudgetDecision {
+            id: event_id,
+            decision: response.decision,
+        })
+        .await
+    {
+        error!("failed to submit BudgetDecision: {err}");
+    }
+}
+
 fn extract_conversation_summary(
     path: PathBuf,
     head: &[serde_json::Value],
+    title: Option<String>,
+    tags: BTreeMap<String, String>,
 ) -> Option<ConversationSummary> {
     let session_meta = match head.first() {
         Some(first_line) => serde_json::from_value::<SessionMeta>(first_line.clone()).ok()?,
@@ -1482,6 +1731,8 @@ fn extract_conversation_summary(
         timestamp,
         path,
         preview: preview.to_string(),
+        title,
+        tags,
     })
 }
 
@@ -1525,7 +1776,8 @@ mod tests {
             }),
         ];
 
-        let summary = extract_conversation_summary(path.clone(), &head).expect("summary");
+        let summary = extract_conversation_summary(path.clone(), &head, None, BTreeMap::new())
+            .expect("summary");
 
         assert_eq!(summary.conversation_id, conversation_id);
         assert_eq!(
@@ -119,6 +119,7 @@ async fn test_codex_jsonrpc_conversation_flow() {
             items: vec![codex_app_server_protocol::InputItem::Text {
                 text: "text".to_string(),
             }],
+            queue: false,
         })
         .await
         .expect("send sendUserMessage");
@@ -269,6 +270,7 @@ async fn test_send_user_turn_changes_approval_policy_behavior() {
             items: vec![codex_app_server_protocol::InputItem::Text {
                 text: "run python".to_string(),
             }],
+            queue: false,
         })
         .await
         .expect("send sendUserMessage");
@@ -378,6 +380,9 @@ approval_policy = "untrusted"
 
 model_provider = "mock_provider"
 
+[session_titles]
+enabled = false
+
 [model_providers.mock_provider]
 name = "Mock provider for test"
 base_url = "{server_uri}/v1"
@@ -89,6 +89,7 @@ async fn test_conversation_create_and_send_message_ok() {
             items: vec![InputItem::Text {
                 text: "Hello".to_string(),
             }],
+            queue: false,
         })
         .await
         .expect("send sendUserMessage");
@@ -145,6 +146,9 @@ sandbox_mode = "danger-full-access"
 
 model_provider = "mock_provider"
 
+[session_titles]
+enabled = false
+
 [model_providers.mock_provider]
 name = "Mock provider for test"
 base_url = "{server_uri}/v1"
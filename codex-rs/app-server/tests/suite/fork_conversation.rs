@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use app_test_support::McpProcess;
+use app_test_support::to_response;
+use codex_app_server_protocol::ForkConversationParams;
+use codex_app_server_protocol::ForkConversationResponse;
+use codex_app_server_protocol::JSONRPCResponse;
+use codex_app_server_protocol::RequestId;
+use codex_core::protocol::EventMsg;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+const DEFAULT_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn fork_conversation_keeps_history_up_to_turn() {
+    let codex_home = TempDir::new().expect("create temp dir");
+    let (source_id, path) = create_rollout_with_turns(codex_home.path(), &["first", "second"]);
+
+    let mut mcp = McpProcess::new(codex_home.path())
+        .await
+        .expect("spawn mcp process");
+    timeout(DEFAULT_READ_TIMEOUT, mcp.initialize())
+        .await
+        .expect("init timeout")
+        .expect("init failed");
+
+    // Keep only the first turn.
+    let ForkConversationResponse {
+        conversation_id,
+        initial_messages,
+        ..
+    } = fork(&mut mcp, path.clone(), Some(1))
+        .await
+        .expect("fork at turn");
+    assert_ne!(source_id, conversation_id.to_string());
+    assert_eq!(vec!["first"], user_messages(initial_messages));
+
+    // Without a turn, the whole history is shared.
+    let ForkConversationResponse {
+        initial_messages, ..
+    } = fork(&mut mcp, path, None).await.expect("fork at latest");
+    assert_eq!(vec!["first", "second"], user_messages(initial_messages));
+}
+
+async fn fork(
+    mcp: &mut McpProcess,
+    path: PathBuf,
+    turn: Option<usize>,
+) -> anyhow::Result<ForkConversationResponse> {
+    let request_id = mcp
+        .send_fork_conversation_request(ForkConversationParams {
+            path,
+            turn,
+            overrides: None,
+        })
+        .await?;
+    let response: JSONRPCResponse = timeout(
+        DEFAULT_READ_TIMEOUT,
+        mcp.read_stream_until_response_message(RequestId::Integer(request_id)),
+    )
+    .await??;
+    to_response::<ForkConversationResponse>(response)
+}
+
+fn user_messages(initial_messages: Option<Vec<EventMsg>>) -> Vec<String> {
+    initial_messages
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|event| match event {
+            EventMsg::UserMessage(user_message) => Some(user_message.message),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Write a rollout whose conversation consists of one user message per entry
+/// of `turns`, returning the conversation id and the rollout path.
+fn create_rollout_with_turns(codex_home: &Path, turns: &[&str]) -> (String, PathBuf) {
+    let uuid = Uuid::new_v4();
+    let timestamp = "2025-01-02T12:00:00Z";
+    let dir = codex_home.join("sessions/2025/01/02");
+    fs::create_dir_all(&dir).unwrap_or_else(|e| panic!("create sessions dir: {e}"));
+
+    let mut lines = vec![
+        json!({
+            "timestamp": timestamp,
+            "type": "session_meta",
+            "payload": {
+                "id": uuid,
+                "timestamp": timestamp,
+                "cwd": "/",
+                "originator": "codex",
+                "cli_version": "0.0.0",
+                "instructions": null
+            }
+        })
+        .to_string(),
+    ];
+    for text in turns {
+        lines.push(
+            json!({
+                "timestamp": timestamp,
+                "type": "response_item",
+                "payload": {
+                    "type": "message",
+                    "role": "user",
+                    "content": [{"type": "input_text", "text": text}]
+                }
+            })
+            .to_string(),
+        );
+        lines.push(
+            json!({
+                "timestamp": timestamp,
+                "type": "event_msg",
+                "payload": {
+                    "type": "user_message",
+                    "message": text,
+                    "kind": "plain"
+                }
+            })
+            .to_string(),
+        );
+    }
+
+    let path = dir.join(format!("rollout-2025-01-02T12-00-00-{uuid}.jsonl"));
+    fs::write(&path, lines.join("\n") + "\n").unwrap_or_else(|e| panic!("write rollout: {e}"));
+    (uuid.to_string(), path)
+}
@@ -103,6 +103,7 @@ async fn shell_command_interruption() -> anyhow::Result<()> {
             items: vec![codex_app_server_protocol::InputItem::Text {
                 text: "run first sleep command".to_string(),
             }],
+            queue: false,
         })
         .await?;
     let send_user_resp: JSONRPCResponse = timeout(
@@ -147,6 +148,9 @@ sandbox_mode = "danger-full-access"
 
 model_provider = "mock_provider"
 
+[session_titles]
+enabled = false
+
 [model_providers.mock_provider]
 name = "Mock provider for test"
 base_url = "{server_uri}/v1"
@@ -89,6 +89,7 @@ async fn send_message(message: &str, conversation_id: ConversationId, mcp: &mut
             items: vec![InputItem::Text {
                 text: message.to_string(),
             }],
+            queue: false,
         })
         .await
         .expect("send sendUserMessage");
@@ -143,6 +144,7 @@ async fn test_send_message_session_not_found() {
             items: vec![InputItem::Text {
                 text: "ping".to_string(),
             }],
+            queue: false,
         })
         .await
         .expect("send sendUserMessage");
@@ -174,6 +176,9 @@ sandbox_mode = "danger-full-access"
 
 model_provider = "mock_provider"
 
+[session_titles]
+enabled = false
+
 [model_providers.mock_provider]
 name = "Mock provider for test"
 base_url = "{server_uri}/v1"
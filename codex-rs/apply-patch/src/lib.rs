@@ -179,6 +179,78 @@ impl ApplyPatchAction {
         &self.changes
     }
 
+    /// An action that replaces the whole content of `path`, which must be
+    /// absolute, with `content`. `original` is the current content of the
+    /// file, or `None` if the file does not exist yet.
+    pub fn new_write(path: &Path, original: Option<&str>, content: String) -> Self {
+        let display_path = path.display();
+        let (change, patch) = match original {
+            None => {
+                let added: String = content.lines().map(|line| format!("+{line}\n")).collect();
+                let patch =
+                    format!("*** Begin Patch\n*** Add File: {display_path}\n{added}*** End Patch");
+                (ApplyPatchFileChange::Add { content }, patch)
+            }
+            Some(original) => {
+                let removed: String = original.lines().map(|line| format!("-{line}\n")).collect();
+                let added: String = content.lines().map(|line| format!("+{line}\n")).collect();
+                let patch = format!(
+                    "*** Begin Patch\n*** Update File: {display_path}\n@@\n{removed}{added}*** End Patch"
+                );
+                let unified_diff = TextDiff::from_lines(original, &content)
+                    .unified_diff()
+                    .context_radius(1)
+                    .to_string();
+                let change = ApplyPatchFileChange::Update {
+                    unified_diff,
+                    move_path: None,
+                    new_content: content,
+                };
+                (change, patch)
+            }
+        };
+        let cwd = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self {
+            changes: HashMap::from([(path.to_path_buf(), change)]),
+            patch,
+            cwd,
+        }
+    }
+
+    /// An action that rewrites several existing files at once. Each update
+    /// is an absolute path with the file's current and new content.
+    pub fn new_updates(cwd: &Path, updates: Vec<(PathBuf, String, String)>) -> Self {
+        let mut changes = HashMap::new();
+        let mut patch = String::from("*** Begin Patch\n");
+        for (path, original, content) in updates {
+            patch.push_str(&format!("*** Update File: {}\n@@\n", path.display()));
+            for line in original.lines() {
+                patch.push_str(&format!("-{line}\n"));
+            }
+            for line in content.lines() {
+                patch.push_str(&format!("+{line}\n"));
+            }
+            let unified_diff = TextDiff::from_lines(&original, &content)
+                .unified_diff()
+                .context_radius(1)
+                .to_string();
+            changes.insert(
+                path,
+                ApplyPatchFileChange::Update {
+                    unified_diff,
+                    move_path: None,
+                    new_content: content,
+                },
+            );
+        }
+        patch.push_str("*** End Patch");
+        Self {
+            changes,
+            patch,
+            cwd: cwd.to_path_buf(),
+        }
+    }
+
     /// Should be used exclusively for testing. (Not worth the overhead of
     /// creating a feature flag for this.)
     pub fn new_add_for_test(path: &Path, content: String) -> Self {
@@ -1377,6 +1449,31 @@ PATCH"#,
         assert_eq!(expected, diff);
     }
 
+    #[test]
+    fn test_new_write_matches_equivalent_patch() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("existing.txt");
+        fs::write(&existing, "foo\nbar\nbaz\n").unwrap();
+        let writes = [
+            ApplyPatchAction::new_write(
+                &existing,
+                Some("foo\nbar\nbaz\n"),
+                "foo\nBAR\nbaz\n".to_string(),
+            ),
+            ApplyPatchAction::new_write(&dir.path().join("new.txt"), None, "a\nb\n".to_string()),
+        ];
+
+        for write in writes {
+            let argv = strs_to_strings(&["apply_patch", &write.patch]);
+            match maybe_parse_apply_patch_verified(&argv, dir.path()) {
+                MaybeApplyPatchVerified::Body(parsed) => {
+                    assert_eq!(parsed.changes(), write.changes());
+                }
+                other => panic!("expected a patch body, got {other:?}"),
+            }
+        }
+    }
+
     #[test]
     fn test_unified_diff_first_line_replacement() {
         // Replace the very first line of the file.
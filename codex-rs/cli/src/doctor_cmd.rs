@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::doctor::DoctorReport;
+use codex_core::doctor::DoctorStatus;
+use codex_core::doctor::full_report;
+use codex_core::doctor::load_error_report;
+
+/// Check the configuration, the MCP servers, the sandbox and the model
+/// provider, and report what is wrong.
+#[derive(Debug, clap::Parser)]
+pub struct DoctorCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Print the report as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl DoctorCommand {
+    /// Returns whether any check failed.
+    pub async fn run(self, codex_linux_sandbox_exe: Option<PathBuf>) -> Result<bool> {
+        let DoctorCommand {
+            config_overrides,
+            json,
+        } = self;
+        let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(
+            overrides,
+            ConfigOverrides {
+                codex_linux_sandbox_exe,
+                ..Default::default()
+            },
+        );
+        let report = match config {
+            Ok(config) => full_report(&config).await,
+            Err(err) => {
+                let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+                load_error_report(&codex_home, &err)
+            }
+        };
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+        Ok(report.has_errors())
+    }
+}
+
+fn print_report(report: &DoctorReport) {
+    for check in &report.checks {
+        let mark = match check.status {
+            DoctorStatus::Ok => "ok",
+            DoctorStatus::Warning => "warning",
+            DoctorStatus::Error => "error",
+        };
+        println!("{mark:>7}  {check}");
+    }
+    let problems = report.problems().count();
+    println!();
+    if problems == 0 {
+        println!("No problems found.");
+    } else {
+        println!("{problems} problem(s) found.");
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use codex_core::ExportFormat;
+use codex_core::config::find_codex_home;
+use codex_core::export_session;
+use codex_core::find_conversation_path_by_id_str;
+
+/// Export a recorded session as a Markdown or HTML report.
+#[derive(Debug, clap::Parser)]
+pub struct ExportCommand {
+    /// Session id (UUID) or path to a rollout file.
+    #[arg(value_name = "SESSION")]
+    pub session: String,
+
+    /// Report format. Defaults to HTML when `--output` ends in `.html`,
+    /// Markdown otherwise.
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormatArg>,
+
+    /// Write the report to this file instead of stdout.
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportFormatArg {
+    Markdown,
+    Html,
+}
+
+impl ExportCommand {
+    pub async fn run(self) -> Result<()> {
+        let ExportCommand {
+            session,
+            format,
+            output,
+        } = self;
+
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let path = resolve_session_path(&codex_home, &session).await?;
+        let format = match format {
+            Some(ExportFormatArg::Markdown) => ExportFormat::Markdown,
+            Some(ExportFormatArg::Html) => ExportFormat::Html,
+            None => infer_format(output.as_deref()),
+        };
+        let report = export_session(&path, format)
+            .await
+            .with_context(|| format!("failed to export {}", path.display()))?;
+
+        match output {
+            Some(output) => {
+                std::fs::write(&output, report)
+                    .with_context(|| format!("failed to write {}", output.display()))?;
+                println!("Exported session to {}", output.display());
+            }
+            None => print!("{report}"),
+        }
+        Ok(())
+    }
+}
+
+/// Resolve a session given either as a rollout file path or as a session id.
+pub(crate) async fn resolve_session_path(codex_home: &Path, session: &str) -> Result<PathBuf> {
+    let path = Path::new(session);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+    match find_conversation_path_by_id_str(codex_home, session).await? {
+        Some(path) => Ok(path),
+        None => bail!("No session file or recorded session with id {session} found"),
+    }
+}
+
+fn infer_format(output: Option<&Path>) -> ExportFormat {
+    let is_html = output
+        .and_then(Path::extension)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    if is_html {
+        ExportFormat::Html
+    } else {
+        ExportFormat::Markdown
+    }
+}
@@ -15,6 +15,9 @@ use codex_cli::login::run_login_with_device_code;
 use codex_cli::login::run_logout;
 use codex_cloud_tasks::Cli as CloudTasksCli;
 use codex_common::CliConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::config_migration::migrate_config;
+use codex_core::config_schema::config_schema;
 use codex_exec::Cli as ExecCli;
 use codex_responses_api_proxy::Args as ResponsesApiProxyArgs;
 use codex_tui::AppExitInfo;
@@ -23,9 +26,19 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod doctor_cmd;
+mod export_cmd;
 mod mcp_cmd;
+mod replay_cmd;
+mod search_cmd;
+mod secret_cmd;
 
+use crate::doctor_cmd::DoctorCommand;
+use crate::export_cmd::ExportCommand;
 use crate::mcp_cmd::McpCli;
+use crate::replay_cmd::ReplayCommand;
+use crate::search_cmd::SearchCommand;
+use crate::secret_cmd::SecretCli;
 
 /// Codex CLI
 ///
@@ -86,6 +99,27 @@ enum Subcommand {
     /// Resume a previous interactive session (picker by default; use --last to continue the most recent).
     Resume(ResumeCommand),
 
+    /// Export a recorded session as a Markdown or HTML report.
+    Export(ExportCommand),
+
+    /// Search the text of recorded sessions.
+    Search(SearchCommand),
+
+    /// [experimental] Replay a recorded session and show where this build's tool calls diverge.
+    Replay(ReplayCommand),
+
+    /// Check the configuration, MCP servers, sandbox and model provider.
+    Doctor(DoctorCommand),
+
+    /// Store API keys and other secrets in the OS credential store.
+    Secret(SecretCli),
+
+    /// Print the JSON Schema of config.toml, for editors and config generators.
+    ConfigSchema(ConfigSchemaCommand),
+
+    /// Rewrite settings of config.toml that earlier versions named differently.
+    ConfigMigrate(ConfigMigrateCommand),
+
     /// Internal: generate TypeScript protocol bindings.
     #[clap(hide = true)]
     GenerateTs(GenerateTsCommand),
@@ -195,6 +229,20 @@ struct GenerateTsCommand {
     prettier: Option<PathBuf>,
 }
 
+#[derive(Debug, Parser)]
+struct ConfigSchemaCommand {
+    /// Write the schema to this file instead of stdout.
+    #[arg(short = 'o', long = "out", value_name = "FILE")]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+struct ConfigMigrateCommand {
+    /// Only list the settings that would be rewritten.
+    #[arg(long = "dry-run", default_value_t = false)]
+    dry_run: bool,
+}
+
 fn format_exit_messages(exit_info: AppExitInfo, color_enabled: bool) -> Vec<String> {
     let AppExitInfo {
         token_usage,
@@ -293,6 +341,53 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             );
             codex_tui::run_main(interactive, codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Export(export_cli)) => {
+            export_cli.run().await?;
+        }
+        Some(Subcommand::Search(search_cli)) => {
+            search_cli.run().await?;
+        }
+        Some(Subcommand::Replay(mut replay_cli)) => {
+            prepend_config_flags(
+                &mut replay_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            if replay_cli.run().await? {
+                std::process::exit(1);
+            }
+        }
+        Some(Subcommand::Doctor(mut doctor_cli)) => {
+            prepend_config_flags(
+                &mut doctor_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            if doctor_cli.run(codex_linux_sandbox_exe).await? {
+                std::process::exit(1);
+            }
+        }
+        Some(Subcommand::Secret(secret_cli)) => {
+            secret_cli.run()?;
+        }
+        Some(Subcommand::ConfigSchema(schema_cli)) => {
+            let schema = serde_json::to_string_pretty(&config_schema())?;
+            match schema_cli.out {
+                Some(out) => std::fs::write(&out, schema + "\n")?,
+                None => println!("{schema}"),
+            }
+        }
+        Some(Subcommand::ConfigMigrate(migrate_cli)) => {
+            let codex_home = find_codex_home()?;
+            let report = migrate_config(&codex_home, migrate_cli.dry_run)?;
+            if report.changes.is_empty() {
+                println!("config.toml is up to date.");
+            }
+            for change in &report.changes {
+                println!("{change}");
+            }
+            if let Some(backup) = report.backup {
+                println!("The previous config.toml is kept in {}.", backup.display());
+            }
+        }
         Some(Subcommand::Login(mut login_cli)) => {
             prepend_config_flags(
                 &mut login_cli.config_overrides,
@@ -450,6 +545,9 @@ fn merge_resume_cli_flags(interactive: &mut TuiCli, resume_cli: TuiCli) {
     if resume_cli.web_search {
         interactive.web_search = true;
     }
+    if !resume_cli.tags.is_empty() {
+        interactive.tags.extend(resume_cli.tags);
+    }
     if !resume_cli.images.is_empty() {
         interactive.images = resume_cli.images;
     }
@@ -632,6 +730,29 @@ mod tests {
         assert_eq!(interactive.resume_session_id.as_deref(), Some("sid"));
     }
 
+    #[test]
+    fn resume_tags_add_to_root_tags() {
+        let interactive = finalize_from_args(
+            [
+                "codex",
+                "--tag",
+                "repo=codex",
+                "resume",
+                "--last",
+                "--tag",
+                "ticket=ABC-1",
+            ]
+            .as_ref(),
+        );
+        let tags: Vec<_> = interactive
+            .tags
+            .iter()
+            .map(|tag| (tag.key.as_str(), tag.value.as_str()))
+            .collect();
+        assert_eq!(tags, vec![("repo", "codex"), ("ticket", "ABC-1")]);
+        assert!(interactive.resume_last);
+    }
+
     #[test]
     fn resume_merges_dangerously_bypass_flag() {
         let interactive = finalize_from_args(
@@ -8,10 +8,14 @@ use codex_common::CliConfigOverrides;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::config::find_codex_home;
+use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::config::load_global_mcp_servers;
 use codex_core::config::write_global_mcp_servers;
 use codex_core::config_types::McpServerConfig;
 use codex_core::config_types::McpServerTransportConfig;
+use codex_core::config_types::McpToolApproval;
+use codex_rmcp_client::delete_oauth_tokens;
+use codex_rmcp_client::perform_oauth_login;
 
 /// [experimental] Launch Codex as an MCP server or manage configured MCP servers.
 ///
@@ -19,7 +23,7 @@ use codex_core::config_types::McpServerTransportConfig;
 /// - `serve`  — run the MCP server on stdio
 /// - `list`   — list configured servers (with `--json`)
 /// - `get`    — show a single server (with `--json`)
-/// - `add`    — add a server launcher entry to `~/.codex/config.toml`
+/// - `add`    — add a server launcher or URL entry to `~/.codex/config.toml`
 /// - `remove` — delete a server entry
 #[derive(Debug, clap::Parser)]
 pub struct McpCli {
@@ -43,6 +47,13 @@ pub enum McpSubcommand {
 
     /// [experimental] Remove a global MCP server entry.
     Remove(RemoveArgs),
+
+    /// [experimental] Authorize Codex with a streamable HTTP MCP server
+    /// through OAuth.
+    Login(LoginArgs),
+
+    /// [experimental] Remove the stored OAuth tokens of an MCP server.
+    Logout(LogoutArgs),
 }
 
 #[derive(Debug, clap::Parser)]
@@ -71,6 +82,25 @@ pub struct AddArgs {
     #[arg(long, value_parser = parse_env_pair, value_name = "KEY=VALUE")]
     pub env: Vec<(String, String)>,
 
+    /// URL of a streamable HTTP MCP server to connect to instead of launching
+    /// a command.
+    #[arg(long, conflicts_with_all = ["command", "env"])]
+    pub url: Option<String>,
+
+    /// Environment variable holding the bearer token sent to the `--url`
+    /// server.
+    #[arg(long, requires = "url", value_name = "ENV_VAR")]
+    pub bearer_token_env_var: Option<String>,
+
+    /// HTTP headers sent to the `--url` server.
+    #[arg(
+        long = "header",
+        requires = "url",
+        value_parser = parse_header_pair,
+        value_name = "NAME=VALUE"
+    )]
+    pub headers: Vec<(String, String)>,
+
     /// Command to launch the MCP server.
     #[arg(trailing_var_arg = true, num_args = 1..)]
     pub command: Vec<String>,
@@ -82,6 +112,18 @@ pub struct RemoveArgs {
     pub name: String,
 }
 
+#[derive(Debug, clap::Parser)]
+pub struct LoginArgs {
+    /// Name of the MCP server to log in to.
+    pub name: String,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct LogoutArgs {
+    /// Name of the MCP server to log out of.
+    pub name: String,
+}
+
 impl McpCli {
     pub async fn run(self) -> Result<()> {
         let McpCli {
@@ -102,6 +144,12 @@ impl McpCli {
             McpSubcommand::Remove(args) => {
                 run_remove(&config_overrides, args)?;
             }
+            McpSubcommand::Login(args) => {
+                run_login(&config_overrides, args).await?;
+            }
+            McpSubcommand::Logout(args) => {
+                run_logout(&config_overrides, args)?;
+            }
         }
 
         Ok(())
@@ -112,24 +160,46 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
     // Validate any provided overrides even though they are not currently applied.
     config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
 
-    let AddArgs { name, env, command } = add_args;
+    let AddArgs {
+        name,
+        env,
+        url,
+        bearer_token_env_var,
+        headers,
+        command,
+    } = add_args;
 
     validate_server_name(&name)?;
 
-    let mut command_parts = command.into_iter();
-    let command_bin = command_parts
-        .next()
-        .ok_or_else(|| anyhow!("command is required"))?;
-    let command_args: Vec<String> = command_parts.collect();
-
-    let env_map = if env.is_empty() {
-        None
-    } else {
-        let mut map = HashMap::new();
-        for (key, value) in env {
-            map.insert(key, value);
+    let transport = match url {
+        Some(url) => McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token: None,
+            bearer_token_env_var,
+            http_headers: (!headers.is_empty()).then(|| headers.into_iter().collect()),
+        },
+        None => {
+            let mut command_parts = command.into_iter();
+            let command_bin = command_parts
+                .next()
+                .ok_or_else(|| anyhow!("command or --url is required"))?;
+            let command_args: Vec<String> = command_parts.collect();
+
+            let env_map = if env.is_empty() {
+                None
+            } else {
+                let mut map = HashMap::new();
+                for (key, value) in env {
+                    map.insert(key, value);
+                }
+                Some(map)
+            };
+            McpServerTransportConfig::Stdio {
+                command: command_bin,
+                args: command_args,
+                env: env_map,
+            }
         }
-        Some(map)
     };
 
     let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
@@ -137,13 +207,17 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
         .with_context(|| format!("failed to load MCP servers from {}", codex_home.display()))?;
 
     let new_entry = McpServerConfig {
-        transport: McpServerTransportConfig::Stdio {
-            command: command_bin,
-            args: command_args,
-            env: env_map,
-        },
+        transport,
         startup_timeout_sec: None,
         tool_timeout_sec: None,
+        tool_prefix: None,
+        tool_aliases: HashMap::new(),
+        max_concurrent_calls: None,
+        max_result_bytes: None,
+        enabled_tools: None,
+        disabled_tools: Vec::new(),
+        tool_approval: None,
+        tool_approvals: HashMap::new(),
     };
 
     servers.insert(name.clone(), new_entry);
@@ -183,6 +257,56 @@ fn run_remove(config_overrides: &CliConfigOverrides, remove_args: RemoveArgs) ->
     Ok(())
 }
 
+async fn run_login(config_overrides: &CliConfigOverrides, login_args: LoginArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+    let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+        .context("failed to load configuration")?;
+
+    let LoginArgs { name } = login_args;
+
+    let Some(server) = config.mcp_servers.get(&name) else {
+        bail!("No MCP server named '{name}' found.");
+    };
+    let McpServerTransportConfig::StreamableHttp { url, .. } = &server.transport else {
+        bail!("OAuth login is only supported for streamable HTTP MCP servers.");
+    };
+
+    let store = config.mcp_oauth_credentials_store;
+    perform_oauth_login(&config.codex_home, store, &name, url, |authorize_url| {
+        println!("Open the following URL to authorize Codex with '{name}':\n\n{authorize_url}\n");
+        if let Err(e) = webbrowser::open(authorize_url) {
+            eprintln!("Failed to open the browser: {e}");
+        }
+    })
+    .await
+    .with_context(|| format!("OAuth login to MCP server '{name}' failed"))?;
+
+    println!("Logged in to MCP server '{name}'.");
+
+    Ok(())
+}
+
+fn run_logout(config_overrides: &CliConfigOverrides, logout_args: LogoutArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+
+    let LogoutArgs { name } = logout_args;
+
+    validate_server_name(&name)?;
+
+    let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+    let store = load_config_as_toml_with_cli_overrides(&codex_home, overrides)
+        .context("failed to load configuration")?
+        .mcp_oauth_credentials_store
+        .unwrap_or_default();
+    if delete_oauth_tokens(&codex_home, store, &name)? {
+        println!("Logged out of MCP server '{name}'.");
+    } else {
+        println!("Not logged in to MCP server '{name}'.");
+    }
+
+    Ok(())
+}
+
 fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Result<()> {
     let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
     let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
@@ -202,11 +326,18 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
                         "args": args,
                         "env": env,
                     }),
-                    McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+                    McpServerTransportConfig::StreamableHttp {
+                        url,
+                        bearer_token,
+                        bearer_token_env_var,
+                        http_headers,
+                    } => {
                         serde_json::json!({
                             "type": "streamable_http",
                             "url": url,
                             "bearer_token": bearer_token,
+                            "bearer_token_env_var": bearer_token_env_var,
+                            "http_headers": http_headers,
                         })
                     }
                 };
@@ -259,8 +390,13 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
                 };
                 stdio_rows.push([name.clone(), command.clone(), args_display, env_display]);
             }
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
-                let has_bearer = if bearer_token.is_some() {
+            McpServerTransportConfig::StreamableHttp {
+                url,
+                bearer_token,
+                bearer_token_env_var,
+                ..
+            } => {
+                let has_bearer = if bearer_token.is_some() || bearer_token_env_var.is_some() {
                     "True"
                 } else {
                     "False"
@@ -360,10 +496,17 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
                 "args": args,
                 "env": env,
             }),
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => serde_json::json!({
+            McpServerTransportConfig::StreamableHttp {
+                url,
+                bearer_token,
+                bearer_token_env_var,
+                http_headers,
+            } => serde_json::json!({
                 "type": "streamable_http",
                 "url": url,
                 "bearer_token": bearer_token,
+                "bearer_token_env_var": bearer_token_env_var,
+                "http_headers": http_headers,
             }),
         };
         let output = serde_json::to_string_pretty(&serde_json::json!({
@@ -406,11 +549,28 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
             };
             println!("  env: {env_display}");
         }
-        McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token,
+            bearer_token_env_var,
+            http_headers,
+        } => {
             println!("  transport: streamable_http");
             println!("  url: {url}");
             let bearer = bearer_token.as_deref().unwrap_or("-");
             println!("  bearer_token: {bearer}");
+            let bearer_env_var = bearer_token_env_var.as_deref().unwrap_or("-");
+            println!("  bearer_token_env_var: {bearer_env_var}");
+            let headers_display = match http_headers.as_ref() {
+                None => "-".to_string(),
+                Some(map) if map.is_empty() => "-".to_string(),
+                Some(map) => {
+                    let mut names: Vec<_> = map.keys().cloned().collect();
+                    names.sort();
+                    names.join(", ")
+                }
+            };
+            println!("  http_headers: {headers_display}");
         }
     }
     if let Some(timeout) = server.startup_timeout_sec {
@@ -419,6 +579,48 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
     if let Some(timeout) = server.tool_timeout_sec {
         println!("  tool_timeout_sec: {}", timeout.as_secs_f64());
     }
+    if let Some(prefix) = &server.tool_prefix {
+        println!("  tool_prefix: {prefix}");
+    }
+    if !server.tool_aliases.is_empty() {
+        let mut pairs: Vec<_> = server.tool_aliases.iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let aliases_display = pairs
+            .into_iter()
+            .map(|(tool, alias)| format!("{tool}={alias}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  tool_aliases: {aliases_display}");
+    }
+    if let Some(limit) = server.max_concurrent_calls {
+        println!("  max_concurrent_calls: {limit}");
+    }
+    if let Some(limit) = server.max_result_bytes {
+        println!("  max_result_bytes: {limit}");
+    }
+    if let Some(tools) = &server.enabled_tools {
+        println!("  enabled_tools: {}", tools.join(", "));
+    }
+    if !server.disabled_tools.is_empty() {
+        println!("  disabled_tools: {}", server.disabled_tools.join(", "));
+    }
+    let approval_display = |approval: McpToolApproval| match approval {
+        McpToolApproval::Auto => "auto",
+        McpToolApproval::Ask => "ask",
+    };
+    if let Some(approval) = server.tool_approval {
+        println!("  tool_approval: {}", approval_display(approval));
+    }
+    if !server.tool_approvals.is_empty() {
+        let mut pairs: Vec<_> = server.tool_approvals.iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let approvals_display = pairs
+            .into_iter()
+            .map(|(tool, approval)| format!("{tool}={}", approval_display(*approval)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("  tool_approvals: {approvals_display}");
+    }
     println!("  remove: codex mcp remove {}", get_args.name);
 
     Ok(())
@@ -439,6 +641,17 @@ fn parse_env_pair(raw: &str) -> Result<(String, String), String> {
     Ok((key.to_string(), value))
 }
 
+fn parse_header_pair(raw: &str) -> Result<(String, String), String> {
+    let (name, value) = raw
+        .split_once('=')
+        .ok_or_else(|| "headers must be in NAME=VALUE form".to_string())?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("headers must be in NAME=VALUE form".to_string());
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
 fn validate_server_name(name: &str) -> Result<()> {
     let is_valid = !name.is_empty()
         && name
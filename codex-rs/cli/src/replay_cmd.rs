@@ -0,0 +1,65 @@
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::replay::replay_session;
+
+use crate::export_cmd::resolve_session_path;
+
+/// Replay a recorded session against this build, answering tool calls with
+/// the recorded outputs, and show where its tool calls diverge.
+#[derive(Debug, clap::Parser)]
+pub struct ReplayCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Session id (UUID) or path to a rollout file.
+    #[arg(value_name = "SESSION")]
+    pub session: String,
+}
+
+impl ReplayCommand {
+    /// Returns whether the replay diverged from the recording.
+    pub async fn run(self) -> Result<bool> {
+        let ReplayCommand {
+            config_overrides,
+            session,
+        } = self;
+        let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+        let config = Config::load_with_cli_overrides(overrides, ConfigOverrides::default())
+            .context("failed to load configuration")?;
+        let path = resolve_session_path(&config.codex_home, &session).await?;
+        let auth_manager = AuthManager::shared(config.codex_home.clone(), true);
+
+        let report = replay_session(config, auth_manager, &path)
+            .await
+            .with_context(|| format!("failed to replay {}", path.display()))?;
+
+        for (index, turn) in report.turns.iter().enumerate() {
+            let status = if turn.diff.is_empty() {
+                "same"
+            } else {
+                "diverged"
+            };
+            let message = turn.user_message.lines().next().unwrap_or_default();
+            println!("Turn {}: {status} — {message}", index + 1);
+            if !turn.diff.is_empty() {
+                println!("{}", turn.diff);
+            }
+        }
+        println!("Replayed session: {}", report.rollout_path.display());
+        match report.first_divergence() {
+            Some(index) => {
+                println!("First divergence at turn {}.", index + 1);
+                Ok(true)
+            }
+            None => {
+                println!("No divergence.");
+                Ok(false)
+            }
+        }
+    }
+}
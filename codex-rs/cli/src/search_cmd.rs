@@ -0,0 +1,41 @@
+use anyhow::Context;
+use anyhow::Result;
+use codex_core::RolloutRecorder;
+use codex_core::config::find_codex_home;
+
+/// Search the text of recorded sessions.
+#[derive(Debug, clap::Parser)]
+pub struct SearchCommand {
+    /// Words to look for, e.g. "migration deadlock".
+    #[arg(value_name = "QUERY", required = true, num_args = 1..)]
+    pub query: Vec<String>,
+
+    /// Maximum number of sessions to show.
+    #[arg(long, default_value_t = 10)]
+    pub limit: usize,
+}
+
+impl SearchCommand {
+    pub async fn run(self) -> Result<()> {
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let query = self.query.join(" ");
+        let hits = RolloutRecorder::search_conversations(&codex_home, &query, self.limit, &[])
+            .await
+            .context("failed to search sessions")?;
+        if hits.is_empty() {
+            println!("No sessions match “{query}”.");
+            return Ok(());
+        }
+
+        for hit in &hits {
+            let title = hit.title.as_deref().unwrap_or("(untitled)");
+            println!("{}  {}  {title}", hit.conversation_id, hit.timestamp);
+            for excerpt in &hit.excerpts {
+                println!("    turn {}: {}", excerpt.turn + 1, excerpt.text);
+            }
+        }
+        println!();
+        println!("Resume a session with `codex resume <SESSION_ID>`.");
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::IsTerminal;
+use std::io::Read;
+use std::io::Write;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::bail;
+use codex_core::config::find_codex_home;
+use codex_core::config_encryption::CONFIG_KEY_ACCOUNT;
+use codex_core::config_encryption::ConfigCipher;
+use codex_core::config_encryption::rotate_config_key;
+use codex_keyring_store::KeyringStore;
+use codex_keyring_store::OsKeyring;
+
+/// Store API keys and other secrets in the OS credential store (the macOS
+/// Keychain, the Secret Service on Linux, the Windows Credential Manager).
+///
+/// Config values refer to a stored secret as `${keychain:NAME}`, and a model
+/// provider whose `env_key` variable is not set uses the secret named after
+/// the variable. Values can also be kept in `config.toml` encrypted with a
+/// key stored here, for a codex home synced through a dotfile repository.
+#[derive(Debug, clap::Parser)]
+pub struct SecretCli {
+    #[command(subcommand)]
+    pub subcommand: SecretSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SecretSubcommand {
+    /// Store a secret, read from stdin.
+    Set(SecretArgs),
+
+    /// Remove a stored secret.
+    Delete(SecretArgs),
+
+    /// Encrypt a value, read from stdin, for use in config.toml. Creates the
+    /// config key when there is none.
+    Encrypt,
+
+    /// Print the config key, to store it on another machine with
+    /// `codex secret set config_encryption_key`.
+    ExportConfigKey,
+
+    /// Replace the config key and re-encrypt the values of config.toml.
+    RotateConfigKey,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct SecretArgs {
+    /// Name of the secret, e.g. `OPENROUTER_API_KEY`.
+    pub name: String,
+}
+
+impl SecretCli {
+    pub fn run(self) -> Result<()> {
+        match self.subcommand {
+            SecretSubcommand::Set(SecretArgs { name }) => {
+                validate_secret_name(&name)?;
+                let value = read_secret(&format!("Value of {name}"))?;
+                if value.is_empty() {
+                    bail!("no value given for secret '{name}'");
+                }
+                OsKeyring
+                    .save(&name, &value)
+                    .with_context(|| format!("failed to store secret '{name}'"))?;
+                println!("Stored secret '{name}'.");
+            }
+            SecretSubcommand::Delete(SecretArgs { name }) => {
+                validate_secret_name(&name)?;
+                if OsKeyring
+                    .delete(&name)
+                    .with_context(|| format!("failed to remove secret '{name}'"))?
+                {
+                    println!("Removed secret '{name}'.");
+                } else {
+                    println!("No secret named '{name}' found.");
+                }
+            }
+            SecretSubcommand::Encrypt => {
+                let value = read_secret("Value to encrypt")?;
+                if value.is_empty() {
+                    bail!("no value given to encrypt");
+                }
+                let cipher = ConfigCipher::load_or_create(&OsKeyring)
+                    .context("failed to load the config key")?;
+                println!("{}", cipher.encrypt(&value)?);
+            }
+            SecretSubcommand::ExportConfigKey => {
+                match ConfigCipher::load(&OsKeyring).context("failed to load the config key")? {
+                    Some(cipher) => println!("{}", cipher.export_key()),
+                    None => bail!(
+                        "no config key yet; `codex secret encrypt` creates one, or store one \
+                         with `codex secret set {CONFIG_KEY_ACCOUNT}`"
+                    ),
+                }
+            }
+            SecretSubcommand::RotateConfigKey => {
+                let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+                let count = rotate_config_key(&codex_home, &OsKeyring)
+                    .context("failed to rotate the config key")?;
+                println!(
+                    "Re-encrypted {count} value(s) of config.toml; copy the new key to your \
+                     other machines with `codex secret export-config-key`."
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read a value from stdin, showing `prompt` when stdin is a terminal.
+/// Trailing newlines are dropped.
+fn read_secret(prompt: &str) -> Result<String> {
+    let mut stdin = std::io::stdin();
+    let mut value = String::new();
+    if stdin.is_terminal() {
+        eprint!("{prompt}: ");
+        std::io::stderr().flush()?;
+        stdin.read_line(&mut value)?;
+    } else {
+        stdin.read_to_string(&mut value)?;
+    }
+    Ok(value.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Secret names share the `${keychain:NAME}` syntax, which ends at `}`.
+fn validate_secret_name(name: &str) -> Result<()> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        Ok(())
+    } else {
+        bail!("invalid secret name '{name}' (use letters, digits, '-', '_' or '.')");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 
 use anyhow::Result;
@@ -93,3 +94,53 @@ fn add_with_env_preserves_key_order_and_values() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn add_with_url_writes_streamable_http_server() -> Result<()> {
+    let codex_home = TempDir::new()?;
+
+    let mut add_cmd = codex_command(codex_home.path())?;
+    add_cmd
+        .args([
+            "mcp",
+            "add",
+            "hosted",
+            "--url",
+            "https://example.com/mcp",
+            "--bearer-token-env-var",
+            "HOSTED_TOKEN",
+            "--header",
+            "X-Team=infra",
+        ])
+        .assert()
+        .success()
+        .stdout(contains("Added global MCP server 'hosted'."));
+
+    let servers = load_global_mcp_servers(codex_home.path())?;
+    let hosted = servers.get("hosted").expect("server should exist");
+    assert_eq!(
+        hosted.transport,
+        McpServerTransportConfig::StreamableHttp {
+            url: "https://example.com/mcp".to_string(),
+            bearer_token: None,
+            bearer_token_env_var: Some("HOSTED_TOKEN".to_string()),
+            http_headers: Some(HashMap::from([("X-Team".to_string(), "infra".to_string())])),
+        }
+    );
+
+    let mut conflicting_cmd = codex_command(codex_home.path())?;
+    conflicting_cmd
+        .args([
+            "mcp",
+            "add",
+            "both",
+            "--url",
+            "https://example.com/mcp",
+            "--",
+            "echo",
+        ])
+        .assert()
+        .failure();
+
+    Ok(())
+}
@@ -27,6 +27,20 @@ pub fn create_config_summary_entries(config: &Config) -> Vec<(&'static str, Stri
             config.model_reasoning_summary.to_string(),
         ));
     }
+    if let Some(project_config) = &config.project_config {
+        let status = if project_config.trusted {
+            "applied"
+        } else {
+            "not applied: project not trusted"
+        };
+        entries.push((
+            "project config",
+            format!("{} ({status})", project_config.path.display()),
+        ));
+    }
+    if let Some(pattern) = &config.directory_policy {
+        entries.push(("directory policy", pattern.clone()));
+    }
 
     entries
 }
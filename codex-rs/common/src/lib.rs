@@ -13,6 +13,12 @@ mod sandbox_mode_cli_arg;
 #[cfg(feature = "cli")]
 pub use sandbox_mode_cli_arg::SandboxModeCliArg;
 
+#[cfg(feature = "cli")]
+mod session_tag_cli_arg;
+
+#[cfg(feature = "cli")]
+pub use session_tag_cli_arg::SessionTagCliArg;
+
 #[cfg(any(feature = "cli", test))]
 mod config_override;
 
@@ -0,0 +1,67 @@
+//! Standard type to use with the repeatable `--tag key=value` CLI option.
+//!
+//! Each tag becomes a `session_tags.<key>` configuration override, so tags
+//! given on the command line are added to those from `config.toml`. A bare
+//! `--tag key` sets the tag with an empty value.
+
+use std::str::FromStr;
+
+use codex_core::is_valid_tag_key;
+use toml::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionTagCliArg {
+    pub key: String,
+    pub value: String,
+}
+
+impl SessionTagCliArg {
+    /// The `-c`-style override that sets this tag.
+    pub fn to_config_override(&self) -> (String, Value) {
+        (
+            format!("session_tags.{}", self.key),
+            Value::String(self.value.clone()),
+        )
+    }
+}
+
+impl FromStr for SessionTagCliArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s.split_once('=').unwrap_or((s, ""));
+        let key = key.trim();
+        if !is_valid_tag_key(key) {
+            return Err(format!(
+                "invalid tag key `{key}`: use letters, digits, `-` and `_`"
+            ));
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_value_and_bare_keys() {
+        let tag: SessionTagCliArg = "ticket=ABC-1".parse().unwrap();
+        assert_eq!(
+            (
+                "session_tags.ticket".to_string(),
+                Value::String("ABC-1".to_string())
+            ),
+            tag.to_config_override()
+        );
+
+        let tag: SessionTagCliArg = "urgent".parse().unwrap();
+        assert_eq!(String::new(), tag.value);
+
+        assert!("repo.name=codex".parse::<SessionTagCliArg>().is_err());
+        assert!("=codex".parse::<SessionTagCliArg>().is_err());
+    }
+}
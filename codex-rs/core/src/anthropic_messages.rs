@@ -0,0 +1,936 @@
+//! Implementation for Anthropic's Messages API (`wire_api = "anthropic"`).
+//!
+//! The conversation is sent as alternating `user` and `assistant` messages of
+//! content blocks: function calls become `tool_use` blocks, their outputs
+//! `tool_result` blocks, and reasoning items that carry a signature are sent
+//! back as `thinking` blocks. The streamed content blocks are mapped back onto
+//! [`ResponseEvent`]s, one `OutputItemDone` per block, like the Responses API.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryLimitReachedError;
+use crate::error::UnexpectedResponseError;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::OpenAiTool;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use bytes::Bytes;
+use codex_otel::otel_event_manager::OtelEventManager;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+/// `max_tokens` of a request when `model_max_output_tokens` is not set; the
+/// Messages API requires one.
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+/// Output tokens left for the answer on top of the thinking budget.
+const MIN_ANSWER_TOKENS: u64 = 4096;
+
+/// Name of the single string parameter freeform tools are offered with, as the
+/// Messages API only has JSON tools.
+pub(crate) const FREEFORM_INPUT_PARAM: &str = "input";
+
+/// Stream a turn from the Messages API.
+pub(crate) async fn stream_anthropic_messages(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    otel_event_manager: &OtelEventManager,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Anthropic Messages API".to_string(),
+        ));
+    }
+
+    let payload = build_payload(
+        prompt,
+        model_family,
+        effort,
+        max_output_tokens,
+        provider.prompt_caching(),
+    );
+    let freeform_tools = freeform_tool_names(&prompt.tools);
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let req_builder = provider.create_request_builder(client, &None).await?;
+
+        let res = otel_event_manager
+            .log_request(attempt, || {
+                req_builder
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(&payload)
+                    .send()
+            })
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_anthropic_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                    freeform_tools,
+                    otel_event_manager.clone(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                        status,
+                        body,
+                        request_id: None,
+                    }));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(RetryLimitReachedError {
+                        status,
+                        request_id: None,
+                    }));
+                }
+
+                let retry_after_secs = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok());
+
+                let delay = retry_after_secs
+                    .map(|s| Duration::from_millis(s * 1_000))
+                    .unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// The request body for `prompt`. Thinking is enabled when a reasoning effort
+/// is set, with a budget that grows with the effort.
+fn build_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    prompt_caching: bool,
+) -> Value {
+    let thinking_budget = thinking_budget(effort);
+    let max_tokens = max_tokens(max_output_tokens, thinking_budget);
+
+    let mut payload = json!({
+        "model": model_family.slug,
+        "system": prompt.get_full_instructions(model_family),
+        "messages": build_messages(&prompt.get_formatted_input()),
+        "max_tokens": max_tokens,
+        "stream": true,
+    });
+    let tools = create_tools_json_for_anthropic_api(&prompt.tools);
+    if let Some(obj) = payload.as_object_mut() {
+        if !tools.is_empty() {
+            obj.insert("tools".to_string(), Value::Array(tools));
+        }
+        if let Some(budget) = thinking_budget {
+            obj.insert(
+                "thinking".to_string(),
+                json!({"type": "enabled", "budget_tokens": budget}),
+            );
+        }
+    }
+    if prompt_caching {
+        add_cache_breakpoints(&mut payload);
+    }
+    payload
+}
+
+/// Mark cache breakpoints at the end of the system prompt, which caches the
+/// tools along with it, and at the end of the last two user messages: the last
+/// one writes the cache the next request reads, and the one before it is where
+/// the previous request wrote the cache this one reads.
+fn add_cache_breakpoints(payload: &mut Value) {
+    let cache_control = json!({"type": "ephemeral"});
+    if let Some(system) = payload.get_mut("system") {
+        let text = system.take();
+        *system = json!([{"type": "text", "text": text, "cache_control": cache_control}]);
+    }
+    let Some(Value::Array(messages)) = payload.get_mut("messages") else {
+        return;
+    };
+    for message in messages
+        .iter_mut()
+        .rev()
+        .filter(|message| message["role"] == "user")
+        .take(2)
+    {
+        if let Some(Value::Array(content)) = message.get_mut("content")
+            && let Some(Value::Object(block)) = content.last_mut()
+        {
+            block.insert("cache_control".to_string(), cache_control.clone());
+        }
+    }
+}
+
+/// Thinking budget of a reasoning effort; thinking is off without one.
+pub(crate) fn thinking_budget(effort: Option<ReasoningEffortConfig>) -> Option<u64> {
+    effort.map(|effort| match effort {
+        ReasoningEffortConfig::Minimal | ReasoningEffortConfig::Low => 1024,
+        ReasoningEffortConfig::Medium => 4096,
+        ReasoningEffortConfig::High => 16384,
+    })
+}
+
+/// Output token limit of a request, leaving room for an answer beyond the
+/// thinking budget.
+pub(crate) fn max_tokens(max_output_tokens: Option<u64>, thinking_budget: Option<u64>) -> u64 {
+    let max_tokens = max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    match thinking_budget {
+        Some(budget) => max_tokens.max(budget + MIN_ANSWER_TOKENS),
+        None => max_tokens,
+    }
+}
+
+/// Map the conversation onto Messages API messages, merging consecutive
+/// blocks of the same role into one message as the API requires.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    let mut push = |role: &str, block: Value| {
+        if let Some(last) = messages.last_mut()
+            && last.get("role").and_then(Value::as_str) == Some(role)
+            && let Some(Value::Array(content)) = last.get_mut("content")
+        {
+            content.push(block);
+            return;
+        }
+        messages.push(json!({"role": role, "content": [block]}));
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push(role, json!({"type": "text", "text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            push(role, image_block(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::Reasoning {
+                content,
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                let text: String = content
+                    .iter()
+                    .flatten()
+                    .map(|c| match c {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => text.as_str(),
+                    })
+                    .collect();
+                if text.is_empty() {
+                    push(
+                        "assistant",
+                        json!({"type": "redacted_thinking", "data": signature}),
+                    );
+                } else {
+                    push(
+                        "assistant",
+                        json!({"type": "thinking", "thinking": text, "signature": signature}),
+                    );
+                }
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push(
+                    "assistant",
+                    json!({
+                        "type": "tool_use",
+                        "id": call_id,
+                        "name": name,
+                        "input": { FREEFORM_INPUT_PARAM: input },
+                    }),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let Some(call_id) = call_id.as_ref().or(id.as_ref()) else {
+                    continue;
+                };
+                push(
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": "local_shell", "input": action}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let mut block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": call_id,
+                    "content": output.content,
+                });
+                if output.success == Some(false)
+                    && let Some(obj) = block.as_object_mut()
+                {
+                    obj.insert("is_error".to_string(), Value::Bool(true));
+                }
+                push("user", block);
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push(
+                    "user",
+                    json!({"type": "tool_result", "tool_use_id": call_id, "content": output}),
+                );
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    messages
+}
+
+/// An image block for `image_url`, inlined when it is a base64 data URL.
+fn image_block(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({"type": "image", "source": {"type": "url", "url": image_url}})
+}
+
+/// Tool definitions in the Messages API format. Freeform tools take their
+/// input as a single string parameter; the built-in local shell and web
+/// search tools have no equivalent and are left out.
+pub(crate) fn create_tools_json_for_anthropic_api(tools: &[OpenAiTool]) -> Vec<Value> {
+    tools
+        .iter()
+        .filter_map(|tool| match tool {
+            OpenAiTool::Function(tool) => Some(json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.parameters,
+            })),
+            OpenAiTool::Freeform(tool) => Some(json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": {
+                    "type": "object",
+                    "properties": {
+                        FREEFORM_INPUT_PARAM: {
+                            "type": "string",
+                            "description": format!(
+                                "Input in the following {} grammar:\n{}",
+                                tool.format.syntax, tool.format.definition
+                            ),
+                        },
+                    },
+                    "required": [FREEFORM_INPUT_PARAM],
+                },
+            })),
+            OpenAiTool::LocalShell {} | OpenAiTool::WebSearch {} => None,
+        })
+        .collect()
+}
+
+pub(crate) fn freeform_tool_names(tools: &[OpenAiTool]) -> HashSet<String> {
+    tools
+        .iter()
+        .filter_map(|tool| match tool {
+            OpenAiTool::Freeform(tool) => Some(tool.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A content block being streamed.
+pub(crate) enum Block {
+    Text(String),
+    Thinking {
+        text: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: String,
+    },
+}
+
+/// SSE processor for the Messages API stream format.
+async fn process_anthropic_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    freeform_tools: HashSet<String>,
+    otel_event_manager: OtelEventManager,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+    let mut block: Option<Block> = None;
+    let mut response_id = String::new();
+    let mut usage = TokenUsage::default();
+
+    loop {
+        let sse = match otel_event_manager
+            .log_sse_event(|| timeout(idle_timeout, stream.next()))
+            .await
+        {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("anthropic_messages received SSE event: {event:?}");
+
+        match event
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "message_start" => {
+                let message = event.get("message");
+                if let Some(id) = message.and_then(|m| m.get("id")).and_then(Value::as_str) {
+                    response_id = id.to_string();
+                }
+                if let Some(start_usage) = message.and_then(|m| m.get("usage")) {
+                    let count = |key: &str| {
+                        start_usage
+                            .get(key)
+                            .and_then(Value::as_u64)
+                            .unwrap_or_default()
+                    };
+                    usage.cached_input_tokens = count("cache_read_input_tokens");
+                    usage.input_tokens = count("input_tokens")
+                        + count("cache_creation_input_tokens")
+                        + usage.cached_input_tokens;
+                }
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "content_block_start" => {
+                let Some(content_block) = event.get("content_block") else {
+                    continue;
+                };
+                let field = |key: &str| {
+                    content_block
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                block = match content_block.get("type").and_then(Value::as_str) {
+                    Some("text") => Some(Block::Text(field("text"))),
+                    Some("thinking") => Some(Block::Thinking {
+                        text: field("thinking"),
+                        signature: field("signature"),
+                    }),
+                    Some("redacted_thinking") => Some(Block::RedactedThinking(field("data"))),
+                    Some("tool_use") => Some(Block::ToolUse {
+                        id: field("id"),
+                        name: field("name"),
+                        input: String::new(),
+                    }),
+                    _ => None,
+                };
+            }
+            "content_block_delta" => {
+                let Some(delta) = event.get("delta") else {
+                    continue;
+                };
+                let text = |key: &str| {
+                    delta
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                match (block.as_mut(), delta.get("type").and_then(Value::as_str)) {
+                    (Some(Block::Text(buffer)), Some("text_delta")) => {
+                        let delta = text("text");
+                        buffer.push_str(&delta);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::OutputTextDelta(delta)))
+                            .await;
+                    }
+                    (Some(Block::Thinking { text: buffer, .. }), Some("thinking_delta")) => {
+                        let delta = text("thinking");
+                        buffer.push_str(&delta);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::ReasoningContentDelta(delta)))
+                            .await;
+                    }
+                    (Some(Block::Thinking { signature, .. }), Some("signature_delta")) => {
+                        signature.push_str(&text("signature"));
+                    }
+                    (Some(Block::ToolUse { id, name, input }), Some("input_json_delta")) => {
+                        let delta = text("partial_json");
+                        input.push_str(&delta);
+                        if !delta.is_empty() {
+                            let event = ResponseEvent::ToolCallArgumentsDelta {
+                                call_id: id.clone(),
+                                name: name.clone(),
+                                delta,
+                            };
+                            let _ = tx_event.send(Ok(event)).await;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = block.take().and_then(|b| block_item(b, &freeform_tools)) {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                if let Some(output_tokens) = event
+                    .get("usage")
+                    .and_then(|u| u.get("output_tokens"))
+                    .and_then(Value::as_u64)
+                {
+                    usage.output_tokens = output_tokens;
+                }
+            }
+            "message_stop" => {
+                usage.total_tokens = usage.input_tokens + usage.output_tokens;
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id: std::mem::take(&mut response_id),
+                        token_usage: Some(std::mem::take(&mut usage)),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let error = event.get("error");
+                let message = error
+                    .and_then(|e| e.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                let kind = error
+                    .and_then(|e| e.get("type"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("error");
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(format!("{kind}: {message}"), None)))
+                    .await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The response item of a finished content block. Thinking signatures and
+/// redacted thinking are kept as the encrypted content of reasoning items so
+/// they can be sent back on the next request.
+pub(crate) fn block_item(block: Block, freeform_tools: &HashSet<String>) -> Option<ResponseItem> {
+    match block {
+        Block::Text(text) if text.is_empty() => None,
+        Block::Text(text) => Some(ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText { text }],
+        }),
+        Block::Thinking { text, signature } => Some(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText { text }]),
+            encrypted_content: (!signature.is_empty()).then_some(signature),
+        }),
+        Block::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: None,
+            encrypted_content: Some(data),
+        }),
+        Block::ToolUse { id, name, input } => {
+            let input = if input.trim().is_empty() {
+                "{}".to_string()
+            } else {
+                input
+            };
+            if freeform_tools.contains(&name) {
+                let input = serde_json::from_str::<Value>(&input)
+                    .ok()
+                    .and_then(|v| {
+                        v.get(FREEFORM_INPUT_PARAM)
+                            .and_then(Value::as_str)
+                            .map(str::to_string)
+                    })
+                    .unwrap_or_default();
+                Some(ResponseItem::CustomToolCall {
+                    id: None,
+                    status: None,
+                    call_id: id,
+                    name,
+                    input,
+                })
+            } else {
+                Some(ResponseItem::FunctionCall {
+                    id: None,
+                    name,
+                    arguments: input,
+                    call_id: id,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use codex_protocol::ConversationId;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn otel_event_manager() -> OtelEventManager {
+        OtelEventManager::new(
+            ConversationId::new(),
+            "test",
+            "test",
+            None,
+            None,
+            false,
+            "test".to_string(),
+        )
+    }
+
+    async fn run_sse(events: Vec<Value>, freeform_tools: &[&str]) -> Vec<Result<ResponseEvent>> {
+        let mut body = String::new();
+        for event in events {
+            let kind = event
+                .get("type")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            body.push_str(&format!("event: {kind}\ndata: {event}\n\n"));
+        }
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        let stream =
+            tokio_util::io::ReaderStream::new(std::io::Cursor::new(body)).map_err(CodexErr::Io);
+        tokio::spawn(process_anthropic_sse(
+            stream,
+            tx,
+            Duration::from_secs(5),
+            freeform_tools.iter().map(ToString::to_string).collect(),
+            otel_event_manager(),
+        ));
+        let mut out = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            out.push(ev);
+        }
+        out
+    }
+
+    #[test]
+    fn maps_conversation_onto_alternating_messages() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText {
+                    text: "use ls".to_string(),
+                }]),
+                encrypted_content: Some("sig".to_string()),
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "exit 1".to_string(),
+                    success: Some(false),
+                },
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                }],
+            },
+        ];
+
+        assert_eq!(
+            build_messages(&input),
+            vec![
+                json!({"role": "user", "content": [{"type": "text", "text": "list files"}]}),
+                json!({"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "use ls", "signature": "sig"},
+                    {"type": "tool_use", "id": "toolu_1", "name": "shell", "input": {"command": ["ls"]}},
+                ]}),
+                json!({"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "exit 1", "is_error": true},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+                ]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn enables_thinking_with_a_reasoning_effort() {
+        let family = derive_default_model_family("claude-sonnet-4-5");
+        let prompt = Prompt::default();
+
+        let payload = build_payload(
+            &prompt,
+            &family,
+            Some(ReasoningEffortConfig::High),
+            None,
+            false,
+        );
+        assert_eq!(
+            payload["thinking"],
+            json!({"type": "enabled", "budget_tokens": 16384})
+        );
+        assert_eq!(payload["max_tokens"], json!(16384 + MIN_ANSWER_TOKENS));
+
+        let payload = build_payload(&prompt, &family, None, Some(2048), false);
+        assert_eq!(payload.get("thinking"), None);
+        assert_eq!(payload["max_tokens"], json!(2048));
+    }
+
+    #[test]
+    fn marks_the_system_prompt_and_last_user_messages_for_caching() {
+        let family = derive_default_model_family("claude-sonnet-4-5");
+        let message = |role: &str, text: &str| ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        };
+        let prompt = Prompt {
+            input: vec![
+                message("user", "one"),
+                message("assistant", "two"),
+                message("user", "three"),
+                message("assistant", "four"),
+                message("user", "five"),
+            ],
+            base_instructions_override: Some("Be brief.".to_string()),
+            ..Default::default()
+        };
+
+        let payload = build_payload(&prompt, &family, None, None, true);
+
+        let cache_control = json!({"type": "ephemeral"});
+        assert_eq!(
+            payload["system"],
+            json!([{"type": "text", "text": "Be brief.", "cache_control": cache_control}])
+        );
+        assert_eq!(
+            payload["messages"],
+            json!([
+                {"role": "user", "content": [{"type": "text", "text": "one"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "two"}]},
+                {"role": "user", "content": [{"type": "text", "text": "three", "cache_control": cache_control}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "four"}]},
+                {"role": "user", "content": [{"type": "text", "text": "five", "cache_control": cache_control}]},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_blocks_onto_response_items() {
+        let events = vec![
+            json!({"type": "message_start", "message": {"id": "msg_1", "usage": {"input_tokens": 10, "cache_read_input_tokens": 90}}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "patch it"}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "sig"}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "Done."}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "apply_patch", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"input\": \"*** Begin Patch\"}"}}),
+            json!({"type": "content_block_stop", "index": 2}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 5}}),
+            json!({"type": "message_stop"}),
+        ];
+
+        let items: Vec<ResponseItem> = run_sse(events, &["apply_patch"])
+            .await
+            .into_iter()
+            .filter_map(|ev| match ev {
+                Ok(ResponseEvent::OutputItemDone(item)) => Some(item),
+                Ok(ResponseEvent::Completed {
+                    response_id,
+                    token_usage,
+                }) => {
+                    assert_eq!(response_id, "msg_1");
+                    let usage = token_usage.expect("usage");
+                    assert_eq!(usage.input_tokens, 100);
+                    assert_eq!(usage.cached_input_tokens, 90);
+                    assert_eq!(usage.total_tokens, 105);
+                    None
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Reasoning {
+                    id: String::new(),
+                    summary: Vec::new(),
+                    content: Some(vec![ReasoningItemContent::ReasoningText {
+                        text: "patch it".to_string(),
+                    }]),
+                    encrypted_content: Some("sig".to_string()),
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Done.".to_string(),
+                    }],
+                },
+                ResponseItem::CustomToolCall {
+                    id: None,
+                    status: None,
+                    call_id: "toolu_1".to_string(),
+                    name: "apply_patch".to_string(),
+                    input: "*** Begin Patch".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_stream_errors() {
+        let events = vec![
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}),
+        ];
+
+        let events = run_sse(events, &[]).await;
+
+        assert!(matches!(
+            events.last(),
+            Some(Err(CodexErr::Stream(message, None))) if message == "overloaded_error: Overloaded"
+        ));
+    }
+}
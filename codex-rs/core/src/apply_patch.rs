@@ -1,5 +1,6 @@
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::file_locks::FileLockConflict;
 use crate::function_tool::FunctionCallError;
 use crate::protocol::FileChange;
 use crate::protocol::ReviewDecision;
@@ -8,6 +9,7 @@ use crate::safety::assess_patch_safety;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 use std::collections::HashMap;
+use std::path::Path;
 use std::path::PathBuf;
 
 pub const CODEX_APPLY_PATCH_ARG1: &str = "--codex-run-as-apply-patch";
@@ -39,6 +41,16 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
+    // Refuse up front if a concurrent task is editing any of these files.
+    if let Err(FileLockConflict { path, holder }) = sess.lock_files(patch_paths(&action)) {
+        return InternalApplyPatchInvocation::Output(Err(FunctionCallError::RespondToModel(
+            format!(
+                "{} is being edited by another task (conversation {holder}); leave it alone or retry once that task has finished",
+                path.display()
+            ),
+        )));
+    }
+
     match assess_patch_safety(
         &action,
         turn_context.approval_policy,
@@ -109,3 +121,14 @@ pub(crate) fn convert_apply_patch_to_protocol(
     }
     result
 }
+
+/// Every path written by `action`, including rename destinations.
+fn patch_paths(action: &ApplyPatchAction) -> impl Iterator<Item = &Path> {
+    action.changes().iter().flat_map(|(path, change)| {
+        let move_path = match change {
+            ApplyPatchFileChange::Update { move_path, .. } => move_path.as_deref(),
+            ApplyPatchFileChange::Add { .. } | ApplyPatchFileChange::Delete { .. } => None,
+        };
+        std::iter::once(path.as_path()).chain(move_path)
+    })
+}
@@ -0,0 +1,325 @@
+//! AWS credentials and Signature Version 4 request signing, for the Bedrock
+//! provider.
+//!
+//! Credentials come from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+//! `AWS_SESSION_TOKEN` when they are set, and otherwise from a profile of the
+//! shared credentials file (`~/.aws/credentials`).
+
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use chrono::Utc;
+use ring::digest;
+use ring::hmac;
+
+use crate::util::hex;
+
+const ACCESS_KEY_ID_ENV_VAR: &str = "AWS_ACCESS_KEY_ID";
+const SECRET_ACCESS_KEY_ENV_VAR: &str = "AWS_SECRET_ACCESS_KEY";
+const SESSION_TOKEN_ENV_VAR: &str = "AWS_SESSION_TOKEN";
+const PROFILE_ENV_VAR: &str = "AWS_PROFILE";
+const CREDENTIALS_FILE_ENV_VAR: &str = "AWS_SHARED_CREDENTIALS_FILE";
+const DEFAULT_PROFILE: &str = "default";
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Credentials requests are signed with.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AwsCredentials {
+    /// Credentials from the environment, or from `profile` (default:
+    /// `AWS_PROFILE`, then `default`) of the shared credentials file.
+    pub(crate) fn load(profile: Option<&str>) -> std::io::Result<Self> {
+        let env = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (env(ACCESS_KEY_ID_ENV_VAR), env(SECRET_ACCESS_KEY_ENV_VAR))
+        {
+            return Ok(Self {
+                access_key_id,
+                secret_access_key,
+                session_token: env(SESSION_TOKEN_ENV_VAR),
+            });
+        }
+
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| env(PROFILE_ENV_VAR))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        let path = env(CREDENTIALS_FILE_ENV_VAR)
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".aws").join("credentials")));
+        let contents = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        parse_credentials_file(&contents, &profile).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "no AWS credentials found: set {ACCESS_KEY_ID_ENV_VAR} and \
+                     {SECRET_ACCESS_KEY_ENV_VAR}, or add profile `{profile}` to {}",
+                    path.map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "~/.aws/credentials".to_string())
+                ),
+            )
+        })
+    }
+}
+
+/// The credentials of `profile` in the INI-formatted shared credentials file.
+fn parse_credentials_file(contents: &str, profile: &str) -> Option<AwsCredentials> {
+    let mut in_profile = false;
+    let mut access_key_id = None;
+    let mut secret_access_key = None;
+    let mut session_token = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section.trim() == profile;
+            continue;
+        }
+        if !in_profile {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = Some(value.trim().to_string());
+        match key.trim() {
+            "aws_access_key_id" => access_key_id = value,
+            "aws_secret_access_key" => secret_access_key = value,
+            "aws_session_token" => session_token = value,
+            _ => {}
+        }
+    }
+    Some(AwsCredentials {
+        access_key_id: access_key_id?,
+        secret_access_key: secret_access_key?,
+        session_token,
+    })
+}
+
+/// The headers that sign `request`: `x-amz-date`, `x-amz-security-token` for
+/// temporary credentials, and `authorization`. The headers of `request` are
+/// signed along with `host`, which is taken from its URL.
+pub(crate) fn sign_request(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    request: &reqwest::Request,
+    now: DateTime<Utc>,
+) -> Vec<(String, String)> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+
+    let url = request.url();
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let mut signed: Vec<(String, String)> = request
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            (name.as_str().to_string(), value.trim().to_string())
+        })
+        .collect();
+    signed.push(("host".to_string(), host));
+    signed.push(("x-amz-date".to_string(), amz_date.clone()));
+    if let Some(token) = &credentials.session_token {
+        signed.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    signed.sort();
+
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = signed
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let body = request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .unwrap_or_default();
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{canonical_headers}\n{signed_headers}\n{}",
+        request.method(),
+        canonical_uri(url.path()),
+        canonical_query(url),
+        hex_sha256(body)
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        hex_sha256(canonical_request.as_bytes())
+    );
+    let key = signing_key(&credentials.secret_access_key, &date, region, service);
+    let signature = hex(hmac::sign(&key, string_to_sign.as_bytes()).as_ref());
+
+    let mut out = vec![("x-amz-date".to_string(), amz_date)];
+    if let Some(token) = &credentials.session_token {
+        out.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    out.push((
+        "authorization".to_string(),
+        format!(
+            "{ALGORITHM} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            credentials.access_key_id
+        ),
+    ));
+    out
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> hmac::Key {
+    let mut key = hmac::Key::new(hmac::HMAC_SHA256, format!("AWS4{secret}").as_bytes());
+    for part in [date, region, service, "aws4_request"] {
+        let tag = hmac::sign(&key, part.as_bytes());
+        key = hmac::Key::new(hmac::HMAC_SHA256, tag.as_ref());
+    }
+    key
+}
+
+/// The path of the canonical request. Services other than S3 expect each
+/// segment of the (already encoded) path to be encoded once more.
+fn canonical_uri(path: &str) -> String {
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &url::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Percent-encode everything but the unreserved characters, as SigV4
+/// requires.
+pub(crate) fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn signs_the_reference_request() {
+        // The example request of the AWS Signature Version 4 documentation.
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        };
+        let mut request = reqwest::Request::new(
+            reqwest::Method::GET,
+            url::Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08")
+                .unwrap(),
+        );
+        request.headers_mut().insert(
+            reqwest::header::CONTENT_TYPE,
+            reqwest::header::HeaderValue::from_static(
+                "application/x-www-form-urlencoded; charset=utf-8",
+            ),
+        );
+        let now = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+
+        let headers = sign_request(&credentials, "us-east-1", "iam", &request, now);
+
+        assert_eq!(
+            headers,
+            vec![
+                ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+                (
+                    "authorization".to_string(),
+                    "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+                     SignedHeaders=content-type;host;x-amz-date, \
+                     Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_profile_of_the_credentials_file() {
+        let contents = "\
+[default]
+aws_access_key_id = AKIDDEFAULT
+aws_secret_access_key = secret-default
+
+[work]
+aws_access_key_id=AKIDWORK
+aws_secret_access_key=secret-work
+aws_session_token=token
+";
+
+        assert_eq!(
+            parse_credentials_file(contents, "work"),
+            Some(AwsCredentials {
+                access_key_id: "AKIDWORK".to_string(),
+                secret_access_key: "secret-work".to_string(),
+                session_token: Some("token".to_string()),
+            })
+        );
+        assert_eq!(parse_credentials_file(contents, "missing"), None);
+    }
+
+    #[test]
+    fn double_encodes_path_segments() {
+        assert_eq!(
+            canonical_uri("/model/us.anthropic.claude%3A0/converse-stream"),
+            "/model/us.anthropic.claude%253A0/converse-stream"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Microsoft Entra ID (AAD) tokens for Azure OpenAI providers with
+//! `auth = "entra"`.
+//!
+//! The token is taken from `AZURE_OPENAI_AD_TOKEN` when it is set, and
+//! otherwise obtained from the Azure CLI (`az account get-access-token`) of
+//! the signed-in user, cached until shortly before it expires.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+/// Environment variable holding a ready-made token.
+const AD_TOKEN_ENV_VAR: &str = "AZURE_OPENAI_AD_TOKEN";
+
+/// Resource the tokens are requested for.
+const COGNITIVE_SERVICES_RESOURCE: &str = "https://cognitiveservices.azure.com";
+
+/// Tokens are refreshed this long before they expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// Assumed lifetime of a token whose expiry the Azure CLI does not report.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(45 * 60);
+
+struct CachedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+static TOKEN: LazyLock<Mutex<Option<CachedToken>>> = LazyLock::new(Mutex::default);
+
+#[derive(Deserialize)]
+struct AzCliToken {
+    #[serde(rename = "accessToken")]
+    access_token: String,
+    /// Seconds since the epoch; reported by recent versions of the CLI, next
+    /// to the local time in `expiresOn`.
+    expires_on: Option<u64>,
+}
+
+/// An Entra ID access token for Azure OpenAI.
+pub(crate) async fn entra_token() -> std::io::Result<String> {
+    if let Ok(token) = std::env::var(AD_TOKEN_ENV_VAR)
+        && !token.trim().is_empty()
+    {
+        return Ok(token.trim().to_string());
+    }
+
+    let mut cached = TOKEN.lock().await;
+    if let Some(cached) = cached.as_ref()
+        && Instant::now() < cached.refresh_at
+    {
+        return Ok(cached.token.clone());
+    }
+    let token = az_cli_token().await?;
+    let lifetime = token
+        .expires_on
+        .and_then(|expires_on| {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?;
+            Duration::from_secs(expires_on).checked_sub(now)
+        })
+        .unwrap_or(DEFAULT_LIFETIME);
+    *cached = Some(CachedToken {
+        token: token.access_token.clone(),
+        refresh_at: Instant::now() + lifetime.saturating_sub(REFRESH_MARGIN),
+    });
+    Ok(token.access_token)
+}
+
+async fn az_cli_token() -> std::io::Result<AzCliToken> {
+    let output = Command::new(if cfg!(windows) { "az.cmd" } else { "az" })
+        .args([
+            "account",
+            "get-access-token",
+            "--resource",
+            COGNITIVE_SERVICES_RESOURCE,
+            "--output",
+            "json",
+        ])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .await
+        .map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!(
+                    "failed to run the Azure CLI for an Entra ID token ({e}); install it and \
+                     run `az login`, or set {AD_TOKEN_ENV_VAR}"
+                ),
+            )
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!(
+            "`az account get-access-token` failed with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    parse_az_cli_token(&output.stdout)
+}
+
+fn parse_az_cli_token(stdout: &[u8]) -> std::io::Result<AzCliToken> {
+    serde_json::from_slice(stdout).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("unexpected output of `az account get-access-token`: {e}"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_azure_cli_output() -> std::io::Result<()> {
+        let token = parse_az_cli_token(
+            br#"{
+  "accessToken": "eyJ0eXAi",
+  "expiresOn": "2026-10-17 12:00:00.000000",
+  "expires_on": 1792231200,
+  "tenant": "00000000-0000-0000-0000-000000000000",
+  "tokenType": "Bearer"
+}"#,
+        )?;
+
+        assert_eq!(token.access_token, "eyJ0eXAi");
+        assert_eq!(token.expires_on, Some(1792231200));
+        Ok(())
+    }
+}
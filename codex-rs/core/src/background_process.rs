@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
@@ -21,13 +22,16 @@ use tokio::process::Child;
 use tokio::sync::Mutex as AsyncMutex;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
+use tracing::warn;
 
 use crate::codex::ExecCommandContext;
 use crate::codex::Session;
+use crate::config_types::BackgroundProcess;
 use crate::exec::ExecParams;
 use crate::exec::SandboxType;
 use crate::function_tool::FunctionCallError;
 use crate::landlock::spawn_command_under_linux_sandbox;
+use crate::protocol::BackgroundProcessRequest;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxPolicy;
 use crate::safety::SafetyCheck;
@@ -37,7 +41,6 @@ use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use codex_otel::otel_event_manager::ToolDecisionSource;
 
-const LOG_CAP_BYTES: usize = 512 * 1024; // 512 KiB cap per process
 const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
 const BACKGROUND_TOOL_NAME: &str = "background_process";
 
@@ -85,22 +88,36 @@ impl LogStream {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct ProcessLog {
     entries: VecDeque<LogEntry>,
     total_bytes: usize,
+    cap_bytes: usize,
+    strip_ansi: bool,
 }
 
 impl ProcessLog {
+    fn new(settings: &BackgroundProcess) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            total_bytes: 0,
+            cap_bytes: settings.log_cap_bytes,
+            strip_ansi: settings.strip_ansi,
+        }
+    }
+
     fn append(&mut self, stream: LogStream, chunk: &[u8]) {
-        if chunk.is_empty() {
+        let mut text = String::from_utf8_lossy(chunk).into_owned();
+        if self.strip_ansi {
+            text = strip_ansi_escapes(&text);
+        }
+        if text.is_empty() {
             return;
         }
-        let text = String::from_utf8_lossy(chunk).into_owned();
         self.total_bytes = self.total_bytes.saturating_add(text.len());
         self.entries.push_back(LogEntry { stream, text });
 
-        while self.total_bytes > LOG_CAP_BYTES {
+        while self.total_bytes > self.cap_bytes {
             if let Some(front) = self.entries.pop_front() {
                 self.total_bytes = self.total_bytes.saturating_sub(front.text.len());
             } else {
@@ -120,6 +137,7 @@ struct ManagedBackgroundProcess {
     cwd: PathBuf,
     started_at: SystemTime,
     sandbox_type: SandboxType,
+    pid: Option<u32>,
     child: Arc<AsyncMutex<Child>>,
     state: Arc<RwLock<BackgroundProcessState>>,
     log: Arc<AsyncMutex<ProcessLog>>,
@@ -138,6 +156,7 @@ impl ManagedBackgroundProcess {
             started_at: self.started_at,
             state,
             sandbox_type: self.sandbox_type,
+            pid: self.pid,
         }
     }
 
@@ -152,7 +171,18 @@ impl ManagedBackgroundProcess {
             .collect()
     }
 
-    async fn kill(&self) -> Result<(), std::io::Error> {
+    fn is_running(&self) -> bool {
+        self.state.try_read().map_or(true, |state| {
+            matches!(*state, BackgroundProcessState::Running)
+        })
+    }
+
+    /// Ask the process to exit with SIGTERM and kill it if it is still
+    /// running after `grace`.
+    async fn kill(&self, grace: Duration) -> Result<(), std::io::Error> {
+        if !grace.is_zero() && self.is_running() && self.terminate(grace).await {
+            return Ok(());
+        }
         let mut child = self.child.lock().await;
         match child.start_kill() {
             Ok(()) => Ok(()),
@@ -160,6 +190,32 @@ impl ManagedBackgroundProcess {
             Err(err) => Err(err),
         }
     }
+
+    /// Send SIGTERM and wait up to `grace` for the process to exit. Returns
+    /// whether it did.
+    #[cfg(unix)]
+    async fn terminate(&self, grace: Duration) -> bool {
+        let Some(pid) = self.pid.and_then(|pid| libc::pid_t::try_from(pid).ok()) else {
+            return false;
+        };
+        // SAFETY: sending a signal has no memory-safety requirements.
+        unsafe {
+            libc::kill(pid, libc::SIGTERM);
+        }
+        let deadline = tokio::time::Instant::now() + grace;
+        while tokio::time::Instant::now() < deadline {
+            if !matches!(*self.state.read().await, BackgroundProcessState::Running) {
+                return true;
+            }
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        }
+        false
+    }
+
+    #[cfg(not(unix))]
+    async fn terminate(&self, _grace: Duration) -> bool {
+        false
+    }
 }
 
 impl Drop for ManagedBackgroundProcess {
@@ -178,6 +234,8 @@ pub(crate) struct BackgroundProcessSummary {
     pub(crate) started_at: SystemTime,
     pub(crate) state: BackgroundProcessState,
     pub(crate) sandbox_type: SandboxType,
+    /// `None` once the process has been reaped.
+    pub(crate) pid: Option<u32>,
 }
 
 #[derive(Debug, Clone)]
@@ -192,6 +250,7 @@ pub(crate) struct StartProcessResponse {
 }
 
 pub(crate) struct BackgroundProcessManager {
+    settings: BackgroundProcess,
     next_id: AtomicU64,
     processes: AsyncMutex<HashMap<String, Arc<ManagedBackgroundProcess>>>,
     running_count: Arc<AtomicU64>,
@@ -199,8 +258,9 @@ pub(crate) struct BackgroundProcessManager {
 }
 
 impl BackgroundProcessManager {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(settings: BackgroundProcess) -> Self {
         Self {
+            settings,
             next_id: AtomicU64::new(0),
             processes: AsyncMutex::new(HashMap::new()),
             running_count: Arc::new(AtomicU64::new(0)),
@@ -225,6 +285,15 @@ impl BackgroundProcessManager {
         approved_commands: HashSet<Vec<String>>,
         codex_linux_sandbox_exe: Option<PathBuf>,
     ) -> Result<StartProcessResponse, FunctionCallError> {
+        let max_concurrent = self.settings.max_concurrent;
+        if max_concurrent > 0 && self.running_count.load(Ordering::SeqCst) >= max_concurrent as u64
+        {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{max_concurrent} background processes are already running, the most \
+                 `background_process.max_concurrent` allows; kill one first"
+            )));
+        }
+
         let id_num = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
         let process_id = format!("bg-{id_num}");
         let otel_event_manager = turn_context.client.get_otel_event_manager();
@@ -316,9 +385,10 @@ impl BackgroundProcessManager {
             FunctionCallError::RespondToModel("failed to capture stderr".to_string())
         })?;
 
+        let pid = child.id();
         let child = Arc::new(AsyncMutex::new(child));
         let state = Arc::new(RwLock::new(BackgroundProcessState::Running));
-        let log = Arc::new(AsyncMutex::new(ProcessLog::default()));
+        let log = Arc::new(AsyncMutex::new(ProcessLog::new(&self.settings)));
 
         let stdout_task =
             spawn_log_task(Arc::clone(&log), BufReader::new(stdout), LogStream::Stdout);
@@ -337,6 +407,7 @@ impl BackgroundProcessManager {
             cwd: exec_params.cwd.clone(),
             started_at: SystemTime::now(),
             sandbox_type,
+            pid,
             child,
             state,
             log,
@@ -393,10 +464,59 @@ impl BackgroundProcessManager {
         })?;
 
         process
-            .kill()
+            .kill(self.kill_grace())
             .await
             .map_err(|err| FunctionCallError::RespondToModel(err.to_string()))
     }
+
+    /// Kill every process that is still running. Returns how many were
+    /// signalled.
+    pub(crate) async fn kill_all(&self) -> usize {
+        let processes: Vec<Arc<ManagedBackgroundProcess>> = {
+            let guard = self.processes.lock().await;
+            guard.values().cloned().collect()
+        };
+
+        let grace = self.kill_grace();
+        let kills = processes.iter().map(|process| async move {
+            if !matches!(*process.state.read().await, BackgroundProcessState::Running) {
+                return false;
+            }
+            match process.kill(grace).await {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!("failed to kill background process {}: {err}", process.id);
+                    false
+                }
+            }
+        });
+        futures::future::join_all(kills)
+            .await
+            .into_iter()
+            .filter(|killed| *killed)
+            .count()
+    }
+
+    /// Stop the running processes as the session ends, unless
+    /// `kill_on_session_end` is off. Then they are left running instead:
+    /// their handles are leaked, so dropping the session does not kill them
+    /// and their output is still read while Codex runs.
+    pub(crate) async fn end_session(&self) {
+        if self.settings.kill_on_session_end {
+            self.kill_all().await;
+            return;
+        }
+        let processes = std::mem::take(&mut *self.processes.lock().await);
+        for process in processes.into_values() {
+            if process.is_running() {
+                std::mem::forget(process);
+            }
+        }
+    }
+
+    fn kill_grace(&self) -> Duration {
+        Duration::from_secs(self.settings.kill_grace_sec)
+    }
 }
 
 async fn spawn_background_child(
@@ -563,11 +683,48 @@ async fn notify_running_count(
     }
 }
 
+/// `text` without ANSI escape sequences: CSI sequences such as colors and
+/// cursor movement, OSC sequences such as window titles, and two-character
+/// escapes. A sequence cut off at the end of `text` is dropped.
+fn strip_ansi_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\u{40}'..='\u{7e}').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' {
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
 pub(crate) fn make_exec_context_for_background(
     sub_id: String,
     call_id: String,
     command_for_display: Vec<String>,
     cwd: PathBuf,
+    session_env: BTreeMap<String, String>,
 ) -> ExecCommandContext {
     ExecCommandContext {
         sub_id,
@@ -575,6 +732,7 @@ pub(crate) fn make_exec_context_for_background(
         command_for_display,
         cwd,
         apply_patch: None,
+        session_env,
     }
 }
 
@@ -604,6 +762,46 @@ pub(crate) enum BackgroundProcessAction {
     Kill,
 }
 
+impl From<BackgroundProcessRequest> for BackgroundProcessInvocation {
+    fn from(request: BackgroundProcessRequest) -> Self {
+        let (action, command, cwd, env, process_id) = match request {
+            BackgroundProcessRequest::Start { command, cwd, env } => (
+                BackgroundProcessAction::Start,
+                Some(command),
+                cwd,
+                env,
+                None,
+            ),
+            BackgroundProcessRequest::List => {
+                (BackgroundProcessAction::List, None, None, None, None)
+            }
+            BackgroundProcessRequest::Logs { process_id } => (
+                BackgroundProcessAction::Logs,
+                None,
+                None,
+                None,
+                Some(process_id),
+            ),
+            BackgroundProcessRequest::Kill { process_id } => (
+                BackgroundProcessAction::Kill,
+                None,
+                None,
+                None,
+                Some(process_id),
+            ),
+        };
+        Self {
+            action,
+            command,
+            cwd,
+            env,
+            process_id,
+            with_escalated_permissions: None,
+            justification: None,
+        }
+    }
+}
+
 pub(crate) fn system_time_to_unix_millis(time: SystemTime) -> Option<u128> {
     time.duration_since(SystemTime::UNIX_EPOCH)
         .ok()
@@ -641,6 +839,31 @@ mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn log_strips_ansi_and_keeps_the_latest_output() {
+        let mut log = ProcessLog::new(&BackgroundProcess {
+            log_cap_bytes: 12,
+            ..Default::default()
+        });
+        log.append(LogStream::Stdout, b"\x1b[1;32mready\x1b[0m\n");
+        log.append(LogStream::Stderr, b"\x1b]0;title\x07warn\n");
+        log.append(LogStream::Stdout, b"\x1b[2K");
+        log.append(LogStream::Stdout, b"done\n");
+
+        let entries: Vec<(LogStream, String)> = log
+            .snapshot()
+            .into_iter()
+            .map(|entry| (entry.stream, entry.text))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (LogStream::Stderr, "warn\n".to_string()),
+                (LogStream::Stdout, "done\n".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn system_time_converts_to_millis() {
         let ts = SystemTime::UNIX_EPOCH + Duration::from_millis(1234);
@@ -0,0 +1,428 @@
+//! The `batch_edit` tool, which makes the same mechanical edit across many
+//! files in one call: a regex replacement, or a rename of an identifier that
+//! uses tree-sitter to leave strings, comments and longer names alone.
+//!
+//! Every changed file goes into one `apply_patch`-style change, so the whole
+//! edit is previewed as a diff and approved once. A dry run returns the diffs
+//! without writing anything.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::ApplyPatchAction;
+use regex_lite::Regex;
+use regex_lite::RegexBuilder;
+use serde::Deserialize;
+use serde::Serialize;
+use similar::TextDiff;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+
+use crate::code_search::walk_builder;
+use crate::code_symbols::SourceLanguage;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::read_file::is_readable;
+
+pub(crate) const BATCH_EDIT_TOOL_NAME: &str = "batch_edit";
+
+/// An edit that would change more files than this is refused.
+const MAX_EDITED_FILES: usize = 500;
+/// Larger files are skipped.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum Edit {
+    /// Replace every match of `pattern` with `replacement`, which may refer
+    /// to capture groups as `$1` or `${name}`.
+    Regex {
+        pattern: String,
+        replacement: String,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    /// Rename the identifier `from` to `to` in Rust, Python, JavaScript,
+    /// TypeScript and Go files.
+    Rename { from: String, to: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct BatchEditArgs {
+    #[serde(flatten)]
+    edit: Edit,
+    /// File or directory to edit, relative to the working directory.
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    globs: Vec<String>,
+    #[serde(default)]
+    pub(crate) dry_run: bool,
+}
+
+/// The files an edit changes, in path order.
+#[derive(Debug)]
+pub(crate) struct BatchEditPlan {
+    pub(crate) files: Vec<EditedFile>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct EditedFile {
+    pub(crate) path: PathBuf,
+    original: String,
+    pub(crate) content: String,
+    replacements: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct BatchEditOutput {
+    applied: bool,
+    replacements: usize,
+    files: Vec<EditedFileOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct EditedFileOutput {
+    /// Relative to the working directory.
+    path: String,
+    replacements: usize,
+    /// Only included in a dry run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+pub(crate) fn parse_batch_edit_arguments(
+    arguments: &str,
+) -> Result<BatchEditArgs, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })
+}
+
+/// Work out what `args` would change under `cwd` without writing anything.
+pub(crate) async fn plan_batch_edit(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    args: BatchEditArgs,
+) -> Result<BatchEditPlan, FunctionCallError> {
+    let requested = args.path.as_deref().unwrap_or(".");
+    if !is_readable(sandbox_policy, cwd, &cwd.join(requested)) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "reading {requested} is not allowed by the sandbox policy"
+        )));
+    }
+    let cwd = cwd.to_path_buf();
+    tokio::task::spawn_blocking(move || plan(&cwd, args))
+        .await
+        .map_err(|e| FunctionCallError::RespondToModel(format!("batch_edit failed: {e}")))?
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+impl BatchEditPlan {
+    /// The change that applying the plan makes, for approval and the turn's
+    /// diff tracker.
+    pub(crate) fn action(&self, cwd: &Path) -> ApplyPatchAction {
+        let updates = self
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.clone(),
+                    file.original.clone(),
+                    file.content.clone(),
+                )
+            })
+            .collect();
+        ApplyPatchAction::new_updates(cwd, updates)
+    }
+
+    /// The JSON result of the call. A dry run includes each file's diff.
+    pub(crate) fn output(&self, cwd: &Path, applied: bool) -> String {
+        let files = self
+            .files
+            .iter()
+            .map(|file| EditedFileOutput {
+                path: file
+                    .path
+                    .strip_prefix(cwd)
+                    .unwrap_or(&file.path)
+                    .to_string_lossy()
+                    .into_owned(),
+                replacements: file.replacements,
+                diff: (!applied).then(|| {
+                    TextDiff::from_lines(&file.original, &file.content)
+                        .unified_diff()
+                        .context_radius(1)
+                        .to_string()
+                }),
+            })
+            .collect();
+        let output = BatchEditOutput {
+            applied,
+            replacements: self.files.iter().map(|file| file.replacements).sum(),
+            files,
+        };
+        serde_json::to_string(&output).unwrap_or_else(|e| format!("failed to encode edit: {e}"))
+    }
+}
+
+fn plan(cwd: &Path, args: BatchEditArgs) -> Result<BatchEditPlan, String> {
+    let root = cwd.join(args.path.as_deref().unwrap_or("."));
+    if !root.exists() {
+        return Err(format!("{} does not exist", root.display()));
+    }
+    let edit = CompiledEdit::new(args.edit)?;
+    let mut files = Vec::new();
+    for entry in walk_builder(&root, &args.globs)?.build().flatten() {
+        let is_small_file = entry
+            .metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.len() <= MAX_FILE_BYTES);
+        if !is_small_file {
+            continue;
+        }
+        // Files that are not UTF-8 text are left alone rather than rewritten
+        // lossily.
+        let Ok(original) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if original.contains('\0') {
+            continue;
+        }
+        let Some((content, replacements)) = edit.apply(entry.path(), &original)? else {
+            continue;
+        };
+        if files.len() == MAX_EDITED_FILES {
+            return Err(format!(
+                "the edit would change more than {MAX_EDITED_FILES} files; narrow it with `path` or `globs`"
+            ));
+        }
+        files.push(EditedFile {
+            path: entry.path().to_path_buf(),
+            original,
+            content,
+            replacements,
+        });
+    }
+    Ok(BatchEditPlan { files })
+}
+
+enum CompiledEdit {
+    Regex { regex: Regex, replacement: String },
+    Rename { from: String, to: String },
+}
+
+impl CompiledEdit {
+    fn new(edit: Edit) -> Result<Self, String> {
+        match edit {
+            Edit::Regex {
+                pattern,
+                replacement,
+                case_insensitive,
+            } => {
+                let regex = RegexBuilder::new(&pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|e| format!("invalid pattern: {e}"))?;
+                Ok(Self::Regex { regex, replacement })
+            }
+            Edit::Rename { from, to } => {
+                for name in [&from, &to] {
+                    if !is_identifier(name) {
+                        return Err(format!("`{name}` is not an identifier"));
+                    }
+                }
+                if from == to {
+                    return Err("`from` and `to` are the same name".to_string());
+                }
+                Ok(Self::Rename { from, to })
+            }
+        }
+    }
+
+    /// The edited content of the file at `path` and the number of
+    /// replacements, or `None` when the edit does not change it.
+    fn apply(&self, path: &Path, original: &str) -> Result<Option<(String, usize)>, String> {
+        let (content, replacements) = match self {
+            Self::Regex { regex, replacement } => {
+                let replacements = regex.find_iter(original).count();
+                if replacements == 0 {
+                    return Ok(None);
+                }
+                let content = regex.replace_all(original, replacement.as_str());
+                (content.into_owned(), replacements)
+            }
+            Self::Rename { from, to } => {
+                let Some(language) = SourceLanguage::from_path(path) else {
+                    return Ok(None);
+                };
+                let ranges = identifier_ranges(language, original, from)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                if ranges.is_empty() {
+                    return Ok(None);
+                }
+                let mut content = String::with_capacity(original.len());
+                let mut last = 0;
+                for range in &ranges {
+                    content.push_str(&original[last..range.start]);
+                    content.push_str(to);
+                    last = range.end;
+                }
+                content.push_str(&original[last..]);
+                (content, ranges.len())
+            }
+        };
+        Ok((content != original).then_some((content, replacements)))
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+/// The byte ranges of the identifiers in `source` spelled exactly `name`, in
+/// order. Strings and comments are single tokens to tree-sitter, so names
+/// inside them are not matched.
+fn identifier_ranges(
+    language: SourceLanguage,
+    source: &str,
+    name: &str,
+) -> Result<Vec<std::ops::Range<usize>>, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|e| format!("failed to load grammar: {e}"))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| "failed to parse".to_string())?;
+    let mut ranges = Vec::new();
+    collect_identifiers(tree.root_node(), source.as_bytes(), name, &mut ranges);
+    Ok(ranges)
+}
+
+fn collect_identifiers(
+    node: Node,
+    source: &[u8],
+    name: &str,
+    ranges: &mut Vec<std::ops::Range<usize>>,
+) {
+    // Grammars name their identifier tokens `identifier`, `type_identifier`,
+    // `field_identifier`, `property_identifier` and so on.
+    if node.child_count() == 0 {
+        if node.kind().ends_with("identifier")
+            && node.utf8_text(source).is_ok_and(|text| text == name)
+        {
+            ranges.push(node.byte_range());
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_identifiers(child, source, name, ranges);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn args(json: &str) -> BatchEditArgs {
+        parse_batch_edit_arguments(json).expect("valid arguments")
+    }
+
+    #[test]
+    fn renames_identifiers_but_not_strings_or_longer_names() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(
+            dir.path().join("src/lib.rs"),
+            "// old_name is documented here\nfn old_name() -> u32 { 1 }\nfn old_name_2() -> u32 { old_name() + 1 }\nconst LABEL: &str = \"old_name\";\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "old_name\n").unwrap();
+
+        let plan = plan(
+            dir.path(),
+            args(r#"{"mode": "rename", "from": "old_name", "to": "new_name"}"#),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![EditedFile {
+                path: dir.path().join("src/lib.rs"),
+                original: std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+                content: "// old_name is documented here\nfn new_name() -> u32 { 1 }\nfn old_name_2() -> u32 { new_name() + 1 }\nconst LABEL: &str = \"old_name\";\n".to_string(),
+                replacements: 2,
+            }],
+            plan.files
+        );
+        assert!(
+            plan.action(dir.path())
+                .changes()
+                .contains_key(&dir.path().join("src/lib.rs"))
+        );
+    }
+
+    #[test]
+    fn replaces_regex_matches_in_matching_files() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.py"), "import foo_v1\nfoo_v1.run()\n").unwrap();
+        std::fs::write(dir.path().join("b.md"), "foo_v1\n").unwrap();
+        std::fs::write(dir.path().join("c.py"), "print('hi')\n").unwrap();
+
+        let plan = plan(
+            dir.path(),
+            args(
+                r#"{"mode": "regex", "pattern": "foo_v(\\d)", "replacement": "foo_v${1}_compat", "globs": ["*.py"]}"#,
+            ),
+        )
+        .unwrap();
+        let edited: Vec<(&str, usize)> = plan
+            .files
+            .iter()
+            .map(|file| (file.content.as_str(), file.replacements))
+            .collect();
+        assert_eq!(
+            vec![("import foo_v1_compat\nfoo_v1_compat.run()\n", 2)],
+            edited
+        );
+        let output: serde_json::Value =
+            serde_json::from_str(&plan.output(dir.path(), false)).unwrap();
+        assert_eq!("a.py", output["files"][0]["path"]);
+        assert!(
+            output["files"][0]["diff"]
+                .as_str()
+                .is_some_and(|diff| diff.contains("+import foo_v1_compat"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_edits() {
+        let dir = TempDir::new().unwrap();
+        assert!(
+            plan(
+                dir.path(),
+                args(r#"{"mode": "rename", "from": "a-b", "to": "c"}"#)
+            )
+            .is_err()
+        );
+        assert!(
+            plan(
+                dir.path(),
+                args(r#"{"mode": "rename", "from": "a", "to": "a"}"#)
+            )
+            .is_err()
+        );
+        assert!(
+            plan(
+                dir.path(),
+                args(r#"{"mode": "regex", "pattern": "(", "replacement": ""}"#)
+            )
+            .is_err()
+        );
+    }
+}
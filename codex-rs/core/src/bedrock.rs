@@ -0,0 +1,898 @@
+//! Implementation for the Converse API of Amazon Bedrock
+//! (`wire_api = "bedrock"`).
+//!
+//! Requests are signed with AWS Signature Version 4, or carry a Bedrock API
+//! key when the provider has an `env_key`. The conversation is mapped onto
+//! Converse messages the way it is for the Anthropic Messages API, and the
+//! streamed content blocks, which arrive in the binary AWS event stream
+//! encoding rather than as SSE, onto the same [`ResponseEvent`]s.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::ModelProviderInfo;
+use crate::anthropic_messages::Block;
+use crate::anthropic_messages::FREEFORM_INPUT_PARAM;
+use crate::anthropic_messages::block_item;
+use crate::anthropic_messages::create_tools_json_for_anthropic_api;
+use crate::anthropic_messages::freeform_tool_names;
+use crate::anthropic_messages::max_tokens;
+use crate::anthropic_messages::thinking_budget;
+use crate::aws_sigv4::AwsCredentials;
+use crate::aws_sigv4::sign_request;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::EnvVarError;
+use crate::error::Result;
+use crate::error::RetryLimitReachedError;
+use crate::error::UnexpectedResponseError;
+use crate::model_family::ModelFamily;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use bytes::Buf;
+use bytes::Bytes;
+use bytes::BytesMut;
+use codex_otel::otel_event_manager::OtelEventManager;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+/// Service name requests are signed for.
+const SIGNING_SERVICE: &str = "bedrock";
+
+/// Header carrying the ID of a request, reported as the response ID.
+const REQUEST_ID_HEADER: &str = "x-amzn-requestid";
+
+/// Stream a turn from the Converse API.
+pub(crate) async fn stream_bedrock_converse(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    otel_event_manager: &OtelEventManager,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Bedrock Converse API".to_string(),
+        ));
+    }
+
+    let Some(region) = provider.bedrock_region() else {
+        return Err(CodexErr::EnvVar(EnvVarError {
+            var: "AWS_REGION".to_string(),
+            instructions: Some(
+                "Set `region` in the `bedrock` table of the provider, or AWS_REGION.".to_string(),
+            ),
+        }));
+    };
+    let model_id = provider
+        .bedrock
+        .as_ref()
+        .map_or(model_family.slug.as_str(), |bedrock| {
+            bedrock.model_id(&model_family.slug)
+        });
+    let url = provider.bedrock_converse_url(model_id);
+
+    // A Bedrock API key when `env_key` names one, SigV4 otherwise.
+    let api_key = provider.api_key()?;
+    let credentials = match api_key {
+        Some(_) => None,
+        None => {
+            let profile = provider
+                .bedrock
+                .as_ref()
+                .and_then(|bedrock| bedrock.profile.as_deref());
+            Some(AwsCredentials::load(profile)?)
+        }
+    };
+
+    let payload = build_payload(
+        prompt,
+        model_family,
+        model_id,
+        effort,
+        max_output_tokens,
+        provider.prompt_caching(),
+    );
+    let freeform_tools = freeform_tool_names(&prompt.tools);
+
+    debug!(
+        "POST to {url}: {}",
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+    let body = serde_json::to_vec(&payload)?;
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let mut req_builder = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(
+                reqwest::header::ACCEPT,
+                "application/vnd.amazon.eventstream",
+            )
+            .body(body.clone());
+        if let Some(api_key) = &api_key {
+            req_builder = req_builder.bearer_auth(api_key);
+        }
+        let mut request = provider.apply_http_headers(req_builder).build()?;
+        if let Some(credentials) = &credentials {
+            let signature = sign_request(
+                credentials,
+                &region,
+                SIGNING_SERVICE,
+                &request,
+                chrono::Utc::now(),
+            );
+            for (name, value) in signature {
+                if let (Ok(name), Ok(value)) = (
+                    reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+                    reqwest::header::HeaderValue::from_str(&value),
+                ) {
+                    request.headers_mut().insert(name, value);
+                }
+            }
+        }
+
+        let res = otel_event_manager
+            .log_request(attempt, || client.execute(request))
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let response_id = resp
+                    .headers()
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_converse_stream(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                    response_id,
+                    freeform_tools,
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                        status,
+                        body,
+                        request_id: None,
+                    }));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(RetryLimitReachedError {
+                        status,
+                        request_id: None,
+                    }));
+                }
+
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+        }
+    }
+}
+
+/// The request body for `prompt`. Extended thinking is requested from
+/// Anthropic models when a reasoning effort is set.
+fn build_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    model_id: &str,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    prompt_caching: bool,
+) -> Value {
+    let thinking_budget = thinking_budget(effort).filter(|_| model_id.contains("anthropic."));
+
+    let mut payload = json!({
+        "system": [{"text": prompt.get_full_instructions(model_family)}],
+        "messages": build_messages(&prompt.get_formatted_input()),
+    });
+    let Some(obj) = payload.as_object_mut() else {
+        return payload;
+    };
+    if let Some(budget) = thinking_budget {
+        obj.insert(
+            "inferenceConfig".to_string(),
+            json!({"maxTokens": max_tokens(max_output_tokens, Some(budget))}),
+        );
+        obj.insert(
+            "additionalModelRequestFields".to_string(),
+            json!({"thinking": {"type": "enabled", "budget_tokens": budget}}),
+        );
+    } else if let Some(max_output_tokens) = max_output_tokens {
+        obj.insert(
+            "inferenceConfig".to_string(),
+            json!({"maxTokens": max_output_tokens}),
+        );
+    }
+    let tools: Vec<Value> = create_tools_json_for_anthropic_api(&prompt.tools)
+        .into_iter()
+        .map(|tool| {
+            json!({
+                "toolSpec": {
+                    "name": tool.get("name"),
+                    "description": tool.get("description"),
+                    "inputSchema": {"json": tool.get("input_schema")},
+                }
+            })
+        })
+        .collect();
+    if !tools.is_empty() {
+        obj.insert("toolConfig".to_string(), json!({"tools": tools}));
+    }
+    if prompt_caching && supports_cache_points(model_id) {
+        add_cache_points(&mut payload);
+    }
+    payload
+}
+
+/// Whether the model takes cache points; the Claude and Nova models do.
+fn supports_cache_points(model_id: &str) -> bool {
+    model_id.contains("anthropic.") || model_id.contains("amazon.nova")
+}
+
+/// Add cache points after the system prompt, which caches the tools along
+/// with it, and after the last two user messages, as
+/// `anthropic_messages` places its cache breakpoints.
+fn add_cache_points(payload: &mut Value) {
+    let cache_point = json!({"cachePoint": {"type": "default"}});
+    if let Some(Value::Array(system)) = payload.get_mut("system") {
+        system.push(cache_point.clone());
+    }
+    let Some(Value::Array(messages)) = payload.get_mut("messages") else {
+        return;
+    };
+    for message in messages
+        .iter_mut()
+        .rev()
+        .filter(|message| message["role"] == "user")
+        .take(2)
+    {
+        if let Some(Value::Array(content)) = message.get_mut("content") {
+            content.push(cache_point.clone());
+        }
+    }
+}
+
+/// Map the conversation onto Converse messages, merging consecutive blocks of
+/// the same role into one message as the API requires.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    let mut push = |role: &str, block: Value| {
+        if let Some(last) = messages.last_mut()
+            && last.get("role").and_then(Value::as_str) == Some(role)
+            && let Some(Value::Array(content)) = last.get_mut("content")
+        {
+            content.push(block);
+            return;
+        }
+        messages.push(json!({"role": role, "content": [block]}));
+    };
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                push(role, json!({"text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            if let Some(block) = image_block(image_url) {
+                                push(role, block);
+                            }
+                        }
+                    }
+                }
+            }
+            ResponseItem::Reasoning {
+                content,
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                let text: String = content
+                    .iter()
+                    .flatten()
+                    .map(|c| match c {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => text.as_str(),
+                    })
+                    .collect();
+                let reasoning = if text.is_empty() {
+                    json!({"redactedContent": signature})
+                } else {
+                    json!({"reasoningText": {"text": text, "signature": signature}})
+                };
+                push("assistant", json!({"reasoningContent": reasoning}));
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                push(
+                    "assistant",
+                    json!({"toolUse": {"toolUseId": call_id, "name": name, "input": input}}),
+                );
+            }
+            ResponseItem::CustomToolCall {
+                call_id,
+                name,
+                input,
+                ..
+            } => {
+                push(
+                    "assistant",
+                    json!({"toolUse": {
+                        "toolUseId": call_id,
+                        "name": name,
+                        "input": { FREEFORM_INPUT_PARAM: input },
+                    }}),
+                );
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action,
+                ..
+            } => {
+                let Some(call_id) = call_id.as_ref().or(id.as_ref()) else {
+                    continue;
+                };
+                push(
+                    "assistant",
+                    json!({"toolUse": {"toolUseId": call_id, "name": "local_shell", "input": action}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let status = if output.success == Some(false) {
+                    "error"
+                } else {
+                    "success"
+                };
+                push(
+                    "user",
+                    json!({"toolResult": {
+                        "toolUseId": call_id,
+                        "content": [{"text": output.content}],
+                        "status": status,
+                    }}),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                push(
+                    "user",
+                    json!({"toolResult": {"toolUseId": call_id, "content": [{"text": output}]}}),
+                );
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    messages
+}
+
+/// An image block for a base64 data URL. Converse only takes inline images,
+/// so images by URL are left out.
+fn image_block(image_url: &str) -> Option<Value> {
+    let (media_type, data) = image_url.strip_prefix("data:")?.split_once(";base64,")?;
+    let format = media_type.strip_prefix("image/")?;
+    let format = if format == "jpg" { "jpeg" } else { format };
+    Some(json!({"image": {"format": format, "source": {"bytes": data}}}))
+}
+
+/// A message of the AWS event stream encoding: its string headers and its
+/// payload.
+struct EventMessage {
+    headers: Vec<(String, String)>,
+    payload: Bytes,
+}
+
+impl EventMessage {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Length of the prelude (total length, headers length and prelude CRC) and
+/// of the trailing message CRC of an event stream message.
+const PRELUDE_LEN: usize = 12;
+const MESSAGE_CRC_LEN: usize = 4;
+
+/// Take the first complete message off `buffer`, if it holds one. The CRCs
+/// are not checked; the connection is TLS.
+fn decode_message(buffer: &mut BytesMut) -> std::result::Result<Option<EventMessage>, String> {
+    if buffer.len() < PRELUDE_LEN {
+        return Ok(None);
+    }
+    let total_len = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+    let headers_len = u32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]) as usize;
+    if total_len < PRELUDE_LEN + headers_len + MESSAGE_CRC_LEN {
+        return Err(format!(
+            "malformed event stream message of {total_len} bytes"
+        ));
+    }
+    if buffer.len() < total_len {
+        return Ok(None);
+    }
+
+    let mut message = buffer.split_to(total_len).freeze();
+    message.advance(PRELUDE_LEN);
+    let mut headers_bytes = message.split_to(headers_len);
+    message.truncate(message.len() - MESSAGE_CRC_LEN);
+
+    let mut headers = Vec::new();
+    while headers_bytes.has_remaining() {
+        let name_len = headers_bytes.get_u8() as usize;
+        if headers_bytes.remaining() < name_len + 1 {
+            return Err("truncated event stream header".to_string());
+        }
+        let name = String::from_utf8_lossy(&headers_bytes.split_to(name_len)).into_owned();
+        let value_len = match headers_bytes.get_u8() {
+            // true, false
+            0 | 1 => 0,
+            // byte, short, integer, long, timestamp, uuid
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            // byte array, string
+            6 | 7 => {
+                if headers_bytes.remaining() < 2 {
+                    return Err("truncated event stream header".to_string());
+                }
+                let len = headers_bytes.get_u16() as usize;
+                if headers_bytes.remaining() < len {
+                    return Err("truncated event stream header".to_string());
+                }
+                let value = headers_bytes.split_to(len);
+                headers.push((name, String::from_utf8_lossy(&value).into_owned()));
+                continue;
+            }
+            other => return Err(format!("unknown event stream header type {other}")),
+        };
+        if headers_bytes.remaining() < value_len {
+            return Err("truncated event stream header".to_string());
+        }
+        headers_bytes.advance(value_len);
+    }
+
+    Ok(Some(EventMessage {
+        headers,
+        payload: message,
+    }))
+}
+
+/// Processor for the event stream of `converse-stream`.
+async fn process_converse_stream<S>(
+    mut stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    response_id: String,
+    freeform_tools: HashSet<String>,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut buffer = BytesMut::new();
+    let mut block: Option<Block> = None;
+    let mut stopped = false;
+
+    loop {
+        let message = match decode_message(&mut buffer) {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                match timeout(idle_timeout, stream.next()).await {
+                    Ok(Some(Ok(chunk))) => buffer.extend_from_slice(&chunk),
+                    Ok(Some(Err(e))) => {
+                        let _ = tx_event
+                            .send(Err(CodexErr::Stream(e.to_string(), None)))
+                            .await;
+                        return;
+                    }
+                    Ok(None) if stopped => {
+                        // The metadata event with the token usage is optional.
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::Completed {
+                                response_id,
+                                token_usage: None,
+                            }))
+                            .await;
+                        return;
+                    }
+                    Ok(None) => {
+                        let _ = tx_event
+                            .send(Err(CodexErr::Stream(
+                                "stream closed before messageStop".into(),
+                                None,
+                            )))
+                            .await;
+                        return;
+                    }
+                    Err(_) => {
+                        let _ = tx_event
+                            .send(Err(CodexErr::Stream(
+                                "idle timeout waiting for the event stream".into(),
+                                None,
+                            )))
+                            .await;
+                        return;
+                    }
+                }
+                continue;
+            }
+            Err(e) => {
+                let _ = tx_event.send(Err(CodexErr::Stream(e, None))).await;
+                return;
+            }
+        };
+
+        let event: Value = serde_json::from_slice(&message.payload).unwrap_or_default();
+        trace!("bedrock received event: {:?} {event:?}", message.headers);
+
+        if matches!(message.header(":message-type"), Some("exception" | "error")) {
+            let kind = message
+                .header(":exception-type")
+                .or_else(|| message.header(":error-code"))
+                .unwrap_or("error");
+            let text = event
+                .get("message")
+                .or_else(|| event.get("Message"))
+                .and_then(Value::as_str)
+                .or_else(|| message.header(":error-message"))
+                .unwrap_or("unknown error");
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(format!("{kind}: {text}"), None)))
+                .await;
+            return;
+        }
+
+        match message.header(":event-type").unwrap_or_default() {
+            "messageStart" => {
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "contentBlockStart" => {
+                if let Some(tool_use) = event.get("start").and_then(|s| s.get("toolUse")) {
+                    let field = |key: &str| {
+                        tool_use
+                            .get(key)
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string()
+                    };
+                    block = Some(Block::ToolUse {
+                        id: field("toolUseId"),
+                        name: field("name"),
+                        input: String::new(),
+                    });
+                }
+            }
+            "contentBlockDelta" => {
+                let Some(delta) = event.get("delta") else {
+                    continue;
+                };
+                if let Some(text) = delta.get("text").and_then(Value::as_str) {
+                    if let Block::Text(buffer) =
+                        block.get_or_insert_with(|| Block::Text(String::new()))
+                    {
+                        buffer.push_str(text);
+                    }
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::OutputTextDelta(text.to_string())))
+                        .await;
+                } else if let Some(input) = delta
+                    .get("toolUse")
+                    .and_then(|t| t.get("input"))
+                    .and_then(Value::as_str)
+                {
+                    if let Some(Block::ToolUse {
+                        id,
+                        name,
+                        input: buffer,
+                    }) = block.as_mut()
+                    {
+                        buffer.push_str(input);
+                        if !input.is_empty() {
+                            let event = ResponseEvent::ToolCallArgumentsDelta {
+                                call_id: id.clone(),
+                                name: name.clone(),
+                                delta: input.to_string(),
+                            };
+                            let _ = tx_event.send(Ok(event)).await;
+                        }
+                    }
+                } else if let Some(reasoning) = delta.get("reasoningContent") {
+                    let field = |key: &str| reasoning.get(key).and_then(Value::as_str);
+                    if let Some(data) = field("redactedContent") {
+                        match block.get_or_insert_with(|| Block::RedactedThinking(String::new())) {
+                            Block::RedactedThinking(buffer) => buffer.push_str(data),
+                            _ => continue,
+                        }
+                        continue;
+                    }
+                    let Block::Thinking { text, signature } =
+                        block.get_or_insert_with(|| Block::Thinking {
+                            text: String::new(),
+                            signature: String::new(),
+                        })
+                    else {
+                        continue;
+                    };
+                    if let Some(delta) = field("text") {
+                        text.push_str(delta);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::ReasoningContentDelta(delta.to_string())))
+                            .await;
+                    }
+                    if let Some(delta) = field("signature") {
+                        signature.push_str(delta);
+                    }
+                }
+            }
+            "contentBlockStop" => {
+                if let Some(item) = block.take().and_then(|b| block_item(b, &freeform_tools)) {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "messageStop" => {
+                if let Some(item) = block.take().and_then(|b| block_item(b, &freeform_tools)) {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+                stopped = true;
+            }
+            "metadata" => {
+                let usage = event.get("usage");
+                let count = |key: &str| {
+                    usage
+                        .and_then(|u| u.get(key))
+                        .and_then(Value::as_u64)
+                        .unwrap_or_default()
+                };
+                let cached_input_tokens = count("cacheReadInputTokens");
+                let input_tokens =
+                    count("inputTokens") + cached_input_tokens + count("cacheWriteInputTokens");
+                let output_tokens = count("outputTokens");
+                let token_usage = TokenUsage {
+                    input_tokens,
+                    cached_input_tokens,
+                    output_tokens,
+                    total_tokens: input_tokens + output_tokens,
+                    ..Default::default()
+                };
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(token_usage),
+                    }))
+                    .await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use bytes::BufMut;
+    use pretty_assertions::assert_eq;
+
+    /// Encode an event of the stream, with zeroed CRCs.
+    fn encode_event(event_type: &str, payload: Value) -> Vec<u8> {
+        let mut headers = BytesMut::new();
+        for (name, value) in [(":message-type", "event"), (":event-type", event_type)] {
+            headers.put_u8(name.len() as u8);
+            headers.put_slice(name.as_bytes());
+            headers.put_u8(7);
+            headers.put_u16(value.len() as u16);
+            headers.put_slice(value.as_bytes());
+        }
+        let payload = payload.to_string();
+        let total_len = PRELUDE_LEN + headers.len() + payload.len() + MESSAGE_CRC_LEN;
+        let mut out = BytesMut::new();
+        out.put_u32(total_len as u32);
+        out.put_u32(headers.len() as u32);
+        out.put_u32(0);
+        out.put_slice(&headers);
+        out.put_slice(payload.as_bytes());
+        out.put_u32(0);
+        out.to_vec()
+    }
+
+    #[test]
+    fn maps_conversation_onto_converse_messages() {
+        let prompt = Prompt {
+            input: vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "list files".to_string(),
+                    }],
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "tooluse_1".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "tooluse_1".to_string(),
+                    output: codex_protocol::models::FunctionCallOutputPayload {
+                        content: "README.md".to_string(),
+                        success: Some(true),
+                    },
+                },
+            ],
+            base_instructions_override: Some("Be brief.".to_string()),
+            ..Default::default()
+        };
+
+        let payload = build_payload(
+            &prompt,
+            &derive_default_model_family("claude-sonnet-4-5"),
+            "us.anthropic.claude-sonnet-4-5-20250929-v1:0",
+            Some(ReasoningEffortConfig::Low),
+            None,
+            true,
+        );
+
+        let cache_point = json!({"cachePoint": {"type": "default"}});
+        assert_eq!(
+            payload,
+            json!({
+                "system": [{"text": "Be brief."}, cache_point],
+                "messages": [
+                    {"role": "user", "content": [{"text": "list files"}, cache_point]},
+                    {"role": "assistant", "content": [{"toolUse": {
+                        "toolUseId": "tooluse_1",
+                        "name": "shell",
+                        "input": {"command": ["ls"]},
+                    }}]},
+                    {"role": "user", "content": [{"toolResult": {
+                        "toolUseId": "tooluse_1",
+                        "content": [{"text": "README.md"}],
+                        "status": "success",
+                    }}, cache_point]},
+                ],
+                "inferenceConfig": {"maxTokens": 8192},
+                "additionalModelRequestFields": {
+                    "thinking": {"type": "enabled", "budget_tokens": 1024},
+                },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_blocks_onto_response_items() {
+        let mut body = Vec::new();
+        for (event_type, payload) in [
+            ("messageStart", json!({"role": "assistant"})),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 0, "delta": {"text": "Listing."}}),
+            ),
+            ("contentBlockStop", json!({"contentBlockIndex": 0})),
+            (
+                "contentBlockStart",
+                json!({"contentBlockIndex": 1, "start": {"toolUse": {"toolUseId": "tooluse_1", "name": "shell"}}}),
+            ),
+            (
+                "contentBlockDelta",
+                json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "{\"command\":[\"ls\"]}"}}}),
+            ),
+            ("contentBlockStop", json!({"contentBlockIndex": 1})),
+            ("messageStop", json!({"stopReason": "tool_use"})),
+            (
+                "metadata",
+                json!({"usage": {"inputTokens": 10, "outputTokens": 5, "totalTokens": 15}}),
+            ),
+        ] {
+            body.extend(encode_event(event_type, payload));
+        }
+        // Split the body so messages straddle chunks.
+        let chunks: Vec<Result<Bytes>> = body
+            .chunks(7)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        tokio::spawn(process_converse_stream(
+            futures::stream::iter(chunks),
+            tx,
+            Duration::from_secs(5),
+            "req-1".to_string(),
+            HashSet::new(),
+        ));
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        let items: Vec<ResponseItem> = events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(ResponseEvent::OutputItemDone(item)) => Some(item.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "Listing.".to_string(),
+                    }],
+                },
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "tooluse_1".to_string(),
+                },
+            ]
+        );
+        let Some(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(token_usage),
+        })) = events.last()
+        else {
+            panic!("expected a completed event, got {:?}", events.last());
+        };
+        assert_eq!(response_id, "req-1");
+        assert_eq!(token_usage.total_tokens, 15);
+    }
+}
@@ -0,0 +1,568 @@
+//! The `browser` tool, which drives a headless Chrome or Chromium through the
+//! DevTools protocol so the agent can check that a page it serves actually
+//! renders: navigate, read the accessibility tree, click elements and take
+//! screenshots. The browser is started on first use and kept, with its open
+//! page, until the session ends or goes idle.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use base64::Engine;
+use futures::SinkExt;
+use futures::StreamExt;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::net::TcpStream;
+use tokio::process::Child;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::config_types::Browser;
+use crate::protocol::SandboxPolicy;
+use crate::util::find_executable;
+
+pub(crate) const BROWSER_TOOL_NAME: &str = "browser";
+
+/// Looked up on `PATH` when `browser.executable` is not set.
+const EXECUTABLE_CANDIDATES: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "microsoft-edge",
+];
+const DEVTOOLS_LISTENING_PREFIX: &str = "DevTools listening on ";
+const LOAD_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Time given to a click to start a navigation before waiting for the load.
+const CLICK_SETTLE_DELAY: Duration = Duration::from_millis(300);
+const MAX_SNAPSHOT_LINES: usize = 2_000;
+/// Accessibility roles left out of snapshots; their children are kept.
+const SKIPPED_ROLES: &[&str] = &["none", "generic", "InlineTextBox", "LineBreak"];
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum BrowserArgs {
+    Navigate {
+        url: String,
+    },
+    Snapshot,
+    Click {
+        selector: String,
+    },
+    Screenshot {
+        #[serde(default)]
+        full_page: bool,
+    },
+}
+
+pub(crate) enum BrowserOutput {
+    Text(String),
+    /// A PNG file to attach to the conversation.
+    Screenshot(PathBuf),
+}
+
+/// The session's browser, started on first use.
+#[derive(Default)]
+pub(crate) struct BrowserManager {
+    browser: Mutex<Option<ChromeSession>>,
+}
+
+impl BrowserManager {
+    pub(crate) async fn run(
+        &self,
+        config: &Browser,
+        sandbox_policy: &SandboxPolicy,
+        args: BrowserArgs,
+    ) -> Result<BrowserOutput, String> {
+        if let BrowserArgs::Navigate { url } = &args {
+            check_navigation(sandbox_policy, url)?;
+        }
+        let mut browser = self.browser.lock().await;
+        let session = match &mut *browser {
+            Some(session) => session,
+            None => browser.insert(ChromeSession::launch(config).await?),
+        };
+        let result = session.run(args).await;
+        if session.is_closed() {
+            // Start a fresh browser on the next call.
+            *browser = None;
+        }
+        result
+    }
+
+    /// Stop the browser, if it is running. Returns whether it was.
+    pub(crate) async fn close(&self) -> bool {
+        self.browser.lock().await.take().is_some()
+    }
+}
+
+/// Only loopback hosts can be opened without network access.
+fn check_navigation(sandbox_policy: &SandboxPolicy, url: &str) -> Result<(), String> {
+    let parsed = Url::parse(url).map_err(|e| format!("invalid URL `{url}`: {e}"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!(
+            "cannot open `{url}`: only http and https URLs are supported"
+        ));
+    }
+    let host = parsed.host_str().unwrap_or_default();
+    let is_loopback = host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback());
+    if !is_loopback && !sandbox_policy.has_full_network_access() {
+        return Err(format!(
+            "opening {url} needs network access, which the sandbox policy disables; only localhost can be opened"
+        ));
+    }
+    Ok(())
+}
+
+/// A browser process and the DevTools connection to its single page.
+struct ChromeSession {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// DevTools session of the page; commands without it go to the browser.
+    page_session_id: Option<String>,
+    next_id: u64,
+    timeout: Duration,
+    closed: bool,
+    screenshots: usize,
+    _process: Child,
+    /// Profile and screenshots; removed after the process is killed.
+    dir: TempDir,
+}
+
+impl ChromeSession {
+    async fn launch(config: &Browser) -> Result<Self, String> {
+        let executable = match &config.executable {
+            Some(executable) => executable.clone(),
+            None => find_executable(EXECUTABLE_CANDIDATES).ok_or_else(|| {
+                "no Chrome or Chromium executable found; set `browser.executable`".to_string()
+            })?,
+        };
+        let timeout = Duration::from_secs(config.timeout_sec);
+        let dir = tempfile::Builder::new()
+            .prefix("codex-browser-")
+            .tempdir()
+            .map_err(|e| format!("failed to create the browser profile: {e}"))?;
+        let mut process = Command::new(&executable)
+            .arg("--headless=new")
+            .arg("--remote-debugging-port=0")
+            .arg(format!(
+                "--user-data-dir={}",
+                dir.path().join("profile").display()
+            ))
+            .arg(format!(
+                "--window-size={},{}",
+                config.viewport_width, config.viewport_height
+            ))
+            .args([
+                "--no-first-run",
+                "--no-default-browser-check",
+                "--disable-extensions",
+                "--hide-scrollbars",
+                "--mute-audio",
+                "about:blank",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("failed to start {}: {e}", executable.display()))?;
+
+        let stderr = process
+            .stderr
+            .take()
+            .ok_or_else(|| "failed to read the browser's output".to_string())?;
+        let mut lines = BufReader::new(stderr).lines();
+        let websocket_url = tokio::time::timeout(timeout, async {
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(url) = line.strip_prefix(DEVTOOLS_LISTENING_PREFIX) {
+                    return Some(url.trim().to_string());
+                }
+            }
+            None
+        })
+        .await
+        .ok()
+        .flatten()
+        .ok_or_else(|| format!("{} did not start a DevTools server", executable.display()))?;
+        // Keep draining the browser's output so it never blocks on a full pipe.
+        tokio::spawn(async move { while let Ok(Some(_)) = lines.next_line().await {} });
+
+        let (socket, _) = tokio_tungstenite::connect_async(websocket_url.as_str())
+            .await
+            .map_err(|e| format!("failed to connect to the browser: {e}"))?;
+        let mut session = Self {
+            socket,
+            page_session_id: None,
+            next_id: 0,
+            timeout,
+            closed: false,
+            screenshots: 0,
+            _process: process,
+            dir,
+        };
+        let target = session
+            .call("Target.createTarget", json!({ "url": "about:blank" }))
+            .await?;
+        let attached = session
+            .call(
+                "Target.attachToTarget",
+                json!({ "targetId": target["targetId"], "flatten": true }),
+            )
+            .await?;
+        session.page_session_id = attached["sessionId"].as_str().map(str::to_string);
+        Ok(session)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    async fn run(&mut self, args: BrowserArgs) -> Result<BrowserOutput, String> {
+        match args {
+            BrowserArgs::Navigate { url } => {
+                let result = self.call("Page.navigate", json!({ "url": url })).await?;
+                if let Some(error) = result["errorText"].as_str() {
+                    return Err(format!("failed to load {url}: {error}"));
+                }
+                self.wait_for_load().await?;
+                self.page_summary().await.map(BrowserOutput::Text)
+            }
+            BrowserArgs::Snapshot => {
+                let summary = self.page_summary().await?;
+                let tree = self.call("Accessibility.getFullAXTree", json!({})).await?;
+                let nodes = tree["nodes"].as_array().cloned().unwrap_or_default();
+                Ok(BrowserOutput::Text(format!(
+                    "{summary}\n{}",
+                    render_accessibility_tree(&nodes)
+                )))
+            }
+            BrowserArgs::Click { selector } => {
+                self.click(&selector).await?;
+                tokio::time::sleep(CLICK_SETTLE_DELAY).await;
+                self.wait_for_load().await?;
+                self.page_summary().await.map(BrowserOutput::Text)
+            }
+            BrowserArgs::Screenshot { full_page } => {
+                let mut params = json!({ "format": "png" });
+                if full_page {
+                    let metrics = self.call("Page.getLayoutMetrics", json!({})).await?;
+                    let size = &metrics["cssContentSize"];
+                    params["captureBeyondViewport"] = json!(true);
+                    params["clip"] = json!({
+                        "x": 0,
+                        "y": 0,
+                        "width": size["width"],
+                        "height": size["height"],
+                        "scale": 1,
+                    });
+                }
+                let result = self.call("Page.captureScreenshot", params).await?;
+                let png = base64::engine::general_purpose::STANDARD
+                    .decode(result["data"].as_str().unwrap_or_default())
+                    .map_err(|e| format!("invalid screenshot data: {e}"))?;
+                self.screenshots += 1;
+                let path = self
+                    .dir
+                    .path()
+                    .join(format!("screenshot-{}.png", self.screenshots));
+                tokio::fs::write(&path, png)
+                    .await
+                    .map_err(|e| format!("failed to save the screenshot: {e}"))?;
+                Ok(BrowserOutput::Screenshot(path))
+            }
+        }
+    }
+
+    /// Click the center of the first element matching `selector`, as a user
+    /// would, after scrolling it into view.
+    async fn click(&mut self, selector: &str) -> Result<(), String> {
+        let selector_literal = serde_json::to_string(selector).unwrap_or_default();
+        let point = self
+            .evaluate(&format!(
+                "(() => {{
+                    const element = document.querySelector({selector_literal});
+                    if (!element) return null;
+                    element.scrollIntoView({{ block: 'center', inline: 'center' }});
+                    const rect = element.getBoundingClientRect();
+                    return {{ x: rect.left + rect.width / 2, y: rect.top + rect.height / 2 }};
+                }})()"
+            ))
+            .await?;
+        if point.is_null() {
+            return Err(format!("no element matches `{selector}`"));
+        }
+        for event in ["mouseMoved", "mousePressed", "mouseReleased"] {
+            self.call(
+                "Input.dispatchMouseEvent",
+                json!({
+                    "type": event,
+                    "x": point["x"],
+                    "y": point["y"],
+                    "button": "left",
+                    "clickCount": 1,
+                }),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_load(&mut self) -> Result<(), String> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if self.evaluate("document.readyState").await? == "complete" {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "the page did not finish loading within {}s",
+                    self.timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(LOAD_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn page_summary(&mut self) -> Result<String, String> {
+        let page = self
+            .evaluate("({ url: location.href, title: document.title })")
+            .await?;
+        Ok(json!({ "url": page["url"], "title": page["title"] }).to_string())
+    }
+
+    async fn evaluate(&mut self, expression: &str) -> Result<Value, String> {
+        let mut result = self
+            .call(
+                "Runtime.evaluate",
+                json!({
+                    "expression": expression,
+                    "returnByValue": true,
+                    "awaitPromise": true,
+                }),
+            )
+            .await?;
+        if let Some(details) = result.get("exceptionDetails") {
+            let message = details["exception"]["description"]
+                .as_str()
+                .or(details["text"].as_str())
+                .unwrap_or("unknown error");
+            return Err(format!("script failed in the page: {message}"));
+        }
+        Ok(result["result"]["value"].take())
+    }
+
+    /// Send a DevTools command and wait for its result, skipping the events
+    /// that arrive meanwhile.
+    async fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut message = json!({ "id": id, "method": method, "params": params });
+        if let Some(session_id) = &self.page_session_id {
+            message["sessionId"] = json!(session_id);
+        }
+        let timeout = self.timeout;
+        let reply = tokio::time::timeout(timeout, async {
+            self.socket
+                .send(Message::Text(message.to_string().into()))
+                .await
+                .map_err(|e| format!("lost the connection to the browser: {e}"))?;
+            loop {
+                let text = match self.socket.next().await {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(format!("lost the connection to the browser: {e}")),
+                    None => return Err("the browser closed the connection".to_string()),
+                };
+                let Ok(reply) = serde_json::from_str::<Value>(&text) else {
+                    continue;
+                };
+                if reply["id"] == id {
+                    return Ok(reply);
+                }
+            }
+        })
+        .await;
+        let mut reply = match reply {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => {
+                self.closed = true;
+                return Err(e);
+            }
+            Err(_) => {
+                return Err(format!("{method} timed out after {}s", timeout.as_secs()));
+            }
+        };
+        if let Some(error) = reply.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(format!("{method} failed: {message}"));
+        }
+        Ok(reply["result"].take())
+    }
+}
+
+/// An indented outline of the page's accessibility tree, one node per line.
+/// Ignored nodes and roles without meaning of their own are left out, and
+/// text already given as the name of its parent is not repeated.
+fn render_accessibility_tree(nodes: &[Value]) -> String {
+    let by_id: HashMap<&str, &Value> = nodes
+        .iter()
+        .filter_map(|node| Some((node["nodeId"].as_str()?, node)))
+        .collect();
+    let mut lines = Vec::new();
+    if let Some(root) = nodes.first() {
+        render_node(&by_id, root, 0, false, &mut lines);
+    }
+    let total = lines.len();
+    if total > MAX_SNAPSHOT_LINES {
+        lines.truncate(MAX_SNAPSHOT_LINES);
+        lines.push(format!("… ({} more nodes)", total - MAX_SNAPSHOT_LINES));
+    }
+    lines.join("\n")
+}
+
+fn render_node(
+    by_id: &HashMap<&str, &Value>,
+    node: &Value,
+    depth: usize,
+    parent_named: bool,
+    lines: &mut Vec<String>,
+) {
+    let role = node["role"]["value"].as_str().unwrap_or_default();
+    let name = node["name"]["value"].as_str().unwrap_or_default().trim();
+    let shown = !node["ignored"].as_bool().unwrap_or(false)
+        && !SKIPPED_ROLES.contains(&role)
+        && !(role == "StaticText" && (parent_named || name.is_empty()));
+    let (child_depth, named) = if shown {
+        let indent = "  ".repeat(depth);
+        if name.is_empty() {
+            lines.push(format!("{indent}- {role}"));
+        } else {
+            lines.push(format!("{indent}- {role} {}", json!(name)));
+        }
+        (depth + 1, !name.is_empty())
+    } else {
+        (depth, parent_named)
+    };
+    for child_id in node["childIds"].as_array().into_iter().flatten() {
+        if let Some(child) = child_id.as_str().and_then(|id| by_id.get(id)) {
+            render_node(by_id, child, child_depth, named, lines);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn node(id: &str, role: &str, name: &str, children: &[&str]) -> Value {
+        json!({
+            "nodeId": id,
+            "ignored": false,
+            "role": { "type": "role", "value": role },
+            "name": { "type": "computedString", "value": name },
+            "childIds": children,
+        })
+    }
+
+    #[test]
+    fn renders_accessibility_tree_outline() {
+        let nodes = vec![
+            node("1", "RootWebArea", "Dashboard", &["2"]),
+            node("2", "generic", "", &["3", "5", "7"]),
+            node("3", "heading", "Welcome back", &["4"]),
+            node("4", "StaticText", "Welcome back", &[]),
+            node("5", "paragraph", "", &["6"]),
+            node("6", "StaticText", "3 new \"alerts\"", &[]),
+            node("7", "button", "Refresh", &["8"]),
+            json!({ "nodeId": "8", "ignored": true, "childIds": [] }),
+        ];
+
+        assert_eq!(
+            r#"- RootWebArea "Dashboard"
+  - heading "Welcome back"
+  - paragraph
+    - StaticText "3 new \"alerts\""
+  - button "Refresh""#,
+            render_accessibility_tree(&nodes)
+        );
+    }
+
+    #[test]
+    fn navigation_outside_localhost_needs_network_access() {
+        let policy = SandboxPolicy::new_workspace_write_policy();
+        assert!(check_navigation(&policy, "http://localhost:5173/").is_ok());
+        assert!(check_navigation(&policy, "http://127.0.0.1:3000/app").is_ok());
+        assert!(check_navigation(&policy, "http://[::1]:8080/").is_ok());
+        assert!(check_navigation(&policy, "https://example.com/").is_err());
+        assert!(check_navigation(&policy, "file:///etc/passwd").is_err());
+        assert!(check_navigation(&SandboxPolicy::DangerFullAccess, "https://example.com/").is_ok());
+    }
+
+    #[tokio::test]
+    async fn drives_an_installed_browser() {
+        if find_executable(EXECUTABLE_CANDIDATES).is_none() {
+            return;
+        }
+        let dir = TempDir::new().unwrap();
+        let page = dir.path().join("index.html");
+        std::fs::write(
+            &page,
+            "<title>Counter</title><button onclick=\"document.title='Clicked'\">Add</button>",
+        )
+        .unwrap();
+        let mut session = ChromeSession::launch(&Browser::default()).await.unwrap();
+        // `file:` URLs are refused by the tool but fine for the session itself.
+        let url = Url::from_file_path(&page).unwrap();
+
+        let BrowserOutput::Text(summary) = session
+            .run(BrowserArgs::Navigate {
+                url: url.to_string(),
+            })
+            .await
+            .unwrap()
+        else {
+            panic!("expected a page summary");
+        };
+        assert!(summary.contains("\"title\":\"Counter\""), "{summary}");
+
+        let BrowserOutput::Text(summary) = session
+            .run(BrowserArgs::Click {
+                selector: "button".to_string(),
+            })
+            .await
+            .unwrap()
+        else {
+            panic!("expected a page summary");
+        };
+        assert!(summary.contains("\"title\":\"Clicked\""), "{summary}");
+
+        let BrowserOutput::Screenshot(path) = session
+            .run(BrowserArgs::Screenshot { full_page: true })
+            .await
+            .unwrap()
+        else {
+            panic!("expected a screenshot");
+        };
+        assert!(path.starts_with(session.dir.path()));
+        assert!(std::fs::read(&path).unwrap().starts_with(b"\x89PNG"));
+    }
+}
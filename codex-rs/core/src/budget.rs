@@ -0,0 +1,141 @@
+//! Session budgets from the `[session_budget]` table.
+//!
+//! Usage is the sum of the [`TurnStats`] of the session's turns, including
+//! the running one, and is checked before every model request. When a limit
+//! is reached the turn pauses with `EventMsg::BudgetExceeded`; continuing
+//! grants a fresh budget of the same size counted from that point.
+
+use std::time::Duration;
+
+use crate::config_types::SessionBudget;
+use crate::protocol::BudgetExceededEvent;
+use crate::protocol::BudgetKind;
+use crate::protocol::TurnStats;
+
+/// What a session used, as counted against its budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct BudgetUsage {
+    tokens: u64,
+    cost_usd: f64,
+    tool_calls: u32,
+    wall_time: Duration,
+}
+
+impl BudgetUsage {
+    pub(crate) fn from_stats<'a>(stats: impl IntoIterator<Item = &'a TurnStats>) -> Self {
+        stats.into_iter().fold(Self::default(), |mut usage, stats| {
+            usage.tokens += stats.token_usage.total_tokens;
+            usage.cost_usd += stats.estimated_cost_usd.unwrap_or(0.0);
+            usage.tool_calls += stats.tool_calls;
+            usage.wall_time += Duration::from_millis(stats.duration_ms);
+            usage
+        })
+    }
+
+    /// Usage since `baseline` was taken.
+    fn since(&self, baseline: &Self) -> Self {
+        Self {
+            tokens: self.tokens.saturating_sub(baseline.tokens),
+            cost_usd: (self.cost_usd - baseline.cost_usd).max(0.0),
+            tool_calls: self.tool_calls.saturating_sub(baseline.tool_calls),
+            wall_time: self.wall_time.saturating_sub(baseline.wall_time),
+        }
+    }
+}
+
+/// The first limit of `budget` reached by the usage since `baseline`.
+pub(crate) fn exceeded_budget(
+    budget: &SessionBudget,
+    usage: &BudgetUsage,
+    baseline: &BudgetUsage,
+) -> Option<BudgetExceededEvent> {
+    let used = usage.since(baseline);
+    let limits = [
+        (
+            BudgetKind::Tokens,
+            budget.max_tokens.map(|max| max as f64),
+            used.tokens as f64,
+        ),
+        (BudgetKind::CostUsd, budget.max_cost_usd, used.cost_usd),
+        (
+            BudgetKind::ToolCalls,
+            budget.max_tool_calls.map(f64::from),
+            f64::from(used.tool_calls),
+        ),
+        (
+            BudgetKind::WallTime,
+            budget.max_wall_time_sec.map(|max| max as f64),
+            used.wall_time.as_secs_f64(),
+        ),
+    ];
+    limits.into_iter().find_map(|(budget, limit, used)| {
+        let limit = limit?;
+        (used >= limit).then_some(BudgetExceededEvent {
+            budget,
+            limit,
+            used,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TokenUsage;
+    use pretty_assertions::assert_eq;
+
+    fn turn(total_tokens: u64, tool_calls: u32) -> TurnStats {
+        TurnStats {
+            duration_ms: 1_500,
+            tool_calls,
+            token_usage: TokenUsage {
+                total_tokens,
+                ..Default::default()
+            },
+            estimated_cost_usd: Some(0.25),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn first_reached_limit_is_reported_relative_to_baseline() {
+        let budget = SessionBudget {
+            max_tokens: Some(1_000),
+            max_tool_calls: Some(3),
+            ..Default::default()
+        };
+        let turns = [turn(400, 1), turn(700, 2)];
+        let usage = BudgetUsage::from_stats(&turns);
+
+        assert_eq!(
+            Some(BudgetExceededEvent {
+                budget: BudgetKind::Tokens,
+                limit: 1_000.0,
+                used: 1_100.0,
+            }),
+            exceeded_budget(&budget, &usage, &BudgetUsage::default())
+        );
+
+        // After continuing past the first turn, only the second one counts.
+        let baseline = BudgetUsage::from_stats(&turns[..1]);
+        assert_eq!(None, exceeded_budget(&budget, &usage, &baseline));
+
+        let budget = SessionBudget {
+            max_cost_usd: Some(0.5),
+            max_wall_time_sec: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(
+            Some(BudgetExceededEvent {
+                budget: BudgetKind::CostUsd,
+                limit: 0.5,
+                used: 0.5,
+            }),
+            exceeded_budget(&budget, &usage, &BudgetUsage::default())
+        );
+        assert_eq!(
+            None,
+            exceeded_budget(&SessionBudget::default(), &usage, &BudgetUsage::default())
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use crate::ModelProviderInfo;
@@ -21,6 +22,7 @@ use futures::Stream;
 use futures::StreamExt;
 use futures::TryStreamExt;
 use reqwest::StatusCode;
+use serde_json::Value;
 use serde_json::json;
 use std::pin::Pin;
 use std::task::Context;
@@ -34,6 +36,7 @@ use tracing::trace;
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model_family: &ModelFamily,
+    parallel_tool_calls: bool,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
     otel_event_manager: &OtelEventManager,
@@ -207,7 +210,7 @@ pub(crate) async fn stream_chat_completions(
                 {
                     obj.insert("reasoning".to_string(), json!(reasoning));
                 }
-                messages.push(msg);
+                push_tool_call(&mut messages, msg);
             }
             ResponseItem::LocalShellCall {
                 id,
@@ -231,7 +234,7 @@ pub(crate) async fn stream_chat_completions(
                 {
                     obj.insert("reasoning".to_string(), json!(reasoning));
                 }
-                messages.push(msg);
+                push_tool_call(&mut messages, msg);
             }
             ResponseItem::FunctionCallOutput { call_id, output } => {
                 messages.push(json!({
@@ -247,18 +250,21 @@ pub(crate) async fn stream_chat_completions(
                 input,
                 status: _,
             } => {
-                messages.push(json!({
-                    "role": "assistant",
-                    "content": null,
-                    "tool_calls": [{
-                        "id": id,
-                        "type": "custom",
-                        "custom": {
-                            "name": name,
-                            "input": input,
-                        }
-                    }]
-                }));
+                push_tool_call(
+                    &mut messages,
+                    json!({
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": id,
+                            "type": "custom",
+                            "custom": {
+                                "name": name,
+                                "input": input,
+                            }
+                        }]
+                    }),
+                );
             }
             ResponseItem::CustomToolCallOutput { call_id, output } => {
                 messages.push(json!({
@@ -277,12 +283,20 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model_family.slug,
         "messages": messages,
         "stream": true,
         "tools": tools_json,
     });
+    // Only sent when enabled, as not every server implementing the API
+    // knows the parameter.
+    if parallel_tool_calls
+        && !tools_json.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert("parallel_tool_calls".to_string(), Value::Bool(true));
+    }
 
     debug!(
         "POST to {}: {}",
@@ -358,6 +372,22 @@ pub(crate) async fn stream_chat_completions(
     }
 }
 
+/// Append the assistant message `msg`, which holds one tool call, merging the
+/// call into the previous message when that one holds the other calls of the
+/// same response: the API expects parallel calls in a single assistant
+/// message, followed by one tool message per call.
+fn push_tool_call(messages: &mut Vec<Value>, msg: Value) {
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some("assistant")
+        && let Some(Value::Array(calls)) = last.get_mut("tool_calls")
+        && let Some(Value::Array(new_calls)) = msg.get("tool_calls")
+    {
+        calls.extend(new_calls.iter().cloned());
+        return;
+    }
+    messages.push(msg);
+}
+
 /// Lightweight SSE processor for the Chat Completions streaming format. The
 /// output is mapped onto Codex's internal [`ResponseEvent`] so that the rest
 /// of the pipeline can stay agnostic of the underlying wire format.
@@ -371,20 +401,20 @@ async fn process_chat_sse<S>(
 {
     let mut stream = stream.eventsource();
 
-    // State to accumulate a function call across streaming chunks.
-    // OpenAI may split the `arguments` string over multiple `delta` events
-    // until the chunk whose `finish_reason` is `tool_calls` is emitted. We
-    // keep collecting the pieces here and forward a single
-    // `ResponseItem::FunctionCall` once the call is complete.
+    // State to accumulate function calls across streaming chunks, keyed by
+    // their `index`: parallel calls are streamed interleaved, and OpenAI may
+    // split the `arguments` string over multiple `delta` events until the
+    // chunk whose `finish_reason` is `tool_calls` is emitted. We keep
+    // collecting the pieces here and forward one `ResponseItem::FunctionCall`
+    // per call once they are complete.
     #[derive(Default)]
     struct FunctionCallState {
         name: Option<String>,
         arguments: String,
         call_id: Option<String>,
-        active: bool,
     }
 
-    let mut fn_call_state = FunctionCallState::default();
+    let mut fn_calls: BTreeMap<u64, FunctionCallState> = BTreeMap::new();
     let mut assistant_text = String::new();
     let mut reasoning_text = String::new();
 
@@ -540,17 +570,33 @@ async fn process_chat_sse<S>(
             }
 
             // Handle streaming function / tool calls.
-            if let Some(tool_calls) = choice
+            let tool_calls = choice
                 .get("delta")
                 .and_then(|d| d.get("tool_calls"))
                 .and_then(|tc| tc.as_array())
-                && let Some(tool_call) = tool_calls.first()
-            {
-                // Mark that we have an active function call in progress.
-                fn_call_state.active = true;
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for tool_call in tool_calls {
+                let id = tool_call.get("id").and_then(|v| v.as_str());
+                let index = tool_call
+                    .get("index")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| {
+                        // Servers that omit `index` start each call with its id.
+                        match fn_calls.last_key_value() {
+                            Some((&last, state))
+                                if id.is_some() && state.call_id.as_deref() != id =>
+                            {
+                                last + 1
+                            }
+                            Some((&last, _)) => last,
+                            None => 0,
+                        }
+                    });
+                let fn_call_state = fn_calls.entry(index).or_default();
 
                 // Extract call_id if present.
-                if let Some(id) = tool_call.get("id").and_then(|v| v.as_str()) {
+                if let Some(id) = id {
                     fn_call_state.call_id.get_or_insert_with(|| id.to_string());
                 }
 
@@ -563,6 +609,17 @@ async fn process_chat_sse<S>(
                     if let Some(args_fragment) = function.get("arguments").and_then(|a| a.as_str())
                     {
                         fn_call_state.arguments.push_str(args_fragment);
+
+                        if !args_fragment.is_empty()
+                            && let Some(call_id) = fn_call_state.call_id.clone()
+                        {
+                            let event = ResponseEvent::ToolCallArgumentsDelta {
+                                call_id,
+                                name: fn_call_state.name.clone().unwrap_or_default(),
+                                delta: args_fragment.to_string(),
+                            };
+                            let _ = tx_event.send(Ok(event)).await;
+                        }
                     }
                 }
             }
@@ -570,7 +627,7 @@ async fn process_chat_sse<S>(
             // Emit end-of-turn when finish_reason signals completion.
             if let Some(finish_reason) = choice.get("finish_reason").and_then(|v| v.as_str()) {
                 match finish_reason {
-                    "tool_calls" if fn_call_state.active => {
+                    "tool_calls" if !fn_calls.is_empty() => {
                         // First, flush the terminal raw reasoning so UIs can finalize
                         // the reasoning stream before any exec/tool events begin.
                         if !reasoning_text.is_empty() {
@@ -585,15 +642,16 @@ async fn process_chat_sse<S>(
                             let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
                         }
 
-                        // Then emit the FunctionCall response item.
-                        let item = ResponseItem::FunctionCall {
-                            id: None,
-                            name: fn_call_state.name.clone().unwrap_or_else(|| "".to_string()),
-                            arguments: fn_call_state.arguments.clone(),
-                            call_id: fn_call_state.call_id.clone().unwrap_or_else(String::new),
-                        };
-
-                        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                        // Then emit the FunctionCall response items, in order.
+                        for fn_call_state in std::mem::take(&mut fn_calls).into_values() {
+                            let item = ResponseItem::FunctionCall {
+                                id: None,
+                                name: fn_call_state.name.unwrap_or_default(),
+                                arguments: fn_call_state.arguments,
+                                call_id: fn_call_state.call_id.unwrap_or_default(),
+                            };
+                            let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                        }
                     }
                     "stop" => {
                         // Regular turn without tool-call. Emit the final assistant message
@@ -632,9 +690,6 @@ async fn process_chat_sse<S>(
                     }))
                     .await;
 
-                // Prepare for potential next turn (should not happen in same stream).
-                // fn_call_state = FunctionCallState::default();
-
                 return; // End processing for this SSE stream.
             }
         }
@@ -839,6 +894,12 @@ where
                 Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id }))) => {
                     return Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id })));
                 }
+                Poll::Ready(Some(Ok(
+                    ev @ (ResponseEvent::ToolCallArgumentsDelta { .. }
+                    | ResponseEvent::ModelFailover { .. }),
+                ))) => {
+                    return Poll::Ready(Some(Ok(ev)));
+                }
             }
         }
     }
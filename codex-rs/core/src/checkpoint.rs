@@ -0,0 +1,290 @@
+//! Named session checkpoints: a copy of the conversation history together with
+//! a snapshot of the working tree, so both can be restored later.
+//!
+//! Working-tree snapshots are git tree objects built from a throwaway index,
+//! which captures tracked and untracked (non-ignored) files without touching
+//! the user's index, branches or stash.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::models::ResponseItem;
+use tokio::process::Command;
+
+use crate::git_info::get_git_repo_root;
+
+/// State captured by `Op::CreateCheckpoint`.
+#[derive(Debug, Clone)]
+pub(crate) struct Checkpoint {
+    pub(crate) history: Vec<ResponseItem>,
+    /// Repository root and tree id of the working-tree snapshot; `None` when
+    /// the session's `cwd` is not inside a git repository.
+    pub(crate) worktree: Option<WorktreeSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorktreeSnapshot {
+    pub(crate) repo_root: PathBuf,
+    pub(crate) tree: String,
+}
+
+/// Record the current contents of the repository containing `cwd`.
+pub(crate) async fn snapshot_worktree(cwd: &Path) -> Result<Option<WorktreeSnapshot>, String> {
+    let Some(repo_root) = get_git_repo_root(cwd) else {
+        return Ok(None);
+    };
+    let index = TempIndex::new()?;
+    index.stage_worktree(&repo_root).await?;
+    let tree = index.git(&repo_root, ["write-tree"]).await?;
+    Ok(Some(WorktreeSnapshot {
+        repo_root,
+        tree: tree.trim().to_string(),
+    }))
+}
+
+/// Restore the files captured by `snapshot`: files changed since are reset,
+/// deleted files are recreated and files created since are removed.
+pub(crate) async fn restore_worktree(snapshot: &WorktreeSnapshot) -> Result<(), String> {
+    let WorktreeSnapshot { repo_root, tree } = snapshot;
+    let index = TempIndex::new()?;
+    index.stage_worktree(repo_root).await?;
+
+    let added = index
+        .git(
+            repo_root,
+            [
+                "diff-index",
+                "--cached",
+                "--name-only",
+                "--diff-filter=A",
+                "-z",
+                tree,
+            ],
+        )
+        .await?;
+    for path in added.split('\0').filter(|p| !p.is_empty()) {
+        let path = repo_root.join(path);
+        if let Err(e) = tokio::fs::remove_file(&path).await
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(format!("failed to remove {}: {e}", path.display()));
+        }
+    }
+
+    index.git(repo_root, ["read-tree", tree]).await?;
+    index.git(repo_root, ["checkout-index", "-a", "-f"]).await?;
+    Ok(())
+}
+
+/// Files that differ between `snapshot` and the working tree, each with its
+/// contents in the snapshot (`None` for files created since).
+pub(crate) async fn changes_since(
+    snapshot: &WorktreeSnapshot,
+) -> Result<Vec<(PathBuf, Option<Vec<u8>>)>, String> {
+    let WorktreeSnapshot { repo_root, tree } = snapshot;
+    let index = TempIndex::new()?;
+    let mut changes = Vec::new();
+    for (kind, path) in index.status_since(snapshot).await? {
+        let original = if kind == "A" {
+            None
+        } else {
+            Some(
+                index
+                    .git_bytes(repo_root, ["cat-file", "blob", &format!("{tree}:{path}")])
+                    .await?,
+            )
+        };
+        changes.push((repo_root.join(path), original));
+    }
+    Ok(changes)
+}
+
+/// Files that differ between `snapshot` and the working tree, each with its
+/// git status letter (`A`dded, `M`odified, `D`eleted, ...) and its path
+/// relative to the repository root.
+pub(crate) async fn status_since(
+    snapshot: &WorktreeSnapshot,
+) -> Result<Vec<(String, String)>, String> {
+    TempIndex::new()?.status_since(snapshot).await
+}
+
+/// A git index file that lives only as long as this value.
+struct TempIndex {
+    _dir: tempfile::TempDir,
+    path: PathBuf,
+}
+
+impl TempIndex {
+    fn new() -> Result<Self, String> {
+        let dir = tempfile::tempdir().map_err(|e| format!("failed to create temp dir: {e}"))?;
+        let path = dir.path().join("index");
+        Ok(Self { _dir: dir, path })
+    }
+
+    /// Fill the index with every tracked and untracked (non-ignored) file.
+    async fn stage_worktree(&self, repo_root: &Path) -> Result<(), String> {
+        // Start from HEAD (when there is one) so tracked files that match an
+        // ignore rule are kept.
+        if self
+            .git(repo_root, ["rev-parse", "--verify", "--quiet", "HEAD"])
+            .await
+            .is_ok()
+        {
+            self.git(repo_root, ["read-tree", "HEAD"]).await?;
+        }
+        self.git(repo_root, ["add", "-A"]).await?;
+        Ok(())
+    }
+
+    /// Stage the working tree and compare it with `snapshot`.
+    async fn status_since(
+        &self,
+        snapshot: &WorktreeSnapshot,
+    ) -> Result<Vec<(String, String)>, String> {
+        let WorktreeSnapshot { repo_root, tree } = snapshot;
+        self.stage_worktree(repo_root).await?;
+        let status = self
+            .git(
+                repo_root,
+                [
+                    "diff-index",
+                    "--cached",
+                    "--name-status",
+                    "--no-renames",
+                    "-z",
+                    tree,
+                ],
+            )
+            .await?;
+        let mut fields = status.split('\0').filter(|f| !f.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(kind), Some(path)) = (fields.next(), fields.next()) {
+            changes.push((kind.to_string(), path.to_string()));
+        }
+        Ok(changes)
+    }
+
+    async fn git<I, S>(&self, repo_root: &Path, args: I) -> Result<String, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let stdout = self.git_bytes(repo_root, args).await?;
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    async fn git_bytes<I, S>(&self, repo_root: &Path, args: I) -> Result<Vec<u8>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args: Vec<S> = args.into_iter().collect();
+        let output = Command::new("git")
+            .args(&args)
+            .current_dir(repo_root)
+            .env("GIT_INDEX_FILE", &self.path)
+            .output()
+            .await
+            .map_err(|e| format!("failed to run git: {e}"))?;
+        if !output.status.success() {
+            let command = args
+                .iter()
+                .map(|a| a.as_ref().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            return Err(format!(
+                "git {command} failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .await
+            .expect("run git");
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[tokio::test]
+    async fn restores_modified_deleted_and_created_files() {
+        let temp = TempDir::new().expect("tempdir");
+        let repo = temp.path();
+        git(repo, &["init", "-q"]).await;
+        git(repo, &["config", "user.email", "test@example.com"]).await;
+        git(repo, &["config", "user.name", "Test"]).await;
+        std::fs::write(repo.join("tracked.txt"), "committed\n").expect("write");
+        git(repo, &["add", "."]).await;
+        git(repo, &["commit", "-q", "-m", "init"]).await;
+
+        // Uncommitted work present at checkpoint time is captured too.
+        std::fs::write(repo.join("tracked.txt"), "edited\n").expect("write");
+        std::fs::write(repo.join("untracked.txt"), "keep me\n").expect("write");
+
+        let snapshot = snapshot_worktree(repo)
+            .await
+            .expect("snapshot")
+            .expect("inside a repo");
+
+        std::fs::write(repo.join("tracked.txt"), "clobbered\n").expect("write");
+        std::fs::remove_file(repo.join("untracked.txt")).expect("remove");
+        std::fs::write(repo.join("new.txt"), "created later\n").expect("write");
+
+        restore_worktree(&snapshot).await.expect("restore");
+
+        let read = |name: &str| std::fs::read_to_string(repo.join(name)).expect("read");
+        assert_eq!("edited\n", read("tracked.txt"));
+        assert_eq!("keep me\n", read("untracked.txt"));
+        assert!(!repo.join("new.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn changes_since_reports_original_contents() {
+        let temp = TempDir::new().expect("tempdir");
+        let repo = temp.path();
+        git(repo, &["init", "-q"]).await;
+        std::fs::write(repo.join("kept.txt"), "same\n").expect("write");
+        std::fs::write(repo.join("edited.txt"), "before\n").expect("write");
+
+        let snapshot = snapshot_worktree(repo)
+            .await
+            .expect("snapshot")
+            .expect("inside a repo");
+
+        std::fs::write(repo.join("edited.txt"), "after\n").expect("write");
+        std::fs::write(repo.join("created.txt"), "new\n").expect("write");
+
+        let mut changes = changes_since(&snapshot).await.expect("changes");
+        changes.sort();
+        assert_eq!(
+            vec![
+                (repo.join("created.txt"), None),
+                (repo.join("edited.txt"), Some(b"before\n".to_vec())),
+            ],
+            changes
+        );
+    }
+
+    #[tokio::test]
+    async fn outside_git_repo_has_no_snapshot() {
+        let temp = TempDir::new().expect("tempdir");
+        assert!(
+            snapshot_worktree(temp.path())
+                .await
+                .expect("snapshot")
+                .is_none()
+        );
+    }
+}
@@ -1,7 +1,12 @@
+use std::collections::HashMap;
 use std::io::BufRead;
 use std::path::Path;
+use std::sync::Mutex;
 use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::auth::CodexAuth;
@@ -25,6 +30,8 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::anthropic_messages::stream_anthropic_messages;
+use crate::bedrock::stream_bedrock_converse;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -34,20 +41,32 @@ use crate::client_common::ResponsesApiRequest;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::client_common::create_text_param_for_request;
 use crate::config::Config;
+use crate::config_types::AutoCompaction;
+use crate::config_types::ModelFallback;
+use crate::config_types::ResponseCacheMode;
 use crate::default_client::create_client;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::gemini::stream_gemini;
 use crate::model_family::ModelFamily;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
+use crate::model_provider_info::ToolCalling;
 use crate::model_provider_info::WireApi;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::create_tools_json_for_responses_api;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::RateLimitWindow;
 use crate::protocol::TokenUsage;
+use crate::response_cache;
 use crate::token_data::PlanType;
+use crate::tool_emulation::emulate_tool_calls;
+use crate::tool_emulation::extract_tool_calls;
+use crate::tool_emulation::freeform_tool_names;
+use crate::tool_emulation::is_no_tool_support_error;
 use crate::util::backoff;
 use codex_otel::otel_event_manager::OtelEventManager;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -82,6 +101,26 @@ pub struct ModelClient {
     conversation_id: ConversationId,
     effort: Option<ReasoningEffortConfig>,
     summary: ReasoningSummaryConfig,
+    /// Whether tools are described in the prompt rather than sent as
+    /// definitions; set once the server rejects tool definitions.
+    emulate_tool_calls: Arc<AtomicBool>,
+    /// The fallback model turns currently go to, if the configured model
+    /// failed within the last `FAILOVER_COOLDOWN`.
+    failover: Arc<Mutex<Option<Failover>>>,
+    /// Whether a turn was ever served by a fallback model.
+    failed_over: Arc<AtomicBool>,
+}
+
+/// How long turns keep going to a fallback model before the configured model
+/// is tried again.
+const FAILOVER_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone)]
+struct Failover {
+    /// Index into `model_fallbacks`.
+    index: usize,
+    since: Instant,
+    reason: String,
 }
 
 impl ModelClient {
@@ -95,6 +134,9 @@ impl ModelClient {
         conversation_id: ConversationId,
     ) -> Self {
         let client = create_client();
+        let emulate_tool_calls = Arc::new(AtomicBool::new(
+            provider.tool_calling == ToolCalling::Prompt,
+        ));
 
         Self {
             config,
@@ -105,6 +147,9 @@ impl ModelClient {
             conversation_id,
             effort,
             summary,
+            emulate_tool_calls,
+            failover: Arc::new(Mutex::new(None)),
+            failed_over: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -115,27 +160,204 @@ impl ModelClient {
     }
 
     pub fn get_auto_compact_token_limit(&self) -> Option<i64> {
-        self.config.model_auto_compact_token_limit.or_else(|| {
+        let limit = self.config.model_auto_compact_token_limit.or_else(|| {
             get_model_info(&self.config.model_family).and_then(|info| info.auto_compact_token_limit)
-        })
+        });
+        let threshold = self
+            .config
+            .auto_compaction
+            .threshold_percent
+            .zip(self.get_model_context_window())
+            .map(|(percent, window)| (window * u64::from(percent) / 100) as i64);
+        match (limit, threshold) {
+            (Some(limit), Some(threshold)) => Some(limit.min(threshold)),
+            (limit, threshold) => limit.or(threshold),
+        }
+    }
+
+    pub fn get_auto_compaction(&self) -> &AutoCompaction {
+        &self.config.auto_compaction
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
-    /// specialised helpers are private to avoid accidental misuse.
+    /// Streams a turn, from the response cache when one is configured and
+    /// holds the response to `prompt`. Public callers always invoke
+    /// `stream()` – the specialised helpers are private to avoid accidental
+    /// misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let Some(cache) = &self.config.response_cache else {
+            return self.stream_with_failover(prompt).await;
+        };
+        let key = response_cache::request_key(
+            &self.config.model,
+            &self.config.model_family,
+            &self.provider,
+            self.effort,
+            self.summary,
+            prompt,
+        );
+        if cache.mode != ResponseCacheMode::Record
+            && let Some(stream) = response_cache::replay(cache, &key).await
+        {
+            debug!("replaying cached response {key}");
+            return Ok(stream);
+        }
+        if cache.mode == ResponseCacheMode::Replay {
+            return Err(CodexErr::ResponseNotCached(key));
+        }
+        let stream = self.stream_with_failover(prompt).await?;
+        Ok(response_cache::record(
+            cache,
+            key,
+            self.config.model.clone(),
+            stream,
+        ))
+    }
+
+    /// Streams a turn from the configured model or, when its provider is down
+    /// or rate limited, from the first of `model_fallbacks` that answers.
+    async fn stream_with_failover(&self, prompt: &Prompt) -> Result<ResponseStream> {
+        let fallbacks = &self.config.model_fallbacks;
+        if fallbacks.is_empty() {
+            return self.stream_once(prompt).await;
+        }
+
+        // Reasoning items are only understood by the model that produced
+        // them, so they are dropped once turns may be served by another one.
+        let stripped;
+        let prompt = if self.failed_over.load(Ordering::Relaxed) {
+            stripped = without_reasoning(prompt);
+            &stripped
+        } else {
+            prompt
+        };
+
+        let active = self.active_failover();
+        let candidates: Vec<Option<usize>> = match &active {
+            Some(failover) => (failover.index..fallbacks.len())
+                .map(Some)
+                .chain([None])
+                .collect(),
+            None => [None]
+                .into_iter()
+                .chain((0..fallbacks.len()).map(Some))
+                .collect(),
+        };
+
+        let mut reason = active.as_ref().map(|failover| failover.reason.clone());
+        let mut first_err: Option<CodexErr> = None;
+        for candidate in candidates {
+            let Some(index) = candidate else {
+                match self.stream_once(prompt).await {
+                    Ok(stream) => {
+                        self.set_failover(None);
+                        return Ok(stream);
+                    }
+                    Err(e) if is_failover_error(&e) => {
+                        warn!("{} is unavailable: {e}", self.config.model);
+                        reason = Some(format!("{} is unavailable: {e}", self.config.model));
+                        first_err.get_or_insert(e);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+
+            let fallback = &fallbacks[index];
+            let reason = reason
+                .clone()
+                .unwrap_or_else(|| format!("{} is unavailable", self.config.model));
+            self.failed_over.store(true, Ordering::Relaxed);
+            let prompt = without_reasoning(prompt);
+            match self.fallback_client(fallback).stream_once(&prompt).await {
+                Ok(stream) => {
+                    self.set_failover(Some(Failover {
+                        index,
+                        since: active
+                            .as_ref()
+                            .filter(|failover| failover.index == index)
+                            .map_or_else(Instant::now, |failover| failover.since),
+                        reason: reason.clone(),
+                    }));
+                    return Ok(announce_failover(stream, fallback, reason));
+                }
+                Err(e) if is_failover_error(&e) => {
+                    warn!("fallback model {} is unavailable: {e}", fallback.model);
+                    first_err.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(first_err.unwrap_or(CodexErr::InternalServerError))
+    }
+
+    /// The fallback turns currently go to, unless its cooldown has passed.
+    fn active_failover(&self) -> Option<Failover> {
+        let failover = self
+            .failover
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        failover
+            .as_ref()
+            .filter(|failover| failover.since.elapsed() < FAILOVER_COOLDOWN)
+            .cloned()
+    }
+
+    fn set_failover(&self, failover: Option<Failover>) {
+        *self
+            .failover
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = failover;
+    }
+
+    /// A client for `fallback` that shares this client's settings.
+    fn fallback_client(&self, fallback: &ModelFallback) -> ModelClient {
+        let mut config = (*self.config).clone();
+        config.model = fallback.model.clone();
+        config.model_family = find_family_for_model(&fallback.model)
+            .unwrap_or_else(|| derive_default_model_family(&fallback.model));
+        config.model_provider_id = fallback.model_provider_id.clone();
+        config.model_provider = fallback.model_provider.clone();
+        config.model_fallbacks = Vec::new();
+        let otel_event_manager = self
+            .otel_event_manager
+            .with_model(config.model.as_str(), config.model_family.slug.as_str());
+        ModelClient::new(
+            Arc::new(config),
+            self.auth_manager.clone(),
+            otel_event_manager,
+            fallback.model_provider.clone(),
+            self.effort,
+            self.summary,
+            self.conversation_id,
+        )
+    }
+
+    /// Dispatches to the Responses, Chat, Anthropic Messages, Gemini or
+    /// Bedrock implementation depending on the provider config.
+    async fn stream_once(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
             WireApi::Chat => {
-                // Create the raw streaming connection first.
-                let response_stream = stream_chat_completions(
-                    prompt,
-                    &self.config.model_family,
-                    &self.client,
-                    &self.provider,
-                    &self.otel_event_manager,
-                )
-                .await?;
+                // Create the raw streaming connection first, describing the
+                // tools in the prompt for models without native tool calling.
+                let mut emulate_tools = self.emulate_tool_calls.load(Ordering::Relaxed);
+                let response_stream = match self.stream_chat(prompt, emulate_tools).await {
+                    Err(CodexErr::UnexpectedStatus(err))
+                        if !emulate_tools
+                            && !prompt.tools.is_empty()
+                            && is_no_tool_support_error(&err.body) =>
+                    {
+                        warn!(
+                            "{} does not support tools; describing them in the prompt instead",
+                            self.config.model
+                        );
+                        self.emulate_tool_calls.store(true, Ordering::Relaxed);
+                        emulate_tools = true;
+                        self.stream_chat(prompt, true).await?
+                    }
+                    result => result?,
+                };
+                let freeform_tools = freeform_tool_names(&prompt.tools);
 
                 // Wrap it with the aggregation adapter so callers see *only*
                 // the final assistant message per turn (matching the
@@ -153,18 +375,84 @@ impl ModelClient {
                 tokio::spawn(async move {
                     use futures::StreamExt;
                     while let Some(ev) = aggregated.next().await {
-                        // Exit early if receiver hung up.
-                        if tx.send(ev).await.is_err() {
-                            break;
+                        let events = match ev {
+                            Ok(ev) if emulate_tools => extract_tool_calls(ev, &freeform_tools)
+                                .into_iter()
+                                .map(Ok)
+                                .collect(),
+                            ev => vec![ev],
+                        };
+                        for ev in events {
+                            // Exit early if receiver hung up.
+                            if tx.send(ev).await.is_err() {
+                                return;
+                            }
                         }
                     }
                 });
 
                 Ok(ResponseStream { rx_event: rx })
             }
+            WireApi::Anthropic => {
+                stream_anthropic_messages(
+                    prompt,
+                    &self.config.model_family,
+                    self.effort,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                    &self.otel_event_manager,
+                )
+                .await
+            }
+            WireApi::Gemini => {
+                stream_gemini(
+                    prompt,
+                    &self.config.model_family,
+                    self.effort,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                    &self.otel_event_manager,
+                )
+                .await
+            }
+            WireApi::Bedrock => {
+                stream_bedrock_converse(
+                    prompt,
+                    &self.config.model_family,
+                    self.effort,
+                    self.config.model_max_output_tokens,
+                    &self.client,
+                    &self.provider,
+                    &self.otel_event_manager,
+                )
+                .await
+            }
         }
     }
 
+    /// Start a Chat Completions stream for `prompt`, with the tools described
+    /// in the prompt when `emulate_tools` is set.
+    async fn stream_chat(&self, prompt: &Prompt, emulate_tools: bool) -> Result<ResponseStream> {
+        let emulated;
+        let prompt = if emulate_tools {
+            emulated = emulate_tool_calls(prompt, &self.config.model_family);
+            &emulated
+        } else {
+            prompt
+        };
+        stream_chat_completions(
+            prompt,
+            &self.config.model_family,
+            self.config.parallel_tool_calls,
+            &self.client,
+            &self.provider,
+            &self.otel_event_manager,
+        )
+        .await
+    }
+
     /// Implementation for the OpenAI *Responses* experimental API.
     async fn stream_responses(&self, prompt: &Prompt) -> Result<ResponseStream> {
         if let Some(path) = &*CODEX_RS_SSE_FIXTURE {
@@ -223,12 +511,12 @@ impl ModelClient {
         let azure_workaround = self.provider.is_azure_responses_endpoint();
 
         let payload = ResponsesApiRequest {
-            model: &self.config.model,
+            model: self.provider.request_model(&self.config.model),
             instructions: &full_instructions,
             input: &input_with_instructions,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            parallel_tool_calls: self.config.parallel_tool_calls,
             reasoning,
             store: azure_workaround,
             stream: true,
@@ -452,6 +740,63 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    pub(crate) fn get_config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+}
+
+/// Whether `err` means the provider is down or rate limited, so that the turn
+/// may be served by a fallback model instead.
+fn is_failover_error(err: &CodexErr) -> bool {
+    match err {
+        CodexErr::RetryLimit(_)
+        | CodexErr::InternalServerError
+        | CodexErr::UsageLimitReached(_)
+        | CodexErr::Stream(..)
+        | CodexErr::Reqwest(_) => true,
+        CodexErr::UnexpectedStatus(err) => {
+            err.status == StatusCode::TOO_MANY_REQUESTS || err.status.is_server_error()
+        }
+        _ => false,
+    }
+}
+
+fn without_reasoning(prompt: &Prompt) -> Prompt {
+    Prompt {
+        input: prompt
+            .input
+            .iter()
+            .filter(|item| !matches!(item, ResponseItem::Reasoning { .. }))
+            .cloned()
+            .collect(),
+        ..prompt.clone()
+    }
+}
+
+/// `stream`, preceded by an event saying that `fallback` serves the turn.
+fn announce_failover(
+    mut stream: ResponseStream,
+    fallback: &ModelFallback,
+    reason: String,
+) -> ResponseStream {
+    let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+    let announcement = ResponseEvent::ModelFailover {
+        model: fallback.model.clone(),
+        model_provider: fallback.model_provider_id.clone(),
+        reason,
+    };
+    tokio::spawn(async move {
+        if tx.send(Ok(announcement)).await.is_err() {
+            return;
+        }
+        while let Some(ev) = stream.rx_event.recv().await {
+            if tx.send(ev).await.is_err() {
+                return;
+            }
+        }
+    });
+    ResponseStream { rx_event: rx }
 }
 
 enum StreamAttemptError {
@@ -504,6 +849,7 @@ struct SseEvent {
     kind: String,
     response: Option<Value>,
     item: Option<Value>,
+    item_id: Option<String>,
     delta: Option<String>,
 }
 
@@ -648,6 +994,10 @@ async fn process_sse<S>(
     // The response id returned from the "complete" message.
     let mut response_completed: Option<ResponseCompleted> = None;
     let mut response_error: Option<CodexErr> = None;
+    // Tool calls whose arguments are still streaming, keyed by output item id.
+    // Argument deltas only carry the item id, so remember the call id and
+    // tool name announced by `response.output_item.added`.
+    let mut tool_calls_in_progress: HashMap<String, (String, String)> = HashMap::new();
 
     loop {
         let sse = match otel_event_manager
@@ -823,9 +1173,24 @@ async fn process_sse<S>(
                     };
                 };
             }
+            "response.function_call_arguments.delta" | "response.custom_tool_call_input.delta" => {
+                let (Some(item_id), Some(delta)) = (event.item_id, event.delta) else {
+                    continue;
+                };
+                let Some((call_id, name)) = tool_calls_in_progress.get(&item_id) else {
+                    continue;
+                };
+                let event = ResponseEvent::ToolCallArgumentsDelta {
+                    call_id: call_id.clone(),
+                    name: name.clone(),
+                    delta,
+                };
+                if tx_event.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
             "response.content_part.done"
-            | "response.function_call_arguments.delta"
-            | "response.custom_tool_call_input.delta"
+            | "response.function_call_arguments.done"
             | "response.custom_tool_call_input.done" // also emitted as response.output_item.done
             | "response.in_progress"
             | "response.output_text.done" => {}
@@ -845,6 +1210,20 @@ async fn process_sse<S>(
                             return;
                         }
                     }
+
+                    // Remember tool calls so their argument deltas can be attributed.
+                    if let Some("function_call" | "custom_tool_call") =
+                        item.get("type").and_then(|v| v.as_str())
+                        && let Some(id) = item.get("id").and_then(|v| v.as_str())
+                        && let Some(call_id) = item.get("call_id").and_then(|v| v.as_str())
+                    {
+                        let name = item
+                            .get("name")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default();
+                        tool_calls_in_progress
+                            .insert(id.to_string(), (call_id.to_string(), name.to_string()));
+                    }
                 }
             }
             "response.reasoning_summary_part.added" => {
@@ -1060,6 +1439,10 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1123,6 +1506,10 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1159,6 +1546,10 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let otel_event_manager = otel_event_manager();
@@ -1185,6 +1576,72 @@ mod tests {
 
     /// Verifies that the adapter produces the right `ResponseEvent` for a
     /// variety of incoming `type` values.
+    #[tokio::test]
+    async fn streams_tool_call_argument_deltas() {
+        let events = vec![
+            json!({
+                "type": "response.output_item.added",
+                "item": {
+                    "type": "function_call",
+                    "id": "fc_1",
+                    "call_id": "call_1",
+                    "name": "shell",
+                    "arguments": ""
+                }
+            }),
+            json!({
+                "type": "response.function_call_arguments.delta",
+                "item_id": "fc_1",
+                "delta": "{\"command\":"
+            }),
+            json!({
+                "type": "response.function_call_arguments.delta",
+                "item_id": "unknown",
+                "delta": "ignored"
+            }),
+            json!({
+                "type": "response.completed",
+                "response": { "id": "resp1" }
+            }),
+        ];
+
+        let provider = ModelProviderInfo {
+            name: "test".to_string(),
+            base_url: Some("https://test.com".to_string()),
+            env_key: Some("TEST_API_KEY".to_string()),
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: Some(0),
+            stream_max_retries: Some(0),
+            stream_idle_timeout_ms: Some(1000),
+            requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
+        };
+
+        let out = run_sse(events, provider, otel_event_manager()).await;
+
+        assert_eq!(out.len(), 2);
+        match &out[0] {
+            ResponseEvent::ToolCallArgumentsDelta {
+                call_id,
+                name,
+                delta,
+            } => {
+                assert_eq!(call_id, "call_1");
+                assert_eq!(name, "shell");
+                assert_eq!(delta, "{\"command\":");
+            }
+            other => panic!("unexpected first event: {other:?}"),
+        }
+        assert!(matches!(out[1], ResponseEvent::Completed { .. }));
+    }
+
     #[tokio::test]
     async fn table_driven_event_kinds() {
         struct TestCase {
@@ -1266,6 +1723,10 @@ mod tests {
                 stream_max_retries: Some(0),
                 stream_idle_timeout_ms: Some(1000),
                 requires_openai_auth: false,
+                tool_calling: ToolCalling::Native,
+                azure: None,
+                bedrock: None,
+                prompt_caching: None,
             };
 
             let otel_event_manager = otel_event_manager();
@@ -1339,4 +1800,22 @@ mod tests {
         let plan_json = serde_json::to_string(&resp.error.plan_type).expect("serialize plan_type");
         assert_eq!(plan_json, "\"vip\"");
     }
+
+    #[test]
+    fn fails_over_on_outages_and_rate_limits_only() {
+        let status = |status| {
+            CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                status,
+                body: String::new(),
+                request_id: None,
+            })
+        };
+
+        assert!(is_failover_error(&status(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(is_failover_error(&status(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(is_failover_error(&CodexErr::InternalServerError));
+        assert!(!is_failover_error(&status(StatusCode::BAD_REQUEST)));
+        assert!(!is_failover_error(&status(StatusCode::UNAUTHORIZED)));
+        assert!(!is_failover_error(&CodexErr::Interrupted));
+    }
 }
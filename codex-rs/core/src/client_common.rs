@@ -83,7 +83,21 @@ pub enum ResponseEvent {
     WebSearchCallBegin {
         call_id: String,
     },
+    /// Fragment of a tool call's arguments (or custom tool input) that the
+    /// model is still generating.
+    ToolCallArgumentsDelta {
+        call_id: String,
+        name: String,
+        delta: String,
+    },
     RateLimits(RateLimitSnapshot),
+    /// The response comes from a fallback model because the configured one
+    /// failed; sent before any other event.
+    ModelFailover {
+        model: String,
+        model_provider: String,
+        reason: String,
+    },
 }
 
 #[derive(Debug, Serialize)]
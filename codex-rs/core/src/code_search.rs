@@ -0,0 +1,340 @@
+//! The `search_code` tool: an in-process equivalent of `rg` that returns
+//! structured matches. Files are walked with the same `ignore` rules as
+//! ripgrep (`.gitignore`, `.ignore`, hidden files skipped), so the agent gets
+//! the same results whether or not `rg` is installed.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use regex_lite::Regex;
+use regex_lite::RegexBuilder;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::read_file::is_readable;
+
+pub(crate) const CODE_SEARCH_TOOL_NAME: &str = "search_code";
+
+const DEFAULT_MAX_RESULTS: usize = 100;
+const MAX_RESULTS_LIMIT: usize = 1_000;
+const MAX_CONTEXT_LINES: usize = 10;
+/// Matched and context lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 300;
+/// Files with a NUL byte in this prefix are treated as binary and skipped.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct CodeSearchArgs {
+    pattern: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    globs: Vec<String>,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    context_lines: usize,
+    #[serde(default)]
+    max_results: Option<usize>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct CodeSearchOutput {
+    matches: Vec<CodeMatch>,
+    /// Whether the search stopped at `max_results`.
+    truncated: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct CodeMatch {
+    /// Relative to the searched directory.
+    path: String,
+    /// 1-based.
+    line: usize,
+    text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+pub(crate) async fn handle_code_search_tool(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: CodeSearchArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let root = cwd.join(args.path.as_deref().unwrap_or("."));
+    if !is_readable(sandbox_policy, cwd, &root) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "reading {} is not allowed by the sandbox policy",
+            root.display()
+        )));
+    }
+    let cwd = cwd.to_path_buf();
+    let output = tokio::task::spawn_blocking(move || search(&cwd, args))
+        .await
+        .map_err(|e| FunctionCallError::RespondToModel(format!("search failed: {e}")))?
+        .map_err(FunctionCallError::RespondToModel)?;
+    serde_json::to_string(&output)
+        .map_err(|e| FunctionCallError::RespondToModel(format!("failed to encode matches: {e}")))
+}
+
+fn search(cwd: &Path, args: CodeSearchArgs) -> Result<CodeSearchOutput, String> {
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.case_insensitive)
+        .build()
+        .map_err(|e| format!("invalid pattern: {e}"))?;
+    let root = match &args.path {
+        Some(path) => cwd.join(path),
+        None => cwd.to_path_buf(),
+    };
+    if !root.exists() {
+        return Err(format!("{} does not exist", root.display()));
+    }
+    let context_lines = args.context_lines.min(MAX_CONTEXT_LINES);
+    let max_results = args
+        .max_results
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS_LIMIT);
+
+    let mut output = CodeSearchOutput::default();
+    for entry in walk_builder(&root, &args.globs)?.build().flatten() {
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+        let Some(contents) = read_text_file(entry.path()) else {
+            continue;
+        };
+        let display_path = display_path(&root, entry.path());
+        let remaining = max_results - output.matches.len();
+        let (matches, truncated) =
+            search_text(&regex, &contents, &display_path, context_lines, remaining);
+        output.matches.extend(matches);
+        if truncated {
+            output.truncated = true;
+            break;
+        }
+    }
+    Ok(output)
+}
+
+/// A walk of `root` in path order that follows the ignore rules of ripgrep
+/// and, when `globs` are given, only yields files matching them.
+pub(crate) fn walk_builder(root: &Path, globs: &[String]) -> Result<WalkBuilder, String> {
+    let mut builder = WalkBuilder::new(root);
+    builder.require_git(false).sort_by_file_path(Path::cmp);
+    if !globs.is_empty() {
+        let mut overrides = OverrideBuilder::new(root);
+        for glob in globs {
+            overrides
+                .add(glob)
+                .map_err(|e| format!("invalid glob `{glob}`: {e}"))?;
+        }
+        builder.overrides(
+            overrides
+                .build()
+                .map_err(|e| format!("invalid globs: {e}"))?,
+        );
+    }
+    Ok(builder)
+}
+
+/// The contents of `path`, or `None` if it cannot be read or looks binary.
+fn read_text_file(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn display_path(root: &Path, path: &Path) -> String {
+    // A file passed as `path` is shown by its file name.
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative.to_path_buf(),
+        _ => path.file_name().map(PathBuf::from).unwrap_or_default(),
+    };
+    relative.to_string_lossy().into_owned()
+}
+
+/// Up to `limit` matches in `contents`, and whether more were left out.
+fn search_text(
+    regex: &Regex,
+    contents: &str,
+    path: &str,
+    context_lines: usize,
+    limit: usize,
+) -> (Vec<CodeMatch>, bool) {
+    let lines: Vec<&str> = contents.lines().collect();
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+        if matches.len() == limit {
+            return (matches, true);
+        }
+        let before_start = index.saturating_sub(context_lines);
+        let after_end = (index + 1 + context_lines).min(lines.len());
+        matches.push(CodeMatch {
+            path: path.to_string(),
+            line: index + 1,
+            text: truncate_line(line),
+            before: lines[before_start..index]
+                .iter()
+                .map(|line| truncate_line(line))
+                .collect(),
+            after: lines[index + 1..after_end]
+                .iter()
+                .map(|line| truncate_line(line))
+                .collect(),
+        });
+    }
+    (matches, false)
+}
+
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn args(pattern: &str) -> CodeSearchArgs {
+        CodeSearchArgs {
+            pattern: pattern.to_string(),
+            path: None,
+            globs: Vec::new(),
+            case_insensitive: false,
+            context_lines: 0,
+            max_results: None,
+        }
+    }
+
+    fn matched_lines(output: &CodeSearchOutput) -> Vec<(String, usize)> {
+        output
+            .matches
+            .iter()
+            .map(|m| (m.path.clone(), m.line))
+            .collect()
+    }
+
+    #[test]
+    fn searches_files_respecting_ignore_rules_and_globs() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn alpha() {}\nfn beta() {}\n").unwrap();
+        std::fs::write(root.join("src/notes.md"), "Alpha release\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "fn alpha() {}\n").unwrap();
+        std::fs::write(root.join("blob.bin"), b"alpha\0").unwrap();
+
+        let output = search(root, args("alpha")).unwrap();
+        assert_eq!(vec![("src/lib.rs".to_string(), 1)], matched_lines(&output));
+
+        let output = search(
+            root,
+            CodeSearchArgs {
+                case_insensitive: true,
+                ..args("alpha")
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("src/lib.rs".to_string(), 1),
+                ("src/notes.md".to_string(), 1)
+            ],
+            matched_lines(&output)
+        );
+
+        let output = search(
+            root,
+            CodeSearchArgs {
+                case_insensitive: true,
+                globs: vec!["*.md".to_string()],
+                ..args("alpha")
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            vec![("src/notes.md".to_string(), 1)],
+            matched_lines(&output)
+        );
+
+        let output = search(
+            root,
+            CodeSearchArgs {
+                path: Some("src/lib.rs".to_string()),
+                ..args(r"fn \w+")
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            vec![("lib.rs".to_string(), 1), ("lib.rs".to_string(), 2)],
+            matched_lines(&output)
+        );
+
+        assert!(search(root, args("(")).is_err());
+        assert!(
+            search(
+                root,
+                CodeSearchArgs {
+                    path: Some("missing".to_string()),
+                    ..args("alpha")
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn includes_context_lines_and_stops_at_limit() {
+        let regex = Regex::new("match").unwrap();
+        let contents = "one\nmatch a\ntwo\nthree\nmatch b\nmatch c\n";
+
+        let (matches, truncated) = search_text(&regex, contents, "f.txt", 1, 2);
+        assert_eq!(
+            vec![
+                CodeMatch {
+                    path: "f.txt".to_string(),
+                    line: 2,
+                    text: "match a".to_string(),
+                    before: vec!["one".to_string()],
+                    after: vec!["two".to_string()],
+                },
+                CodeMatch {
+                    path: "f.txt".to_string(),
+                    line: 5,
+                    text: "match b".to_string(),
+                    before: vec!["three".to_string()],
+                    after: vec!["match c".to_string()],
+                },
+            ],
+            matches
+        );
+        assert!(truncated);
+
+        let (matches, truncated) = search_text(&regex, contents, "f.txt", 0, 3);
+        assert_eq!(3, matches.len());
+        assert!(!truncated);
+    }
+}
@@ -0,0 +1,426 @@
+//! The `code_symbols` tool, which parses source files with tree-sitter to
+//! find where a symbol is defined, outline the symbols of a file and name the
+//! function enclosing a line, with exact line ranges instead of grep matches.
+//!
+//! Rust, Python, JavaScript, TypeScript and Go are understood. Directories
+//! are walked with the same ignore rules as `search_code`.
+
+use std::path::Path;
+
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use serde::Serialize;
+use tree_sitter::Language;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::read_file::is_readable;
+
+pub(crate) const CODE_SYMBOLS_TOOL_NAME: &str = "code_symbols";
+
+const MAX_DEFINITIONS: usize = 100;
+/// Larger files are skipped when searching a directory for definitions.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum CodeSymbolsArgs {
+    /// Where the symbol `name` is defined, searching `path` (a file or a
+    /// directory, default: the working directory).
+    FindDefinition {
+        name: String,
+        #[serde(default)]
+        path: Option<String>,
+    },
+    /// Every symbol defined in the file `path`.
+    Outline { path: String },
+    /// The innermost symbol of `path` whose definition spans `line`.
+    Enclosing { path: String, line: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Symbol {
+    name: String,
+    kind: &'static str,
+    /// Relative to the working directory.
+    path: String,
+    /// 1-based and inclusive.
+    start_line: usize,
+    end_line: usize,
+    /// The symbol this one is defined in, such as the class of a method.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DefinitionsOutput {
+    definitions: Vec<Symbol>,
+    /// Whether the search stopped at the result limit.
+    truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SourceLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SourceLanguage {
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn grammar(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// The symbol kind a node of `node_kind` defines, if any.
+    fn symbol_kind(self, node_kind: &str) -> Option<&'static str> {
+        let kind = match (self, node_kind) {
+            (Self::Rust, "function_item" | "function_signature_item") => "function",
+            (Self::Rust, "struct_item") => "struct",
+            (Self::Rust, "enum_item") => "enum",
+            (Self::Rust, "union_item") => "union",
+            (Self::Rust, "trait_item") => "trait",
+            (Self::Rust, "impl_item") => "impl",
+            (Self::Rust, "mod_item") => "module",
+            (Self::Rust, "const_item") => "const",
+            (Self::Rust, "static_item") => "static",
+            (Self::Rust, "type_item") => "type",
+            (Self::Rust, "macro_definition") => "macro",
+            (Self::Python, "function_definition") => "function",
+            (Self::Python, "class_definition") => "class",
+            (
+                Self::JavaScript | Self::TypeScript | Self::Tsx,
+                "function_declaration" | "generator_function_declaration",
+            ) => "function",
+            (
+                Self::JavaScript | Self::TypeScript | Self::Tsx,
+                "class_declaration" | "abstract_class_declaration",
+            ) => "class",
+            (Self::JavaScript | Self::TypeScript | Self::Tsx, "method_definition") => "method",
+            (Self::JavaScript | Self::TypeScript | Self::Tsx, "variable_declarator") => "function",
+            (Self::TypeScript | Self::Tsx, "interface_declaration") => "interface",
+            (Self::TypeScript | Self::Tsx, "type_alias_declaration") => "type",
+            (Self::TypeScript | Self::Tsx, "enum_declaration") => "enum",
+            (Self::Go, "function_declaration") => "function",
+            (Self::Go, "method_declaration") => "method",
+            (Self::Go, "type_spec") => "type",
+            (Self::Go, "const_spec") => "const",
+            _ => return None,
+        };
+        Some(kind)
+    }
+}
+
+pub(crate) async fn handle_code_symbols_tool(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: CodeSymbolsArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let requested = match &args {
+        CodeSymbolsArgs::FindDefinition { path, .. } => path.as_deref().unwrap_or("."),
+        CodeSymbolsArgs::Outline { path } | CodeSymbolsArgs::Enclosing { path, .. } => path,
+    };
+    if !is_readable(sandbox_policy, cwd, &cwd.join(requested)) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "reading {requested} is not allowed by the sandbox policy"
+        )));
+    }
+    let cwd = cwd.to_path_buf();
+    tokio::task::spawn_blocking(move || run(&cwd, args))
+        .await
+        .map_err(|e| FunctionCallError::RespondToModel(format!("code_symbols failed: {e}")))?
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+fn run(cwd: &Path, args: CodeSymbolsArgs) -> Result<String, String> {
+    let json = match args {
+        CodeSymbolsArgs::FindDefinition { name, path } => {
+            let root = cwd.join(path.as_deref().unwrap_or("."));
+            if !root.exists() {
+                return Err(format!("{} does not exist", root.display()));
+            }
+            serde_json::to_string(&find_definitions(cwd, &root, &name))
+        }
+        CodeSymbolsArgs::Outline { path } => {
+            let symbols = file_symbols(cwd, &cwd.join(&path))?;
+            serde_json::to_string(&symbols)
+        }
+        CodeSymbolsArgs::Enclosing { path, line } => {
+            let symbols = file_symbols(cwd, &cwd.join(&path))?;
+            let enclosing = symbols
+                .into_iter()
+                .filter(|symbol| symbol.start_line <= line && line <= symbol.end_line)
+                .min_by_key(|symbol| symbol.end_line - symbol.start_line);
+            serde_json::to_string(&enclosing)
+        }
+    };
+    json.map_err(|e| format!("failed to encode symbols: {e}"))
+}
+
+fn find_definitions(cwd: &Path, root: &Path, name: &str) -> DefinitionsOutput {
+    let mut output = DefinitionsOutput {
+        definitions: Vec::new(),
+        truncated: false,
+    };
+    let mut builder = WalkBuilder::new(root);
+    builder.require_git(false).sort_by_file_path(Path::cmp);
+    for entry in builder.build().flatten() {
+        let is_small_file = entry
+            .metadata()
+            .is_ok_and(|meta| meta.is_file() && meta.len() <= MAX_FILE_BYTES);
+        if !is_small_file || SourceLanguage::from_path(entry.path()).is_none() {
+            continue;
+        }
+        let Ok(symbols) = file_symbols(cwd, entry.path()) else {
+            continue;
+        };
+        for symbol in symbols.into_iter().filter(|symbol| symbol.name == name) {
+            if output.definitions.len() == MAX_DEFINITIONS {
+                output.truncated = true;
+                return output;
+            }
+            output.definitions.push(symbol);
+        }
+    }
+    output
+}
+
+/// The symbols defined in the file at `path`, in source order.
+fn file_symbols(cwd: &Path, path: &Path) -> Result<Vec<Symbol>, String> {
+    let language = SourceLanguage::from_path(path).ok_or_else(|| {
+        format!(
+            "{} is not a Rust, Python, JavaScript, TypeScript or Go file",
+            path.display()
+        )
+    })?;
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let display_path = path
+        .strip_prefix(cwd)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned();
+    parse_symbols(language, &source, &display_path)
+}
+
+fn parse_symbols(
+    language: SourceLanguage,
+    source: &str,
+    path: &str,
+) -> Result<Vec<Symbol>, String> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language.grammar())
+        .map_err(|e| format!("failed to load grammar: {e}"))?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| format!("failed to parse {path}"))?;
+    let mut symbols = Vec::new();
+    collect_symbols(
+        language,
+        tree.root_node(),
+        source.as_bytes(),
+        path,
+        None,
+        &mut symbols,
+    );
+    Ok(symbols)
+}
+
+fn collect_symbols(
+    language: SourceLanguage,
+    node: Node,
+    source: &[u8],
+    path: &str,
+    container: Option<&str>,
+    symbols: &mut Vec<Symbol>,
+) {
+    let symbol = language
+        .symbol_kind(node.kind())
+        .and_then(|kind| symbol_name(node, source).map(|name| (kind, name)))
+        .map(|(kind, name)| Symbol {
+            name,
+            kind: method_kind(kind, container),
+            path: path.to_string(),
+            start_line: node.start_position().row + 1,
+            end_line: node.end_position().row + 1,
+            container: container.map(str::to_string),
+        });
+    let child_container = symbol.as_ref().map(|symbol| symbol.name.clone());
+    if let Some(symbol) = symbol {
+        symbols.push(symbol);
+    }
+    let container = child_container.as_deref().or(container);
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_symbols(language, child, source, path, container, symbols);
+    }
+}
+
+/// Functions defined inside another symbol, such as a class or an `impl`
+/// block, are reported as methods.
+fn method_kind(kind: &'static str, container: Option<&str>) -> &'static str {
+    if kind == "function" && container.is_some() {
+        "method"
+    } else {
+        kind
+    }
+}
+
+/// The name a definition node declares, or `None` for nodes that only look
+/// like definitions, such as a variable that does not hold a function.
+fn symbol_name(node: Node, source: &[u8]) -> Option<String> {
+    let name_node = match node.kind() {
+        // `impl Trait for Type` is named after its type.
+        "impl_item" => node.child_by_field_name("type")?,
+        "variable_declarator" => {
+            let value = node.child_by_field_name("value")?;
+            if !matches!(
+                value.kind(),
+                "arrow_function" | "function_expression" | "function"
+            ) {
+                return None;
+            }
+            node.child_by_field_name("name")?
+        }
+        _ => node.child_by_field_name("name")?,
+    };
+    name_node
+        .utf8_text(source)
+        .ok()
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn summary(symbols: &[Symbol]) -> Vec<(&str, &str, usize, usize, Option<&str>)> {
+        symbols
+            .iter()
+            .map(|s| {
+                (
+                    s.kind,
+                    s.name.as_str(),
+                    s.start_line,
+                    s.end_line,
+                    s.container.as_deref(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outlines_rust_items() {
+        let source = "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self {\n        Point { x: 0 }\n    }\n}\n\nfn main() {}\n";
+        let symbols = parse_symbols(SourceLanguage::Rust, source, "lib.rs").unwrap();
+        assert_eq!(
+            vec![
+                ("struct", "Point", 1, 3, None),
+                ("impl", "Point", 5, 9, None),
+                ("method", "new", 6, 8, Some("Point")),
+                ("function", "main", 11, 11, None),
+            ],
+            summary(&symbols)
+        );
+    }
+
+    #[test]
+    fn outlines_python_and_typescript() {
+        let python =
+            "class Greeter:\n    def greet(self):\n        pass\n\ndef main():\n    pass\n";
+        let symbols = parse_symbols(SourceLanguage::Python, python, "app.py").unwrap();
+        assert_eq!(
+            vec![
+                ("class", "Greeter", 1, 3, None),
+                ("method", "greet", 2, 3, Some("Greeter")),
+                ("function", "main", 5, 6, None),
+            ],
+            summary(&symbols)
+        );
+
+        let typescript = "interface Shape {}\nconst area = (s: Shape) => 0;\nconst limit = 3;\n";
+        let symbols = parse_symbols(SourceLanguage::TypeScript, typescript, "shape.ts").unwrap();
+        assert_eq!(
+            vec![
+                ("interface", "Shape", 1, 1, None),
+                ("function", "area", 2, 2, None),
+            ],
+            summary(&symbols)
+        );
+    }
+
+    #[test]
+    fn finds_definitions_and_enclosing_symbols() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "fn helper() {\n    let x = 1;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/util.py"), "def helper():\n    pass\n").unwrap();
+        std::fs::write(root.join("notes.md"), "fn helper() {}\n").unwrap();
+
+        let output = find_definitions(root, root, "helper");
+        assert_eq!(
+            vec!["src/lib.rs", "src/util.py"],
+            output
+                .definitions
+                .iter()
+                .map(|s| s.path.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(!output.truncated);
+
+        let enclosing = run(
+            root,
+            CodeSymbolsArgs::Enclosing {
+                path: "src/lib.rs".to_string(),
+                line: 2,
+            },
+        )
+        .unwrap();
+        let enclosing: serde_json::Value = serde_json::from_str(&enclosing).unwrap();
+        assert_eq!("helper", enclosing["name"]);
+
+        assert!(
+            run(
+                root,
+                CodeSymbolsArgs::Outline {
+                    path: "notes.md".to_string()
+                }
+            )
+            .is_err()
+        );
+    }
+}
@@ -1,19 +1,29 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::background_process::BackgroundProcessAction;
 use crate::background_process::BackgroundProcessInvocation;
 use crate::background_process::BackgroundProcessManager;
+use crate::background_process::BackgroundProcessState;
 use crate::background_process::background_state_to_json;
 use crate::background_process::make_exec_context_for_background;
 use crate::background_process::system_time_to_unix_millis;
+use crate::browser::BROWSER_TOOL_NAME;
+use crate::browser::BrowserArgs;
+use crate::browser::BrowserManager;
+use crate::browser::BrowserOutput;
+use crate::budget::BudgetUsage;
+use crate::budget::exceeded_budget;
 use crate::client_common::REVIEW_PROMPT;
 use crate::event_mapping::map_response_item_to_event_messages;
 use crate::function_tool::FunctionCallError;
@@ -25,6 +35,7 @@ use async_channel::Sender;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::MaybeApplyPatchVerified;
 use codex_apply_patch::maybe_parse_apply_patch_verified;
+use codex_mcp_client::ProgressHandler;
 use codex_protocol::ConversationId;
 use codex_protocol::protocol::ConversationPathResponseEvent;
 use codex_protocol::protocol::ExitedReviewModeEvent;
@@ -34,6 +45,8 @@ use codex_protocol::protocol::SessionSource;
 use codex_protocol::protocol::TaskStartedEvent;
 use codex_protocol::protocol::TurnAbortReason;
 use codex_protocol::protocol::TurnContextItem;
+use futures::future::BoxFuture;
+use futures::future::join_all;
 use futures::prelude::*;
 use mcp_types::CallToolResult;
 use serde::Deserialize;
@@ -42,6 +55,7 @@ use serde_json;
 use serde_json::Value;
 use serde_json::json;
 use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 use tokio::sync::oneshot;
 use tracing::debug;
 use tracing::error;
@@ -55,12 +69,37 @@ use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
 use crate::apply_patch::InternalApplyPatchInvocation;
 use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::batch_edit::BATCH_EDIT_TOOL_NAME;
+use crate::batch_edit::parse_batch_edit_arguments;
+use crate::batch_edit::plan_batch_edit;
+use crate::checkpoint;
+use crate::checkpoint::Checkpoint;
 use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::code_search::CODE_SEARCH_TOOL_NAME;
+use crate::code_search::handle_code_search_tool;
+use crate::code_symbols::CODE_SYMBOLS_TOOL_NAME;
+use crate::code_symbols::handle_code_symbols_tool;
 use crate::config::Config;
+use crate::config::load_global_mcp_server;
+use crate::config_profile::SessionProfile;
+use crate::config_reload::ConfigChanges;
+use crate::config_reload::ConfigFiles;
+use crate::config_reload::spawn_config_watcher;
+use crate::config_types::BusySessionPolicy;
+use crate::config_types::CustomToolConfig;
+use crate::config_types::McpServerConfig;
+use crate::config_types::SessionBudget;
 use crate::config_types::ShellEnvironmentPolicy;
+use crate::config_types::ToolFilter;
 use crate::conversation_history::ConversationHistory;
+use crate::cost::costs_by_model;
+use crate::cost::record_turn_cost;
+use crate::database_query::DATABASE_QUERY_TOOL_NAME;
+use crate::database_query::handle_database_query_tool;
+use crate::dependency_audit::AUDIT_DEPENDENCIES_TOOL_NAME;
+use crate::dependency_audit::handle_audit_dependencies_tool;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -78,25 +117,84 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::exec_env::update_session_env;
+use crate::extension_config::EXTENSION_CONFIG_ENV_VAR;
+use crate::extract_archive::EXTRACT_ARCHIVE_TOOL_NAME;
+use crate::extract_archive::handle_extract_archive;
+use crate::extract_archive::parse_extract_archive_arguments;
+use crate::file_locks::FileLockConflict;
+use crate::file_locks::FileLocks;
+use crate::git_info::collect_turn_git_state;
+use crate::git_tool::GIT_TOOL_NAME;
+use crate::git_tool::GitToolArgs;
+use crate::git_tool::handle_git_tool;
+use crate::git_tool::parse_git_tool_arguments;
+use crate::http_request::HTTP_REQUEST_TOOL_NAME;
+use crate::http_request::handle_http_request_tool;
+use crate::lint::LINT_TOOL_NAME;
+use crate::lint::LintArgs;
+use crate::lint::compute_fixes;
+use crate::lint::fix_patch;
+use crate::lint::lint_targets;
+use crate::lint::render_lint_output;
+use crate::lint::run_linters;
+use crate::list_ports::LIST_PORTS_TOOL_NAME;
+use crate::list_ports::handle_list_ports_tool;
+use crate::mcp_connection_manager::McpCallError;
+use crate::mcp_connection_manager::McpConnectOptions;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_elicitation::McpElicitor;
+use crate::mcp_health::spawn_mcp_health_monitor;
+use crate::mcp_prompts::expand_mcp_prompt;
+use crate::mcp_prompts::list_mcp_prompts;
+use crate::mcp_resources::MCP_RESOURCES_TOOL_NAME;
+use crate::mcp_resources::handle_mcp_resources_tool;
+use crate::mcp_roots::McpRoots;
+use crate::mcp_roots::workspace_roots;
+use crate::mcp_sampling::McpSampler;
 use crate::mcp_tool_call::handle_mcp_tool_call;
+use crate::memory::MEMORY_TOOL_NAME;
+use crate::memory::handle_memory_tool;
+use crate::memory::with_project_memory;
 use crate::model_family::find_family_for_model;
 use crate::openai_model_info::get_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
+use crate::openai_tools::add_custom_tools;
+use crate::openai_tools::add_plugin_tools;
+use crate::openai_tools::filter_tools;
 use crate::openai_tools::get_openai_tools;
+use crate::openai_tools::is_builtin_tool;
 use crate::parse_command::parse_command;
+use crate::pinned_context::PIN_CONTEXT_TOOL_NAME;
+use crate::pinned_context::add_pin;
+use crate::pinned_context::handle_pin_context_tool;
+use crate::pinned_context::render_pinned_context;
+use crate::pinned_context::resolve_pin;
+use crate::plan_tool::UpdatePlanArgs;
 use crate::plan_tool::handle_update_plan;
+use crate::plan_tool::last_plan;
+use crate::plugins::PluginManager;
 use crate::project_doc::get_user_instructions;
 use crate::protocol::AgentMessageDeltaEvent;
 use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningRawContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
+use crate::protocol::ApprovalBatchRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::BackgroundProcessResponseEvent;
 use crate::protocol::BackgroundProcessStatusEvent;
+use crate::protocol::BudgetDecision;
+use crate::protocol::CheckpointCreatedEvent;
+use crate::protocol::CheckpointRestoredEvent;
+use crate::protocol::ClientCapabilities;
+use crate::protocol::CompactedItem;
+use crate::protocol::ConfigReloadedEvent;
+use crate::protocol::ContextBudgetEvent;
+use crate::protocol::CostSummaryEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -104,39 +202,126 @@ use crate::protocol::ExecApprovalRequestEvent;
 use crate::protocol::ExecCommandBeginEvent;
 use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
+use crate::protocol::HistoryRewoundItem;
+use crate::protocol::HunkAction;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpGetPromptResponseEvent;
+use crate::protocol::McpListPromptsResponseEvent;
+use crate::protocol::McpServerConnectionEvent;
+use crate::protocol::ModelFailoverEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
+use crate::protocol::PinnedContextEvent;
+use crate::protocol::QueuedUserInput;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::ReviewDecision;
 use crate::protocol::ReviewOutputEvent;
 use crate::protocol::SandboxPolicy;
+use crate::protocol::SessionBusyEvent;
 use crate::protocol::SessionConfiguredEvent;
+use crate::protocol::SessionDiffEvent;
+use crate::protocol::SessionEnvEvent;
+use crate::protocol::SessionSettingsUpdatedEvent;
+use crate::protocol::SessionTagsEvent;
+use crate::protocol::SessionTitleEvent;
 use crate::protocol::StreamErrorEvent;
+use crate::protocol::SubAgentBeginEvent;
+use crate::protocol::SubAgentEndEvent;
+use crate::protocol::SubAgentOutcome;
 use crate::protocol::Submission;
 use crate::protocol::TokenCountEvent;
 use crate::protocol::TokenUsage;
+use crate::protocol::ToolCallArgumentsDeltaEvent;
 use crate::protocol::TurnDiffEvent;
+use crate::protocol::TurnStats;
+use crate::protocol::TurnStatsResponseEvent;
+use crate::protocol::TurnUndoneEvent;
+use crate::protocol::UserInputQueueEvent;
 use crate::protocol::ViewImageToolCallEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::python_kernel::KernelSpawn;
+use crate::python_kernel::PYTHON_TOOL_NAME;
+use crate::python_kernel::PythonArgs;
+use crate::python_kernel::PythonKernelManager;
+use crate::read_file::READ_FILE_TOOL_NAME;
+use crate::read_file::handle_read_file_tool;
+use crate::replay::ReplayExecBackend;
+use crate::rollout::INTERACTIVE_SESSION_SOURCES;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::safety::get_platform_sandbox;
+use crate::sandbox_denial::detect_sandbox_denial;
+use crate::scratch::ScratchDir;
+use crate::scratch::model_visible_sandbox_policy;
+use crate::secret_scan::SCAN_SECRETS_TOOL_NAME;
+use crate::secret_scan::SecretScanner;
+use crate::secret_scan::describe_findings;
+use crate::secret_scan::handle_scan_secrets_tool;
+use crate::secret_scan::is_git_commit;
+use crate::secret_scan::scan_changes;
+use crate::session_diff::REVIEW_CHANGES_TOOL_NAME;
+use crate::session_diff::handle_review_changes_tool;
+use crate::session_tags::last_tags;
+use crate::session_tags::update_tags;
+use crate::session_title::generate_title;
+use crate::session_title::last_title;
+use crate::session_title::normalize_title;
+use crate::session_title::title_client;
 use crate::shell;
 use crate::state::ActiveTurn;
+use crate::state::QueuedInput;
 use crate::state::SessionServices;
+use crate::sub_agent::Budget;
+use crate::sub_agent::DELEGATE_TASK_TOOL_NAME;
+use crate::sub_agent::DelegateTaskArgs;
+use crate::sub_agent::SubAgent;
+use crate::sub_agent::sub_agent_config;
+use crate::system_info::SYSTEM_INFO_TOOL_NAME;
+use crate::system_info::handle_system_info_tool;
 use crate::tasks::CompactTask;
 use crate::tasks::RegularTask;
 use crate::tasks::ReviewTask;
+use crate::test_runner::RUN_TESTS_TOOL_NAME;
+use crate::test_runner::RunTestsArgs;
+use crate::test_runner::plan_test_run;
+use crate::test_runner::render_report;
+use crate::token_estimate::estimate_prompt_tokens;
+use crate::token_estimate::load_encoder;
+use crate::tool_output::builtin_output_schema;
+use crate::tool_output::check_json_text;
+use crate::tool_output::check_output;
+use crate::tool_output::invalid_output_error;
+use crate::tool_timeout::call_timeout;
+use crate::tool_timeout::configured_timeout;
+use crate::tool_timeout::default_exec_timeout_ms;
+use crate::tool_timeout::timed_out;
 use crate::turn_diff_tracker::TurnDiffTracker;
+use crate::turn_stats::ToolTime;
+use crate::turn_stats::TurnStatsRecorder;
+use crate::undo;
+use crate::undo::TurnFileLog;
+use crate::undo::UndoneFiles;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
 use crate::user_notification::UserNotification;
 use crate::util::backoff;
+use crate::view_image::prepare_image;
+use crate::web_fetch::WEB_FETCH_TOOL_NAME;
+use crate::web_fetch::handle_web_fetch_tool;
+use crate::web_search::WEB_SEARCH_TOOL_NAME;
+use crate::web_search::handle_web_search_tool;
+use crate::workspace_drift::WorkspaceChanges;
+use crate::workspace_drift::capture_workspace_state;
+use crate::write_file::WRITE_FILE_TOOL_NAME;
+use crate::write_file::is_path_writable;
+use crate::write_file::parse_write_file_arguments;
+use crate::write_file::write_atomically;
+use crate::write_file::write_file_action;
 use codex_otel::otel_event_manager::OtelEventManager;
 use codex_otel::otel_event_manager::ToolDecisionSource;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -180,6 +365,10 @@ pub(crate) const MODEL_FORMAT_HEAD_LINES: usize = MODEL_FORMAT_MAX_LINES / 2;
 pub(crate) const MODEL_FORMAT_TAIL_LINES: usize = MODEL_FORMAT_MAX_LINES - MODEL_FORMAT_HEAD_LINES; // 128
 pub(crate) const MODEL_FORMAT_HEAD_BYTES: usize = MODEL_FORMAT_MAX_BYTES / 2;
 
+/// Share of the context window a prompt may fill before the conversation is
+/// compacted ahead of sending it; the rest is room for the response.
+const PROMPT_CONTEXT_WINDOW_PERCENT: u64 = 90;
+
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
     pub async fn spawn(
@@ -187,11 +376,13 @@ impl Codex {
         auth_manager: Arc<AuthManager>,
         conversation_history: InitialHistory,
         session_source: SessionSource,
+        file_locks: Arc<FileLocks>,
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
         let (tx_event, rx_event) = async_channel::unbounded();
 
-        let user_instructions = get_user_instructions(&config).await;
+        let user_instructions =
+            with_project_memory(&config, get_user_instructions(&config).await).await;
 
         let config = Arc::new(config);
 
@@ -216,6 +407,7 @@ impl Codex {
             tx_event.clone(),
             conversation_history,
             session_source,
+            file_locks,
         )
         .await
         .map_err(|e| {
@@ -223,6 +415,8 @@ impl Codex {
             CodexErr::InternalAgentDied
         })?;
         let conversation_id = session.conversation_id;
+        spawn_mcp_health_monitor(&session, Arc::clone(&config));
+        spawn_config_watcher(&config, tx_sub.clone());
 
         // This task will run until Op::Shutdown is received.
         tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
@@ -351,6 +545,7 @@ impl Session {
         tx_event: Sender<Event>,
         initial_history: InitialHistory,
         session_source: SessionSource,
+        file_locks: Arc<FileLocks>,
     ) -> anyhow::Result<(Arc<Self>, TurnContext)> {
         let ConfigureSession {
             provider,
@@ -398,22 +593,76 @@ impl Session {
         // - load history metadata
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
+        let mcp_sampler = config
+            .mcp_sampling
+            .enabled
+            .then(|| Arc::new(McpSampler::new(config.mcp_sampling.clone())));
+        let mcp_roots = Arc::new(McpRoots::new(workspace_roots(
+            &config.cwd,
+            &config.sandbox_policy,
+        )));
+        let mcp_elicitor = Arc::new(McpElicitor::new());
         let mcp_fut = McpConnectionManager::new(
             config.mcp_servers.clone(),
-            config.use_experimental_use_rmcp_client,
+            McpConnectOptions {
+                use_rmcp_client: config.use_experimental_use_rmcp_client,
+                sampler: mcp_sampler.clone(),
+                roots: Arc::clone(&mcp_roots),
+                elicitor: Arc::clone(&mcp_elicitor),
+                codex_home: config.codex_home.clone(),
+                oauth_store: config.mcp_oauth_credentials_store,
+            },
         );
         let default_shell_fut = shell::default_user_shell();
+        let model_family = config.model_family.clone();
+        tokio::task::spawn_blocking(move || load_encoder(&model_family));
         let history_meta_fut = crate::message_history::history_metadata(&config);
+        let plugins_fut = PluginManager::load(
+            config.plugins.clone(),
+            config.extensions.clone(),
+            &config.codex_home,
+        );
 
         // Join all independent futures.
-        let (rollout_recorder, mcp_res, default_shell, (history_log_id, history_entry_count)) =
-            tokio::join!(rollout_fut, mcp_fut, default_shell_fut, history_meta_fut);
+        let (
+            rollout_recorder,
+            mcp_res,
+            default_shell,
+            (history_log_id, history_entry_count),
+            (plugin_manager, failed_plugins),
+        ) = tokio::join!(
+            rollout_fut,
+            mcp_fut,
+            default_shell_fut,
+            history_meta_fut,
+            plugins_fut
+        );
 
         let rollout_recorder = rollout_recorder.map_err(|e| {
             error!("failed to initialize rollout recorder: {e:#}");
             anyhow::anyhow!("failed to initialize rollout recorder: {e:#}")
         })?;
         let rollout_path = rollout_recorder.rollout_path.clone();
+        let replay = match &config.replay_rollout {
+            Some(path) => Some(ReplayExecBackend::load(path).await.map_err(|e| {
+                anyhow::anyhow!("failed to load replay rollout {}: {e:#}", path.display())
+            })?),
+            None => None,
+        };
+        let scratch_dir = match ScratchDir::create() {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                error!("failed to create scratch directory: {e:#}");
+                None
+            }
+        };
+        let (sandbox_policy, shell_environment_policy) = match &scratch_dir {
+            Some(dir) => (
+                dir.apply_to_sandbox(sandbox_policy),
+                dir.apply_to_env(config.shell_environment_policy.clone()),
+            ),
+            None => (sandbox_policy, config.shell_environment_policy.clone()),
+        };
         // Create the mutable state for the Session.
         let state = SessionState::new();
 
@@ -443,6 +692,15 @@ impl Session {
             }
         }
 
+        for (path, err) in failed_plugins {
+            let message = format!("Plugin `{}` failed to load: {err}", path.display());
+            error!("{message}");
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            });
+        }
+
         let otel_event_manager = OtelEventManager::new(
             conversation_id,
             config.model.as_str(),
@@ -479,26 +737,25 @@ impl Session {
         );
         let turn_context = TurnContext {
             client,
-            tools_config: ToolsConfig::new(&ToolsConfigParams {
-                model_family: &config.model_family,
-                include_plan_tool: config.include_plan_tool,
-                include_apply_patch_tool: config.include_apply_patch_tool,
-                include_web_search_request: config.tools_web_search_request,
-                use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-                include_view_image_tool: config.include_view_image_tool,
-                experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-            }),
+            tools_config: ToolsConfig::new(&ToolsConfigParams::from_config(
+                &config,
+                &config.model_family,
+            )),
             user_instructions,
             base_instructions,
             approval_policy,
             sandbox_policy,
-            shell_environment_policy: config.shell_environment_policy.clone(),
+            shell_environment_policy,
             cwd,
             is_review_mode: false,
             final_output_json_schema: None,
         };
         let services = SessionServices {
-            mcp_connection_manager,
+            mcp_connection_manager: RwLock::new(mcp_connection_manager),
+            mcp_servers: Mutex::new(config.mcp_servers.clone()),
+            mcp_sampler,
+            mcp_roots,
+            mcp_elicitor,
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: notify,
@@ -506,7 +763,21 @@ impl Session {
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            background_process_manager: BackgroundProcessManager::new(),
+            approval_batching: config.approval_batching,
+            busy_session_policy: config.busy_session_policy,
+            generate_title: config.session_titles.enabled
+                && INTERACTIVE_SESSION_SOURCES.contains(&session_source),
+            binary_payload_framing: config.binary_payload_framing,
+            client_capabilities: config.client_capabilities,
+            background_process_manager: BackgroundProcessManager::new(
+                config.background_process.clone(),
+            ),
+            browser_manager: BrowserManager::default(),
+            python_kernel_manager: PythonKernelManager::default(),
+            plugin_manager,
+            file_locks,
+            replay,
+            scratch_dir,
         };
 
         let sess = Arc::new(Session {
@@ -520,6 +791,12 @@ impl Session {
 
         sess.background_processes()
             .set_session(Arc::downgrade(&sess));
+        if let Some(sampler) = &sess.services.mcp_sampler {
+            sampler.set_session(Arc::downgrade(&sess));
+        }
+        sess.services
+            .mcp_elicitor
+            .set_session(Arc::downgrade(&sess));
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
         // If resuming, include converted initial messages in the payload so UIs can render them immediately.
@@ -527,6 +804,27 @@ impl Session {
         sess.record_initial_history(&turn_context, initial_history)
             .await;
 
+        // Tags from the configuration are added to those the session already
+        // has and recorded if that changed anything.
+        let tags_event = {
+            let mut state = sess.state.lock().await;
+            match update_tags(&mut state.tags, config.session_tags.clone(), &[]) {
+                Ok(changed) => changed.then(|| Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::SessionTags(SessionTagsEvent {
+                        tags: state.tags.clone(),
+                    }),
+                }),
+                Err(message) => {
+                    post_session_configured_error_events.push(Event {
+                        id: INITIAL_SUBMIT_ID.to_owned(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    });
+                    None
+                }
+            }
+        };
+
         let events = std::iter::once(Event {
             id: INITIAL_SUBMIT_ID.to_owned(),
             msg: EventMsg::SessionConfigured(SessionConfiguredEvent {
@@ -539,6 +837,7 @@ impl Session {
                 rollout_path,
             }),
         })
+        .chain(tags_event)
         .chain(post_session_configured_error_events.into_iter());
         for event in events {
             sess.send_event(event).await;
@@ -575,6 +874,39 @@ impl Session {
                 if !reconstructed_history.is_empty() {
                     self.record_into_history(&reconstructed_history).await;
                 }
+                {
+                    let mut state = self.state.lock().await;
+                    state.title = last_title(&rollout_items);
+                    state.tags = last_tags(&rollout_items).unwrap_or_default();
+                    state.plan = last_plan(&rollout_items);
+                    // Earlier turns count towards the cost of the session,
+                    // but not against a budget set for this run of it.
+                    state.turn_stats = rollout_items
+                        .iter()
+                        .filter_map(|item| match item {
+                            RolloutItem::EventMsg(EventMsg::TurnStats(stats)) => {
+                                Some(stats.clone())
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    state.budget_baseline = BudgetUsage::from_stats(&state.turn_stats);
+                }
+
+                // Tell the model what changed in the workspace while the
+                // session was inactive.
+                if !persist
+                    && let Some(last_state) = rollout_items.iter().rev().find_map(|item| match item
+                    {
+                        RolloutItem::EventMsg(EventMsg::WorkspaceState(state)) => Some(state),
+                        _ => None,
+                    })
+                    && let Some(changes) =
+                        WorkspaceChanges::detect(last_state, &turn_context.cwd).await
+                {
+                    self.record_conversation_items(&[ResponseItem::from(changes)])
+                        .await;
+                }
 
                 // If persisting, persist all rollout items as-is (recorder filters)
                 if persist && !rollout_items.is_empty() {
@@ -685,17 +1017,182 @@ impl Session {
             Some(tx_approve) => {
                 tx_approve.send(decision).ok();
             }
+            // Individual items of an approval batch are answered by call_id.
+            None if self.record_batch_item_decision(sub_id, decision).await => {}
             None => {
                 warn!("No pending approval found for sub_id: {sub_id}");
             }
         }
     }
 
+    /// Pause the turn with `EventMsg::BudgetExceeded` when the session has
+    /// reached a limit of its `[session_budget]`. Returns whether the turn
+    /// may go on.
+    async fn check_session_budget(
+        &self,
+        sub_id: &str,
+        turn_context: &TurnContext,
+        turn_stats: &TurnStatsRecorder,
+    ) -> bool {
+        let budget = turn_context.client.get_config().session_budget;
+        if budget == SessionBudget::default() {
+            return true;
+        }
+        let current = turn_stats.current();
+        let (usage, exceeded) = {
+            let state = self.state.lock().await;
+            let usage = BudgetUsage::from_stats(state.turn_stats.iter().chain([&current]));
+            let exceeded = exceeded_budget(&budget, &usage, &state.budget_baseline);
+            (usage, exceeded)
+        };
+        let Some(event) = exceeded else {
+            return true;
+        };
+
+        let (tx_decision, rx_decision) = oneshot::channel();
+        {
+            let mut active = self.active_turn.lock().await;
+            if let Some(at) = active.as_mut() {
+                let mut ts = at.turn_state.lock().await;
+                ts.insert_pending_budget_decision(sub_id.to_string(), tx_decision);
+            }
+        }
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::BudgetExceeded(event),
+        })
+        .await;
+        match rx_decision.await.unwrap_or_default() {
+            BudgetDecision::Continue => {
+                self.state.lock().await.budget_baseline = usage;
+                true
+            }
+            BudgetDecision::Stop => false,
+        }
+    }
+
+    /// Whether the turns completed so far reached a limit of the
+    /// `[session_budget]`.
+    pub(crate) async fn session_budget_reached(&self, turn_context: &TurnContext) -> bool {
+        let budget = turn_context.client.get_config().session_budget;
+        if budget == SessionBudget::default() {
+            return false;
+        }
+        let state = self.state.lock().await;
+        let usage = BudgetUsage::from_stats(state.turn_stats.iter());
+        exceeded_budget(&budget, &usage, &state.budget_baseline).is_some()
+    }
+
+    pub async fn notify_budget_decision(&self, sub_id: &str, decision: BudgetDecision) {
+        let entry = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.remove_pending_budget_decision(sub_id)
+                }
+                None => None,
+            }
+        };
+        match entry {
+            Some(tx_decision) => {
+                tx_decision.send(decision).ok();
+            }
+            None => {
+                warn!("No pending budget decision found for sub_id: {sub_id}");
+            }
+        }
+    }
+
+    /// Ask the user to approve several commands with a single
+    /// `ApprovalBatchRequest`. Returns once every item has a decision; the
+    /// decisions are consumed per call via `take_batch_decision`.
+    pub async fn request_batch_approval(
+        &self,
+        sub_id: String,
+        items: Vec<ExecApprovalRequestEvent>,
+    ) {
+        let (tx_done, rx_done) = oneshot::channel();
+        let call_ids = items.iter().map(|item| item.call_id.clone()).collect();
+        {
+            let mut active = self.active_turn.lock().await;
+            let Some(at) = active.as_mut() else {
+                return;
+            };
+            let mut ts = at.turn_state.lock().await;
+            ts.insert_pending_approval_batch(sub_id.clone(), call_ids, tx_done);
+        }
+
+        let event = Event {
+            id: sub_id,
+            msg: EventMsg::ApprovalBatchRequest(ApprovalBatchRequestEvent { items }),
+        };
+        self.send_event(event).await;
+        rx_done.await.ok();
+    }
+
+    pub async fn notify_approval_batch(
+        &self,
+        sub_id: &str,
+        decisions: HashMap<String, ReviewDecision>,
+        default_decision: Option<ReviewDecision>,
+    ) {
+        let resolved = {
+            let mut active = self.active_turn.lock().await;
+            match active.as_mut() {
+                Some(at) => {
+                    let mut ts = at.turn_state.lock().await;
+                    ts.resolve_approval_batch(sub_id, decisions, default_decision)
+                }
+                None => false,
+            }
+        };
+        if !resolved {
+            warn!("No pending approval batch found for sub_id: {sub_id}");
+        }
+    }
+
+    async fn record_batch_item_decision(&self, call_id: &str, decision: ReviewDecision) -> bool {
+        let mut active = self.active_turn.lock().await;
+        match active.as_mut() {
+            Some(at) => {
+                let mut ts = at.turn_state.lock().await;
+                ts.record_batch_item_decision(call_id, decision)
+            }
+            None => false,
+        }
+    }
+
+    async fn take_batch_decision(&self, call_id: &str) -> Option<ReviewDecision> {
+        let mut active = self.active_turn.lock().await;
+        match active.as_mut() {
+            Some(at) => {
+                let mut ts = at.turn_state.lock().await;
+                ts.take_batch_decision(call_id)
+            }
+            None => None,
+        }
+    }
+
     pub async fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock().await;
         state.add_approved_command(cmd);
     }
 
+    pub(crate) async fn add_approved_mcp_tool(&self, server: &str, tool: &str) {
+        let mut state = self.state.lock().await;
+        state
+            .approved_mcp_tools
+            .insert((server.to_string(), tool.to_string()));
+    }
+
+    pub(crate) async fn is_mcp_tool_approved(&self, server: &str, tool: &str) -> bool {
+        let state = self.state.lock().await;
+        state
+            .approved_mcp_tools
+            .contains(&(server.to_string(), tool.to_string()))
+    }
+
     /// Records input items: always append to conversation history and
     /// persist these response items to rollout.
     async fn record_conversation_items(&self, items: &[ResponseItem]) {
@@ -714,6 +1211,15 @@ impl Session {
                 RolloutItem::ResponseItem(response_item) => {
                     history.record_items(std::iter::once(response_item));
                 }
+                RolloutItem::Compacted(CompactedItem {
+                    replacement_history: Some(replacement),
+                    ..
+                }) => {
+                    history.replace(replacement.clone());
+                }
+                RolloutItem::HistoryRewound(rewound) => {
+                    history.replace(rewound.history.clone());
+                }
                 RolloutItem::Compacted(compacted) => {
                     let snapshot = history.contents();
                     let user_messages = collect_user_messages(&snapshot);
@@ -741,6 +1247,14 @@ impl Session {
         state.replace_history(items);
     }
 
+    /// Record in the rollout that the history was rewound to its current
+    /// contents, so that resuming the session restores them.
+    async fn persist_history_rewind(&self) {
+        let history = self.history_snapshot().await;
+        self.persist_rollout_items(&[RolloutItem::HistoryRewound(HistoryRewoundItem { history })])
+            .await;
+    }
+
     async fn persist_rollout_response_items(&self, items: &[ResponseItem]) {
         let rollout_items: Vec<RolloutItem> = items
             .iter()
@@ -758,7 +1272,7 @@ impl Session {
         items.push(ResponseItem::from(EnvironmentContext::new(
             Some(turn_context.cwd.clone()),
             Some(turn_context.approval_policy),
-            Some(turn_context.sandbox_policy.clone()),
+            Some(model_visible_sandbox_policy(turn_context)),
             Some(self.user_shell().clone()),
         )));
         items
@@ -853,6 +1367,7 @@ impl Session {
             command_for_display,
             cwd,
             apply_patch,
+            session_env,
         } = exec_command_context;
         let msg = match apply_patch {
             Some(ApplyPatchCommandContext {
@@ -875,6 +1390,7 @@ impl Session {
                     .into_iter()
                     .map(Into::into)
                     .collect(),
+                session_env,
             }),
         };
         let event = Event {
@@ -933,7 +1449,7 @@ impl Session {
 
         // If this is an apply_patch, after we emit the end patch, emit a second event
         // with the full turn diff if there is one.
-        if is_apply_patch {
+        if is_apply_patch && self.services.client_capabilities.show_diffs {
             let unified_diff = turn_diff_tracker.get_unified_diff();
             if let Ok(Some(unified_diff)) = unified_diff {
                 let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
@@ -1003,7 +1519,7 @@ impl Session {
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
-    async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
+    pub(crate) async fn notify_background_event(&self, sub_id: &str, message: impl Into<String>) {
         let event = Event {
             id: sub_id.to_string(),
             msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
@@ -1054,6 +1570,141 @@ impl Session {
         }
     }
 
+    /// Queue input to run as a turn of its own once the running turn and any
+    /// input queued earlier have finished. Hands the input back when nothing
+    /// is running or queued, so the caller can start the turn immediately.
+    async fn queue_input(&self, queued: QueuedInput) -> Result<(), QueuedInput> {
+        let active = self.active_turn.lock().await;
+        let mut state = self.state.lock().await;
+        if active.is_none() && state.queued_input.is_empty() {
+            return Err(queued);
+        }
+        let sub_id = queued.sub_id.clone();
+        state.queued_input.push_back(queued);
+        let pending = state.queued_input_summary();
+        drop(state);
+        drop(active);
+        self.send_user_input_queue(&sub_id, pending).await;
+        Ok(())
+    }
+
+    /// Route turn input that may arrive while a turn is running, following
+    /// the session's [`BusySessionPolicy`]. Returns the input when the caller
+    /// should go on and [`Self::start_turn`] with it.
+    async fn admit_turn_input(
+        &self,
+        sub_id: &str,
+        items: Vec<InputItem>,
+    ) -> Option<Vec<InputItem>> {
+        match self.services.busy_session_policy {
+            BusySessionPolicy::Inject => self.inject_input(items).await.err(),
+            // Queuing happens in `start_turn`, once the turn context is known.
+            BusySessionPolicy::Queue => Some(items),
+            BusySessionPolicy::Reject => (!self.reject_if_busy(sub_id).await).then_some(items),
+        }
+    }
+
+    /// Start a regular turn, or queue it behind the running turn when the
+    /// session queues turn input.
+    async fn start_turn(
+        self: &Arc<Self>,
+        turn_context: Arc<TurnContext>,
+        sub_id: String,
+        items: Vec<InputItem>,
+    ) {
+        let mut queued = QueuedInput {
+            sub_id,
+            items,
+            turn_context,
+        };
+        if self.services.busy_session_policy == BusySessionPolicy::Queue {
+            match self.queue_input(queued).await {
+                Ok(()) => return,
+                Err(not_queued) => queued = not_queued,
+            }
+        }
+        self.spawn_task(
+            queued.turn_context,
+            queued.sub_id,
+            queued.items,
+            RegularTask,
+        )
+        .await;
+    }
+
+    /// Whether a turn is running or waiting to run.
+    async fn is_busy(&self) -> bool {
+        let active = self.active_turn.lock().await;
+        active.is_some() || !self.state.lock().await.queued_input.is_empty()
+    }
+
+    /// Send `EventMsg::SessionBusy` for `sub_id` if a turn is running or
+    /// waiting to run. Returns whether the submission was rejected.
+    async fn reject_if_busy(&self, sub_id: &str) -> bool {
+        let busy = {
+            let active = self.active_turn.lock().await;
+            let state = self.state.lock().await;
+            let running: Vec<String> = active
+                .as_ref()
+                .map(|turn| turn.tasks.keys().cloned().collect())
+                .unwrap_or_default();
+            let queued = state.queued_input.len();
+            (!running.is_empty() || queued > 0).then_some(SessionBusyEvent { running, queued })
+        };
+        let Some(busy) = busy else {
+            return false;
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::SessionBusy(busy),
+        })
+        .await;
+        true
+    }
+
+    /// Start a turn for the oldest queued input, unless a turn is running.
+    ///
+    /// Returns a boxed future to break the `spawn_task` -> `on_task_finished`
+    /// -> here cycle, which otherwise keeps the compiler from proving the
+    /// spawned task `Send`.
+    pub(crate) fn start_next_queued_input(self: &Arc<Self>) -> BoxFuture<'_, ()> {
+        async move {
+            let (next, pending) = {
+                let active = self.active_turn.lock().await;
+                if active.is_some() {
+                    return;
+                }
+                let mut state = self.state.lock().await;
+                let Some(next) = state.queued_input.pop_front() else {
+                    return;
+                };
+                (next, state.queued_input_summary())
+            };
+            self.send_user_input_queue(&next.sub_id, pending).await;
+            self.spawn_task(next.turn_context, next.sub_id, next.items, RegularTask)
+                .await;
+        }
+        .boxed()
+    }
+
+    async fn clear_queued_input(&self, sub_id: &str) {
+        let cleared = {
+            let mut state = self.state.lock().await;
+            !std::mem::take(&mut state.queued_input).is_empty()
+        };
+        if cleared {
+            self.send_user_input_queue(sub_id, Vec::new()).await;
+        }
+    }
+
+    async fn send_user_input_queue(&self, sub_id: &str, pending: Vec<QueuedUserInput>) {
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::UserInputQueue(UserInputQueueEvent { pending }),
+        };
+        self.send_event(event).await;
+    }
+
     pub async fn get_pending_input(&self) -> Vec<ResponseInputItem> {
         let mut active = self.active_turn.lock().await;
         match active.as_mut() {
@@ -1070,10 +1721,14 @@ impl Session {
         server: &str,
         tool: &str,
         arguments: Option<serde_json::Value>,
-    ) -> anyhow::Result<CallToolResult> {
+        timeout: Option<Duration>,
+        on_progress: ProgressHandler,
+    ) -> Result<CallToolResult, McpCallError> {
         self.services
             .mcp_connection_manager
-            .call_tool(server, tool, arguments)
+            .read()
+            .await
+            .call_tool(server, tool, arguments, timeout, on_progress)
             .await
     }
 
@@ -1103,157 +1758,614 @@ impl Session {
         &self.services.user_shell
     }
 
+    pub(crate) fn conversation_id(&self) -> ConversationId {
+        self.conversation_id
+    }
+
+    pub(crate) fn mcp_connection_manager(&self) -> &RwLock<McpConnectionManager> {
+        &self.services.mcp_connection_manager
+    }
+
     pub(crate) fn background_processes(&self) -> &BackgroundProcessManager {
         &self.services.background_process_manager
     }
 
-    fn show_raw_agent_reasoning(&self) -> bool {
-        self.services.show_raw_agent_reasoning
+    pub(crate) fn file_locks(&self) -> &Arc<FileLocks> {
+        &self.services.file_locks
     }
-}
 
-impl Drop for Session {
-    fn drop(&mut self) {
-        self.interrupt_task_sync();
+    /// Claim `paths` for this session's current task.
+    pub(crate) fn lock_files<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Result<(), FileLockConflict> {
+        self.services
+            .file_locks
+            .try_lock(self.conversation_id, paths)
     }
-}
 
-#[derive(Clone, Debug)]
-pub(crate) struct ExecCommandContext {
-    pub(crate) sub_id: String,
-    pub(crate) call_id: String,
-    pub(crate) command_for_display: Vec<String>,
-    pub(crate) cwd: PathBuf,
-    pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
-}
-
-#[derive(Clone, Debug)]
-pub(crate) struct ApplyPatchCommandContext {
-    pub(crate) user_explicitly_approved_this_action: bool,
-    pub(crate) changes: HashMap<PathBuf, FileChange>,
-}
+    pub(crate) fn release_file_locks(&self) {
+        self.services.file_locks.release(self.conversation_id);
+    }
 
-async fn submission_loop(
-    sess: Arc<Session>,
-    turn_context: TurnContext,
-    config: Arc<Config>,
-    rx_sub: Receiver<Submission>,
-) {
-    // Wrap once to avoid cloning TurnContext for each task.
-    let mut turn_context = Arc::new(turn_context);
-    // To break out of this loop, send Op::Shutdown.
-    while let Ok(sub) = rx_sub.recv().await {
-        debug!(?sub, "Submission");
-        match sub.op {
-            Op::Interrupt => {
-                sess.interrupt_task().await;
-            }
-            Op::OverrideTurnContext {
-                cwd,
-                approval_policy,
-                sandbox_policy,
-                model,
-                effort,
-                summary,
-            } => {
-                // Recalculate the persistent turn context with provided overrides.
-                let prev = Arc::clone(&turn_context);
-                let provider = prev.client.get_provider();
-
-                // Effective model + family
-                let (effective_model, effective_family) = if let Some(ref m) = model {
-                    let fam =
-                        find_family_for_model(m).unwrap_or_else(|| config.model_family.clone());
-                    (m.clone(), fam)
-                } else {
-                    (prev.client.get_model(), prev.client.get_model_family())
-                };
+    /// Point the MCP sampling and elicitation requests at the turn that is
+    /// about to start.
+    pub(crate) fn set_mcp_turn(&self, sub_id: &str, turn_context: &Arc<TurnContext>) {
+        if let Some(sampler) = &self.services.mcp_sampler {
+            sampler.set_turn(sub_id.to_string(), Arc::clone(turn_context));
+        }
+        self.services
+            .mcp_elicitor
+            .set_turn(sub_id.to_string(), turn_context.approval_policy);
+    }
 
-                // Effective reasoning settings
-                let effective_effort = effort.unwrap_or(prev.client.get_reasoning_effort());
-                let effective_summary = summary.unwrap_or(prev.client.get_reasoning_summary());
+    /// Decline the MCP elicitation requests still waiting for the user.
+    pub(crate) fn cancel_mcp_elicitations(&self) {
+        self.services.mcp_elicitor.cancel_all();
+    }
 
-                let auth_manager = prev.client.get_auth_manager();
+    fn show_raw_agent_reasoning(&self) -> bool {
+        self.services.show_raw_agent_reasoning
+    }
 
-                // Build updated config for the client
-                let mut updated_config = (*config).clone();
-                updated_config.model = effective_model.clone();
-                updated_config.model_family = effective_family.clone();
-                if let Some(model_info) = get_model_info(&effective_family) {
-                    updated_config.model_context_window = Some(model_info.context_window);
-                }
+    pub(crate) fn binary_payload_framing(&self) -> bool {
+        self.services.binary_payload_framing
+    }
 
-                let otel_event_manager = prev.client.get_otel_event_manager().with_model(
-                    updated_config.model.as_str(),
-                    updated_config.model_family.slug.as_str(),
-                );
+    pub(crate) fn client_capabilities(&self) -> ClientCapabilities {
+        self.services.client_capabilities
+    }
 
-                let client = ModelClient::new(
-                    Arc::new(updated_config),
-                    auth_manager,
-                    otel_event_manager,
-                    provider,
-                    effective_effort,
-                    effective_summary,
-                    sess.conversation_id,
-                );
+    async fn record_turn_stats(&self, sub_id: &str, turn_context: &TurnContext, stats: TurnStats) {
+        let session = {
+            let mut state = self.state.lock().await;
+            state.turn_stats.push(stats.clone());
+            costs_by_model(&state.turn_stats)
+        };
+        let codex_home = &turn_context.client.get_config().codex_home;
+        let all_sessions = match record_turn_cost(codex_home, &stats).await {
+            Ok(costs) => costs,
+            Err(e) => {
+                warn!("failed to record the cost of turn {sub_id}: {e}");
+                Vec::new()
+            }
+        };
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::TurnStats(stats),
+        })
+        .await;
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::CostSummary(CostSummaryEvent {
+                session,
+                all_sessions,
+            }),
+        })
+        .await;
+    }
 
-                let new_approval_policy = approval_policy.unwrap_or(prev.approval_policy);
-                let new_sandbox_policy = sandbox_policy
-                    .clone()
-                    .unwrap_or(prev.sandbox_policy.clone());
-                let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
-
-                let tools_config = ToolsConfig::new(&ToolsConfigParams {
-                    model_family: &effective_family,
-                    include_plan_tool: config.include_plan_tool,
-                    include_apply_patch_tool: config.include_apply_patch_tool,
-                    include_web_search_request: config.tools_web_search_request,
-                    use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-                    include_view_image_tool: config.include_view_image_tool,
-                    experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-                });
+    /// Replace the plan and report it to clients.
+    pub(crate) async fn set_plan(&self, sub_id: &str, plan: UpdatePlanArgs) {
+        self.state.lock().await.plan = Some(plan.clone());
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::PlanUpdate(plan),
+        })
+        .await;
+    }
 
-                let new_turn_context = TurnContext {
-                    client,
-                    tools_config,
-                    user_instructions: prev.user_instructions.clone(),
-                    base_instructions: prev.base_instructions.clone(),
-                    approval_policy: new_approval_policy,
-                    sandbox_policy: new_sandbox_policy.clone(),
-                    shell_environment_policy: prev.shell_environment_policy.clone(),
-                    cwd: new_cwd.clone(),
-                    is_review_mode: false,
-                    final_output_json_schema: None,
-                };
+    /// Counts the output of a finished call of `name` against its quota.
+    async fn record_tool_output(&self, name: &str, output: &ResponseInputItem) {
+        let bytes = match output {
+            ResponseInputItem::FunctionCallOutput { output, .. } => output.content.len(),
+            ResponseInputItem::CustomToolCallOutput { output, .. } => output.len(),
+            ResponseInputItem::McpToolCallOutput { result, .. } => match result {
+                Ok(result) => serde_json::to_string(result).map_or(0, |text| text.len()),
+                Err(e) => e.len(),
+            },
+            ResponseInputItem::Message { .. } => 0,
+        };
+        self.state
+            .lock()
+            .await
+            .tool_usage
+            .record_output(name, bytes);
+    }
 
-                // Install the new persistent context for subsequent tasks/turns.
-                turn_context = Arc::new(new_turn_context);
+    async fn send_pinned_context(&self, sub_id: &str) {
+        let pins = self.state.lock().await.pinned_context.clone();
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::PinnedContext(PinnedContextEvent { pins }),
+        })
+        .await;
+    }
 
-                // Optionally persist changes to model / effort
-                if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
-                    sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
-                        cwd,
-                        approval_policy,
-                        sandbox_policy,
-                        // Shell is not configurable from turn to turn
-                        None,
-                    ))])
+    /// `input` preceded by the pinned context, with pinned files read afresh.
+    async fn with_pinned_context(&self, mut input: Vec<ResponseItem>) -> Vec<ResponseItem> {
+        let pins = self.state.lock().await.pinned_context.clone();
+        if let Some(pinned) = render_pinned_context(&pins).await {
+            input.insert(0, pinned);
+        }
+        input
+    }
+
+    /// Generate a title in the background unless the session already has one
+    /// or one is being generated. A failed attempt is retried after the next
+    /// turn.
+    async fn spawn_title_generation(self: &Arc<Self>, sub_id: &str, turn_context: &TurnContext) {
+        if !self.services.generate_title {
+            return;
+        }
+        let history = {
+            let mut state = self.state.lock().await;
+            if state.title.is_some() || state.title_pending {
+                return;
+            }
+            state.title_pending = true;
+            state.history_snapshot()
+        };
+        let client = title_client(turn_context, self.conversation_id);
+        let sess = Arc::clone(self);
+        let sub_id = sub_id.to_string();
+        tokio::spawn(async move {
+            let title = match generate_title(&client, &history).await {
+                Ok(title) => title,
+                Err(e) => {
+                    warn!("failed to generate session title: {e}");
+                    None
+                }
+            };
+            sess.state.lock().await.title_pending = false;
+            if let Some(title) = title {
+                sess.set_title(&sub_id, title, false).await;
+            }
+        });
+    }
+
+    /// Record `title`. A generated title (`replace == false`) never replaces
+    /// one set in the meantime.
+    async fn set_title(&self, sub_id: &str, title: String, replace: bool) {
+        {
+            let mut state = self.state.lock().await;
+            if state.title.is_some() && !replace {
+                return;
+            }
+            state.title = Some(title.clone());
+        }
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::SessionTitle(SessionTitleEvent { title }),
+        })
+        .await;
+    }
+
+    /// Release what an idle session holds on to: running background
+    /// processes (those of the `browser` and `python` tools included), open
+    /// exec sessions and MCP server connections. The conversation itself is
+    /// untouched, so it can carry on afterwards.
+    /// Returns `false` without releasing anything while a turn is running.
+    async fn release_idle_resources(&self, idle_for: Duration) -> bool {
+        if self.active_turn.lock().await.is_some() {
+            return false;
+        }
+        let processes = self.services.background_process_manager.kill_all().await
+            + usize::from(self.services.browser_manager.close().await)
+            + usize::from(self.services.python_kernel_manager.close().await);
+        let exec_sessions = self.services.session_manager.close_all().await
+            + self.services.unified_exec_manager.close_all().await;
+        let mcp_servers = {
+            let mut manager = self.services.mcp_connection_manager.write().await;
+            std::mem::take(&mut *manager).server_count()
+        };
+        info!(
+            "session idle for {}s: killed {processes} background processes, closed {exec_sessions} exec sessions and {mcp_servers} MCP connections",
+            idle_for.as_secs()
+        );
+        if processes + exec_sessions + mcp_servers > 0 {
+            self.notify_background_event(
+                INITIAL_SUBMIT_ID,
+                format!(
+                    "Session idle for {}s: stopped {processes} background processes and {exec_sessions} exec sessions, disconnected {mcp_servers} MCP servers. MCP servers reconnect on the next request.",
+                    idle_for.as_secs()
+                ),
+            )
+            .await;
+        }
+        true
+    }
+
+    /// Reconnect the MCP servers dropped by [`Self::release_idle_resources`].
+    async fn restore_idle_resources(&self, sub_id: &str, config: &Config) {
+        let mcp_servers = self.services.mcp_servers.lock().await.clone();
+        if mcp_servers.is_empty() {
+            return;
+        }
+        let (manager, failed_clients) =
+            match McpConnectionManager::new(mcp_servers, self.mcp_connect_options(config)).await {
+                Ok(result) => result,
+                Err(e) => {
+                    let message = format!("Failed to reconnect MCP servers: {e:#}");
+                    error!("{message}");
+                    self.send_event(Event {
+                        id: sub_id.to_string(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    })
                     .await;
+                    return;
+                }
+            };
+        *self.services.mcp_connection_manager.write().await = manager;
+        for (server_name, err) in failed_clients {
+            let message = format!("MCP client for `{server_name}` failed to start: {err:#}");
+            error!("{message}");
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            })
+            .await;
+        }
+    }
+
+    /// The configuration `server` was connected with, if it is one of the
+    /// session's servers.
+    pub(crate) async fn mcp_server_config(&self, server: &str) -> Option<McpServerConfig> {
+        self.services.mcp_servers.lock().await.get(server).cloned()
+    }
+
+    /// Connect `name` for `Op::ConnectMcpServer`, preferring its current
+    /// entry in `config.toml`. Returns the number of its tools.
+    async fn connect_mcp_server(&self, config: &Config, name: &str) -> anyhow::Result<usize> {
+        let cfg = load_global_mcp_server(&config.codex_home, name)?
+            .or_else(|| config.mcp_servers.get(name).cloned())
+            .ok_or_else(|| anyhow::anyhow!("no MCP server named `{name}` is configured"))?;
+        self.connect_mcp_server_config(config, name, cfg).await
+    }
+
+    /// Connect `name` with `cfg`, replacing the server of that name if the
+    /// session has one. Returns the number of its tools.
+    async fn connect_mcp_server_config(
+        &self,
+        config: &Config,
+        name: &str,
+        cfg: McpServerConfig,
+    ) -> anyhow::Result<usize> {
+        let connected = McpConnectionManager::reconnect_server(
+            name,
+            cfg.clone(),
+            &self.mcp_connect_options(config),
+        )
+        .await?;
+        self.services
+            .mcp_servers
+            .lock()
+            .await
+            .insert(name.to_string(), cfg);
+        Ok(self
+            .services
+            .mcp_connection_manager
+            .write()
+            .await
+            .insert_server(name, connected))
+    }
+
+    /// Disconnect `name` for `Op::DisconnectMcpServer`. Returns whether it
+    /// was one of the session's servers.
+    async fn disconnect_mcp_server(&self, name: &str) -> bool {
+        let configured = self
+            .services
+            .mcp_servers
+            .lock()
+            .await
+            .remove(name)
+            .is_some();
+        let connected = self
+            .services
+            .mcp_connection_manager
+            .write()
+            .await
+            .remove_server(name);
+        configured || connected
+    }
+
+    /// How the session connects to its MCP servers.
+    pub(crate) fn mcp_connect_options(&self, config: &Config) -> McpConnectOptions {
+        McpConnectOptions {
+            use_rmcp_client: config.use_experimental_use_rmcp_client,
+            sampler: self.services.mcp_sampler.clone(),
+            roots: Arc::clone(&self.services.mcp_roots),
+            elicitor: Arc::clone(&self.services.mcp_elicitor),
+            codex_home: config.codex_home.clone(),
+            oauth_store: config.mcp_oauth_credentials_store,
+        }
+    }
+
+    /// Advertise the roots of `turn_context` to the MCP servers and notify
+    /// them if the roots changed.
+    async fn update_mcp_roots(&self, turn_context: &TurnContext) {
+        let roots = workspace_roots(
+            &turn_context.cwd,
+            &model_visible_sandbox_policy(turn_context),
+        );
+        if self.services.mcp_roots.update(roots) {
+            self.services
+                .mcp_connection_manager
+                .read()
+                .await
+                .notify_roots_list_changed()
+                .await;
+        }
+    }
+
+    /// `policy` with the session's scratch directory made writable.
+    fn with_scratch_dir(&self, policy: SandboxPolicy) -> SandboxPolicy {
+        match &self.services.scratch_dir {
+            Some(dir) => dir.apply_to_sandbox(policy),
+            None => policy,
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.interrupt_task_sync();
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExecCommandContext {
+    pub(crate) sub_id: String,
+    pub(crate) call_id: String,
+    pub(crate) command_for_display: Vec<String>,
+    pub(crate) cwd: PathBuf,
+    pub(crate) apply_patch: Option<ApplyPatchCommandContext>,
+    /// Variables from `Op::UpdateSessionEnv` added to the command's
+    /// environment.
+    pub(crate) session_env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ApplyPatchCommandContext {
+    pub(crate) user_explicitly_approved_this_action: bool,
+    pub(crate) changes: HashMap<PathBuf, FileChange>,
+}
+
+async fn submission_loop(
+    sess: Arc<Session>,
+    turn_context: TurnContext,
+    config: Arc<Config>,
+    rx_sub: Receiver<Submission>,
+) {
+    // Wrap once to avoid cloning TurnContext for each task.
+    let mut turn_context = Arc::new(turn_context);
+    // Set while the resources of an idle session are released; they are
+    // restored before the next submission is handled.
+    let mut idle = false;
+    // The config files as last applied, to find what `Op::ReloadConfig`
+    // changes.
+    let mut config_files = if config.watch_config {
+        ConfigFiles::load(&config).ok()
+    } else {
+        None
+    };
+    // To break out of this loop, send Op::Shutdown.
+    loop {
+        let sub = match config.idle_timeout {
+            Some(idle_timeout) if !idle => {
+                match tokio::time::timeout(idle_timeout, rx_sub.recv()).await {
+                    Ok(sub) => sub,
+                    Err(_) => {
+                        idle = sess.release_idle_resources(idle_timeout).await;
+                        continue;
+                    }
+                }
+            }
+            _ => rx_sub.recv().await,
+        };
+        let Ok(sub) = sub else {
+            break;
+        };
+        if idle {
+            idle = false;
+            if !matches!(sub.op, Op::Shutdown) {
+                sess.restore_idle_resources(&sub.id, &config).await;
+            }
+        }
+        debug!(?sub, "Submission");
+        match sub.op {
+            Op::Interrupt => {
+                sess.interrupt_task().await;
+                sess.clear_queued_input(&sub.id).await;
+            }
+            Op::OverrideTurnContext {
+                cwd,
+                approval_policy,
+                sandbox_policy,
+                model,
+                effort,
+                summary,
+            } => {
+                // Install the new persistent context for subsequent tasks/turns.
+                turn_context = Arc::new(
+                    apply_turn_context_overrides(
+                        &sess,
+                        &config,
+                        &turn_context,
+                        TurnContextOverrides {
+                            cwd,
+                            approval_policy,
+                            sandbox_policy,
+                            model,
+                            effort,
+                            summary,
+                            tool_filter: None,
+                        },
+                    )
+                    .await,
+                );
+                sess.update_mcp_roots(&turn_context).await;
+            }
+            Op::UpdateSessionSettings {
+                model,
+                approval_policy,
+                sandbox_policy,
+                effort,
+            } => {
+                if let Err(message) = validate_session_settings_update(
+                    &config,
+                    &turn_context,
+                    model.as_deref(),
+                    effort,
+                    sandbox_policy.as_ref(),
+                ) {
+                    let event = Event {
+                        id: sub.id.clone(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    };
+                    sess.send_event(event).await;
+                    continue;
+                }
+
+                turn_context = Arc::new(
+                    apply_turn_context_overrides(
+                        &sess,
+                        &config,
+                        &turn_context,
+                        TurnContextOverrides {
+                            cwd: None,
+                            approval_policy,
+                            sandbox_policy,
+                            model,
+                            effort,
+                            summary: None,
+                            tool_filter: None,
+                        },
+                    )
+                    .await,
+                );
+                sess.update_mcp_roots(&turn_context).await;
+
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::SessionSettingsUpdated(SessionSettingsUpdatedEvent {
+                        model: turn_context.client.get_model(),
+                        approval_policy: turn_context.approval_policy,
+                        sandbox_policy: turn_context.sandbox_policy.clone(),
+                        reasoning_effort: turn_context.client.get_reasoning_effort(),
+                        profile: None,
+                    }),
+                };
+                sess.send_event(event).await;
+            }
+            Op::SwitchProfile { name } => {
+                let Some(profile) = config.profiles.get(&name).cloned() else {
+                    let available = config.profiles.keys().cloned().collect::<Vec<_>>();
+                    let message = if available.is_empty() {
+                        format!("no profile named {name}; no profiles are configured")
+                    } else {
+                        format!(
+                            "no profile named {name}; available profiles: {}",
+                            available.join(", ")
+                        )
+                    };
+                    let event = Event {
+                        id: sub.id.clone(),
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    };
+                    sess.send_event(event).await;
+                    continue;
+                };
+                let SessionProfile {
+                    model,
+                    model_reasoning_effort,
+                    approval_policy,
+                    sandbox_policy,
+                    tool_filter,
+                } = profile;
+                let effort = model_reasoning_effort.map(Some);
+                if let Err(message) = validate_session_settings_update(
+                    &config,
+                    &turn_context,
+                    model.as_deref(),
+                    effort,
+                    sandbox_policy.as_ref(),
+                ) {
+                    let event = Event {
+                        id: sub.id.clone(),
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: format!("cannot switch to profile {name}: {message}"),
+                        }),
+                    };
+                    sess.send_event(event).await;
+                    continue;
                 }
+
+                turn_context = Arc::new(
+                    apply_turn_context_overrides(
+                        &sess,
+                        &config,
+                        &turn_context,
+                        TurnContextOverrides {
+                            cwd: None,
+                            approval_policy,
+                            sandbox_policy,
+                            model,
+                            effort,
+                            summary: None,
+                            tool_filter: Some(tool_filter),
+                        },
+                    )
+                    .await,
+                );
+                sess.update_mcp_roots(&turn_context).await;
+
+                let event = Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::SessionSettingsUpdated(SessionSettingsUpdatedEvent {
+                        model: turn_context.client.get_model(),
+                        approval_policy: turn_context.approval_policy,
+                        sandbox_policy: turn_context.sandbox_policy.clone(),
+                        reasoning_effort: turn_context.client.get_reasoning_effort(),
+                        profile: Some(name),
+                    }),
+                };
+                sess.send_event(event).await;
             }
             Op::UserInput { items } => {
                 turn_context
                     .client
                     .get_otel_event_manager()
                     .user_prompt(&items);
-                // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
-                    // no current task, spawn a new one
-                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
+                if let Some(items) = sess.admit_turn_input(&sub.id, items).await {
+                    sess.start_turn(Arc::clone(&turn_context), sub.id, items)
                         .await;
                 }
             }
+            Op::QueueUserInput { items } => {
+                turn_context
+                    .client
+                    .get_otel_event_manager()
+                    .user_prompt(&items);
+                let queued = QueuedInput {
+                    sub_id: sub.id,
+                    items,
+                    turn_context: Arc::clone(&turn_context),
+                };
+                if let Err(queued) = sess.queue_input(queued).await {
+                    // nothing running or waiting, start the turn right away
+                    sess.spawn_task(
+                        queued.turn_context,
+                        queued.sub_id,
+                        queued.items,
+                        RegularTask,
+                    )
+                    .await;
+                }
+            }
+            Op::ClearQueuedUserInput => {
+                sess.clear_queued_input(&sub.id).await;
+            }
             Op::UserTurn {
                 items,
                 cwd,
@@ -1268,8 +2380,7 @@ async fn submission_loop(
                     .client
                     .get_otel_event_manager()
                     .user_prompt(&items);
-                // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items).await {
+                if let Some(items) = sess.admit_turn_input(&sub.id, items).await {
                     // Derive a fresh TurnContext for this turn using the provided overrides.
                     let provider = turn_context.client.get_provider();
                     let auth_manager = turn_context.client.get_auth_manager();
@@ -1306,40 +2417,40 @@ async fn submission_loop(
 
                     let fresh_turn_context = TurnContext {
                         client,
-                        tools_config: ToolsConfig::new(&ToolsConfigParams {
-                            model_family: &model_family,
-                            include_plan_tool: config.include_plan_tool,
-                            include_apply_patch_tool: config.include_apply_patch_tool,
-                            include_web_search_request: config.tools_web_search_request,
-                            use_streamable_shell_tool: config
-                                .use_experimental_streamable_shell_tool,
-                            include_view_image_tool: config.include_view_image_tool,
-                            experimental_unified_exec_tool: config
-                                .use_experimental_unified_exec_tool,
-                        }),
+                        tools_config: ToolsConfig::new(&ToolsConfigParams::from_config(
+                            &config,
+                            &model_family,
+                        )),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
                         approval_policy,
-                        sandbox_policy,
+                        sandbox_policy: sess.with_scratch_dir(sandbox_policy),
                         shell_environment_policy: turn_context.shell_environment_policy.clone(),
                         cwd,
                         is_review_mode: false,
                         final_output_json_schema,
                     };
 
-                    // if the environment context has changed, record it in the conversation history
+                    // if the environment context has changed, record it in the conversation
+                    // history, unless the turn waits in the queue: recording now would slip
+                    // it into the running turn
                     let previous_env_context = EnvironmentContext::from(turn_context.as_ref());
                     let new_env_context = EnvironmentContext::from(&fresh_turn_context);
-                    if !new_env_context.equals_except_shell(&previous_env_context) {
+                    let waits_in_queue = sess.services.busy_session_policy
+                        == BusySessionPolicy::Queue
+                        && sess.is_busy().await;
+                    if !waits_in_queue
+                        && !new_env_context.equals_except_shell(&previous_env_context)
+                    {
                         sess.record_conversation_items(&[ResponseItem::from(new_env_context)])
                             .await;
                     }
 
                     // Install the new persistent context for subsequent tasks/turns.
                     turn_context = Arc::new(fresh_turn_context);
+                    sess.update_mcp_roots(&turn_context).await;
 
-                    // no current task, spawn a new one with the per-turn context
-                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, RegularTask)
+                    sess.start_turn(Arc::clone(&turn_context), sub.id, items)
                         .await;
                 }
             }
@@ -1349,12 +2460,29 @@ async fn submission_loop(
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::ApprovalBatch {
+                id,
+                decisions,
+                default_decision,
+            } => {
+                if default_decision == Some(ReviewDecision::Abort)
+                    || decisions.values().any(|d| *d == ReviewDecision::Abort)
+                {
+                    sess.interrupt_task().await;
+                } else {
+                    sess.notify_approval_batch(&id, decisions, default_decision)
+                        .await;
+                }
+            }
             Op::PatchApproval { id, decision } => match decision {
                 ReviewDecision::Abort => {
                     sess.interrupt_task().await;
                 }
                 other => sess.notify_approval(&id, other).await,
             },
+            Op::BudgetDecision { id, decision } => {
+                sess.notify_budget_decision(&id, decision).await;
+            }
             Op::AddToHistory { text } => {
                 let id = sess.conversation_id;
                 let config = config.clone();
@@ -1403,7 +2531,12 @@ async fn submission_loop(
                 let sub_id = sub.id.clone();
 
                 // This is a cheap lookup from the connection manager's cache.
-                let tools = sess.services.mcp_connection_manager.list_all_tools();
+                let tools = sess
+                    .services
+                    .mcp_connection_manager
+                    .read()
+                    .await
+                    .list_all_tools();
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::McpListToolsResponse(
@@ -1430,39 +2563,404 @@ async fn submission_loop(
                 };
                 sess.send_event(event).await;
             }
-            Op::Compact => {
-                // Attempt to inject input into current task
-                if let Err(items) = sess
-                    .inject_input(vec![InputItem::Text {
-                        text: compact::SUMMARIZATION_PROMPT.to_string(),
-                    }])
-                    .await
-                {
-                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, CompactTask)
-                        .await;
-                }
+            Op::ListMcpPrompts => {
+                // Listing queries every server that offers prompts.
+                let sess = Arc::clone(&sess);
+                tokio::spawn(async move {
+                    let prompts =
+                        list_mcp_prompts(&*sess.services.mcp_connection_manager.read().await).await;
+                    let msg =
+                        EventMsg::McpListPromptsResponse(McpListPromptsResponseEvent { prompts });
+                    sess.send_event(Event { id: sub.id, msg }).await;
+                });
             }
-            Op::Shutdown => {
-                sess.abort_all_tasks(TurnAbortReason::Interrupted).await;
-                info!("Shutting down Codex instance");
-
-                // Gracefully flush and shutdown rollout recorder on session end so tests
-                // that inspect the rollout file do not race with the background writer.
-                let recorder_opt = {
-                    let mut guard = sess.services.rollout.lock().await;
-                    guard.take()
-                };
-                if let Some(rec) = recorder_opt
-                    && let Err(e) = rec.shutdown().await
-                {
-                    warn!("failed to shutdown rollout recorder: {e}");
-                    let event = Event {
-                        id: sub.id.clone(),
-                        msg: EventMsg::Error(ErrorEvent {
-                            message: "Failed to shutdown rollout recorder".to_string(),
-                        }),
-                    };
-                    sess.send_event(event).await;
+            Op::GetMcpPrompt {
+                server,
+                name,
+                arguments,
+            } => {
+                let sess = Arc::clone(&sess);
+                tokio::spawn(async move {
+                    let result = expand_mcp_prompt(
+                        &*sess.services.mcp_connection_manager.read().await,
+                        &server,
+                        &name,
+                        arguments,
+                    )
+                    .await;
+                    let msg = EventMsg::McpGetPromptResponse(McpGetPromptResponseEvent {
+                        server,
+                        name,
+                        result,
+                    });
+                    sess.send_event(Event { id: sub.id, msg }).await;
+                });
+            }
+            Op::ConnectMcpServer { name } => {
+                // Starting the server can take up to its startup timeout.
+                let sess = Arc::clone(&sess);
+                let config = Arc::clone(&config);
+                tokio::spawn(async move {
+                    let msg = match sess.connect_mcp_server(&config, &name).await {
+                        Ok(tools) => EventMsg::McpServerConnection(McpServerConnectionEvent {
+                            server: name,
+                            connected: true,
+                            tools: tools as u64,
+                        }),
+                        Err(e) => EventMsg::Error(ErrorEvent {
+                            message: format!("Failed to connect MCP server `{name}`: {e:#}"),
+                        }),
+                    };
+                    sess.send_event(Event { id: sub.id, msg }).await;
+                });
+            }
+            Op::DisconnectMcpServer { name } => {
+                let msg = if sess.disconnect_mcp_server(&name).await {
+                    EventMsg::McpServerConnection(McpServerConnectionEvent {
+                        server: name,
+                        connected: false,
+                        tools: 0,
+                    })
+                } else {
+                    EventMsg::Error(ErrorEvent {
+                        message: format!("MCP server `{name}` is not connected"),
+                    })
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::ReloadConfig => {
+                let new_files = match ConfigFiles::load(&config) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        let event = Event {
+                            id: sub.id.clone(),
+                            msg: EventMsg::Error(ErrorEvent {
+                                message: format!("Failed to reload config: {e}"),
+                            }),
+                        };
+                        sess.send_event(event).await;
+                        continue;
+                    }
+                };
+                let Some(old_files) = config_files.replace(new_files.clone()) else {
+                    continue;
+                };
+                let changes = old_files.changes(&new_files);
+                if changes == ConfigChanges::default() {
+                    continue;
+                }
+                let ConfigChanges {
+                    approval_policy,
+                    tool_filter,
+                    connect,
+                    disconnect,
+                    requires_restart,
+                } = changes;
+
+                let mut applied = Vec::new();
+                if approval_policy.is_some() || tool_filter.is_some() {
+                    if tool_filter.is_some() {
+                        applied.push("tool set and disabled tools updated".to_string());
+                    }
+                    turn_context = Arc::new(
+                        apply_turn_context_overrides(
+                            &sess,
+                            &config,
+                            &turn_context,
+                            TurnContextOverrides {
+                                cwd: None,
+                                approval_policy,
+                                sandbox_policy: None,
+                                model: None,
+                                effort: None,
+                                summary: None,
+                                tool_filter,
+                            },
+                        )
+                        .await,
+                    );
+                    if approval_policy.is_some() {
+                        applied.push(format!(
+                            "approval policy set to {}",
+                            turn_context.approval_policy
+                        ));
+                    }
+                }
+                let approval_policy = approval_policy.map(|_| turn_context.approval_policy);
+
+                // Connecting servers can take up to their startup timeouts.
+                let sess = Arc::clone(&sess);
+                let config = Arc::clone(&config);
+                tokio::spawn(async move {
+                    for name in disconnect {
+                        if sess.disconnect_mcp_server(&name).await {
+                            applied.push(format!("MCP server `{name}` disconnected"));
+                        }
+                    }
+                    for name in connect {
+                        let result = match new_files.mcp_server(&name) {
+                            Ok(cfg) => sess.connect_mcp_server_config(&config, &name, cfg).await,
+                            Err(e) => Err(e.into()),
+                        };
+                        match result {
+                            Ok(tools) => applied.push(format!(
+                                "MCP server `{name}` connected with {tools} tool(s)"
+                            )),
+                            Err(e) => {
+                                let event = Event {
+                                    id: sub.id.clone(),
+                                    msg: EventMsg::Error(ErrorEvent {
+                                        message: format!(
+                                            "Failed to connect MCP server `{name}`: {e:#}"
+                                        ),
+                                    }),
+                                };
+                                sess.send_event(event).await;
+                            }
+                        }
+                    }
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::ConfigReloaded(ConfigReloadedEvent {
+                            applied,
+                            requires_restart,
+                            approval_policy,
+                        }),
+                    };
+                    sess.send_event(event).await;
+                });
+            }
+            Op::ResolveElicitation {
+                id,
+                action,
+                content,
+            } => {
+                if !sess.services.mcp_elicitor.resolve(&id, action, content) {
+                    warn!("no pending elicitation request found for id: {id}");
+                }
+            }
+            Op::CreateCheckpoint { name } => {
+                let history = sess.history_snapshot().await;
+                let msg = match checkpoint::snapshot_worktree(&turn_context.cwd).await {
+                    Ok(worktree) => {
+                        let files_captured = worktree.is_some();
+                        let mut state = sess.state.lock().await;
+                        state.insert_checkpoint(name.clone(), Checkpoint { history, worktree });
+                        EventMsg::CheckpointCreated(CheckpointCreatedEvent {
+                            name,
+                            files_captured,
+                        })
+                    }
+                    Err(e) => EventMsg::Error(ErrorEvent {
+                        message: format!("failed to create checkpoint {name}: {e}"),
+                    }),
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::RollbackToCheckpoint { name } => {
+                let msg = match rollback_to_checkpoint(&sess, &name).await {
+                    Ok(()) => EventMsg::CheckpointRestored(CheckpointRestoredEvent { name }),
+                    Err(message) => EventMsg::Error(ErrorEvent { message }),
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::GetTurnStats => {
+                let turns = sess.state.lock().await.turn_stats.clone();
+                let event = Event {
+                    id: sub.id,
+                    msg: EventMsg::TurnStatsResponse(TurnStatsResponseEvent { turns }),
+                };
+                sess.send_event(event).await;
+            }
+            Op::UndoTurn => {
+                let msg = match undo_turn(&sess).await {
+                    Ok(UndoneFiles { restored, removed }) => {
+                        EventMsg::TurnUndone(TurnUndoneEvent { restored, removed })
+                    }
+                    Err(message) => EventMsg::Error(ErrorEvent { message }),
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::GetSessionDiff => {
+                let msg = EventMsg::SessionDiff(session_diff(&sess).await);
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::ResolveHunks {
+                path,
+                hunks,
+                action,
+            } => {
+                let path = turn_context.cwd.join(path);
+                let msg = match resolve_hunks(&sess, &path, &hunks, action).await {
+                    Ok(diff) => EventMsg::SessionDiff(diff),
+                    Err(message) => EventMsg::Error(ErrorEvent { message }),
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::RetryTurn {
+                correction,
+                revert_changes,
+            } => {
+                if let Err(message) = retry_turn(
+                    &sess,
+                    Arc::clone(&turn_context),
+                    sub.id.clone(),
+                    correction,
+                    revert_changes,
+                )
+                .await
+                {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent { message }),
+                    };
+                    sess.send_event(event).await;
+                }
+            }
+            Op::PinContext { pin } => {
+                let pin = resolve_pin(pin, &turn_context.cwd);
+                add_pin(&mut sess.state.lock().await.pinned_context, pin);
+                sess.send_pinned_context(&sub.id).await;
+            }
+            Op::UnpinContext { index } => {
+                let removed = {
+                    let mut state = sess.state.lock().await;
+                    (index < state.pinned_context.len()).then(|| state.pinned_context.remove(index))
+                };
+                if removed.is_some() {
+                    sess.send_pinned_context(&sub.id).await;
+                } else {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: format!("no pin with index {index}"),
+                        }),
+                    };
+                    sess.send_event(event).await;
+                }
+            }
+            Op::ListPinnedContext => {
+                sess.send_pinned_context(&sub.id).await;
+            }
+            Op::GetPlan => {
+                let plan = sess
+                    .state
+                    .lock()
+                    .await
+                    .plan
+                    .clone()
+                    .unwrap_or(UpdatePlanArgs {
+                        explanation: None,
+                        plan: Vec::new(),
+                    });
+                let event = Event {
+                    id: sub.id,
+                    msg: EventMsg::PlanUpdate(plan),
+                };
+                sess.send_event(event).await;
+            }
+            Op::SetSessionTitle { title } => match normalize_title(&title) {
+                Some(title) => sess.set_title(&sub.id, title, true).await,
+                None => {
+                    let event = Event {
+                        id: sub.id,
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: "session title must not be empty".to_string(),
+                        }),
+                    };
+                    sess.send_event(event).await;
+                }
+            },
+            Op::UpdateSessionTags { set, remove } => {
+                let msg = {
+                    let mut state = sess.state.lock().await;
+                    match update_tags(&mut state.tags, set, &remove) {
+                        Ok(_) => EventMsg::SessionTags(SessionTagsEvent {
+                            tags: state.tags.clone(),
+                        }),
+                        Err(message) => EventMsg::Error(ErrorEvent { message }),
+                    }
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::UpdateSessionEnv { set, remove } => {
+                let msg = {
+                    let mut state = sess.state.lock().await;
+                    match update_session_env(&mut state.env, set, &remove) {
+                        Ok(()) => EventMsg::SessionEnv(SessionEnvEvent {
+                            env: state.env.clone(),
+                        }),
+                        Err(message) => EventMsg::Error(ErrorEvent { message }),
+                    }
+                };
+                sess.send_event(Event { id: sub.id, msg }).await;
+            }
+            Op::BackgroundProcess { request } => {
+                // Starting a process may wait for approval, which arrives
+                // through this loop.
+                let sess = Arc::clone(&sess);
+                let turn_context = Arc::clone(&turn_context);
+                tokio::spawn(async move {
+                    let result = run_background_process_action(
+                        &sess,
+                        &turn_context,
+                        sub.id.clone(),
+                        sub.id.clone(),
+                        request.into(),
+                    )
+                    .await
+                    .map_err(|FunctionCallError::RespondToModel(message)| message);
+                    let msg = EventMsg::BackgroundProcessResponse(BackgroundProcessResponseEvent {
+                        result,
+                    });
+                    sess.send_event(Event { id: sub.id, msg }).await;
+                });
+            }
+            Op::Compact => {
+                if sess.services.busy_session_policy != BusySessionPolicy::Inject
+                    && sess.reject_if_busy(&sub.id).await
+                {
+                    continue;
+                }
+                // Attempt to inject input into current task
+                if let Err(items) = sess
+                    .inject_input(vec![InputItem::Text {
+                        text: compact::SUMMARIZATION_PROMPT.to_string(),
+                    }])
+                    .await
+                {
+                    sess.spawn_task(Arc::clone(&turn_context), sub.id, items, CompactTask)
+                        .await;
+                }
+            }
+            Op::Shutdown => {
+                sess.abort_all_tasks(TurnAbortReason::Interrupted).await;
+                info!("Shutting down Codex instance");
+
+                // Gracefully flush and shutdown rollout recorder on session end so tests
+                // that inspect the rollout file do not race with the background writer.
+                let recorder_opt = {
+                    let mut guard = sess.services.rollout.lock().await;
+                    guard.take()
+                };
+                if let Some(rec) = recorder_opt
+                    && let Err(e) = rec.shutdown().await
+                {
+                    warn!("failed to shutdown rollout recorder: {e}");
+                    let event = Event {
+                        id: sub.id.clone(),
+                        msg: EventMsg::Error(ErrorEvent {
+                            message: "Failed to shutdown rollout recorder".to_string(),
+                        }),
+                    };
+                    sess.send_event(event).await;
+                }
+
+                sess.services.background_process_manager.end_session().await;
+                sess.services.browser_manager.close().await;
+                sess.services.python_kernel_manager.close().await;
+                if let Some(scratch_dir) = &sess.services.scratch_dir {
+                    scratch_dir.remove();
                 }
 
                 let event = Event {
@@ -1500,6 +2998,11 @@ async fn submission_loop(
                 sess.send_event(event).await;
             }
             Op::Review { review_request } => {
+                if sess.services.busy_session_policy != BusySessionPolicy::Inject
+                    && sess.reject_if_busy(&sub.id).await
+                {
+                    continue;
+                }
                 spawn_review_thread(
                     sess.clone(),
                     config.clone(),
@@ -1529,13 +3032,9 @@ async fn spawn_review_thread(
     let review_model_family = find_family_for_model(&model)
         .unwrap_or_else(|| parent_turn_context.client.get_model_family());
     let tools_config = ToolsConfig::new(&ToolsConfigParams {
-        model_family: &review_model_family,
-        include_plan_tool: false,
         include_apply_patch_tool: config.include_apply_patch_tool,
-        include_web_search_request: false,
-        use_streamable_shell_tool: false,
-        include_view_image_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        ..ToolsConfigParams::new(&review_model_family)
     });
 
     let base_instructions = REVIEW_PROMPT.to_string();
@@ -1604,6 +3103,266 @@ async fn spawn_review_thread(
     .await;
 }
 
+/// Overrides applied to the persistent turn context by
+/// `Op::OverrideTurnContext`, `Op::UpdateSessionSettings` and
+/// `Op::SwitchProfile`. `None` keeps the existing value.
+struct TurnContextOverrides {
+    cwd: Option<PathBuf>,
+    approval_policy: Option<AskForApproval>,
+    sandbox_policy: Option<SandboxPolicy>,
+    model: Option<String>,
+    effort: Option<Option<ReasoningEffortConfig>>,
+    summary: Option<ReasoningSummaryConfig>,
+    tool_filter: Option<ToolFilter>,
+}
+
+/// Recalculate the persistent turn context with the provided overrides.
+async fn apply_turn_context_overrides(
+    sess: &Session,
+    config: &Arc<Config>,
+    prev: &TurnContext,
+    overrides: TurnContextOverrides,
+) -> TurnContext {
+    let TurnContextOverrides {
+        cwd,
+        approval_policy,
+        sandbox_policy,
+        model,
+        effort,
+        summary,
+        tool_filter,
+    } = overrides;
+    let provider = prev.client.get_provider();
+
+    // Effective model + family
+    let (effective_model, effective_family) = if let Some(ref m) = model {
+        let fam = find_family_for_model(m).unwrap_or_else(|| config.model_family.clone());
+        (m.clone(), fam)
+    } else {
+        (prev.client.get_model(), prev.client.get_model_family())
+    };
+
+    // Effective reasoning settings
+    let effective_effort = effort.unwrap_or(prev.client.get_reasoning_effort());
+    let effective_summary = summary.unwrap_or(prev.client.get_reasoning_summary());
+
+    let auth_manager = prev.client.get_auth_manager();
+
+    // Build updated config for the client
+    let mut updated_config = (**config).clone();
+    updated_config.model = effective_model.clone();
+    updated_config.model_family = effective_family.clone();
+    updated_config.tool_filter =
+        tool_filter.unwrap_or_else(|| prev.client.get_config().tool_filter.clone());
+    if let Some(model_info) = get_model_info(&effective_family) {
+        updated_config.model_context_window = Some(model_info.context_window);
+    }
+
+    let otel_event_manager = prev.client.get_otel_event_manager().with_model(
+        updated_config.model.as_str(),
+        updated_config.model_family.slug.as_str(),
+    );
+
+    let client = ModelClient::new(
+        Arc::new(updated_config),
+        auth_manager,
+        otel_event_manager,
+        provider,
+        effective_effort,
+        effective_summary,
+        sess.conversation_id,
+    );
+
+    let new_approval_policy = sess
+        .client_capabilities()
+        .effective_approval_policy(approval_policy.unwrap_or(prev.approval_policy));
+    let new_sandbox_policy = match &sandbox_policy {
+        Some(policy) => sess.with_scratch_dir(policy.clone()),
+        None => prev.sandbox_policy.clone(),
+    };
+    let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
+
+    let tools_config =
+        ToolsConfig::new(&ToolsConfigParams::from_config(config, &effective_family));
+
+    let new_turn_context = TurnContext {
+        client,
+        tools_config,
+        user_instructions: prev.user_instructions.clone(),
+        base_instructions: prev.base_instructions.clone(),
+        approval_policy: new_approval_policy,
+        sandbox_policy: new_sandbox_policy.clone(),
+        shell_environment_policy: prev.shell_environment_policy.clone(),
+        cwd: new_cwd.clone(),
+        is_review_mode: false,
+        final_output_json_schema: None,
+    };
+
+    // Optionally persist changes to model / effort
+    if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
+        sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
+            cwd,
+            approval_policy,
+            sandbox_policy,
+            // Shell is not configurable from turn to turn
+            None,
+        ))])
+        .await;
+    }
+
+    new_turn_context
+}
+
+/// Restore the conversation history and working tree saved under `name`.
+async fn rollback_to_checkpoint(sess: &Session, name: &str) -> Result<(), String> {
+    if sess.active_turn.lock().await.is_some() {
+        return Err(format!(
+            "cannot roll back to checkpoint {name} while a task is running"
+        ));
+    }
+    let Some(Checkpoint { history, worktree }) = sess.state.lock().await.checkpoint(name) else {
+        return Err(format!("no checkpoint named {name}"));
+    };
+    if let Some(worktree) = &worktree {
+        checkpoint::restore_worktree(worktree)
+            .await
+            .map_err(|e| format!("failed to restore checkpoint {name}: {e}"))?;
+    }
+    sess.replace_history(history).await;
+    sess.persist_history_rewind().await;
+    Ok(())
+}
+
+/// Revert the file changes of the most recent turn that changed files.
+async fn undo_turn(sess: &Session) -> Result<UndoneFiles, String> {
+    if sess.active_turn.lock().await.is_some() {
+        return Err("cannot undo a turn while a task is running".to_string());
+    }
+    let Some(log) = sess.state.lock().await.pop_turn_file_log() else {
+        return Err("no turn with file changes to undo".to_string());
+    };
+    log.undo()
+        .await
+        .map_err(|e| format!("failed to undo turn: {e}"))
+}
+
+/// The session's changes to the working tree. The files are compared
+/// without holding the state lock.
+async fn session_diff(sess: &Session) -> SessionDiffEvent {
+    let changes = sess.state.lock().await.session_changes.clone();
+    SessionDiffEvent {
+        files: changes.diff().await,
+    }
+}
+
+/// Accept or revert hunks of the session's changes to `path`.
+async fn resolve_hunks(
+    sess: &Session,
+    path: &Path,
+    hunks: &[usize],
+    action: HunkAction,
+) -> Result<SessionDiffEvent, String> {
+    if sess.active_turn.lock().await.is_some() {
+        return Err("cannot resolve hunks while a task is running".to_string());
+    }
+    let mut changes = sess.state.lock().await.session_changes.clone();
+    changes.resolve(path, hunks, action).await?;
+    sess.state.lock().await.session_changes = changes.clone();
+    Ok(SessionDiffEvent {
+        files: changes.diff().await,
+    })
+}
+
+/// Discard the most recent turn and run it again with `correction` appended
+/// to its input, first reverting its file changes if asked to.
+async fn retry_turn(
+    sess: &Arc<Session>,
+    turn_context: Arc<TurnContext>,
+    sub_id: String,
+    correction: String,
+    revert_changes: bool,
+) -> Result<(), String> {
+    if sess.is_busy().await {
+        return Err("cannot retry a turn while a task is running".to_string());
+    }
+    let file_log = {
+        let mut state = sess.state.lock().await;
+        let Some(last_turn) = state.last_turn.as_ref() else {
+            return Err("no turn to retry".to_string());
+        };
+        let turn_id = last_turn.sub_id.clone();
+        if revert_changes {
+            state.pop_turn_file_log_of(&turn_id)
+        } else {
+            None
+        }
+    };
+    if let Some(log) = file_log {
+        let UndoneFiles { restored, removed } = log
+            .undo()
+            .await
+            .map_err(|e| format!("failed to revert the turn's changes: {e}"))?;
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::TurnUndone(TurnUndoneEvent { restored, removed }),
+        })
+        .await;
+    }
+    let Some(last_turn) = sess.state.lock().await.rewind_last_turn() else {
+        return Err("no turn to retry".to_string());
+    };
+    sess.persist_history_rewind().await;
+
+    let mut items = last_turn.items;
+    let correction = correction.trim();
+    if !correction.is_empty() {
+        items.push(InputItem::Text {
+            text: correction.to_string(),
+        });
+    }
+    sess.spawn_task(turn_context, sub_id, items, RegularTask)
+        .await;
+    Ok(())
+}
+
+/// Check an `Op::UpdateSessionSettings` request before anything is applied,
+/// returning a message suitable for an `ErrorEvent` when it is rejected.
+fn validate_session_settings_update(
+    config: &Config,
+    prev: &TurnContext,
+    model: Option<&str>,
+    effort: Option<Option<ReasoningEffortConfig>>,
+    sandbox_policy: Option<&SandboxPolicy>,
+) -> Result<(), String> {
+    let model_family = match model {
+        Some(model) if model.trim().is_empty() => {
+            return Err("model must not be empty".to_string());
+        }
+        Some(model) => find_family_for_model(model).unwrap_or_else(|| config.model_family.clone()),
+        None => prev.client.get_model_family(),
+    };
+
+    if let Some(Some(effort)) = effort
+        && !model_family.supports_reasoning_summaries
+    {
+        let model = model.map_or_else(|| prev.client.get_model(), str::to_string);
+        return Err(format!(
+            "model {model} does not support reasoning effort (requested {effort})"
+        ));
+    }
+
+    if let Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. }) = sandbox_policy
+        && let Some(root) = writable_roots.iter().find(|root| !root.is_absolute())
+    {
+        return Err(format!(
+            "sandbox writable root must be an absolute path: {}",
+            root.display()
+        ));
+    }
+
+    Ok(())
+}
+
 /// Takes a user message as input and runs a loop where, at each turn, the model
 /// replies with either:
 ///
@@ -1638,6 +3397,9 @@ pub(crate) async fn run_task(
     };
     sess.send_event(event).await;
 
+    if !turn_context.is_review_mode {
+        sess.state.lock().await.begin_turn(&sub_id, &input);
+    }
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
     // For review threads, keep an isolated in-memory history so the
     // model sees a fresh conversation without the parent session's history.
@@ -1657,9 +3419,32 @@ pub(crate) async fn run_task(
     // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
     // many turns, from the perspective of the user, it is a single turn.
     let mut turn_diff_tracker = TurnDiffTracker::new();
-    let mut auto_compact_recently_attempted = false;
+    let mut turn_stats = TurnStatsRecorder::start(&sub_id, &turn_context);
+    // Review threads do not write files, so there is nothing to undo.
+    let worktree_before_turn = if is_review_mode {
+        None
+    } else {
+        undo::snapshot_before_turn(&turn_context.cwd).await
+    };
+    if !is_review_mode
+        && let Some(git_state) = collect_turn_git_state(&sub_id, &turn_context.cwd).await
+    {
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::TurnGitState(git_state),
+        })
+        .await;
+    }
+    let mut auto_compact_attempts = 0;
 
     loop {
+        if !sess
+            .check_session_budget(&sub_id, turn_context.as_ref(), &turn_stats)
+            .await
+        {
+            break;
+        }
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1687,7 +3472,8 @@ pub(crate) async fn run_task(
             review_thread_history.clone()
         } else {
             sess.record_conversation_items(&pending_input).await;
-            sess.turn_input_with_history(pending_input).await
+            let input = sess.turn_input_with_history(pending_input).await;
+            sess.with_pinned_context(input).await
         };
 
         let turn_input_messages: Vec<String> = turn_input
@@ -1703,12 +3489,43 @@ pub(crate) async fn run_task(
                 })
             })
             .collect();
+        let prompt = build_turn_prompt(&sess, turn_context.as_ref(), turn_input).await;
+        let budget = context_budget(turn_context.as_ref(), &prompt);
+        let estimated_prompt_tokens = budget.estimated_prompt_tokens;
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::ContextBudget(budget),
+        })
+        .await;
+        // Compact before sending a prompt that would not fit, rather than
+        // letting the provider reject it. Review threads keep their own
+        // history, which compaction does not touch.
+        if !is_review_mode
+            && let Some(limit) = prompt_token_limit(turn_context.as_ref())
+            && estimated_prompt_tokens as i64 >= limit
+        {
+            if !auto_compact(
+                &sess,
+                &turn_context,
+                &sub_id,
+                &mut auto_compact_attempts,
+                limit,
+                Some(estimated_prompt_tokens),
+            )
+            .await
+            {
+                break;
+            }
+            continue;
+        }
+
+        let request_started = Instant::now();
         match run_turn(
             &sess,
             turn_context.as_ref(),
             &mut turn_diff_tracker,
             sub_id.clone(),
-            turn_input,
+            prompt,
         )
         .await
         {
@@ -1716,7 +3533,13 @@ pub(crate) async fn run_task(
                 let TurnRunResult {
                     processed_items,
                     total_token_usage,
+                    tool_time,
                 } = turn_output;
+                turn_stats.record_model_request(
+                    request_started.elapsed(),
+                    &tool_time,
+                    total_token_usage.as_ref(),
+                );
                 let limit = turn_context
                     .client
                     .get_auto_compact_token_limit()
@@ -1832,28 +3655,22 @@ pub(crate) async fn run_task(
                 }
 
                 if token_limit_reached {
-                    if auto_compact_recently_attempted {
-                        let limit_str = limit.to_string();
-                        let current_tokens = total_usage_tokens
-                            .map(|tokens| tokens.to_string())
-                            .unwrap_or_else(|| "unknown".to_string());
-                        let event = Event {
-                            id: sub_id.clone(),
-                            msg: EventMsg::Error(ErrorEvent {
-                                message: format!(
-                                    "Conversation is still above the token limit after automatic summarization (limit {limit_str}, current {current_tokens}). Please start a new session or trim your input."
-                                ),
-                            }),
-                        };
-                        sess.send_event(event).await;
+                    if !auto_compact(
+                        &sess,
+                        &turn_context,
+                        &sub_id,
+                        &mut auto_compact_attempts,
+                        limit,
+                        total_usage_tokens,
+                    )
+                    .await
+                    {
                         break;
                     }
-                    auto_compact_recently_attempted = true;
-                    compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone()).await;
                     continue;
                 }
 
-                auto_compact_recently_attempted = false;
+                auto_compact_attempts = 0;
 
                 if responses.is_empty() {
                     last_agent_message = get_last_assistant_message_from_turn(
@@ -1871,6 +3688,11 @@ pub(crate) async fn run_task(
             }
             Err(e) => {
                 info!("Turn error: {e:#}");
+                turn_stats.record_model_request(
+                    request_started.elapsed(),
+                    &ToolTime::default(),
+                    None,
+                );
                 let event = Event {
                     id: sub_id.clone(),
                     msg: EventMsg::Error(ErrorEvent {
@@ -1884,6 +3706,26 @@ pub(crate) async fn run_task(
         }
     }
 
+    sess.record_turn_stats(&sub_id, turn_context.as_ref(), turn_stats.finish())
+        .await;
+
+    if !is_review_mode {
+        let log =
+            TurnFileLog::collect(&sub_id, &turn_diff_tracker, worktree_before_turn.as_ref()).await;
+        if !log.is_empty() {
+            sess.state.lock().await.push_turn_file_log(log);
+        }
+        let workspace =
+            capture_workspace_state(&turn_context.cwd, &sess.services.background_process_manager)
+                .await;
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::WorkspaceState(workspace),
+        })
+        .await;
+        sess.spawn_title_generation(&sub_id, &turn_context).await;
+    }
+
     // If this was a review thread and we have a final assistant message,
     // try to parse it as a ReviewOutput.
     //
@@ -1928,25 +3770,94 @@ fn parse_review_output_event(text: &str) -> ReviewOutputEvent {
     }
 }
 
-async fn run_turn(
+/// The prompt of a model request with `input` as the conversation.
+async fn build_turn_prompt(
     sess: &Session,
     turn_context: &TurnContext,
-    turn_diff_tracker: &mut TurnDiffTracker,
-    sub_id: String,
     input: Vec<ResponseItem>,
-) -> CodexResult<TurnRunResult> {
-    let tools = get_openai_tools(
-        &turn_context.tools_config,
-        Some(sess.services.mcp_connection_manager.list_all_tools()),
-    );
+) -> Prompt {
+    let mcp_tools = sess
+        .services
+        .mcp_connection_manager
+        .read()
+        .await
+        .list_all_tools();
+    let mut tools = get_openai_tools(&turn_context.tools_config, Some(mcp_tools));
+    add_custom_tools(&mut tools, &turn_context.client.get_config().custom_tools);
+    add_plugin_tools(&mut tools, &sess.services.plugin_manager);
+    filter_tools(&mut tools, &turn_context.client.get_config().tool_filter);
 
-    let prompt = Prompt {
+    Prompt {
         input,
         tools,
         base_instructions_override: turn_context.base_instructions.clone(),
         output_schema: turn_context.final_output_json_schema.clone(),
+    }
+}
+
+/// The estimated size of `prompt` against the model's context window.
+fn context_budget(turn_context: &TurnContext, prompt: &Prompt) -> ContextBudgetEvent {
+    let estimated_prompt_tokens =
+        estimate_prompt_tokens(prompt, &turn_context.client.get_model_family());
+    let model_context_window = turn_context.client.get_model_context_window();
+    ContextBudgetEvent {
+        estimated_prompt_tokens,
+        model_context_window,
+        remaining_tokens: model_context_window
+            .map(|window| window.saturating_sub(estimated_prompt_tokens)),
+    }
+}
+
+/// Tokens a prompt may take before the conversation is compacted ahead of
+/// sending it. The auto-compaction limit is checked against the usage the
+/// provider reports instead; this only guards the context window itself.
+fn prompt_token_limit(turn_context: &TurnContext) -> Option<i64> {
+    turn_context
+        .client
+        .get_model_context_window()
+        .map(|window| (window * PROMPT_CONTEXT_WINDOW_PERCENT / 100) as i64)
+}
+
+/// Compact the conversation of the running turn because it has grown past
+/// `limit` tokens, escalating the strategy on consecutive attempts. Returns
+/// `false`, after reporting the error, when compaction has been exhausted.
+async fn auto_compact(
+    sess: &Arc<Session>,
+    turn_context: &Arc<TurnContext>,
+    sub_id: &str,
+    attempts: &mut usize,
+    limit: i64,
+    current_tokens: Option<u64>,
+) -> bool {
+    let configured = turn_context.client.get_auto_compaction().strategy;
+    let Some(strategy) = compact::auto_compact_strategy(configured, *attempts) else {
+        let limit_str = limit.to_string();
+        let current_tokens = current_tokens
+            .map(|tokens| tokens.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let event = Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::Error(ErrorEvent {
+                message: format!(
+                    "Conversation is still above the token limit after automatic summarization (limit {limit_str}, current {current_tokens}). Please start a new session or trim your input."
+                ),
+            }),
+        };
+        sess.send_event(event).await;
+        return false;
     };
+    *attempts += 1;
+    compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone(), strategy).await;
+    true
+}
 
+async fn run_turn(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    prompt: Prompt,
+) -> CodexResult<TurnRunResult> {
     let mut retries = 0;
     loop {
         match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt).await {
@@ -1961,6 +3872,7 @@ async fn run_turn(
                 return Err(CodexErr::UsageLimitReached(e));
             }
             Err(CodexErr::UsageNotIncluded) => return Err(CodexErr::UsageNotIncluded),
+            Err(e @ CodexErr::ResponseNotCached(_)) => return Err(e),
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
                 let max_retries = turn_context.client.get_provider().stream_max_retries();
@@ -2008,6 +3920,7 @@ struct ProcessedResponseItem {
 struct TurnRunResult {
     processed_items: Vec<ProcessedResponseItem>,
     total_token_usage: Option<TokenUsage>,
+    tool_time: ToolTime,
 }
 
 async fn try_run_turn(
@@ -2084,6 +3997,16 @@ async fn try_run_turn(
     let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
     let mut output = Vec::new();
+    // Indices into `output` of the calls from the first exec or
+    // `delegate_task` call on, held back until the response completes so
+    // that the approvals of its exec calls can be requested together and its
+    // `delegate_task` calls can run side by side before any of the calls runs.
+    let mut queued_calls = Vec::new();
+    // Indices into `output` of read-only tool calls made before any call was
+    // queued, which run side by side before the next tool call or once the
+    // response completes.
+    let mut read_only_calls = Vec::new();
+    let mut tool_time = ToolTime::default();
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
@@ -2111,15 +4034,43 @@ async fn try_run_turn(
         match event {
             ResponseEvent::Created => {}
             ResponseEvent::OutputItemDone(item) => {
-                let response = handle_response_item(
-                    sess,
-                    turn_context,
-                    turn_diff_tracker,
-                    sub_id,
-                    item.clone(),
-                )
-                .await?;
-                output.push(ProcessedResponseItem { item, response });
+                if is_tool_call(&item)
+                    && (!queued_calls.is_empty()
+                        || is_delegate_task_call(&item)
+                        || sess.services.approval_batching && is_batchable_exec_call(&item))
+                {
+                    queued_calls.push(output.len());
+                    output.push(ProcessedResponseItem {
+                        item,
+                        response: None,
+                    });
+                } else if is_read_only_tool_call(&item) {
+                    read_only_calls.push(output.len());
+                    output.push(ProcessedResponseItem {
+                        item,
+                        response: None,
+                    });
+                } else {
+                    if is_tool_call(&item) && !read_only_calls.is_empty() {
+                        let started = Instant::now();
+                        let calls = std::mem::take(&mut read_only_calls);
+                        run_concurrently(sess, turn_context, sub_id, &mut output, &calls).await?;
+                        tool_time.record(calls.len() as u32, started.elapsed());
+                    }
+                    let started = Instant::now();
+                    let response = handle_response_item(
+                        sess,
+                        turn_context,
+                        turn_diff_tracker,
+                        sub_id,
+                        item.clone(),
+                    )
+                    .await?;
+                    if response.is_some() {
+                        tool_time.record(1, started.elapsed());
+                    }
+                    output.push(ProcessedResponseItem { item, response });
+                }
             }
             ResponseEvent::WebSearchCallBegin { call_id } => {
                 let _ = sess
@@ -2130,6 +4081,36 @@ async fn try_run_turn(
                     })
                     .await;
             }
+            ResponseEvent::ToolCallArgumentsDelta {
+                call_id,
+                name,
+                delta,
+            } => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::ToolCallArgumentsDelta(ToolCallArgumentsDeltaEvent {
+                        call_id,
+                        tool_name: name,
+                        delta,
+                    }),
+                };
+                sess.send_event(event).await;
+            }
+            ResponseEvent::ModelFailover {
+                model,
+                model_provider,
+                reason,
+            } => {
+                let event = Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::ModelFailover(ModelFailoverEvent {
+                        model,
+                        model_provider,
+                        reason,
+                    }),
+                };
+                sess.send_event(event).await;
+            }
             ResponseEvent::RateLimits(snapshot) => {
                 // Update internal state with latest rate limits, but defer sending until
                 // token usage is available to avoid duplicate TokenCount events.
@@ -2139,11 +4120,63 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
+                if !read_only_calls.is_empty() {
+                    let started = Instant::now();
+                    let calls = std::mem::take(&mut read_only_calls);
+                    run_concurrently(sess, turn_context, sub_id, &mut output, &calls).await?;
+                    tool_time.record(calls.len() as u32, started.elapsed());
+                }
+
+                if !queued_calls.is_empty() {
+                    let started = Instant::now();
+                    let calls = queued_calls.len() as u32;
+                    let exec_calls = queued_calls
+                        .iter()
+                        .map(|&idx| output[idx].item.clone())
+                        .filter(is_batchable_exec_call)
+                        .collect::<Vec<_>>();
+                    request_batched_approvals(sess, turn_context, sub_id, &exec_calls).await;
+                    // The calls then run in the order the model made them,
+                    // except that consecutive `delegate_task` and read-only
+                    // calls run side by side.
+                    let queued = std::mem::take(&mut queued_calls)
+                        .into_iter()
+                        .map(|idx| {
+                            let item = &output[idx].item;
+                            (
+                                idx,
+                                is_delegate_task_call(item) || is_read_only_tool_call(item),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    for run in queued.chunk_by(|a, b| a.1 && b.1) {
+                        if run[0].1 {
+                            let indices = run.iter().map(|&(idx, _)| idx).collect::<Vec<_>>();
+                            run_concurrently(sess, turn_context, sub_id, &mut output, &indices)
+                                .await?;
+                            continue;
+                        }
+                        let (idx, _) = run[0];
+                        let item = output[idx].item.clone();
+                        output[idx].response = handle_response_item(
+                            sess,
+                            turn_context,
+                            turn_diff_tracker,
+                            sub_id,
+                            item,
+                        )
+                        .await?;
+                    }
+                    tool_time.record(calls, started.elapsed());
+                }
+
                 sess.update_token_usage_info(sub_id, turn_context, token_usage.as_ref())
                     .await;
 
                 let unified_diff = turn_diff_tracker.get_unified_diff();
-                if let Ok(Some(unified_diff)) = unified_diff {
+                if sess.services.client_capabilities.show_diffs
+                    && let Ok(Some(unified_diff)) = unified_diff
+                {
                     let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
                     let event = Event {
                         id: sub_id.to_string(),
@@ -2155,6 +4188,7 @@ async fn try_run_turn(
                 let result = TurnRunResult {
                     processed_items: output,
                     total_token_usage: token_usage.clone(),
+                    tool_time,
                 };
 
                 return Ok(result);
@@ -2201,6 +4235,152 @@ async fn try_run_turn(
     }
 }
 
+fn is_tool_call(item: &ResponseItem) -> bool {
+    matches!(
+        item,
+        ResponseItem::FunctionCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+            | ResponseItem::LocalShellCall { .. }
+    )
+}
+
+/// Exec calls whose approval may be requested as part of a batch.
+fn is_batchable_exec_call(item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::FunctionCall { name, .. } => {
+            matches!(name.as_str(), "container.exec" | "shell")
+        }
+        ResponseItem::LocalShellCall { .. } => true,
+        _ => false,
+    }
+}
+
+fn is_delegate_task_call(item: &ResponseItem) -> bool {
+    matches!(item, ResponseItem::FunctionCall { name, .. } if name == DELEGATE_TASK_TOOL_NAME)
+}
+
+/// Calls of tools that neither change the workspace nor ask for approval,
+/// which may run side by side when the model calls several at once.
+fn is_read_only_tool_call(item: &ResponseItem) -> bool {
+    let ResponseItem::FunctionCall { name, .. } = item else {
+        return false;
+    };
+    matches!(
+        name.as_str(),
+        READ_FILE_TOOL_NAME
+            | CODE_SEARCH_TOOL_NAME
+            | CODE_SYMBOLS_TOOL_NAME
+            | SYSTEM_INFO_TOOL_NAME
+            | LIST_PORTS_TOOL_NAME
+    )
+}
+
+/// Run the calls at `indices` of `output` side by side and record their
+/// responses. None of them edits files through the turn's diff tracker (a
+/// sub-agent has its own), so each call gets a tracker of its own.
+async fn run_concurrently(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    output: &mut [ProcessedResponseItem],
+    indices: &[usize],
+) -> CodexResult<()> {
+    let responses = join_all(indices.iter().map(|&idx| {
+        let item = output[idx].item.clone();
+        async move {
+            let mut tracker = TurnDiffTracker::new();
+            handle_response_item(sess, turn_context, &mut tracker, sub_id, item).await
+        }
+    }))
+    .await;
+    for (&idx, response) in indices.iter().zip(responses) {
+        output[idx].response = response?;
+    }
+    Ok(())
+}
+
+/// When more than one of the queued exec `items` needs user approval, ask
+/// for all of them with a single `ApprovalBatchRequest` before any runs.
+/// Calls that do not need approval, or that turn out to be `apply_patch`
+/// invocations, are left to the regular per-call flow.
+async fn request_batched_approvals(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    items: &[ResponseItem],
+) {
+    let mut pending = Vec::new();
+    for item in items {
+        let (call_id, params) = match item {
+            ResponseItem::FunctionCall {
+                arguments, call_id, ..
+            } => {
+                let Ok(params) =
+                    parse_container_exec_arguments(arguments.clone(), turn_context, call_id)
+                else {
+                    continue;
+                };
+                (call_id.clone(), params)
+            }
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                status: _,
+                action,
+            } => {
+                let Some(call_id) = call_id.clone().or_else(|| id.clone()) else {
+                    continue;
+                };
+                let LocalShellAction::Exec(action) = action;
+                let params = ShellToolCallParams {
+                    command: action.command.clone(),
+                    workdir: action.working_directory.clone(),
+                    timeout_ms: action.timeout_ms,
+                    with_escalated_permissions: None,
+                    justification: None,
+                };
+                (call_id, to_exec_params(params, turn_context))
+            }
+            _ => continue,
+        };
+
+        let escalated = params.with_escalated_permissions.unwrap_or(false);
+        if escalated && !matches!(turn_context.approval_policy, AskForApproval::OnRequest) {
+            continue;
+        }
+        if !matches!(
+            maybe_parse_apply_patch_verified(&params.command, &params.cwd),
+            MaybeApplyPatchVerified::NotApplyPatch | MaybeApplyPatchVerified::ShellParseError(_)
+        ) {
+            continue;
+        }
+
+        let safety = {
+            let state = sess.state.lock().await;
+            assess_command_safety(
+                &params.command,
+                turn_context.approval_policy,
+                &turn_context.sandbox_policy,
+                state.approved_commands_ref(),
+                escalated,
+            )
+        };
+        if safety == SafetyCheck::AskUser {
+            pending.push(ExecApprovalRequestEvent {
+                call_id,
+                command: params.command,
+                cwd: params.cwd,
+                reason: params.justification,
+            });
+        }
+    }
+
+    if pending.len() > 1 {
+        sess.request_batch_approval(sub_id.to_string(), pending)
+            .await;
+    }
+}
+
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2209,6 +4389,11 @@ async fn handle_response_item(
     item: ResponseItem,
 ) -> CodexResult<Option<ResponseInputItem>> {
     debug!(?item, "Output item");
+    if let Some(replay) = &sess.services.replay
+        && let Some(output) = replay.respond(&item)
+    {
+        return Ok(Some(output));
+    }
     let output = match item {
         ResponseItem::FunctionCall {
             name,
@@ -2217,32 +4402,63 @@ async fn handle_response_item(
             ..
         } => {
             info!("FunctionCall: {name}({arguments})");
-            if let Some((server, tool_name)) =
-                sess.services.mcp_connection_manager.parse_tool_name(&name)
+            if let Some(content) =
+                refuse_tool_call(sess, turn_context, sub_id, &name, &arguments).await
             {
-                let resp = handle_mcp_tool_call(
+                return Ok(Some(ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content,
+                        success: Some(false),
+                    },
+                }));
+            }
+            // Built-in tools win over MCP tools named like them; see
+            // `get_openai_tools`.
+            let mcp_tool = if is_builtin_tool(&turn_context.tools_config, &name) {
+                None
+            } else {
+                sess.services
+                    .mcp_connection_manager
+                    .read()
+                    .await
+                    .parse_tool_name(&name)
+            };
+            let resp = if let Some((server, tool_name)) = mcp_tool {
+                // MCP arguments belong to the server, so only the configured
+                // limit applies.
+                let timeout =
+                    configured_timeout(&turn_context.client.get_config().tool_timeouts, &name);
+                handle_mcp_tool_call(
                     sess,
+                    turn_context,
                     sub_id,
                     call_id.clone(),
                     server,
                     tool_name,
                     arguments,
+                    timeout,
                 )
-                .await;
-                Some(resp)
+                .await
             } else {
                 let result = turn_context
                     .client
                     .get_otel_event_manager()
                     .log_tool_result(name.as_str(), call_id.as_str(), arguments.as_str(), || {
-                        handle_function_call(
+                        run_with_timeout(
                             sess,
                             turn_context,
-                            turn_diff_tracker,
-                            sub_id.to_string(),
-                            name.to_owned(),
-                            arguments.to_owned(),
-                            call_id.clone(),
+                            &name,
+                            &arguments,
+                            handle_function_call(
+                                sess,
+                                turn_context,
+                                turn_diff_tracker,
+                                sub_id.to_string(),
+                                name.to_owned(),
+                                arguments.to_owned(),
+                                call_id.clone(),
+                            ),
                         )
                     })
                     .await;
@@ -2257,8 +4473,10 @@ async fn handle_response_item(
                         success: Some(false),
                     },
                 };
-                Some(ResponseInputItem::FunctionCallOutput { call_id, output })
-            }
+                ResponseInputItem::FunctionCallOutput { call_id, output }
+            };
+            sess.record_tool_output(&name, &resp).await;
+            Some(resp)
         }
         ResponseItem::LocalShellCall {
             id,
@@ -2269,6 +4487,18 @@ async fn handle_response_item(
             let name = "local_shell";
             let LocalShellAction::Exec(action) = action;
             tracing::info!("LocalShellCall: {action:?}");
+            let arguments = serde_json::to_string(&action.command).unwrap_or_default();
+            if let Some(content) =
+                refuse_tool_call(sess, turn_context, sub_id, name, &arguments).await
+            {
+                return Ok(Some(ResponseInputItem::FunctionCallOutput {
+                    call_id: call_id.or(id).unwrap_or_default(),
+                    output: FunctionCallOutputPayload {
+                        content,
+                        success: Some(false),
+                    },
+                }));
+            }
             let params = ShellToolCallParams {
                 command: action.command,
                 workdir: action.working_directory,
@@ -2331,10 +4561,12 @@ async fn handle_response_item(
                         success: Some(false),
                     },
                 };
-                Some(ResponseInputItem::FunctionCallOutput {
+                let resp = ResponseInputItem::FunctionCallOutput {
                     call_id: effective_call_id,
                     output,
-                })
+                };
+                sess.record_tool_output(name, &resp).await;
+                Some(resp)
             }
         }
         ResponseItem::CustomToolCall {
@@ -2344,18 +4576,31 @@ async fn handle_response_item(
             input,
             status: _,
         } => {
+            if let Some(output) = refuse_tool_call(sess, turn_context, sub_id, &name, &input).await
+            {
+                return Ok(Some(ResponseInputItem::CustomToolCallOutput {
+                    call_id,
+                    output,
+                }));
+            }
             let result = turn_context
                 .client
                 .get_otel_event_manager()
                 .log_tool_result(name.as_str(), call_id.as_str(), input.as_str(), || {
-                    handle_custom_tool_call(
+                    run_with_timeout(
                         sess,
                         turn_context,
-                        turn_diff_tracker,
-                        sub_id.to_string(),
-                        name.to_owned(),
-                        input.to_owned(),
-                        call_id.clone(),
+                        &name,
+                        &input,
+                        handle_custom_tool_call(
+                            sess,
+                            turn_context,
+                            turn_diff_tracker,
+                            sub_id.to_string(),
+                            name.to_owned(),
+                            input.to_owned(),
+                            call_id.clone(),
+                        ),
                     )
                 })
                 .await;
@@ -2364,7 +4609,9 @@ async fn handle_response_item(
                 Ok(content) => content,
                 Err(FunctionCallError::RespondToModel(msg)) => msg,
             };
-            Some(ResponseInputItem::CustomToolCallOutput { call_id, output })
+            let resp = ResponseInputItem::CustomToolCallOutput { call_id, output };
+            sess.record_tool_output(&name, &resp).await;
+            Some(resp)
         }
         ResponseItem::FunctionCallOutput { .. } => {
             debug!("unexpected FunctionCallOutput from stream");
@@ -2451,6 +4698,89 @@ async fn handle_unified_exec_tool_call(
     })
 }
 
+/// Run a tool call under its time limit, if it has one. A call that runs out
+/// of time is dropped, and what it may have left running is released: the
+/// background processes it started, and the kernel or browser of the
+/// `python` and `browser` tools.
+async fn run_with_timeout(
+    sess: &Session,
+    turn_context: &TurnContext,
+    name: &str,
+    arguments: &str,
+    call: impl Future<Output = Result<String, FunctionCallError>>,
+) -> Result<String, FunctionCallError> {
+    let config = turn_context.client.get_config();
+    let Some(limit) = call_timeout(&config.tool_timeouts, name, arguments) else {
+        return call.await;
+    };
+    let background_before: HashSet<String> = sess
+        .background_processes()
+        .list()
+        .await
+        .into_iter()
+        .map(|summary| summary.id)
+        .collect();
+    let Ok(result) = tokio::time::timeout(limit, call).await else {
+        let mut cleaned_up = Vec::new();
+        for summary in sess.background_processes().list().await {
+            if !background_before.contains(&summary.id)
+                && summary.state == BackgroundProcessState::Running
+                && sess.background_processes().kill(&summary.id).await.is_ok()
+            {
+                cleaned_up.push(format!("killed background process {}", summary.id));
+            }
+        }
+        match name {
+            PYTHON_TOOL_NAME if sess.services.python_kernel_manager.close().await => {
+                cleaned_up.push("stopped the python kernel; its state is lost".to_string());
+            }
+            BROWSER_TOOL_NAME if sess.services.browser_manager.close().await => {
+                cleaned_up.push("closed the browser".to_string());
+            }
+            _ => {}
+        }
+        return Err(FunctionCallError::RespondToModel(timed_out(
+            name,
+            limit,
+            &cleaned_up,
+        )));
+    };
+    result
+}
+
+/// The reply to a call of a tool the user's tool set or `disabled_tools` rule
+/// out, which the model was not offered but called anyway.
+fn disabled_tool_refusal(turn_context: &TurnContext, name: &str) -> Option<String> {
+    (!turn_context.client.get_config().tool_filter.allows(name)).then(|| {
+        format!("tool `{name}` is disabled in this session; use one of the tools you were given")
+    })
+}
+
+/// The reply to a tool call that must not run: one of a disabled tool (see
+/// [`disabled_tool_refusal`]) or one beyond the tool's quota. Other calls are
+/// counted against the quota.
+async fn refuse_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    name: &str,
+    arguments: &str,
+) -> Option<String> {
+    if let Some(refusal) = disabled_tool_refusal(turn_context, name) {
+        return Some(refusal);
+    }
+    let config = turn_context.client.get_config();
+    if config.tool_quotas.is_empty() {
+        return None;
+    }
+    sess.state
+        .lock()
+        .await
+        .tool_usage
+        .begin_call(&config.tool_quotas, sub_id, name, arguments)
+        .err()
+}
+
 async fn handle_function_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2460,6 +4790,64 @@ async fn handle_function_call(
     arguments: String,
     call_id: String,
 ) -> Result<String, FunctionCallError> {
+    let config = turn_context.client.get_config();
+    let custom_tool = config.custom_tools.get(&name);
+    let plugin_tool = sess.services.plugin_manager.get(&name);
+    if (custom_tool.is_some() || plugin_tool.is_some())
+        && !is_builtin_tool(&turn_context.tools_config, &name)
+    {
+        if let Some(tool) = custom_tool {
+            return handle_custom_command_call(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                &name,
+                tool,
+                &arguments,
+            )
+            .await;
+        }
+        if let Some(tool) = plugin_tool {
+            return tool
+                .invoke(&name, arguments)
+                .await
+                .map_err(FunctionCallError::RespondToModel);
+        }
+    }
+    let output = handle_builtin_function_call(
+        sess,
+        turn_context,
+        turn_diff_tracker,
+        sub_id,
+        name.clone(),
+        arguments,
+        call_id,
+    )
+    .await?;
+    match builtin_output_schema(&name) {
+        Some(schema) => {
+            check_output(&name, &schema, output).map_err(FunctionCallError::RespondToModel)
+        }
+        None => Ok(output),
+    }
+}
+
+async fn handle_builtin_function_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    name: String,
+    arguments: String,
+    call_id: String,
+) -> Result<String, FunctionCallError> {
+    // Checked again here so that no path into the built-in tools can run a
+    // disabled one.
+    if let Some(refusal) = disabled_tool_refusal(turn_context, &name) {
+        return Err(FunctionCallError::RespondToModel(refusal));
+    }
     match name.as_str() {
         "container.exec" | "shell" => {
             let params = parse_container_exec_arguments(arguments, turn_context, &call_id)?;
@@ -2503,13 +4891,23 @@ async fn handle_function_call(
                 ))
             })?;
             let abs = turn_context.resolve_path(Some(args.path));
-            sess.inject_input(vec![InputItem::LocalImage { path: abs.clone() }])
+            let limits = turn_context.client.get_config().view_image.clone();
+            let path = abs.clone();
+            let prepared = tokio::task::spawn_blocking(move || prepare_image(&path, &limits))
                 .await
-                .map_err(|_| {
-                    FunctionCallError::RespondToModel(
-                        "unable to attach image (no active task)".to_string(),
-                    )
-                })?;
+                .map_err(|e| {
+                    FunctionCallError::RespondToModel(format!("failed to load image: {e}"))
+                })?
+                .map_err(FunctionCallError::RespondToModel)?;
+            sess.inject_input(vec![InputItem::Image {
+                image_url: prepared.image_url,
+            }])
+            .await
+            .map_err(|_| {
+                FunctionCallError::RespondToModel(
+                    "unable to attach image (no active task)".to_string(),
+                )
+            })?;
             sess.send_event(Event {
                 id: sub_id.clone(),
                 msg: EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
@@ -2519,7 +4917,7 @@ async fn handle_function_call(
             })
             .await;
 
-            Ok("attached local image path".to_string())
+            Ok(format!("attached image: {}", prepared.summary))
         }
         "apply_patch" => {
             let args: ApplyPatchToolArgs = serde_json::from_str(&arguments).map_err(|e| {
@@ -2547,10 +4945,156 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        DELEGATE_TASK_TOOL_NAME => {
+            let args: DelegateTaskArgs = serde_json::from_str(&arguments).map_err(|e| {
+                FunctionCallError::RespondToModel(format!(
+                    "failed to parse function arguments: {e:?}"
+                ))
+            })?;
+            handle_delegate_task(sess, turn_context, sub_id, call_id, args).await
+        }
         "background_process" => {
             handle_background_process_tool_call(sess, turn_context, sub_id, call_id, arguments)
                 .await
         }
+        MEMORY_TOOL_NAME => {
+            let codex_home = &turn_context.client.get_config().codex_home;
+            handle_memory_tool(codex_home, &turn_context.cwd, &arguments).await
+        }
+        WEB_FETCH_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            handle_web_fetch_tool(&config.web_fetch, &turn_context.sandbox_policy, &arguments).await
+        }
+        CODE_SEARCH_TOOL_NAME => {
+            handle_code_search_tool(&turn_context.cwd, &turn_context.sandbox_policy, &arguments)
+                .await
+        }
+        SYSTEM_INFO_TOOL_NAME => handle_system_info_tool(&turn_context.cwd).await,
+        EXTRACT_ARCHIVE_TOOL_NAME => {
+            handle_extract_archive_tool_call(sess, turn_context, sub_id, call_id, &arguments).await
+        }
+        MCP_RESOURCES_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            let manager = sess.services.mcp_connection_manager.read().await;
+            handle_mcp_resources_tool(&manager, &config.mcp_resources, &arguments).await
+        }
+        LIST_PORTS_TOOL_NAME => {
+            let background = sess.background_processes().list().await;
+            handle_list_ports_tool(&background, &arguments).await
+        }
+        SCAN_SECRETS_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            handle_scan_secrets_tool(&config.secret_scan, &turn_context.cwd, &arguments).await
+        }
+        REVIEW_CHANGES_TOOL_NAME => {
+            // Include the files the running turn has patched so far.
+            let mut changes = sess.state.lock().await.session_changes.clone();
+            changes.record(turn_diff_tracker.original_contents());
+            handle_review_changes_tool(&changes, &turn_context.cwd, &arguments).await
+        }
+        AUDIT_DEPENDENCIES_TOOL_NAME => {
+            handle_audit_dependencies_tool(
+                &turn_context.cwd,
+                &turn_context.sandbox_policy,
+                &arguments,
+            )
+            .await
+        }
+        CODE_SYMBOLS_TOOL_NAME => {
+            handle_code_symbols_tool(&turn_context.cwd, &turn_context.sandbox_policy, &arguments)
+                .await
+        }
+        BROWSER_TOOL_NAME => {
+            handle_browser_tool_call(sess, turn_context, sub_id, call_id, &arguments).await
+        }
+        PYTHON_TOOL_NAME => {
+            handle_python_tool_call(sess, turn_context, sub_id, call_id, &arguments).await
+        }
+        RUN_TESTS_TOOL_NAME => {
+            handle_run_tests_tool_call(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                &arguments,
+            )
+            .await
+        }
+        LINT_TOOL_NAME => {
+            handle_lint_tool_call(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                &arguments,
+            )
+            .await
+        }
+        HTTP_REQUEST_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            handle_http_request_tool(
+                &config.http_request,
+                &turn_context.sandbox_policy,
+                &arguments,
+            )
+            .await
+        }
+        DATABASE_QUERY_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            handle_database_query_tool(
+                &config.databases,
+                &turn_context.cwd,
+                &turn_context.sandbox_policy,
+                &arguments,
+            )
+            .await
+        }
+        GIT_TOOL_NAME => {
+            handle_git_tool_call(sess, turn_context, sub_id, call_id, &arguments).await
+        }
+        BATCH_EDIT_TOOL_NAME => {
+            handle_batch_edit(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                &arguments,
+            )
+            .await
+        }
+        WRITE_FILE_TOOL_NAME => {
+            handle_write_file(
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
+                &arguments,
+            )
+            .await
+        }
+        READ_FILE_TOOL_NAME => {
+            handle_read_file_tool(&turn_context.cwd, &turn_context.sandbox_policy, &arguments).await
+        }
+        WEB_SEARCH_TOOL_NAME => {
+            let config = turn_context.client.get_config();
+            handle_web_search_tool(&config.web_search, &turn_context.sandbox_policy, &arguments)
+                .await
+        }
+        PIN_CONTEXT_TOOL_NAME => {
+            let result = handle_pin_context_tool(
+                &mut sess.state.lock().await.pinned_context,
+                &turn_context.cwd,
+                &arguments,
+            );
+            if result.is_ok() {
+                sess.send_pinned_context(&sub_id).await;
+            }
+            result
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params: ExecCommandParams = serde_json::from_str(&arguments).map_err(|e| {
@@ -2635,6 +5179,68 @@ async fn handle_custom_tool_call(
     }
 }
 
+async fn handle_delegate_task(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    args: DelegateTaskArgs,
+) -> Result<String, FunctionCallError> {
+    let Some(auth_manager) = turn_context.client.get_auth_manager() else {
+        return Err(FunctionCallError::RespondToModel(
+            "sub-agents are unavailable in this session".to_string(),
+        ));
+    };
+    let parent_config = turn_context.client.get_config();
+    let config = sub_agent_config(
+        &parent_config,
+        turn_context.cwd.clone(),
+        &turn_context.sandbox_policy,
+        args.allow_writes,
+    );
+    let budget = Budget::new(&parent_config, args.max_turns);
+
+    let agent = SubAgent::spawn(
+        config,
+        auth_manager,
+        sess.file_locks().clone(),
+        sess.conversation_id,
+    )
+    .await
+    .map_err(|e| FunctionCallError::RespondToModel(format!("failed to start sub-agent: {e}")))?;
+    let conversation_id = agent.conversation_id;
+    let rollout_path = agent.rollout_path.clone();
+    sess.send_event(Event {
+        id: sub_id.clone(),
+        msg: EventMsg::SubAgentBegin(SubAgentBeginEvent {
+            call_id: call_id.clone(),
+            conversation_id,
+            task: args.task.clone(),
+            rollout_path: rollout_path.clone(),
+        }),
+    })
+    .await;
+
+    let report = agent.run(args.task, budget).await;
+    let output = report.to_tool_output(&rollout_path);
+    sess.send_event(Event {
+        id: sub_id,
+        msg: EventMsg::SubAgentEnd(SubAgentEndEvent {
+            call_id,
+            conversation_id,
+            outcome: report.outcome,
+            message: report.message,
+            rollout_path,
+        }),
+    })
+    .await;
+
+    match report.outcome {
+        SubAgentOutcome::Failed => Err(FunctionCallError::RespondToModel(output)),
+        SubAgentOutcome::Completed | SubAgentOutcome::BudgetExhausted => Ok(output),
+    }
+}
+
 async fn handle_background_process_tool_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2645,7 +5251,18 @@ async fn handle_background_process_tool_call(
     let invocation: BackgroundProcessInvocation = serde_json::from_str(&input).map_err(|err| {
         FunctionCallError::RespondToModel(format!("failed to parse arguments: {err}"))
     })?;
+    run_background_process_action(sess, turn_context, sub_id, call_id, invocation).await
+}
 
+/// Run a `background_process` action for the model or, through
+/// `Op::BackgroundProcess`, for the client.
+async fn run_background_process_action(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    invocation: BackgroundProcessInvocation,
+) -> Result<String, FunctionCallError> {
     match invocation.action {
         BackgroundProcessAction::Start => {
             let command = invocation.command.ok_or_else(|| {
@@ -2670,8 +5287,11 @@ async fn handle_background_process_tool_call(
 
             let cwd = turn_context.resolve_path(invocation.cwd.clone());
             let mut env = create_env(&turn_context.shell_environment_policy);
+            let mut session_env = sess.state.lock().await.env.clone();
+            env.extend(session_env.clone());
             if let Some(custom_env) = invocation.env {
                 for (key, value) in custom_env {
+                    session_env.remove(&key);
                     env.insert(key, value);
                 }
             }
@@ -2686,8 +5306,13 @@ async fn handle_background_process_tool_call(
             };
 
             let exec_params = maybe_translate_shell_command(exec_params, sess, turn_context);
-            let exec_context =
-                make_exec_context_for_background(sub_id.clone(), call_id.clone(), command, cwd);
+            let exec_context = make_exec_context_for_background(
+                sub_id.clone(),
+                call_id.clone(),
+                command,
+                cwd,
+                session_env,
+            );
 
             let approved_snapshot = {
                 let state = sess.state.lock().await;
@@ -2730,6 +5355,7 @@ async fn handle_background_process_tool_call(
                         "command": summary.command,
                         "cwd": summary.cwd.display().to_string(),
                         "sandbox": format!("{:?}", summary.sandbox_type),
+                        "pid": summary.pid,
                         "started_at_ms": system_time_to_unix_millis(summary.started_at),
                         "state": background_state_to_json(&summary.state),
                     })
@@ -2765,77 +5391,779 @@ async fn handle_background_process_tool_call(
             }))
             .map_err(|err| FunctionCallError::RespondToModel(err.to_string()))
         }
-        BackgroundProcessAction::Kill => {
-            let process_id = invocation.process_id.ok_or_else(|| {
-                FunctionCallError::RespondToModel(
-                    "process_id is required when action is \"kill\"".to_string(),
-                )
-            })?;
+        BackgroundProcessAction::Kill => {
+            let process_id = invocation.process_id.ok_or_else(|| {
+                FunctionCallError::RespondToModel(
+                    "process_id is required when action is \"kill\"".to_string(),
+                )
+            })?;
+
+            sess.background_processes().kill(&process_id).await?;
+            sess.notify_background_event(
+                &sub_id,
+                format!("Requested termination for background process {process_id}"),
+            )
+            .await;
+
+            serde_json::to_string(&json!({
+                "status": "killed",
+                "process_id": process_id,
+            }))
+            .map_err(|err| FunctionCallError::RespondToModel(err.to_string()))
+        }
+    }
+}
+
+/// Runs one of the user's `[custom_tools]`, like a `shell` call of the
+/// command its template renders to.
+#[allow(clippy::too_many_arguments)]
+async fn handle_custom_command_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    name: &str,
+    tool: &CustomToolConfig,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args = match serde_json::from_str::<Value>(arguments) {
+        Ok(Value::Object(args)) => args,
+        Ok(_) => {
+            return Err(FunctionCallError::RespondToModel(
+                "function arguments must be a JSON object".to_string(),
+            ));
+        }
+        Err(e) => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "failed to parse function arguments: {e:?}"
+            )));
+        }
+    };
+    let command = crate::custom_tools::render_command(&tool.command, &args)
+        .map_err(FunctionCallError::RespondToModel)?;
+    let sandbox_policy =
+        crate::custom_tools::sandbox_policy(tool.sandbox_mode, &turn_context.sandbox_policy);
+    let tool_turn_context;
+    let turn_context = if sandbox_policy == turn_context.sandbox_policy {
+        turn_context
+    } else {
+        tool_turn_context = TurnContext {
+            client: turn_context.client.clone(),
+            cwd: turn_context.cwd.clone(),
+            base_instructions: turn_context.base_instructions.clone(),
+            user_instructions: turn_context.user_instructions.clone(),
+            approval_policy: turn_context.approval_policy,
+            sandbox_policy,
+            shell_environment_policy: turn_context.shell_environment_policy.clone(),
+            tools_config: turn_context.tools_config.clone(),
+            is_review_mode: turn_context.is_review_mode,
+            final_output_json_schema: turn_context.final_output_json_schema.clone(),
+        };
+        &tool_turn_context
+    };
+    let mut env = create_env(&turn_context.shell_environment_policy);
+    if let Some(section) = turn_context.client.get_config().extensions.section(name) {
+        env.insert(EXTENSION_CONFIG_ENV_VAR.to_string(), section.to_string());
+    }
+    let params = ExecParams {
+        command,
+        cwd: match &tool.cwd {
+            Some(cwd) => turn_context.cwd.join(cwd),
+            None => turn_context.cwd.clone(),
+        },
+        timeout_ms: tool.timeout_sec.map(|secs| secs.saturating_mul(1000)),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let content = handle_container_exec_with_params(
+        name,
+        params,
+        sess,
+        turn_context,
+        turn_diff_tracker,
+        sub_id,
+        call_id,
+    )
+    .await?;
+    let Some(schema) = &tool.output_schema else {
+        return Ok(content);
+    };
+    // The schema describes the command's output, which successful runs report
+    // under `output`.
+    let output = serde_json::from_str::<Value>(&content)
+        .ok()
+        .and_then(|payload| payload.get("output")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| content.clone());
+    match check_json_text(schema, &output) {
+        Ok(()) => Ok(content),
+        Err(violations) => Err(FunctionCallError::RespondToModel(invalid_output_error(
+            name,
+            &violations,
+            &output,
+        ))),
+    }
+}
+
+fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> ExecParams {
+    ExecParams {
+        command: params.command,
+        cwd: turn_context.resolve_path(params.workdir.clone()),
+        timeout_ms: params.timeout_ms,
+        env: create_env(&turn_context.shell_environment_policy),
+        with_escalated_permissions: params.with_escalated_permissions,
+        justification: params.justification,
+    }
+}
+
+fn parse_container_exec_arguments(
+    arguments: String,
+    turn_context: &TurnContext,
+    _call_id: &str,
+) -> Result<ExecParams, FunctionCallError> {
+    serde_json::from_str::<ShellToolCallParams>(&arguments)
+        .map(|p| to_exec_params(p, turn_context))
+        .map_err(|e| {
+            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+        })
+}
+
+pub struct ExecInvokeArgs<'a> {
+    pub params: ExecParams,
+    pub sandbox_type: SandboxType,
+    pub sandbox_policy: &'a SandboxPolicy,
+    pub sandbox_cwd: &'a Path,
+    pub codex_linux_sandbox_exe: &'a Option<PathBuf>,
+    pub stdout_stream: Option<StdoutStream>,
+}
+
+fn maybe_translate_shell_command(
+    params: ExecParams,
+    sess: &Session,
+    turn_context: &TurnContext,
+) -> ExecParams {
+    let should_translate = matches!(sess.user_shell(), crate::shell::Shell::PowerShell(_))
+        || turn_context.shell_environment_policy.use_profile;
+
+    if should_translate
+        && let Some(command) = sess
+            .user_shell()
+            .format_default_shell_invocation(params.command.clone())
+    {
+        return ExecParams { command, ..params };
+    }
+    params
+}
+
+/// Handle a `git` call, asking the user first when the operation needs
+/// approval.
+async fn handle_git_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args = parse_git_tool_arguments(arguments)?;
+    if matches!(args, GitToolArgs::Commit { .. }) {
+        check_commit_for_secrets(
+            sess,
+            turn_context,
+            sub_id.clone(),
+            call_id.clone(),
+            args.command_for_display(),
+            &turn_context.cwd,
+        )
+        .await?;
+    }
+    if let Some(reason) = args.approval_reason(&turn_context.sandbox_policy, &turn_context.cwd) {
+        if turn_context.approval_policy == AskForApproval::Never {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{reason}, which needs approval; the approval policy does not allow asking for it"
+            )));
+        }
+        let decision = sess
+            .request_command_approval(
+                sub_id,
+                call_id,
+                args.command_for_display(),
+                turn_context.cwd.clone(),
+                Some(reason),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(FunctionCallError::RespondToModel(
+                    "git operation rejected by user".to_string(),
+                ));
+            }
+        }
+    }
+    handle_git_tool(&turn_context.cwd, args).await
+}
+
+/// Scan the staged changes before a commit the agent makes. A commit with
+/// findings only goes ahead when the user approves it.
+async fn check_commit_for_secrets(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    command: Vec<String>,
+    cwd: &Path,
+) -> Result<(), FunctionCallError> {
+    let config = turn_context.client.get_config();
+    if !config.secret_scan.block_commits {
+        return Ok(());
+    }
+    let scanner = SecretScanner::new(&config.secret_scan).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("invalid secret_scan pattern: {e}"))
+    })?;
+    let findings = match scan_changes(&scanner, cwd, false).await {
+        Ok(findings) => findings,
+        Err(e) => {
+            // Not a repository, or nothing git can diff; the commit itself
+            // will report the problem.
+            debug!("secret scan before commit failed: {e}");
+            return Ok(());
+        }
+    };
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let summary = describe_findings(&findings);
+    if turn_context.approval_policy == AskForApproval::Never {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "commit blocked: staged changes may contain secrets: {summary}"
+        )));
+    }
+    let decision = sess
+        .request_command_approval(
+            sub_id,
+            call_id,
+            command,
+            cwd.to_path_buf(),
+            Some(format!(
+                "staged changes may contain secrets: {summary}; approve to commit anyway"
+            )),
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved | ReviewDecision::ApprovedForSession => Ok(()),
+        ReviewDecision::Denied | ReviewDecision::Abort => Err(FunctionCallError::RespondToModel(
+            format!("commit blocked: staged changes may contain secrets: {summary}"),
+        )),
+    }
+}
+
+/// Handle an `extract_archive` call, asking the user first when the
+/// destination is outside the sandbox's writable roots.
+async fn handle_extract_archive_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args = parse_extract_archive_arguments(arguments)?;
+    let archive = turn_context.resolve_path(Some(args.path));
+    let destination = turn_context.resolve_path(args.destination);
+    if !is_path_writable(
+        &turn_context.sandbox_policy,
+        &turn_context.cwd,
+        &destination,
+    ) {
+        let reason = format!(
+            "{} is outside the sandbox's writable roots",
+            destination.display()
+        );
+        if turn_context.approval_policy == AskForApproval::Never {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "{reason}, which needs approval; the approval policy does not allow asking for it"
+            )));
+        }
+        let decision = sess
+            .request_command_approval(
+                sub_id,
+                call_id,
+                vec![
+                    EXTRACT_ARCHIVE_TOOL_NAME.to_string(),
+                    archive.display().to_string(),
+                    destination.display().to_string(),
+                ],
+                turn_context.cwd.clone(),
+                Some(reason),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(FunctionCallError::RespondToModel(
+                    "archive extraction rejected by user".to_string(),
+                ));
+            }
+        }
+    }
+    let config = turn_context.client.get_config();
+    handle_extract_archive(
+        &archive,
+        &destination,
+        &config.extract_archive,
+        args.overwrite,
+    )
+    .await
+}
+
+/// Handle a `browser` call. Screenshots are attached to the conversation as
+/// images, like those of `view_image`.
+async fn handle_browser_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: BrowserArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let config = turn_context.client.get_config();
+    let output = sess
+        .services
+        .browser_manager
+        .run(&config.browser, &turn_context.sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+    match output {
+        BrowserOutput::Text(text) => Ok(text),
+        BrowserOutput::Screenshot(path) => {
+            sess.inject_input(vec![InputItem::LocalImage { path: path.clone() }])
+                .await
+                .map_err(|_| {
+                    FunctionCallError::RespondToModel(
+                        "unable to attach screenshot (no active task)".to_string(),
+                    )
+                })?;
+            sess.send_event(Event {
+                id: sub_id,
+                msg: EventMsg::ViewImageToolCall(ViewImageToolCallEvent { call_id, path }),
+            })
+            .await;
+            Ok("attached screenshot".to_string())
+        }
+    }
+}
+
+/// Handle a `run_tests` call. The test command runs under the sandbox with
+/// the usual exec events, but the model gets a parsed report instead of its
+/// output. Failing tests are a normal result, not a failed call.
+async fn handle_run_tests_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: RunTestsArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let config = turn_context.client.get_config();
+    let run = plan_test_run(&config.run_tests, &turn_context.cwd, &args)
+        .map_err(FunctionCallError::RespondToModel)?;
+    if turn_context.approval_policy == AskForApproval::UnlessTrusted {
+        let decision = sess
+            .request_command_approval(
+                sub_id.clone(),
+                call_id.clone(),
+                run.command.clone(),
+                turn_context.cwd.clone(),
+                Some("run the project's tests".to_string()),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(FunctionCallError::RespondToModel(
+                    "test run rejected by user".to_string(),
+                ));
+            }
+        }
+    }
+    let sandbox_type = match &turn_context.sandbox_policy {
+        SandboxPolicy::DangerFullAccess => SandboxType::None,
+        _ => get_platform_sandbox().ok_or_else(|| {
+            FunctionCallError::RespondToModel(
+                "tests run sandboxed, but there is no sandbox on this platform".to_string(),
+            )
+        })?,
+    };
+    let session_env = sess.state.lock().await.env.clone();
+    let mut env = create_env(&turn_context.shell_environment_policy);
+    env.extend(session_env.clone());
+    let params = ExecParams {
+        command: run.command.clone(),
+        cwd: turn_context.cwd.clone(),
+        timeout_ms: Some(config.run_tests.timeout_sec.saturating_mul(1000)),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let exec_command_context = ExecCommandContext {
+        sub_id: sub_id.clone(),
+        call_id: call_id.clone(),
+        command_for_display: run.command.clone(),
+        cwd: turn_context.cwd.clone(),
+        apply_patch: None,
+        session_env,
+    };
+    let result = sess
+        .run_exec_with_events(
+            turn_diff_tracker,
+            exec_command_context,
+            ExecInvokeArgs {
+                params,
+                sandbox_type,
+                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_cwd: &turn_context.cwd,
+                codex_linux_sandbox_exe: &sess.services.codex_linux_sandbox_exe,
+                stdout_stream: Some(StdoutStream {
+                    sub_id,
+                    call_id,
+                    tx_event: sess.tx_event.clone(),
+                }),
+            },
+        )
+        .await;
+    // Failing tests exit non-zero, which the sandbox reports as a denial.
+    let output = match result {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Timeout { output } | SandboxErr::Denied { output })) => {
+            *output
+        }
+        Err(e) => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "failed to run tests: {e}"
+            )));
+        }
+    };
+    Ok(render_report(
+        run,
+        output.exit_code,
+        output.timed_out,
+        output.duration,
+        &output.stdout.text,
+        &output.aggregated_output.text,
+    ))
+}
 
-            sess.background_processes().kill(&process_id).await?;
-            sess.notify_background_event(
-                &sub_id,
-                format!("Requested termination for background process {process_id}"),
+/// Handle a `lint` call. The configured linters run on the requested or
+/// changed files; with `fix`, the formatters' changes are first applied as a
+/// patch, which goes through the same approval as `apply_patch`.
+async fn handle_lint_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: LintArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let config = turn_context.client.get_config();
+    let files = lint_targets(&turn_context.cwd, &args.paths)
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+    if files.is_empty() {
+        return Ok(json!({ "total": 0, "files": [], "notice": "no changed files" }).to_string());
+    }
+    let mut fixed = Vec::new();
+    let mut fix_errors = Vec::new();
+    if args.fix {
+        let fixes = compute_fixes(&config.linters, &turn_context.cwd, &files).await;
+        fix_errors = fixes.errors;
+        if let Some(patch) = fix_patch(&turn_context.cwd, &fixes.files) {
+            let params = ExecParams {
+                command: vec!["apply_patch".to_string(), patch],
+                cwd: turn_context.cwd.clone(),
+                timeout_ms: None,
+                env: HashMap::new(),
+                with_escalated_permissions: None,
+                justification: None,
+            };
+            match handle_container_exec_with_params(
+                LINT_TOOL_NAME,
+                params,
+                sess,
+                turn_context,
+                turn_diff_tracker,
+                sub_id,
+                call_id,
             )
-            .await;
-
-            serde_json::to_string(&json!({
-                "status": "killed",
-                "process_id": process_id,
-            }))
-            .map_err(|err| FunctionCallError::RespondToModel(err.to_string()))
+            .await
+            {
+                Ok(_) => fixed = fixes.files.into_iter().map(|file| file.path).collect(),
+                Err(FunctionCallError::RespondToModel(e)) => {
+                    fix_errors.push(format!("fixes were not applied: {e}"));
+                }
+            }
         }
     }
+    let report = run_linters(&config.linters, &turn_context.cwd, &files).await;
+    Ok(render_lint_output(
+        &turn_context.cwd,
+        report,
+        &fixed,
+        fix_errors,
+    ))
 }
 
-fn to_exec_params(params: ShellToolCallParams, turn_context: &TurnContext) -> ExecParams {
-    ExecParams {
-        command: params.command,
-        cwd: turn_context.resolve_path(params.workdir.clone()),
-        timeout_ms: params.timeout_ms,
-        env: create_env(&turn_context.shell_environment_policy),
-        with_escalated_permissions: params.with_escalated_permissions,
-        justification: params.justification,
+/// Handle a `python` call. Code runs in the sandbox without approval unless
+/// the approval policy asks for it on every command; figures are attached to
+/// the conversation as images.
+async fn handle_python_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: PythonArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let code = match args {
+        PythonArgs::Execute { code } => code,
+        PythonArgs::Reset => {
+            sess.services.python_kernel_manager.close().await;
+            return Ok(
+                json!({ "notice": "the kernel was reset; the next cell starts a fresh one" })
+                    .to_string(),
+            );
+        }
+    };
+    if turn_context.approval_policy == AskForApproval::UnlessTrusted {
+        let decision = sess
+            .request_command_approval(
+                sub_id.clone(),
+                call_id.clone(),
+                vec!["python".to_string(), "-c".to_string(), code.clone()],
+                turn_context.cwd.clone(),
+                Some("run code in the Python kernel".to_string()),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Err(FunctionCallError::RespondToModel(
+                    "Python cell rejected by user".to_string(),
+                ));
+            }
+        }
+    }
+    let config = turn_context.client.get_config();
+    let output = sess
+        .services
+        .python_kernel_manager
+        .execute(
+            KernelSpawn {
+                config: &config.python,
+                sandbox_policy: &turn_context.sandbox_policy,
+                cwd: &turn_context.cwd,
+                env: create_env(&turn_context.shell_environment_policy),
+                codex_linux_sandbox_exe: sess.services.codex_linux_sandbox_exe.as_ref(),
+            },
+            &code,
+        )
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+    if !output.images.is_empty() {
+        let images = output
+            .images
+            .iter()
+            .map(|path| InputItem::LocalImage { path: path.clone() })
+            .collect();
+        sess.inject_input(images).await.map_err(|_| {
+            FunctionCallError::RespondToModel(
+                "unable to attach figures (no active task)".to_string(),
+            )
+        })?;
+        for path in output.images {
+            sess.send_event(Event {
+                id: sub_id.clone(),
+                msg: EventMsg::ViewImageToolCall(ViewImageToolCallEvent {
+                    call_id: call_id.clone(),
+                    path,
+                }),
+            })
+            .await;
+        }
     }
+    Ok(output.text)
 }
 
-fn parse_container_exec_arguments(
-    arguments: String,
+/// Handle a `write_file` call. The write is approved and reported like an
+/// `apply_patch` change, but made in-process so it can be atomic.
+async fn handle_write_file(
+    sess: &Session,
     turn_context: &TurnContext,
-    _call_id: &str,
-) -> Result<ExecParams, FunctionCallError> {
-    serde_json::from_str::<ShellToolCallParams>(&arguments)
-        .map(|p| to_exec_params(p, turn_context))
-        .map_err(|e| {
-            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
-        })
-}
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args = parse_write_file_arguments(arguments)?;
+    let path = turn_context.resolve_path(Some(args.path));
+    let Some(action) = write_file_action(&path, args.content.clone()).await? else {
+        return Ok(format!("{} already has this content.", path.display()));
+    };
+    let ApplyPatchExec {
+        action,
+        user_explicitly_approved_this_action,
+    } = match apply_patch::apply_patch(sess, turn_context, &sub_id, &call_id, action).await {
+        InternalApplyPatchInvocation::Output(result) => return result,
+        InternalApplyPatchInvocation::DelegateToExec(apply_patch_exec) => apply_patch_exec,
+    };
+    // Auto-approved patches rely on the sandbox to stop writes outside the
+    // writable roots; this write bypasses it, so check them here.
+    if !user_explicitly_approved_this_action
+        && !is_path_writable(&turn_context.sandbox_policy, &turn_context.cwd, &path)
+    {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "writing {} is not allowed by the sandbox policy",
+            path.display()
+        )));
+    }
 
-pub struct ExecInvokeArgs<'a> {
-    pub params: ExecParams,
-    pub sandbox_type: SandboxType,
-    pub sandbox_policy: &'a SandboxPolicy,
-    pub sandbox_cwd: &'a Path,
-    pub codex_linux_sandbox_exe: &'a Option<PathBuf>,
-    pub stdout_stream: Option<StdoutStream>,
+    sess.on_exec_command_begin(
+        turn_diff_tracker,
+        ExecCommandContext {
+            sub_id: sub_id.clone(),
+            call_id: call_id.clone(),
+            command_for_display: vec![WRITE_FILE_TOOL_NAME.to_string(), path.display().to_string()],
+            cwd: turn_context.cwd.clone(),
+            apply_patch: Some(ApplyPatchCommandContext {
+                user_explicitly_approved_this_action,
+                changes: convert_apply_patch_to_protocol(&action),
+            }),
+            session_env: BTreeMap::new(),
+        },
+    )
+    .await;
+    let start = Instant::now();
+    let result = write_atomically(&path, args.content).await;
+    let (exit_code, stdout, stderr) = match &result {
+        Ok(()) => (0, format!("Wrote {}.", path.display()), String::new()),
+        Err(e) => (
+            1,
+            String::new(),
+            format!("failed to write {}: {e}", path.display()),
+        ),
+    };
+    let output = ExecToolCallOutput {
+        exit_code,
+        stdout: StreamOutput::new(stdout.clone()),
+        stderr: StreamOutput::new(stderr.clone()),
+        aggregated_output: StreamOutput::new(format!("{stdout}{stderr}")),
+        duration: start.elapsed(),
+        timed_out: false,
+    };
+    sess.on_exec_command_end(turn_diff_tracker, &sub_id, &call_id, &output, true)
+        .await;
+    match result {
+        Ok(()) => Ok(stdout),
+        Err(_) => Err(FunctionCallError::RespondToModel(stderr)),
+    }
 }
 
-fn maybe_translate_shell_command(
-    params: ExecParams,
+/// Handle a `batch_edit` call. Every file the edit changes is approved and
+/// reported as one `apply_patch` change, then written atomically in turn.
+async fn handle_batch_edit(
     sess: &Session,
     turn_context: &TurnContext,
-) -> ExecParams {
-    let should_translate = matches!(sess.user_shell(), crate::shell::Shell::PowerShell(_))
-        || turn_context.shell_environment_policy.use_profile;
-
-    if should_translate
-        && let Some(command) = sess
-            .user_shell()
-            .format_default_shell_invocation(params.command.clone())
+    turn_diff_tracker: &mut TurnDiffTracker,
+    sub_id: String,
+    call_id: String,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args = parse_batch_edit_arguments(arguments)?;
+    let dry_run = args.dry_run;
+    let cwd = &turn_context.cwd;
+    let plan = plan_batch_edit(cwd, &turn_context.sandbox_policy, args).await?;
+    if plan.files.is_empty() {
+        return Ok("Nothing matched; no files were changed.".to_string());
+    }
+    if dry_run {
+        return Ok(plan.output(cwd, false));
+    }
+    let ApplyPatchExec {
+        action,
+        user_explicitly_approved_this_action,
+    } = match apply_patch::apply_patch(sess, turn_context, &sub_id, &call_id, plan.action(cwd))
+        .await
     {
-        return ExecParams { command, ..params };
+        InternalApplyPatchInvocation::Output(result) => return result,
+        InternalApplyPatchInvocation::DelegateToExec(apply_patch_exec) => apply_patch_exec,
+    };
+    // Auto-approved patches rely on the sandbox to stop writes outside the
+    // writable roots; these writes bypass it, so check them here.
+    if !user_explicitly_approved_this_action
+        && let Some(file) = plan
+            .files
+            .iter()
+            .find(|file| !is_path_writable(&turn_context.sandbox_policy, cwd, &file.path))
+    {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "writing {} is not allowed by the sandbox policy",
+            file.path.display()
+        )));
+    }
+
+    sess.on_exec_command_begin(
+        turn_diff_tracker,
+        ExecCommandContext {
+            sub_id: sub_id.clone(),
+            call_id: call_id.clone(),
+            command_for_display: vec![
+                BATCH_EDIT_TOOL_NAME.to_string(),
+                format!("{} files", plan.files.len()),
+            ],
+            cwd: cwd.clone(),
+            apply_patch: Some(ApplyPatchCommandContext {
+                user_explicitly_approved_this_action,
+                changes: convert_apply_patch_to_protocol(&action),
+            }),
+            session_env: BTreeMap::new(),
+        },
+    )
+    .await;
+    let start = Instant::now();
+    let mut failure = None;
+    for file in &plan.files {
+        if let Err(e) = write_atomically(&file.path, file.content.clone()).await {
+            failure = Some(format!("failed to write {}: {e}", file.path.display()));
+            break;
+        }
+    }
+    let (exit_code, stdout, stderr) = match &failure {
+        None => (0, plan.output(cwd, true), String::new()),
+        Some(error) => (1, String::new(), error.clone()),
+    };
+    let output = ExecToolCallOutput {
+        exit_code,
+        stdout: StreamOutput::new(stdout.clone()),
+        stderr: StreamOutput::new(stderr.clone()),
+        aggregated_output: StreamOutput::new(format!("{stdout}{stderr}")),
+        duration: start.elapsed(),
+        timed_out: false,
+    };
+    sess.on_exec_command_end(turn_diff_tracker, &sub_id, &call_id, &output, true)
+        .await;
+    match failure {
+        None => Ok(stdout),
+        Some(_) => Err(FunctionCallError::RespondToModel(stderr)),
     }
-    params
 }
 
 async fn handle_container_exec_with_params(
@@ -2848,6 +6176,11 @@ async fn handle_container_exec_with_params(
     call_id: String,
 ) -> Result<String, FunctionCallError> {
     let otel_event_manager = turn_context.client.get_otel_event_manager();
+    let mut params = params;
+    if params.timeout_ms.is_none() {
+        params.timeout_ms =
+            default_exec_timeout_ms(&turn_context.client.get_config().tool_timeouts, tool_name);
+    }
 
     if params.with_escalated_permissions.unwrap_or(false)
         && !matches!(turn_context.approval_policy, AskForApproval::OnRequest)
@@ -2883,7 +6216,19 @@ async fn handle_container_exec_with_params(
         MaybeApplyPatchVerified::NotApplyPatch => None,
     };
 
-    let (params, safety, command_for_display) = match &apply_patch_exec {
+    if apply_patch_exec.is_none() && is_git_commit(&params.command) {
+        check_commit_for_secrets(
+            sess,
+            turn_context,
+            sub_id.clone(),
+            call_id.clone(),
+            params.command.clone(),
+            &params.cwd,
+        )
+        .await?;
+    }
+
+    let (params, safety, command_for_display, session_env) = match &apply_patch_exec {
         Some(ApplyPatchExec {
             action: ApplyPatchAction { patch, cwd, .. },
             user_explicitly_approved_this_action,
@@ -2925,21 +6270,25 @@ async fn handle_container_exec_with_params(
                 params,
                 safety,
                 vec!["apply_patch".to_string(), patch.clone()],
+                BTreeMap::new(),
             )
         }
         None => {
-            let safety = {
+            let mut params = params;
+            let (safety, session_env) = {
                 let state = sess.state.lock().await;
-                assess_command_safety(
+                let safety = assess_command_safety(
                     &params.command,
                     turn_context.approval_policy,
                     &turn_context.sandbox_policy,
                     state.approved_commands_ref(),
                     params.with_escalated_permissions.unwrap_or(false),
-                )
+                );
+                (safety, state.env.clone())
             };
+            params.env.extend(session_env.clone());
             let command_for_display = params.command.clone();
-            (params, safety, command_for_display)
+            (params, safety, command_for_display, session_env)
         }
     };
 
@@ -2962,15 +6311,19 @@ async fn handle_container_exec_with_params(
             sandbox_type
         }
         SafetyCheck::AskUser => {
-            let decision = sess
-                .request_command_approval(
-                    sub_id.clone(),
-                    call_id.clone(),
-                    params.command.clone(),
-                    params.cwd.clone(),
-                    params.justification.clone(),
-                )
-                .await;
+            let decision = match sess.take_batch_decision(&call_id).await {
+                Some(decision) => decision,
+                None => {
+                    sess.request_command_approval(
+                        sub_id.clone(),
+                        call_id.clone(),
+                        params.command.clone(),
+                        params.cwd.clone(),
+                        params.justification.clone(),
+                    )
+                    .await
+                }
+            };
             match decision {
                 ReviewDecision::Approved => {
                     otel_event_manager.tool_decision(
@@ -3045,6 +6398,7 @@ async fn handle_container_exec_with_params(
                 changes: convert_apply_patch_to_protocol(&action),
             },
         ),
+        session_env,
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
@@ -3122,6 +6476,16 @@ async fn handle_sandbox_error(
         return Err(FunctionCallError::RespondToModel(content));
     }
 
+    if let SandboxErr::Denied { output } = &error
+        && let Some(denial) = detect_sandbox_denial(&call_id, &cwd, output)
+    {
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::SandboxDenied(denial),
+        })
+        .await;
+    }
+
     // Early out if either the user never wants to be asked for approval, or
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
@@ -3496,7 +6860,6 @@ mod tests {
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
 
-    use crate::protocol::CompactedItem;
     use crate::protocol::InitialHistory;
     use crate::protocol::ResumedHistory;
     use crate::state::TaskKind;
@@ -3527,6 +6890,28 @@ mod tests {
         assert_eq!(expected, reconstructed);
     }
 
+    #[test]
+    fn reconstruct_history_honors_rewinds() {
+        let (session, turn_context) = make_session_and_context();
+        let (mut rollout_items, live_history) = sample_rollout(&session, &turn_context);
+        // Roll back past the last turn after another one was recorded.
+        let checkpoint = live_history[..live_history.len() - 2].to_vec();
+        rollout_items.push(RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: "fourth user".to_string(),
+            }],
+        }));
+        rollout_items.push(RolloutItem::HistoryRewound(HistoryRewoundItem {
+            history: checkpoint.clone(),
+        }));
+
+        let reconstructed = session.reconstruct_history_from_rollout(&turn_context, &rollout_items);
+
+        assert_eq!(checkpoint, reconstructed);
+    }
+
     #[test]
     fn record_initial_history_reconstructs_resumed_transcript() {
         let (session, turn_context) = make_session_and_context();
@@ -3773,15 +7158,10 @@ mod tests {
             config.model_reasoning_summary,
             conversation_id,
         );
-        let tools_config = ToolsConfig::new(&ToolsConfigParams {
-            model_family: &config.model_family,
-            include_plan_tool: config.include_plan_tool,
-            include_apply_patch_tool: config.include_apply_patch_tool,
-            include_web_search_request: config.tools_web_search_request,
-            use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-            include_view_image_tool: config.include_view_image_tool,
-            experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-        });
+        let tools_config = ToolsConfig::new(&ToolsConfigParams::from_config(
+            &config,
+            &config.model_family,
+        ));
         let turn_context = TurnContext {
             client,
             cwd: config.cwd.clone(),
@@ -3795,7 +7175,11 @@ mod tests {
             final_output_json_schema: None,
         };
         let services = SessionServices {
-            mcp_connection_manager: McpConnectionManager::default(),
+            mcp_connection_manager: RwLock::new(McpConnectionManager::default()),
+            mcp_servers: Mutex::new(HashMap::new()),
+            mcp_sampler: None,
+            mcp_roots: Arc::new(McpRoots::new(Vec::new())),
+            mcp_elicitor: Arc::new(McpElicitor::new()),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
@@ -3803,7 +7187,20 @@ mod tests {
             codex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            background_process_manager: BackgroundProcessManager::new(),
+            approval_batching: config.approval_batching,
+            busy_session_policy: config.busy_session_policy,
+            generate_title: false,
+            binary_payload_framing: config.binary_payload_framing,
+            client_capabilities: config.client_capabilities,
+            background_process_manager: BackgroundProcessManager::new(
+                config.background_process.clone(),
+            ),
+            browser_manager: BrowserManager::default(),
+            python_kernel_manager: PythonKernelManager::default(),
+            plugin_manager: PluginManager::default(),
+            file_locks: Arc::new(FileLocks::default()),
+            replay: None,
+            scratch_dir: None,
         };
         let session = Session {
             conversation_id,
@@ -3843,15 +7240,10 @@ mod tests {
             config.model_reasoning_summary,
             conversation_id,
         );
-        let tools_config = ToolsConfig::new(&ToolsConfigParams {
-            model_family: &config.model_family,
-            include_plan_tool: config.include_plan_tool,
-            include_apply_patch_tool: config.include_apply_patch_tool,
-            include_web_search_request: config.tools_web_search_request,
-            use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-            include_view_image_tool: config.include_view_image_tool,
-            experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-        });
+        let tools_config = ToolsConfig::new(&ToolsConfigParams::from_config(
+            &config,
+            &config.model_family,
+        ));
         let turn_context = Arc::new(TurnContext {
             client,
             cwd: config.cwd.clone(),
@@ -3865,7 +7257,11 @@ mod tests {
             final_output_json_schema: None,
         });
         let services = SessionServices {
-            mcp_connection_manager: McpConnectionManager::default(),
+            mcp_connection_manager: RwLock::new(McpConnectionManager::default()),
+            mcp_servers: Mutex::new(HashMap::new()),
+            mcp_sampler: None,
+            mcp_roots: Arc::new(McpRoots::new(Vec::new())),
+            mcp_elicitor: Arc::new(McpElicitor::new()),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
@@ -3873,7 +7269,20 @@ mod tests {
             codex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
-            background_process_manager: BackgroundProcessManager::new(),
+            approval_batching: config.approval_batching,
+            busy_session_policy: config.busy_session_policy,
+            generate_title: false,
+            binary_payload_framing: config.binary_payload_framing,
+            client_capabilities: config.client_capabilities,
+            background_process_manager: BackgroundProcessManager::new(
+                config.background_process.clone(),
+            ),
+            browser_manager: BrowserManager::default(),
+            python_kernel_manager: PythonKernelManager::default(),
+            plugin_manager: PluginManager::default(),
+            file_locks: Arc::new(FileLocks::default()),
+            replay: None,
+            scratch_dir: None,
         };
         let session = Arc::new(Session {
             conversation_id,
@@ -3939,6 +7348,65 @@ mod tests {
         assert!(rx.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn approval_batch_accepts_per_item_and_bulk_decisions() {
+        let (sess, tc, rx) = make_session_and_context_with_rx();
+        *sess.active_turn.lock().await = Some(ActiveTurn::default());
+
+        let items = ["call-1", "call-2", "call-3"]
+            .into_iter()
+            .map(|call_id| ExecApprovalRequestEvent {
+                call_id: call_id.to_string(),
+                command: vec!["touch".to_string(), call_id.to_string()],
+                cwd: tc.cwd.clone(),
+                reason: None,
+            })
+            .collect::<Vec<_>>();
+        let requester = {
+            let sess = Arc::clone(&sess);
+            tokio::spawn(async move {
+                sess.request_batch_approval("sub-batch".to_string(), items)
+                    .await;
+            })
+        };
+
+        let evt = rx.recv().await.expect("event");
+        let EventMsg::ApprovalBatchRequest(ApprovalBatchRequestEvent { items }) = evt.msg else {
+            panic!("unexpected event: {:?}", evt.msg);
+        };
+        assert_eq!("sub-batch", evt.id);
+        assert_eq!(
+            vec!["call-1", "call-2", "call-3"],
+            items.iter().map(|i| i.call_id.as_str()).collect::<Vec<_>>()
+        );
+
+        // Answer one item individually, then the rest in bulk.
+        sess.notify_approval("call-2", ReviewDecision::Approved)
+            .await;
+        sess.notify_approval_batch(
+            "sub-batch",
+            HashMap::from([("call-3".to_string(), ReviewDecision::ApprovedForSession)]),
+            Some(ReviewDecision::Denied),
+        )
+        .await;
+        requester.await.expect("batch request completes");
+
+        assert_eq!(
+            vec![
+                Some(ReviewDecision::Denied),
+                Some(ReviewDecision::Approved),
+                Some(ReviewDecision::ApprovedForSession),
+                None,
+            ],
+            vec![
+                sess.take_batch_decision("call-1").await,
+                sess.take_batch_decision("call-2").await,
+                sess.take_batch_decision("call-3").await,
+                sess.take_batch_decision("call-3").await,
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn abort_review_task_emits_exited_then_aborted_and_records_history() {
         let (sess, tc, rx) = make_session_and_context_with_rx();
@@ -4031,6 +7499,7 @@ mod tests {
         live_history.replace(rebuilt1);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary1.to_string(),
+            replacement_history: None,
         }));
 
         let user2 = ResponseItem::Message {
@@ -4064,6 +7533,7 @@ mod tests {
         live_history.replace(rebuilt2);
         rollout_items.push(RolloutItem::Compacted(CompactedItem {
             message: summary2.to_string(),
+            replacement_history: None,
         }));
 
         let user3 = ResponseItem::Message {
@@ -4189,4 +7659,43 @@ mod tests {
         pretty_assertions::assert_eq!(exec_output.metadata, ResponseExecMetadata { exit_code: 0 });
         assert!(exec_output.output.contains("hi"));
     }
+
+    #[tokio::test]
+    async fn disabled_builtin_tools_are_refused_at_dispatch() {
+        let (session, mut turn_context) = make_session_and_context();
+        let mut config = (*turn_context.client.get_config()).clone();
+        config.tool_filter = ToolFilter::new(None, &["update_plan".to_string()]);
+        let config = Arc::new(config);
+        turn_context.client = ModelClient::new(
+            config.clone(),
+            None,
+            otel_event_manager(session.conversation_id, config.as_ref()),
+            config.model_provider.clone(),
+            config.model_reasoning_effort,
+            config.model_reasoning_summary,
+            session.conversation_id,
+        );
+
+        let plan = json!({ "plan": [{ "step": "test", "status": "pending" }] });
+        let result = handle_builtin_function_call(
+            &session,
+            &turn_context,
+            &mut TurnDiffTracker::new(),
+            "test-sub".to_string(),
+            "update_plan".to_string(),
+            plan.to_string(),
+            "test-call".to_string(),
+        )
+        .await;
+
+        assert_eq!(
+            Err(FunctionCallError::RespondToModel(
+                "tool `update_plan` is disabled in this session; use one of the tools you were \
+                 given"
+                    .to_string()
+            )),
+            result
+        );
+        assert!(session.state.lock().await.plan.is_none());
+    }
 }
@@ -9,6 +9,7 @@ use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
 use crate::protocol::AgentMessageEvent;
 use crate::protocol::CompactedItem;
+use crate::protocol::ContextCompactedEvent;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -19,6 +20,7 @@ use crate::protocol::TurnContextItem;
 use crate::truncate::truncate_middle;
 use crate::util::backoff;
 use askama::Template;
+use codex_protocol::config_types::CompactionStrategy;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
@@ -27,6 +29,7 @@ use futures::prelude::*;
 
 pub const SUMMARIZATION_PROMPT: &str = include_str!("../../templates/compact/prompt.md");
 const COMPACT_USER_MESSAGE_MAX_TOKENS: usize = 20_000;
+const DROPPED_TOOL_OUTPUT: &str = "[output removed during context compaction]";
 
 #[derive(Template)]
 #[template(path = "compact/history_bridge.md", escape = "none")]
@@ -38,12 +41,33 @@ struct HistoryBridgeTemplate<'a> {
 pub(crate) async fn run_inline_auto_compact_task(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
+    strategy: CompactionStrategy,
 ) {
     let sub_id = sess.next_internal_sub_id();
+    if strategy == CompactionStrategy::DropToolOutputs
+        && drop_stale_tool_outputs(&sess, &turn_context, &sub_id).await
+    {
+        return;
+    }
     let input = vec![InputItem::Text {
         text: SUMMARIZATION_PROMPT.to_string(),
     }];
-    run_compact_task_inner(sess, turn_context, sub_id, input).await;
+    run_compact_task_inner(sess, turn_context, sub_id, input, strategy).await;
+}
+
+/// Strategy to use for the `attempt`-th consecutive automatic compaction
+/// (0-based) of a turn. A cheaper strategy that did not free enough context
+/// escalates to a full summary; `None` means compaction has been exhausted.
+pub(crate) fn auto_compact_strategy(
+    configured: CompactionStrategy,
+    attempt: usize,
+) -> Option<CompactionStrategy> {
+    match (attempt, configured) {
+        (0, strategy) => Some(strategy),
+        (1, CompactionStrategy::Summarize) => None,
+        (1, _) => Some(CompactionStrategy::Summarize),
+        _ => None,
+    }
 }
 
 pub(crate) async fn run_compact_task(
@@ -59,20 +83,81 @@ pub(crate) async fn run_compact_task(
         }),
     };
     sess.send_event(start_event).await;
-    run_compact_task_inner(sess.clone(), turn_context, sub_id.clone(), input).await;
+    run_compact_task_inner(
+        sess.clone(),
+        turn_context,
+        sub_id.clone(),
+        input,
+        CompactionStrategy::Summarize,
+    )
+    .await;
     None
 }
 
+/// Replace the output of tool calls that precede the most recent turns with a
+/// placeholder. Returns `false` (leaving history untouched) when there was
+/// nothing to drop.
+async fn drop_stale_tool_outputs(sess: &Session, turn_context: &TurnContext, sub_id: &str) -> bool {
+    let settings = turn_context.client.get_auto_compaction();
+    let mut history = sess.history_snapshot().await;
+    let split = recent_turns_start(&history, settings.keep_recent_turns);
+    let (tool_outputs_dropped, pinned_items_kept) =
+        replace_tool_outputs(&mut history[..split], &settings.pinned);
+    if tool_outputs_dropped == 0 {
+        return false;
+    }
+
+    let items = history.len();
+    sess.replace_history(history.clone()).await;
+    let rollout_item = RolloutItem::Compacted(CompactedItem {
+        message: String::new(),
+        replacement_history: Some(history),
+    });
+    sess.persist_rollout_items(&[rollout_item]).await;
+    let event = Event {
+        id: sub_id.to_string(),
+        msg: EventMsg::ContextCompacted(ContextCompactedEvent {
+            strategy: CompactionStrategy::DropToolOutputs,
+            items_before: items,
+            items_after: items,
+            turns_summarized: 0,
+            tool_outputs_dropped,
+            pinned_items_kept,
+        }),
+    };
+    sess.send_event(event).await;
+    true
+}
+
 async fn run_compact_task_inner(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
     sub_id: String,
     input: Vec<InputItem>,
+    strategy: CompactionStrategy,
 ) {
+    let settings = turn_context.client.get_auto_compaction().clone();
+    let history_before = sess.history_snapshot().await;
+    // Only the turns before `split` are summarized; the rest is kept as-is.
+    let split = match strategy {
+        CompactionStrategy::SummarizeOldest => {
+            match recent_turns_start(&history_before, settings.keep_recent_turns) {
+                0 => history_before.len(),
+                split => split,
+            }
+        }
+        CompactionStrategy::Summarize | CompactionStrategy::DropToolOutputs => history_before.len(),
+    };
+    let strategy = if split == history_before.len() {
+        CompactionStrategy::Summarize
+    } else {
+        strategy
+    };
+    let (older, recent) = history_before.split_at(split);
+
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    let turn_input = sess
-        .turn_input_with_history(vec![initial_input_for_turn.clone().into()])
-        .await;
+    let mut turn_input = older.to_vec();
+    turn_input.push(initial_input_for_turn.into());
 
     let prompt = Prompt {
         input: turn_input,
@@ -132,13 +217,18 @@ async fn run_compact_task_inner(
 
     let history_snapshot = sess.history_snapshot().await;
     let summary_text = get_last_assistant_message_from_turn(&history_snapshot).unwrap_or_default();
-    let user_messages = collect_user_messages(&history_snapshot);
+    let user_messages = collect_user_messages(older);
+    let pinned = pinned_messages(older, &settings.pinned);
     let initial_context = sess.build_initial_context(turn_context.as_ref());
-    let new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
-    sess.replace_history(new_history).await;
+    let mut new_history = build_compacted_history(initial_context, &user_messages, &summary_text);
+    new_history.extend(pinned.iter().cloned());
+    new_history.extend(recent.iter().cloned());
+    let items_after = new_history.len();
+    sess.replace_history(new_history.clone()).await;
 
     let rollout_item = RolloutItem::Compacted(CompactedItem {
         message: summary_text.clone(),
+        replacement_history: Some(new_history),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
 
@@ -149,6 +239,87 @@ async fn run_compact_task_inner(
         }),
     };
     sess.send_event(event).await;
+
+    let event = Event {
+        id: sub_id,
+        msg: EventMsg::ContextCompacted(ContextCompactedEvent {
+            strategy,
+            items_before: history_before.len(),
+            items_after,
+            turns_summarized: user_messages.len(),
+            tool_outputs_dropped: 0,
+            pinned_items_kept: pinned.len(),
+        }),
+    };
+    sess.send_event(event).await;
+}
+
+/// Index of the first item belonging to the `keep` most recent user turns, or
+/// 0 when the history has no more than `keep` turns.
+fn recent_turns_start(items: &[ResponseItem], keep: usize) -> usize {
+    let turn_starts: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| match item {
+            ResponseItem::Message { role, content, .. } if role == "user" => {
+                content_items_to_text(content)
+                    .filter(|text| !is_session_prefix_message(text))
+                    .map(|_| idx)
+            }
+            _ => None,
+        })
+        .collect();
+    if keep == 0 {
+        return items.len();
+    }
+    match turn_starts.len().checked_sub(keep) {
+        Some(first_kept) if first_kept > 0 => turn_starts[first_kept],
+        _ => 0,
+    }
+}
+
+fn is_pinned(text: &str, pinned: &[String]) -> bool {
+    pinned.iter().any(|pattern| text.contains(pattern.as_str()))
+}
+
+/// Messages (other than the session prefix) that mention a pinned string.
+fn pinned_messages(items: &[ResponseItem], pinned: &[String]) -> Vec<ResponseItem> {
+    if pinned.is_empty() {
+        return Vec::new();
+    }
+    items
+        .iter()
+        .filter(|item| match item {
+            ResponseItem::Message { content, .. } => content_items_to_text(content)
+                .is_some_and(|text| !is_session_prefix_message(&text) && is_pinned(&text, pinned)),
+            _ => false,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Replace tool outputs in `items` with a placeholder, returning how many were
+/// replaced and how many were kept because they are pinned.
+fn replace_tool_outputs(items: &mut [ResponseItem], pinned: &[String]) -> (usize, usize) {
+    let mut dropped = 0;
+    let mut kept = 0;
+    for item in items {
+        let output = match item {
+            ResponseItem::FunctionCallOutput { output, .. } => &mut output.content,
+            ResponseItem::CustomToolCallOutput { output, .. } => output,
+            _ => continue,
+        };
+        if output == DROPPED_TOOL_OUTPUT {
+            continue;
+        }
+        if is_pinned(output, pinned) {
+            kept += 1;
+            continue;
+        }
+        *output = DROPPED_TOOL_OUTPUT.to_string();
+        dropped += 1;
+    }
+    (dropped, kept)
 }
 
 pub fn content_items_to_text(content: &[ContentItem]) -> Option<String> {
@@ -389,4 +560,95 @@ mod tests {
             "bridge should include the provided summary text"
         );
     }
+
+    fn user(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn tool_output(call_id: &str, output: &str) -> ResponseItem {
+        ResponseItem::CustomToolCallOutput {
+            call_id: call_id.to_string(),
+            output: output.to_string(),
+        }
+    }
+
+    #[test]
+    fn recent_turns_start_keeps_latest_user_turns() {
+        let items = vec![
+            user("<environment_context>cwd</environment_context>"),
+            user("first"),
+            tool_output("a", "out a"),
+            user("second"),
+            tool_output("b", "out b"),
+            user("third"),
+        ];
+
+        assert_eq!(3, recent_turns_start(&items, 2));
+        assert_eq!(5, recent_turns_start(&items, 1));
+        assert_eq!(items.len(), recent_turns_start(&items, 0));
+        assert_eq!(0, recent_turns_start(&items, 3));
+    }
+
+    #[test]
+    fn replace_tool_outputs_skips_pinned_and_already_dropped() {
+        let mut items = vec![
+            tool_output("a", "large build log"),
+            tool_output("b", "contains IMPORTANT note"),
+            tool_output("c", DROPPED_TOOL_OUTPUT),
+            user("keep me"),
+        ];
+
+        let counts = replace_tool_outputs(&mut items, &["IMPORTANT".to_string()]);
+
+        assert_eq!((1, 1), counts);
+        assert_eq!(
+            vec![
+                tool_output("a", DROPPED_TOOL_OUTPUT),
+                tool_output("b", "contains IMPORTANT note"),
+                tool_output("c", DROPPED_TOOL_OUTPUT),
+                user("keep me"),
+            ],
+            items
+        );
+    }
+
+    #[test]
+    fn pinned_messages_ignores_tool_outputs() {
+        let items = vec![
+            user("remember TODO.md"),
+            user("unrelated"),
+            tool_output("a", "TODO.md"),
+        ];
+
+        assert_eq!(
+            vec![user("remember TODO.md")],
+            pinned_messages(&items, &["TODO.md".to_string()])
+        );
+    }
+
+    #[test]
+    fn auto_compact_strategy_escalates_to_summarize() {
+        assert_eq!(
+            Some(CompactionStrategy::DropToolOutputs),
+            auto_compact_strategy(CompactionStrategy::DropToolOutputs, 0)
+        );
+        assert_eq!(
+            Some(CompactionStrategy::Summarize),
+            auto_compact_strategy(CompactionStrategy::DropToolOutputs, 1)
+        );
+        assert_eq!(
+            None,
+            auto_compact_strategy(CompactionStrategy::Summarize, 1)
+        );
+        assert_eq!(
+            None,
+            auto_compact_strategy(CompactionStrategy::SummarizeOldest, 2)
+        );
+    }
 }
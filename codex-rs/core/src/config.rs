@@ -1,18 +1,63 @@
+use crate::config_conditions::Host;
+use crate::config_conditions::apply_conditional_sections;
+use crate::config_encryption::decrypt_config;
+use crate::config_include::resolve_includes;
+use crate::config_interpolation::interpolate_config;
+use crate::config_interpolation::interpolate_mcp_server;
+use crate::config_migration::migrate_config;
 use crate::config_profile::ConfigProfile;
+use crate::config_profile::SessionProfile;
+use crate::config_types::AutoCompaction;
+use crate::config_types::BackgroundProcess;
+use crate::config_types::Browser;
+use crate::config_types::BusySessionPolicy;
+use crate::config_types::CustomToolConfig;
 use crate::config_types::DEFAULT_OTEL_ENVIRONMENT;
+use crate::config_types::DatabaseConnection;
+use crate::config_types::DirectoryPolicyToml;
+use crate::config_types::ExtractArchive;
 use crate::config_types::History;
+use crate::config_types::HttpRequest;
+use crate::config_types::LinterConfig;
+use crate::config_types::McpHealth;
+use crate::config_types::McpResources;
+use crate::config_types::McpSampling;
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::McpToolApproval;
+use crate::config_types::Memory;
+use crate::config_types::ModelFallback;
+use crate::config_types::ModelFallbackToml;
+use crate::config_types::ModelPricing;
 use crate::config_types::Notifications;
 use crate::config_types::OtelConfig;
 use crate::config_types::OtelConfigToml;
 use crate::config_types::OtelExporterKind;
+use crate::config_types::Plugins;
+use crate::config_types::PythonKernel;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::ResponseCache;
+use crate::config_types::ResponseCacheToml;
+use crate::config_types::RolloutRedaction;
+use crate::config_types::RunTests;
 use crate::config_types::SandboxWorkspaceWrite;
+use crate::config_types::SecretScan;
+use crate::config_types::SessionBudget;
+use crate::config_types::SessionTitles;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
+use crate::config_types::SubAgents;
+use crate::config_types::TeamConfigToml;
+use crate::config_types::ToolFilter;
+use crate::config_types::ToolQuota;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::ViewImage;
+use crate::config_types::WebFetch;
+use crate::config_types::WebSearch;
+use crate::config_types::WebSearchProvider;
+use crate::directory_policy::resolve_directory_policy;
+use crate::extension_config::ExtensionConfigs;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
@@ -20,21 +65,32 @@ use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
 use crate::openai_model_info::get_model_info;
+use crate::project_config::ProjectConfigFile;
+use crate::project_config::apply_project_config;
+use crate::project_config::project_root;
 use crate::protocol::AskForApproval;
+use crate::protocol::ClientCapabilities;
 use crate::protocol::SandboxPolicy;
+use crate::rollout::redact::Redactor;
+use crate::secret_scan::SecretScanner;
+use crate::team_config::apply_team_config;
 use anyhow::Context;
 use codex_app_server_protocol::Tools;
 use codex_app_server_protocol::UserSavedConfig;
+use codex_keyring_store::OsKeyring;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::config_types::Verbosity;
+use codex_rmcp_client::OAuthCredentialsStore;
 use dirs::home_dir;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 use toml::Value as TomlValue;
 use toml_edit::Array as TomlArray;
@@ -53,6 +109,9 @@ pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
+/// Default directory of the response cache, relative to `CODEX_HOME`.
+const RESPONSE_CACHE_DIR: &str = "response_cache";
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -135,9 +194,20 @@ pub struct Config {
     /// Definition for MCP servers that Codex can reach out to for tool calls.
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Where the OAuth tokens of MCP servers are stored.
+    pub mcp_oauth_credentials_store: OAuthCredentialsStore,
+
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Models a turn fails over to, in order, when the configured model's
+    /// provider is down or rate limited.
+    pub model_fallbacks: Vec<ModelFallback>,
+
+    /// Whether the model may call several tools in one response. Read-only
+    /// tool calls of a response then run side by side.
+    pub parallel_tool_calls: bool,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
@@ -151,6 +221,112 @@ pub struct Config {
     /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
     pub history: History,
 
+    /// How and when the conversation history is compacted automatically.
+    pub auto_compaction: AutoCompaction,
+
+    /// Whether and how the model may delegate tasks to sub-agents.
+    pub sub_agents: SubAgents,
+
+    /// Persistent per-project memory shared across sessions.
+    pub memory: Memory,
+
+    /// Settings for the `web_fetch` tool.
+    pub web_fetch: WebFetch,
+
+    /// Settings for the `web_search` tool.
+    pub web_search: WebSearch,
+
+    /// Settings for the `http_request` tool.
+    pub http_request: HttpRequest,
+
+    /// Settings for the `browser` tool.
+    pub browser: Browser,
+
+    /// Size limits of the images the `view_image` tool attaches.
+    pub view_image: ViewImage,
+
+    /// Defaults of the `background_process` tool.
+    pub background_process: BackgroundProcess,
+
+    /// Settings for the `python` tool.
+    pub python: PythonKernel,
+
+    /// Settings for the `run_tests` tool.
+    pub run_tests: RunTests,
+
+    /// Model prices used to estimate turn costs, overriding the built-in
+    /// ones, keyed by model slug.
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Database connections the `query_database` tool can query, keyed by
+    /// name.
+    pub databases: HashMap<String, DatabaseConnection>,
+
+    /// Command tools declared by the user, keyed by tool name.
+    pub custom_tools: BTreeMap<String, CustomToolConfig>,
+
+    /// Config sections of plugins, custom tools and other extensions.
+    pub extensions: ExtensionConfigs,
+
+    /// Formatters and linters the `lint` tool runs, keyed by name.
+    pub linters: BTreeMap<String, LinterConfig>,
+
+    /// Settings for WebAssembly tool plugins.
+    pub plugins: Plugins,
+
+    /// Tools offered to the model, from the selected tool set and the
+    /// disabled tools.
+    pub tool_filter: ToolFilter,
+
+    /// Limits on tool calls, keyed by tool name or pattern.
+    pub tool_quotas: BTreeMap<String, ToolQuota>,
+
+    /// Seconds a tool call may run, keyed by tool name or pattern.
+    pub tool_timeouts: BTreeMap<String, u64>,
+
+    /// How long a session may sit without submissions before its background
+    /// processes, exec sessions and MCP connections are released. `None`
+    /// keeps them for the lifetime of the session.
+    pub idle_timeout: Option<Duration>,
+
+    /// Whether running sessions pick up edits to the config files.
+    pub watch_config: bool,
+
+    /// How submissions that would start a turn are handled while a turn is
+    /// running.
+    pub busy_session_policy: BusySessionPolicy,
+
+    /// Automatic titling of sessions shown in session pickers.
+    pub session_titles: SessionTitles,
+
+    /// Cache of model responses keyed on the request, when enabled.
+    pub response_cache: Option<ResponseCache>,
+
+    /// Tags attached to every session started or resumed with this
+    /// configuration.
+    pub session_tags: BTreeMap<String, String>,
+
+    /// Limits on tokens, cost, tool calls and time used by a session.
+    pub session_budget: SessionBudget,
+
+    /// Redaction applied to rollout files before they are written.
+    pub rollout_redaction: RolloutRedaction,
+
+    /// Credential scanning of staged changes and of the agent's commits.
+    pub secret_scan: SecretScan,
+
+    /// Settings for the `extract_archive` tool.
+    pub extract_archive: ExtractArchive,
+
+    /// Settings for the `mcp_resources` tool.
+    pub mcp_resources: McpResources,
+
+    /// Settings for the sampling requests of MCP servers.
+    pub mcp_sampling: McpSampling,
+
+    /// Health monitoring and reconnection of MCP servers.
+    pub mcp_health: McpHealth,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: UriBasedFileOpener,
@@ -199,14 +375,102 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `pin_context` tool that lets the agent pin files and notes
+    /// into its context.
+    pub include_pin_context_tool: bool,
+
+    /// Include the `search_code` tool that searches file contents in-process.
+    pub include_code_search_tool: bool,
+
+    /// Include the `read_file` tool that reads line ranges of a file.
+    pub include_read_file_tool: bool,
+
+    /// Include the `write_file` tool that replaces the content of a file.
+    pub include_write_file_tool: bool,
+
+    /// Include the `git` tool that runs git operations with structured
+    /// results.
+    pub include_git_tool: bool,
+
+    /// Include the `query_database` tool that runs read-only SQL queries.
+    pub include_database_tool: bool,
+
+    /// Include the `browser` tool that drives a headless browser.
+    pub include_browser_tool: bool,
+
+    /// Include the `python` tool that runs code in a persistent Python
+    /// process.
+    pub include_python_tool: bool,
+
+    /// Include the `system_info` tool that describes the host and its
+    /// toolchains.
+    pub include_system_info_tool: bool,
+
+    /// Include the `code_symbols` tool that finds and outlines symbols with
+    /// tree-sitter.
+    pub include_code_symbols_tool: bool,
+
+    /// Include the `run_tests` tool that runs the project's tests and parses
+    /// their results.
+    pub include_run_tests_tool: bool,
+
+    /// Include the `audit_dependencies` tool that reports outdated and
+    /// vulnerable dependencies.
+    pub include_audit_dependencies_tool: bool,
+
+    /// Include the `review_changes` tool that returns the session's changes
+    /// to the working tree as hunks.
+    pub include_review_changes_tool: bool,
+
+    /// Include the `scan_secrets` tool that scans staged changes for
+    /// credentials.
+    pub include_scan_secrets_tool: bool,
+
+    /// Include the `list_ports` tool that reports listening ports and their
+    /// owning processes.
+    pub include_list_ports_tool: bool,
+
+    /// Include the `batch_edit` tool that makes the same edit across many
+    /// files.
+    pub include_batch_edit_tool: bool,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
+    /// The profiles a session can switch to with `Op::SwitchProfile`, keyed
+    /// by name.
+    pub profiles: BTreeMap<String, SessionProfile>,
+
     /// When true, disables burst-paste detection for typed input entirely.
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: bool,
 
+    /// When true, commands from a single model response that all require
+    /// approval are surfaced as one `ApprovalBatchRequest` instead of being
+    /// prompted for one at a time.
+    pub approval_batching: bool,
+
+    /// When true, binary content in tool results (e.g. images returned by MCP
+    /// tools) is sent to clients as `PayloadFrame` events and referenced from
+    /// the tool-call event instead of being inlined as base64 strings.
+    pub binary_payload_framing: bool,
+
+    /// What the client driving this session can present to the user.
+    pub client_capabilities: ClientCapabilities,
+
+    /// When set, tool calls are answered with the outputs recorded in this
+    /// rollout file instead of being executed. Used by `replay_session`.
+    pub replay_rollout: Option<PathBuf>,
+
+    /// The project's `.codex/config.toml`, when the workspace has one. It is
+    /// only applied when the workspace is trusted.
+    pub project_config: Option<ProjectConfigFile>,
+
+    /// Pattern of the `directory_policies` rule that set the approval or
+    /// sandbox policy for this workspace, if one did.
+    pub directory_policy: Option<String>,
+
     /// OTEL configuration (exporter type, endpoint, headers, etc.).
     pub otel: crate::config_types::OtelConfig,
 }
@@ -216,8 +480,9 @@ impl Config {
     /// **in between** the values parsed from `config.toml` and the
     /// strongly-typed overrides specified via [`ConfigOverrides`].
     ///
-    /// The precedence order is therefore: `config.toml` < `-c` overrides <
-    /// `ConfigOverrides`.
+    /// The precedence order is therefore: the team config < `config.toml` <
+    /// the project's `.codex/config.toml` (when the project is trusted) <
+    /// `-c` overrides < `ConfigOverrides`.
     pub fn load_with_cli_overrides(
         cli_overrides: Vec<(String, TomlValue)>,
         overrides: ConfigOverrides,
@@ -227,23 +492,54 @@ impl Config {
         // `Config` instance.
         let codex_home = find_codex_home()?;
 
-        // Step 1: parse `config.toml` into a generic JSON value.
+        // Step 1: point out settings an earlier version of Codex named
+        // differently, then parse `config.toml` into a generic JSON value.
+        match migrate_config(&codex_home, true) {
+            Ok(report) => {
+                for change in &report.changes {
+                    tracing::warn!(
+                        "config.toml sets `{}`, which is now `{}`; run `codex config-migrate` to update it",
+                        change.from,
+                        change.to
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("failed to check config.toml for old settings: {e}"),
+        }
         let mut root_value = load_config_as_toml(&codex_home)?;
 
-        // Step 2: apply the `-c` overrides.
+        // Step 2: place the team config beneath it.
+        apply_team_config(&mut root_value, &codex_home)?;
+
+        // Step 3: overlay the project config of a trusted workspace.
+        let cwd = match &overrides.cwd {
+            Some(cwd) if cwd.is_absolute() => cwd.clone(),
+            Some(cwd) => std::env::current_dir()?.join(cwd),
+            None => std::env::current_dir()?,
+        };
+        let project_config = apply_project_config(&mut root_value, &cwd, &codex_home)?;
+
+        // Step 4: apply the `-c` overrides.
         for (path, value) in cli_overrides.into_iter() {
             apply_toml_override(&mut root_value, &path, value);
         }
 
-        // Step 3: deserialize into `ConfigToml` so that Serde can enforce the
+        // Step 5: resolve `${VAR}` and `$(command)` in the settings that
+        // carry secrets, and decrypt the `enc:v1:` values.
+        interpolate_config(&mut root_value)?;
+        decrypt_config(&mut root_value, &OsKeyring)?;
+
+        // Step 6: deserialize into `ConfigToml` so that Serde can enforce the
         // correct types.
         let cfg: ConfigToml = root_value.try_into().map_err(|e| {
             tracing::error!("Failed to deserialize overridden config: {e}");
             std::io::Error::new(std::io::ErrorKind::InvalidData, e)
         })?;
 
-        // Step 4: merge with the strongly-typed overrides.
-        Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)
+        // Step 7: merge with the strongly-typed overrides.
+        let mut config = Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)?;
+        config.project_config = project_config;
+        Ok(config)
     }
 }
 
@@ -265,13 +561,22 @@ pub fn load_config_as_toml_with_cli_overrides(
     Ok(cfg)
 }
 
-/// Read `CODEX_HOME/config.toml` and return it as a generic TOML value. Returns
-/// an empty TOML table when the file does not exist.
+/// Read `CODEX_HOME/config.toml` and return it as a generic TOML value, with
+/// the `[when.*]` sections matching this host applied and the files it
+/// includes merged in. Returns an empty TOML table when the file does not
+/// exist.
 pub fn load_config_as_toml(codex_home: &Path) -> std::io::Result<TomlValue> {
     let config_path = codex_home.join(CONFIG_TOML_FILE);
     match std::fs::read_to_string(&config_path) {
         Ok(contents) => match toml::from_str::<TomlValue>(&contents) {
-            Ok(val) => Ok(val),
+            Ok(mut val) => {
+                let host = Host::current();
+                apply_conditional_sections(&mut val, &host);
+                resolve_includes(&mut val, &config_path, &host).inspect_err(|e| {
+                    tracing::error!("Failed to include config files: {e}");
+                })?;
+                Ok(val)
+            }
             Err(e) => {
                 tracing::error!("Failed to parse config.toml: {e}");
                 Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
@@ -302,6 +607,28 @@ pub fn load_global_mcp_servers(
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
 }
 
+/// The MCP server `name` from `CODEX_HOME/config.toml`, with its settings
+/// interpolated for connecting to it.
+pub(crate) fn load_global_mcp_server(
+    codex_home: &Path,
+    name: &str,
+) -> std::io::Result<Option<McpServerConfig>> {
+    let root_value = load_config_as_toml(codex_home)?;
+    let Some(mut server) = root_value
+        .get("mcp_servers")
+        .and_then(|servers| servers.get(name))
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    interpolate_mcp_server(name, &mut server)?;
+    decrypt_config(&mut server, &OsKeyring)?;
+    server
+        .try_into()
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 pub fn write_global_mcp_servers(
     codex_home: &Path,
     servers: &BTreeMap<String, McpServerConfig>,
@@ -350,11 +677,31 @@ pub fn write_global_mcp_servers(
                         entry["env"] = TomlItem::Table(env_table);
                     }
                 }
-                McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+                McpServerTransportConfig::StreamableHttp {
+                    url,
+                    bearer_token,
+                    bearer_token_env_var,
+                    http_headers,
+                } => {
                     entry["url"] = toml_edit::value(url.clone());
                     if let Some(token) = bearer_token {
                         entry["bearer_token"] = toml_edit::value(token.clone());
                     }
+                    if let Some(var) = bearer_token_env_var {
+                        entry["bearer_token_env_var"] = toml_edit::value(var.clone());
+                    }
+                    if let Some(headers) = http_headers
+                        && !headers.is_empty()
+                    {
+                        let mut headers_table = TomlTable::new();
+                        headers_table.set_implicit(false);
+                        let mut pairs: Vec<_> = headers.iter().collect();
+                        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                        for (key, value) in pairs {
+                            headers_table.insert(key, toml_edit::value(value.clone()));
+                        }
+                        entry["http_headers"] = TomlItem::Table(headers_table);
+                    }
                 }
             }
 
@@ -366,6 +713,64 @@ pub fn write_global_mcp_servers(
                 entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
             }
 
+            if let Some(prefix) = &config.tool_prefix {
+                entry["tool_prefix"] = toml_edit::value(prefix.clone());
+            }
+
+            if let Some(limit) = config.max_concurrent_calls {
+                entry["max_concurrent_calls"] = toml_edit::value(limit as i64);
+            }
+
+            if let Some(limit) = config.max_result_bytes {
+                entry["max_result_bytes"] = toml_edit::value(limit as i64);
+            }
+
+            if !config.tool_aliases.is_empty() {
+                let mut aliases_table = TomlTable::new();
+                aliases_table.set_implicit(false);
+                let mut pairs: Vec<_> = config.tool_aliases.iter().collect();
+                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (tool, alias) in pairs {
+                    aliases_table.insert(tool, toml_edit::value(alias.clone()));
+                }
+                entry["tool_aliases"] = TomlItem::Table(aliases_table);
+            }
+
+            if let Some(tools) = &config.enabled_tools {
+                let mut tools_array = TomlArray::new();
+                for tool in tools {
+                    tools_array.push(tool.clone());
+                }
+                entry["enabled_tools"] = TomlItem::Value(tools_array.into());
+            }
+
+            if !config.disabled_tools.is_empty() {
+                let mut tools_array = TomlArray::new();
+                for tool in &config.disabled_tools {
+                    tools_array.push(tool.clone());
+                }
+                entry["disabled_tools"] = TomlItem::Value(tools_array.into());
+            }
+
+            let approval_value = |approval: McpToolApproval| match approval {
+                McpToolApproval::Auto => "auto",
+                McpToolApproval::Ask => "ask",
+            };
+            if let Some(approval) = config.tool_approval {
+                entry["tool_approval"] = toml_edit::value(approval_value(approval));
+            }
+
+            if !config.tool_approvals.is_empty() {
+                let mut approvals_table = TomlTable::new();
+                approvals_table.set_implicit(false);
+                let mut pairs: Vec<_> = config.tool_approvals.iter().collect();
+                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (tool, approval) in pairs {
+                    approvals_table.insert(tool, toml_edit::value(approval_value(*approval)));
+                }
+                entry["tool_approvals"] = TomlItem::Table(approvals_table);
+            }
+
             doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
         }
     }
@@ -624,7 +1029,7 @@ fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
 }
 
 /// Base config deserialized from ~/.codex/config.toml.
-#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
 pub struct ConfigToml {
     /// Optional override of model selection.
     pub model: Option<String>,
@@ -666,10 +1071,30 @@ pub struct ConfigToml {
     #[serde(default)]
     pub mcp_servers: HashMap<String, McpServerConfig>,
 
+    /// Where the OAuth tokens of MCP servers are stored: `auto` (default),
+    /// `keychain` or `file`.
+    pub mcp_oauth_credentials_store: Option<OAuthCredentialsStore>,
+
+    /// Organization defaults loaded beneath this file.
+    pub team_config: Option<TeamConfigToml>,
+
+    /// Approval and sandbox policies by directory, keyed by a path pattern
+    /// matched against the workspace root and its parent directories.
+    #[serde(default)]
+    pub directory_policies: BTreeMap<String, DirectoryPolicyToml>,
+
     /// User-defined provider entries that extend/override the built-in list.
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Models to fail over to, in order, when the provider of the configured
+    /// model is unavailable or rate limited.
+    #[serde(default)]
+    pub model_fallbacks: Vec<ModelFallbackToml>,
+
+    /// Let the model call several tools in one response.
+    pub parallel_tool_calls: Option<bool>,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
@@ -687,6 +1112,138 @@ pub struct ConfigToml {
     #[serde(default)]
     pub history: Option<History>,
 
+    /// Automatic context compaction settings.
+    #[serde(default)]
+    pub auto_compaction: Option<AutoCompaction>,
+
+    /// Sub-agent delegation settings.
+    #[serde(default)]
+    pub sub_agents: Option<SubAgents>,
+
+    /// Persistent project memory settings.
+    #[serde(default)]
+    pub memory: Option<Memory>,
+
+    /// `web_fetch` tool settings.
+    #[serde(default)]
+    pub web_fetch: Option<WebFetch>,
+
+    /// `web_search` tool settings.
+    #[serde(default)]
+    pub web_search: Option<WebSearch>,
+
+    /// `http_request` tool settings.
+    #[serde(default)]
+    pub http_request: Option<HttpRequest>,
+
+    /// `browser` tool settings.
+    #[serde(default)]
+    pub browser: Option<Browser>,
+
+    /// Size limits of the images `view_image` attaches.
+    #[serde(default)]
+    pub view_image: Option<ViewImage>,
+
+    /// `background_process` tool defaults.
+    #[serde(default)]
+    pub background_process: Option<BackgroundProcess>,
+
+    /// `python` tool settings.
+    #[serde(default)]
+    pub python: Option<PythonKernel>,
+
+    /// `run_tests` tool settings.
+    #[serde(default)]
+    pub run_tests: Option<RunTests>,
+
+    /// Model prices used to estimate turn costs, keyed by model slug.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Named database connections for the `query_database` tool.
+    #[serde(default)]
+    pub databases: HashMap<String, DatabaseConnection>,
+
+    /// Command tools offered to the model, keyed by tool name.
+    #[serde(default)]
+    pub custom_tools: BTreeMap<String, CustomToolConfig>,
+
+    /// `[extensions.<name>]` sections, the settings of extensions, keyed by
+    /// the extension's name.
+    #[serde(default)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+
+    /// Formatters and linters for the `lint` tool, keyed by name.
+    #[serde(default)]
+    pub linters: BTreeMap<String, LinterConfig>,
+
+    /// WebAssembly tool plugin settings.
+    #[serde(default)]
+    pub plugins: Option<Plugins>,
+
+    /// Named lists of tools, by name or `*` pattern, that `tool_set` can
+    /// restrict the model to.
+    #[serde(default)]
+    pub tool_sets: HashMap<String, Vec<String>>,
+
+    /// Name of the entry of `tool_sets` listing the tools offered to the
+    /// model. All tools are offered when unset.
+    pub tool_set: Option<String>,
+
+    /// Tools, by name or `*` pattern, never offered to the model.
+    pub disabled_tools: Option<Vec<String>>,
+
+    /// Limits on tool calls, keyed by tool name or `*` pattern.
+    #[serde(default)]
+    pub tool_quotas: BTreeMap<String, ToolQuota>,
+
+    /// Seconds a tool call may run, keyed by tool name or `*` pattern.
+    #[serde(default)]
+    pub tool_timeouts: BTreeMap<String, u64>,
+
+    /// Seconds without submissions after which an idle session releases its
+    /// background processes and MCP connections.
+    pub idle_timeout_sec: Option<u64>,
+
+    /// Apply edits of the config files to running sessions. Defaults to true.
+    pub watch_config: Option<bool>,
+
+    /// Whether turn input arriving during a turn is injected, queued or
+    /// rejected.
+    #[serde(default)]
+    pub busy_session_policy: Option<BusySessionPolicy>,
+
+    /// Automatic titling of sessions.
+    pub session_titles: Option<SessionTitles>,
+
+    /// Record model responses and replay them for identical requests.
+    pub response_cache: Option<ResponseCacheToml>,
+
+    /// Tags attached to sessions, such as a ticket id.
+    #[serde(default)]
+    pub session_tags: BTreeMap<String, String>,
+
+    /// Limits enforced on each session.
+    pub session_budget: Option<SessionBudget>,
+
+    /// Redaction of API keys, emails and custom patterns in rollout files.
+    pub rollout_redaction: Option<RolloutRedaction>,
+
+    /// Scanning of staged changes for credentials before commits.
+    pub secret_scan: Option<SecretScan>,
+
+    /// `extract_archive` tool settings.
+    pub extract_archive: Option<ExtractArchive>,
+
+    /// `mcp_resources` tool settings.
+    pub mcp_resources: Option<McpResources>,
+
+    /// Sampling requests of MCP servers.
+    pub mcp_sampling: Option<McpSampling>,
+
+    /// Health monitoring of MCP servers.
+    pub mcp_health: Option<McpHealth>,
+
     /// Optional URI-based file opener. If set, citations to files in the model
     /// output will be hyperlinked using the specified URI scheme.
     pub file_opener: Option<UriBasedFileOpener>,
@@ -733,6 +1290,14 @@ pub struct ConfigToml {
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: Option<bool>,
 
+    /// When true, batch approval requests for commands issued in the same
+    /// model response. Defaults to `false`.
+    pub approval_batching: Option<bool>,
+
+    /// When true, send binary tool output as framed payloads. Defaults to
+    /// `false`.
+    pub binary_payload_framing: Option<bool>,
+
     /// OTEL configuration.
     pub otel: Option<crate::config_types::OtelConfigToml>,
 }
@@ -760,12 +1325,12 @@ impl From<ConfigToml> for UserSavedConfig {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
 pub struct ProjectConfig {
     pub trust_level: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
 pub struct ToolsToml {
     #[serde(default, alias = "web_search_request")]
     pub web_search: Option<bool>,
@@ -773,6 +1338,82 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `pin_context` tool that lets the agent pin files and notes
+    /// into its context.
+    #[serde(default)]
+    pub pin_context: Option<bool>,
+
+    /// Enable the `search_code` tool that searches file contents without
+    /// relying on `rg`.
+    #[serde(default)]
+    pub code_search: Option<bool>,
+
+    /// Enable the `read_file` tool that reads line ranges of a file.
+    #[serde(default)]
+    pub read_file: Option<bool>,
+
+    /// Enable the `write_file` tool that replaces the content of a file.
+    #[serde(default)]
+    pub write_file: Option<bool>,
+
+    /// Enable the `git` tool that runs git operations with structured
+    /// results.
+    #[serde(default)]
+    pub git: Option<bool>,
+
+    /// Enable the `query_database` tool that runs read-only SQL queries.
+    #[serde(default)]
+    pub database: Option<bool>,
+
+    /// Enable the `browser` tool that drives a headless Chrome or Chromium.
+    #[serde(default)]
+    pub browser: Option<bool>,
+
+    /// Enable the `python` tool that runs code in a persistent Python
+    /// process.
+    #[serde(default)]
+    pub python: Option<bool>,
+
+    /// Enable the `system_info` tool that describes the host and its
+    /// toolchains.
+    #[serde(default)]
+    pub system_info: Option<bool>,
+
+    /// Enable the `code_symbols` tool that finds and outlines symbols with
+    /// tree-sitter.
+    #[serde(default)]
+    pub code_symbols: Option<bool>,
+
+    /// Enable the `run_tests` tool that runs the project's tests and parses
+    /// their results.
+    #[serde(default)]
+    pub run_tests: Option<bool>,
+
+    /// Enable the `audit_dependencies` tool that reports outdated and
+    /// vulnerable dependencies.
+    #[serde(default)]
+    pub audit_dependencies: Option<bool>,
+
+    /// Enable the `review_changes` tool that returns the session's changes
+    /// to the working tree as hunks.
+    #[serde(default)]
+    pub review_changes: Option<bool>,
+
+    /// Enable the `scan_secrets` tool that scans staged changes for
+    /// credentials.
+    #[serde(default)]
+    pub scan_secrets: Option<bool>,
+
+    /// Enable the `list_ports` tool that reports listening ports and their
+    /// owning processes.
+    #[serde(default)]
+    pub list_ports: Option<bool>,
+
+    /// Enable the `batch_edit` tool that makes the same edit across many
+    /// files.
+    #[serde(default)]
+    pub batch_edit: Option<bool>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -810,6 +1451,45 @@ impl ConfigToml {
         }
     }
 
+    /// Resolve the tools offered to the model under `profile`, falling back
+    /// to the top-level `tool_set` and `disabled_tools`.
+    pub(crate) fn resolve_tool_filter(
+        &self,
+        profile: &ConfigProfile,
+    ) -> std::io::Result<ToolFilter> {
+        let tool_set = match profile.tool_set.as_ref().or(self.tool_set.as_ref()) {
+            Some(name) => Some(self.tool_sets.get(name).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("tool set `{name}` not found in tool_sets"),
+                )
+            })?),
+            None => None,
+        };
+        Ok(ToolFilter::new(
+            tool_set.map(Vec::as_slice),
+            profile
+                .disabled_tools
+                .as_ref()
+                .or(self.disabled_tools.as_ref())
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        ))
+    }
+
+    /// Resolve `profile` into the settings a running session switches to.
+    fn resolve_session_profile(&self, profile: &ConfigProfile) -> std::io::Result<SessionProfile> {
+        Ok(SessionProfile {
+            model: profile.model.clone(),
+            model_reasoning_effort: profile.model_reasoning_effort,
+            approval_policy: profile.approval_policy,
+            sandbox_policy: profile
+                .sandbox_mode
+                .map(|mode| self.derive_sandbox_policy(Some(mode))),
+            tool_filter: self.resolve_tool_filter(profile)?,
+        })
+    }
+
     pub fn is_cwd_trusted(&self, resolved_cwd: &Path) -> bool {
         let projects = self.projects.clone().unwrap_or_default();
 
@@ -875,6 +1555,7 @@ pub struct ConfigOverrides {
     pub include_view_image_tool: Option<bool>,
     pub show_raw_agent_reasoning: Option<bool>,
     pub tools_web_search_request: Option<bool>,
+    pub client_capabilities: Option<ClientCapabilities>,
 }
 
 impl Config {
@@ -903,7 +1584,9 @@ impl Config {
             include_view_image_tool,
             show_raw_agent_reasoning,
             tools_web_search_request: override_tools_web_search_request,
+            client_capabilities,
         } = overrides;
+        let client_capabilities = client_capabilities.unwrap_or_default();
 
         let active_profile_name = config_profile_key
             .as_ref()
@@ -923,7 +1606,42 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
-        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
+        let resolved_cwd = {
+            use std::env;
+
+            match cwd {
+                None => {
+                    tracing::info!("cwd not set, using current dir");
+                    env::current_dir()?
+                }
+                Some(p) if p.is_absolute() => p,
+                Some(p) => {
+                    // Resolve relative path against the current working directory.
+                    tracing::info!("cwd is relative, resolving against current dir");
+                    let mut current = env::current_dir()?;
+                    current.push(p);
+                    current
+                }
+            }
+        };
+
+        let (directory_policy, directory_policy_rule) =
+            match resolve_directory_policy(&cfg.directory_policies, &project_root(&resolved_cwd)) {
+                Some((pattern, rule)) => (Some(pattern.to_string()), rule.clone()),
+                None => (None, DirectoryPolicyToml::default()),
+            };
+        let sandbox_policy = cfg.derive_sandbox_policy(
+            sandbox_mode
+                .or(directory_policy_rule.sandbox_mode)
+                .or(config_profile.sandbox_mode),
+        );
+
+        let tool_filter = cfg.resolve_tool_filter(&config_profile)?;
+        let profiles = cfg
+            .profiles
+            .iter()
+            .map(|(name, profile)| Ok((name.clone(), cfg.resolve_session_profile(profile)?)))
+            .collect::<std::io::Result<BTreeMap<_, _>>>()?;
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -945,28 +1663,74 @@ impl Config {
             })?
             .clone();
 
+        let model_fallbacks = cfg
+            .model_fallbacks
+            .iter()
+            .map(|fallback| {
+                let provider_id = fallback
+                    .model_provider
+                    .clone()
+                    .unwrap_or_else(|| model_provider_id.clone());
+                let provider = model_providers.get(&provider_id).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!(
+                            "Model provider `{provider_id}` of fallback model `{}` not found",
+                            fallback.model
+                        ),
+                    )
+                })?;
+                Ok(ModelFallback {
+                    model: fallback.model.clone(),
+                    model_provider_id: provider_id,
+                    model_provider: provider.clone(),
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
         let shell_environment_policy = cfg.shell_environment_policy.into();
 
-        let resolved_cwd = {
-            use std::env;
+        let history = cfg.history.unwrap_or_default();
 
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
-            }
-        };
+        // Reject bad redaction patterns up front rather than when the first
+        // rollout is opened.
+        let rollout_redaction = cfg.rollout_redaction.unwrap_or_default();
+        Redactor::new(&rollout_redaction).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid rollout_redaction pattern: {e}"),
+            )
+        })?;
+        let secret_scan = cfg.secret_scan.unwrap_or_default();
+        SecretScanner::new(&secret_scan).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid secret_scan pattern: {e}"),
+            )
+        })?;
 
-        let history = cfg.history.unwrap_or_default();
+        let web_search = cfg.web_search.unwrap_or_default();
+        if matches!(
+            web_search.provider,
+            Some(WebSearchProvider::Searxng | WebSearchProvider::Custom)
+        ) && web_search.base_url.is_none()
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "web_search.base_url is required for the searxng and custom providers",
+            ));
+        }
+
+        let extensions = ExtensionConfigs::new(cfg.extensions.clone());
+        for (name, tool) in &cfg.custom_tools {
+            crate::custom_tools::validate(name, tool)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            if let Some(schema) = &tool.config_schema {
+                extensions
+                    .validated(name, schema)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            }
+        }
 
         let tools_web_search_request = override_tools_web_search_request
             .or(cfg.tools.as_ref().and_then(|t| t.web_search))
@@ -1017,6 +1781,15 @@ impl Config {
             Self::get_base_instructions(experimental_instructions_path, &resolved_cwd)?;
         let base_instructions = base_instructions.or(file_base_instructions);
 
+        let response_cache = cfg.response_cache.map(|cache| ResponseCache {
+            dir: codex_home.join(
+                cache
+                    .dir
+                    .unwrap_or_else(|| PathBuf::from(RESPONSE_CACHE_DIR)),
+            ),
+            mode: cache.mode,
+        });
+
         // Default review model when not set in config; allow CLI override to take precedence.
         let review_model = override_review_model
             .or(cfg.review_model)
@@ -1032,10 +1805,13 @@ impl Config {
             model_provider_id,
             model_provider,
             cwd: resolved_cwd,
-            approval_policy: approval_policy
-                .or(config_profile.approval_policy)
-                .or(cfg.approval_policy)
-                .unwrap_or_else(AskForApproval::default),
+            approval_policy: client_capabilities.effective_approval_policy(
+                approval_policy
+                    .or(directory_policy_rule.approval_policy)
+                    .or(config_profile.approval_policy)
+                    .or(cfg.approval_policy)
+                    .unwrap_or_else(AskForApproval::default),
+            ),
             sandbox_policy,
             shell_environment_policy,
             notify: cfg.notify,
@@ -1043,6 +1819,8 @@ impl Config {
             base_instructions,
             mcp_servers: cfg.mcp_servers,
             model_providers,
+            model_fallbacks,
+            parallel_tool_calls: cfg.parallel_tool_calls.unwrap_or(false),
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             project_doc_fallback_filenames: cfg
                 .project_doc_fallback_filenames
@@ -1059,6 +1837,40 @@ impl Config {
                 .collect(),
             codex_home,
             history,
+            auto_compaction: cfg.auto_compaction.unwrap_or_default(),
+            sub_agents: cfg.sub_agents.unwrap_or_default(),
+            memory: cfg.memory.unwrap_or_default(),
+            web_fetch: cfg.web_fetch.unwrap_or_default(),
+            web_search,
+            http_request: cfg.http_request.unwrap_or_default(),
+            browser: cfg.browser.unwrap_or_default(),
+            view_image: cfg.view_image.unwrap_or_default(),
+            background_process: cfg.background_process.unwrap_or_default(),
+            python: cfg.python.unwrap_or_default(),
+            run_tests: cfg.run_tests.unwrap_or_default(),
+            model_pricing: cfg.model_pricing,
+            databases: cfg.databases,
+            custom_tools: cfg.custom_tools,
+            extensions,
+            linters: cfg.linters,
+            plugins: cfg.plugins.unwrap_or_default(),
+            tool_filter,
+            tool_quotas: cfg.tool_quotas,
+            tool_timeouts: cfg.tool_timeouts,
+            idle_timeout: cfg.idle_timeout_sec.map(Duration::from_secs),
+            watch_config: cfg.watch_config.unwrap_or(true),
+            mcp_oauth_credentials_store: cfg.mcp_oauth_credentials_store.unwrap_or_default(),
+            busy_session_policy: cfg.busy_session_policy.unwrap_or_default(),
+            session_titles: cfg.session_titles.unwrap_or_default(),
+            response_cache,
+            session_tags: cfg.session_tags,
+            session_budget: cfg.session_budget.unwrap_or_default(),
+            rollout_redaction,
+            secret_scan,
+            extract_archive: cfg.extract_archive.unwrap_or_default(),
+            mcp_resources: cfg.mcp_resources.unwrap_or_default(),
+            mcp_sampling: cfg.mcp_sampling.unwrap_or_default(),
+            mcp_health: cfg.mcp_health.unwrap_or_default(),
             file_opener: cfg.file_opener.unwrap_or(UriBasedFileOpener::VsCode),
             codex_linux_sandbox_exe,
 
@@ -1090,8 +1902,79 @@ impl Config {
                 .unwrap_or(false),
             use_experimental_use_rmcp_client: cfg.experimental_use_rmcp_client.unwrap_or(false),
             include_view_image_tool,
+            include_pin_context_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.pin_context)
+                .unwrap_or(false),
+            include_code_search_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.code_search)
+                .unwrap_or(false),
+            include_read_file_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.read_file)
+                .unwrap_or(false),
+            include_write_file_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.write_file)
+                .unwrap_or(false),
+            include_git_tool: cfg.tools.as_ref().and_then(|t| t.git).unwrap_or(false),
+            include_database_tool: cfg.tools.as_ref().and_then(|t| t.database).unwrap_or(false),
+            include_browser_tool: cfg.tools.as_ref().and_then(|t| t.browser).unwrap_or(false),
+            include_python_tool: cfg.tools.as_ref().and_then(|t| t.python).unwrap_or(false),
+            include_system_info_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.system_info)
+                .unwrap_or(false),
+            include_code_symbols_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.code_symbols)
+                .unwrap_or(false),
+            include_run_tests_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.run_tests)
+                .unwrap_or(false),
+            include_audit_dependencies_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.audit_dependencies)
+                .unwrap_or(false),
+            include_review_changes_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.review_changes)
+                .unwrap_or(false),
+            include_scan_secrets_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.scan_secrets)
+                .unwrap_or(false),
+            include_list_ports_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.list_ports)
+                .unwrap_or(false),
+            include_batch_edit_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.batch_edit)
+                .unwrap_or(false),
             active_profile: active_profile_name,
+            profiles,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
+            approval_batching: cfg.approval_batching.unwrap_or(false),
+            binary_payload_framing: cfg.binary_payload_framing.unwrap_or(false),
+            client_capabilities,
+            replay_rollout: None,
+            project_config: None,
+            directory_policy,
             tui_notifications: cfg
                 .tui
                 .as_ref()
@@ -1354,6 +2237,14 @@ exclude_slash_tmp = true
                 },
                 startup_timeout_sec: Some(Duration::from_secs(3)),
                 tool_timeout_sec: Some(Duration::from_secs(5)),
+                tool_prefix: None,
+                tool_aliases: HashMap::new(),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
             },
         );
 
@@ -1420,6 +2311,14 @@ startup_timeout_ms = 2500
                 },
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                tool_prefix: None,
+                tool_aliases: HashMap::new(),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
             },
         )]);
 
@@ -1467,9 +2366,19 @@ ZIG_VAR = "3"
                 transport: McpServerTransportConfig::StreamableHttp {
                     url: "https://example.com/mcp".to_string(),
                     bearer_token: Some("secret-token".to_string()),
+                    bearer_token_env_var: None,
+                    http_headers: None,
                 },
                 startup_timeout_sec: Some(Duration::from_secs(2)),
                 tool_timeout_sec: None,
+                tool_prefix: None,
+                tool_aliases: HashMap::new(),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
             },
         )]);
 
@@ -1489,7 +2398,9 @@ startup_timeout_sec = 2.0
         let loaded = load_global_mcp_servers(codex_home.path())?;
         let docs = loaded.get("docs").expect("docs entry");
         match &docs.transport {
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpServerTransportConfig::StreamableHttp {
+                url, bearer_token, ..
+            } => {
                 assert_eq!(url, "https://example.com/mcp");
                 assert_eq!(bearer_token.as_deref(), Some("secret-token"));
             }
@@ -1503,9 +2414,19 @@ startup_timeout_sec = 2.0
                 transport: McpServerTransportConfig::StreamableHttp {
                     url: "https://example.com/mcp".to_string(),
                     bearer_token: None,
+                    bearer_token_env_var: None,
+                    http_headers: None,
                 },
                 startup_timeout_sec: None,
                 tool_timeout_sec: None,
+                tool_prefix: None,
+                tool_aliases: HashMap::new(),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
             },
         );
         write_global_mcp_servers(codex_home.path(), &servers)?;
@@ -1521,7 +2442,9 @@ url = "https://example.com/mcp"
         let loaded = load_global_mcp_servers(codex_home.path())?;
         let docs = loaded.get("docs").expect("docs entry");
         match &docs.transport {
-            McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
+            McpServerTransportConfig::StreamableHttp {
+                url, bearer_token, ..
+            } => {
                 assert_eq!(url, "https://example.com/mcp");
                 assert!(bearer_token.is_none());
             }
@@ -1531,6 +2454,103 @@ url = "https://example.com/mcp"
         Ok(())
     }
 
+    #[test]
+    fn write_global_mcp_servers_serializes_http_headers_and_token_env_var() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        let servers = BTreeMap::from([(
+            "docs".to_string(),
+            McpServerConfig {
+                transport: McpServerTransportConfig::StreamableHttp {
+                    url: "https://example.com/mcp".to_string(),
+                    bearer_token: None,
+                    bearer_token_env_var: Some("DOCS_TOKEN".to_string()),
+                    http_headers: Some(HashMap::from([(
+                        "X-Team".to_string(),
+                        "infra".to_string(),
+                    )])),
+                },
+                startup_timeout_sec: None,
+                tool_timeout_sec: None,
+                tool_prefix: None,
+                tool_aliases: HashMap::new(),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
+            },
+        )]);
+
+        write_global_mcp_servers(codex_home.path(), &servers)?;
+
+        let serialized = std::fs::read_to_string(codex_home.path().join(CONFIG_TOML_FILE))?;
+        assert_eq!(
+            serialized,
+            r#"[mcp_servers.docs]
+url = "https://example.com/mcp"
+bearer_token_env_var = "DOCS_TOKEN"
+
+[mcp_servers.docs.http_headers]
+X-Team = "infra"
+"#
+        );
+
+        let loaded = load_global_mcp_servers(codex_home.path())?;
+        assert_eq!(loaded.get("docs"), servers.get("docs"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_global_mcp_servers_serializes_tool_naming() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        let servers = BTreeMap::from([(
+            "github".to_string(),
+            McpServerConfig {
+                transport: McpServerTransportConfig::Stdio {
+                    command: "github-mcp".to_string(),
+                    args: Vec::new(),
+                    env: None,
+                },
+                startup_timeout_sec: None,
+                tool_timeout_sec: None,
+                tool_prefix: Some("gh".to_string()),
+                tool_aliases: HashMap::from([(
+                    "search_repositories".to_string(),
+                    "repo_search".to_string(),
+                )]),
+                max_concurrent_calls: None,
+                max_result_bytes: None,
+                enabled_tools: None,
+                disabled_tools: Vec::new(),
+                tool_approval: None,
+                tool_approvals: HashMap::new(),
+            },
+        )]);
+
+        write_global_mcp_servers(codex_home.path(), &servers)?;
+
+        let serialized = std::fs::read_to_string(codex_home.path().join(CONFIG_TOML_FILE))?;
+        assert_eq!(
+            serialized,
+            r#"[mcp_servers.github]
+command = "github-mcp"
+tool_prefix = "gh"
+
+[mcp_servers.github.tool_aliases]
+search_repositories = "repo_search"
+"#
+        );
+
+        let loaded = load_global_mcp_servers(codex_home.path())?;
+        assert_eq!(loaded.get("github"), servers.get("github"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn persist_model_selection_updates_defaults() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1680,6 +2700,7 @@ model = "gpt-5-codex"
         model_provider_map: HashMap<String, ModelProviderInfo>,
         openai_provider: ModelProviderInfo,
         openai_chat_completions_provider: ModelProviderInfo,
+        profiles: BTreeMap<String, SessionProfile>,
     }
 
     impl PrecedenceTestFixture {
@@ -1760,6 +2781,10 @@ model_verbosity = "high"
             stream_max_retries: Some(10),
             stream_idle_timeout_ms: Some(300_000),
             requires_openai_auth: false,
+            tool_calling: crate::ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -1775,6 +2800,40 @@ model_verbosity = "high"
             .expect("openai provider should exist")
             .clone();
 
+        let profile =
+            |model: &str,
+             approval_policy: Option<AskForApproval>,
+             model_reasoning_effort: Option<ReasoningEffort>| SessionProfile {
+                model: Some(model.to_string()),
+                model_reasoning_effort,
+                approval_policy,
+                sandbox_policy: None,
+                tool_filter: ToolFilter::default(),
+            };
+        let profiles = BTreeMap::from([
+            (
+                "o3".to_string(),
+                profile(
+                    "o3",
+                    Some(AskForApproval::Never),
+                    Some(ReasoningEffort::High),
+                ),
+            ),
+            ("gpt3".to_string(), profile("gpt-3.5-turbo", None, None)),
+            (
+                "zdr".to_string(),
+                profile("o3", Some(AskForApproval::OnFailure), None),
+            ),
+            (
+                "gpt5".to_string(),
+                profile(
+                    "gpt-5",
+                    Some(AskForApproval::OnFailure),
+                    Some(ReasoningEffort::High),
+                ),
+            ),
+        ]);
+
         Ok(PrecedenceTestFixture {
             cwd: cwd_temp_dir,
             codex_home: codex_home_temp_dir,
@@ -1782,6 +2841,7 @@ model_verbosity = "high"
             model_provider_map,
             openai_provider,
             openai_chat_completions_provider,
+            profiles,
         })
     }
 
@@ -1829,10 +2889,46 @@ model_verbosity = "high"
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
+                model_fallbacks: Vec::new(),
+                parallel_tool_calls: false,
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 project_doc_fallback_filenames: Vec::new(),
                 codex_home: fixture.codex_home(),
                 history: History::default(),
+                auto_compaction: AutoCompaction::default(),
+                sub_agents: SubAgents::default(),
+                memory: Memory::default(),
+                web_fetch: WebFetch::default(),
+                web_search: WebSearch::default(),
+                http_request: HttpRequest::default(),
+                browser: Browser::default(),
+                view_image: ViewImage::default(),
+                background_process: BackgroundProcess::default(),
+                python: PythonKernel::default(),
+                run_tests: RunTests::default(),
+                model_pricing: HashMap::new(),
+                databases: HashMap::new(),
+                custom_tools: BTreeMap::new(),
+                extensions: ExtensionConfigs::default(),
+                linters: BTreeMap::new(),
+                plugins: Plugins::default(),
+                tool_filter: ToolFilter::default(),
+                tool_quotas: BTreeMap::new(),
+                tool_timeouts: BTreeMap::new(),
+                idle_timeout: None,
+                watch_config: true,
+                mcp_oauth_credentials_store: OAuthCredentialsStore::Auto,
+                busy_session_policy: BusySessionPolicy::default(),
+                session_titles: SessionTitles::default(),
+                response_cache: None,
+                session_tags: BTreeMap::new(),
+                session_budget: SessionBudget::default(),
+                rollout_redaction: RolloutRedaction::default(),
+                secret_scan: SecretScan::default(),
+                extract_archive: ExtractArchive::default(),
+                mcp_resources: McpResources::default(),
+                mcp_sampling: McpSampling::default(),
+                mcp_health: McpHealth::default(),
                 file_opener: UriBasedFileOpener::VsCode,
                 codex_linux_sandbox_exe: None,
                 hide_agent_reasoning: false,
@@ -1849,8 +2945,31 @@ model_verbosity = "high"
                 use_experimental_unified_exec_tool: false,
                 use_experimental_use_rmcp_client: false,
                 include_view_image_tool: true,
+                include_pin_context_tool: false,
+                include_code_search_tool: false,
+                include_read_file_tool: false,
+                include_write_file_tool: false,
+                include_git_tool: false,
+                include_database_tool: false,
+                include_browser_tool: false,
+                include_python_tool: false,
+                include_system_info_tool: false,
+                include_code_symbols_tool: false,
+                include_run_tests_tool: false,
+                include_audit_dependencies_tool: false,
+                include_review_changes_tool: false,
+                include_scan_secrets_tool: false,
+                include_list_ports_tool: false,
+                include_batch_edit_tool: false,
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
+                approval_batching: false,
+                binary_payload_framing: false,
+                client_capabilities: ClientCapabilities::default(),
+                replay_rollout: None,
+                project_config: None,
+                directory_policy: None,
+                profiles: fixture.profiles,
                 tui_notifications: Default::default(),
                 otel: OtelConfig::default(),
             },
@@ -1890,10 +3009,46 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_fallbacks: Vec::new(),
+            parallel_tool_calls: false,
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             codex_home: fixture.codex_home(),
             history: History::default(),
+            auto_compaction: AutoCompaction::default(),
+            sub_agents: SubAgents::default(),
+            memory: Memory::default(),
+            web_fetch: WebFetch::default(),
+            web_search: WebSearch::default(),
+            http_request: HttpRequest::default(),
+            browser: Browser::default(),
+            view_image: ViewImage::default(),
+            background_process: BackgroundProcess::default(),
+            python: PythonKernel::default(),
+            run_tests: RunTests::default(),
+            model_pricing: HashMap::new(),
+            databases: HashMap::new(),
+            custom_tools: BTreeMap::new(),
+            extensions: ExtensionConfigs::default(),
+            linters: BTreeMap::new(),
+            plugins: Plugins::default(),
+            tool_filter: ToolFilter::default(),
+            tool_quotas: BTreeMap::new(),
+            tool_timeouts: BTreeMap::new(),
+            idle_timeout: None,
+            watch_config: true,
+            mcp_oauth_credentials_store: OAuthCredentialsStore::Auto,
+            busy_session_policy: BusySessionPolicy::default(),
+            session_titles: SessionTitles::default(),
+            response_cache: None,
+            session_tags: BTreeMap::new(),
+            session_budget: SessionBudget::default(),
+            rollout_redaction: RolloutRedaction::default(),
+            secret_scan: SecretScan::default(),
+            extract_archive: ExtractArchive::default(),
+            mcp_resources: McpResources::default(),
+            mcp_sampling: McpSampling::default(),
+            mcp_health: McpHealth::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -1910,8 +3065,31 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_pin_context_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_batch_edit_tool: false,
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
+            approval_batching: false,
+            binary_payload_framing: false,
+            client_capabilities: ClientCapabilities::default(),
+            replay_rollout: None,
+            project_config: None,
+            directory_policy: None,
+            profiles: fixture.profiles.clone(),
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
         };
@@ -1966,10 +3144,46 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_fallbacks: Vec::new(),
+            parallel_tool_calls: false,
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             codex_home: fixture.codex_home(),
             history: History::default(),
+            auto_compaction: AutoCompaction::default(),
+            sub_agents: SubAgents::default(),
+            memory: Memory::default(),
+            web_fetch: WebFetch::default(),
+            web_search: WebSearch::default(),
+            http_request: HttpRequest::default(),
+            browser: Browser::default(),
+            view_image: ViewImage::default(),
+            background_process: BackgroundProcess::default(),
+            python: PythonKernel::default(),
+            run_tests: RunTests::default(),
+            model_pricing: HashMap::new(),
+            databases: HashMap::new(),
+            custom_tools: BTreeMap::new(),
+            extensions: ExtensionConfigs::default(),
+            linters: BTreeMap::new(),
+            plugins: Plugins::default(),
+            tool_filter: ToolFilter::default(),
+            tool_quotas: BTreeMap::new(),
+            tool_timeouts: BTreeMap::new(),
+            idle_timeout: None,
+            watch_config: true,
+            mcp_oauth_credentials_store: OAuthCredentialsStore::Auto,
+            busy_session_policy: BusySessionPolicy::default(),
+            session_titles: SessionTitles::default(),
+            response_cache: None,
+            session_tags: BTreeMap::new(),
+            session_budget: SessionBudget::default(),
+            rollout_redaction: RolloutRedaction::default(),
+            secret_scan: SecretScan::default(),
+            extract_archive: ExtractArchive::default(),
+            mcp_resources: McpResources::default(),
+            mcp_sampling: McpSampling::default(),
+            mcp_health: McpHealth::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -1986,8 +3200,31 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_pin_context_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_batch_edit_tool: false,
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
+            approval_batching: false,
+            binary_payload_framing: false,
+            client_capabilities: ClientCapabilities::default(),
+            replay_rollout: None,
+            project_config: None,
+            directory_policy: None,
+            profiles: fixture.profiles,
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
         };
@@ -2028,10 +3265,46 @@ model_verbosity = "high"
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_fallbacks: Vec::new(),
+            parallel_tool_calls: false,
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             project_doc_fallback_filenames: Vec::new(),
             codex_home: fixture.codex_home(),
             history: History::default(),
+            auto_compaction: AutoCompaction::default(),
+            sub_agents: SubAgents::default(),
+            memory: Memory::default(),
+            web_fetch: WebFetch::default(),
+            web_search: WebSearch::default(),
+            http_request: HttpRequest::default(),
+            browser: Browser::default(),
+            view_image: ViewImage::default(),
+            background_process: BackgroundProcess::default(),
+            python: PythonKernel::default(),
+            run_tests: RunTests::default(),
+            model_pricing: HashMap::new(),
+            databases: HashMap::new(),
+            custom_tools: BTreeMap::new(),
+            extensions: ExtensionConfigs::default(),
+            linters: BTreeMap::new(),
+            plugins: Plugins::default(),
+            tool_filter: ToolFilter::default(),
+            tool_quotas: BTreeMap::new(),
+            tool_timeouts: BTreeMap::new(),
+            idle_timeout: None,
+            watch_config: true,
+            mcp_oauth_credentials_store: OAuthCredentialsStore::Auto,
+            busy_session_policy: BusySessionPolicy::default(),
+            session_titles: SessionTitles::default(),
+            response_cache: None,
+            session_tags: BTreeMap::new(),
+            session_budget: SessionBudget::default(),
+            rollout_redaction: RolloutRedaction::default(),
+            secret_scan: SecretScan::default(),
+            extract_archive: ExtractArchive::default(),
+            mcp_resources: McpResources::default(),
+            mcp_sampling: McpSampling::default(),
+            mcp_health: McpHealth::default(),
             file_opener: UriBasedFileOpener::VsCode,
             codex_linux_sandbox_exe: None,
             hide_agent_reasoning: false,
@@ -2048,8 +3321,31 @@ model_verbosity = "high"
             use_experimental_unified_exec_tool: false,
             use_experimental_use_rmcp_client: false,
             include_view_image_tool: true,
+            include_pin_context_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_batch_edit_tool: false,
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
+            approval_batching: false,
+            binary_payload_framing: false,
+            client_capabilities: ClientCapabilities::default(),
+            replay_rollout: None,
+            project_config: None,
+            directory_policy: None,
+            profiles: fixture.profiles,
             tui_notifications: Default::default(),
             otel: OtelConfig::default(),
         };
@@ -2059,6 +3355,282 @@ model_verbosity = "high"
         Ok(())
     }
 
+    #[test]
+    fn client_without_interactive_approvals_is_never_asked() -> std::io::Result<()> {
+        let fixture = create_test_fixture()?;
+
+        let overrides = ConfigOverrides {
+            cwd: Some(fixture.cwd()),
+            approval_policy: Some(AskForApproval::OnRequest),
+            client_capabilities: Some(ClientCapabilities {
+                interactive_approvals: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = Config::load_from_base_config_with_overrides(
+            fixture.cfg.clone(),
+            overrides,
+            fixture.codex_home(),
+        )?;
+
+        assert_eq!(AskForApproval::Never, config.approval_policy);
+        assert!(config.client_capabilities.render_images);
+        Ok(())
+    }
+
+    #[test]
+    fn directory_policy_applies_to_workspaces_under_it() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let work = TempDir::new()?;
+        let workspace = work.path().join("prod-infra");
+        std::fs::create_dir(&workspace)?;
+        let pattern = format!("{}/prod-*", work.path().display());
+        let mut cfg: ConfigToml = toml::from_str(
+            r#"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+"#,
+        )
+        .expect("valid config");
+        cfg.directory_policies.insert(
+            pattern.clone(),
+            toml::from_str(
+                r#"
+approval_policy = "untrusted"
+sandbox_mode = "read-only"
+"#,
+            )
+            .expect("valid policy"),
+        );
+        let load = |cwd: PathBuf, approval_policy| {
+            Config::load_from_base_config_with_overrides(
+                cfg.clone(),
+                ConfigOverrides {
+                    cwd: Some(cwd),
+                    approval_policy,
+                    ..Default::default()
+                },
+                codex_home.path().to_path_buf(),
+            )
+        };
+
+        let config = load(workspace.clone(), None)?;
+        assert_eq!(config.directory_policy, Some(pattern));
+        assert_eq!(config.approval_policy, AskForApproval::UnlessTrusted);
+        assert_eq!(config.sandbox_policy, SandboxPolicy::new_read_only_policy());
+
+        let config = load(workspace, Some(AskForApproval::OnRequest))?;
+        assert_eq!(config.approval_policy, AskForApproval::OnRequest);
+
+        let config = load(work.path().to_path_buf(), None)?;
+        assert_eq!(config.directory_policy, None);
+        assert_eq!(config.approval_policy, AskForApproval::Never);
+        assert_eq!(config.sandbox_policy, SandboxPolicy::DangerFullAccess);
+        Ok(())
+    }
+
+    #[test]
+    fn custom_tools_are_parsed_and_validated() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+            [custom_tools.run_migrations]
+            description = "Apply pending database migrations"
+            command = ["./scripts/migrate", "--target", "{version}"]
+            sandbox_mode = "workspace-write"
+            parameters = { type = "object", properties = { version = { type = "string" } } }
+            "#,
+        )
+        .expect("deserialize custom_tools");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+
+        let tool = &config.custom_tools["run_migrations"];
+        assert_eq!(
+            vec!["./scripts/migrate", "--target", "{version}"],
+            tool.command
+        );
+        assert_eq!(Some(SandboxMode::WorkspaceWrite), tool.sandbox_mode);
+        assert_eq!(
+            serde_json::json!({
+                "type": "object",
+                "properties": { "version": { "type": "string" } },
+            }),
+            tool.parameters
+        );
+
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+            [custom_tools."run migrations"]
+            command = ["./scripts/migrate"]
+            "#,
+        )
+        .expect("deserialize custom_tools");
+        let err = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn extension_sections_are_checked_against_custom_tool_schemas() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |toml: &str| {
+            let cfg: ConfigToml = toml::from_str(toml).expect("deserialize extensions");
+            Config::load_from_base_config_with_overrides(
+                cfg,
+                ConfigOverrides::default(),
+                codex_home.path().to_path_buf(),
+            )
+        };
+        let tool = r#"
+            [custom_tools.run_migrations]
+            command = ["./scripts/migrate"]
+            config_schema = { type = "object", required = ["database"], properties = { database = { type = "string" } } }
+            "#;
+
+        let config = load(&format!(
+            "{tool}\n[extensions.run_migrations]\ndatabase = \"postgres://localhost/app\"\n"
+        ))?;
+        assert_eq!(
+            Some(&serde_json::json!({ "database": "postgres://localhost/app" })),
+            config.extensions.section("run_migrations")
+        );
+
+        let err = load(&format!(
+            "{tool}\n[extensions.run_migrations]\ndatabase = 5\n"
+        ))
+        .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(
+            "[extensions.run_migrations] does not match its schema: $.database: expected string, got number",
+            err.to_string()
+        );
+        assert!(load(tool).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn tool_sets_are_selected_per_profile() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+            disabled_tools = ["python"]
+
+            [tool_sets]
+            review = ["read_file", "search_code", "git"]
+
+            [profiles.review]
+            tool_set = "review"
+            disabled_tools = ["git"]
+            "#,
+        )
+        .expect("deserialize tool sets");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert!(config.tool_filter.allows("shell"));
+        assert!(!config.tool_filter.allows("python"));
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides {
+                config_profile: Some("review".to_string()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        assert!(config.tool_filter.allows("read_file"));
+        assert!(!config.tool_filter.allows("git"));
+        assert!(!config.tool_filter.allows("shell"));
+
+        let err = Config::load_from_base_config_with_overrides(
+            ConfigToml {
+                tool_set: Some("missing".to_string()),
+                ..cfg
+            },
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        Ok(())
+    }
+
+    #[test]
+    fn profiles_are_resolved_for_switching() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg: ConfigToml = toml::from_str(
+            r#"
+            model = "gpt-5-codex"
+            sandbox_mode = "workspace-write"
+
+            [tool_sets]
+            review = ["read_file", "search_code"]
+
+            [profiles.read-only-review]
+            approval_policy = "never"
+            sandbox_mode = "read-only"
+            tool_set = "review"
+
+            [profiles.deep-reasoning]
+            model = "gpt-5"
+            model_reasoning_effort = "high"
+            "#,
+        )
+        .expect("deserialize profiles");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            config.profiles.keys().collect::<Vec<_>>(),
+            vec!["deep-reasoning", "read-only-review"]
+        );
+        assert_eq!(
+            config.profiles["deep-reasoning"],
+            SessionProfile {
+                model: Some("gpt-5".to_string()),
+                model_reasoning_effort: Some(ReasoningEffort::High),
+                approval_policy: None,
+                sandbox_policy: None,
+                tool_filter: ToolFilter::default(),
+            }
+        );
+        let review = &config.profiles["read-only-review"];
+        assert_eq!(review.approval_policy, Some(AskForApproval::Never));
+        assert_eq!(
+            review.sandbox_policy,
+            Some(SandboxPolicy::new_read_only_policy())
+        );
+        assert!(review.tool_filter.allows("read_file"));
+        assert!(!review.tool_filter.allows("shell"));
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                config_profile: Some("read-only-review".to_string()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(config.sandbox_policy, SandboxPolicy::new_read_only_policy());
+        Ok(())
+    }
+
     #[test]
     fn test_set_project_trusted_writes_explicit_tables() -> anyhow::Result<()> {
         let project_dir = Path::new("/some/path");
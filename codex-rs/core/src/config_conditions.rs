@@ -0,0 +1,185 @@
+//! Conditional sections of a config file: `[when.<kind>.<predicate>]` tables
+//! whose settings only apply on matching hosts, so one config shared across
+//! machines (e.g. through dotfiles) can set Linux and macOS options side by
+//! side.
+//!
+//! ```toml
+//! [when.os.linux.sandbox_workspace_write]
+//! writable_roots = ["/var/cache/build"]
+//!
+//! [when.hostname."ci-*"]
+//! approval_policy = "never"
+//!
+//! [when.env."CODEX_PROFILE=work"]
+//! model_provider = "azure"
+//! ```
+//!
+//! Matching sections are merged over the rest of the file in the order `os`,
+//! `hostname`, `shell`, `env`, so a later kind wins over an earlier one.
+
+use toml::Value as TomlValue;
+use wildmatch::WildMatchPattern;
+
+use crate::project_config::merge_toml;
+
+/// Top-level key of the conditional sections.
+const WHEN_KEY: &str = "when";
+
+/// The kinds of predicates, in the order their sections are applied.
+const CONDITION_KINDS: &[&str] = &["os", "hostname", "shell", "env"];
+
+/// Looks up an environment variable.
+type EnvLookup = Box<dyn Fn(&str) -> Option<String>>;
+
+/// What conditional sections are matched against.
+pub(crate) struct Host {
+    /// `linux`, `macos`, `windows`, ...
+    os: &'static str,
+    /// `unix` or `windows`.
+    os_family: &'static str,
+    hostname: Option<String>,
+    /// File name of the user's shell, e.g. `zsh`.
+    shell: Option<String>,
+    env: EnvLookup,
+}
+
+impl Host {
+    /// The host Codex runs on.
+    pub(crate) fn current() -> Self {
+        Self {
+            os: std::env::consts::OS,
+            os_family: std::env::consts::FAMILY,
+            hostname: gethostname::gethostname().into_string().ok(),
+            shell: std::env::var("SHELL").ok().and_then(|shell| {
+                std::path::Path::new(&shell)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            }),
+            env: Box::new(|name| std::env::var(name).ok()),
+        }
+    }
+
+    fn matches(&self, kind: &str, predicate: &str) -> bool {
+        match kind {
+            "os" => predicate == self.os || predicate == self.os_family,
+            "hostname" => self.hostname.as_deref().is_some_and(|hostname| {
+                WildMatchPattern::<'*', '?'>::new_case_insensitive(predicate).matches(hostname)
+            }),
+            "shell" => self.shell.as_deref() == Some(predicate),
+            "env" => match predicate.split_once('=') {
+                Some((name, value)) => (self.env)(name).as_deref() == Some(value),
+                None => (self.env)(predicate).is_some_and(|value| !value.is_empty()),
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Remove the `when` table of `root` and merge the sections matching `host`
+/// over the rest of `root`.
+pub(crate) fn apply_conditional_sections(root: &mut TomlValue, host: &Host) {
+    let Some(when) = root.as_table_mut().and_then(|table| table.remove(WHEN_KEY)) else {
+        return;
+    };
+    let TomlValue::Table(mut when) = when else {
+        tracing::warn!("ignoring `{WHEN_KEY}` in config: not a table");
+        return;
+    };
+    for kind in when
+        .keys()
+        .filter(|kind| !CONDITION_KINDS.contains(&kind.as_str()))
+    {
+        tracing::warn!(
+            "ignoring `{WHEN_KEY}.{kind}` in config: not one of os, hostname, shell, env"
+        );
+    }
+    for kind in CONDITION_KINDS {
+        let Some(TomlValue::Table(sections)) = when.remove(*kind) else {
+            continue;
+        };
+        for (predicate, section) in sections {
+            if host.matches(kind, &predicate) {
+                merge_toml(root, section);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn toml(s: &str) -> TomlValue {
+        toml::from_str(s).expect("valid toml")
+    }
+
+    fn linux_host() -> Host {
+        Host {
+            os: "linux",
+            os_family: "unix",
+            hostname: Some("ci-runner-7".to_string()),
+            shell: Some("zsh".to_string()),
+            env: Box::new(|name| match name {
+                "CODEX_PROFILE" => Some("work".to_string()),
+                "EMPTY" => Some(String::new()),
+                _ => None,
+            }),
+        }
+    }
+
+    #[test]
+    fn matching_sections_are_merged_in_order() {
+        let mut root = toml(
+            r#"
+model = "gpt-5-codex"
+approval_policy = "on-request"
+
+[sandbox_workspace_write]
+network_access = false
+
+[when.os.linux.sandbox_workspace_write]
+writable_roots = ["/var/cache/build"]
+
+[when.os.macos]
+model = "o3"
+
+[when.os.unix]
+approval_policy = "untrusted"
+
+[when.hostname."CI-*"]
+approval_policy = "never"
+
+[when.shell.zsh]
+notify = ["zsh-notify"]
+
+[when.env.CODEX_PROFILE]
+model_provider = "corp"
+
+[when.env."CODEX_PROFILE=home"]
+model_provider = "openai"
+
+[when.env.EMPTY]
+model = "never-applied"
+"#,
+        );
+
+        apply_conditional_sections(&mut root, &linux_host());
+
+        assert_eq!(
+            root,
+            toml(
+                r#"
+model = "gpt-5-codex"
+approval_policy = "never"
+notify = ["zsh-notify"]
+model_provider = "corp"
+
+[sandbox_workspace_write]
+network_access = false
+writable_roots = ["/var/cache/build"]
+"#
+            )
+        );
+    }
+}
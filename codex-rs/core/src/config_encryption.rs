@@ -0,0 +1,413 @@
+//! Encrypted values in `config.toml`, so a codex home synced through a
+//! dotfile repository need not carry secrets in the clear:
+//!
+//! ```toml
+//! [mcp_servers.docs]
+//! bearer_token = "enc:v1:2yV0f0u8bqQ9...=="
+//! ```
+//!
+//! Any string value starting with [`ENCRYPTED_PREFIX`] is decrypted when the
+//! config is loaded. Values are sealed with AES-256-GCM under a key kept in
+//! the OS credential store as [`CONFIG_KEY_ACCOUNT`], or given in the
+//! [`CONFIG_KEY_ENV_VAR`] environment variable, which takes precedence, for
+//! hosts without a credential store. The key never lives in the codex home,
+//! so each machine that reads the config needs a copy of it (see
+//! `codex secret export-config-key`).
+
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use codex_keyring_store::KeyringStore;
+use ring::aead::AES_256_GCM;
+use ring::aead::Aad;
+use ring::aead::LessSafeKey;
+use ring::aead::NONCE_LEN;
+use ring::aead::Nonce;
+use ring::aead::UnboundKey;
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use tempfile::NamedTempFile;
+use toml::Value as TomlValue;
+use toml_edit::DocumentMut;
+
+use crate::config::CONFIG_TOML_FILE;
+use crate::util::invalid_data;
+
+/// Prefix of encrypted config values; the rest is the base64 of the nonce
+/// followed by the sealed value.
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Account of the config key in the OS credential store.
+pub const CONFIG_KEY_ACCOUNT: &str = "config_encryption_key";
+
+/// Environment variable holding the base64 config key, overriding the one in
+/// the OS credential store.
+pub const CONFIG_KEY_ENV_VAR: &str = "CODEX_CONFIG_KEY";
+
+/// Length of the config key in bytes.
+const KEY_LEN: usize = 32;
+
+/// Seals and opens encrypted config values with one key.
+pub struct ConfigCipher {
+    key: LessSafeKey,
+    encoded: String,
+}
+
+impl std::fmt::Debug for ConfigCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigCipher").finish_non_exhaustive()
+    }
+}
+
+impl ConfigCipher {
+    /// A cipher with a new random key.
+    pub fn generate() -> std::io::Result<Self> {
+        let mut bytes = [0u8; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| std::io::Error::other("failed to generate a config key"))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// A cipher with the base64 key `encoded`.
+    pub fn from_encoded(encoded: &str) -> std::io::Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| invalid_data(format!("config key is not valid base64: {e}")))?;
+        Self::from_bytes(&bytes)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> std::io::Result<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, bytes).map_err(|_| {
+            invalid_data(format!(
+                "config key must be {KEY_LEN} bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            encoded: BASE64.encode(bytes),
+        })
+    }
+
+    /// The config key from [`CONFIG_KEY_ENV_VAR`] or, when that is unset, from
+    /// `keyring`. `None` when neither has one.
+    pub fn load(keyring: &dyn KeyringStore) -> std::io::Result<Option<Self>> {
+        let encoded = match std::env::var(CONFIG_KEY_ENV_VAR) {
+            Ok(encoded) if !encoded.trim().is_empty() => Some(encoded),
+            _ => keyring
+                .load(CONFIG_KEY_ACCOUNT)
+                .map_err(std::io::Error::other)?,
+        };
+        encoded.as_deref().map(Self::from_encoded).transpose()
+    }
+
+    /// The config key as [`ConfigCipher::load`] finds it, or a new one saved
+    /// to `keyring` when there is none.
+    pub fn load_or_create(keyring: &dyn KeyringStore) -> std::io::Result<Self> {
+        if let Some(cipher) = Self::load(keyring)? {
+            return Ok(cipher);
+        }
+        let cipher = Self::generate()?;
+        cipher.save(keyring)?;
+        Ok(cipher)
+    }
+
+    /// Store this key in `keyring`, replacing the previous one.
+    pub fn save(&self, keyring: &dyn KeyringStore) -> std::io::Result<()> {
+        keyring
+            .save(CONFIG_KEY_ACCOUNT, &self.encoded)
+            .map_err(std::io::Error::other)
+    }
+
+    /// The key as base64, to copy it to another machine.
+    pub fn export_key(&self) -> &str {
+        &self.encoded
+    }
+
+    /// Encrypt `plaintext` into a config value starting with
+    /// [`ENCRYPTED_PREFIX`].
+    pub fn encrypt(&self, plaintext: &str) -> std::io::Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| std::io::Error::other("failed to generate a nonce"))?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(|_| std::io::Error::other("failed to encrypt config value"))?;
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&sealed);
+        Ok(format!("{ENCRYPTED_PREFIX}{}", BASE64.encode(payload)))
+    }
+
+    /// Decrypt the config value `value`, which starts with
+    /// [`ENCRYPTED_PREFIX`].
+    pub fn decrypt(&self, value: &str) -> Result<String, String> {
+        let encoded = value
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| format!("value does not start with `{ENCRYPTED_PREFIX}`"))?;
+        let payload = BASE64
+            .decode(encoded)
+            .map_err(|e| format!("encrypted value is not valid base64: {e}"))?;
+        if payload.len() < NONCE_LEN {
+            return Err("encrypted value is truncated".to_string());
+        }
+        let (nonce, sealed) = payload.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| "encrypted value is truncated".to_string())?;
+        let mut sealed = sealed.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::empty(), &mut sealed)
+            .map_err(|_| "encrypted value cannot be decrypted with the config key".to_string())?;
+        String::from_utf8(plaintext.to_vec())
+            .map_err(|_| "encrypted value is not valid UTF-8".to_string())
+    }
+}
+
+/// Decrypt every encrypted string value of the config `root`. The key is
+/// only looked up in `keyring` when there is such a value.
+pub(crate) fn decrypt_config(
+    root: &mut TomlValue,
+    keyring: &dyn KeyringStore,
+) -> std::io::Result<()> {
+    let mut cipher = None;
+    decrypt_value(root, "", keyring, &mut cipher)
+}
+
+fn decrypt_value(
+    value: &mut TomlValue,
+    path: &str,
+    keyring: &dyn KeyringStore,
+    cipher: &mut Option<ConfigCipher>,
+) -> std::io::Result<()> {
+    match value {
+        TomlValue::String(s) if s.starts_with(ENCRYPTED_PREFIX) => {
+            let cipher = match cipher {
+                Some(cipher) => cipher,
+                None => cipher.insert(ConfigCipher::load(keyring)?.ok_or_else(|| {
+                    invalid_data(format!(
+                        "{path}: encrypted value but no config key; set \
+                         {CONFIG_KEY_ENV_VAR} or store it with \
+                         `codex secret set {CONFIG_KEY_ACCOUNT}`"
+                    ))
+                })?),
+            };
+            *s = cipher
+                .decrypt(s)
+                .map_err(|e| invalid_data(format!("{path}: {e}")))?;
+        }
+        TomlValue::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                decrypt_value(value, &format!("{path}[{i}]"), keyring, cipher)?;
+            }
+        }
+        TomlValue::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                decrypt_value(value, &path, keyring, cipher)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Re-encrypt the encrypted values of `CODEX_HOME/config.toml` under a new
+/// config key, which replaces the current one in `keyring`. Formatting and
+/// comments are kept; files the config includes are not rewritten. Returns
+/// the number of values re-encrypted.
+pub fn rotate_config_key(codex_home: &Path, keyring: &dyn KeyringStore) -> std::io::Result<usize> {
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    let current = ConfigCipher::load(keyring)?;
+    let next = ConfigCipher::generate()?;
+    let contents = match std::fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut doc = contents
+        .parse::<DocumentMut>()
+        .map_err(|e| invalid_data(e.to_string()))?;
+    let mut count = 0;
+    let mut reencrypt = |value: &str| -> std::io::Result<String> {
+        let current = current.as_ref().ok_or_else(|| {
+            invalid_data("config.toml has encrypted values but there is no config key".to_string())
+        })?;
+        let plaintext = current.decrypt(value).map_err(invalid_data)?;
+        count += 1;
+        next.encrypt(&plaintext)
+    };
+    reencrypt_item(doc.as_item_mut(), &mut reencrypt)?;
+
+    if count == 0 {
+        next.save(keyring)?;
+        return Ok(0);
+    }
+    // Save the new key only once the rewritten file is ready to replace the
+    // old one, so a failure leaves the config readable with the old key.
+    let tmp_file = NamedTempFile::new_in(codex_home)?;
+    std::fs::write(tmp_file.path(), doc.to_string())?;
+    next.save(keyring)?;
+    tmp_file.persist(&config_path)?;
+    Ok(count)
+}
+
+fn reencrypt_item(
+    item: &mut toml_edit::Item,
+    reencrypt: &mut impl FnMut(&str) -> std::io::Result<String>,
+) -> std::io::Result<()> {
+    match item {
+        toml_edit::Item::Value(value) => reencrypt_edit_value(value, reencrypt),
+        toml_edit::Item::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                reencrypt_item(item, reencrypt)?;
+            }
+            Ok(())
+        }
+        toml_edit::Item::ArrayOfTables(tables) => {
+            for table in tables.iter_mut() {
+                for (_, item) in table.iter_mut() {
+                    reencrypt_item(item, reencrypt)?;
+                }
+            }
+            Ok(())
+        }
+        toml_edit::Item::None => Ok(()),
+    }
+}
+
+fn reencrypt_edit_value(
+    value: &mut toml_edit::Value,
+    reencrypt: &mut impl FnMut(&str) -> std::io::Result<String>,
+) -> std::io::Result<()> {
+    match value {
+        toml_edit::Value::String(s) if s.value().starts_with(ENCRYPTED_PREFIX) => {
+            let decor = s.decor().clone();
+            let mut replacement = toml_edit::Formatted::new(reencrypt(s.value())?);
+            *replacement.decor_mut() = decor;
+            *s = replacement;
+        }
+        toml_edit::Value::Array(values) => {
+            for value in values.iter_mut() {
+                reencrypt_edit_value(value, reencrypt)?;
+            }
+        }
+        toml_edit::Value::InlineTable(table) => {
+            for (_, value) in table.iter_mut() {
+                reencrypt_edit_value(value, reencrypt)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_keyring_store::MemoryKeyring;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn encrypted_values_round_trip() -> std::io::Result<()> {
+        let cipher = ConfigCipher::generate()?;
+        let value = cipher.encrypt("sk-secret")?;
+
+        assert!(value.starts_with(ENCRYPTED_PREFIX));
+        assert_ne!(value, cipher.encrypt("sk-secret")?);
+        assert_eq!(cipher.decrypt(&value), Ok("sk-secret".to_string()));
+        assert_eq!(
+            ConfigCipher::generate()?.decrypt(&value),
+            Err("encrypted value cannot be decrypted with the config key".to_string())
+        );
+        assert_eq!(
+            ConfigCipher::from_encoded(cipher.export_key())?.decrypt(&value),
+            Ok("sk-secret".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn decrypts_values_anywhere_in_the_config() -> std::io::Result<()> {
+        let keyring = MemoryKeyring::default();
+        let cipher = ConfigCipher::load_or_create(&keyring)?;
+        let mut root: TomlValue = toml::from_str(&format!(
+            r#"
+model = "gpt-5-codex"
+
+[mcp_servers.docs]
+bearer_token = "{}"
+args = ["--token", "{}"]
+"#,
+            cipher.encrypt("token")?,
+            cipher.encrypt("arg")?,
+        ))
+        .expect("valid toml");
+
+        decrypt_config(&mut root, &keyring)?;
+
+        assert_eq!(
+            root,
+            toml::from_str::<TomlValue>(
+                r#"
+model = "gpt-5-codex"
+
+[mcp_servers.docs]
+bearer_token = "token"
+args = ["--token", "arg"]
+"#
+            )
+            .expect("valid toml")
+        );
+
+        let mut root: TomlValue =
+            toml::from_str(&format!("model = \"{}\"\n", cipher.encrypt("o3")?))
+                .expect("valid toml");
+        let err = decrypt_config(&mut root, &MemoryKeyring::default()).expect_err("no key");
+        assert!(
+            err.to_string()
+                .starts_with("model: encrypted value but no config key")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rotating_the_key_reencrypts_config_toml() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let keyring = MemoryKeyring::default();
+        let old = ConfigCipher::load_or_create(&keyring)?;
+        let config_path = codex_home.path().join(CONFIG_TOML_FILE);
+        std::fs::write(
+            &config_path,
+            format!(
+                "# provider keys\n[model_providers.proxy]\nhttp_headers = {{ Authorization = \"{}\" }}\n",
+                old.encrypt("Bearer sk")?
+            ),
+        )?;
+
+        assert_eq!(rotate_config_key(codex_home.path(), &keyring)?, 1);
+
+        let contents = std::fs::read_to_string(&config_path)?;
+        assert!(contents.starts_with("# provider keys\n"));
+        let mut root: TomlValue = toml::from_str(&contents).expect("valid toml");
+        assert!(decrypt_config(&mut root.clone(), &MemoryKeyring::default()).is_err());
+        decrypt_config(&mut root, &keyring)?;
+        assert_eq!(
+            root["model_providers"]["proxy"]["http_headers"]["Authorization"].as_str(),
+            Some("Bearer sk")
+        );
+        Ok(())
+    }
+}
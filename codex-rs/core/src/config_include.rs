@@ -0,0 +1,233 @@
+//! `include` in `config.toml`: a list of further config files, so a large
+//! config can be split into files for, e.g., MCP servers, custom tools and
+//! profiles.
+//!
+//! ```toml
+//! include = ["mcp_servers.toml", "~/work/codex-work.toml"]
+//! ```
+//!
+//! Included files are merged beneath the file that includes them, tables key
+//! by key, so its own settings win; of the included files, a later one wins
+//! over an earlier one. Included files may include others, but not a file
+//! that is already being included.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use toml::Value as TomlValue;
+
+use crate::config_conditions::Host;
+use crate::config_conditions::apply_conditional_sections;
+use crate::project_config::merge_toml;
+use crate::util::invalid_data;
+
+/// Top-level key listing the files to include.
+const INCLUDE_KEY: &str = "include";
+
+/// Remove the `include` list of `root`, the contents of the config file
+/// `path`, and merge the files it lists beneath `root`.
+pub(crate) fn resolve_includes(
+    root: &mut TomlValue,
+    path: &Path,
+    host: &Host,
+) -> std::io::Result<()> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    resolve(root, &mut vec![path], host, &mut Vec::new())
+}
+
+/// The files the config file `path` includes, directly or through others.
+/// A listed file that cannot be read is returned too, so that creating it
+/// can be noticed; the error itself is left to loading the config.
+pub(crate) fn included_files(path: &Path, host: &Host) -> Vec<PathBuf> {
+    let Some(mut root) = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| toml::from_str::<TomlValue>(&contents).ok())
+    else {
+        return Vec::new();
+    };
+    apply_conditional_sections(&mut root, host);
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut files = Vec::new();
+    let _ = resolve(&mut root, &mut vec![path], host, &mut files);
+    files
+}
+
+/// `chain` holds the file `root` was read from, last, and the files that
+/// include it. Each file included is added to `files`.
+fn resolve(
+    root: &mut TomlValue,
+    chain: &mut Vec<PathBuf>,
+    host: &Host,
+    files: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    let Some(include) = root
+        .as_table_mut()
+        .and_then(|table| table.remove(INCLUDE_KEY))
+    else {
+        return Ok(());
+    };
+    let current = chain.last().cloned().unwrap_or_default();
+    let paths: Vec<String> = include.try_into().map_err(|e| {
+        invalid_data(format!(
+            "`{INCLUDE_KEY}` in {}: expected a list of paths: {e}",
+            current.display()
+        ))
+    })?;
+    let dir = current.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut base = TomlValue::Table(Default::default());
+    for path in paths {
+        let path = resolve_path(&path, dir);
+        let path = path.canonicalize().map_err(|e| {
+            files.push(path.clone());
+            std::io::Error::new(
+                e.kind(),
+                format!(
+                    "config {} includes {}: {e}",
+                    current.display(),
+                    path.display()
+                ),
+            )
+        })?;
+        if chain.contains(&path) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|path| path.display().to_string())
+                .collect();
+            return Err(invalid_data(format!(
+                "config include cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        if !files.contains(&path) {
+            files.push(path.clone());
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        let mut included: TomlValue = toml::from_str(&contents)
+            .map_err(|e| invalid_data(format!("config {}: {e}", path.display())))?;
+        apply_conditional_sections(&mut included, host);
+        chain.push(path);
+        let resolved = resolve(&mut included, chain, host, files);
+        chain.pop();
+        resolved?;
+        merge_toml(&mut base, included);
+    }
+    let own = std::mem::replace(root, base);
+    merge_toml(root, own);
+    Ok(())
+}
+
+/// `path` with a leading `~/` expanded to the home directory and, when
+/// relative, resolved against `dir`.
+fn resolve_path(path: &str, dir: &Path) -> PathBuf {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => match dirs::home_dir() {
+            Some(home) => home.join(rest),
+            None => PathBuf::from(path),
+        },
+        None => PathBuf::from(path),
+    };
+    dir.join(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn load(path: &Path) -> std::io::Result<TomlValue> {
+        let mut root = toml::from_str(&std::fs::read_to_string(path)?).expect("valid toml");
+        resolve_includes(&mut root, path, &Host::current())?;
+        Ok(root)
+    }
+
+    #[test]
+    fn included_files_are_merged_beneath_the_including_file() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        std::fs::create_dir(dir.path().join("parts"))?;
+        let config = dir.path().join("config.toml");
+        std::fs::write(
+            &config,
+            r#"
+include = ["parts/servers.toml", "parts/work.toml"]
+model = "gpt-5-codex"
+
+[mcp_servers.docs]
+args = ["--verbose"]
+"#,
+        )?;
+        std::fs::write(
+            dir.path().join("parts/servers.toml"),
+            r#"
+include = ["../shared.toml"]
+
+[mcp_servers.docs]
+command = "docs-server"
+"#,
+        )?;
+        std::fs::write(
+            dir.path().join("parts/work.toml"),
+            "model = \"o3\"\nmodel_provider = \"work\"\n",
+        )?;
+        std::fs::write(
+            dir.path().join("shared.toml"),
+            "model_provider = \"shared\"\napproval_policy = \"on-request\"\n",
+        )?;
+
+        assert_eq!(
+            load(&config)?,
+            toml::from_str::<TomlValue>(
+                r#"
+model = "gpt-5-codex"
+model_provider = "work"
+approval_policy = "on-request"
+
+[mcp_servers.docs]
+command = "docs-server"
+args = ["--verbose"]
+"#
+            )
+            .expect("valid toml")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn included_files_lists_nested_and_missing_files() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path().canonicalize()?;
+        let config = root.join("config.toml");
+        std::fs::write(&config, "include = [\"a.toml\", \"missing.toml\"]\n")?;
+        std::fs::write(root.join("a.toml"), "include = [\"b.toml\"]\n")?;
+        std::fs::write(root.join("b.toml"), "model = \"o3\"\n")?;
+
+        assert_eq!(
+            included_files(&config, &Host::current()),
+            vec![
+                root.join("a.toml"),
+                root.join("b.toml"),
+                root.join("missing.toml"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn include_cycles_are_rejected() -> std::io::Result<()> {
+        let dir = TempDir::new()?;
+        let config = dir.path().join("config.toml");
+        std::fs::write(&config, "include = [\"a.toml\"]\n")?;
+        std::fs::write(dir.path().join("a.toml"), "include = [\"config.toml\"]\n")?;
+
+        let err = load(&config).expect_err("cycle");
+        assert!(err.to_string().starts_with("config include cycle: "));
+        assert!(err.to_string().ends_with("config.toml"));
+
+        std::fs::write(&config, "include = [\"missing.toml\"]\n")?;
+        let err = load(&config).expect_err("missing include");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+//! Interpolation in config values, so secrets need not be written into
+//! `config.toml`: `${VAR}` is replaced with the value of the environment
+//! variable `VAR`, `${keychain:NAME}` with the secret stored as `NAME` in the
+//! OS credential store (see `codex secret set`), `$(command)` with the output
+//! of running `command` in the shell, without trailing newlines, and `$$` with
+//! a literal `$`.
+//!
+//! Only the settings that usually carry secrets or endpoints are
+//! interpolated: the base URL, headers and query parameters of model
+//! providers, and the URL, bearer token, headers and environment of MCP
+//! servers. Values are resolved when the config is loaded; an unset variable
+//! or a failing command is an error naming the setting.
+//!
+//! Commands only run from the user's own `config.toml` and `-c` overrides: a
+//! team config or project config with a `$(command)` is rejected, since
+//! whoever controls it could otherwise run commands on the user's machine.
+
+use std::process::Command;
+use std::process::Stdio;
+
+use codex_keyring_store::KeyringStore;
+use codex_keyring_store::OsKeyring;
+use toml::Value as TomlValue;
+
+/// Prefix of `${...}` names that refer to the OS credential store.
+const KEYCHAIN_PREFIX: &str = "keychain:";
+
+/// Interpolated settings of each `[model_providers.<name>]` table.
+const MODEL_PROVIDER_KEYS: &[&str] = &["base_url", "http_headers", "query_params"];
+
+/// Interpolated settings of each `[mcp_servers.<name>]` table.
+const MCP_SERVER_KEYS: &[&str] = &["url", "bearer_token", "http_headers", "env"];
+
+/// Interpolate the model providers and MCP servers of the config `root`.
+pub(crate) fn interpolate_config(root: &mut TomlValue) -> std::io::Result<()> {
+    rewrite_config(root, &resolve)
+}
+
+/// Interpolate the table of the MCP server `name`.
+pub(crate) fn interpolate_mcp_server(name: &str, server: &mut TomlValue) -> std::io::Result<()> {
+    rewrite_keys(
+        server,
+        MCP_SERVER_KEYS,
+        &format!("mcp_servers.{name}"),
+        &resolve,
+    )
+}
+
+/// Fail when an interpolated setting of the config layer `root`, read from
+/// `origin`, runs a command. Nothing is resolved.
+pub(crate) fn reject_commands(root: &TomlValue, origin: &str) -> std::io::Result<()> {
+    let check = |s: &str| {
+        interpolate(
+            s,
+            |_| Some(String::new()),
+            |_| Ok(Some(String::new())),
+            |command| {
+                Err(format!(
+                    "`$({command})` is not allowed in {origin}; only config.toml in \
+                     CODEX_HOME and -c overrides may run commands"
+                ))
+            },
+        )
+    };
+    rewrite_config(&mut root.clone(), &check)
+}
+
+fn resolve(s: &str) -> Result<String, String> {
+    interpolate(
+        s,
+        |name| std::env::var(name).ok(),
+        |name| OsKeyring.load(name).map_err(|e| e.to_string()),
+        run_command,
+    )
+}
+
+/// Replace each interpolated string of `root` with `rewrite` of it.
+fn rewrite_config(
+    root: &mut TomlValue,
+    rewrite: &impl Fn(&str) -> Result<String, String>,
+) -> std::io::Result<()> {
+    if let Some(TomlValue::Table(providers)) = root.get_mut("model_providers") {
+        for (name, provider) in providers.iter_mut() {
+            rewrite_keys(
+                provider,
+                MODEL_PROVIDER_KEYS,
+                &format!("model_providers.{name}"),
+                rewrite,
+            )?;
+        }
+    }
+    if let Some(TomlValue::Table(servers)) = root.get_mut("mcp_servers") {
+        for (name, server) in servers.iter_mut() {
+            rewrite_keys(
+                server,
+                MCP_SERVER_KEYS,
+                &format!("mcp_servers.{name}"),
+                rewrite,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_keys(
+    table: &mut TomlValue,
+    keys: &[&str],
+    path: &str,
+    rewrite: &impl Fn(&str) -> Result<String, String>,
+) -> std::io::Result<()> {
+    let Some(table) = table.as_table_mut() else {
+        return Ok(());
+    };
+    for key in keys {
+        if let Some(value) = table.get_mut(*key) {
+            rewrite_value(value, &format!("{path}.{key}"), rewrite)?;
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_value(
+    value: &mut TomlValue,
+    path: &str,
+    rewrite: &impl Fn(&str) -> Result<String, String>,
+) -> std::io::Result<()> {
+    match value {
+        TomlValue::String(s) => {
+            *s = rewrite(s).map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{path}: {e}"))
+            })?;
+        }
+        TomlValue::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                rewrite_value(value, &format!("{path}.{key}"), rewrite)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replace the `${VAR}`, `${keychain:NAME}`, `$(command)` and `$$` of `s`,
+/// looking variables up with `env`, secrets with `keychain` and running
+/// commands with `run`. A `$` followed by anything else is kept as is.
+fn interpolate(
+    s: &str,
+    env: impl Fn(&str) -> Option<String>,
+    keychain: impl Fn(&str) -> Result<Option<String>, String>,
+    run: impl Fn(&str) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| "unterminated `${` in value".to_string())?;
+            let name = &after[..end];
+            if name.is_empty() {
+                return Err("empty variable name in `${}`".to_string());
+            }
+            let value = match name.strip_prefix(KEYCHAIN_PREFIX) {
+                Some(secret) => keychain(secret)?.ok_or_else(|| {
+                    format!("no secret named {secret} in the OS credential store")
+                })?,
+                None => {
+                    env(name).ok_or_else(|| format!("environment variable {name} is not set"))?
+                }
+            };
+            out.push_str(&value);
+            rest = &after[end + 1..];
+        } else if let Some(after) = after.strip_prefix('(') {
+            let end =
+                closing_paren(after).ok_or_else(|| "unterminated `$(` in value".to_string())?;
+            let command = after[..end].trim();
+            if command.is_empty() {
+                return Err("empty command in `$()`".to_string());
+            }
+            out.push_str(&run(command)?);
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Byte offset of the `)` closing a `$(` whose command starts `s`.
+/// Parentheses inside the command must be balanced.
+fn closing_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Run `command` in the shell and return its output without trailing
+/// newlines.
+fn run_command(command: &str) -> Result<String, String> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    let output = cmd
+        .arg(command)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run `{command}`: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("`{command}` failed with {}", output.status)
+        } else {
+            format!("`{command}` failed with {}: {stderr}", output.status)
+        });
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| format!("`{command}` printed invalid UTF-8"))?;
+    Ok(stdout.trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn env(name: &str) -> Option<String> {
+        (name == "API_KEY").then(|| "secret".to_string())
+    }
+
+    fn keychain(name: &str) -> Result<Option<String>, String> {
+        Ok((name == "openrouter").then(|| "stored".to_string()))
+    }
+
+    fn run(command: &str) -> Result<String, String> {
+        match command {
+            "pass show api" => Ok("from-pass".to_string()),
+            "echo (nested)" => Ok("nested".to_string()),
+            _ => Err(format!("`{command}` failed with exit status: 1")),
+        }
+    }
+
+    #[test]
+    fn interpolates_variables_and_commands() {
+        assert_eq!(
+            interpolate("Bearer ${API_KEY}", env, keychain, run),
+            Ok("Bearer secret".to_string())
+        );
+        assert_eq!(
+            interpolate("Bearer ${keychain:openrouter}", env, keychain, run),
+            Ok("Bearer stored".to_string())
+        );
+        assert_eq!(
+            interpolate("$(pass show api)/$(echo (nested))", env, keychain, run),
+            Ok("from-pass/nested".to_string())
+        );
+        assert_eq!(
+            interpolate("price: $$5, $HOME, trailing $", env, keychain, run),
+            Ok("price: $5, $HOME, trailing $".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_unresolved_values() {
+        assert_eq!(
+            interpolate("${MISSING}", env, keychain, run),
+            Err("environment variable MISSING is not set".to_string())
+        );
+        assert_eq!(
+            interpolate("${API_KEY", env, keychain, run),
+            Err("unterminated `${` in value".to_string())
+        );
+        assert_eq!(
+            interpolate("${keychain:missing}", env, keychain, run),
+            Err("no secret named missing in the OS credential store".to_string())
+        );
+        assert_eq!(
+            interpolate("$(false)", env, keychain, run),
+            Err("`false` failed with exit status: 1".to_string())
+        );
+    }
+
+    #[test]
+    fn interpolates_only_secret_bearing_settings() {
+        let mut root: TomlValue = toml::from_str(
+            r#"
+model = "$(never-run)"
+
+[model_providers.proxy]
+name = "${NOT_INTERPOLATED}"
+base_url = "https://$$host"
+
+[mcp_servers.docs]
+command = "docs-server"
+args = ["$(never-run)"]
+env = { TOKEN = "literal" }
+"#,
+        )
+        .expect("valid toml");
+        let expected = toml::from_str::<TomlValue>(
+            r#"
+model = "$(never-run)"
+
+[model_providers.proxy]
+name = "${NOT_INTERPOLATED}"
+base_url = "https://$host"
+
+[mcp_servers.docs]
+command = "docs-server"
+args = ["$(never-run)"]
+env = { TOKEN = "literal" }
+"#,
+        )
+        .expect("valid toml");
+
+        interpolate_config(&mut root).expect("interpolate");
+
+        assert_eq!(root, expected);
+    }
+
+    #[test]
+    fn names_the_setting_that_failed() {
+        let mut root: TomlValue = toml::from_str(
+            r#"
+[mcp_servers.docs]
+url = "https://example.com"
+http_headers = { Authorization = "Bearer ${CODEX_TEST_UNSET_VARIABLE}" }
+"#,
+        )
+        .expect("valid toml");
+
+        let err = interpolate_config(&mut root).expect_err("unset variable");
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(
+            err.to_string(),
+            "mcp_servers.docs.http_headers.Authorization: environment variable \
+             CODEX_TEST_UNSET_VARIABLE is not set"
+        );
+    }
+
+    #[test]
+    fn rejects_commands_from_other_config_layers() {
+        let team: TomlValue = toml::from_str(
+            r#"
+[model_providers.proxy]
+base_url = "https://${PROXY_HOST}/$$(literal)"
+
+[mcp_servers.docs]
+command = "docs-server"
+env = { TOKEN = "$(curl https://example.com/steal)" }
+"#,
+        )
+        .expect("valid toml");
+
+        let err = reject_commands(&team, "team config https://example.com/team.toml")
+            .expect_err("command in a team config");
+
+        assert_eq!(
+            err.to_string(),
+            "mcp_servers.docs.env.TOKEN: `$(curl https://example.com/steal)` is not allowed in \
+             team config https://example.com/team.toml; only config.toml in CODEX_HOME and -c \
+             overrides may run commands"
+        );
+        let mut without_command = team;
+        without_command["mcp_servers"]["docs"]["env"]["TOKEN"] =
+            TomlValue::String("${TOKEN}".to_string());
+        assert!(reject_commands(&without_command, "team config").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_commands_in_the_shell() {
+        assert_eq!(run_command("printf 'token\\n\\n'"), Ok("token".to_string()));
+        assert!(run_command("echo oops >&2; exit 3").is_err_and(|e| e.ends_with(": oops")));
+    }
+}
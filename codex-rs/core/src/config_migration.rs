@@ -0,0 +1,356 @@
+//! Migration of `config.toml` files written for earlier versions of Codex.
+//!
+//! Settings that were renamed or moved are rewritten to their current keys,
+//! keeping the rest of the file, its comments and formatting as they are, so
+//! an upgrade does not silently ignore them. The file as it was before is
+//! kept next to it as `config.toml.bak`.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tempfile::NamedTempFile;
+use toml_edit::DocumentMut;
+use toml_edit::Item;
+use toml_edit::TableLike;
+
+use crate::config::CONFIG_TOML_FILE;
+
+/// Suffix of the copy of `config.toml` kept before it is migrated.
+const BACKUP_SUFFIX: &str = ".bak";
+
+/// A setting moved from `from` to `to`. `*` stands for any key, e.g. the
+/// name of an MCP server, and is the same key in `to` as in `from`.
+struct Migration {
+    from: &'static [&'static str],
+    to: &'static [&'static str],
+    /// Converts the value to the new setting's unit or layout; `None` leaves
+    /// a value that cannot be converted where it is.
+    convert: fn(&Item) -> Option<Item>,
+}
+
+/// Every migration, oldest first.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: &["sandbox", "mode"],
+        to: &["sandbox_mode"],
+        convert: same_value,
+    },
+    Migration {
+        from: &["sandbox", "writable_roots"],
+        to: &["sandbox_workspace_write", "writable_roots"],
+        convert: same_value,
+    },
+    Migration {
+        from: &["sandbox", "network_access"],
+        to: &["sandbox_workspace_write", "network_access"],
+        convert: same_value,
+    },
+    Migration {
+        from: &["sandbox", "exclude_tmpdir_env_var"],
+        to: &["sandbox_workspace_write", "exclude_tmpdir_env_var"],
+        convert: same_value,
+    },
+    Migration {
+        from: &["sandbox", "exclude_slash_tmp"],
+        to: &["sandbox_workspace_write", "exclude_slash_tmp"],
+        convert: same_value,
+    },
+];
+
+/// A setting rewritten by a migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigratedSetting {
+    /// Dotted key the setting had.
+    pub from: String,
+    /// Dotted key the setting has now.
+    pub to: String,
+    /// Whether `to` was already set, so the old setting was only removed.
+    pub superseded: bool,
+}
+
+impl fmt::Display for MigratedSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.superseded {
+            write!(
+                f,
+                "removed `{}`, as `{}` is already set",
+                self.from, self.to
+            )
+        } else {
+            write!(f, "moved `{}` to `{}`", self.from, self.to)
+        }
+    }
+}
+
+/// What migrating `config.toml` changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigMigrationReport {
+    pub changes: Vec<MigratedSetting>,
+    /// Copy of the file before it was migrated, when it was rewritten.
+    pub backup: Option<PathBuf>,
+}
+
+/// Migrate `CODEX_HOME/config.toml` to the current layout. With `dry_run`
+/// the changes are only reported.
+pub fn migrate_config(codex_home: &Path, dry_run: bool) -> std::io::Result<ConfigMigrationReport> {
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    let contents = match std::fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(ConfigMigrationReport::default());
+        }
+        Err(e) => return Err(e),
+    };
+    let mut doc = contents
+        .parse::<DocumentMut>()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let changes = migrate_document(&mut doc);
+    if changes.is_empty() || dry_run {
+        return Ok(ConfigMigrationReport {
+            changes,
+            backup: None,
+        });
+    }
+
+    let mut backup = config_path.clone().into_os_string();
+    backup.push(BACKUP_SUFFIX);
+    let backup = PathBuf::from(backup);
+    std::fs::write(&backup, &contents)?;
+    let tmp_file = NamedTempFile::new_in(codex_home)?;
+    std::fs::write(tmp_file.path(), doc.to_string())?;
+    tmp_file.persist(&config_path)?;
+
+    Ok(ConfigMigrationReport {
+        changes,
+        backup: Some(backup),
+    })
+}
+
+/// Apply every migration to `doc`.
+fn migrate_document(doc: &mut DocumentMut) -> Vec<MigratedSetting> {
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS {
+        let mut matches = Vec::new();
+        find_keys(
+            doc.as_table(),
+            migration.from,
+            &mut Vec::new(),
+            &mut matches,
+        );
+        for from in matches {
+            let to = target_key(migration.to, migration.from, &from);
+            if let Some(change) = apply(doc, migration, &from, &to) {
+                changes.push(change);
+            }
+        }
+    }
+    changes
+}
+
+/// Collect the keys of `table` matching `pattern` under `prefix`.
+fn find_keys(
+    table: &dyn TableLike,
+    pattern: &[&str],
+    prefix: &mut Vec<String>,
+    matches: &mut Vec<Vec<String>>,
+) {
+    let Some((segment, rest)) = pattern.split_first() else {
+        return;
+    };
+    let keys: Vec<String> = if *segment == "*" {
+        table.iter().map(|(key, _)| key.to_string()).collect()
+    } else if table.contains_key(segment) {
+        vec![segment.to_string()]
+    } else {
+        Vec::new()
+    };
+    for key in keys {
+        prefix.push(key.clone());
+        if rest.is_empty() {
+            matches.push(prefix.clone());
+        } else if let Some(child) = table.get(&key).and_then(Item::as_table_like) {
+            find_keys(child, rest, prefix, matches);
+        }
+        prefix.pop();
+    }
+}
+
+/// The key `to` for the matched key `from`, with each `*` of `to` replaced
+/// by the key matched by the `*` at the same place in `from_pattern`.
+fn target_key(to: &[&str], from_pattern: &[&str], from: &[String]) -> Vec<String> {
+    let mut wildcards = from_pattern
+        .iter()
+        .zip(from)
+        .filter(|(segment, _)| **segment == "*")
+        .map(|(_, key)| key.clone());
+    to.iter()
+        .map(|segment| match *segment {
+            "*" => wildcards.next().unwrap_or_default(),
+            segment => segment.to_string(),
+        })
+        .collect()
+}
+
+fn apply(
+    doc: &mut DocumentMut,
+    migration: &Migration,
+    from: &[String],
+    to: &[String],
+) -> Option<MigratedSetting> {
+    let (from_key, from_parent) = from.split_last()?;
+    let (to_key, to_parent) = to.split_last()?;
+    let value = table_at(doc, from_parent)?.get(from_key)?;
+    let mut converted = (migration.convert)(value)?;
+    // Padding from inside an inline table such as `sandbox = { mode = "x" }`
+    // would trail the moved setting; a comment stays with it.
+    if let Some(value) = converted.as_value_mut()
+        && value
+            .decor()
+            .suffix()
+            .and_then(|suffix| suffix.as_str())
+            .is_some_and(|suffix| suffix.trim().is_empty())
+    {
+        value.decor_mut().set_suffix("");
+    }
+
+    let superseded = table_at(doc, to_parent).is_some_and(|table| table.contains_key(to_key));
+    table_at(doc, from_parent)?.remove(from_key);
+    // Drop a table such as `[sandbox]` once its last setting has moved.
+    if let Some((parent_key, grandparent)) = from_parent.split_last()
+        && table_at(doc, from_parent).is_some_and(|table| table.is_empty())
+        && let Some(table) = table_at(doc, grandparent)
+    {
+        table.remove(parent_key);
+    }
+    if !superseded {
+        create_table_at(doc, to_parent)?.insert(to_key, converted);
+    }
+    Some(MigratedSetting {
+        from: from.join("."),
+        to: to.join("."),
+        superseded,
+    })
+}
+
+fn table_at<'a>(doc: &'a mut DocumentMut, path: &[String]) -> Option<&'a mut dyn TableLike> {
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for key in path {
+        table = table.get_mut(key)?.as_table_like_mut()?;
+    }
+    Some(table)
+}
+
+fn create_table_at<'a>(doc: &'a mut DocumentMut, path: &[String]) -> Option<&'a mut dyn TableLike> {
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for key in path {
+        if !table.contains_key(key) {
+            let mut child = toml_edit::Table::new();
+            child.set_implicit(true);
+            table.insert(key, Item::Table(child));
+        }
+        table = table.get_mut(key)?.as_table_like_mut()?;
+    }
+    Some(table)
+}
+
+fn same_value(item: &Item) -> Option<Item> {
+    Some(item.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn migrate(contents: &str) -> (String, Vec<MigratedSetting>) {
+        let mut doc = contents.parse::<DocumentMut>().expect("valid toml");
+        let changes = migrate_document(&mut doc);
+        (doc.to_string(), changes)
+    }
+
+    #[test]
+    fn renamed_settings_move_to_their_current_keys() {
+        let (migrated, changes) = migrate(
+            r#"# My settings
+model = "gpt-5-codex"
+
+[sandbox]
+mode = "workspace-write" # let it edit the checkout
+writable_roots = ["/tmp/cache"]
+network_access = true
+
+[sandbox_workspace_write]
+network_access = false
+"#,
+        );
+
+        assert_eq!(
+            migrated,
+            r#"# My settings
+model = "gpt-5-codex"
+sandbox_mode = "workspace-write" # let it edit the checkout
+
+[sandbox_workspace_write]
+network_access = false
+writable_roots = ["/tmp/cache"]
+"#
+        );
+        assert_eq!(
+            changes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "moved `sandbox.mode` to `sandbox_mode`".to_string(),
+                "moved `sandbox.writable_roots` to `sandbox_workspace_write.writable_roots`"
+                    .to_string(),
+                "removed `sandbox.network_access`, as `sandbox_workspace_write.network_access` is already set"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn current_settings_are_left_alone() {
+        let contents = r#"sandbox_mode = "read-only"
+
+[tools]
+web_search_request = true
+
+[mcp_servers.docs]
+command = "docs-server"
+startup_timeout_ms = 20000
+"#;
+        let (migrated, changes) = migrate(contents);
+        assert_eq!(migrated, contents);
+        assert_eq!(changes, Vec::new());
+    }
+
+    #[test]
+    fn migrating_keeps_a_backup() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let config_path = codex_home.path().join(CONFIG_TOML_FILE);
+        let original = "sandbox = { mode = \"read-only\" }\n";
+        std::fs::write(&config_path, original)?;
+
+        let report = migrate_config(codex_home.path(), true)?;
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(report.backup, None);
+        assert_eq!(std::fs::read_to_string(&config_path)?, original);
+
+        let report = migrate_config(codex_home.path(), false)?;
+        let backup = report.backup.expect("backup");
+        assert_eq!(std::fs::read_to_string(backup)?, original);
+        assert_eq!(
+            std::fs::read_to_string(&config_path)?,
+            "sandbox_mode = \"read-only\"\n"
+        );
+        assert_eq!(
+            migrate_config(codex_home.path(), false)?,
+            ConfigMigrationReport::default()
+        );
+        Ok(())
+    }
+}
@@ -1,25 +1,49 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::path::PathBuf;
 
+use crate::config_types::ToolFilter;
 use crate::protocol::AskForApproval;
+use crate::protocol::SandboxPolicy;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
+use codex_protocol::config_types::SandboxMode;
 use codex_protocol::config_types::Verbosity;
 
 /// Collection of common configuration options that a user can define as a unit
 /// in `config.toml`.
-#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
 pub struct ConfigProfile {
     pub model: Option<String>,
     /// The key in the `model_providers` map identifying the
     /// [`ModelProviderInfo`] to use.
     pub model_provider: Option<String>,
     pub approval_policy: Option<AskForApproval>,
+    pub sandbox_mode: Option<SandboxMode>,
     pub model_reasoning_effort: Option<ReasoningEffort>,
     pub model_reasoning_summary: Option<ReasoningSummary>,
     pub model_verbosity: Option<Verbosity>,
     pub chatgpt_base_url: Option<String>,
     pub experimental_instructions_file: Option<PathBuf>,
+    /// Name of the entry of `[tool_sets]` listing the tools offered to the
+    /// model.
+    pub tool_set: Option<String>,
+    /// Tools never offered to the model; replaces the top-level list.
+    pub disabled_tools: Option<Vec<String>>,
+}
+
+/// A profile resolved against the rest of the configuration, as applied when
+/// a session switches to it with `Op::SwitchProfile`. `None` keeps the
+/// session's current value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionProfile {
+    pub model: Option<String>,
+    pub model_reasoning_effort: Option<ReasoningEffort>,
+    pub approval_policy: Option<AskForApproval>,
+    pub sandbox_policy: Option<SandboxPolicy>,
+    /// Tools offered to the model, from the profile's tool set and disabled
+    /// tools or else the top-level ones.
+    pub tool_filter: ToolFilter,
 }
 
 impl From<ConfigProfile> for codex_app_server_protocol::Profile {
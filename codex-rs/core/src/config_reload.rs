@@ -0,0 +1,388 @@
+//! Hot reload of the config files. While `watch_config` is on, every session
+//! polls `config.toml`, the files it includes, a team config kept in a file
+//! and the project config for changes and submits `Op::ReloadConfig` when
+//! one of them is edited. The reload applies the
+//! settings a running session can change (the approval policy, the tool set
+//! and disabled tools, the MCP servers) and reports the other changed
+//! settings, which only take effect in a new session.
+//!
+//! Changes are found by comparing the files with how they were when the
+//! session started or last reloaded, so a setting given on the command line
+//! is only replaced once the files change it.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use async_channel::Sender;
+use toml::Value as TomlValue;
+
+use crate::config::CONFIG_TOML_FILE;
+use crate::config::Config;
+use crate::config::ConfigToml;
+use crate::config::load_config_as_toml;
+use crate::config_conditions::Host;
+use crate::config_include::included_files;
+use crate::config_interpolation::interpolate_mcp_server;
+use crate::config_types::McpServerConfig;
+use crate::config_types::ToolFilter;
+use crate::directory_policy::resolve_directory_policy;
+use crate::project_config::PROJECT_CONFIG_DIR;
+use crate::project_config::apply_project_config;
+use crate::project_config::project_root;
+use crate::protocol::AskForApproval;
+use crate::protocol::Op;
+use crate::protocol::Submission;
+use crate::team_config::apply_team_config;
+
+/// How often the config files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Top-level settings a reload applies to running sessions.
+const RELOADED_KEYS: &[&str] = &[
+    "approval_policy",
+    "tool_set",
+    "tool_sets",
+    "disabled_tools",
+    "mcp_servers",
+];
+
+/// Top-level settings Codex writes itself, e.g. when the model is changed
+/// with `/model` or a folder is trusted, and which the session already uses
+/// or does not need.
+const IGNORED_KEYS: &[&str] = &["model", "model_reasoning_effort", "projects"];
+
+/// Watch the config files of `config` and submit `Op::ReloadConfig` through
+/// `tx_sub` when they change. The files are listed again after each change,
+/// since it may add or remove an include or the team config. The watcher
+/// stops once the session's submission channel closes or has no other
+/// sender.
+pub(crate) fn spawn_config_watcher(config: &Config, tx_sub: Sender<Submission>) {
+    if !config.watch_config {
+        return;
+    }
+    let codex_home = config.codex_home.clone();
+    let cwd = config.cwd.clone();
+    tokio::spawn(async move {
+        let mut paths = watched_paths(&codex_home, &cwd);
+        let mut stamps = modification_stamps(&paths);
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.tick().await;
+        let mut reloads = 0u64;
+        loop {
+            interval.tick().await;
+            if tx_sub.is_closed() || tx_sub.sender_count() <= 1 {
+                break;
+            }
+            let current = modification_stamps(&paths);
+            if current == stamps {
+                continue;
+            }
+            paths = watched_paths(&codex_home, &cwd);
+            stamps = modification_stamps(&paths);
+            reloads += 1;
+            let sub = Submission {
+                id: format!("config-reload-{reloads}"),
+                op: Op::ReloadConfig,
+            };
+            if tx_sub.send(sub).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// The config files a session in `cwd` is loaded from: `config.toml`, the
+/// project config, the files `config.toml` includes and the team config when
+/// it is a file.
+fn watched_paths(codex_home: &Path, cwd: &Path) -> Vec<PathBuf> {
+    let config_toml = codex_home.join(CONFIG_TOML_FILE);
+    let mut paths = vec![
+        config_toml.clone(),
+        project_root(cwd)
+            .join(PROJECT_CONFIG_DIR)
+            .join(CONFIG_TOML_FILE),
+    ];
+    paths.extend(included_files(&config_toml, &Host::current()));
+    let team_config = load_config_as_toml(codex_home).ok().and_then(|root| {
+        root.get("team_config")?
+            .get("path")?
+            .as_str()
+            .map(|path| codex_home.join(path))
+    });
+    paths.extend(team_config);
+    paths
+}
+
+fn modification_stamps(paths: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+/// The config files as last read by a session.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConfigFiles {
+    /// `config.toml` over the team config, with the project config overlaid,
+    /// not interpolated.
+    root: TomlValue,
+    approval_policy: Option<AskForApproval>,
+    tool_filter: ToolFilter,
+    mcp_servers: BTreeMap<String, TomlValue>,
+}
+
+/// What a reload changes in a running session.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ConfigChanges {
+    pub(crate) approval_policy: Option<AskForApproval>,
+    pub(crate) tool_filter: Option<ToolFilter>,
+    /// Servers to (re)connect: new servers and changed ones.
+    pub(crate) connect: Vec<String>,
+    pub(crate) disconnect: Vec<String>,
+    /// Changed top-level settings that need a new session.
+    pub(crate) requires_restart: Vec<String>,
+}
+
+impl ConfigFiles {
+    /// Read the config files the session `config` was loaded from.
+    pub(crate) fn load(config: &Config) -> std::io::Result<Self> {
+        let mut root = load_config_as_toml(&config.codex_home)?;
+        apply_team_config(&mut root, &config.codex_home)?;
+        apply_project_config(&mut root, &config.cwd, &config.codex_home)?;
+        Self::from_toml(
+            root,
+            config.active_profile.as_deref(),
+            &project_root(&config.cwd),
+        )
+    }
+
+    fn from_toml(
+        root: TomlValue,
+        profile: Option<&str>,
+        workspace_root: &Path,
+    ) -> std::io::Result<Self> {
+        let cfg: ConfigToml = root
+            .clone()
+            .try_into()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let profile = profile
+            .and_then(|name| cfg.profiles.get(name))
+            .cloned()
+            .unwrap_or_default();
+        let tool_filter = cfg.resolve_tool_filter(&profile)?;
+        let mcp_servers = root
+            .get("mcp_servers")
+            .and_then(TomlValue::as_table)
+            .map(|servers| {
+                servers
+                    .iter()
+                    .map(|(name, server)| (name.clone(), server.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            approval_policy: resolve_directory_policy(&cfg.directory_policies, workspace_root)
+                .and_then(|(_, rule)| rule.approval_policy)
+                .or(profile.approval_policy)
+                .or(cfg.approval_policy),
+            tool_filter,
+            mcp_servers,
+            root,
+        })
+    }
+
+    /// The changes from `self` to `new`.
+    pub(crate) fn changes(&self, new: &Self) -> ConfigChanges {
+        let approval_policy = new
+            .approval_policy
+            .filter(|_| new.approval_policy != self.approval_policy);
+        let tool_filter = (new.tool_filter != self.tool_filter).then(|| new.tool_filter.clone());
+        let connect = new
+            .mcp_servers
+            .iter()
+            .filter(|(name, server)| self.mcp_servers.get(*name) != Some(*server))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let disconnect = self
+            .mcp_servers
+            .keys()
+            .filter(|name| !new.mcp_servers.contains_key(*name))
+            .cloned()
+            .collect();
+
+        let empty = toml::map::Map::new();
+        let old_root = self.root.as_table().unwrap_or(&empty);
+        let new_root = new.root.as_table().unwrap_or(&empty);
+        let mut requires_restart: Vec<String> = old_root
+            .keys()
+            .chain(new_root.keys())
+            .filter(|key| {
+                !RELOADED_KEYS.contains(&key.as_str()) && !IGNORED_KEYS.contains(&key.as_str())
+            })
+            .filter(|key| old_root.get(*key) != new_root.get(*key))
+            .cloned()
+            .collect();
+        requires_restart.sort();
+        requires_restart.dedup();
+
+        ConfigChanges {
+            approval_policy,
+            tool_filter,
+            connect,
+            disconnect,
+            requires_restart,
+        }
+    }
+
+    /// The MCP server `name`, interpolated for connecting to it.
+    pub(crate) fn mcp_server(&self, name: &str) -> std::io::Result<McpServerConfig> {
+        let mut server = self.mcp_servers.get(name).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no MCP server named `{name}` is configured"),
+            )
+        })?;
+        interpolate_mcp_server(name, &mut server)?;
+        server
+            .try_into()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn files(s: &str) -> ConfigFiles {
+        ConfigFiles::from_toml(
+            toml::from_str(s).expect("valid toml"),
+            None,
+            Path::new("/work/app"),
+        )
+        .expect("valid config")
+    }
+
+    #[test]
+    fn reload_applies_safe_changes_and_lists_the_rest() {
+        let old = files(
+            r#"
+model = "gpt-5-codex"
+approval_policy = "on-request"
+hide_agent_reasoning = false
+
+[mcp_servers.docs]
+command = "docs-server"
+
+[mcp_servers.search]
+command = "search-server"
+"#,
+        );
+        let new = files(
+            r#"
+model = "gpt-5"
+approval_policy = "never"
+hide_agent_reasoning = true
+disabled_tools = ["python"]
+
+[mcp_servers.docs]
+command = "docs-server"
+args = ["--verbose"]
+
+[mcp_servers.github]
+command = "github-mcp-server"
+"#,
+        );
+
+        let changes = old.changes(&new);
+
+        assert_eq!(changes.approval_policy, Some(AskForApproval::Never));
+        assert!(
+            changes
+                .tool_filter
+                .is_some_and(|filter| !filter.allows("python"))
+        );
+        assert_eq!(
+            changes.connect,
+            vec!["docs".to_string(), "github".to_string()]
+        );
+        assert_eq!(changes.disconnect, vec!["search".to_string()]);
+        assert_eq!(
+            changes.requires_restart,
+            vec!["hide_agent_reasoning".to_string()]
+        );
+        assert_eq!(new.changes(&new), ConfigChanges::default());
+    }
+
+    #[test]
+    fn removed_approval_policy_keeps_the_current_one() {
+        let old = files("approval_policy = \"never\"\n");
+        let new = files("");
+
+        assert_eq!(old.changes(&new), ConfigChanges::default());
+    }
+
+    #[test]
+    fn directory_policy_approval_outranks_the_top_level_one() {
+        let old = files("approval_policy = \"never\"\n");
+        let new = files(
+            r#"
+approval_policy = "never"
+
+[directory_policies."/work"]
+approval_policy = "untrusted"
+"#,
+        );
+
+        let changes = old.changes(&new);
+
+        assert_eq!(changes.approval_policy, Some(AskForApproval::UnlessTrusted));
+        assert_eq!(
+            changes.requires_restart,
+            vec!["directory_policies".to_string()]
+        );
+    }
+
+    #[test]
+    fn includes_and_team_config_file_are_watched() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let codex_home = codex_home.path().canonicalize()?;
+        let cwd = TempDir::new()?;
+        std::fs::write(
+            codex_home.join(CONFIG_TOML_FILE),
+            "include = [\"servers.toml\"]\n",
+        )?;
+        std::fs::write(
+            codex_home.join("servers.toml"),
+            "[team_config]\npath = \"team/codex.toml\"\n",
+        )?;
+
+        let project_config = project_root(cwd.path())
+            .join(PROJECT_CONFIG_DIR)
+            .join(CONFIG_TOML_FILE);
+        assert_eq!(
+            watched_paths(&codex_home, cwd.path()),
+            vec![
+                codex_home.join(CONFIG_TOML_FILE),
+                project_config.clone(),
+                codex_home.join("servers.toml"),
+                codex_home.join("team/codex.toml"),
+            ]
+        );
+
+        // Dropping the include drops the files it brought in.
+        std::fs::write(codex_home.join(CONFIG_TOML_FILE), "model = \"o3\"\n")?;
+        assert_eq!(
+            watched_paths(&codex_home, cwd.path()),
+            vec![codex_home.join(CONFIG_TOML_FILE), project_config]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! JSON Schema of `config.toml`, generated from [`ConfigToml`], so editors can
+//! validate and complete the file and tools that write config can check it.
+
+use schemars::r#gen::SchemaSettings;
+
+use crate::config::ConfigToml;
+
+/// Title of the generated schema.
+const SCHEMA_TITLE: &str = "Codex configuration";
+
+/// The JSON Schema (draft-07) of `config.toml`.
+pub fn config_schema() -> serde_json::Value {
+    let generator = SchemaSettings::draft07().into_generator();
+    let mut schema = generator.into_root_schema_for::<ConfigToml>();
+    schema.schema.metadata().title = Some(SCHEMA_TITLE.to_string());
+    serde_json::to_value(schema).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn schema_describes_settings_and_mcp_servers() {
+        let schema = config_schema();
+
+        assert_eq!(schema["title"], json!(SCHEMA_TITLE));
+        assert_eq!(
+            schema["properties"]["mcp_servers"]["additionalProperties"]["$ref"],
+            json!("#/definitions/McpServerConfig")
+        );
+        let server = &schema["definitions"]["McpServerConfig"]["properties"];
+        for key in ["command", "url", "bearer_token_env_var", "tool_timeout_sec"] {
+            assert!(server.get(key).is_some(), "missing {key}");
+        }
+        assert!(
+            schema["definitions"]["AskForApproval"]
+                .to_string()
+                .contains("on-request")
+        );
+    }
+}
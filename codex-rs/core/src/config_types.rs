@@ -3,6 +3,11 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use crate::model_provider_info::ModelProviderInfo;
+use codex_protocol::config_types::CompactionStrategy;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::protocol::AskForApproval;
+use schemars::JsonSchema;
 use serde::Deserializer;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -31,6 +36,135 @@ pub struct McpServerConfig {
     /// Default timeout for MCP tool calls initiated via this server.
     #[serde(default, with = "option_duration_secs")]
     pub tool_timeout_sec: Option<Duration>,
+
+    /// Prefix of the names the server's tools are offered to the model
+    /// under, `<prefix>__<tool>`, instead of the server name. Empty offers
+    /// the tools under their own names.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_prefix: Option<String>,
+
+    /// Names individual tools are offered under, keyed by the name the
+    /// server gives them. Takes precedence over `tool_prefix`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_aliases: HashMap<String, String>,
+
+    /// Most tool calls the server handles at once; further calls wait for a
+    /// free slot for up to the tool timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_calls: Option<usize>,
+
+    /// Largest tool result, in bytes of JSON, passed on to the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_result_bytes: Option<usize>,
+
+    /// When set, only these tools, by the name the server gives them, are
+    /// offered to the model. `*` and `?` are wildcards.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled_tools: Option<Vec<String>>,
+
+    /// Tools never offered to the model, by the name the server gives them.
+    /// `*` and `?` are wildcards.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_tools: Vec<String>,
+
+    /// Whether calls to the server's tools need the user's approval.
+    /// Defaults to `auto`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_approval: Option<McpToolApproval>,
+
+    /// Approval of individual tools, keyed by the name the server gives
+    /// them. Takes precedence over `tool_approval`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tool_approvals: HashMap<String, McpToolApproval>,
+}
+
+/// Whether calls to an MCP tool need the user's approval.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum McpToolApproval {
+    /// Call the tool without asking.
+    #[default]
+    Auto,
+    /// Ask before every call, unless the tool was approved for the session.
+    Ask,
+}
+
+/// `[mcp_servers.<id>]` as written in `config.toml`: `command` for a stdio
+/// server or `url` for a streamable HTTP server, with the settings of that
+/// transport.
+#[derive(Deserialize, JsonSchema)]
+struct RawMcpServerConfig {
+    /// Command that starts a stdio server.
+    command: Option<String>,
+    /// Arguments of `command`.
+    #[serde(default)]
+    args: Option<Vec<String>>,
+    /// Environment variables set for `command`.
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+
+    /// URL of a streamable HTTP server.
+    url: Option<String>,
+    /// Bearer token sent to `url`, in clear text.
+    bearer_token: Option<String>,
+    /// Environment variable holding the bearer token sent to `url`.
+    bearer_token_env_var: Option<String>,
+    /// Extra HTTP headers sent to `url`.
+    #[serde(default)]
+    http_headers: Option<HashMap<String, String>>,
+
+    /// Startup timeout in seconds (default: 10).
+    #[serde(default)]
+    startup_timeout_sec: Option<f64>,
+    /// Startup timeout in milliseconds, if `startup_timeout_sec` is not set.
+    #[serde(default)]
+    startup_timeout_ms: Option<u64>,
+    /// Timeout of tool calls in seconds (default: 60).
+    #[serde(default, with = "option_duration_secs")]
+    #[schemars(with = "Option<f64>")]
+    tool_timeout_sec: Option<Duration>,
+
+    /// Prefix of the server's tool names, instead of the server name; empty
+    /// for bare names.
+    #[serde(default)]
+    tool_prefix: Option<String>,
+    /// Names offered to the model for individual tools, keyed by the
+    /// server's tool name.
+    #[serde(default)]
+    tool_aliases: HashMap<String, String>,
+
+    /// Most tool calls of the server running at once.
+    #[serde(default)]
+    #[schemars(range(min = 1))]
+    max_concurrent_calls: Option<usize>,
+    /// Largest tool result passed to the model, in bytes of JSON.
+    #[serde(default)]
+    #[schemars(range(min = 1))]
+    max_result_bytes: Option<usize>,
+
+    /// Only these tools of the server are offered; `*` and `?` are
+    /// wildcards.
+    #[serde(default)]
+    enabled_tools: Option<Vec<String>>,
+    /// Tools of the server never offered; `*` and `?` are wildcards.
+    #[serde(default)]
+    disabled_tools: Vec<String>,
+    /// Whether calls to the server's tools ask for approval first.
+    #[serde(default)]
+    tool_approval: Option<McpToolApproval>,
+    /// Approval of individual tools, keyed by the server's tool name.
+    #[serde(default)]
+    tool_approvals: HashMap<String, McpToolApproval>,
+}
+
+impl JsonSchema for McpServerConfig {
+    fn schema_name() -> String {
+        "McpServerConfig".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        RawMcpServerConfig::json_schema(generator)
+    }
 }
 
 impl<'de> Deserialize<'de> for McpServerConfig {
@@ -38,26 +172,36 @@ impl<'de> Deserialize<'de> for McpServerConfig {
     where
         D: Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        struct RawMcpServerConfig {
-            command: Option<String>,
-            #[serde(default)]
-            args: Option<Vec<String>>,
-            #[serde(default)]
-            env: Option<HashMap<String, String>>,
-
-            url: Option<String>,
-            bearer_token: Option<String>,
-
-            #[serde(default)]
-            startup_timeout_sec: Option<f64>,
-            #[serde(default)]
-            startup_timeout_ms: Option<u64>,
-            #[serde(default, with = "option_duration_secs")]
-            tool_timeout_sec: Option<Duration>,
+        let mut raw = RawMcpServerConfig::deserialize(deserializer)?;
+
+        let tool_prefix = raw.tool_prefix.take();
+        let tool_aliases = std::mem::take(&mut raw.tool_aliases);
+        let enabled_tools = raw.enabled_tools.take();
+        let disabled_tools = std::mem::take(&mut raw.disabled_tools);
+        let tool_approvals = std::mem::take(&mut raw.tool_approvals);
+        if let Some(prefix) = &tool_prefix
+            && !is_valid_tool_name(prefix, true)
+        {
+            return Err(SerdeError::custom(format!(
+                "tool_prefix `{prefix}` may only contain letters, digits, `_` and `-`"
+            )));
+        }
+        for (tool, alias) in &tool_aliases {
+            if !is_valid_tool_name(alias, false) {
+                return Err(SerdeError::custom(format!(
+                    "alias `{alias}` of tool `{tool}` must be 1 to {MAX_MCP_TOOL_NAME_LEN} letters, digits, `_` or `-`"
+                )));
+            }
         }
 
-        let raw = RawMcpServerConfig::deserialize(deserializer)?;
+        for (field, value) in [
+            ("max_concurrent_calls", raw.max_concurrent_calls),
+            ("max_result_bytes", raw.max_result_bytes),
+        ] {
+            if value == Some(0) {
+                return Err(SerdeError::custom(format!("{field} must be at least 1")));
+            }
+        }
 
         let startup_timeout_sec = match (raw.startup_timeout_sec, raw.startup_timeout_ms) {
             (Some(sec), _) => {
@@ -87,10 +231,18 @@ impl<'de> Deserialize<'de> for McpServerConfig {
                 env,
                 url,
                 bearer_token,
+                bearer_token_env_var,
+                http_headers,
                 ..
             } => {
                 throw_if_set("stdio", "url", url.as_ref())?;
                 throw_if_set("stdio", "bearer_token", bearer_token.as_ref())?;
+                throw_if_set(
+                    "stdio",
+                    "bearer_token_env_var",
+                    bearer_token_env_var.as_ref(),
+                )?;
+                throw_if_set("stdio", "http_headers", http_headers.as_ref())?;
                 McpServerTransportConfig::Stdio {
                     command,
                     args: args.unwrap_or_default(),
@@ -100,6 +252,8 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             RawMcpServerConfig {
                 url: Some(url),
                 bearer_token,
+                bearer_token_env_var,
+                http_headers,
                 command,
                 args,
                 env,
@@ -108,7 +262,17 @@ impl<'de> Deserialize<'de> for McpServerConfig {
                 throw_if_set("streamable_http", "command", command.as_ref())?;
                 throw_if_set("streamable_http", "args", args.as_ref())?;
                 throw_if_set("streamable_http", "env", env.as_ref())?;
-                McpServerTransportConfig::StreamableHttp { url, bearer_token }
+                if bearer_token.is_some() && bearer_token_env_var.is_some() {
+                    return Err(SerdeError::custom(
+                        "bearer_token and bearer_token_env_var cannot both be set",
+                    ));
+                }
+                McpServerTransportConfig::StreamableHttp {
+                    url,
+                    bearer_token,
+                    bearer_token_env_var,
+                    http_headers,
+                }
             }
             _ => return Err(SerdeError::custom("invalid transport")),
         };
@@ -117,10 +281,30 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             transport,
             startup_timeout_sec,
             tool_timeout_sec: raw.tool_timeout_sec,
+            tool_prefix,
+            tool_aliases,
+            max_concurrent_calls: raw.max_concurrent_calls,
+            max_result_bytes: raw.max_result_bytes,
+            enabled_tools,
+            disabled_tools,
+            tool_approval: raw.tool_approval,
+            tool_approvals,
         })
     }
 }
 
+/// Longest tool name the model accepts.
+const MAX_MCP_TOOL_NAME_LEN: usize = 64;
+
+/// Whether `name` can be (part of) a tool name offered to the model.
+fn is_valid_tool_name(name: &str, allow_empty: bool) -> bool {
+    (allow_empty || !name.is_empty())
+        && name.len() <= MAX_MCP_TOOL_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged, deny_unknown_fields, rename_all = "snake_case")]
 pub enum McpServerTransportConfig {
@@ -140,6 +324,13 @@ pub enum McpServerTransportConfig {
         /// This should be used with caution because it lives on disk in clear text.
         #[serde(default, skip_serializing_if = "Option::is_none")]
         bearer_token: Option<String>,
+        /// Name of the environment variable that holds the bearer token. It is
+        /// read when the server is connected, so the token stays off disk.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bearer_token_env_var: Option<String>,
+        /// Extra headers sent with every request, such as an API key header.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        http_headers: Option<HashMap<String, String>>,
     },
 }
 
@@ -169,7 +360,7 @@ mod option_duration_secs {
     }
 }
 
-#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, JsonSchema)]
 pub enum UriBasedFileOpener {
     #[serde(rename = "vscode")]
     VsCode,
@@ -201,7 +392,7 @@ impl UriBasedFileOpener {
 }
 
 /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 pub struct History {
     /// If true, history entries will not be written to disk.
     pub persistence: HistoryPersistence,
@@ -211,7 +402,7 @@ pub struct History {
     pub max_bytes: Option<usize>,
 }
 
-#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum HistoryPersistence {
     /// Save all history entries to disk.
@@ -221,9 +412,830 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Settings for automatic context compaction, from the `[auto_compaction]`
+/// table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct AutoCompaction {
+    /// How the history is shrunk once the token limit is reached.
+    pub strategy: CompactionStrategy,
+
+    /// Compact once this percentage of the model's context window is in use.
+    /// The lower of this and `model_auto_compact_token_limit` applies.
+    pub threshold_percent: Option<u8>,
+
+    /// Number of most recent user turns left untouched by the
+    /// `summarize_oldest` and `drop_tool_outputs` strategies.
+    pub keep_recent_turns: usize,
+
+    /// Items whose text contains any of these strings survive compaction
+    /// verbatim.
+    pub pinned: Vec<String>,
+}
+
+impl Default for AutoCompaction {
+    fn default() -> Self {
+        Self {
+            strategy: CompactionStrategy::default(),
+            threshold_percent: None,
+            keep_recent_turns: 2,
+            pinned: Vec::new(),
+        }
+    }
+}
+
+/// Settings for delegating work to sub-agents, from the `[sub_agents]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct SubAgents {
+    /// Expose the `delegate_task` tool to the model.
+    pub enabled: bool,
+
+    /// Model used by sub-agents; defaults to the session's model.
+    pub model: Option<String>,
+
+    /// Maximum number of model requests a sub-agent may make.
+    pub max_turns: u32,
+
+    /// Maximum number of tokens a sub-agent may consume.
+    pub max_tokens: Option<u64>,
+}
+
+impl Default for SubAgents {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            max_turns: 20,
+            max_tokens: None,
+        }
+    }
+}
+
+/// Settings for the persistent per-project memory, from the `[memory]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct Memory {
+    /// Expose the `memory` tool and show saved memory to new sessions.
+    pub enabled: bool,
+
+    /// Maximum size of the memory shown to a new session, in bytes.
+    pub max_injected_bytes: usize,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_injected_bytes: 4 * 1024,
+        }
+    }
+}
+
+/// Settings for the `web_fetch` tool, from the `[web_fetch]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct WebFetch {
+    /// Expose the `web_fetch` tool.
+    pub enabled: bool,
+
+    /// Maximum size of a downloaded response body, in bytes.
+    pub max_download_bytes: usize,
+
+    /// Maximum size of the text returned to the model, in bytes.
+    pub max_output_bytes: usize,
+
+    /// Timeout of each request, in seconds.
+    pub timeout_sec: u64,
+
+    /// Refuse URLs that the site's `robots.txt` disallows.
+    pub respect_robots_txt: bool,
+}
+
+impl Default for WebFetch {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_download_bytes: 5 * 1024 * 1024,
+            max_output_bytes: 64 * 1024,
+            timeout_sec: 30,
+            respect_robots_txt: true,
+        }
+    }
+}
+
+/// Settings for the `web_search` tool, from the `[web_search]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct WebSearch {
+    /// Search provider; the `web_search` tool is only exposed when set.
+    pub provider: Option<WebSearchProvider>,
+
+    /// Endpoint of the provider. Required for `searxng` and `custom`; the
+    /// public API is used for the others when unset.
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the API key. Defaults to
+    /// `BING_SEARCH_API_KEY` for Bing and `BRAVE_SEARCH_API_KEY` for Brave.
+    pub api_key_env: Option<String>,
+
+    /// Maximum number of results returned to the model.
+    pub max_results: usize,
+
+    /// Timeout of each search request, in seconds.
+    pub timeout_sec: u64,
+}
+
+impl Default for WebSearch {
+    fn default() -> Self {
+        Self {
+            provider: None,
+            base_url: None,
+            api_key_env: None,
+            max_results: 5,
+            timeout_sec: 15,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebSearchProvider {
+    Bing,
+    Brave,
+    Searxng,
+    /// An endpoint answering `GET <base_url>?q=<query>&count=<n>` with
+    /// `{"results": [{"title", "url", "snippet"}]}`.
+    Custom,
+}
+
+/// Settings for the `http_request` tool, from the `[http_request]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct HttpRequest {
+    /// Hosts the tool may send requests to, as `host`, `host:port` or
+    /// `*.domain`. The `http_request` tool is only exposed when non-empty.
+    pub allowed_hosts: Vec<String>,
+
+    /// Maximum size of the response body returned to the model, in bytes.
+    pub max_body_bytes: usize,
+
+    /// Timeout of each request, in seconds.
+    pub timeout_sec: u64,
+}
+
+impl Default for HttpRequest {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: Vec::new(),
+            max_body_bytes: 64 * 1024,
+            timeout_sec: 30,
+        }
+    }
+}
+
+/// Settings for the `browser` tool, from the `[browser]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct Browser {
+    /// Chrome or Chromium executable. When unset, common names are looked up
+    /// on `PATH`.
+    pub executable: Option<PathBuf>,
+
+    /// Size of the browser window, in CSS pixels.
+    pub viewport_width: u32,
+    pub viewport_height: u32,
+
+    /// Timeout of starting the browser and of each page operation, in
+    /// seconds.
+    pub timeout_sec: u64,
+}
+
+impl Default for Browser {
+    fn default() -> Self {
+        Self {
+            executable: None,
+            viewport_width: 1280,
+            viewport_height: 800,
+            timeout_sec: 30,
+        }
+    }
+}
+
+/// Limits on the images the `view_image` tool attaches, from the
+/// `[view_image]` table. Larger images are downscaled and recompressed.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct ViewImage {
+    /// Longest side of an attached image, in pixels.
+    pub max_dimension: u32,
+
+    /// Largest encoded size of an attached image, in bytes.
+    pub max_bytes: u64,
+}
+
+impl Default for ViewImage {
+    fn default() -> Self {
+        Self {
+            max_dimension: 2048,
+            max_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Defaults of the `background_process` tool, from the
+/// `[background_process]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct BackgroundProcess {
+    /// Bytes of output kept per process; older output is dropped.
+    pub log_cap_bytes: usize,
+
+    /// Processes that may run at once. Starting another fails until one
+    /// exits. 0 means no limit.
+    pub max_concurrent: usize,
+
+    /// Seconds a process is given to exit after SIGTERM before it is
+    /// killed. 0 kills it right away.
+    pub kill_grace_sec: u64,
+
+    /// Stop the processes still running when the session ends. When off,
+    /// they keep running after it.
+    pub kill_on_session_end: bool,
+
+    /// Remove ANSI escape sequences, such as colors, from the logs.
+    pub strip_ansi: bool,
+}
+
+impl Default for BackgroundProcess {
+    fn default() -> Self {
+        Self {
+            log_cap_bytes: 512 * 1024,
+            max_concurrent: 16,
+            kill_grace_sec: 5,
+            kill_on_session_end: true,
+            strip_ansi: true,
+        }
+    }
+}
+
+/// Settings for the `python` tool, from the `[python]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct PythonKernel {
+    /// Python interpreter. When unset, `python3` or `python` is looked up on
+    /// `PATH`.
+    pub executable: Option<PathBuf>,
+
+    /// Time a cell may run before it is interrupted, in seconds.
+    pub timeout_sec: u64,
+}
+
+impl Default for PythonKernel {
+    fn default() -> Self {
+        Self {
+            executable: None,
+            timeout_sec: 120,
+        }
+    }
+}
+
+/// Test frameworks the `run_tests` tool can run and parse.
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Jest,
+}
+
+/// Settings for the `run_tests` tool, from the `[run_tests]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct RunTests {
+    /// Framework of the project. When unset, it is detected from the files
+    /// in the working directory.
+    pub framework: Option<TestFramework>,
+
+    /// Command that starts the framework, such as `["uv", "run", "pytest"]`,
+    /// used instead of the framework's default.
+    pub command: Option<Vec<String>>,
+
+    /// Time a test run may take before it is killed, in seconds.
+    pub timeout_sec: u64,
+}
+
+impl Default for RunTests {
+    fn default() -> Self {
+        Self {
+            framework: None,
+            command: None,
+            timeout_sec: 600,
+        }
+    }
+}
+
+/// How the `lint` tool reads the output of a linter.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LintOutputFormat {
+    /// `path:line:column: message` lines, as printed by gcc, flake8, mypy
+    /// and `shellcheck -f gcc`.
+    #[default]
+    Gcc,
+    /// The report of `eslint -f json`.
+    Eslint,
+    /// The report of `ruff check --output-format json`.
+    Ruff,
+}
+
+/// A formatter or linter run by the `lint` tool, from a `[linters.<name>]`
+/// table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct LinterConfig {
+    /// Program and arguments that check files. `{files}` is replaced by the
+    /// files to check; they are appended when it is absent.
+    pub command: Vec<String>,
+
+    /// How to read the command's output.
+    #[serde(default)]
+    pub output_format: LintOutputFormat,
+
+    /// Extensions of the files the linter handles, without the dot. All files
+    /// when empty.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+
+    /// Program and arguments that read a file on stdin and print it fixed on
+    /// stdout. `{file}` is replaced by the file's path.
+    #[serde(default)]
+    pub fix_command: Option<Vec<String>>,
+
+    /// Time each command may run, in seconds.
+    #[serde(default = "default_linter_timeout_sec")]
+    pub timeout_sec: u64,
+}
+
+fn default_linter_timeout_sec() -> u64 {
+    60
+}
+
+pub type ToolNamePattern = WildMatchPattern<'*', '?'>;
+
+/// Which tools are offered to the model, resolved from `tool_set`,
+/// `[tool_sets]` and `disabled_tools`. Patterns match tool names, with `*` and
+/// `?` as wildcards, so `github__*` covers all tools of the `github` MCP
+/// server.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ToolFilter {
+    /// Patterns of the selected tool set. `None` allows every tool.
+    pub allowed: Option<Vec<ToolNamePattern>>,
+
+    /// Patterns of tools that are never offered, even when in the tool set.
+    pub disabled: Vec<ToolNamePattern>,
+}
+
+impl ToolFilter {
+    pub fn new(allowed: Option<&[String]>, disabled: &[String]) -> Self {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| ToolNamePattern::new(pattern))
+                .collect()
+        };
+        Self {
+            allowed: allowed.map(compile),
+            disabled: compile(disabled),
+        }
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        self.allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|pattern| pattern.matches(name)))
+            && !self.disabled.iter().any(|pattern| pattern.matches(name))
+    }
+}
+
+/// Limits on one tool, or on each tool matching a pattern, from a
+/// `[tool_quotas.<name>]` table. Unset limits do not apply.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct ToolQuota {
+    /// Calls allowed in one turn.
+    pub max_calls_per_turn: Option<u64>,
+
+    /// Calls with the same arguments allowed in one turn.
+    pub max_identical_calls_per_turn: Option<u64>,
+
+    /// Calls allowed in the whole session.
+    pub max_calls_per_session: Option<u64>,
+
+    /// Output, in bytes, the tool may return over the session. The call that
+    /// crosses the limit still completes; later ones are refused.
+    pub max_output_bytes: Option<u64>,
+}
+
+/// Settings for WebAssembly tool plugins, from the `[plugins]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct Plugins {
+    /// Load the plugins found in `dir` when a session starts.
+    pub enabled: bool,
+
+    /// Directory searched for `*.wasm` plugins; `$CODEX_HOME/plugins` when
+    /// unset.
+    pub dir: Option<PathBuf>,
+
+    /// Fuel each call may consume, roughly the number of WebAssembly
+    /// instructions it may run.
+    pub fuel: u64,
+
+    /// Memory each call may use, in MiB.
+    pub max_memory_mb: u64,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+            fuel: 1_000_000_000,
+            max_memory_mb: 64,
+        }
+    }
+}
+
+/// A tool that runs a command, from a `[custom_tools.<name>]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct CustomToolConfig {
+    /// What the tool does, shown to the model.
+    #[serde(default)]
+    pub description: String,
+
+    /// JSON schema of the tool's arguments; an object with no properties when
+    /// unset.
+    #[serde(default = "default_custom_tool_parameters")]
+    pub parameters: serde_json::Value,
+
+    /// Program and arguments to run. `{name}` is replaced by the argument
+    /// `name` of the call.
+    pub command: Vec<String>,
+
+    /// Directory the command runs in, relative to the session's.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// Sandbox the command runs under; the session's when unset.
+    #[serde(default)]
+    pub sandbox_mode: Option<SandboxMode>,
+
+    /// Time the command may run, in seconds; the `shell` tool's default when
+    /// unset.
+    #[serde(default)]
+    pub timeout_sec: Option<u64>,
+
+    /// JSON schema the command's output must match. Output that does not is
+    /// reported to the model as an error.
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
+
+    /// JSON schema of the tool's `[extensions.<name>]` section, checked when
+    /// the config is loaded. The section is passed to the command as JSON in
+    /// `CODEX_EXTENSION_CONFIG`.
+    #[serde(default)]
+    pub config_schema: Option<serde_json::Value>,
+}
+
+fn default_custom_tool_parameters() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// An entry of `model_fallbacks`: a model to fail over to when the configured
+/// one is unavailable.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct ModelFallbackToml {
+    pub model: String,
+
+    /// Key of the model's provider in `model_providers`; defaults to the
+    /// session's provider.
+    pub model_provider: Option<String>,
+}
+
+/// A fallback model with its provider resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFallback {
+    pub model: String,
+    pub model_provider_id: String,
+    pub model_provider: ModelProviderInfo,
+}
+
+/// The response cache, from the `[response_cache]` table. Model responses are
+/// stored under a hash of the request that produced them and replayed for the
+/// same request, so sessions and integration tests can run without the API.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct ResponseCacheToml {
+    /// Directory responses are stored in; defaults to
+    /// `$CODEX_HOME/response_cache`. Relative paths are resolved against
+    /// `CODEX_HOME`.
+    pub dir: Option<PathBuf>,
+
+    #[serde(default)]
+    pub mode: ResponseCacheMode,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseCacheMode {
+    /// Replay cached responses and record the others.
+    #[default]
+    ReadWrite,
+
+    /// Only replay cached responses: requests without one fail instead of
+    /// reaching the API.
+    Replay,
+
+    /// Always query the API, replacing cached responses.
+    Record,
+}
+
+/// The response cache with its directory resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCache {
+    pub dir: PathBuf,
+    pub mode: ResponseCacheMode,
+}
+
+/// Settings for automatic session titles, from the `[session_titles]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct SessionTitles {
+    /// Title each session after its first turn.
+    pub enabled: bool,
+
+    /// Model used to write titles; defaults to the session's model.
+    pub model: Option<String>,
+}
+
+impl Default for SessionTitles {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            model: None,
+        }
+    }
+}
+
+/// Redaction of rollout files, from the `[rollout_redaction]` table. Matches
+/// in message and tool-output text are replaced before the rollout is
+/// written, so transcripts can be synced or shared.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct RolloutRedaction {
+    pub enabled: bool,
+
+    /// Redact well-known API key and token formats.
+    pub api_keys: bool,
+
+    /// Redact email addresses.
+    pub emails: bool,
+
+    /// Additional regular expressions to redact.
+    pub patterns: Vec<String>,
+}
+
+impl Default for RolloutRedaction {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_keys: true,
+            emails: true,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Scanning of staged changes for credentials, from the `[secret_scan]`
+/// table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct SecretScan {
+    /// Scan before every commit the agent makes and ask the user before
+    /// committing changes with findings.
+    pub block_commits: bool,
+
+    /// Additional regular expressions reported as secrets.
+    pub patterns: Vec<String>,
+
+    /// Regular expressions for lines that are never reported.
+    pub allowlist: Vec<String>,
+}
+
+impl Default for SecretScan {
+    fn default() -> Self {
+        Self {
+            block_commits: true,
+            patterns: Vec::new(),
+            allowlist: Vec::new(),
+        }
+    }
+}
+
+/// Settings for the `extract_archive` tool, from the `[extract_archive]`
+/// table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct ExtractArchive {
+    /// Expose the `extract_archive` tool.
+    pub enabled: bool,
+
+    /// Maximum total size of the extracted files, in bytes.
+    pub max_bytes: u64,
+
+    /// Maximum number of files in an archive.
+    pub max_files: usize,
+}
+
+impl Default for ExtractArchive {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 1024 * 1024 * 1024,
+            max_files: 10_000,
+        }
+    }
+}
+
+/// Settings for the `mcp_resources` tool, from the `[mcp_resources]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct McpResources {
+    /// Expose the `mcp_resources` tool.
+    pub enabled: bool,
+
+    /// Maximum size of the resource text returned to the model, in bytes.
+    pub max_bytes: usize,
+}
+
+impl Default for McpResources {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Settings for answering the `sampling/createMessage` requests of MCP
+/// servers, from the `[mcp_sampling]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct McpSampling {
+    /// Let MCP servers request completions from the model.
+    pub enabled: bool,
+
+    /// Model that answers the requests. Defaults to the session's model.
+    pub model: Option<String>,
+
+    /// Maximum number of requests answered per session, across all servers.
+    pub max_requests: u32,
+}
+
+impl Default for McpSampling {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            max_requests: 20,
+        }
+    }
+}
+
+/// Health monitoring of MCP servers, from the `[mcp_health]` table. Servers
+/// that stop answering pings are reconnected with exponential backoff.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct McpHealth {
+    /// Ping the connected servers and reconnect those that do not answer.
+    pub enabled: bool,
+
+    /// Seconds between two rounds of pings.
+    pub check_interval_sec: u64,
+
+    /// Seconds a server has to answer a ping before it counts as hung.
+    pub ping_timeout_sec: u64,
+
+    /// Reconnect attempts before a server is given up on for the session.
+    pub max_reconnect_attempts: u32,
+
+    /// Upper bound, in seconds, of the doubling delay between attempts.
+    pub max_backoff_sec: u64,
+}
+
+impl Default for McpHealth {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_sec: 30,
+            ping_timeout_sec: 10,
+            max_reconnect_attempts: 5,
+            max_backoff_sec: 60,
+        }
+    }
+}
+
+/// Limits on what a session may use, from the `[session_budget]` table. When a
+/// limit is reached the running turn pauses until the user decides whether to
+/// continue. Unset limits are not enforced.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct SessionBudget {
+    /// Total tokens used by the session's turns.
+    pub max_tokens: Option<u64>,
+
+    /// Estimated cost in US dollars; only enforced for models with known
+    /// pricing.
+    pub max_cost_usd: Option<f64>,
+
+    /// Tool calls made by the model.
+    pub max_tool_calls: Option<u32>,
+
+    /// Seconds spent running turns.
+    pub max_wall_time_sec: Option<u64>,
+}
+
+/// Prices of a model in US dollars per million tokens, used to estimate the
+/// cost of each turn. Entries of the `[model_pricing]` table are keyed by
+/// model slug.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+pub struct ModelPricing {
+    pub input_per_million: f64,
+
+    /// Price of cached input tokens; defaults to the input price.
+    #[serde(default)]
+    pub cached_input_per_million: Option<f64>,
+
+    /// Price of output tokens, including reasoning tokens.
+    pub output_per_million: f64,
+}
+
+/// A database the `query_database` tool can query, from an entry of the
+/// `[databases]` table.
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct DatabaseConnection {
+    /// `sqlite:<path>`, `postgres://…` or `mysql://…`. A relative SQLite path
+    /// is resolved against the session's working directory.
+    pub url: String,
+}
+
+/// What happens to a submission that would start a turn while another turn
+/// is running, for example when several clients drive the same session.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BusySessionPolicy {
+    /// User input joins the running turn; compaction joins it too and a
+    /// review replaces it.
+    #[default]
+    Inject,
+    /// User input runs as a turn of its own once the running turn finishes.
+    /// Compaction and reviews are rejected with `EventMsg::SessionBusy`.
+    Queue,
+    /// Every such submission is rejected with `EventMsg::SessionBusy`.
+    Reject,
+}
+
+/// Where to load the team config from, and how to check it. Set `url` or
+/// `path`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+pub struct TeamConfigToml {
+    /// HTTPS URL of the team config. The last copy fetched is cached and
+    /// used while the URL cannot be reached.
+    pub url: Option<String>,
+
+    /// Path of the team config in a git checkout, relative to
+    /// `CODEX_HOME`. The file must be tracked by git and have no
+    /// uncommitted changes.
+    pub path: Option<PathBuf>,
+
+    /// Hex SHA-256 the team config must have.
+    pub sha256: Option<String>,
+
+    /// Base64 Ed25519 public key. The team config must then come with a
+    /// base64 signature of its contents at the same location plus `.sig`.
+    pub public_key: Option<String>,
+}
+
+/// Approval and sandbox policy for the workspaces under a directory, set in
+/// `[directory_policies."<pattern>"]`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+pub struct DirectoryPolicyToml {
+    pub approval_policy: Option<AskForApproval>,
+    pub sandbox_mode: Option<SandboxMode>,
+}
+
 // ===== OTEL configuration =====
 
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum OtelHttpProtocol {
     /// Binary payload
@@ -233,7 +1245,7 @@ pub enum OtelHttpProtocol {
 }
 
 /// Which OTEL exporter to use.
-#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Debug, Clone, PartialEq, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum OtelExporterKind {
     None,
@@ -249,7 +1261,7 @@ pub enum OtelExporterKind {
 }
 
 /// OTEL settings loaded from config.toml. Fields are optional so we can apply defaults.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 pub struct OtelConfigToml {
     /// Log user prompt in traces
     pub log_user_prompt: Option<bool>,
@@ -279,7 +1291,7 @@ impl Default for OtelConfig {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Notifications {
     Enabled(bool),
@@ -293,7 +1305,7 @@ impl Default for Notifications {
 }
 
 /// Collection of settings that are specific to the TUI.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 pub struct Tui {
     /// Enable desktop notifications from the TUI when the terminal is unfocused.
     /// Defaults to `false`.
@@ -301,7 +1313,7 @@ pub struct Tui {
     pub notifications: Notifications,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
@@ -324,7 +1336,7 @@ impl From<SandboxWorkspaceWrite> for codex_app_server_protocol::SandboxSettings
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ShellEnvironmentPolicyInherit {
     /// "Core" environment variables for the platform. On UNIX, this would
@@ -341,7 +1353,7 @@ pub enum ShellEnvironmentPolicyInherit {
 
 /// Policy for building the `env` when spawning a process via either the
 /// `shell` or `local_shell` tool.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 pub struct ShellEnvironmentPolicyToml {
     pub inherit: Option<ShellEnvironmentPolicyInherit>,
 
@@ -420,7 +1432,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default, Hash)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default, Hash, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum ReasoningSummaryFormat {
     #[default]
@@ -506,7 +1518,9 @@ mod tests {
             cfg.transport,
             McpServerTransportConfig::StreamableHttp {
                 url: "https://example.com/mcp".to_string(),
-                bearer_token: None
+                bearer_token: None,
+                bearer_token_env_var: None,
+                http_headers: None,
             }
         );
     }
@@ -525,11 +1539,138 @@ mod tests {
             cfg.transport,
             McpServerTransportConfig::StreamableHttp {
                 url: "https://example.com/mcp".to_string(),
-                bearer_token: Some("secret".to_string())
+                bearer_token: Some("secret".to_string()),
+                bearer_token_env_var: None,
+                http_headers: None,
             }
         );
     }
 
+    #[test]
+    fn deserialize_streamable_http_server_config_with_env_token_and_headers() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            url = "https://example.com/mcp"
+            bearer_token_env_var = "EXAMPLE_MCP_TOKEN"
+            http_headers = { "X-Team" = "infra" }
+        "#,
+        )
+        .expect("should deserialize http config");
+
+        assert_eq!(
+            cfg.transport,
+            McpServerTransportConfig::StreamableHttp {
+                url: "https://example.com/mcp".to_string(),
+                bearer_token: None,
+                bearer_token_env_var: Some("EXAMPLE_MCP_TOKEN".to_string()),
+                http_headers: Some(HashMap::from([("X-Team".to_string(), "infra".to_string())])),
+            }
+        );
+    }
+
+    #[test]
+    fn deserialize_tool_prefix_and_aliases() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "echo"
+            tool_prefix = "gh"
+            tool_aliases = { "search_repositories" = "repo_search" }
+        "#,
+        )
+        .expect("should deserialize tool naming");
+
+        assert_eq!(Some("gh".to_string()), cfg.tool_prefix);
+        assert_eq!(
+            HashMap::from([("search_repositories".to_string(), "repo_search".to_string())]),
+            cfg.tool_aliases
+        );
+
+        toml::from_str::<McpServerConfig>(
+            r#"
+            command = "echo"
+            tool_aliases = { "search" = "find.things" }
+        "#,
+        )
+        .expect_err("should reject an alias the model cannot call");
+        toml::from_str::<McpServerConfig>(
+            r#"
+            command = "echo"
+            tool_prefix = "git hub"
+        "#,
+        )
+        .expect_err("should reject an invalid prefix");
+    }
+
+    #[test]
+    fn deserialize_call_limits() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "echo"
+            max_concurrent_calls = 2
+            max_result_bytes = 65536
+        "#,
+        )
+        .expect("should deserialize call limits");
+
+        assert_eq!(Some(2), cfg.max_concurrent_calls);
+        assert_eq!(Some(65536), cfg.max_result_bytes);
+
+        toml::from_str::<McpServerConfig>(
+            r#"
+            command = "echo"
+            max_concurrent_calls = 0
+        "#,
+        )
+        .expect_err("should reject a limit of zero");
+    }
+
+    #[test]
+    fn deserialize_tool_filters_and_approvals() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "echo"
+            enabled_tools = ["search", "create_issue"]
+            disabled_tools = ["delete_repo"]
+            tool_approval = "ask"
+
+            [tool_approvals]
+            search = "auto"
+        "#,
+        )
+        .expect("should deserialize tool filters");
+
+        assert_eq!(
+            Some(vec!["search".to_string(), "create_issue".to_string()]),
+            cfg.enabled_tools
+        );
+        assert_eq!(vec!["delete_repo".to_string()], cfg.disabled_tools);
+        assert_eq!(Some(McpToolApproval::Ask), cfg.tool_approval);
+        assert_eq!(
+            HashMap::from([("search".to_string(), McpToolApproval::Auto)]),
+            cfg.tool_approvals
+        );
+
+        toml::from_str::<McpServerConfig>(
+            r#"
+            command = "echo"
+            tool_approval = "sometimes"
+        "#,
+        )
+        .expect_err("should reject an unknown approval");
+    }
+
+    #[test]
+    fn deserialize_rejects_bearer_token_with_env_var() {
+        toml::from_str::<McpServerConfig>(
+            r#"
+            url = "https://example.com/mcp"
+            bearer_token = "secret"
+            bearer_token_env_var = "EXAMPLE_MCP_TOKEN"
+        "#,
+        )
+        .expect_err("should reject both bearer token sources");
+    }
+
     #[test]
     fn deserialize_rejects_command_and_url() {
         toml::from_str::<McpServerConfig>(
@@ -9,6 +9,7 @@ use crate::codex_conversation::CodexConversation;
 use crate::config::Config;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
+use crate::file_locks::FileLocks;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::SessionConfiguredEvent;
@@ -37,6 +38,9 @@ pub struct ConversationManager {
     conversations: Arc<RwLock<HashMap<ConversationId, Arc<CodexConversation>>>>,
     auth_manager: Arc<AuthManager>,
     session_source: SessionSource,
+    /// Shared by every conversation so concurrent tasks in the same workspace
+    /// detect conflicting edits.
+    file_locks: Arc<FileLocks>,
 }
 
 impl ConversationManager {
@@ -45,6 +49,7 @@ impl ConversationManager {
             conversations: Arc::new(RwLock::new(HashMap::new())),
             auth_manager,
             session_source,
+            file_locks: Arc::new(FileLocks::default()),
         }
     }
 
@@ -75,6 +80,7 @@ impl ConversationManager {
             auth_manager,
             InitialHistory::New,
             self.session_source,
+            self.file_locks.clone(),
         )
         .await?;
         self.finalize_spawn(codex, conversation_id).await
@@ -133,7 +139,14 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, initial_history, self.session_source).await?;
+        } = Codex::spawn(
+            config,
+            auth_manager,
+            initial_history,
+            self.session_source,
+            self.file_locks.clone(),
+        )
+        .await?;
         self.finalize_spawn(codex, conversation_id).await
     }
 
@@ -162,12 +175,46 @@ impl ConversationManager {
         let history = RolloutRecorder::get_rollout_history(&path).await?;
         let history = truncate_before_nth_user_message(history, nth_user_message);
 
+        self.spawn_fork(history, config).await
+    }
+
+    /// Fork an existing conversation keeping its entire history, so the new
+    /// conversation continues from the latest turn under a fresh id.
+    pub async fn fork_conversation_from_latest(
+        &self,
+        config: Config,
+        path: PathBuf,
+    ) -> CodexResult<NewConversation> {
+        let items = RolloutRecorder::get_rollout_history(&path)
+            .await?
+            .get_rollout_items();
+        let history = if items.is_empty() {
+            InitialHistory::New
+        } else {
+            InitialHistory::Forked(items)
+        };
+
+        self.spawn_fork(history, config).await
+    }
+
+    async fn spawn_fork(
+        &self,
+        history: InitialHistory,
+        config: Config,
+    ) -> CodexResult<NewConversation> {
         // Spawn a new conversation with the computed initial history.
         let auth_manager = self.auth_manager.clone();
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, history, self.session_source).await?;
+        } = Codex::spawn(
+            config,
+            auth_manager,
+            history,
+            self.session_source,
+            self.file_locks.clone(),
+        )
+        .await?;
 
         self.finalize_spawn(codex, conversation_id).await
     }
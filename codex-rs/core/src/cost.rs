@@ -0,0 +1,184 @@
+//! Estimated cost per model, for one session and across every session.
+//!
+//! The cost of a session is summed from the [`TurnStats`] of its turns,
+//! including those recorded before it was resumed. The cost of every session
+//! is a running total kept in `$CODEX_HOME/costs.json`, to which each
+//! finished turn is added.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::protocol::ModelCost;
+use crate::protocol::TurnStats;
+
+/// File of `$CODEX_HOME` holding the cost of every session.
+const COSTS_FILE: &str = "costs.json";
+
+/// Attempts to lock the costs file before giving up on a turn.
+const MAX_RETRIES: u32 = 10;
+const RETRY_SLEEP: Duration = Duration::from_millis(100);
+
+/// The cost of `turns` per model, sorted by model.
+pub(crate) fn costs_by_model<'a>(turns: impl IntoIterator<Item = &'a TurnStats>) -> Vec<ModelCost> {
+    let mut costs = BTreeMap::<String, ModelCost>::new();
+    for stats in turns {
+        add_turn(
+            costs
+                .entry(stats.model.clone())
+                .or_insert_with(|| ModelCost {
+                    model: stats.model.clone(),
+                    ..Default::default()
+                }),
+            stats,
+        );
+    }
+    costs.into_values().collect()
+}
+
+fn add_turn(cost: &mut ModelCost, stats: &TurnStats) {
+    cost.turns += 1;
+    cost.token_usage.add_assign(&stats.token_usage);
+    match stats.estimated_cost_usd {
+        Some(usd) => cost.estimated_cost_usd += usd,
+        None => cost.unpriced_turns += 1,
+    }
+}
+
+/// Add `stats` to the cost of every session and return the new totals.
+/// Sessions running side by side take turns through an advisory lock on the
+/// file.
+pub(crate) async fn record_turn_cost(
+    codex_home: &Path,
+    stats: &TurnStats,
+) -> std::io::Result<Vec<ModelCost>> {
+    tokio::fs::create_dir_all(codex_home).await?;
+    let path = codex_home.join(COSTS_FILE);
+    let stats = stats.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        for _ in 0..MAX_RETRIES {
+            match file.try_lock() {
+                Ok(()) => return update_costs(&mut file, &stats),
+                Err(std::fs::TryLockError::WouldBlock) => std::thread::sleep(RETRY_SLEEP),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::WouldBlock,
+            format!("could not lock {} after multiple attempts", path.display()),
+        ))
+    })
+    .await?
+}
+
+fn update_costs(file: &mut std::fs::File, stats: &TurnStats) -> std::io::Result<Vec<ModelCost>> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let mut costs: Vec<ModelCost> = if contents.trim().is_empty() {
+        Vec::new()
+    } else {
+        serde_json::from_str(&contents)?
+    };
+    match costs.iter_mut().find(|cost| cost.model == stats.model) {
+        Some(cost) => add_turn(cost, stats),
+        None => {
+            costs.extend(costs_by_model([stats]));
+            costs.sort_by(|a, b| a.model.cmp(&b.model));
+        }
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    file.set_len(0)?;
+    file.write_all(&serde_json::to_vec_pretty(&costs)?)?;
+    file.flush()?;
+    Ok(costs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::TokenUsage;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn turn(model: &str, total_tokens: u64, cost: Option<f64>) -> TurnStats {
+        TurnStats {
+            model: model.to_string(),
+            token_usage: TokenUsage {
+                total_tokens,
+                ..Default::default()
+            },
+            estimated_cost_usd: cost,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sums_turns_per_model() {
+        let turns = [
+            turn("gpt-5-codex", 100, Some(0.5)),
+            turn("claude-sonnet-4-5", 10, None),
+            turn("gpt-5-codex", 50, Some(0.25)),
+        ];
+
+        assert_eq!(
+            costs_by_model(&turns),
+            vec![
+                ModelCost {
+                    model: "claude-sonnet-4-5".to_string(),
+                    turns: 1,
+                    token_usage: TokenUsage {
+                        total_tokens: 10,
+                        ..Default::default()
+                    },
+                    estimated_cost_usd: 0.0,
+                    unpriced_turns: 1,
+                },
+                ModelCost {
+                    model: "gpt-5-codex".to_string(),
+                    turns: 2,
+                    token_usage: TokenUsage {
+                        total_tokens: 150,
+                        ..Default::default()
+                    },
+                    estimated_cost_usd: 0.75,
+                    unpriced_turns: 0,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_a_running_total_across_sessions() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+
+        record_turn_cost(codex_home.path(), &turn("gpt-5-codex", 100, Some(0.5))).await?;
+        record_turn_cost(codex_home.path(), &turn("gpt-5", 10, Some(0.125))).await?;
+        let costs =
+            record_turn_cost(codex_home.path(), &turn("gpt-5-codex", 100, Some(0.5))).await?;
+
+        let totals: Vec<(String, u32, f64)> = costs
+            .into_iter()
+            .map(|cost| (cost.model, cost.turns, cost.estimated_cost_usd))
+            .collect();
+        assert_eq!(
+            totals,
+            vec![
+                ("gpt-5".to_string(), 1, 0.125),
+                ("gpt-5-codex".to_string(), 2, 1.0),
+            ]
+        );
+        Ok(())
+    }
+}
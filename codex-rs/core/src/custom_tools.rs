@@ -0,0 +1,252 @@
+//! Tools declared by the user under `[custom_tools.<name>]`. Each one runs a
+//! fixed command whose arguments are filled in from the model's call, so a
+//! project script such as `run_migrations` can be offered to the model as a
+//! first-class tool instead of something it has to discover and type out.
+
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::config_types::CustomToolConfig;
+use crate::protocol::SandboxPolicy;
+use codex_protocol::config_types::SandboxMode;
+
+/// Longest tool name the model APIs accept.
+const MAX_NAME_LEN: usize = 64;
+
+/// Checks a `[custom_tools.<name>]` entry when the config is loaded.
+pub(crate) fn validate(name: &str, tool: &CustomToolConfig) -> Result<(), String> {
+    validate_name(name).map_err(|e| format!("custom {e}"))?;
+    if tool.command.is_empty() {
+        return Err(format!("custom tool `{name}` has an empty command"));
+    }
+    match tool.parameters.get("type") {
+        Some(Value::String(kind)) if kind == "object" => {}
+        _ => {
+            return Err(format!(
+                "the parameters of custom tool `{name}` must be a JSON schema of type \"object\""
+            ));
+        }
+    }
+    for (field, schema) in [
+        ("output_schema", &tool.output_schema),
+        ("config_schema", &tool.config_schema),
+    ] {
+        if schema.as_ref().is_some_and(|schema| !schema.is_object()) {
+            return Err(format!(
+                "the {field} of custom tool `{name}` must be a JSON schema object"
+            ));
+        }
+    }
+    for element in &tool.command {
+        parse_template(element).map_err(|e| format!("custom tool `{name}`: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Checks the name of a tool that is not built in.
+pub(crate) fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LEN
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "tool name `{name}` must be 1 to {MAX_NAME_LEN} letters, digits, `_` or `-`"
+        ));
+    }
+    if name.contains("__") {
+        // Reserved for the fully-qualified names of MCP tools.
+        return Err(format!("tool name `{name}` must not contain `__`"));
+    }
+    Ok(())
+}
+
+/// Builds the command to run from the tool's template and the model's
+/// arguments.
+///
+/// `{name}` in an element is replaced by the argument `name`: strings as they
+/// are, other values as JSON. An element that is exactly `{name}` is dropped
+/// when the argument is absent or null, and expands to one element per item
+/// when it is an array. `{{` and `}}` stand for literal braces.
+pub(crate) fn render_command(
+    template: &[String],
+    args: &Map<String, Value>,
+) -> Result<Vec<String>, String> {
+    let mut command = Vec::with_capacity(template.len());
+    for element in template {
+        let parts = parse_template(element)?;
+        if let [Part::Placeholder(name)] = parts.as_slice() {
+            match args.get(*name) {
+                None | Some(Value::Null) => {}
+                Some(Value::Array(items)) => command.extend(items.iter().map(to_arg)),
+                Some(value) => command.push(to_arg(value)),
+            }
+            continue;
+        }
+        let mut rendered = String::new();
+        for part in parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(&text),
+                Part::Placeholder(name) => match args.get(name) {
+                    None | Some(Value::Null) => {
+                        return Err(format!("missing argument `{name}`"));
+                    }
+                    Some(value) => rendered.push_str(&to_arg(value)),
+                },
+            }
+        }
+        command.push(rendered);
+    }
+    Ok(command)
+}
+
+/// Policy a custom tool's command runs under. Without a `sandbox_mode` it is
+/// the session's; a `workspace-write` tool keeps the session's writable roots
+/// when the session is itself `workspace-write`.
+pub(crate) fn sandbox_policy(mode: Option<SandboxMode>, session: &SandboxPolicy) -> SandboxPolicy {
+    match mode {
+        None => session.clone(),
+        Some(SandboxMode::ReadOnly) => SandboxPolicy::new_read_only_policy(),
+        Some(SandboxMode::WorkspaceWrite) => match session {
+            SandboxPolicy::WorkspaceWrite { .. } => session.clone(),
+            _ => SandboxPolicy::new_workspace_write_policy(),
+        },
+        Some(SandboxMode::DangerFullAccess) => SandboxPolicy::DangerFullAccess,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Literal(String),
+    Placeholder(&'a str),
+}
+
+fn parse_template(element: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut rest = element;
+    while let Some(index) = rest.find(['{', '}']) {
+        literal.push_str(&rest[..index]);
+        let tail = &rest[index..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            literal.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        if tail.starts_with('}') {
+            return Err(format!("unmatched `}}` in `{element}`"));
+        }
+        let Some(end) = tail.find('}') else {
+            return Err(format!("unclosed `{{` in `{element}`"));
+        };
+        let name = &tail[1..end];
+        if name.is_empty() || name.contains('{') {
+            return Err(format!("invalid placeholder in `{element}`"));
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(std::mem::take(&mut literal)));
+        }
+        parts.push(Part::Placeholder(name));
+        rest = &tail[end + 1..];
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() || parts.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    Ok(parts)
+}
+
+fn to_arg(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn render(template: &[&str], args: Value) -> Result<Vec<String>, String> {
+        let template: Vec<String> = template.iter().map(ToString::to_string).collect();
+        let Value::Object(args) = args else {
+            panic!("arguments must be an object");
+        };
+        render_command(&template, &args)
+    }
+
+    #[test]
+    fn fills_in_arguments() {
+        let command = render(
+            &[
+                "./migrate",
+                "--target={version}",
+                "{dry_run}",
+                "{{literal}}",
+            ],
+            json!({ "version": "42", "dry_run": true }),
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["./migrate", "--target=42", "true", "{literal}"],
+            command
+        );
+    }
+
+    #[test]
+    fn whole_element_placeholders_drop_or_expand() {
+        let command = render(
+            &["pytest", "{flags}", "{paths}"],
+            json!({ "paths": ["a.py", "b.py"], "flags": null }),
+        )
+        .unwrap();
+        assert_eq!(vec!["pytest", "a.py", "b.py"], command);
+    }
+
+    #[test]
+    fn embedded_placeholders_require_their_argument() {
+        let error = render(&["deploy", "--env={env}"], json!({})).unwrap_err();
+        assert_eq!("missing argument `env`", error);
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert!(parse_template("{unclosed").is_err());
+        assert!(parse_template("stray}").is_err());
+        assert!(parse_template("{}").is_err());
+        assert_eq!(
+            vec![Part::Literal(String::new())],
+            parse_template("").unwrap()
+        );
+    }
+
+    #[test]
+    fn validates_names_and_schemas() {
+        let tool = CustomToolConfig {
+            description: String::new(),
+            parameters: json!({ "type": "object" }),
+            command: vec!["true".to_string()],
+            cwd: None,
+            sandbox_mode: None,
+            timeout_sec: None,
+            output_schema: None,
+            config_schema: None,
+        };
+        assert_eq!(Ok(()), validate("run_migrations", &tool));
+        assert!(validate("run migrations", &tool).is_err());
+        assert!(validate("server__tool", &tool).is_err());
+        let array_schema = CustomToolConfig {
+            parameters: json!({ "type": "array" }),
+            ..tool.clone()
+        };
+        assert!(validate("run_migrations", &array_schema).is_err());
+        let no_command = CustomToolConfig {
+            command: Vec::new(),
+            ..tool
+        };
+        assert!(validate("run_migrations", &no_command).is_err());
+    }
+}
@@ -0,0 +1,615 @@
+//! The `query_database` tool, which runs a read-only SQL query against a
+//! SQLite file or a database configured under `[databases]` and returns the
+//! rows as JSON. Queries run through the `sqlite3`, `psql` and `mysql` command
+//! line clients, opened so that the server or file refuses writes.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use indexmap::IndexMap;
+use reqwest::Url;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config_types::DatabaseConnection;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::read_file::is_readable;
+
+pub(crate) const DATABASE_QUERY_TOOL_NAME: &str = "query_database";
+
+const DEFAULT_MAX_ROWS: usize = 200;
+const MAX_ROWS_LIMIT: usize = 5_000;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Statements accepted for SQLite databases.
+const SQLITE_STATEMENTS: &[&str] = &["select", "with", "values", "pragma", "explain"];
+/// Statements accepted for Postgres, where the query becomes a subquery.
+const POSTGRES_STATEMENTS: &[&str] = &["select", "with", "values", "table"];
+/// Statements accepted for MySQL.
+const MYSQL_STATEMENTS: &[&str] = &["select", "with", "show", "describe", "desc", "explain"];
+
+#[derive(Debug, Deserialize)]
+struct DatabaseQueryArgs {
+    query: String,
+    /// SQLite file, relative to the working directory.
+    #[serde(default)]
+    path: Option<String>,
+    /// Name of a connection from `[databases]`.
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    max_rows: Option<usize>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct QueryOutput {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    /// Whether rows past `max_rows` were left out.
+    truncated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Target {
+    Sqlite(PathBuf),
+    Postgres(String),
+    Mysql(Url),
+}
+
+pub(crate) async fn handle_database_query_tool(
+    databases: &HashMap<String, DatabaseConnection>,
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: DatabaseQueryArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let output = query(databases, cwd, sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+    serde_json::to_string(&output)
+        .map_err(|e| FunctionCallError::RespondToModel(format!("failed to encode rows: {e}")))
+}
+
+async fn query(
+    databases: &HashMap<String, DatabaseConnection>,
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    args: DatabaseQueryArgs,
+) -> Result<QueryOutput, String> {
+    let target = match (&args.path, &args.database) {
+        (Some(path), None) => Target::Sqlite(cwd.join(path)),
+        (None, Some(name)) => {
+            let connection = databases
+                .get(name)
+                .ok_or_else(|| format!("no database named `{name}` is configured"))?;
+            parse_target(cwd, &connection.url)?
+        }
+        _ => return Err("pass exactly one of `path` and `database`".to_string()),
+    };
+    let max_rows = args
+        .max_rows
+        .unwrap_or(DEFAULT_MAX_ROWS)
+        .clamp(1, MAX_ROWS_LIMIT);
+
+    let mut output = match &target {
+        Target::Sqlite(path) => {
+            if !is_readable(sandbox_policy, cwd, path) {
+                return Err(format!(
+                    "reading {} is not allowed by the sandbox policy",
+                    path.display()
+                ));
+            }
+            if !path.is_file() {
+                return Err(format!("{} does not exist", path.display()));
+            }
+            let statement = read_only_statement(&args.query, SQLITE_STATEMENTS, false)?;
+            query_sqlite(path, statement).await?
+        }
+        Target::Postgres(url) => {
+            if !sandbox_policy.has_full_network_access() {
+                return Err("network access is disabled by the sandbox policy".to_string());
+            }
+            let statement = read_only_statement(&args.query, POSTGRES_STATEMENTS, false)?;
+            query_postgres(url, statement, max_rows).await?
+        }
+        Target::Mysql(url) => {
+            if !sandbox_policy.has_full_network_access() {
+                return Err("network access is disabled by the sandbox policy".to_string());
+            }
+            let statement = read_only_statement(&args.query, MYSQL_STATEMENTS, true)?;
+            query_mysql(url, statement).await?
+        }
+    };
+    if output.rows.len() > max_rows {
+        output.rows.truncate(max_rows);
+        output.truncated = true;
+    }
+    Ok(output)
+}
+
+fn parse_target(cwd: &Path, url: &str) -> Result<Target, String> {
+    if let Some(path) = url.strip_prefix("sqlite:") {
+        let path = path.strip_prefix("//").unwrap_or(path);
+        return Ok(Target::Sqlite(cwd.join(path)));
+    }
+    let parsed = Url::parse(url).map_err(|e| format!("invalid database URL: {e}"))?;
+    match parsed.scheme() {
+        "postgres" | "postgresql" => Ok(Target::Postgres(url.to_string())),
+        "mysql" => Ok(Target::Mysql(parsed)),
+        scheme => Err(format!("unsupported database scheme `{scheme}`")),
+    }
+}
+
+/// The single statement in `query`, without a trailing `;`, if it starts
+/// with one of the `allowed` keywords. `backslash_escapes` is whether a
+/// backslash escapes the next character of a string, as in MySQL. The
+/// databases are also opened read-only; this check only gives a clearer
+/// error.
+fn read_only_statement<'a>(
+    query: &'a str,
+    allowed: &[&str],
+    backslash_escapes: bool,
+) -> Result<&'a str, String> {
+    let (statement, mut rest) = split_statement(query.trim(), backslash_escapes);
+    loop {
+        rest = skip_comments(rest);
+        match rest.strip_prefix(';') {
+            Some(after) => rest = after,
+            None if rest.is_empty() => break,
+            None => return Err("run one statement at a time".to_string()),
+        }
+    }
+    let statement = statement.trim_end();
+    let keyword = skip_comments(statement)
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if !allowed.contains(&keyword.as_str()) {
+        return Err(format!(
+            "only read-only queries are allowed; expected a statement starting with one of: {}",
+            allowed.join(", ")
+        ));
+    }
+    Ok(statement)
+}
+
+/// `sql` split at its first `;` outside strings, quoted identifiers and
+/// comments, without the `;`.
+fn split_statement(sql: &str, backslash_escapes: bool) -> (&str, &str) {
+    let bytes = sql.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b';' => return (&sql[..index], &sql[index + 1..]),
+            b'-' if bytes.get(index + 1) == Some(&b'-') => {
+                index = sql[index..]
+                    .find('\n')
+                    .map_or(bytes.len(), |end| index + end + 1);
+            }
+            b'/' if bytes.get(index + 1) == Some(&b'*') => {
+                index = sql[index + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| index + 2 + end + 2);
+            }
+            quote @ (b'\'' | b'"' | b'`') => {
+                index += 1;
+                while index < bytes.len() && bytes[index] != quote {
+                    if backslash_escapes && bytes[index] == b'\\' {
+                        index += 1;
+                    }
+                    index += 1;
+                }
+                index += 1;
+            }
+            _ => index += 1,
+        }
+    }
+    (sql, "")
+}
+
+fn skip_comments(mut sql: &str) -> &str {
+    loop {
+        sql = sql.trim_start();
+        if let Some(rest) = sql.strip_prefix("--") {
+            sql = rest.split_once('\n').map_or("", |(_, rest)| rest);
+        } else if let Some(rest) = sql.strip_prefix("/*") {
+            sql = rest.split_once("*/").map_or("", |(_, rest)| rest);
+        } else {
+            return sql;
+        }
+    }
+}
+
+async fn query_sqlite(path: &Path, statement: &str) -> Result<QueryOutput, String> {
+    // The statement goes on stdin, where one starting with `-`, such as a
+    // comment, is not taken for an option.
+    let path = path.to_string_lossy();
+    let stdout = run(
+        "sqlite3",
+        &["-readonly", "-safe", "-bail", "-json", &path],
+        &[],
+        Some(statement),
+    )
+    .await?;
+    if stdout.trim().is_empty() {
+        return Ok(QueryOutput::default());
+    }
+    json_rows(&stdout)
+}
+
+async fn query_postgres(
+    url: &str,
+    statement: &str,
+    max_rows: usize,
+) -> Result<QueryOutput, String> {
+    // The newline ends a line comment the statement may end with.
+    let sql = format!(
+        "SELECT coalesce(json_agg(t), '[]'::json) FROM (SELECT * FROM ({statement}\n) q LIMIT {}) t",
+        max_rows + 1
+    );
+    let stdout = run(
+        "psql",
+        &[
+            "--no-psqlrc",
+            "--quiet",
+            "--no-align",
+            "--tuples-only",
+            "--set=ON_ERROR_STOP=1",
+            "--dbname",
+            url,
+            "--command",
+            &sql,
+        ],
+        &[("PGOPTIONS", "-c default_transaction_read_only=on")],
+        None,
+    )
+    .await?;
+    json_rows(&stdout)
+}
+
+async fn query_mysql(url: &Url, statement: &str) -> Result<QueryOutput, String> {
+    let mut args = vec![
+        "--batch".to_string(),
+        format!("--host={}", url.host_str().unwrap_or("localhost")),
+        format!("--port={}", url.port().unwrap_or(3306)),
+    ];
+    if !url.username().is_empty() {
+        args.push(format!("--user={}", percent_decode(url.username())));
+    }
+    let database = url.path().trim_start_matches('/');
+    if !database.is_empty() {
+        args.push(format!("--database={}", percent_decode(database)));
+    }
+    args.push(format!(
+        "--execute=SET SESSION TRANSACTION READ ONLY; {statement}"
+    ));
+    let password = url.password().map(percent_decode).unwrap_or_default();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let stdout = run("mysql", &args, &[("MYSQL_PWD", password.as_str())], None).await?;
+    Ok(parse_mysql_batch(&stdout))
+}
+
+/// Run `program`, writing `input`, if any, to its stdin, and return its
+/// stdout.
+async fn run(
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+    input: Option<&str>,
+) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+    if let Some(input) = input
+        && let Some(mut stdin) = child.stdin.take()
+    {
+        // Written alongside reading the output, so that neither pipe fills
+        // up while the other waits. Dropping stdin ends the input.
+        let input = input.to_string();
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input.as_bytes()).await;
+        });
+    }
+    let output = tokio::time::timeout(QUERY_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("query timed out after {}s", QUERY_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "query failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Rows from a JSON array of objects, keeping the column order.
+fn json_rows(json: &str) -> Result<QueryOutput, String> {
+    let objects: Vec<IndexMap<String, Value>> =
+        serde_json::from_str(json.trim()).map_err(|e| format!("unexpected query output: {e}"))?;
+    let columns = objects
+        .first()
+        .map(|row| row.keys().cloned().collect())
+        .unwrap_or_default();
+    Ok(QueryOutput {
+        columns,
+        rows: objects
+            .into_iter()
+            .map(|row| row.into_values().collect())
+            .collect(),
+        truncated: false,
+    })
+}
+
+/// Parse the tab-separated output of `mysql --batch`. Values are strings,
+/// except `NULL`.
+fn parse_mysql_batch(output: &str) -> QueryOutput {
+    let mut lines = output.lines();
+    let Some(header) = lines.next() else {
+        return QueryOutput::default();
+    };
+    QueryOutput {
+        columns: header.split('\t').map(unescape_mysql).collect(),
+        rows: lines
+            .map(|line| {
+                line.split('\t')
+                    .map(|field| match field {
+                        "NULL" => Value::Null,
+                        field => Value::String(unescape_mysql(field)),
+                    })
+                    .collect()
+            })
+            .collect(),
+        truncated: false,
+    }
+}
+
+fn unescape_mysql(field: &str) -> String {
+    let mut value = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('0') => value.push('\0'),
+            Some(other) => value.push(other),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn accepts_only_single_read_only_statements() {
+        assert_eq!(
+            Ok("-- tables\nSELECT name FROM sqlite_master"),
+            read_only_statement(
+                "  -- tables\nSELECT name FROM sqlite_master;\n",
+                SQLITE_STATEMENTS,
+                false
+            )
+        );
+        assert_eq!(
+            Ok("/* schema */ pragma table_info(users)"),
+            read_only_statement(
+                "/* schema */ pragma table_info(users)",
+                SQLITE_STATEMENTS,
+                false
+            )
+        );
+        assert!(read_only_statement("DELETE FROM users", SQLITE_STATEMENTS, false).is_err());
+        assert!(
+            read_only_statement("pragma table_info(users)", POSTGRES_STATEMENTS, false).is_err()
+        );
+        assert!(
+            read_only_statement("SELECT 1; DROP TABLE users", SQLITE_STATEMENTS, false).is_err()
+        );
+        assert!(read_only_statement(".tables", SQLITE_STATEMENTS, false).is_err());
+    }
+
+    #[test]
+    fn semicolons_in_strings_and_comments_do_not_split_statements() {
+        assert_eq!(
+            Ok("SELECT * FROM notes WHERE body LIKE '%;%' AND \"a;b\" = 'it''s;'"),
+            read_only_statement(
+                "SELECT * FROM notes WHERE body LIKE '%;%' AND \"a;b\" = 'it''s;';",
+                SQLITE_STATEMENTS,
+                false
+            )
+        );
+        assert_eq!(
+            Ok("SELECT 1 -- one; or two\n/* ; */"),
+            read_only_statement(
+                "SELECT 1 -- one; or two\n/* ; */; -- done;\n",
+                SQLITE_STATEMENTS,
+                false
+            )
+        );
+        assert_eq!(
+            Ok("SELECT 'a\\'; b'"),
+            read_only_statement("SELECT 'a\\'; b'", MYSQL_STATEMENTS, true)
+        );
+        assert!(read_only_statement("SELECT 'a\\'; b'", SQLITE_STATEMENTS, false).is_err());
+        assert!(
+            read_only_statement(
+                "SELECT ';' -- x\n; DROP TABLE users",
+                SQLITE_STATEMENTS,
+                false
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_targets_and_mysql_output() {
+        let cwd = Path::new("/work");
+        assert_eq!(
+            Ok(Target::Sqlite(PathBuf::from("/work/data/app.db"))),
+            parse_target(cwd, "sqlite:data/app.db")
+        );
+        assert_eq!(
+            Ok(Target::Postgres("postgres://ro@db/app".to_string())),
+            parse_target(cwd, "postgres://ro@db/app")
+        );
+        assert!(parse_target(cwd, "redis://localhost").is_err());
+        assert_eq!("p@ss word", percent_decode("p%40ss%20word"));
+
+        assert_eq!(
+            QueryOutput {
+                columns: vec!["id".to_string(), "note".to_string()],
+                rows: vec![
+                    vec![json!("1"), json!("two\tlines\n")],
+                    vec![json!("2"), Value::Null],
+                ],
+                truncated: false,
+            },
+            parse_mysql_batch("id\tnote\n1\ttwo\\tlines\\n\n2\tNULL\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn queries_sqlite_files() {
+        if which::which("sqlite3").is_err() {
+            return;
+        }
+        let dir = TempDir::new().unwrap();
+        let status = std::process::Command::new("sqlite3")
+            .arg(dir.path().join("app.db"))
+            .arg(
+                "CREATE TABLE users (id INTEGER, name TEXT, score REAL, note TEXT);
+                 INSERT INTO users VALUES (1, 'ada', 1.5, NULL), (2, 'bob', 2.0, 'x');",
+            )
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let databases = HashMap::from([(
+            "app".to_string(),
+            DatabaseConnection {
+                url: "sqlite:app.db".to_string(),
+            },
+        )]);
+        let args = |query: &str, max_rows: Option<usize>| DatabaseQueryArgs {
+            query: query.to_string(),
+            path: None,
+            database: Some("app".to_string()),
+            max_rows,
+        };
+        let policy = SandboxPolicy::ReadOnly;
+
+        let output = query(
+            &databases,
+            dir.path(),
+            &policy,
+            args("SELECT * FROM users ORDER BY id", Some(1)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            QueryOutput {
+                columns: vec![
+                    "id".to_string(),
+                    "name".to_string(),
+                    "score".to_string(),
+                    "note".to_string()
+                ],
+                rows: vec![vec![json!(1), json!("ada"), json!(1.5), Value::Null]],
+                truncated: true,
+            },
+            output
+        );
+
+        let output = query(
+            &databases,
+            dir.path(),
+            &policy,
+            DatabaseQueryArgs {
+                path: Some("app.db".to_string()),
+                database: None,
+                ..args("SELECT id FROM users WHERE id > 5", None)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(QueryOutput::default(), output);
+
+        let output = query(
+            &databases,
+            dir.path(),
+            &policy,
+            args(
+                "-- names with a semicolon\nSELECT name FROM users WHERE name != 'a;b';",
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(vec![vec![json!("ada")], vec![json!("bob")]], output.rows);
+
+        let err = query(
+            &databases,
+            dir.path(),
+            &policy,
+            args(
+                "WITH x AS (SELECT 1) INSERT INTO users (id) SELECT * FROM x",
+                None,
+            ),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("query failed"), "{err}");
+    }
+}
@@ -0,0 +1,840 @@
+//! The `audit_dependencies` tool, which reads the project's manifests
+//! (`Cargo.toml`, `package.json`, `pyproject.toml`) and reports the
+//! dependencies that are outdated or have known vulnerabilities, with the
+//! versions to upgrade to.
+//!
+//! Latest versions come from crates.io, the npm registry and PyPI, and
+//! vulnerabilities from the OSV database, so the tool needs network access.
+//! The version in use is read from `Cargo.lock` or `package-lock.json` when
+//! there is one and is otherwise the lowest version the requirement allows.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream;
+use reqwest::Client;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+
+use crate::default_client::create_client;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const AUDIT_DEPENDENCIES_TOOL_NAME: &str = "audit_dependencies";
+
+/// Manifests looked for in the working directory when no `manifest` is given.
+const MANIFESTS: &[&str] = &["Cargo.toml", "package.json", "pyproject.toml"];
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// Registry and OSV requests in flight at once.
+const CONCURRENT_REQUESTS: usize = 8;
+const OSV_QUERY_URL: &str = "https://api.osv.dev/v1/query";
+
+#[derive(Debug, Deserialize)]
+struct AuditArgs {
+    /// Manifest to audit, relative to the working directory.
+    #[serde(default)]
+    manifest: Option<String>,
+    /// Also report dependencies that are up to date.
+    #[serde(default)]
+    include_up_to_date: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ecosystem {
+    Cargo,
+    Npm,
+    PyPi,
+}
+
+impl Ecosystem {
+    /// The ecosystem's name in OSV.
+    fn osv_name(self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "crates.io",
+            Ecosystem::Npm => "npm",
+            Ecosystem::PyPi => "PyPI",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Dependency {
+    name: String,
+    /// The version requirement as written in the manifest.
+    requirement: String,
+    /// `dependencies`, `dev-dependencies` and so on.
+    kind: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ManifestReport {
+    path: String,
+    ecosystem: &'static str,
+    checked: usize,
+    dependencies: Vec<DependencyReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyReport {
+    name: String,
+    kind: String,
+    requirement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest: Option<String>,
+    /// Whether moving to `latest` crosses a breaking version boundary.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    breaking: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    vulnerabilities: Vec<Vulnerability>,
+    /// The lowest version that fixes every reported vulnerability.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upgrade_to_fix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Vulnerability {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    /// Versions that fix the vulnerability, one per affected range.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixed_in: Vec<String>,
+}
+
+pub(crate) async fn handle_audit_dependencies_tool(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: AuditArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    audit(cwd, sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+async fn audit(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    args: AuditArgs,
+) -> Result<String, String> {
+    if !sandbox_policy.has_full_network_access() {
+        return Err("network access is disabled by the sandbox policy".to_string());
+    }
+    let manifests: Vec<PathBuf> = match &args.manifest {
+        Some(manifest) => vec![cwd.join(manifest)],
+        None => MANIFESTS
+            .iter()
+            .map(|name| cwd.join(name))
+            .filter(|path| path.is_file())
+            .collect(),
+    };
+    if manifests.is_empty() {
+        return Err(format!(
+            "no Cargo.toml, package.json or pyproject.toml in {}",
+            cwd.display()
+        ));
+    }
+
+    let client = create_client();
+    let mut reports = Vec::new();
+    for path in manifests {
+        let (ecosystem, dependencies) = read_manifest(&path)?;
+        let locked = locked_versions(ecosystem, &path);
+        let mut dependencies: Vec<DependencyReport> = stream::iter(dependencies)
+            .map(|dependency| {
+                let current = locked
+                    .get(&dependency.name)
+                    .cloned()
+                    .or_else(|| minimum_version(&dependency.requirement));
+                audit_dependency(&client, ecosystem, dependency, current)
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .collect()
+            .await;
+        let checked = dependencies.len();
+        if !args.include_up_to_date {
+            dependencies.retain(|d| {
+                d.error.is_some() || !d.vulnerabilities.is_empty() || d.latest.is_some()
+            });
+        }
+        dependencies.sort_by(|a, b| {
+            b.vulnerabilities
+                .len()
+                .cmp(&a.vulnerabilities.len())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        reports.push(ManifestReport {
+            path: path
+                .strip_prefix(cwd)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+            ecosystem: ecosystem.osv_name(),
+            checked,
+            dependencies,
+        });
+    }
+    serde_json::to_string_pretty(&json!({ "manifests": reports }))
+        .map_err(|e| format!("failed to serialize the audit: {e}"))
+}
+
+async fn audit_dependency(
+    client: &Client,
+    ecosystem: Ecosystem,
+    dependency: Dependency,
+    current: Option<String>,
+) -> DependencyReport {
+    let mut report = DependencyReport {
+        name: dependency.name,
+        kind: dependency.kind,
+        requirement: dependency.requirement,
+        current,
+        latest: None,
+        breaking: false,
+        vulnerabilities: Vec::new(),
+        upgrade_to_fix: None,
+        error: None,
+    };
+    match latest_version(client, ecosystem, &report.name).await {
+        Ok(latest) => {
+            let newer = match &report.current {
+                Some(current) => compare_versions(&latest, current) == Ordering::Greater,
+                None => true,
+            };
+            if newer {
+                report.breaking = report
+                    .current
+                    .as_deref()
+                    .is_some_and(|current| is_breaking(current, &latest));
+                report.latest = Some(latest);
+            }
+        }
+        Err(e) => report.error = Some(e),
+    }
+    if let Some(current) = &report.current {
+        match vulnerabilities(client, ecosystem, &report.name, current).await {
+            Ok(vulnerabilities) => {
+                report.upgrade_to_fix = upgrade_to_fix(current, &vulnerabilities);
+                report.vulnerabilities = vulnerabilities;
+            }
+            Err(e) => report.error = Some(e),
+        }
+    }
+    report
+}
+
+async fn latest_version(
+    client: &Client,
+    ecosystem: Ecosystem,
+    name: &str,
+) -> Result<String, String> {
+    let (url, pointers) = match ecosystem {
+        Ecosystem::Cargo => (
+            format!("https://crates.io/api/v1/crates/{name}"),
+            &["/crate/max_stable_version", "/crate/max_version"][..],
+        ),
+        Ecosystem::Npm => (
+            format!("https://registry.npmjs.org/{}", name.replace('/', "%2F")),
+            &["/dist-tags/latest"][..],
+        ),
+        Ecosystem::PyPi => (
+            format!("https://pypi.org/pypi/{name}/json"),
+            &["/info/version"][..],
+        ),
+    };
+    let response = client
+        .get(&url)
+        // npm's abbreviated metadata is a fraction of the full document.
+        .header(
+            "Accept",
+            "application/vnd.npm.install-v1+json, application/json",
+        )
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("failed to query {url}: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{url} returned HTTP {status}"));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("failed to read {url}: {e}"))?;
+    pointers
+        .iter()
+        .find_map(|pointer| body.pointer(pointer)?.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("{url} did not report a latest version"))
+}
+
+async fn vulnerabilities(
+    client: &Client,
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+) -> Result<Vec<Vulnerability>, String> {
+    let response = client
+        .post(OSV_QUERY_URL)
+        .json(&json!({
+            "package": { "name": name, "ecosystem": ecosystem.osv_name() },
+            "version": version,
+        }))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("failed to query OSV: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("OSV returned HTTP {status}"));
+    }
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("failed to read the OSV response: {e}"))?;
+    Ok(parse_osv_vulnerabilities(&body, name))
+}
+
+fn parse_osv_vulnerabilities(body: &Value, name: &str) -> Vec<Vulnerability> {
+    let strings = |value: &Value| -> Vec<String> {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.as_str().map(str::to_string))
+            .collect()
+    };
+    body["vulns"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|vuln| {
+            let mut fixed_in: Vec<String> = vuln["affected"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|affected| {
+                    affected["package"]["name"]
+                        .as_str()
+                        .is_none_or(|affected| affected.eq_ignore_ascii_case(name))
+                })
+                .flat_map(|affected| affected["ranges"].as_array().into_iter().flatten())
+                .flat_map(|range| range["events"].as_array().into_iter().flatten())
+                .filter_map(|event| event["fixed"].as_str().map(str::to_string))
+                .collect();
+            fixed_in.sort_by(|a, b| compare_versions(a, b));
+            fixed_in.dedup();
+            Vulnerability {
+                id: vuln["id"].as_str().unwrap_or_default().to_string(),
+                summary: vuln["summary"].as_str().map(str::to_string),
+                aliases: strings(&vuln["aliases"]),
+                fixed_in,
+            }
+        })
+        .collect()
+}
+
+/// The lowest version above `current` that fixes every vulnerability, or
+/// `None` when one of them has no fix.
+fn upgrade_to_fix(current: &str, vulnerabilities: &[Vulnerability]) -> Option<String> {
+    let mut target: Option<&str> = None;
+    for vulnerability in vulnerabilities {
+        // Of a vulnerability's fixes, the first after the version in use.
+        let fix = vulnerability
+            .fixed_in
+            .iter()
+            .find(|fixed| compare_versions(fixed, current) == Ordering::Greater)?;
+        if target.is_none_or(|target| compare_versions(fix, target) == Ordering::Greater) {
+            target = Some(fix);
+        }
+    }
+    target.map(str::to_string)
+}
+
+/// The ecosystem of the manifest at `path` and the dependencies it declares.
+fn read_manifest(path: &Path) -> Result<(Ecosystem, Vec<Dependency>), String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let parsed = match file_name {
+        "Cargo.toml" => {
+            toml::from_str(&contents).map(|m| (Ecosystem::Cargo, cargo_dependencies(&m)))
+        }
+        "pyproject.toml" => {
+            toml::from_str(&contents).map(|m| (Ecosystem::PyPi, python_dependencies(&m)))
+        }
+        "package.json" => {
+            return serde_json::from_str(&contents)
+                .map(|m| (Ecosystem::Npm, npm_dependencies(&m)))
+                .map_err(|e| format!("failed to parse {}: {e}", path.display()));
+        }
+        _ => {
+            return Err(format!(
+                "{} is not a Cargo.toml, package.json or pyproject.toml",
+                path.display()
+            ));
+        }
+    };
+    parsed.map_err(|e| format!("failed to parse {}: {e}", path.display()))
+}
+
+fn cargo_dependencies(manifest: &toml::Value) -> Vec<Dependency> {
+    let tables = [
+        (manifest.get("dependencies"), "dependencies"),
+        (manifest.get("dev-dependencies"), "dev-dependencies"),
+        (manifest.get("build-dependencies"), "build-dependencies"),
+        (
+            manifest
+                .get("workspace")
+                .and_then(|w| w.get("dependencies")),
+            "workspace.dependencies",
+        ),
+    ];
+    let mut dependencies = Vec::new();
+    for (table, kind) in tables {
+        let Some(table) = table.and_then(toml::Value::as_table) else {
+            continue;
+        };
+        for (key, spec) in table {
+            // Path, git and workspace-inherited dependencies have no version
+            // of their own on crates.io.
+            let (name, requirement) = match spec {
+                toml::Value::String(requirement) => (key.as_str(), requirement.as_str()),
+                toml::Value::Table(spec) => {
+                    let Some(requirement) = spec.get("version").and_then(toml::Value::as_str)
+                    else {
+                        continue;
+                    };
+                    let name = spec
+                        .get("package")
+                        .and_then(toml::Value::as_str)
+                        .unwrap_or(key);
+                    (name, requirement)
+                }
+                _ => continue,
+            };
+            dependencies.push(Dependency {
+                name: name.to_string(),
+                requirement: requirement.to_string(),
+                kind: kind.to_string(),
+            });
+        }
+    }
+    dependencies
+}
+
+fn npm_dependencies(manifest: &Value) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    for kind in ["dependencies", "devDependencies", "optionalDependencies"] {
+        let Some(table) = manifest[kind].as_object() else {
+            continue;
+        };
+        for (name, requirement) in table {
+            let Some(requirement) = requirement.as_str() else {
+                continue;
+            };
+            // Local, git and URL dependencies do not come from the registry.
+            if requirement.contains(':') || requirement.contains('/') {
+                continue;
+            }
+            dependencies.push(Dependency {
+                name: name.clone(),
+                requirement: requirement.to_string(),
+                kind: kind.to_string(),
+            });
+        }
+    }
+    dependencies
+}
+
+fn python_dependencies(manifest: &toml::Value) -> Vec<Dependency> {
+    let mut dependencies = Vec::new();
+    let project = manifest.get("project");
+    let mut push_requirements = |requirements: Option<&toml::Value>, kind: &str| {
+        for requirement in requirements
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+        {
+            if let Some(dependency) = parse_pep508(requirement, kind) {
+                dependencies.push(dependency);
+            }
+        }
+    };
+    push_requirements(project.and_then(|p| p.get("dependencies")), "dependencies");
+    if let Some(extras) = project
+        .and_then(|p| p.get("optional-dependencies"))
+        .and_then(toml::Value::as_table)
+    {
+        for (extra, requirements) in extras {
+            push_requirements(
+                Some(requirements),
+                &format!("optional-dependencies.{extra}"),
+            );
+        }
+    }
+
+    let poetry = manifest.get("tool").and_then(|t| t.get("poetry"));
+    if let Some(table) = poetry
+        .and_then(|p| p.get("dependencies"))
+        .and_then(toml::Value::as_table)
+    {
+        for (name, spec) in table {
+            let requirement = match spec {
+                toml::Value::String(requirement) => requirement.as_str(),
+                toml::Value::Table(spec) => {
+                    match spec.get("version").and_then(toml::Value::as_str) {
+                        Some(requirement) => requirement,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            if name == "python" {
+                continue;
+            }
+            dependencies.push(Dependency {
+                name: name.clone(),
+                requirement: requirement.to_string(),
+                kind: "tool.poetry.dependencies".to_string(),
+            });
+        }
+    }
+    dependencies
+}
+
+/// Splits a PEP 508 requirement such as `requests[socks]>=2.31; python_version
+/// >= "3.8"` into the distribution name and its version specifier.
+fn parse_pep508(requirement: &str, kind: &str) -> Option<Dependency> {
+    let requirement = requirement.split(';').next()?.trim();
+    // Direct references (`name @ url`) do not come from PyPI.
+    if requirement.contains('@') {
+        return None;
+    }
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+    let mut specifier = requirement[name_end..].trim();
+    if specifier.starts_with('[') {
+        specifier = specifier
+            .split_once(']')
+            .map_or("", |(_, rest)| rest.trim());
+    }
+    Some(Dependency {
+        name: name.to_string(),
+        requirement: specifier.trim_matches(|c| c == '(' || c == ')').to_string(),
+        kind: kind.to_string(),
+    })
+}
+
+/// The versions pinned by the lockfile next to `manifest`, keyed by name.
+fn locked_versions(ecosystem: Ecosystem, manifest: &Path) -> HashMap<String, String> {
+    let dir = manifest.parent().unwrap_or(Path::new("."));
+    match ecosystem {
+        Ecosystem::Cargo => std::fs::read_to_string(dir.join("Cargo.lock"))
+            .ok()
+            .and_then(|lock| toml::from_str::<toml::Value>(&lock).ok())
+            .map(|lock| cargo_locked_versions(&lock))
+            .unwrap_or_default(),
+        Ecosystem::Npm => std::fs::read_to_string(dir.join("package-lock.json"))
+            .ok()
+            .and_then(|lock| serde_json::from_str::<Value>(&lock).ok())
+            .map(|lock| npm_locked_versions(&lock))
+            .unwrap_or_default(),
+        Ecosystem::PyPi => HashMap::new(),
+    }
+}
+
+fn cargo_locked_versions(lock: &toml::Value) -> HashMap<String, String> {
+    let mut versions = HashMap::new();
+    for package in lock
+        .get("package")
+        .and_then(toml::Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let (Some(name), Some(version)) = (
+            package.get("name").and_then(toml::Value::as_str),
+            package.get("version").and_then(toml::Value::as_str),
+        ) else {
+            continue;
+        };
+        // When several versions are locked, report the newest.
+        versions
+            .entry(name.to_string())
+            .and_modify(|locked: &mut String| {
+                if compare_versions(version, locked) == Ordering::Greater {
+                    *locked = version.to_string();
+                }
+            })
+            .or_insert_with(|| version.to_string());
+    }
+    versions
+}
+
+fn npm_locked_versions(lock: &Value) -> HashMap<String, String> {
+    lock["packages"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(path, package)| {
+            // Only direct dependencies, which are not nested under another
+            // package's `node_modules`.
+            let name = path.strip_prefix("node_modules/")?;
+            if name.contains("/node_modules/") {
+                return None;
+            }
+            Some((name.to_string(), package["version"].as_str()?.to_string()))
+        })
+        .collect()
+}
+
+/// The lowest version a requirement such as `^1.2`, `>=2.0,<3` or `~=1.4.2`
+/// allows, padded to three components.
+fn minimum_version(requirement: &str) -> Option<String> {
+    requirement
+        .split([',', ' ', '|'])
+        .map(str::trim)
+        .filter(|part| !part.starts_with('<') && !part.starts_with("!="))
+        .find_map(|part| {
+            let version = part.trim_start_matches(['^', '~', '=', '>', 'v']);
+            if !version.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            let mut components: Vec<&str> = version
+                .split('.')
+                .take_while(|component| component.chars().all(|c| c.is_ascii_digit()))
+                .collect();
+            if components.is_empty() {
+                return None;
+            }
+            while components.len() < 3 {
+                components.push("0");
+            }
+            Some(components.join("."))
+        })
+}
+
+/// The numeric components of a version, ignoring pre-release and build
+/// suffixes.
+fn version_components(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|component| component.parse().ok())
+        .collect()
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_components(a), version_components(b));
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Whether `latest` is outside the range semver treats as compatible with
+/// `current`: a different leftmost non-zero component.
+fn is_breaking(current: &str, latest: &str) -> bool {
+    let (current, latest) = (version_components(current), version_components(latest));
+    let significant = current
+        .iter()
+        .position(|component| *component != 0)
+        .unwrap_or(current.len().saturating_sub(1));
+    (0..=significant).any(|i| current.get(i).unwrap_or(&0) != latest.get(i).unwrap_or(&0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn dependency(name: &str, requirement: &str, kind: &str) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            requirement: requirement.to_string(),
+            kind: kind.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_cargo_dependencies() {
+        let manifest: toml::Value = toml::from_str(
+            r#"
+            [dependencies]
+            serde = "1.0"
+            tokio = { version = "1.38", features = ["full"] }
+            local = { path = "../local" }
+            shared = { workspace = true }
+            json = { package = "serde_json", version = "1" }
+
+            [dev-dependencies]
+            tempfile = "3"
+            "#,
+        )
+        .unwrap();
+        let mut dependencies = cargo_dependencies(&manifest);
+        dependencies.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+        assert_eq!(
+            dependencies,
+            vec![
+                dependency("serde", "1.0", "dependencies"),
+                dependency("serde_json", "1", "dependencies"),
+                dependency("tokio", "1.38", "dependencies"),
+                dependency("tempfile", "3", "dev-dependencies"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_npm_and_python_dependencies() {
+        let package: Value = serde_json::from_str(
+            r#"{
+                "dependencies": { "react": "^18.2.0", "local": "file:../local" },
+                "devDependencies": { "@types/node": "~20.1.0", "fork": "user/repo" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            npm_dependencies(&package),
+            vec![
+                dependency("react", "^18.2.0", "dependencies"),
+                dependency("@types/node", "~20.1.0", "devDependencies"),
+            ]
+        );
+
+        let pyproject: toml::Value = toml::from_str(
+            r#"
+            [project]
+            dependencies = [
+                "requests[socks]>=2.31; python_version >= '3.8'",
+                "click",
+                "pkg @ https://example.com/pkg.whl",
+            ]
+
+            [project.optional-dependencies]
+            test = ["pytest~=8.0"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            python_dependencies(&pyproject),
+            vec![
+                dependency("requests", ">=2.31", "dependencies"),
+                dependency("click", "", "dependencies"),
+                dependency("pytest", "~=8.0", "optional-dependencies.test"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_locked_versions() {
+        let lock: toml::Value = toml::from_str(
+            r#"
+            [[package]]
+            name = "syn"
+            version = "1.0.109"
+
+            [[package]]
+            name = "syn"
+            version = "2.0.48"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cargo_locked_versions(&lock)["syn"], "2.0.48");
+
+        let lock: Value = serde_json::from_str(
+            r#"{ "packages": {
+                "": { "name": "app" },
+                "node_modules/react": { "version": "18.2.0" },
+                "node_modules/a/node_modules/react": { "version": "17.0.0" }
+            } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            npm_locked_versions(&lock),
+            HashMap::from([("react".to_string(), "18.2.0".to_string())])
+        );
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(minimum_version("^1.2"), Some("1.2.0".to_string()));
+        assert_eq!(minimum_version(">=2.0,<3"), Some("2.0.0".to_string()));
+        assert_eq!(minimum_version("~=1.4.2"), Some("1.4.2".to_string()));
+        assert_eq!(minimum_version("*"), None);
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0", "2.0.0"), Ordering::Equal);
+        assert!(is_breaking("1.4.0", "2.0.0"));
+        assert!(!is_breaking("1.4.0", "1.9.0"));
+        assert!(is_breaking("0.3.1", "0.4.0"));
+        assert!(!is_breaking("0.3.1", "0.3.9"));
+    }
+
+    #[test]
+    fn finds_the_upgrade_that_fixes_every_vulnerability() {
+        let body = json!({ "vulns": [
+            {
+                "id": "GHSA-1",
+                "summary": "Header injection",
+                "aliases": ["CVE-2024-1"],
+                "affected": [{
+                    "package": { "name": "requests", "ecosystem": "PyPI" },
+                    "ranges": [{ "events": [
+                        { "introduced": "0" }, { "fixed": "2.32.0" },
+                        { "introduced": "3.0.0" }, { "fixed": "3.0.2" }
+                    ] }]
+                }]
+            },
+            {
+                "id": "GHSA-2",
+                "affected": [{
+                    "package": { "name": "requests", "ecosystem": "PyPI" },
+                    "ranges": [{ "events": [{ "introduced": "2.0" }, { "fixed": "2.31.1" }] }]
+                }]
+            }
+        ] });
+        let vulnerabilities = parse_osv_vulnerabilities(&body, "requests");
+        assert_eq!(
+            vulnerabilities[0],
+            Vulnerability {
+                id: "GHSA-1".to_string(),
+                summary: Some("Header injection".to_string()),
+                aliases: vec!["CVE-2024-1".to_string()],
+                fixed_in: vec!["2.32.0".to_string(), "3.0.2".to_string()],
+            }
+        );
+        assert_eq!(
+            upgrade_to_fix("2.31.0", &vulnerabilities),
+            Some("2.32.0".to_string())
+        );
+    }
+}
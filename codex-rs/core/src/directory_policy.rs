@@ -0,0 +1,118 @@
+//! Approval and sandbox policies bound to directories, e.g. read-only and
+//! always-ask for everything under `~/work/prod-infra`, or full auto under
+//! `~/scratch`. The policy of a session is chosen by its workspace root.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use wildmatch::WildMatchPattern;
+
+use crate::config_types::DirectoryPolicyToml;
+
+/// The rule of `policies` for `workspace_root`, with its pattern.
+///
+/// A pattern applies to the directory it matches and everything under it; a
+/// leading `~` stands for the home directory, and `*` and `?` are wildcards.
+/// When several rules apply, the most specific pattern wins: the one with
+/// the most path components, then the one with the fewest wildcards.
+pub(crate) fn resolve_directory_policy<'a>(
+    policies: &'a BTreeMap<String, DirectoryPolicyToml>,
+    workspace_root: &Path,
+) -> Option<(&'a str, &'a DirectoryPolicyToml)> {
+    let home = dirs::home_dir();
+    policies
+        .iter()
+        .filter_map(|(pattern, policy)| {
+            let expanded = expand_home(pattern, home.as_deref());
+            let expanded = expanded.trim_end_matches(['/', '\\']);
+            let expanded = if expanded.is_empty() { "/" } else { expanded };
+            applies_to(expanded, workspace_root)
+                .then(|| (specificity(expanded), pattern.as_str(), policy))
+        })
+        .max_by_key(|(specificity, _, _)| *specificity)
+        .map(|(_, pattern, policy)| (pattern, policy))
+}
+
+/// Whether `pattern` matches `path` or one of its ancestors.
+fn applies_to(pattern: &str, path: &Path) -> bool {
+    let pattern = WildMatchPattern::<'*', '?'>::new(pattern);
+    path.ancestors()
+        .any(|ancestor| pattern.matches(&ancestor.to_string_lossy()))
+}
+
+fn specificity(pattern: &str) -> (usize, usize) {
+    let literal = pattern.chars().filter(|c| !matches!(c, '*' | '?')).count();
+    (Path::new(pattern).components().count(), literal)
+}
+
+fn expand_home(pattern: &str, home: Option<&Path>) -> String {
+    let rest = match pattern.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return pattern.to_string(),
+    };
+    match home {
+        Some(home) => {
+            let mut expanded = home.to_string_lossy().into_owned();
+            expanded.push_str(rest);
+            expanded
+        }
+        None => pattern.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::config_types::SandboxMode;
+    use codex_protocol::protocol::AskForApproval;
+    use pretty_assertions::assert_eq;
+
+    fn policies() -> BTreeMap<String, DirectoryPolicyToml> {
+        toml::from_str(
+            r#"
+["/work"]
+sandbox_mode = "workspace-write"
+
+["/work/prod-*"]
+approval_policy = "untrusted"
+sandbox_mode = "read-only"
+
+["/work/prod-api"]
+approval_policy = "on-request"
+
+["/scratch/"]
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+"#,
+        )
+        .expect("valid policies")
+    }
+
+    #[test]
+    fn most_specific_pattern_wins() {
+        let policies = policies();
+        let resolve = |root: &str| {
+            resolve_directory_policy(&policies, Path::new(root)).map(|(pattern, _)| pattern)
+        };
+
+        assert_eq!(resolve("/work/prod-infra/terraform"), Some("/work/prod-*"));
+        assert_eq!(resolve("/work/app"), Some("/work"));
+        assert_eq!(resolve("/scratch"), Some("/scratch/"));
+        assert_eq!(resolve("/workshop"), None);
+        assert_eq!(resolve("/work/prod-api"), Some("/work/prod-api"));
+
+        let (_, prod) = resolve_directory_policy(&policies, Path::new("/work/prod-infra"))
+            .expect("prod policy");
+        assert_eq!(prod.approval_policy, Some(AskForApproval::UnlessTrusted));
+        assert_eq!(prod.sandbox_mode, Some(SandboxMode::ReadOnly));
+    }
+
+    #[test]
+    fn tilde_expands_to_the_home_directory() {
+        let home = Path::new("/home/dev");
+        assert_eq!(expand_home("~/scratch", Some(home)), "/home/dev/scratch");
+        assert_eq!(expand_home("~", Some(home)), "/home/dev");
+        assert_eq!(expand_home("~other/x", Some(home)), "~other/x");
+        assert_eq!(expand_home("~/scratch", None), "~/scratch");
+    }
+}
@@ -0,0 +1,463 @@
+//! Checks of the configuration and the environment Codex runs in, as shown by
+//! `codex doctor` and, for the checks that need no network, by frontends when
+//! they start: unknown keys in `config.toml`, settings that fail to load,
+//! MCP server commands that cannot be found, the sandbox, and whether the
+//! model provider can be reached.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use toml::Value as TomlValue;
+
+use crate::auth::CodexAuth;
+use crate::config::CONFIG_TOML_FILE;
+use crate::config::Config;
+use crate::config::ConfigToml;
+use crate::config::ToolsToml;
+use crate::config::load_config_as_toml;
+use crate::config_profile::ConfigProfile;
+use crate::config_types::McpServerConfig;
+use crate::config_types::McpServerTransportConfig;
+use crate::exec::SandboxType;
+use crate::model_provider_info::ModelProviderInfo;
+use crate::protocol::SandboxPolicy;
+use crate::safety::get_platform_sandbox;
+use crate::seatbelt::MACOS_PATH_TO_SEATBELT_EXECUTABLE;
+
+/// How long the model provider gets to answer.
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a check looked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DoctorCategory {
+    Config,
+    ModelProvider,
+    McpServer,
+    Sandbox,
+}
+
+/// Outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DoctorStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DoctorCheck {
+    pub category: DoctorCategory,
+    pub status: DoctorStatus,
+    pub message: String,
+}
+
+impl DoctorCheck {
+    fn new(category: DoctorCategory, status: DoctorStatus, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DoctorCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.category, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// The checks that found something wrong.
+    pub fn problems(&self) -> impl Iterator<Item = &DoctorCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status != DoctorStatus::Ok)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == DoctorStatus::Error)
+    }
+}
+
+/// The checks that need no network, quick enough to run when a frontend
+/// starts.
+pub fn startup_report(config: &Config) -> DoctorReport {
+    let mut checks = check_config_file(&config.codex_home);
+    checks.extend(check_mcp_servers(config));
+    checks.extend(check_sandbox(config));
+    DoctorReport { checks }
+}
+
+/// Every check, including a request to the model provider.
+pub async fn full_report(config: &Config) -> DoctorReport {
+    let mut report = startup_report(config);
+    report.checks.extend(check_model_provider(config).await);
+    report
+}
+
+/// The report when the config could not be loaded: what is wrong with
+/// `config.toml` and the error that stopped the load.
+pub fn load_error_report(codex_home: &Path, err: &std::io::Error) -> DoctorReport {
+    let mut checks = check_config_file(codex_home);
+    checks.push(DoctorCheck::new(
+        DoctorCategory::Config,
+        DoctorStatus::Error,
+        format!("failed to load the configuration: {err}"),
+    ));
+    DoctorReport { checks }
+}
+
+/// Parse `config.toml` in `codex_home` and report its unknown keys.
+pub fn check_config_file(codex_home: &Path) -> Vec<DoctorCheck> {
+    let root = match load_config_as_toml(codex_home) {
+        Ok(root) => root,
+        Err(err) => {
+            return vec![DoctorCheck::new(
+                DoctorCategory::Config,
+                DoctorStatus::Error,
+                format!("{CONFIG_TOML_FILE}: {err}"),
+            )];
+        }
+    };
+    let unknown = unknown_keys(&root);
+    if unknown.is_empty() {
+        return vec![DoctorCheck::new(
+            DoctorCategory::Config,
+            DoctorStatus::Ok,
+            format!("{CONFIG_TOML_FILE} has no unknown keys"),
+        )];
+    }
+    unknown
+        .into_iter()
+        .map(|key| {
+            DoctorCheck::new(
+                DoctorCategory::Config,
+                DoctorStatus::Warning,
+                format!("unknown key `{key}` in {CONFIG_TOML_FILE}"),
+            )
+        })
+        .collect()
+}
+
+/// Dotted paths of the keys of `root` that no setting reads. The top level,
+/// profiles, model providers, MCP servers and `[tools]` are checked.
+fn unknown_keys(root: &TomlValue) -> Vec<String> {
+    let mut unknown = Vec::new();
+    let Some(root) = root.as_table() else {
+        return unknown;
+    };
+    let known = struct_fields::<ConfigToml>();
+    for (key, value) in root {
+        if !known.contains(&key.as_str()) {
+            unknown.push(key.clone());
+            continue;
+        }
+        let entry_fields = match key.as_str() {
+            "profiles" => struct_fields::<ConfigProfile>(),
+            "model_providers" => struct_fields::<ModelProviderInfo>(),
+            "mcp_servers" => struct_fields::<McpServerConfig>(),
+            "tools" => {
+                unknown_table_keys(key, value, struct_fields::<ToolsToml>(), &mut unknown);
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(entries) = value.as_table() {
+            for (name, entry) in entries {
+                unknown_table_keys(&format!("{key}.{name}"), entry, entry_fields, &mut unknown);
+            }
+        }
+    }
+    unknown
+}
+
+fn unknown_table_keys(path: &str, value: &TomlValue, known: &[&str], unknown: &mut Vec<String>) {
+    if let Some(table) = value.as_table() {
+        unknown.extend(
+            table
+                .keys()
+                .filter(|key| !known.contains(&key.as_str()))
+                .map(|key| format!("{path}.{key}")),
+        );
+    }
+}
+
+/// The keys the struct `T` is deserialized from, as its `Deserialize`
+/// implementation passes them to `deserialize_struct`.
+fn struct_fields<T: DeserializeOwned>() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> serde::Deserializer<'de> for FieldNames<'_> {
+        type Error = serde::de::value::Error;
+
+        fn deserialize_any<V: serde::de::Visitor<'de>>(
+            self,
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            Err(serde::de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: serde::de::Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(serde::de::Error::custom("field names recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Check that the command of each stdio MCP server can be found and that
+/// the URL of each HTTP server is valid.
+pub fn check_mcp_servers(config: &Config) -> Vec<DoctorCheck> {
+    let mut servers = config.mcp_servers.iter().collect::<Vec<_>>();
+    servers.sort_by_key(|(name, _)| name.as_str());
+    servers
+        .into_iter()
+        .map(|(name, server)| check_mcp_server(name, server, &config.cwd))
+        .collect()
+}
+
+fn check_mcp_server(name: &str, server: &McpServerConfig, cwd: &Path) -> DoctorCheck {
+    match &server.transport {
+        McpServerTransportConfig::Stdio { command, env, .. } => {
+            let path = env
+                .as_ref()
+                .and_then(|env| env.get("PATH").cloned().map(Into::into))
+                .or_else(|| std::env::var_os("PATH"));
+            match which::which_in(command, path, cwd) {
+                Ok(found) => DoctorCheck::new(
+                    DoctorCategory::McpServer,
+                    DoctorStatus::Ok,
+                    format!("`{name}` runs {}", found.display()),
+                ),
+                Err(_) => DoctorCheck::new(
+                    DoctorCategory::McpServer,
+                    DoctorStatus::Error,
+                    format!("`{name}`: command `{command}` not found"),
+                ),
+            }
+        }
+        McpServerTransportConfig::StreamableHttp { url, .. } => match reqwest::Url::parse(url) {
+            Ok(_) => DoctorCheck::new(
+                DoctorCategory::McpServer,
+                DoctorStatus::Ok,
+                format!("`{name}` connects to {url}"),
+            ),
+            Err(err) => DoctorCheck::new(
+                DoctorCategory::McpServer,
+                DoctorStatus::Error,
+                format!("`{name}`: invalid url `{url}`: {err}"),
+            ),
+        },
+    }
+}
+
+/// Check that the sandbox of the configured policy is available on this
+/// platform.
+pub fn check_sandbox(config: &Config) -> Vec<DoctorCheck> {
+    let check =
+        |status, message: &str| vec![DoctorCheck::new(DoctorCategory::Sandbox, status, message)];
+    if matches!(config.sandbox_policy, SandboxPolicy::DangerFullAccess) {
+        return check(
+            DoctorStatus::Ok,
+            "disabled by the danger-full-access sandbox policy",
+        );
+    }
+    match get_platform_sandbox() {
+        Some(SandboxType::MacosSeatbelt) => {
+            if Path::new(MACOS_PATH_TO_SEATBELT_EXECUTABLE).exists() {
+                check(DoctorStatus::Ok, "Seatbelt is available")
+            } else {
+                check(
+                    DoctorStatus::Error,
+                    &format!(
+                        "{MACOS_PATH_TO_SEATBELT_EXECUTABLE} not found; sandboxed commands will fail"
+                    ),
+                )
+            }
+        }
+        Some(SandboxType::LinuxSeccomp) => match &config.codex_linux_sandbox_exe {
+            Some(exe) if exe.exists() => check(DoctorStatus::Ok, "Landlock sandbox is available"),
+            _ => check(
+                DoctorStatus::Error,
+                "the codex-linux-sandbox executable is not available; sandboxed commands will fail",
+            ),
+        },
+        Some(SandboxType::None) | None => check(
+            DoctorStatus::Warning,
+            "no sandbox on this platform; commands run with your permissions",
+        ),
+    }
+}
+
+/// Check the credentials of the model provider and that its base URL
+/// answers.
+pub async fn check_model_provider(config: &Config) -> Vec<DoctorCheck> {
+    let provider = &config.model_provider;
+    let id = &config.model_provider_id;
+    let mut checks = Vec::new();
+    let auth = CodexAuth::from_codex_home(&config.codex_home)
+        .ok()
+        .flatten();
+    match provider.api_key() {
+        Ok(Some(_)) => {}
+        Ok(None) if provider.requires_openai_auth && auth.is_none() => {
+            checks.push(DoctorCheck::new(
+                DoctorCategory::ModelProvider,
+                DoctorStatus::Error,
+                format!("`{id}`: not logged in; run `codex login`"),
+            ));
+        }
+        Ok(None) => {}
+        Err(err) => checks.push(DoctorCheck::new(
+            DoctorCategory::ModelProvider,
+            DoctorStatus::Error,
+            format!("`{id}`: {err}"),
+        )),
+    }
+
+    let base_url = provider
+        .base_url
+        .clone()
+        .unwrap_or_else(|| "https://api.openai.com/v1".to_string());
+    let client = crate::default_client::create_client();
+    let response = client.get(&base_url).timeout(PROVIDER_TIMEOUT).send().await;
+    checks.push(match response {
+        // Any answer, even an error status, means the server is reachable.
+        Ok(response) => DoctorCheck::new(
+            DoctorCategory::ModelProvider,
+            DoctorStatus::Ok,
+            format!(
+                "`{id}` reachable at {base_url} (HTTP {})",
+                response.status()
+            ),
+        ),
+        Err(err) => DoctorCheck::new(
+            DoctorCategory::ModelProvider,
+            DoctorStatus::Error,
+            format!("`{id}`: cannot reach {base_url}: {err}"),
+        ),
+    });
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reports_unknown_keys() {
+        let root: TomlValue = toml::from_str(
+            r#"
+model = "gpt-5-codex"
+modle = "typo"
+
+[tools]
+web_search = true
+websearch = true
+
+[profiles.fast]
+model = "gpt-5-codex"
+approvals = "never"
+
+[model_providers.proxy]
+name = "Proxy"
+base_url = "https://proxy.example.com/v1"
+baseurl = "typo"
+
+[mcp_servers.docs]
+command = "docs-server"
+tool_timeout_sec = 30
+timeout = 30
+
+[history]
+persistence = "none"
+"#,
+        )
+        .expect("valid toml");
+
+        let mut unknown = unknown_keys(&root);
+        unknown.sort();
+
+        assert_eq!(
+            unknown,
+            vec![
+                "mcp_servers.docs.timeout".to_string(),
+                "model_providers.proxy.baseurl".to_string(),
+                "modle".to_string(),
+                "profiles.fast.approvals".to_string(),
+                "tools.websearch".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_mcp_commands() {
+        let cwd = std::env::temp_dir();
+        let server = |command: &str| McpServerConfig {
+            transport: McpServerTransportConfig::Stdio {
+                command: command.to_string(),
+                args: Vec::new(),
+                env: None,
+            },
+            startup_timeout_sec: None,
+            tool_timeout_sec: None,
+            tool_prefix: None,
+            tool_aliases: HashMap::new(),
+            max_concurrent_calls: None,
+            max_result_bytes: None,
+            enabled_tools: None,
+            disabled_tools: Vec::new(),
+            tool_approval: None,
+            tool_approvals: HashMap::new(),
+        };
+
+        let missing = check_mcp_server("docs", &server("codex-doctor-test-missing-command"), &cwd);
+        assert_eq!(
+            missing,
+            DoctorCheck::new(
+                DoctorCategory::McpServer,
+                DoctorStatus::Error,
+                "`docs`: command `codex-doctor-test-missing-command` not found",
+            )
+        );
+
+        let report = DoctorReport {
+            checks: vec![
+                missing,
+                DoctorCheck::new(DoctorCategory::Sandbox, DoctorStatus::Ok, "available"),
+            ],
+        };
+        assert!(report.has_errors());
+        assert_eq!(report.problems().count(), 1);
+    }
+}
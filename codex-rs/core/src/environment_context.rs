@@ -5,6 +5,7 @@ use strum_macros::Display as DeriveDisplay;
 use crate::codex::TurnContext;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::scratch::model_visible_sandbox_policy;
 use crate::shell::Shell;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::models::ContentItem;
@@ -100,7 +101,7 @@ impl From<&TurnContext> for EnvironmentContext {
         Self::new(
             Some(turn_context.cwd.clone()),
             Some(turn_context.approval_policy),
-            Some(turn_context.sandbox_policy.clone()),
+            Some(model_visible_sandbox_policy(turn_context)),
             // Shell is not configurable from turn to turn
             None,
         )
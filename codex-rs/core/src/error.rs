@@ -108,6 +108,11 @@ pub enum CodexErr {
     #[error("unsupported operation: {0}")]
     UnsupportedOperation(String),
 
+    /// The response cache is in `replay` mode and holds no response for the
+    /// request with this key.
+    #[error("no cached response for request {0} (response_cache mode is `replay`)")]
+    ResponseNotCached(String),
+
     // -----------------------------------------------------------------
     // Automatic conversions for common external error types
     // -----------------------------------------------------------------
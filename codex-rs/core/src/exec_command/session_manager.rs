@@ -224,6 +224,13 @@ impl SessionManager {
             output,
         })
     }
+
+    /// Close every open session, killing its process. Returns how many were
+    /// closed.
+    pub(crate) async fn close_all(&self) -> usize {
+        let sessions = std::mem::take(&mut *self.sessions.lock().await);
+        sessions.len()
+    }
 }
 
 /// Spawn PTY and child process per spawn_exec_command_session logic.
@@ -1,6 +1,7 @@
 use crate::config_types::EnvironmentVariablePattern;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyInherit;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -68,6 +69,30 @@ where
     env_map
 }
 
+/// Apply an `Op::UpdateSessionEnv` update to the session's variables.
+/// Nothing is changed when a name or value cannot be passed to a process.
+pub(crate) fn update_session_env(
+    env: &mut BTreeMap<String, String>,
+    set: BTreeMap<String, String>,
+    remove: &[String],
+) -> Result<(), String> {
+    for (key, value) in &set {
+        if key.is_empty() || key.contains(['=', '\0']) {
+            return Err(format!("invalid environment variable name `{key}`"));
+        }
+        if value.contains('\0') {
+            return Err(format!(
+                "value of environment variable `{key}` contains a NUL byte"
+            ));
+        }
+    }
+    for key in remove {
+        env.remove(key);
+    }
+    env.extend(set);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,6 +106,35 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn test_update_session_env() {
+        let mut env = BTreeMap::from([
+            ("DATABASE_URL".to_string(), "postgres://dev".to_string()),
+            ("RUST_LOG".to_string(), "debug".to_string()),
+        ]);
+        update_session_env(
+            &mut env,
+            BTreeMap::from([("DATABASE_URL".to_string(), "postgres://test".to_string())]),
+            &["RUST_LOG".to_string()],
+        )
+        .unwrap();
+        let expected =
+            BTreeMap::from([("DATABASE_URL".to_string(), "postgres://test".to_string())]);
+        assert_eq!(env, expected);
+
+        for (key, value) in [("", "x"), ("A=B", "x"), ("OK", "nul\0")] {
+            assert!(
+                update_session_env(
+                    &mut env,
+                    BTreeMap::from([(key.to_string(), value.to_string())]),
+                    &["DATABASE_URL".to_string()],
+                )
+                .is_err()
+            );
+        }
+        assert_eq!(env, expected);
+    }
+
     #[test]
     fn test_core_inherit_and_default_excludes() {
         let vars = make_vars(&[
@@ -0,0 +1,195 @@
+//! Config sections of extensions: `[extensions.<name>]` tables in
+//! `config.toml` that belong to a plugin, a custom tool or code embedding
+//! Codex, so each extension reads its settings from the one config file
+//! instead of inventing its own environment variables or sidecar files.
+//!
+//! ```toml
+//! [extensions.run_migrations]
+//! database = "postgres://localhost/app"
+//! dry_run = true
+//! ```
+//!
+//! Codex does not interpret the sections; an extension declares a JSON
+//! schema for its section and the section is checked against it.
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use crate::tool_output::check_json;
+
+/// Environment variable holding a custom tool's section, as JSON, when its
+/// command runs.
+pub const EXTENSION_CONFIG_ENV_VAR: &str = "CODEX_EXTENSION_CONFIG";
+
+/// An extension's settings, read from `[extensions.<SECTION>]`.
+pub trait ExtensionConfig: DeserializeOwned + JsonSchema {
+    /// Name of the section.
+    const SECTION: &'static str;
+}
+
+/// The `[extensions.<name>]` sections of the config, keyed by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtensionConfigs {
+    sections: BTreeMap<String, JsonValue>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExtensionConfigError {
+    #[error("[extensions.{section}] does not match its schema: {}", .violations.join("; "))]
+    Invalid {
+        section: String,
+        violations: Vec<String>,
+    },
+
+    #[error("[extensions.{section}]: {source}")]
+    Deserialize {
+        section: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl ExtensionConfigs {
+    pub fn new(sections: BTreeMap<String, JsonValue>) -> Self {
+        Self { sections }
+    }
+
+    /// The section `name` as written in the config.
+    pub fn section(&self, name: &str) -> Option<&JsonValue> {
+        self.sections.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.sections.keys().map(String::as_str)
+    }
+
+    /// The section `name`, checked against `schema`. An absent section is
+    /// checked, and returned, as an empty table.
+    pub fn validated(
+        &self,
+        name: &str,
+        schema: &JsonValue,
+    ) -> Result<JsonValue, ExtensionConfigError> {
+        let section = self
+            .sections
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| JsonValue::Object(Default::default()));
+        check_json(schema, &section).map_err(|violations| ExtensionConfigError::Invalid {
+            section: name.to_string(),
+            violations,
+        })?;
+        Ok(section)
+    }
+
+    /// The section of `T`, checked against the schema of `T` and
+    /// deserialized. An absent section deserializes as an empty table, so
+    /// `T`'s defaults apply.
+    pub fn get<T: ExtensionConfig>(&self) -> Result<T, ExtensionConfigError> {
+        let section = self.validated(T::SECTION, &schema_for::<T>())?;
+        serde_json::from_value(section).map_err(|source| ExtensionConfigError::Deserialize {
+            section: T::SECTION.to_string(),
+            source,
+        })
+    }
+}
+
+/// The JSON schema of `T`, with its subschemas inlined, as the schema
+/// validator does not follow references.
+fn schema_for<T: JsonSchema>() -> JsonValue {
+    let schema = SchemaSettings::draft07()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.option_add_null_type = false;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+    serde_json::to_value(schema).unwrap_or(JsonValue::Bool(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, JsonSchema, PartialEq)]
+    struct Migrations {
+        database: String,
+        #[serde(default)]
+        dry_run: bool,
+    }
+
+    impl ExtensionConfig for Migrations {
+        const SECTION: &'static str = "run_migrations";
+    }
+
+    #[derive(Debug, Default, Deserialize, JsonSchema, PartialEq)]
+    #[serde(default)]
+    struct Telemetry {
+        sample_rate: u32,
+    }
+
+    impl ExtensionConfig for Telemetry {
+        const SECTION: &'static str = "telemetry";
+    }
+
+    fn configs(toml: &str) -> ExtensionConfigs {
+        ExtensionConfigs::new(toml::from_str(toml).expect("valid sections"))
+    }
+
+    #[test]
+    fn sections_are_read_as_typed_values() {
+        let configs = configs(
+            r#"
+[run_migrations]
+database = "postgres://localhost/app"
+dry_run = true
+"#,
+        );
+
+        assert_eq!(
+            configs.get::<Migrations>().expect("valid section"),
+            Migrations {
+                database: "postgres://localhost/app".to_string(),
+                dry_run: true,
+            }
+        );
+        assert_eq!(
+            configs.get::<Telemetry>().expect("absent section"),
+            Telemetry::default()
+        );
+        assert_eq!(configs.names().collect::<Vec<_>>(), vec!["run_migrations"]);
+    }
+
+    #[test]
+    fn sections_are_checked_against_their_schema() {
+        let configs = configs(
+            r#"
+[run_migrations]
+database = 5
+"#,
+        );
+
+        let err = configs.get::<Migrations>().expect_err("invalid section");
+        assert!(
+            err.to_string()
+                .starts_with("[extensions.run_migrations] does not match its schema: "),
+            "{err}"
+        );
+
+        let schema = json!({ "type": "object", "required": ["token"] });
+        assert!(configs.validated("search", &schema).is_err());
+        assert_eq!(
+            configs
+                .validated("run_migrations", &json!({ "type": "object" }))
+                .expect("valid section"),
+            json!({ "database": 5 })
+        );
+    }
+}
@@ -0,0 +1,547 @@
+//! The `extract_archive` tool, which unpacks zip, tar and gzip-compressed tar
+//! archives without shelling out to `unzip` or `tar xf`.
+//!
+//! The whole archive is checked before anything is written: entries whose
+//! path leaves the destination, archives over the configured size or file
+//! limits and files that already exist (unless overwriting) are refused.
+//! Symbolic and hard links are not extracted, and the bytes written are
+//! counted again while extracting, so a header that understates a file's
+//! size cannot get past the limit.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::config_types::ExtractArchive;
+use crate::function_tool::FunctionCallError;
+use crate::util::is_zero;
+
+pub(crate) const EXTRACT_ARCHIVE_TOOL_NAME: &str = "extract_archive";
+
+/// Files listed by name in the tool output; the rest are only counted.
+const MAX_LISTED_FILES: usize = 500;
+
+const S_IFMT: u32 = 0o170_000;
+const S_IFLNK: u32 = 0o120_000;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExtractArchiveArgs {
+    pub(crate) path: String,
+    /// Directory to extract into, relative to the working directory.
+    #[serde(default)]
+    pub(crate) destination: Option<String>,
+    #[serde(default)]
+    pub(crate) overwrite: bool,
+}
+
+pub(crate) fn parse_extract_archive_arguments(
+    arguments: &str,
+) -> Result<ExtractArchiveArgs, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    File,
+    Directory,
+    Link,
+    Other,
+}
+
+struct RawEntry {
+    name: String,
+    kind: EntryKind,
+    size: u64,
+    mode: Option<u32>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct Skipped {
+    path: String,
+    reason: &'static str,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Extraction {
+    files: Vec<String>,
+    #[serde(skip_serializing_if = "is_zero")]
+    unlisted_files: usize,
+    directories: usize,
+    total_bytes: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<Skipped>,
+}
+
+/// Extract `archive` into `destination`, which the caller has checked
+/// against the sandbox policy.
+pub(crate) async fn handle_extract_archive(
+    archive: &Path,
+    destination: &Path,
+    limits: &ExtractArchive,
+    overwrite: bool,
+) -> Result<String, FunctionCallError> {
+    let (archive, destination, limits) = (
+        archive.to_path_buf(),
+        destination.to_path_buf(),
+        limits.clone(),
+    );
+    let extraction =
+        tokio::task::spawn_blocking(move || extract(&archive, &destination, &limits, overwrite))
+            .await
+            .map_err(|e| FunctionCallError::RespondToModel(format!("extraction failed: {e}")))?
+            .map_err(FunctionCallError::RespondToModel)?;
+    serde_json::to_string(&extraction).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to serialize extraction: {e}"))
+    })
+}
+
+fn extract(
+    archive: &Path,
+    destination: &Path,
+    limits: &ExtractArchive,
+    overwrite: bool,
+) -> Result<Extraction, String> {
+    let format = detect_format(archive)?;
+
+    // Check every entry before writing anything.
+    let mut files = 0;
+    let mut declared_bytes: u64 = 0;
+    let mut conflicts = Vec::new();
+    for_each_entry(archive, format, |entry, _| {
+        let relative = entry_path(&entry.name).ok_or_else(|| {
+            format!(
+                "refusing to extract {}: the entry's path leaves the destination",
+                entry.name
+            )
+        })?;
+        if entry.kind != EntryKind::File {
+            return Ok(());
+        }
+        if relative.as_os_str().is_empty() {
+            return Err(format!("refusing to extract {}: invalid path", entry.name));
+        }
+        files += 1;
+        if files > limits.max_files {
+            return Err(format!(
+                "the archive has more than {} files",
+                limits.max_files
+            ));
+        }
+        declared_bytes = declared_bytes.saturating_add(entry.size);
+        if declared_bytes > limits.max_bytes {
+            return Err(format!(
+                "the archive expands to more than {} bytes",
+                limits.max_bytes
+            ));
+        }
+        if !overwrite && destination.join(&relative).symlink_metadata().is_ok() {
+            conflicts.push(display_path(&relative));
+        }
+        Ok(())
+    })?;
+    if !conflicts.is_empty() {
+        conflicts.truncate(20);
+        return Err(format!(
+            "files already exist in {}: {}; pass overwrite to replace them",
+            destination.display(),
+            conflicts.join(", ")
+        ));
+    }
+
+    std::fs::create_dir_all(destination)
+        .map_err(|e| format!("failed to create {}: {e}", destination.display()))?;
+    let root = dunce::canonicalize(destination)
+        .map_err(|e| format!("failed to resolve {}: {e}", destination.display()))?;
+    let mut extraction = Extraction::default();
+    let result = for_each_entry(archive, format, |entry, reader| {
+        let Some(relative) = entry_path(&entry.name) else {
+            return Err(format!("refusing to extract {}", entry.name));
+        };
+        match entry.kind {
+            EntryKind::Directory => {
+                if !relative.as_os_str().is_empty() {
+                    create_dir_inside(&root, &relative)?;
+                    extraction.directories += 1;
+                }
+            }
+            EntryKind::File => {
+                let remaining = limits.max_bytes - extraction.total_bytes;
+                let written = write_file(&root, &relative, reader, remaining, entry.mode)?;
+                extraction.total_bytes += written;
+                if extraction.files.len() < MAX_LISTED_FILES {
+                    extraction.files.push(display_path(&relative));
+                } else {
+                    extraction.unlisted_files += 1;
+                }
+            }
+            EntryKind::Link => extraction.skipped.push(Skipped {
+                path: entry.name,
+                reason: "links are not extracted",
+            }),
+            EntryKind::Other => extraction.skipped.push(Skipped {
+                path: entry.name,
+                reason: "unsupported entry type",
+            }),
+        }
+        Ok(())
+    });
+    result.map_err(|e| {
+        format!(
+            "extraction stopped after {} files: {e}",
+            extraction.files.len() + extraction.unlisted_files
+        )
+    })?;
+    Ok(extraction)
+}
+
+/// The format of `archive`, from its first bytes.
+fn detect_format(archive: &Path) -> Result<Format, String> {
+    let mut header = Vec::with_capacity(512);
+    File::open(archive)
+        .and_then(|file| file.take(512).read_to_end(&mut header))
+        .map_err(|e| format!("failed to read {}: {e}", archive.display()))?;
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        Ok(Format::Zip)
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Ok(Format::TarGz)
+    } else if header.get(257..262) == Some(b"ustar".as_slice()) {
+        Ok(Format::Tar)
+    } else {
+        Err(format!(
+            "{} is not a zip, tar or tar.gz archive",
+            archive.display()
+        ))
+    }
+}
+
+fn for_each_entry(
+    archive: &Path,
+    format: Format,
+    mut visit: impl FnMut(RawEntry, &mut dyn Read) -> Result<(), String>,
+) -> Result<(), String> {
+    let file =
+        File::open(archive).map_err(|e| format!("failed to open {}: {e}", archive.display()))?;
+    match format {
+        Format::Zip => {
+            let mut zip =
+                zip::ZipArchive::new(file).map_err(|e| format!("invalid zip archive: {e}"))?;
+            for index in 0..zip.len() {
+                let mut entry = zip
+                    .by_index(index)
+                    .map_err(|e| format!("invalid zip archive: {e}"))?;
+                let mode = entry.unix_mode();
+                let kind = if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+                    EntryKind::Link
+                } else if entry.is_dir() {
+                    EntryKind::Directory
+                } else {
+                    EntryKind::File
+                };
+                let raw = RawEntry {
+                    name: entry.name().to_string(),
+                    kind,
+                    size: entry.size(),
+                    mode,
+                };
+                visit(raw, &mut entry)?;
+            }
+            Ok(())
+        }
+        Format::Tar => visit_tar(tar::Archive::new(file), visit),
+        Format::TarGz => visit_tar(tar::Archive::new(flate2::read::GzDecoder::new(file)), visit),
+    }
+}
+
+fn visit_tar<R: Read>(
+    mut archive: tar::Archive<R>,
+    mut visit: impl FnMut(RawEntry, &mut dyn Read) -> Result<(), String>,
+) -> Result<(), String> {
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("invalid tar archive: {e}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("invalid tar archive: {e}"))?;
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let kind = if entry_type.is_file() {
+            EntryKind::File
+        } else if entry_type.is_dir() {
+            EntryKind::Directory
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            EntryKind::Link
+        } else {
+            EntryKind::Other
+        };
+        let raw = RawEntry {
+            name: String::from_utf8_lossy(&entry.path_bytes()).into_owned(),
+            kind,
+            size: entry.size(),
+            mode: header.mode().ok(),
+        };
+        visit(raw, &mut entry)?;
+    }
+    Ok(())
+}
+
+/// The path of an entry relative to the destination, or `None` when it is
+/// absolute or climbs out with `..`. Both separators are accepted, since
+/// archives made on Windows may use backslashes.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => {
+                let mut components = Path::new(part).components();
+                if !matches!(components.next(), Some(Component::Normal(_)))
+                    || components.next().is_some()
+                {
+                    return None;
+                }
+                path.push(part);
+            }
+        }
+    }
+    // A leading separator makes the entry absolute.
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    Some(path)
+}
+
+fn display_path(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Create `relative` under `root`, making sure no existing symbolic link
+/// leads it outside.
+fn create_dir_inside(root: &Path, relative: &Path) -> Result<PathBuf, String> {
+    let dir = root.join(relative);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    let resolved = dunce::canonicalize(&dir)
+        .map_err(|e| format!("failed to resolve {}: {e}", dir.display()))?;
+    if !resolved.starts_with(root) {
+        return Err(format!(
+            "{} leads outside the destination through a symbolic link",
+            display_path(relative)
+        ));
+    }
+    Ok(resolved)
+}
+
+/// Write one file, reading at most `remaining` bytes. Returns the number of
+/// bytes written.
+fn write_file(
+    root: &Path,
+    relative: &Path,
+    reader: &mut dyn Read,
+    remaining: u64,
+    mode: Option<u32>,
+) -> Result<u64, String> {
+    let parent = match relative.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => create_dir_inside(root, parent)?,
+        _ => root.to_path_buf(),
+    };
+    let Some(file_name) = relative.file_name() else {
+        return Err(format!("invalid path {}", relative.display()));
+    };
+    let target = parent.join(file_name);
+    if target
+        .symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+    {
+        return Err(format!(
+            "refusing to write {} through a symbolic link",
+            display_path(relative)
+        ));
+    }
+    let mut file =
+        File::create(&target).map_err(|e| format!("failed to create {}: {e}", target.display()))?;
+    let written = std::io::copy(&mut reader.take(remaining.saturating_add(1)), &mut file)
+        .map_err(|e| format!("failed to extract {}: {e}", display_path(relative)))?;
+    if written > remaining {
+        drop(file);
+        let _ = std::fs::remove_file(&target);
+        return Err("the archive expands beyond the size limit".to_string());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Keep the executable bit, but no other permission bits.
+        let mode = if mode.is_some_and(|mode| mode & 0o111 != 0) {
+            0o755
+        } else {
+            0o644
+        };
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(mode))
+            .map_err(|e| format!("failed to set permissions of {}: {e}", target.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn limits() -> ExtractArchive {
+        ExtractArchive {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn tar_with(path: &Path, entries: &[(&[u8], &[u8])]) {
+        let mut builder = tar::Builder::new(File::create(path).expect("create"));
+        for (name, data) in entries {
+            // Write the name directly, since `append_data` rejects `..`.
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_cksum();
+            builder.append(&header, *data).expect("append");
+        }
+        builder.finish().expect("finish");
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_destination() {
+        assert_eq!(Some(PathBuf::from("a/b.txt")), entry_path("./a//b.txt"));
+        assert_eq!(Some(PathBuf::from("a/b.txt")), entry_path("a\\b.txt"));
+        assert_eq!(None, entry_path("../b.txt"));
+        assert_eq!(None, entry_path("a/../../b.txt"));
+        assert_eq!(None, entry_path("/etc/passwd"));
+        assert_eq!(None, entry_path("\\windows\\system32"));
+    }
+
+    #[test]
+    fn extracts_zip_archives() {
+        let temp = TempDir::new().expect("tempdir");
+        let archive = temp.path().join("site.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).expect("create"));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("site/", options).expect("dir");
+        zip.start_file("site/index.html", options).expect("file");
+        zip.write_all(b"<h1>hi</h1>").expect("write");
+        zip.start_file("site/bin/run.sh", options.unix_permissions(0o755))
+            .expect("file");
+        zip.write_all(b"#!/bin/sh\n").expect("write");
+        zip.add_symlink("site/link", "/etc/passwd", options)
+            .expect("symlink");
+        zip.finish().expect("finish");
+
+        let out = temp.path().join("out");
+        let extraction = extract(&archive, &out, &limits(), false).expect("extract");
+
+        assert_eq!(
+            vec!["site/index.html".to_string(), "site/bin/run.sh".to_string()],
+            extraction.files
+        );
+        assert_eq!(21, extraction.total_bytes);
+        assert_eq!(
+            vec![Skipped {
+                path: "site/link".to_string(),
+                reason: "links are not extracted",
+            }],
+            extraction.skipped
+        );
+        assert_eq!(
+            "<h1>hi</h1>",
+            std::fs::read_to_string(out.join("site/index.html")).expect("read")
+        );
+        assert!(!out.join("site/link").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(out.join("site/bin/run.sh"))
+                .expect("metadata")
+                .permissions()
+                .mode();
+            assert_eq!(0o755, mode & 0o777);
+        }
+
+        // A second extraction refuses to replace the files unless asked to.
+        let err = extract(&archive, &out, &limits(), false).expect_err("conflict");
+        assert!(err.contains("site/index.html"), "{err}");
+        extract(&archive, &out, &limits(), true).expect("overwrite");
+    }
+
+    #[test]
+    fn checks_the_whole_tar_archive_before_writing() {
+        let temp = TempDir::new().expect("tempdir");
+        let out = temp.path().join("out");
+
+        let archive = temp.path().join("evil.tar");
+        tar_with(
+            &archive,
+            &[(b"ok.txt", b"fine"), (b"../escape.txt", b"gotcha")],
+        );
+        let err = extract(&archive, &out, &limits(), false).expect_err("traversal");
+        assert!(err.contains("../escape.txt"), "{err}");
+        assert!(!out.join("ok.txt").exists());
+        assert!(!temp.path().join("escape.txt").exists());
+
+        let archive = temp.path().join("big.tar");
+        tar_with(&archive, &[(b"a.bin", &[0; 600]), (b"b.bin", &[0; 600])]);
+        let small = ExtractArchive {
+            max_bytes: 1000,
+            ..limits()
+        };
+        let err = extract(&archive, &out, &small, false).expect_err("too big");
+        assert!(err.contains("1000 bytes"), "{err}");
+        let few = ExtractArchive {
+            max_files: 1,
+            ..limits()
+        };
+        assert!(extract(&archive, &out, &few, false).is_err());
+
+        let extraction = extract(&archive, &out, &limits(), false).expect("extract");
+        assert_eq!(
+            vec!["a.bin".to_string(), "b.bin".to_string()],
+            extraction.files
+        );
+        assert_eq!(1200, extraction.total_bytes);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlinks_in_the_destination() {
+        let temp = TempDir::new().expect("tempdir");
+        let out = temp.path().join("out");
+        let outside = temp.path().join("outside");
+        std::fs::create_dir_all(&out).expect("mkdir");
+        std::fs::create_dir_all(&outside).expect("mkdir");
+        std::os::unix::fs::symlink(&outside, out.join("link")).expect("symlink");
+
+        let archive = temp.path().join("a.tar");
+        tar_with(&archive, &[(b"link/file.txt", b"data")]);
+        let err = extract(&archive, &out, &limits(), false).expect_err("symlink");
+        assert!(err.contains("symbolic link"), "{err}");
+        assert!(!outside.join("file.txt").exists());
+    }
+}
@@ -0,0 +1,157 @@
+//! Advisory locks on the files sessions edit, so tasks that run concurrently
+//! in the same workspace (separate conversations or parallel sub-agents)
+//! detect when they would overwrite each other's changes.
+//!
+//! A session claims the files touched by each patch it applies and keeps them
+//! until its current task ends. Sub-agents may edit files held by the session
+//! that spawned them, but not files held by their siblings.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use codex_protocol::ConversationId;
+
+/// Registry of file locks shared by every session of a `ConversationManager`.
+#[derive(Debug, Default)]
+pub struct FileLocks {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    holders: HashMap<PathBuf, ConversationId>,
+    parents: HashMap<ConversationId, ConversationId>,
+}
+
+/// A file another session is editing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileLockConflict {
+    pub(crate) path: PathBuf,
+    pub(crate) holder: ConversationId,
+}
+
+impl Inner {
+    /// Whether `owner` may write a file held by `holder`.
+    fn may_write(&self, owner: ConversationId, holder: ConversationId) -> bool {
+        let mut current = Some(owner);
+        while let Some(id) = current {
+            if id == holder {
+                return true;
+            }
+            current = self.parents.get(&id).copied();
+        }
+        false
+    }
+}
+
+impl FileLocks {
+    /// Record that `child` was spawned by `parent`.
+    pub(crate) fn register_child(&self, child: ConversationId, parent: ConversationId) {
+        self.lock().parents.insert(child, parent);
+    }
+
+    /// Claim `paths` for `owner`. Either every path is claimed or, when one
+    /// is held by an unrelated session, none is.
+    pub(crate) fn try_lock<'a>(
+        &self,
+        owner: ConversationId,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Result<(), FileLockConflict> {
+        let mut inner = self.lock();
+        let paths: Vec<&Path> = paths.into_iter().collect();
+        for path in &paths {
+            if let Some(&holder) = inner.holders.get(*path)
+                && !inner.may_write(owner, holder)
+            {
+                return Err(FileLockConflict {
+                    path: path.to_path_buf(),
+                    holder,
+                });
+            }
+        }
+        for path in paths {
+            inner.holders.entry(path.to_path_buf()).or_insert(owner);
+        }
+        Ok(())
+    }
+
+    /// Release every file held by `owner`.
+    pub(crate) fn release(&self, owner: ConversationId) {
+        self.lock().holders.retain(|_, holder| *holder != owner);
+    }
+
+    /// Release `owner`'s files and forget about it entirely.
+    pub(crate) fn forget(&self, owner: ConversationId) {
+        let mut inner = self.lock();
+        inner.holders.retain(|_, holder| *holder != owner);
+        inner.parents.remove(&owner);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn conflicting_sessions_cannot_claim_the_same_file() {
+        let locks = FileLocks::default();
+        let a = ConversationId::new();
+        let b = ConversationId::new();
+        let shared = Path::new("/repo/src/lib.rs");
+        let other = Path::new("/repo/README.md");
+
+        locks.try_lock(a, [shared]).expect("a claims lib.rs");
+        assert_eq!(
+            Err(FileLockConflict {
+                path: shared.to_path_buf(),
+                holder: a,
+            }),
+            locks.try_lock(b, [other, shared])
+        );
+        // The failed claim is all-or-nothing.
+        locks.try_lock(a, [other]).expect("README.md is still free");
+
+        locks.release(a);
+        locks.try_lock(b, [shared]).expect("released by a");
+    }
+
+    #[test]
+    fn sub_agents_may_edit_files_held_by_their_parent() {
+        let locks = FileLocks::default();
+        let parent = ConversationId::new();
+        let first = ConversationId::new();
+        let second = ConversationId::new();
+        locks.register_child(first, parent);
+        locks.register_child(second, parent);
+        let parent_file = Path::new("/repo/a.rs");
+        let child_file = Path::new("/repo/b.rs");
+
+        locks
+            .try_lock(parent, [parent_file])
+            .expect("parent claims");
+        locks
+            .try_lock(first, [parent_file, child_file])
+            .expect("child may write parent's file");
+        assert_eq!(
+            Err(FileLockConflict {
+                path: child_file.to_path_buf(),
+                holder: first,
+            }),
+            locks.try_lock(second, [child_file])
+        );
+
+        locks.forget(first);
+        locks
+            .try_lock(second, [child_file])
+            .expect("sibling finished");
+    }
+}
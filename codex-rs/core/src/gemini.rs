@@ -0,0 +1,936 @@
+//! Implementation for the Gemini API (`wire_api = "gemini"`).
+//!
+//! The conversation is sent as `user` and `model` contents of parts: function
+//! calls become `functionCall` parts and their outputs `functionResponse`
+//! parts, which Gemini matches by function name rather than call ID. Tool
+//! parameter schemas are reduced to the OpenAPI subset Gemini accepts.
+//!
+//! Streamed chunks are accumulated and turned into response items when the
+//! stream ends. Thought signatures are kept as the encrypted content of a
+//! reasoning item and sent back on the first part of the next model turn, as
+//! Gemini requires for function calling with thinking.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::ModelProviderInfo;
+use crate::anthropic_messages::FREEFORM_INPUT_PARAM;
+use crate::anthropic_messages::freeform_tool_names;
+use crate::anthropic_messages::thinking_budget;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::RetryLimitReachedError;
+use crate::error::UnexpectedResponseError;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::OpenAiTool;
+use crate::protocol::TokenUsage;
+use crate::util::backoff;
+use bytes::Bytes;
+use codex_otel::otel_event_manager::OtelEventManager;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use serde_json::Map;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+use uuid::Uuid;
+
+/// Schema keywords Gemini function declarations accept; others are dropped.
+const SUPPORTED_SCHEMA_KEYS: &[&str] = &[
+    "type",
+    "description",
+    "properties",
+    "required",
+    "items",
+    "enum",
+    "nullable",
+    "anyOf",
+    "minItems",
+    "maxItems",
+    "minimum",
+    "maximum",
+];
+
+/// Stream a turn from the Gemini API.
+pub(crate) async fn stream_gemini(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    otel_event_manager: &OtelEventManager,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Gemini API".to_string(),
+        ));
+    }
+
+    let payload = build_payload(prompt, model_family, effort, max_output_tokens);
+    let freeform_tools = freeform_tool_names(&prompt.tools);
+    let url = provider.gemini_stream_url(&model_family.slug);
+
+    debug!(
+        "POST to {url}: {}",
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+
+        let mut req_builder = client.post(&url);
+        if let Some(api_key) = provider.api_key()? {
+            req_builder = req_builder.header("x-goog-api-key", api_key);
+        }
+        let req_builder = provider.apply_http_headers(req_builder);
+
+        let res = otel_event_manager
+            .log_request(attempt, || {
+                req_builder
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .json(&payload)
+                    .send()
+            })
+            .await;
+
+        match res {
+            Ok(resp) if resp.status().is_success() => {
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+                tokio::spawn(process_gemini_sse(
+                    stream,
+                    tx_event,
+                    provider.stream_idle_timeout(),
+                    freeform_tools,
+                    otel_event_manager.clone(),
+                ));
+                return Ok(ResponseStream { rx_event });
+            }
+            Ok(res) => {
+                let status = res.status();
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    let body = (res.text().await).unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(UnexpectedResponseError {
+                        status,
+                        body,
+                        request_id: None,
+                    }));
+                }
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(RetryLimitReachedError {
+                        status,
+                        request_id: None,
+                    }));
+                }
+
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+            Err(e) => {
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                tokio::time::sleep(backoff(attempt)).await;
+            }
+        }
+    }
+}
+
+/// The request body for `prompt`. Thought summaries are requested, with a
+/// thinking budget, when a reasoning effort is set.
+fn build_payload(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    effort: Option<ReasoningEffortConfig>,
+    max_output_tokens: Option<u64>,
+) -> Value {
+    let mut payload = json!({
+        "systemInstruction": {"parts": [{"text": prompt.get_full_instructions(model_family)}]},
+        "contents": build_contents(&prompt.get_formatted_input()),
+    });
+    let Some(obj) = payload.as_object_mut() else {
+        return payload;
+    };
+
+    let mut generation_config = Map::new();
+    if let Some(max_output_tokens) = max_output_tokens {
+        generation_config.insert("maxOutputTokens".to_string(), json!(max_output_tokens));
+    }
+    if let Some(budget) = thinking_budget(effort) {
+        generation_config.insert(
+            "thinkingConfig".to_string(),
+            json!({"thinkingBudget": budget, "includeThoughts": true}),
+        );
+    }
+    if !generation_config.is_empty() {
+        obj.insert(
+            "generationConfig".to_string(),
+            Value::Object(generation_config),
+        );
+    }
+
+    let declarations = create_tools_json_for_gemini_api(&prompt.tools);
+    if !declarations.is_empty() {
+        obj.insert(
+            "tools".to_string(),
+            json!([{"functionDeclarations": declarations}]),
+        );
+    }
+    payload
+}
+
+/// Map the conversation onto Gemini contents, merging consecutive parts of
+/// the same role into one content.
+fn build_contents(input: &[ResponseItem]) -> Vec<Value> {
+    // Function responses name the function rather than the call.
+    let call_names: HashMap<&str, &str> = input
+        .iter()
+        .filter_map(|item| match item {
+            ResponseItem::FunctionCall { call_id, name, .. }
+            | ResponseItem::CustomToolCall { call_id, name, .. } => {
+                Some((call_id.as_str(), name.as_str()))
+            }
+            ResponseItem::LocalShellCall {
+                call_id: Some(call_id),
+                ..
+            } => Some((call_id.as_str(), "local_shell")),
+            _ => None,
+        })
+        .collect();
+
+    let mut contents = Contents::default();
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" { "model" } else { "user" };
+                for c in content {
+                    match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if !text.is_empty() {
+                                contents.push(role, json!({"text": text}));
+                            }
+                        }
+                        ContentItem::InputImage { image_url } => {
+                            contents.push(role, image_part(image_url));
+                        }
+                    }
+                }
+            }
+            ResponseItem::Reasoning {
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                // Sent with the part that follows it, see `Contents::push`.
+                contents.thought_signature = Some(signature);
+            }
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => {
+                let args = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                contents.push(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": args}}),
+                );
+            }
+            ResponseItem::CustomToolCall { name, input, .. } => {
+                contents.push(
+                    "model",
+                    json!({"functionCall": {"name": name, "args": { FREEFORM_INPUT_PARAM: input }}}),
+                );
+            }
+            ResponseItem::LocalShellCall { action, .. } => {
+                contents.push(
+                    "model",
+                    json!({"functionCall": {"name": "local_shell", "args": action}}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                let key = if output.success == Some(false) {
+                    "error"
+                } else {
+                    "output"
+                };
+                let name = call_names
+                    .get(call_id.as_str())
+                    .copied()
+                    .unwrap_or_default();
+                contents.push(
+                    "user",
+                    json!({"functionResponse": {"name": name, "response": {key: output.content}}}),
+                );
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                let name = call_names
+                    .get(call_id.as_str())
+                    .copied()
+                    .unwrap_or_default();
+                contents.push(
+                    "user",
+                    json!({"functionResponse": {"name": name, "response": {"output": output}}}),
+                );
+            }
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+    contents.contents
+}
+
+/// Contents being built, with the thought signature to attach to the next
+/// model part.
+#[derive(Default)]
+struct Contents<'a> {
+    contents: Vec<Value>,
+    thought_signature: Option<&'a str>,
+}
+
+impl Contents<'_> {
+    fn push(&mut self, role: &str, mut part: Value) {
+        if role == "model"
+            && let Some(signature) = self.thought_signature.take()
+            && let Some(obj) = part.as_object_mut()
+        {
+            obj.insert("thoughtSignature".to_string(), json!(signature));
+        }
+        if let Some(last) = self.contents.last_mut()
+            && last.get("role").and_then(Value::as_str) == Some(role)
+            && let Some(Value::Array(parts)) = last.get_mut("parts")
+        {
+            parts.push(part);
+            return;
+        }
+        self.contents.push(json!({"role": role, "parts": [part]}));
+    }
+}
+
+/// An image part for `image_url`: inline data for a base64 data URL, file data
+/// otherwise.
+fn image_part(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((mime_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({"inlineData": {"mimeType": mime_type, "data": data}});
+    }
+    json!({"fileData": {"fileUri": image_url}})
+}
+
+/// Function declarations of `tools`. Freeform tools take their input as a
+/// single string parameter; the built-in local shell and web search tools
+/// have no equivalent and are left out.
+pub(crate) fn create_tools_json_for_gemini_api(tools: &[OpenAiTool]) -> Vec<Value> {
+    tools
+        .iter()
+        .filter_map(|tool| {
+            let (name, description, parameters) = match tool {
+                OpenAiTool::Function(tool) => (
+                    &tool.name,
+                    &tool.description,
+                    serde_json::to_value(&tool.parameters).ok()?,
+                ),
+                OpenAiTool::Freeform(tool) => (
+                    &tool.name,
+                    &tool.description,
+                    json!({
+                        "type": "object",
+                        "properties": {
+                            FREEFORM_INPUT_PARAM: {
+                                "type": "string",
+                                "description": format!(
+                                    "Input in the following {} grammar:\n{}",
+                                    tool.format.syntax, tool.format.definition
+                                ),
+                            },
+                        },
+                        "required": [FREEFORM_INPUT_PARAM],
+                    }),
+                ),
+                OpenAiTool::LocalShell {} | OpenAiTool::WebSearch {} => return None,
+            };
+            let mut declaration = json!({"name": name, "description": description});
+            // Gemini rejects object schemas without properties, so functions
+            // without parameters are declared without a schema.
+            if let Some(parameters) = gemini_schema(&parameters)
+                && parameters.get("properties").is_some()
+                && let Some(obj) = declaration.as_object_mut()
+            {
+                obj.insert("parameters".to_string(), parameters);
+            }
+            Some(declaration)
+        })
+        .collect()
+}
+
+/// `schema` reduced to the OpenAPI subset of Gemini function declarations:
+/// unsupported keywords are dropped, `const` becomes a one-value `enum`, a
+/// list of types becomes one type that may be `nullable`, empty `properties`
+/// are left out and `required` only names declared properties.
+fn gemini_schema(schema: &Value) -> Option<Value> {
+    let Value::Object(schema) = schema else {
+        return None;
+    };
+    let mut out = Map::new();
+    for (key, value) in schema {
+        match key.as_str() {
+            "properties" => {
+                let properties: Map<String, Value> = value
+                    .as_object()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(name, schema)| Some((name.clone(), gemini_schema(schema)?)))
+                    .collect();
+                if !properties.is_empty() {
+                    out.insert(key.clone(), Value::Object(properties));
+                }
+            }
+            "items" => {
+                if let Some(items) = gemini_schema(value) {
+                    out.insert(key.clone(), items);
+                }
+            }
+            "anyOf" => {
+                let variants: Vec<Value> = value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(gemini_schema)
+                    .collect();
+                if !variants.is_empty() {
+                    out.insert(key.clone(), Value::Array(variants));
+                }
+            }
+            "type" => match value {
+                Value::Array(types) => {
+                    let mut types = types.iter().filter_map(Value::as_str);
+                    let nullable = types.clone().any(|t| t == "null");
+                    if let Some(first) = types.find(|t| *t != "null") {
+                        out.insert(key.clone(), json!(first));
+                    }
+                    if nullable {
+                        out.insert("nullable".to_string(), Value::Bool(true));
+                    }
+                }
+                value => {
+                    out.insert(key.clone(), value.clone());
+                }
+            },
+            "const" => {
+                out.insert("enum".to_string(), json!([value]));
+            }
+            key if SUPPORTED_SCHEMA_KEYS.contains(&key) => {
+                out.insert(key.to_string(), value.clone());
+            }
+            _ => {}
+        }
+    }
+    let declared: HashSet<String> = out
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| properties.keys().cloned().collect())
+        .unwrap_or_default();
+    if let Some(Value::Array(required)) = out.get_mut("required") {
+        required.retain(|name| name.as_str().is_some_and(|name| declared.contains(name)));
+        if required.is_empty() {
+            out.remove("required");
+        }
+    }
+    Some(Value::Object(out))
+}
+
+/// Why Gemini stopped generating, from the `finishReason` of a candidate or
+/// the `blockReason` of the prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FinishReason {
+    /// The response is complete, or was cut at the output token limit.
+    Stop,
+    /// The model produced an unparsable or unexpected function call; worth
+    /// retrying.
+    InvalidFunctionCall,
+    /// The prompt or response was blocked; retrying will not help.
+    Blocked,
+}
+
+impl FinishReason {
+    fn parse(reason: &str) -> Self {
+        match reason {
+            "STOP" | "MAX_TOKENS" | "FINISH_REASON_UNSPECIFIED" => FinishReason::Stop,
+            "MALFORMED_FUNCTION_CALL" | "UNEXPECTED_TOOL_CALL" | "TOO_MANY_TOOL_CALLS" => {
+                FinishReason::InvalidFunctionCall
+            }
+            _ => FinishReason::Blocked,
+        }
+    }
+}
+
+/// A message explaining why Gemini blocked a response.
+fn blocked_message(reason: &str) -> String {
+    let why = match reason {
+        "SAFETY" | "IMAGE_SAFETY" => "it was flagged by safety filters",
+        "RECITATION" => "it recited training data",
+        "LANGUAGE" => "it was in an unsupported language",
+        "BLOCKLIST" => "it contained blocklisted terms",
+        "PROHIBITED_CONTENT" => "it contained prohibited content",
+        "SPII" => "it contained sensitive personal information",
+        _ => "of an unspecified reason",
+    };
+    format!("Gemini stopped the response ({reason}) because {why}.")
+}
+
+/// The parts of the response seen so far.
+#[derive(Default)]
+struct GeminiTurn {
+    text: String,
+    thoughts: String,
+    thought_signature: Option<String>,
+    calls: Vec<ResponseItem>,
+    usage: Option<TokenUsage>,
+    response_id: String,
+}
+
+/// SSE processor for the `streamGenerateContent` stream.
+async fn process_gemini_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+    freeform_tools: HashSet<String>,
+    otel_event_manager: OtelEventManager,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+    let mut turn = GeminiTurn::default();
+    let mut finish_reason: Option<String> = None;
+    let mut created = false;
+
+    loop {
+        let sse = match otel_event_manager
+            .log_sse_event(|| timeout(idle_timeout, stream.next()))
+            .await
+        {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let chunk: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("gemini received SSE chunk: {chunk:?}");
+
+        if let Some(error) = chunk.get("error") {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(message.to_string(), None)))
+                .await;
+            return;
+        }
+        if !created {
+            created = true;
+            let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+        }
+        if let Some(id) = chunk.get("responseId").and_then(Value::as_str) {
+            turn.response_id = id.to_string();
+        }
+        if let Some(usage) = chunk.get("usageMetadata") {
+            turn.usage = Some(token_usage(usage));
+        }
+        if let Some(reason) = chunk
+            .get("promptFeedback")
+            .and_then(|f| f.get("blockReason"))
+            .and_then(Value::as_str)
+        {
+            finish_reason = Some(reason.to_string());
+        }
+
+        let candidate = chunk
+            .get("candidates")
+            .and_then(Value::as_array)
+            .and_then(|candidates| candidates.first());
+        let Some(candidate) = candidate else {
+            continue;
+        };
+        let parts = candidate
+            .get("content")
+            .and_then(|c| c.get("parts"))
+            .and_then(Value::as_array);
+        for part in parts.into_iter().flatten() {
+            if let Some(signature) = part.get("thoughtSignature").and_then(Value::as_str) {
+                turn.thought_signature = Some(signature.to_string());
+            }
+            if let Some(call) = part.get("functionCall") {
+                turn.calls.push(function_call_item(call, &freeform_tools));
+            } else if let Some(text) = part.get("text").and_then(Value::as_str) {
+                if part.get("thought").and_then(Value::as_bool) == Some(true) {
+                    turn.thoughts.push_str(text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::ReasoningSummaryDelta(text.to_string())))
+                        .await;
+                } else if !text.is_empty() {
+                    turn.text.push_str(text);
+                    let _ = tx_event
+                        .send(Ok(ResponseEvent::OutputTextDelta(text.to_string())))
+                        .await;
+                }
+            }
+        }
+        if let Some(reason) = candidate.get("finishReason").and_then(Value::as_str) {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+
+    let Some(reason) = finish_reason else {
+        let _ = tx_event
+            .send(Err(CodexErr::Stream(
+                "stream closed before a finish reason".into(),
+                None,
+            )))
+            .await;
+        return;
+    };
+    match FinishReason::parse(&reason) {
+        FinishReason::Stop => {}
+        FinishReason::InvalidFunctionCall => {
+            let _ = tx_event
+                .send(Err(CodexErr::Stream(
+                    format!("Gemini returned an invalid function call ({reason})"),
+                    None,
+                )))
+                .await;
+            return;
+        }
+        FinishReason::Blocked => {
+            // End the turn with an explanation instead of retrying it.
+            turn.calls.clear();
+            if !turn.text.is_empty() {
+                turn.text.push_str("\n\n");
+            }
+            turn.text.push_str(&blocked_message(&reason));
+        }
+    }
+
+    for item in turn_items(&mut turn) {
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id: turn.response_id,
+            token_usage: turn.usage,
+        }))
+        .await;
+}
+
+/// The response items of a finished turn: its thoughts, its text and its
+/// function calls, in that order.
+fn turn_items(turn: &mut GeminiTurn) -> Vec<ResponseItem> {
+    let mut items = Vec::new();
+    if !turn.thoughts.is_empty() || turn.thought_signature.is_some() {
+        let summary = if turn.thoughts.is_empty() {
+            Vec::new()
+        } else {
+            vec![ReasoningItemReasoningSummary::SummaryText {
+                text: std::mem::take(&mut turn.thoughts),
+            }]
+        };
+        items.push(ResponseItem::Reasoning {
+            id: String::new(),
+            summary,
+            content: None,
+            encrypted_content: turn.thought_signature.take(),
+        });
+    }
+    if !turn.text.is_empty() {
+        items.push(ResponseItem::Message {
+            id: None,
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText {
+                text: std::mem::take(&mut turn.text),
+            }],
+        });
+    }
+    items.append(&mut turn.calls);
+    items
+}
+
+/// The response item of a `functionCall` part. Gemini does not always give
+/// calls an ID, so one is made up when it is missing.
+fn function_call_item(call: &Value, freeform_tools: &HashSet<String>) -> ResponseItem {
+    let name = call
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let call_id = call
+        .get("id")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("call_{}", Uuid::new_v4().simple()));
+    let args = call.get("args").cloned().unwrap_or_else(|| json!({}));
+    if freeform_tools.contains(&name) {
+        let input = args
+            .get(FREEFORM_INPUT_PARAM)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        return ResponseItem::CustomToolCall {
+            id: None,
+            status: None,
+            call_id,
+            name,
+            input,
+        };
+    }
+    ResponseItem::FunctionCall {
+        id: None,
+        name,
+        arguments: args.to_string(),
+        call_id,
+    }
+}
+
+fn token_usage(usage: &Value) -> TokenUsage {
+    let count = |key: &str| usage.get(key).and_then(Value::as_u64).unwrap_or_default();
+    let reasoning_output_tokens = count("thoughtsTokenCount");
+    TokenUsage {
+        input_tokens: count("promptTokenCount"),
+        cached_input_tokens: count("cachedContentTokenCount"),
+        output_tokens: count("candidatesTokenCount") + reasoning_output_tokens,
+        reasoning_output_tokens,
+        total_tokens: count("totalTokenCount"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::ConversationId;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    async fn run_sse(chunks: Vec<Value>) -> Vec<Result<ResponseEvent>> {
+        let body: String = chunks
+            .into_iter()
+            .map(|chunk| format!("data: {chunk}\n\n"))
+            .collect();
+        let (tx, mut rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        let stream =
+            tokio_util::io::ReaderStream::new(std::io::Cursor::new(body)).map_err(CodexErr::Io);
+        tokio::spawn(process_gemini_sse(
+            stream,
+            tx,
+            Duration::from_secs(5),
+            HashSet::new(),
+            OtelEventManager::new(
+                ConversationId::new(),
+                "test",
+                "test",
+                None,
+                None,
+                false,
+                "test".to_string(),
+            ),
+        ));
+        let mut out = Vec::new();
+        while let Some(ev) = rx.recv().await {
+            out.push(ev);
+        }
+        out
+    }
+
+    fn items(events: &[Result<ResponseEvent>]) -> Vec<ResponseItem> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Ok(ResponseEvent::OutputItemDone(item)) => Some(item.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reduces_tool_schemas_to_the_openapi_subset() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "command": {"type": "array", "items": {"type": "string"}},
+                "workdir": {"type": ["string", "null"], "format": "path"},
+                "env": {"type": "object", "properties": {}, "additionalProperties": true},
+                "mode": {"const": "fast"},
+            },
+            "required": ["command", "missing"],
+            "additionalProperties": false,
+        });
+
+        assert_eq!(
+            gemini_schema(&schema),
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "command": {"type": "array", "items": {"type": "string"}},
+                    "workdir": {"type": "string", "nullable": true},
+                    "env": {"type": "object"},
+                    "mode": {"enum": ["fast"]},
+                },
+                "required": ["command"],
+            }))
+        );
+    }
+
+    #[test]
+    fn answers_function_calls_by_name_with_thought_signatures() {
+        let input = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "list files".to_string(),
+                }],
+            },
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some("sig".to_string()),
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"]}"#.to_string(),
+                call_id: "call_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    success: Some(true),
+                },
+            },
+        ];
+
+        assert_eq!(
+            build_contents(&input),
+            vec![
+                json!({"role": "user", "parts": [{"text": "list files"}]}),
+                json!({"role": "model", "parts": [{
+                    "functionCall": {"name": "shell", "args": {"command": ["ls"]}},
+                    "thoughtSignature": "sig",
+                }]}),
+                json!({"role": "user", "parts": [{
+                    "functionResponse": {"name": "shell", "response": {"output": "README.md"}},
+                }]}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn maps_streamed_parts_onto_response_items() {
+        let events = run_sse(vec![
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Checking the files.", "thought": true},
+            ]}}]}),
+            json!({"candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Listing."},
+                {"functionCall": {"name": "shell", "args": {"command": ["ls"]}}, "thoughtSignature": "sig"},
+            ]}, "finishReason": "STOP"}],
+             "usageMetadata": {"promptTokenCount": 10, "candidatesTokenCount": 5, "thoughtsTokenCount": 3, "totalTokenCount": 18},
+             "responseId": "resp_1"}),
+        ])
+        .await;
+
+        let items = items(&events);
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0],
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: vec![ReasoningItemReasoningSummary::SummaryText {
+                    text: "Checking the files.".to_string(),
+                }],
+                content: None,
+                encrypted_content: Some("sig".to_string()),
+            }
+        );
+        let ResponseItem::FunctionCall {
+            name, arguments, ..
+        } = &items[2]
+        else {
+            panic!("expected a function call, got {:?}", items[2]);
+        };
+        assert_eq!(name, "shell");
+        assert_eq!(arguments, r#"{"command":["ls"]}"#);
+        let Some(Ok(ResponseEvent::Completed {
+            response_id,
+            token_usage: Some(token_usage),
+        })) = events.last()
+        else {
+            panic!("expected a completed event, got {:?}", events.last());
+        };
+        assert_eq!(response_id, "resp_1");
+        assert_eq!(token_usage.output_tokens, 8);
+    }
+
+    #[tokio::test]
+    async fn explains_blocked_responses_instead_of_retrying() {
+        let events = run_sse(vec![json!({"candidates": [{
+            "content": {"role": "model", "parts": [
+                {"functionCall": {"name": "shell", "args": {}}},
+            ]},
+            "finishReason": "SAFETY",
+        }]})])
+        .await;
+
+        assert_eq!(
+            items(&events),
+            vec![ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Gemini stopped the response (SAFETY) because it was flagged by \
+                           safety filters."
+                        .to_string(),
+                }],
+            }]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(Ok(ResponseEvent::Completed { .. }))
+        ));
+    }
+}
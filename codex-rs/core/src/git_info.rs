@@ -3,7 +3,9 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use codex_app_server_protocol::GitSha;
+use codex_protocol::protocol::GitDirtyFile;
 use codex_protocol::protocol::GitInfo;
+use codex_protocol::protocol::TurnGitStateEvent;
 use futures::future::join_all;
 use serde::Deserialize;
 use serde::Serialize;
@@ -108,6 +110,76 @@ pub async fn collect_git_info(cwd: &Path) -> Option<GitInfo> {
     Some(git_info)
 }
 
+/// Collect the HEAD, uncommitted changes and stashes of the repository
+/// containing `cwd`, recorded at the start of turn `turn_id`.
+/// Returns None outside a git repository or if `git status` fails.
+pub async fn collect_turn_git_state(turn_id: &str, cwd: &Path) -> Option<TurnGitStateEvent> {
+    let (info, status_result, stash_result) = tokio::join!(
+        collect_git_info(cwd),
+        run_git_command_with_timeout(&["status", "--porcelain=v1", "-z"], cwd),
+        run_git_command_with_timeout(&["stash", "list", "--format=%gd: %s"], cwd)
+    );
+    let info = info?;
+    let status = status_result.filter(|output| output.status.success())?;
+
+    let stashes = match stash_result {
+        Some(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some(TurnGitStateEvent {
+        turn_id: turn_id.to_string(),
+        git_head: info.commit_hash,
+        git_branch: info.branch,
+        dirty_files: parse_porcelain_status(&String::from_utf8_lossy(&status.stdout)),
+        stashes,
+    })
+}
+
+/// Absolute paths of the files added, modified or untracked in the repository
+/// containing `cwd`. Deleted files are left out. Returns None outside a git
+/// repository or if `git status` fails.
+pub(crate) async fn changed_files(cwd: &Path) -> Option<Vec<PathBuf>> {
+    let root = get_git_repo_root(cwd)?;
+    let output = run_git_command_with_timeout(
+        &["status", "--porcelain=v1", "-z", "--untracked-files=all"],
+        cwd,
+    )
+    .await
+    .filter(|output| output.status.success())?;
+    Some(
+        parse_porcelain_status(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .filter(|file| !file.status.contains('D'))
+            .map(|file| root.join(file.path))
+            .collect(),
+    )
+}
+
+/// Parse the output of `git status --porcelain=v1 -z`. Renames and copies are
+/// reported under their new path.
+pub(crate) fn parse_porcelain_status(output: &str) -> Vec<GitDirtyFile> {
+    let mut files = Vec::new();
+    let mut entries = output.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(entry) = entries.next() {
+        let (Some(status), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+        if status.contains(['R', 'C']) {
+            // The next entry is the original path.
+            entries.next();
+        }
+        files.push(GitDirtyFile {
+            status: status.to_string(),
+            path: path.to_string(),
+        });
+    }
+    files
+}
+
 /// A minimal commit summary entry used for pickers (subject + timestamp + sha).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommitLogEntry {
@@ -727,6 +799,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_porcelain_status_reports_new_path_of_renames() {
+        let output = " M src/lib.rs\0R  new.rs\0old.rs\0?? notes.txt\0";
+        assert_eq!(
+            parse_porcelain_status(output),
+            vec![
+                GitDirtyFile {
+                    status: " M".to_string(),
+                    path: "src/lib.rs".to_string(),
+                },
+                GitDirtyFile {
+                    status: "R ".to_string(),
+                    path: "new.rs".to_string(),
+                },
+                GitDirtyFile {
+                    status: "??".to_string(),
+                    path: "notes.txt".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_turn_git_state() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        assert_eq!(collect_turn_git_state("1", temp_dir.path()).await, None);
+
+        let repo_path = create_test_git_repo(&temp_dir).await;
+        fs::write(repo_path.join("test.txt"), "stashed").unwrap();
+        Command::new("git")
+            .args(["stash", "push", "-m", "wip"])
+            .current_dir(&repo_path)
+            .output()
+            .await
+            .expect("git stash");
+        fs::write(repo_path.join("test.txt"), "modified").unwrap();
+        fs::write(repo_path.join("untracked.txt"), "new").unwrap();
+
+        let state = collect_turn_git_state("1", &repo_path)
+            .await
+            .expect("Should collect git state from repo");
+        let git_info = collect_git_info(&repo_path).await.unwrap();
+        assert_eq!(state.turn_id, "1");
+        assert_eq!(state.git_head, git_info.commit_hash);
+        assert_eq!(state.git_branch, git_info.branch);
+        assert_eq!(
+            state
+                .dirty_files
+                .iter()
+                .map(|file| (file.status.as_str(), file.path.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(" M", "test.txt"), ("??", "untracked.txt")]
+        );
+        assert_eq!(state.stashes.len(), 1);
+        assert!(state.stashes[0].starts_with("stash@{0}: "));
+        assert!(state.stashes[0].ends_with("wip"));
+    }
+
     async fn create_test_git_repo_with_remote(temp_dir: &TempDir) -> (PathBuf, String) {
         let repo_path = create_test_git_repo(temp_dir).await;
         let remote_path = temp_dir.path().join("remote.git");
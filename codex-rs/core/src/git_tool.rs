@@ -0,0 +1,754 @@
+//! The `git` tool, which runs common git operations in the session's
+//! repository and returns their results as JSON instead of porcelain text.
+//!
+//! Reading operations always run. Operations that write the repository need
+//! approval when the sandbox does not let the agent write its `.git`
+//! directory, and `push` and `reset --hard` always need approval.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use codex_protocol::protocol::GitDirtyFile;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::function_tool::FunctionCallError;
+use crate::git_info::get_git_repo_root;
+use crate::git_info::parse_porcelain_status;
+use crate::protocol::SandboxPolicy;
+use crate::write_file::is_path_writable;
+
+pub(crate) const GIT_TOOL_NAME: &str = "git";
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_LOG_COUNT: usize = 20;
+const MAX_LOG_COUNT: usize = 200;
+/// Files named in a generated commit subject before the rest are counted.
+const MAX_SUBJECT_FILES: usize = 3;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum GitToolArgs {
+    Status,
+    Diff {
+        #[serde(default)]
+        staged: bool,
+        #[serde(default)]
+        paths: Vec<String>,
+    },
+    Log {
+        #[serde(default)]
+        max_count: Option<usize>,
+        #[serde(default)]
+        path: Option<String>,
+    },
+    Blame {
+        path: String,
+        #[serde(default)]
+        start_line: Option<usize>,
+        #[serde(default)]
+        end_line: Option<usize>,
+    },
+    Branch,
+    StageHunks {
+        path: String,
+        /// Indices of the hunks in the unstaged `diff` of `path`.
+        hunks: Vec<usize>,
+    },
+    Commit {
+        #[serde(default)]
+        message: Option<String>,
+    },
+    Push {
+        #[serde(default)]
+        remote: Option<String>,
+        #[serde(default)]
+        branch: Option<String>,
+        #[serde(default)]
+        force: bool,
+    },
+    Reset {
+        #[serde(default)]
+        target: Option<String>,
+        #[serde(default)]
+        hard: bool,
+    },
+}
+
+pub(crate) fn parse_git_tool_arguments(arguments: &str) -> Result<GitToolArgs, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })
+}
+
+impl GitToolArgs {
+    /// Why this operation needs the user's approval, if it does.
+    pub(crate) fn approval_reason(
+        &self,
+        sandbox_policy: &SandboxPolicy,
+        cwd: &Path,
+    ) -> Option<String> {
+        match self {
+            GitToolArgs::Push { .. } => Some("git push publishes commits to a remote".to_string()),
+            GitToolArgs::Reset { hard: true, .. } => {
+                Some("git reset --hard discards uncommitted changes".to_string())
+            }
+            GitToolArgs::StageHunks { .. }
+            | GitToolArgs::Commit { .. }
+            | GitToolArgs::Reset { hard: false, .. } => {
+                let git_dir = get_git_repo_root(cwd)?.join(".git");
+                (!is_path_writable(sandbox_policy, cwd, &git_dir))
+                    .then(|| "the sandbox does not allow writing to the git repository".to_string())
+            }
+            GitToolArgs::Status
+            | GitToolArgs::Diff { .. }
+            | GitToolArgs::Log { .. }
+            | GitToolArgs::Blame { .. }
+            | GitToolArgs::Branch => None,
+        }
+    }
+
+    /// The equivalent git command line, shown when asking for approval.
+    pub(crate) fn command_for_display(&self) -> Vec<String> {
+        let mut command = vec!["git".to_string()];
+        match self {
+            GitToolArgs::Status => command.push("status".to_string()),
+            GitToolArgs::Diff { staged, paths } => {
+                command.push("diff".to_string());
+                if *staged {
+                    command.push("--cached".to_string());
+                }
+                command.extend(paths.iter().cloned());
+            }
+            GitToolArgs::Log { path, .. } => {
+                command.push("log".to_string());
+                command.extend(path.iter().cloned());
+            }
+            GitToolArgs::Blame { path, .. } => {
+                command.extend(["blame".to_string(), path.clone()]);
+            }
+            GitToolArgs::Branch => command.push("branch".to_string()),
+            GitToolArgs::StageHunks { path, .. } => {
+                command.extend(["add".to_string(), "--patch".to_string(), path.clone()]);
+            }
+            GitToolArgs::Commit { .. } => command.push("commit".to_string()),
+            GitToolArgs::Push {
+                remote,
+                branch,
+                force,
+            } => {
+                command.push("push".to_string());
+                if *force {
+                    command.push("--force-with-lease".to_string());
+                }
+                command.extend(remote.iter().chain(branch).cloned());
+            }
+            GitToolArgs::Reset { target, hard } => {
+                command.push("reset".to_string());
+                if *hard {
+                    command.push("--hard".to_string());
+                }
+                command.extend(target.iter().cloned());
+            }
+        }
+        command
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct StatusResult {
+    branch: Option<String>,
+    upstream: Option<String>,
+    ahead: u32,
+    behind: u32,
+    files: Vec<GitDirtyFile>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct DiffFile {
+    path: String,
+    /// The `diff --git` header lines, needed to stage hunks.
+    #[serde(skip)]
+    header: Vec<String>,
+    hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct DiffHunk {
+    index: usize,
+    /// The `@@ ... @@` line.
+    header: String,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct LogEntry {
+    commit: String,
+    author: String,
+    date: String,
+    subject: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct BlameLine {
+    line: usize,
+    commit: String,
+    author: String,
+    summary: String,
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct BranchEntry {
+    name: String,
+    commit: String,
+    upstream: Option<String>,
+    current: bool,
+}
+
+pub(crate) async fn handle_git_tool(
+    cwd: &Path,
+    args: GitToolArgs,
+) -> Result<String, FunctionCallError> {
+    run(cwd, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+async fn run(cwd: &Path, args: GitToolArgs) -> Result<String, String> {
+    match args {
+        GitToolArgs::Status => {
+            let output = git(cwd, &["status", "--porcelain=v1", "--branch", "-z"]).await?;
+            to_json(&parse_status(&output))
+        }
+        GitToolArgs::Diff { staged, paths } => to_json(&diff(cwd, staged, &paths).await?),
+        GitToolArgs::Log { max_count, path } => {
+            let count = max_count
+                .unwrap_or(DEFAULT_LOG_COUNT)
+                .clamp(1, MAX_LOG_COUNT)
+                .to_string();
+            let mut command = vec!["log", "-n", &count, "--format=%H%x1f%an%x1f%aI%x1f%s"];
+            if let Some(path) = &path {
+                command.extend(["--", path.as_str()]);
+            }
+            to_json(&parse_log(&git(cwd, &command).await?))
+        }
+        GitToolArgs::Blame {
+            path,
+            start_line,
+            end_line,
+        } => {
+            let mut command = vec!["blame".to_string(), "--line-porcelain".to_string()];
+            if start_line.is_some() || end_line.is_some() {
+                let start = start_line.unwrap_or(1);
+                let end = end_line.map(|end| end.to_string()).unwrap_or_default();
+                command.extend(["-L".to_string(), format!("{start},{end}")]);
+            }
+            command.extend(["--".to_string(), path]);
+            let command: Vec<&str> = command.iter().map(String::as_str).collect();
+            to_json(&parse_blame(&git(cwd, &command).await?))
+        }
+        GitToolArgs::Branch => {
+            let output = git(
+                cwd,
+                &[
+                    "for-each-ref",
+                    "refs/heads",
+                    "--format=%(refname:short)%1f%(objectname)%1f%(upstream:short)%1f%(HEAD)",
+                ],
+            )
+            .await?;
+            to_json(&parse_branches(&output))
+        }
+        GitToolArgs::StageHunks { path, hunks } => {
+            let files = diff(cwd, false, std::slice::from_ref(&path)).await?;
+            let file = files
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("{path} has no unstaged changes"))?;
+            let patch = select_hunks(&file, &hunks)?;
+            git_with_stdin(cwd, &["apply", "--cached", "-"], &patch).await?;
+            to_json(&serde_json::json!({ "path": file.path, "staged_hunks": hunks }))
+        }
+        GitToolArgs::Commit { message } => {
+            let message = match message {
+                Some(message) => message,
+                None => {
+                    let staged = git(cwd, &["diff", "--cached", "--name-status", "-z"]).await?;
+                    generate_commit_message(&staged).ok_or("nothing is staged for commit")?
+                }
+            };
+            git(cwd, &["commit", "-q", "-m", &message]).await?;
+            let commit = git(cwd, &["rev-parse", "HEAD"]).await?;
+            to_json(&serde_json::json!({ "commit": commit.trim(), "message": message }))
+        }
+        GitToolArgs::Push {
+            remote,
+            branch,
+            force,
+        } => {
+            let mut command = vec!["push", "--porcelain"];
+            if force {
+                command.push("--force-with-lease");
+            }
+            command.extend(remote.iter().chain(&branch).map(String::as_str));
+            let output = git(cwd, &command).await?;
+            to_json(&serde_json::json!({ "output": output.trim() }))
+        }
+        GitToolArgs::Reset { target, hard } => {
+            let mode = if hard { "--hard" } else { "--mixed" };
+            let mut command = vec!["reset", "-q", mode];
+            command.extend(target.as_deref());
+            git(cwd, &command).await?;
+            let head = git(cwd, &["rev-parse", "HEAD"]).await?;
+            to_json(&serde_json::json!({ "head": head.trim() }))
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("failed to encode result: {e}"))
+}
+
+async fn git(cwd: &Path, args: &[&str]) -> Result<String, String> {
+    git_with_stdin(cwd, args, "").await
+}
+
+async fn git_with_stdin(cwd: &Path, args: &[&str], stdin: &str) -> Result<String, String> {
+    let subcommand = args.first().copied().unwrap_or_default();
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if let Some(mut child_stdin) = child.stdin.take() {
+        child_stdin
+            .write_all(stdin.as_bytes())
+            .await
+            .map_err(|e| format!("failed to write to git {subcommand}: {e}"))?;
+    }
+    let output = tokio::time::timeout(GIT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("git {subcommand} timed out"))?
+        .map_err(|e| format!("failed to run git {subcommand}: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {subcommand} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn diff(cwd: &Path, staged: bool, paths: &[String]) -> Result<Vec<DiffFile>, String> {
+    let mut command = vec!["diff", "--no-color", "--no-ext-diff"];
+    if staged {
+        command.push("--cached");
+    }
+    command.push("--");
+    command.extend(paths.iter().map(String::as_str));
+    Ok(parse_diff(&git(cwd, &command).await?))
+}
+
+/// Parse `git status --porcelain=v1 --branch -z`.
+fn parse_status(output: &str) -> StatusResult {
+    let (header, entries) = match output.strip_prefix("## ") {
+        Some(rest) => rest.split_once('\0').unwrap_or((rest, "")),
+        None => ("", output),
+    };
+    let (names, tracking) = match header.split_once(" [") {
+        Some((names, tracking)) => (names, tracking.trim_end_matches(']')),
+        None => (header, ""),
+    };
+    let (branch, upstream) = match names.split_once("...") {
+        Some((branch, upstream)) => (Some(branch), Some(upstream.to_string())),
+        None => (
+            names.strip_prefix("No commits yet on ").or(Some(names)),
+            None,
+        ),
+    };
+    let branch = branch
+        .filter(|branch| !branch.is_empty() && !branch.starts_with("HEAD "))
+        .map(str::to_string);
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in tracking.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+    StatusResult {
+        branch,
+        upstream,
+        ahead,
+        behind,
+        files: parse_porcelain_status(entries),
+    }
+}
+
+fn parse_diff(output: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let path = rest
+                .rsplit_once(" b/")
+                .map(|(_, path)| path)
+                .unwrap_or(rest);
+            files.push(DiffFile {
+                path: path.to_string(),
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks.push(DiffHunk {
+                index: file.hunks.len(),
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        } else {
+            file.header.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// A patch of `file` containing only the hunks at `indices`.
+fn select_hunks(file: &DiffFile, indices: &[usize]) -> Result<String, String> {
+    if indices.is_empty() {
+        return Err("no hunks selected".to_string());
+    }
+    if let Some(index) = indices.iter().find(|index| **index >= file.hunks.len()) {
+        return Err(format!(
+            "{} has {} unstaged hunk(s); there is no hunk {index}",
+            file.path,
+            file.hunks.len()
+        ));
+    }
+    let mut patch = file.header.join("\n");
+    patch.push('\n');
+    for hunk in file
+        .hunks
+        .iter()
+        .filter(|hunk| indices.contains(&hunk.index))
+    {
+        patch.push_str(&hunk.header);
+        patch.push('\n');
+        for line in &hunk.lines {
+            patch.push_str(line);
+            patch.push('\n');
+        }
+    }
+    Ok(patch)
+}
+
+fn parse_log(output: &str) -> Vec<LogEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\u{1f}');
+            Some(LogEntry {
+                commit: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `git blame --line-porcelain`.
+fn parse_blame(output: &str) -> Vec<BlameLine> {
+    let mut lines = Vec::new();
+    let mut commit = String::new();
+    let mut line_number = 0;
+    let mut author = String::new();
+    let mut summary = String::new();
+    for line in output.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            lines.push(BlameLine {
+                line: line_number,
+                commit: commit.clone(),
+                author: author.clone(),
+                summary: summary.clone(),
+                text: text.to_string(),
+            });
+        } else if let Some(value) = line.strip_prefix("author ") {
+            author = value.to_string();
+        } else if let Some(value) = line.strip_prefix("summary ") {
+            summary = value.to_string();
+        } else {
+            let mut fields = line.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) =
+                (fields.next(), fields.next(), fields.next())
+                && sha.len() >= 40
+                && sha.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                commit = sha.to_string();
+                line_number = final_line.parse().unwrap_or(0);
+            }
+        }
+    }
+    lines
+}
+
+fn parse_branches(output: &str) -> Vec<BranchEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\u{1f}');
+            let name = fields.next()?.to_string();
+            let commit = fields.next()?.to_string();
+            let upstream = fields.next().filter(|u| !u.is_empty()).map(str::to_string);
+            let current = fields.next() == Some("*");
+            Some(BranchEntry {
+                name,
+                commit,
+                upstream,
+                current,
+            })
+        })
+        .collect()
+}
+
+/// A commit message describing the staged changes listed by
+/// `git diff --cached --name-status -z`, or `None` when nothing is staged.
+fn generate_commit_message(name_status: &str) -> Option<String> {
+    let mut changes = Vec::new();
+    let mut entries = name_status.split('\0').filter(|entry| !entry.is_empty());
+    while let Some(status) = entries.next() {
+        let mut path = entries.next()?;
+        if status.starts_with(['R', 'C']) {
+            // Renames and copies list the old path, then the new one.
+            path = entries.next()?;
+        }
+        let verb = match status.chars().next() {
+            Some('A' | 'C') => "Add",
+            Some('D') => "Delete",
+            Some('R') => "Rename",
+            _ => "Update",
+        };
+        changes.push((verb, status, path));
+    }
+    let (first_verb, _, _) = changes.first()?;
+    let verb = if changes.iter().all(|(verb, _, _)| verb == first_verb) {
+        first_verb
+    } else {
+        "Update"
+    };
+    let names: Vec<&str> = changes
+        .iter()
+        .map(|(_, _, path)| path.rsplit('/').next().unwrap_or(path))
+        .collect();
+    let subject = match names.as_slice() {
+        [only] => format!("{verb} {only}"),
+        names if names.len() <= MAX_SUBJECT_FILES => {
+            let (last, rest) = names.split_last()?;
+            format!("{verb} {} and {last}", rest.join(", "))
+        }
+        names => format!(
+            "{verb} {}, {} and {} other files",
+            names[0],
+            names[1],
+            names.len() - 2
+        ),
+    };
+    if changes.len() == 1 {
+        return Some(subject);
+    }
+    let body: Vec<String> = changes
+        .iter()
+        .map(|(_, status, path)| format!("- {} {path}", &status[..1]))
+        .collect();
+    Some(format!("{subject}\n\n{}", body.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn run_git(dir: &Path, args: &[&str]) {
+        git(dir, args).await.expect("git");
+    }
+
+    #[test]
+    fn parses_status_headers() {
+        let status =
+            parse_status("## main...origin/main [ahead 2, behind 1]\0 M src/lib.rs\0?? new.txt\0");
+        assert_eq!(
+            StatusResult {
+                branch: Some("main".to_string()),
+                upstream: Some("origin/main".to_string()),
+                ahead: 2,
+                behind: 1,
+                files: vec![
+                    GitDirtyFile {
+                        status: " M".to_string(),
+                        path: "src/lib.rs".to_string(),
+                    },
+                    GitDirtyFile {
+                        status: "??".to_string(),
+                        path: "new.txt".to_string(),
+                    },
+                ],
+            },
+            status
+        );
+        assert_eq!(
+            Some("main".to_string()),
+            parse_status("## No commits yet on main\0").branch
+        );
+        assert_eq!(None, parse_status("## HEAD (no branch)\0").branch);
+    }
+
+    #[test]
+    fn generates_commit_messages_from_staged_changes() {
+        assert_eq!(
+            Some("Add new.rs".to_string()),
+            generate_commit_message("A\0src/new.rs\0")
+        );
+        assert_eq!(
+            Some("Update a.rs and b.rs\n\n- M src/a.rs\n- A src/b.rs".to_string()),
+            generate_commit_message("M\0src/a.rs\0A\0src/b.rs\0")
+        );
+        assert_eq!(
+            Some("Delete a, b and 2 other files\n\n- D a\n- D b\n- D c\n- D d".to_string()),
+            generate_commit_message("D\0a\0D\0b\0D\0c\0D\0d\0")
+        );
+        assert_eq!(
+            Some("Rename new.rs".to_string()),
+            generate_commit_message("R100\0old.rs\0new.rs\0")
+        );
+        assert_eq!(None, generate_commit_message(""));
+    }
+
+    #[tokio::test]
+    async fn stages_selected_hunks_and_commits() {
+        let temp = TempDir::new().expect("tempdir");
+        let repo = temp.path();
+        run_git(repo, &["init", "-q", "-b", "main"]).await;
+        run_git(repo, &["config", "user.email", "codex@example.com"]).await;
+        run_git(repo, &["config", "user.name", "Codex"]).await;
+        let original: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+        std::fs::write(repo.join("file.txt"), &original).expect("write");
+        run_git(repo, &["add", "."]).await;
+        run_git(repo, &["commit", "-q", "-m", "initial"]).await;
+
+        let edited = original
+            .replace("line 2\n", "line two\n")
+            .replace("line 19\n", "line nineteen\n");
+        std::fs::write(repo.join("file.txt"), edited).expect("write");
+
+        let files = diff(repo, false, &[]).await.expect("diff");
+        assert_eq!(1, files.len());
+        assert_eq!("file.txt", files[0].path);
+        assert_eq!(2, files[0].hunks.len());
+
+        let staged = run(
+            repo,
+            parse_git_tool_arguments(
+                r#"{"action": "stage_hunks", "path": "file.txt", "hunks": [1]}"#,
+            )
+            .unwrap(),
+        )
+        .await
+        .expect("stage");
+        assert_eq!(r#"{"path":"file.txt","staged_hunks":[1]}"#, staged);
+        let cached = diff(repo, true, &[]).await.expect("diff --cached");
+        assert_eq!(
+            vec!["-line 19".to_string(), "+line nineteen".to_string()],
+            cached[0].hunks[0]
+                .lines
+                .iter()
+                .filter(|line| line.starts_with(['-', '+']))
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+
+        run(repo, GitToolArgs::Commit { message: None })
+            .await
+            .expect("commit");
+        let log = parse_log(
+            &git(repo, &["log", "--format=%H%x1f%an%x1f%aI%x1f%s"])
+                .await
+                .expect("log"),
+        );
+        assert_eq!(
+            vec!["Update file.txt", "initial"],
+            log.iter()
+                .map(|entry| entry.subject.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        let blame = run(
+            repo,
+            GitToolArgs::Blame {
+                path: "file.txt".to_string(),
+                start_line: Some(19),
+                end_line: Some(19),
+            },
+        )
+        .await
+        .expect("blame");
+        let blame: serde_json::Value = serde_json::from_str(&blame).expect("json");
+        assert_eq!("line nineteen", blame[0]["text"]);
+        assert_eq!(19, blame[0]["line"]);
+        assert_eq!(log[0].commit, blame[0]["commit"]);
+
+        let status = parse_status(
+            &git(repo, &["status", "--porcelain=v1", "--branch", "-z"])
+                .await
+                .expect("status"),
+        );
+        assert_eq!(Some("main".to_string()), status.branch);
+        assert_eq!(
+            vec![" M".to_string()],
+            status
+                .files
+                .iter()
+                .map(|f| f.status.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dangerous_operations_always_need_approval() {
+        let temp = TempDir::new().expect("tempdir");
+        let cwd = temp.path();
+        let push = GitToolArgs::Push {
+            remote: Some("origin".to_string()),
+            branch: None,
+            force: true,
+        };
+        assert!(
+            push.approval_reason(&SandboxPolicy::DangerFullAccess, cwd)
+                .is_some()
+        );
+        assert_eq!(
+            vec!["git", "push", "--force-with-lease", "origin"],
+            push.command_for_display()
+        );
+        assert!(
+            GitToolArgs::Status
+                .approval_reason(&SandboxPolicy::ReadOnly, cwd)
+                .is_none()
+        );
+    }
+}
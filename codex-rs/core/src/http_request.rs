@@ -0,0 +1,325 @@
+//! The `http_request` tool, which sends an arbitrary HTTP request to one of
+//! the hosts listed in `[http_request] allowed_hosts`, for example a dev
+//! server the agent started as a background process. Redirects are only
+//! followed to allowed hosts, and the response body is capped.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reqwest::Method;
+use reqwest::Url;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
+use reqwest::redirect::Policy;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::config_types::HttpRequest;
+use crate::default_client::get_codex_user_agent;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::truncate::truncate_to_bytes;
+
+pub(crate) const HTTP_REQUEST_TOOL_NAME: &str = "http_request";
+
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+struct HttpRequestArgs {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct HttpResponseOutput {
+    status: u16,
+    /// Repeated headers are joined with `, `.
+    headers: BTreeMap<String, String>,
+    body: String,
+    /// Whether the body was cut at `max_body_bytes`.
+    truncated: bool,
+}
+
+pub(crate) async fn handle_http_request_tool(
+    config: &HttpRequest,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: HttpRequestArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let output = send(config, sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)?;
+    serde_json::to_string(&output)
+        .map_err(|e| FunctionCallError::RespondToModel(format!("failed to encode response: {e}")))
+}
+
+async fn send(
+    config: &HttpRequest,
+    sandbox_policy: &SandboxPolicy,
+    args: HttpRequestArgs,
+) -> Result<HttpResponseOutput, String> {
+    if !sandbox_policy.has_full_network_access() {
+        return Err("network access is disabled by the sandbox policy".to_string());
+    }
+    let url = Url::parse(&args.url).map_err(|e| format!("invalid URL `{}`: {e}", args.url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "cannot request `{url}`: only http and https URLs are supported"
+        ));
+    }
+    if !is_host_allowed(&config.allowed_hosts, &url) {
+        return Err(format!(
+            "{} is not in http_request.allowed_hosts",
+            host_and_port(&url)
+        ));
+    }
+    let method = args.method.as_deref().unwrap_or("GET").to_ascii_uppercase();
+    let method = Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("invalid HTTP method `{method}`"))?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in &args.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("invalid header name `{name}`"))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| format!("invalid value for header `{name}`"))?;
+        headers.append(name, value);
+    }
+
+    let allowed_hosts = config.allowed_hosts.clone();
+    let client = reqwest::Client::builder()
+        .user_agent(get_codex_user_agent())
+        .redirect(Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if is_host_allowed(&allowed_hosts, attempt.url()) {
+                attempt.follow()
+            } else {
+                // Return the redirect itself so the model sees where it leads.
+                attempt.stop()
+            }
+        }))
+        .build()
+        .map_err(|e| format!("failed to create HTTP client: {e}"))?;
+    let mut request = client
+        .request(method, url.clone())
+        .headers(headers)
+        .timeout(Duration::from_secs(config.timeout_sec));
+    if let Some(body) = args.body {
+        request = request.body(body);
+    }
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("request to {url} failed: {e}"))?;
+
+    let mut response_headers = BTreeMap::<String, String>::new();
+    for (name, value) in response.headers() {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        response_headers
+            .entry(name.to_string())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    let status = response.status().as_u16();
+
+    let mut body = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to read the response from {url}: {e}"))?
+    {
+        let remaining = config.max_body_bytes - body.len();
+        if chunk.len() > remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    // A body cut in the middle of a character ends in a replacement
+    // character, which may push it past the limit.
+    let body = String::from_utf8_lossy(&body);
+    let (body, cut) = truncate_to_bytes(&body, config.max_body_bytes);
+    Ok(HttpResponseOutput {
+        status,
+        headers: response_headers,
+        body: body.to_string(),
+        truncated: truncated || cut,
+    })
+}
+
+/// Whether `url` matches an entry of `allowed_hosts`: `host`, `host:port` or
+/// `*.domain`, which matches subdomains of `domain`. Entries without a port
+/// match every port.
+fn is_host_allowed(allowed_hosts: &[String], url: &Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let port = url.port_or_known_default();
+    allowed_hosts.iter().any(|entry| {
+        let (pattern, entry_port) = match entry.rsplit_once(':') {
+            Some((pattern, entry_port)) if !pattern.ends_with(':') => {
+                match entry_port.parse::<u16>() {
+                    Ok(entry_port) => (pattern, Some(entry_port)),
+                    Err(_) => (entry.as_str(), None),
+                }
+            }
+            _ => (entry.as_str(), None),
+        };
+        let host_matches = match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .to_ascii_lowercase()
+                .ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+            None => host.eq_ignore_ascii_case(pattern),
+        };
+        host_matches && entry_port.is_none_or(|entry_port| port == Some(entry_port))
+    })
+}
+
+fn host_and_port(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::body_string;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    fn config(allowed_hosts: &[&str]) -> HttpRequest {
+        HttpRequest {
+            allowed_hosts: allowed_hosts.iter().map(ToString::to_string).collect(),
+            max_body_bytes: 8,
+            ..HttpRequest::default()
+        }
+    }
+
+    #[test]
+    fn matches_hosts_ports_and_wildcards() {
+        let allowed =
+            config(&["localhost:3000", "*.internal.test", "[::1]", "127.0.0.1"]).allowed_hosts;
+        let allowed_url = |url: &str| is_host_allowed(&allowed, &Url::parse(url).unwrap());
+
+        assert!(allowed_url("http://localhost:3000/health"));
+        assert!(!allowed_url("http://localhost:8080/"));
+        assert!(allowed_url("https://api.internal.test/v1"));
+        assert!(!allowed_url("https://internal.test/"));
+        assert!(!allowed_url("https://evilinternal.test/"));
+        assert!(allowed_url("http://[::1]:9000/"));
+        assert!(allowed_url("http://127.0.0.1:5173/"));
+        assert!(!allowed_url("http://example.com/"));
+    }
+
+    #[tokio::test]
+    async fn sends_requests_to_allowed_hosts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/items"))
+            .and(header("content-type", "application/json"))
+            .and(body_string(r#"{"name":"a"}"#))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .insert_header("x-request-id", "42")
+                    .set_body_string("created item"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/away"))
+            .respond_with(
+                ResponseTemplate::new(302).insert_header("location", "http://example.com/"),
+            )
+            .mount(&server)
+            .await;
+        let config = config(&["127.0.0.1"]);
+
+        let output = send(
+            &config,
+            &SandboxPolicy::DangerFullAccess,
+            HttpRequestArgs {
+                url: format!("{}/items", server.uri()),
+                method: Some("post".to_string()),
+                headers: HashMap::from([(
+                    "Content-Type".to_string(),
+                    "application/json".to_string(),
+                )]),
+                body: Some(r#"{"name":"a"}"#.to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(201, output.status);
+        assert_eq!(
+            Some("42"),
+            output.headers.get("x-request-id").map(String::as_str)
+        );
+        assert_eq!("created ", output.body);
+        assert!(output.truncated);
+
+        let output = send(
+            &config,
+            &SandboxPolicy::DangerFullAccess,
+            HttpRequestArgs {
+                url: format!("{}/away", server.uri()),
+                method: None,
+                headers: HashMap::new(),
+                body: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(302, output.status);
+
+        let err = send(
+            &config,
+            &SandboxPolicy::DangerFullAccess,
+            HttpRequestArgs {
+                url: "http://example.com/".to_string(),
+                method: None,
+                headers: HashMap::new(),
+                body: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!("example.com is not in http_request.allowed_hosts", err);
+
+        let err = send(
+            &config,
+            &SandboxPolicy::ReadOnly,
+            HttpRequestArgs {
+                url: format!("{}/items", server.uri()),
+                method: None,
+                headers: HashMap::new(),
+                body: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!("network access is disabled by the sandbox policy", err);
+    }
+}
@@ -5,42 +5,81 @@
 // the TUI or the tracing stack).
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
+mod anthropic_messages;
 mod apply_patch;
 pub mod auth;
+mod aws_sigv4;
+mod azure_auth;
 mod background_process;
 pub mod bash;
+mod batch_edit;
+mod bedrock;
+mod browser;
+mod budget;
 mod chat_completions;
+mod checkpoint;
 mod client;
 mod client_common;
+mod code_search;
+mod code_symbols;
 pub mod codex;
 mod codex_conversation;
 pub mod token_data;
 pub use codex_conversation::CodexConversation;
 mod command_safety;
 pub mod config;
+mod config_conditions;
 pub mod config_edit;
+pub mod config_encryption;
+mod config_include;
+mod config_interpolation;
+pub mod config_migration;
 pub mod config_profile;
+mod config_reload;
+pub mod config_schema;
 pub mod config_types;
 mod conversation_history;
+mod cost;
 pub mod custom_prompts;
+mod custom_tools;
+mod database_query;
+mod directory_policy;
+pub mod doctor;
 mod environment_context;
 pub mod error;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+pub mod extension_config;
+mod extract_archive;
+mod file_locks;
 mod flags;
+mod gemini;
 pub mod git_info;
+mod git_tool;
+mod http_request;
 pub mod landlock;
+mod lint;
+mod list_ports;
 mod mcp_connection_manager;
+mod mcp_elicitation;
+mod mcp_health;
+mod mcp_prompts;
+mod mcp_resources;
+mod mcp_roots;
+mod mcp_sampling;
 mod mcp_tool_call;
+mod memory;
 mod message_history;
 mod model_provider_info;
 pub mod parse_command;
 mod truncate;
+mod undo;
 mod unified_exec;
 mod user_instructions;
 pub use model_provider_info::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 pub use model_provider_info::ModelProviderInfo;
+pub use model_provider_info::ToolCalling;
 pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
 pub use model_provider_info::create_oss_provider_with_base_url;
@@ -54,33 +93,68 @@ pub use conversation_manager::NewConversation;
 pub use auth::AuthManager;
 pub use auth::CodexAuth;
 pub mod default_client;
+mod dependency_audit;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+mod pinned_context;
 pub mod plan_tool;
+mod plugins;
+pub mod project_config;
 pub mod project_doc;
+mod python_kernel;
+mod read_file;
+pub mod replay;
+mod response_cache;
 mod rollout;
 pub(crate) mod safety;
+mod sandbox_denial;
+mod scratch;
+pub use scratch::CODEX_SCRATCH_DIR_ENV_VAR;
 pub mod seatbelt;
+mod secret_scan;
+mod session_diff;
+mod session_tags;
+pub use session_tags::is_valid_tag_key;
+mod session_title;
 pub mod shell;
 pub mod spawn;
+mod sub_agent;
+mod system_info;
+mod team_config;
 pub mod terminal;
+mod test_runner;
+mod token_estimate;
 mod tool_apply_patch;
+mod tool_emulation;
+mod tool_output;
+mod tool_quota;
+mod tool_timeout;
 pub mod turn_diff_tracker;
+mod turn_stats;
+mod workspace_drift;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::INTERACTIVE_SESSION_SOURCES;
 pub use rollout::RolloutRecorder;
 pub use rollout::SESSIONS_SUBDIR;
 pub use rollout::SessionMeta;
+pub use rollout::export::ExportFormat;
+pub use rollout::export::export_session;
 pub use rollout::find_conversation_path_by_id_str;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
+pub use rollout::search::SessionSearchHit;
+pub use rollout::search::TurnExcerpt;
 mod function_tool;
 mod state;
 mod tasks;
 mod user_notification;
 pub mod util;
+mod view_image;
+mod web_fetch;
+mod web_search;
+mod write_file;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
 pub use command_safety::is_safe_command;
@@ -0,0 +1,582 @@
+//! The `lint` tool, which runs the formatters and linters configured under
+//! `[linters.<name>]` on the files changed in the working tree and returns
+//! their diagnostics grouped by file and rule. With `fix`, the fixes the
+//! formatters produce are applied as one patch through the usual approval
+//! flow before the files are checked.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex_lite::Regex;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use similar::ChangeTag;
+use similar::TextDiff;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config_types::LintOutputFormat;
+use crate::config_types::LinterConfig;
+use crate::git_info::changed_files;
+
+pub(crate) const LINT_TOOL_NAME: &str = "lint";
+
+/// Diagnostics beyond this many are counted but not listed.
+const MAX_DIAGNOSTICS: usize = 500;
+/// Lines of context around each change in a fix patch.
+const PATCH_CONTEXT_LINES: usize = 3;
+
+#[expect(clippy::expect_used)]
+static GCC_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?):(\d+):(?:(\d+):)?\s*(.*)$").expect("valid regex"));
+#[expect(clippy::expect_used)]
+static GCC_SEVERITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(error|warning|note|info|hint):\s*(.*)$").expect("valid regex"));
+#[expect(clippy::expect_used)]
+static GCC_TRAILING_RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*?)\s*\[([^\]\s]+)\]$").expect("valid regex"));
+#[expect(clippy::expect_used)]
+static GCC_LEADING_RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([A-Z]+[0-9]+)\s+(.*)$").expect("valid regex"));
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LintArgs {
+    /// Files to check, relative to the working directory. The changed files
+    /// of the working tree when empty.
+    #[serde(default)]
+    pub(crate) paths: Vec<String>,
+    /// Apply the formatters' fixes before checking.
+    #[serde(default)]
+    pub(crate) fix: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Diagnostic {
+    /// Absolute path of the file.
+    path: PathBuf,
+    line: usize,
+    column: Option<usize>,
+    rule: Option<String>,
+    severity: Option<String>,
+    message: String,
+}
+
+/// Diagnostics of one run of the linters.
+#[derive(Debug, Default)]
+pub(crate) struct LintReport {
+    diagnostics: Vec<(String, Diagnostic)>,
+    errors: Vec<String>,
+}
+
+/// The new contents the fix commands produce for the files they change.
+#[derive(Debug, Default)]
+pub(crate) struct LintFixes {
+    pub(crate) files: Vec<FixedFile>,
+    pub(crate) errors: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct FixedFile {
+    pub(crate) path: PathBuf,
+    original: String,
+    fixed: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LintOutput {
+    total: usize,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    truncated: bool,
+    files: Vec<FileDiagnostics>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixed: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileDiagnostics {
+    path: String,
+    rules: Vec<RuleDiagnostics>,
+}
+
+#[derive(Debug, Serialize)]
+struct RuleDiagnostics {
+    linter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+    diagnostics: Vec<DiagnosticOutput>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiagnosticOutput {
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<String>,
+    message: String,
+}
+
+/// The files a call checks: `paths`, or the changed files of the working
+/// tree when there are none.
+pub(crate) async fn lint_targets(cwd: &Path, paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    if !paths.is_empty() {
+        return Ok(paths.iter().map(|path| cwd.join(path)).collect());
+    }
+    let files = changed_files(cwd)
+        .await
+        .ok_or_else(|| "not in a git repository; pass the files to check as `paths`".to_string())?;
+    Ok(files.into_iter().filter(|file| file.is_file()).collect())
+}
+
+fn handles(linter: &LinterConfig, path: &Path) -> bool {
+    linter.extensions.is_empty()
+        || path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| linter.extensions.iter().any(|e| e == ext))
+}
+
+/// Runs every linter on the files it handles.
+pub(crate) async fn run_linters(
+    linters: &BTreeMap<String, LinterConfig>,
+    cwd: &Path,
+    files: &[PathBuf],
+) -> LintReport {
+    let mut report = LintReport::default();
+    for (name, linter) in linters {
+        let files: Vec<&PathBuf> = files.iter().filter(|file| handles(linter, file)).collect();
+        if files.is_empty() {
+            continue;
+        }
+        let command = lint_command(&linter.command, &files);
+        let output = match run_command(&command, cwd, None, linter.timeout_sec).await {
+            Ok(output) => output,
+            Err(e) => {
+                report.errors.push(format!("{name}: {e}"));
+                continue;
+            }
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let diagnostics = match linter.output_format {
+            LintOutputFormat::Gcc => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Some(parse_gcc(cwd, &format!("{stdout}\n{stderr}")))
+            }
+            LintOutputFormat::Eslint => parse_eslint(&stdout),
+            LintOutputFormat::Ruff => parse_ruff(cwd, &stdout),
+        };
+        match diagnostics {
+            Some(diagnostics) if !diagnostics.is_empty() || output.status.success() => {
+                report
+                    .diagnostics
+                    .extend(diagnostics.into_iter().map(|d| (name.clone(), d)));
+            }
+            _ => report.errors.push(format!(
+                "{name} exited with {} without reporting diagnostics: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+    report
+}
+
+/// `command` with `{files}` replaced by `files`, or with `files` appended.
+fn lint_command(command: &[String], files: &[&PathBuf]) -> Vec<String> {
+    let files = files.iter().map(|file| file.to_string_lossy().into_owned());
+    if command.iter().any(|arg| arg == "{files}") {
+        let files: Vec<String> = files.collect();
+        command
+            .iter()
+            .flat_map(|arg| {
+                if arg == "{files}" {
+                    files.clone()
+                } else {
+                    vec![arg.clone()]
+                }
+            })
+            .collect()
+    } else {
+        command.iter().cloned().chain(files).collect()
+    }
+}
+
+/// Runs each fix command over the files it handles, feeding the output of one
+/// formatter to the next.
+pub(crate) async fn compute_fixes(
+    linters: &BTreeMap<String, LinterConfig>,
+    cwd: &Path,
+    files: &[PathBuf],
+) -> LintFixes {
+    let mut fixes = LintFixes::default();
+    for path in files {
+        let Ok(original) = tokio::fs::read_to_string(path).await else {
+            continue;
+        };
+        let mut content = original.clone();
+        for (name, linter) in linters {
+            let Some(fix_command) = &linter.fix_command else {
+                continue;
+            };
+            if !handles(linter, path) {
+                continue;
+            }
+            let file = path.to_string_lossy();
+            let command: Vec<String> = fix_command
+                .iter()
+                .map(|arg| arg.replace("{file}", &file))
+                .collect();
+            match run_command(&command, cwd, Some(&content), linter.timeout_sec).await {
+                Ok(output) if output.status.success() => match String::from_utf8(output.stdout) {
+                    Ok(fixed) => content = fixed,
+                    Err(_) => fixes
+                        .errors
+                        .push(format!("{name}: fixed {} is not UTF-8", path.display())),
+                },
+                Ok(output) => fixes.errors.push(format!(
+                    "{name} could not fix {}: {}",
+                    path.display(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Err(e) => fixes.errors.push(format!("{name}: {e}")),
+            }
+        }
+        if content != original {
+            fixes.files.push(FixedFile {
+                path: path.clone(),
+                original,
+                fixed: content,
+            });
+        }
+    }
+    fixes
+}
+
+/// An `apply_patch` patch that makes the fixes, or `None` when there are
+/// none.
+pub(crate) fn fix_patch(cwd: &Path, files: &[FixedFile]) -> Option<String> {
+    if files.is_empty() {
+        return None;
+    }
+    let mut patch = String::from("*** Begin Patch\n");
+    for file in files {
+        let path = file.path.strip_prefix(cwd).unwrap_or(&file.path);
+        patch.push_str(&format!("*** Update File: {}\n", path.display()));
+        let diff = TextDiff::from_lines(&file.original, &file.fixed);
+        for group in diff.grouped_ops(PATCH_CONTEXT_LINES) {
+            patch.push_str("@@\n");
+            for op in &group {
+                for change in diff.iter_changes(op) {
+                    let prefix = match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    };
+                    let line = change.value();
+                    patch.push(prefix);
+                    patch.push_str(line.strip_suffix('\n').unwrap_or(line));
+                    patch.push('\n');
+                }
+            }
+        }
+    }
+    patch.push_str("*** End Patch");
+    Some(patch)
+}
+
+/// A linter name and the rule it reported, if any.
+type RuleKey = (String, Option<String>);
+
+/// The JSON result of a call, with diagnostics grouped by file, then by
+/// linter and rule.
+pub(crate) fn render_lint_output(
+    cwd: &Path,
+    report: LintReport,
+    fixed: &[PathBuf],
+    fix_errors: Vec<String>,
+) -> String {
+    let display = |path: &Path| {
+        path.strip_prefix(cwd)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .into_owned()
+    };
+    let total = report.diagnostics.len();
+    let mut grouped: BTreeMap<String, BTreeMap<RuleKey, Vec<DiagnosticOutput>>> = BTreeMap::new();
+    for (linter, diagnostic) in report.diagnostics.into_iter().take(MAX_DIAGNOSTICS) {
+        grouped
+            .entry(display(&diagnostic.path))
+            .or_default()
+            .entry((linter, diagnostic.rule))
+            .or_default()
+            .push(DiagnosticOutput {
+                line: diagnostic.line,
+                column: diagnostic.column,
+                severity: diagnostic.severity,
+                message: diagnostic.message,
+            });
+    }
+    let output = LintOutput {
+        total,
+        truncated: total > MAX_DIAGNOSTICS,
+        files: grouped
+            .into_iter()
+            .map(|(path, rules)| FileDiagnostics {
+                path,
+                rules: rules
+                    .into_iter()
+                    .map(|((linter, rule), mut diagnostics)| {
+                        diagnostics.sort_by_key(|d| (d.line, d.column));
+                        RuleDiagnostics {
+                            linter,
+                            rule,
+                            diagnostics,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect(),
+        fixed: fixed.iter().map(|path| display(path)).collect(),
+        errors: fix_errors.into_iter().chain(report.errors).collect(),
+    };
+    serde_json::to_string(&output).unwrap_or_else(|e| format!("failed to encode diagnostics: {e}"))
+}
+
+async fn run_command(
+    command: &[String],
+    cwd: &Path,
+    stdin: Option<&str>,
+    timeout_sec: u64,
+) -> Result<std::process::Output, String> {
+    let Some((program, args)) = command.split_first() else {
+        return Err("empty command".to_string());
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        let input = input.to_string();
+        // Write from a task so a formatter that fills its stdout pipe before
+        // reading all of stdin does not deadlock.
+        tokio::spawn(async move {
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
+    tokio::time::timeout(Duration::from_secs(timeout_sec), child.wait_with_output())
+        .await
+        .map_err(|_| format!("{program} timed out after {timeout_sec}s"))?
+        .map_err(|e| format!("failed to run {program}: {e}"))
+}
+
+/// Diagnostics from `path:line[:column]: [severity:] message` lines. Rules
+/// are read from a trailing `[rule]` or a leading code such as `F401`.
+fn parse_gcc(cwd: &Path, output: &str) -> Vec<Diagnostic> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let captures = GCC_LINE.captures(line)?;
+            let mut message = captures.get(4)?.as_str().to_string();
+            let mut severity = None;
+            if let Some(parts) = GCC_SEVERITY.captures(&message) {
+                severity = Some(parts[1].to_string());
+                message = parts[2].to_string();
+            }
+            let mut rule = None;
+            if let Some(parts) = GCC_TRAILING_RULE.captures(&message) {
+                rule = Some(parts[2].to_string());
+                message = parts[1].to_string();
+            } else if let Some(parts) = GCC_LEADING_RULE.captures(&message) {
+                rule = Some(parts[1].to_string());
+                message = parts[2].to_string();
+            }
+            Some(Diagnostic {
+                path: cwd.join(&captures[1]),
+                line: captures[2].parse().ok()?,
+                column: captures.get(3).and_then(|c| c.as_str().parse().ok()),
+                rule,
+                severity,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn parse_eslint(stdout: &str) -> Option<Vec<Diagnostic>> {
+    let report: Value = serde_json::from_str(stdout.trim()).ok()?;
+    let mut diagnostics = Vec::new();
+    for file in report.as_array()? {
+        let path = PathBuf::from(file["filePath"].as_str().unwrap_or_default());
+        for message in file["messages"].as_array().into_iter().flatten() {
+            diagnostics.push(Diagnostic {
+                path: path.clone(),
+                line: message["line"].as_u64().unwrap_or_default() as usize,
+                column: message["column"].as_u64().map(|c| c as usize),
+                rule: message["ruleId"].as_str().map(str::to_string),
+                severity: match message["severity"].as_u64() {
+                    Some(2) => Some("error".to_string()),
+                    Some(1) => Some("warning".to_string()),
+                    _ => None,
+                },
+                message: message["message"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+    }
+    Some(diagnostics)
+}
+
+fn parse_ruff(cwd: &Path, stdout: &str) -> Option<Vec<Diagnostic>> {
+    let report: Value = serde_json::from_str(stdout.trim()).ok()?;
+    Some(
+        report
+            .as_array()?
+            .iter()
+            .map(|entry| Diagnostic {
+                path: cwd.join(entry["filename"].as_str().unwrap_or_default()),
+                line: entry["location"]["row"].as_u64().unwrap_or_default() as usize,
+                column: entry["location"]["column"].as_u64().map(|c| c as usize),
+                rule: entry["code"].as_str().map(str::to_string),
+                severity: None,
+                message: entry["message"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_gcc_style_lines() {
+        let cwd = Path::new("/repo");
+        let output = "\
+src/a.py:3:1: F401 'os' imported but unused
+run.sh:7:5: warning: Double quote to prevent globbing. [SC2086]
+src/b.py:12: error: Incompatible types in assignment [assignment]
+Found 3 errors
+";
+        assert_eq!(
+            vec![
+                Diagnostic {
+                    path: PathBuf::from("/repo/src/a.py"),
+                    line: 3,
+                    column: Some(1),
+                    rule: Some("F401".to_string()),
+                    severity: None,
+                    message: "'os' imported but unused".to_string(),
+                },
+                Diagnostic {
+                    path: PathBuf::from("/repo/run.sh"),
+                    line: 7,
+                    column: Some(5),
+                    rule: Some("SC2086".to_string()),
+                    severity: Some("warning".to_string()),
+                    message: "Double quote to prevent globbing.".to_string(),
+                },
+                Diagnostic {
+                    path: PathBuf::from("/repo/src/b.py"),
+                    line: 12,
+                    column: None,
+                    rule: Some("assignment".to_string()),
+                    severity: Some("error".to_string()),
+                    message: "Incompatible types in assignment".to_string(),
+                },
+            ],
+            parse_gcc(cwd, output)
+        );
+    }
+
+    #[test]
+    fn groups_eslint_diagnostics_by_file_and_rule() {
+        let cwd = Path::new("/repo");
+        let stdout = r#"[{"filePath": "/repo/src/app.js", "messages": [
+            {"ruleId": "no-unused-vars", "severity": 2, "message": "'x' is unused.", "line": 4, "column": 7},
+            {"ruleId": "semi", "severity": 1, "message": "Missing semicolon.", "line": 2, "column": 10},
+            {"ruleId": "no-unused-vars", "severity": 2, "message": "'y' is unused.", "line": 1, "column": 7}
+        ]}]"#;
+        let report = LintReport {
+            diagnostics: parse_eslint(stdout)
+                .unwrap()
+                .into_iter()
+                .map(|d| ("eslint".to_string(), d))
+                .collect(),
+            errors: Vec::new(),
+        };
+        let output = render_lint_output(cwd, report, &[], Vec::new());
+        let output: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(3, output["total"]);
+        let file = &output["files"][0];
+        assert_eq!("src/app.js", file["path"]);
+        assert_eq!("no-unused-vars", file["rules"][0]["rule"]);
+        assert_eq!(1, file["rules"][0]["diagnostics"][0]["line"]);
+        assert_eq!(4, file["rules"][0]["diagnostics"][1]["line"]);
+        assert_eq!("semi", file["rules"][1]["rule"]);
+        assert_eq!("warning", file["rules"][1]["diagnostics"][0]["severity"]);
+    }
+
+    #[test]
+    fn builds_patch_from_fixes() {
+        let cwd = Path::new("/repo");
+        let original: String = (1..=10).map(|n| format!("line {n}\n")).collect();
+        let fixed = original.replace("line 2\n", "line two\n");
+        let patch = fix_patch(
+            cwd,
+            &[FixedFile {
+                path: PathBuf::from("/repo/src/a.txt"),
+                original,
+                fixed,
+            }],
+        )
+        .unwrap();
+        assert_eq!(
+            "*** Begin Patch\n*** Update File: src/a.txt\n@@\n line 1\n-line 2\n+line two\n line 3\n line 4\n line 5\n*** End Patch",
+            patch
+        );
+        assert_eq!(None, fix_patch(cwd, &[]));
+    }
+
+    #[tokio::test]
+    async fn fix_commands_chain_through_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "hello\n").unwrap();
+        let linters = BTreeMap::from([(
+            "upper".to_string(),
+            LinterConfig {
+                command: vec!["true".to_string()],
+                output_format: LintOutputFormat::Gcc,
+                extensions: vec!["txt".to_string()],
+                fix_command: Some(vec!["tr".to_string(), "a-z".to_string(), "A-Z".to_string()]),
+                timeout_sec: 10,
+            },
+        )]);
+
+        let fixes = compute_fixes(&linters, dir.path(), std::slice::from_ref(&path)).await;
+        assert!(fixes.errors.is_empty());
+        assert_eq!(1, fixes.files.len());
+        assert_eq!("HELLO\n", fixes.files[0].fixed);
+
+        let report = run_linters(&linters, dir.path(), &[path]).await;
+        assert!(report.diagnostics.is_empty());
+        assert!(report.errors.is_empty());
+    }
+}
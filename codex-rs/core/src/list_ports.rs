@@ -0,0 +1,614 @@
+//! The `list_ports` tool, which reports the local ports that are listening
+//! and the process that owns each, so the model can diagnose "address already
+//! in use" without parsing `lsof` or `netstat` text.
+//!
+//! Sockets are read from `/proc` on Linux, from `lsof` and `ps` on other
+//! Unix systems and from `netstat` and `tasklist` on Windows. Ports owned by
+//! one of the session's background processes, or by a process they started,
+//! name that background process.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::background_process::BackgroundProcessSummary;
+use crate::function_tool::FunctionCallError;
+
+pub(crate) const LIST_PORTS_TOOL_NAME: &str = "list_ports";
+
+/// How long `lsof`, `ps`, `netstat` or `tasklist` may take.
+#[cfg(not(target_os = "linux"))]
+const COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Parents followed when looking for a background process that started the
+/// owner of a port.
+const MAX_ANCESTORS: usize = 32;
+
+#[derive(Debug, Default, Deserialize)]
+struct ListPortsArgs {
+    /// Only report this port.
+    #[serde(default)]
+    port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// A listening TCP socket or a bound, unconnected UDP socket.
+#[derive(Debug, Clone, PartialEq)]
+struct Socket {
+    protocol: Protocol,
+    address: String,
+    port: u16,
+    /// `None` when the owner is not visible, such as a process of another
+    /// user.
+    pid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ProcessInfo {
+    name: Option<String>,
+    command: Option<String>,
+    parent: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct PortEntry {
+    protocol: Protocol,
+    address: String,
+    port: u16,
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    process: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    /// Id of the session's background process that owns the port.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background_process: Option<String>,
+}
+
+pub(crate) async fn handle_list_ports_tool(
+    background: &[BackgroundProcessSummary],
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: ListPortsArgs = if arguments.trim().is_empty() {
+        ListPortsArgs::default()
+    } else {
+        serde_json::from_str(arguments).map_err(|e| {
+            FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+        })?
+    };
+    let (mut sockets, processes) = listening_sockets()
+        .await
+        .map_err(|e| FunctionCallError::RespondToModel(format!("failed to list ports: {e}")))?;
+    if let Some(port) = args.port {
+        sockets.retain(|socket| socket.port == port);
+    }
+    sockets.sort_by(|a, b| (a.port, a.protocol, &a.address).cmp(&(b.port, b.protocol, &b.address)));
+    sockets.dedup();
+
+    let background: Vec<(u32, &str)> = background
+        .iter()
+        .filter_map(|summary| Some((summary.pid?, summary.id.as_str())))
+        .collect();
+    let ports: Vec<PortEntry> = sockets
+        .into_iter()
+        .map(|socket| {
+            let info = socket.pid.and_then(|pid| processes.get(&pid));
+            PortEntry {
+                background_process: socket
+                    .pid
+                    .and_then(|pid| owning_background_process(pid, &processes, &background)),
+                process: info.and_then(|info| info.name.clone()),
+                command: info.and_then(|info| info.command.clone()),
+                protocol: socket.protocol,
+                address: socket.address,
+                port: socket.port,
+                pid: socket.pid,
+            }
+        })
+        .collect();
+    Ok(serde_json::json!({ "ports": ports }).to_string())
+}
+
+/// The background process that is `pid` or one of its ancestors.
+fn owning_background_process(
+    pid: u32,
+    processes: &HashMap<u32, ProcessInfo>,
+    background: &[(u32, &str)],
+) -> Option<String> {
+    let mut current = pid;
+    for _ in 0..MAX_ANCESTORS {
+        if let Some((_, id)) = background.iter().find(|(pid, _)| *pid == current) {
+            return Some(id.to_string());
+        }
+        current = processes.get(&current)?.parent?;
+    }
+    None
+}
+
+#[cfg(target_os = "linux")]
+async fn listening_sockets() -> Result<(Vec<Socket>, HashMap<u32, ProcessInfo>), String> {
+    tokio::task::spawn_blocking(linux::listening_sockets)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+    use std::collections::HashSet;
+
+    use super::ProcessInfo;
+    use super::Protocol;
+    use super::Socket;
+    use super::parse_proc_net;
+
+    const TABLES: &[(&str, Protocol)] = &[
+        ("/proc/net/tcp", Protocol::Tcp),
+        ("/proc/net/tcp6", Protocol::Tcp),
+        ("/proc/net/udp", Protocol::Udp),
+        ("/proc/net/udp6", Protocol::Udp),
+    ];
+
+    pub(super) fn listening_sockets() -> Result<(Vec<Socket>, HashMap<u32, ProcessInfo>), String> {
+        let mut by_inode = Vec::new();
+        for (path, protocol) in TABLES {
+            // IPv6 tables are missing when IPv6 is disabled.
+            if let Ok(table) = std::fs::read_to_string(path) {
+                by_inode.extend(parse_proc_net(&table, *protocol));
+            }
+        }
+        if by_inode.is_empty() && std::fs::metadata("/proc/net/tcp").is_err() {
+            return Err("/proc/net is not available".to_string());
+        }
+
+        let listening: HashSet<u64> = by_inode.iter().map(|(inode, _)| *inode).collect();
+        let mut owners = HashMap::new();
+        let mut processes = HashMap::new();
+        let entries = std::fs::read_dir("/proc").map_err(|e| e.to_string())?;
+        for entry in entries.flatten() {
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            processes.insert(pid, process_info(pid));
+            // Only the user's own processes can be inspected.
+            let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                let Ok(target) = std::fs::read_link(fd.path()) else {
+                    continue;
+                };
+                if let Some(inode) = target
+                    .to_str()
+                    .and_then(|target| target.strip_prefix("socket:["))
+                    .and_then(|target| target.strip_suffix(']'))
+                    .and_then(|inode| inode.parse::<u64>().ok())
+                    && listening.contains(&inode)
+                {
+                    owners.entry(inode).or_insert(pid);
+                }
+            }
+        }
+
+        for (pid, info) in &mut processes {
+            if owners.values().any(|owner| owner == pid) {
+                info.command = std::fs::read(format!("/proc/{pid}/cmdline"))
+                    .ok()
+                    .map(|cmdline| {
+                        String::from_utf8_lossy(&cmdline)
+                            .split('\0')
+                            .filter(|arg| !arg.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .filter(|command| !command.is_empty());
+            }
+        }
+        let sockets = by_inode
+            .into_iter()
+            .map(|(inode, mut socket)| {
+                socket.pid = owners.get(&inode).copied();
+                socket
+            })
+            .collect();
+        Ok((sockets, processes))
+    }
+
+    fn process_info(pid: u32) -> ProcessInfo {
+        let name = std::fs::read_to_string(format!("/proc/{pid}/comm"))
+            .ok()
+            .map(|comm| comm.trim_end().to_string());
+        // The parent is the second field after the parenthesised name, which
+        // may itself contain spaces and parentheses.
+        let parent = std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .ok()
+            .and_then(|stat| {
+                let (_, rest) = stat.rsplit_once(')')?;
+                rest.split_whitespace().nth(1)?.parse().ok()
+            });
+        ProcessInfo {
+            name,
+            command: None,
+            parent,
+        }
+    }
+}
+
+/// Listening sockets from a `/proc/net/{tcp,udp}{,6}` table, keyed by inode.
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net(table: &str, protocol: Protocol) -> Vec<(u64, Socket)> {
+    // `0A` is TCP_LISTEN; unconnected UDP sockets are `07` (TCP_CLOSE).
+    let listening = match protocol {
+        Protocol::Tcp => "0A",
+        Protocol::Udp => "07",
+    };
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (local, remote, state, inode) = (
+                fields.get(1)?,
+                fields.get(2)?,
+                fields.get(3)?,
+                fields.get(9)?,
+            );
+            if *state != listening {
+                return None;
+            }
+            let (_, remote_port) = remote.split_once(':')?;
+            if protocol == Protocol::Udp && remote_port != "0000" {
+                return None;
+            }
+            let (address, port) = local.split_once(':')?;
+            Some((
+                inode.parse().ok()?,
+                Socket {
+                    protocol,
+                    address: parse_proc_address(address)?,
+                    port: u16::from_str_radix(port, 16).ok()?,
+                    pid: None,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// An address from `/proc/net`, stored as 32-bit words in host byte order,
+/// which is little-endian on every platform Codex runs on.
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_address(hex: &str) -> Option<String> {
+    let words = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+async fn listening_sockets() -> Result<(Vec<Socket>, HashMap<u32, ProcessInfo>), String> {
+    // lsof exits with 1 when nothing matches.
+    let lsof = run(
+        "lsof",
+        &["-nP", "-iTCP", "-sTCP:LISTEN", "-iUDP", "-F", "pcPn"],
+        &[0, 1],
+    )
+    .await?;
+    let ps = run("ps", &["-A", "-ww", "-o", "pid=,ppid=,command="], &[0]).await?;
+    let mut processes = parse_ps(&ps);
+    let (sockets, names) = parse_lsof(&lsof);
+    for (pid, name) in names {
+        processes.entry(pid).or_default().name = Some(name);
+    }
+    Ok((sockets, processes))
+}
+
+#[cfg(windows)]
+async fn listening_sockets() -> Result<(Vec<Socket>, HashMap<u32, ProcessInfo>), String> {
+    let netstat = run("netstat", &["-ano"], &[0]).await?;
+    let tasklist = run("tasklist", &["/FO", "CSV", "/NH"], &[0]).await?;
+    Ok((parse_netstat(&netstat), parse_tasklist(&tasklist)))
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn listening_sockets() -> Result<(Vec<Socket>, HashMap<u32, ProcessInfo>), String> {
+    Err("not supported on this platform".to_string())
+}
+
+/// Stdout of `program`, which must exit with one of `ok_codes`.
+#[cfg(not(target_os = "linux"))]
+async fn run(program: &str, args: &[&str], ok_codes: &[i32]) -> Result<String, String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+        .await
+        .map_err(|_| format!("{program} timed out"))?
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+    if !output
+        .status
+        .code()
+        .is_some_and(|code| ok_codes.contains(&code))
+    {
+        return Err(format!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Sockets and process names from `lsof -F pcPn` output, where each line is
+/// a one-letter field tag followed by its value.
+#[cfg(any(all(unix, not(target_os = "linux")), test))]
+fn parse_lsof(output: &str) -> (Vec<Socket>, HashMap<u32, String>) {
+    let mut sockets = Vec::new();
+    let mut names = HashMap::new();
+    let mut pid = None;
+    let mut protocol = None;
+    for line in output.lines() {
+        let Some(tag) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[tag.len_utf8()..];
+        match tag {
+            'p' => {
+                pid = value.parse::<u32>().ok();
+                protocol = None;
+            }
+            'c' => {
+                if let Some(pid) = pid {
+                    names.insert(pid, value.to_string());
+                }
+            }
+            'f' => protocol = None,
+            'P' => {
+                protocol = match value {
+                    "TCP" => Some(Protocol::Tcp),
+                    "UDP" => Some(Protocol::Udp),
+                    _ => None,
+                }
+            }
+            'n' => {
+                // Connected UDP sockets are named `local->remote`.
+                if let Some(protocol) = protocol
+                    && !value.contains("->")
+                    && let Some((address, port)) = split_host_port(value)
+                {
+                    sockets.push(Socket {
+                        protocol,
+                        address,
+                        port,
+                        pid,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    (sockets, names)
+}
+
+/// Parents and command lines from `ps -o pid=,ppid=,command=` output.
+#[cfg(any(all(unix, not(target_os = "linux")), test))]
+fn parse_ps(output: &str) -> HashMap<u32, ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim_start().splitn(2, char::is_whitespace);
+            let pid = fields.next()?.parse().ok()?;
+            let rest = fields.next()?.trim_start();
+            let mut fields = rest.splitn(2, char::is_whitespace);
+            let parent = fields.next()?.parse().ok();
+            let command = fields
+                .next()
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+                .map(str::to_string);
+            Some((
+                pid,
+                ProcessInfo {
+                    name: None,
+                    command,
+                    parent,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Listening sockets from `netstat -ano` output.
+#[cfg(any(windows, test))]
+fn parse_netstat(output: &str) -> Vec<Socket> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (protocol, pid) = match fields.as_slice() {
+                ["TCP", _, _, "LISTENING", pid] => (Protocol::Tcp, pid),
+                ["UDP", _, "*:*", pid] => (Protocol::Udp, pid),
+                _ => return None,
+            };
+            let (address, port) = split_host_port(fields[1])?;
+            Some(Socket {
+                protocol,
+                address,
+                port,
+                pid: pid.parse().ok().filter(|pid| *pid != 0),
+            })
+        })
+        .collect()
+}
+
+/// Process names from `tasklist /FO CSV /NH` output.
+#[cfg(any(windows, test))]
+fn parse_tasklist(output: &str) -> HashMap<u32, ProcessInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split("\",\"");
+            let name = fields.next()?.trim_start_matches('"');
+            let pid = fields.next()?.parse().ok()?;
+            Some((
+                pid,
+                ProcessInfo {
+                    name: Some(name.to_string()),
+                    ..Default::default()
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Split `host:port`, `[v6]:port` or `*:port`; the wildcard host is reported
+/// as `*`.
+#[cfg(any(not(target_os = "linux"), test))]
+fn split_host_port(name: &str) -> Option<(String, u16)> {
+    let (host, port) = name.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn socket(protocol: Protocol, address: &str, port: u16, pid: Option<u32>) -> Socket {
+        Socket {
+            protocol,
+            address: address.to_string(),
+            port,
+            pid,
+        }
+    }
+
+    #[test]
+    fn parses_proc_net_tables() {
+        let tcp = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41234 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:C350 0100007F:0BB8 01 00000000:00000000 00:00000000 00000000  1000        0 41240 1 0000000000000000 20 4 30 10 -1
+";
+        let tcp6 = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 51000 1 0000000000000000 100 0 0 10 0
+";
+        let udp = "\
+   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  100: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000   105        0 2233 2 0000000000000000 0
+  101: 0F02000A:9C40 08080808:0035 01 00000000:00000000 00:00000000 00000000  1000        0 2234 2 0000000000000000 0
+";
+        assert_eq!(
+            vec![(41234, socket(Protocol::Tcp, "127.0.0.1", 3000, None))],
+            parse_proc_net(tcp, Protocol::Tcp)
+        );
+        assert_eq!(
+            vec![(51000, socket(Protocol::Tcp, "::1", 8080, None))],
+            parse_proc_net(tcp6, Protocol::Tcp)
+        );
+        assert_eq!(
+            vec![(2233, socket(Protocol::Udp, "0.0.0.0", 5353, None))],
+            parse_proc_net(udp, Protocol::Udp)
+        );
+    }
+
+    #[test]
+    fn parses_lsof_and_ps_output() {
+        let lsof = "p812\ncnode\nf23\nPTCP\nn127.0.0.1:3000\nf24\nPTCP\nn[::1]:3000\np90\ncmDNSResponder\nf7\nPUDP\nn*:5353\nf8\nPUDP\nn10.0.0.2:51000->8.8.8.8:53\n";
+        let (sockets, names) = parse_lsof(lsof);
+        assert_eq!(
+            vec![
+                socket(Protocol::Tcp, "127.0.0.1", 3000, Some(812)),
+                socket(Protocol::Tcp, "::1", 3000, Some(812)),
+                socket(Protocol::Udp, "*", 5353, Some(90)),
+            ],
+            sockets
+        );
+        assert_eq!(Some(&"node".to_string()), names.get(&812));
+
+        let processes = parse_ps("  800   1 npm run dev\n  812 800 node server.js --port 3000\n");
+        assert_eq!(
+            Some(&ProcessInfo {
+                name: None,
+                command: Some("node server.js --port 3000".to_string()),
+                parent: Some(800),
+            }),
+            processes.get(&812)
+        );
+    }
+
+    #[test]
+    fn parses_netstat_and_tasklist_output() {
+        let netstat = "\
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1024
+  TCP    127.0.0.1:5432         127.0.0.1:50100        ESTABLISHED     2048
+  TCP    [::]:3000              [::]:0                 LISTENING       4096
+  UDP    0.0.0.0:5353           *:*                                    512
+";
+        assert_eq!(
+            vec![
+                socket(Protocol::Tcp, "0.0.0.0", 135, Some(1024)),
+                socket(Protocol::Tcp, "::", 3000, Some(4096)),
+                socket(Protocol::Udp, "0.0.0.0", 5353, Some(512)),
+            ],
+            parse_netstat(netstat)
+        );
+        let tasklist = "\"node.exe\",\"4096\",\"Console\",\"1\",\"52,120 K\"\n";
+        assert_eq!(
+            Some(&"node.exe".to_string()),
+            parse_tasklist(tasklist)
+                .get(&4096)
+                .and_then(|info| info.name.as_ref())
+        );
+    }
+
+    #[test]
+    fn attributes_ports_to_background_ancestors() {
+        let processes = HashMap::from([
+            (
+                812,
+                ProcessInfo {
+                    parent: Some(800),
+                    ..Default::default()
+                },
+            ),
+            (
+                800,
+                ProcessInfo {
+                    parent: Some(1),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let background = [(800, "bg-1")];
+        assert_eq!(
+            Some("bg-1".to_string()),
+            owning_background_process(812, &processes, &background)
+        );
+        assert_eq!(None, owning_background_process(1, &processes, &background));
+    }
+}
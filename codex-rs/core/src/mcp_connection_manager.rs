@@ -4,32 +4,61 @@
 //! configured server (keyed by the *server name*). It offers convenience
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
-//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key, unless the server's
+//! `tool_prefix` or `tool_aliases` name its tools differently. Servers that offer
+//! resources can also have them listed and read, and servers that offer
+//! prompts can have them listed and expanded. When sampling is enabled the
+//! servers' `sampling/createMessage` requests are answered by the
+//! [`McpSampler`], `roots/list` requests by the [`McpRoots`] and
+//! `elicitation/create` requests by the [`McpElicitor`]. Servers can be
+//! pinged, and reconnected one at a time when they stop answering.
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
+use codex_mcp_client::ElicitationHandler;
 use codex_mcp_client::McpClient;
+use codex_mcp_client::ProgressHandler;
+use codex_mcp_client::RootsHandler;
+use codex_mcp_client::SamplingHandler;
+use codex_rmcp_client::OAuthCredentialsStore;
 use codex_rmcp_client::RmcpClient;
+use codex_rmcp_client::oauth_access_token;
 use mcp_types::ClientCapabilities;
+use mcp_types::ClientCapabilitiesRoots;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::InitializeResult;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
 use mcp_types::Tool;
 
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
+use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
 
 use crate::config_types::McpServerConfig;
 use crate::config_types::McpServerTransportConfig;
+use crate::config_types::ToolFilter;
+use crate::mcp_elicitation::McpElicitor;
+use crate::mcp_roots::McpRoots;
+use crate::mcp_sampling::McpSampler;
 
 /// Delimiter used to separate the server name from the tool name in a fully
 /// qualified tool name.
@@ -45,36 +74,146 @@ const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
 /// Default timeout for individual tool calls.
 const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// `resources/list` is paged through at most this many times per server.
+const MAX_RESOURCE_PAGES: usize = 10;
+
+/// `prompts/list` is paged through at most this many times per server.
+const MAX_PROMPT_PAGES: usize = 10;
+
 /// Map that holds a startup error for every MCP server that could **not** be
 /// spawned successfully.
 pub type ClientStartErrors = HashMap<String, anyhow::Error>;
 
-fn qualify_tools(tools: Vec<ToolInfo>) -> HashMap<String, ToolInfo> {
-    let mut used_names = HashSet::new();
-    let mut qualified_tools = HashMap::new();
-    for tool in tools {
-        let mut qualified_name = format!(
-            "{}{}{}",
-            tool.server_name, MCP_TOOL_NAME_DELIMITER, tool.tool_name
-        );
-        if qualified_name.len() > MAX_TOOL_NAME_LENGTH {
-            let mut hasher = Sha1::new();
-            hasher.update(qualified_name.as_bytes());
-            let sha1 = hasher.finalize();
-            let sha1_str = format!("{sha1:x}");
+/// Why [`McpConnectionManager::call_tool`] did not return a result.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum McpCallError {
+    #[error("unknown MCP server '{0}'")]
+    UnknownServer(String),
+    #[error("MCP server `{server}` is already handling {limit} tool calls")]
+    Busy { server: String, limit: usize },
+    #[error("tool call `{server}/{tool}` did not finish within {timeout:?}")]
+    TimedOut {
+        server: String,
+        tool: String,
+        timeout: Duration,
+    },
+    #[error("result of `{server}/{tool}` is {size} bytes, more than the limit of {limit}")]
+    ResultTooLarge {
+        server: String,
+        tool: String,
+        size: usize,
+        limit: usize,
+    },
+    #[error("tool call failed for `{server}/{tool}`: {error:#}")]
+    Failed {
+        server: String,
+        tool: String,
+        error: anyhow::Error,
+    },
+}
 
-            // Truncate to make room for the hash suffix
-            let prefix_len = MAX_TOOL_NAME_LENGTH - sha1_str.len();
+/// How the tools of one server are offered to the model: which of them, from
+/// its `enabled_tools` and `disabled_tools`, and under what names, from its
+/// `tool_prefix` and `tool_aliases`.
+#[derive(Debug, Clone, Default)]
+struct ToolNaming {
+    prefix: Option<String>,
+    aliases: HashMap<String, String>,
+    filter: ToolFilter,
+}
 
-            qualified_name = format!("{}{}", &qualified_name[..prefix_len], sha1_str);
+impl ToolNaming {
+    fn from_config(cfg: &McpServerConfig) -> Self {
+        Self {
+            prefix: cfg.tool_prefix.clone(),
+            aliases: cfg.tool_aliases.clone(),
+            filter: ToolFilter::new(cfg.enabled_tools.as_deref(), &cfg.disabled_tools),
         }
+    }
+}
+
+/// `name` shortened to [`MAX_TOOL_NAME_LENGTH`] by replacing its end with its
+/// SHA-1 when it is too long.
+fn fit_tool_name(name: String) -> String {
+    if name.len() <= MAX_TOOL_NAME_LENGTH {
+        return name;
+    }
+    let mut hasher = Sha1::new();
+    hasher.update(name.as_bytes());
+    let sha1 = hasher.finalize();
+    let sha1_str = format!("{sha1:x}");
+
+    // Truncate to make room for the hash suffix
+    let prefix_len = MAX_TOOL_NAME_LENGTH - sha1_str.len();
+
+    format!("{}{}", &name[..prefix_len], sha1_str)
+}
 
-        if used_names.contains(&qualified_name) {
-            warn!("skipping duplicated tool {}", qualified_name);
+fn canonical_tool_name(tool: &ToolInfo) -> String {
+    fit_tool_name(format!(
+        "{}{}{}",
+        tool.server_name, MCP_TOOL_NAME_DELIMITER, tool.tool_name
+    ))
+}
+
+/// The name `tool` asks for: its alias, else its name under the server's
+/// prefix, else the canonical `<server>__<tool>`.
+fn preferred_tool_name(tool: &ToolInfo, naming: Option<&ToolNaming>) -> String {
+    let Some(naming) = naming else {
+        return canonical_tool_name(tool);
+    };
+    if let Some(alias) = naming.aliases.get(&tool.tool_name) {
+        return fit_tool_name(alias.clone());
+    }
+    match naming.prefix.as_deref() {
+        Some("") => fit_tool_name(tool.tool_name.clone()),
+        Some(prefix) => fit_tool_name(format!(
+            "{prefix}{MCP_TOOL_NAME_DELIMITER}{}",
+            tool.tool_name
+        )),
+        None => canonical_tool_name(tool),
+    }
+}
+
+/// Give every tool its server lets through a unique name for the model.
+/// Aliased tools are named first so that generated names make way for them. A
+/// tool whose preferred name is taken falls back to its canonical name, and is
+/// skipped when that is taken too.
+fn qualify_tools(
+    mut tools: Vec<ToolInfo>,
+    naming: &HashMap<String, ToolNaming>,
+) -> HashMap<String, ToolInfo> {
+    let is_aliased = |tool: &ToolInfo| {
+        naming
+            .get(&tool.server_name)
+            .is_some_and(|naming| naming.aliases.contains_key(&tool.tool_name))
+    };
+    tools.sort_by(|a, b| {
+        (!is_aliased(a), &a.server_name, &a.tool_name).cmp(&(
+            !is_aliased(b),
+            &b.server_name,
+            &b.tool_name,
+        ))
+    });
+
+    let mut qualified_tools = HashMap::new();
+    for tool in tools {
+        let server_naming = naming.get(&tool.server_name);
+        if server_naming.is_some_and(|naming| !naming.filter.allows(&tool.tool_name)) {
             continue;
         }
-
-        used_names.insert(qualified_name.clone());
+        let preferred = preferred_tool_name(&tool, server_naming);
+        let qualified_name = if !qualified_tools.contains_key(&preferred) {
+            preferred
+        } else {
+            let canonical = canonical_tool_name(&tool);
+            if qualified_tools.contains_key(&canonical) {
+                warn!("skipping duplicated tool {}", canonical);
+                continue;
+            }
+            warn!("tool name {preferred} is taken, offering the tool as {canonical}");
+            canonical
+        };
         qualified_tools.insert(qualified_name, tool);
     }
 
@@ -91,6 +230,13 @@ struct ManagedClient {
     client: McpClientAdapter,
     startup_timeout: Duration,
     tool_timeout: Option<Duration>,
+    /// Whether the server advertised the `resources` capability.
+    supports_resources: bool,
+    /// Whether the server advertised the `prompts` capability.
+    supports_prompts: bool,
+    /// `max_concurrent_calls` and the slots the calls take.
+    call_slots: Option<(usize, Arc<Semaphore>)>,
+    max_result_bytes: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -100,6 +246,7 @@ enum McpClientAdapter {
 }
 
 impl McpClientAdapter {
+    #[allow(clippy::too_many_arguments)]
     async fn new_stdio_client(
         use_rmcp_client: bool,
         program: OsString,
@@ -107,30 +254,57 @@ impl McpClientAdapter {
         env: Option<HashMap<String, String>>,
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
-    ) -> Result<Self> {
+        sampling_handler: Option<SamplingHandler>,
+        roots_handler: RootsHandler,
+        elicitation_handler: ElicitationHandler,
+    ) -> Result<(Self, InitializeResult)> {
         info!(
             "new_stdio_client use_rmcp_client: {use_rmcp_client} program: {program:?} args: {args:?} env: {env:?} params: {params:?} startup_timeout: {startup_timeout:?}"
         );
         if use_rmcp_client {
             let client = Arc::new(RmcpClient::new_stdio_client(program, args, env).await?);
-            client.initialize(params, Some(startup_timeout)).await?;
-            Ok(McpClientAdapter::Rmcp(client))
+            if let Some(handler) = sampling_handler {
+                client.set_sampling_handler(handler);
+            }
+            client.set_roots_handler(roots_handler);
+            client.set_elicitation_handler(elicitation_handler);
+            let initialized = client.initialize(params, Some(startup_timeout)).await?;
+            Ok((McpClientAdapter::Rmcp(client), initialized))
         } else {
             let client = Arc::new(McpClient::new_stdio_client(program, args, env).await?);
-            client.initialize(params, Some(startup_timeout)).await?;
-            Ok(McpClientAdapter::Legacy(client))
+            if let Some(handler) = sampling_handler {
+                client.set_sampling_handler(handler);
+            }
+            client.set_roots_handler(roots_handler);
+            client.set_elicitation_handler(elicitation_handler);
+            let initialized = client.initialize(params, Some(startup_timeout)).await?;
+            Ok((McpClientAdapter::Legacy(client), initialized))
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn new_streamable_http_client(
         url: String,
         bearer_token: Option<String>,
+        http_headers: Option<HashMap<String, String>>,
         params: mcp_types::InitializeRequestParams,
         startup_timeout: Duration,
-    ) -> Result<Self> {
-        let client = Arc::new(RmcpClient::new_streamable_http_client(url, bearer_token)?);
-        client.initialize(params, Some(startup_timeout)).await?;
-        Ok(McpClientAdapter::Rmcp(client))
+        sampling_handler: Option<SamplingHandler>,
+        roots_handler: RootsHandler,
+        elicitation_handler: ElicitationHandler,
+    ) -> Result<(Self, InitializeResult)> {
+        let client = Arc::new(RmcpClient::new_streamable_http_client(
+            url,
+            bearer_token,
+            http_headers,
+        )?);
+        if let Some(handler) = sampling_handler {
+            client.set_sampling_handler(handler);
+        }
+        client.set_roots_handler(roots_handler);
+        client.set_elicitation_handler(elicitation_handler);
+        let initialized = client.initialize(params, Some(startup_timeout)).await?;
+        Ok((McpClientAdapter::Rmcp(client), initialized))
     }
 
     async fn list_tools(
@@ -149,10 +323,77 @@ impl McpClientAdapter {
         name: String,
         arguments: Option<serde_json::Value>,
         timeout: Option<Duration>,
+        on_progress: ProgressHandler,
     ) -> Result<mcp_types::CallToolResult> {
         match self {
-            McpClientAdapter::Legacy(client) => client.call_tool(name, arguments, timeout).await,
-            McpClientAdapter::Rmcp(client) => client.call_tool(name, arguments, timeout).await,
+            McpClientAdapter::Legacy(client) => {
+                client
+                    .call_tool_with_progress(name, arguments, timeout, on_progress)
+                    .await
+            }
+            McpClientAdapter::Rmcp(client) => {
+                client
+                    .call_tool_with_progress(name, arguments, timeout, on_progress)
+                    .await
+            }
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<mcp_types::ListResourcesResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.list_resources(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.list_resources(params, timeout).await,
+        }
+    }
+
+    async fn read_resource(
+        &self,
+        params: ReadResourceRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.read_resource(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.read_resource(params, timeout).await,
+        }
+    }
+
+    async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<mcp_types::ListPromptsResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.list_prompts(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.list_prompts(params, timeout).await,
+        }
+    }
+
+    async fn get_prompt(
+        &self,
+        params: GetPromptRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.get_prompt(params, timeout).await,
+            McpClientAdapter::Rmcp(client) => client.get_prompt(params, timeout).await,
+        }
+    }
+
+    async fn ping(&self, timeout: Option<Duration>) -> Result<()> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.ping(timeout).await,
+            McpClientAdapter::Rmcp(client) => client.ping(timeout).await,
+        }
+    }
+
+    async fn notify_roots_list_changed(&self) -> Result<()> {
+        match self {
+            McpClientAdapter::Legacy(client) => client.notify_roots_list_changed().await,
+            McpClientAdapter::Rmcp(client) => client.notify_roots_list_changed().await,
         }
     }
 }
@@ -168,6 +409,35 @@ pub(crate) struct McpConnectionManager {
 
     /// Fully qualified tool name -> tool instance.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server-name -> how its tools are named.
+    naming: HashMap<String, ToolNaming>,
+}
+
+/// What connecting to a server takes besides its configuration.
+#[derive(Clone)]
+pub(crate) struct McpConnectOptions {
+    pub(crate) use_rmcp_client: bool,
+    /// Answers the servers' sampling requests; servers are only told that
+    /// the client supports sampling when it is set.
+    pub(crate) sampler: Option<Arc<McpSampler>>,
+    /// The roots listed to the servers.
+    pub(crate) roots: Arc<McpRoots>,
+    /// Forwards the servers' elicitation requests to the user.
+    pub(crate) elicitor: Arc<McpElicitor>,
+    /// Where the OAuth tokens of streamable HTTP servers without a configured
+    /// bearer token are looked up.
+    pub(crate) codex_home: PathBuf,
+    pub(crate) oauth_store: OAuthCredentialsStore,
+}
+
+/// A server connected by [`McpConnectionManager::reconnect_server`], ready to
+/// be installed with [`McpConnectionManager::replace_server`] or
+/// [`McpConnectionManager::insert_server`].
+pub(crate) struct ReconnectedServer {
+    client: ManagedClient,
+    tools: Vec<ToolInfo>,
+    naming: ToolNaming,
 }
 
 impl McpConnectionManager {
@@ -176,12 +446,13 @@ impl McpConnectionManager {
     /// * `mcp_servers` – Map loaded from the user configuration where *keys*
     ///   are human-readable server identifiers and *values* are the spawn
     ///   instructions.
+    /// * `options` – Shared by every server.
     ///
     /// Servers that fail to start are reported in `ClientStartErrors`: the
     /// user should be informed about these errors.
     pub async fn new(
         mcp_servers: HashMap<String, McpServerConfig>,
-        use_rmcp_client: bool,
+        options: McpConnectOptions,
     ) -> Result<(Self, ClientStartErrors)> {
         // Early exit if no servers are configured.
         if mcp_servers.is_empty() {
@@ -191,6 +462,7 @@ impl McpConnectionManager {
         // Launch all configured servers concurrently.
         let mut join_set = JoinSet::new();
         let mut errors = ClientStartErrors::new();
+        let mut naming = HashMap::with_capacity(mcp_servers.len());
 
         for (server_name, cfg) in mcp_servers {
             // Validate server name before spawning
@@ -202,79 +474,18 @@ impl McpConnectionManager {
                 continue;
             }
 
-            if matches!(
-                cfg.transport,
-                McpServerTransportConfig::StreamableHttp { .. }
-            ) && !use_rmcp_client
-            {
-                info!(
-                    "skipping MCP server `{}` configured with url because rmcp client is disabled",
-                    server_name
-                );
-                continue;
-            }
-
-            let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
-            let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
-
-            let use_rmcp_client_flag = use_rmcp_client;
+            naming.insert(server_name.clone(), ToolNaming::from_config(&cfg));
+            let options = options.clone();
             join_set.spawn(async move {
-                let McpServerConfig { transport, .. } = cfg;
-                let params = mcp_types::InitializeRequestParams {
-                    capabilities: ClientCapabilities {
-                        experimental: None,
-                        roots: None,
-                        sampling: None,
-                        // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                        // indicates this should be an empty object.
-                        elicitation: Some(json!({})),
-                    },
-                    client_info: Implementation {
-                        name: "codex-mcp-client".to_owned(),
-                        version: env!("CARGO_PKG_VERSION").to_owned(),
-                        title: Some("Codex".into()),
-                        // This field is used by Codex when it is an MCP
-                        // server: it should not be used when Codex is
-                        // an MCP client.
-                        user_agent: None,
-                    },
-                    protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                };
-
-                let client = match transport {
-                    McpServerTransportConfig::Stdio { command, args, env } => {
-                        let command_os: OsString = command.into();
-                        let args_os: Vec<OsString> = args.into_iter().map(Into::into).collect();
-                        McpClientAdapter::new_stdio_client(
-                            use_rmcp_client_flag,
-                            command_os,
-                            args_os,
-                            env,
-                            params.clone(),
-                            startup_timeout,
-                        )
-                        .await
-                    }
-                    McpServerTransportConfig::StreamableHttp { url, bearer_token } => {
-                        McpClientAdapter::new_streamable_http_client(
-                            url,
-                            bearer_token,
-                            params,
-                            startup_timeout,
-                        )
-                        .await
-                    }
-                }
-                .map(|c| (c, startup_timeout));
-
-                ((server_name, tool_timeout), client)
+                let client = connect_server(&server_name, cfg, &options).await;
+                (server_name, client)
             });
         }
 
         let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
 
         while let Some(res) = join_set.join_next().await {
-            let ((server_name, tool_timeout), client_res) = match res {
+            let (server_name, client_res) = match res {
                 Ok(result) => result,
                 Err(e) => {
                     warn!("Task panic when starting MCP server: {e:#}");
@@ -283,15 +494,8 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
-                    clients.insert(
-                        server_name,
-                        ManagedClient {
-                            client,
-                            startup_timeout,
-                            tool_timeout: Some(tool_timeout),
-                        },
-                    );
+                Ok(client) => {
+                    clients.insert(server_name, client);
                 }
                 Err(e) => {
                     errors.insert(server_name, e);
@@ -307,9 +511,129 @@ impl McpConnectionManager {
             }
         };
 
-        let tools = qualify_tools(all_tools);
+        let tools = qualify_tools(all_tools, &naming);
+
+        Ok((
+            Self {
+                clients,
+                tools,
+                naming,
+            },
+            errors,
+        ))
+    }
+
+    /// Connect to `server`, again or for the first time, and list its tools.
+    /// This takes no lock on a manager, so the other servers stay usable in
+    /// the meantime.
+    pub async fn reconnect_server(
+        server: &str,
+        cfg: McpServerConfig,
+        options: &McpConnectOptions,
+    ) -> Result<ReconnectedServer> {
+        if !is_valid_mcp_server_name(server) {
+            return Err(anyhow!(
+                "invalid server name '{server}': must match pattern ^[a-zA-Z0-9_-]+$"
+            ));
+        }
+        let naming = ToolNaming::from_config(&cfg);
+        let client = connect_server(server, cfg, options).await?;
+        let listed = client
+            .client
+            .list_tools(None, Some(client.startup_timeout))
+            .await
+            .with_context(|| format!("listing tools failed for `{server}`"))?;
+        let tools = listed
+            .tools
+            .into_iter()
+            .filter(|tool| naming.filter.allows(&tool.name))
+            .map(|tool| ToolInfo {
+                server_name: server.to_string(),
+                tool_name: tool.name.clone(),
+                tool,
+            })
+            .collect();
+        Ok(ReconnectedServer {
+            client,
+            tools,
+            naming,
+        })
+    }
+
+    /// Install `reconnected` in place of the client of `server`, with its
+    /// freshly listed tools. Returns the number of tools, or `None` when
+    /// `server` is no longer managed, e.g. because the connections of an idle
+    /// session were released in the meantime.
+    pub fn replace_server(
+        &mut self,
+        server: &str,
+        reconnected: ReconnectedServer,
+    ) -> Option<usize> {
+        let managed = self.clients.get_mut(server)?;
+        *managed = reconnected.client;
+        let count = reconnected.tools.len();
+        self.replace_tools(server, reconnected.tools);
+        Some(count)
+    }
+
+    /// Install `connected` as the client of `server`, replacing any client
+    /// it had, with its tools named per its current configuration. Returns
+    /// the number of tools.
+    pub fn insert_server(&mut self, server: &str, connected: ReconnectedServer) -> usize {
+        self.clients.insert(server.to_string(), connected.client);
+        self.naming.insert(server.to_string(), connected.naming);
+        let count = connected.tools.len();
+        self.replace_tools(server, connected.tools);
+        count
+    }
+
+    /// Disconnect `server` and drop its tools. Returns whether it was managed.
+    pub fn remove_server(&mut self, server: &str) -> bool {
+        if self.clients.remove(server).is_none() {
+            return false;
+        }
+        self.naming.remove(server);
+        self.replace_tools(server, Vec::new());
+        true
+    }
+
+    fn replace_tools(&mut self, server: &str, tools: Vec<ToolInfo>) {
+        let mut all_tools: Vec<ToolInfo> = std::mem::take(&mut self.tools)
+            .into_values()
+            .filter(|tool| tool.server_name != server)
+            .collect();
+        all_tools.extend(tools);
+        self.tools = qualify_tools(all_tools, &self.naming);
+    }
+
+    /// Ping every server concurrently. Returns the servers that failed to
+    /// answer within `timeout`, with the reason.
+    pub async fn ping_all(&self, timeout: Duration) -> Vec<(String, anyhow::Error)> {
+        let mut join_set = JoinSet::new();
+        for (server, managed) in &self.clients {
+            let server = server.clone();
+            let client = managed.client.clone();
+            join_set.spawn(async move {
+                let res = client.ping(Some(timeout)).await;
+                (server, res)
+            });
+        }
+
+        let mut failures = Vec::new();
+        while let Some(res) = join_set.join_next().await {
+            match res {
+                Ok((_, Ok(()))) => {}
+                Ok((server, Err(e))) => failures.push((server, e)),
+                Err(e) => warn!("Task panic when pinging MCP server: {e:#}"),
+            }
+        }
+        failures.sort_by(|a, b| a.0.cmp(&b.0));
+        failures
+    }
 
-        Ok((Self { clients, tools }, errors))
+    /// Number of connected servers.
+    pub fn server_count(&self) -> usize {
+        self.clients.len()
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
@@ -321,24 +645,72 @@ impl McpConnectionManager {
             .collect()
     }
 
-    /// Invoke the tool indicated by the (server, tool) pair.
+    /// Invoke the tool indicated by the (server, tool) pair. Waiting for a
+    /// free slot of a server at its `max_concurrent_calls` counts against
+    /// the timeout. The progress the server reports is passed to
+    /// `on_progress`.
     pub async fn call_tool(
         &self,
         server: &str,
         tool: &str,
         arguments: Option<serde_json::Value>,
-    ) -> Result<mcp_types::CallToolResult> {
+        timeout: Option<Duration>,
+        on_progress: ProgressHandler,
+    ) -> Result<mcp_types::CallToolResult, McpCallError> {
         let managed = self
             .clients
             .get(server)
-            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+            .ok_or_else(|| McpCallError::UnknownServer(server.to_string()))?;
         let client = managed.client.clone();
-        let timeout = managed.tool_timeout;
+        let timeout = timeout.or(managed.tool_timeout);
+        let start = Instant::now();
+
+        let _permit = match &managed.call_slots {
+            Some((limit, slots)) => {
+                let busy = || McpCallError::Busy {
+                    server: server.to_string(),
+                    limit: *limit,
+                };
+                let acquired = match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, slots.acquire())
+                        .await
+                        .map_err(|_| busy())?,
+                    None => slots.acquire().await,
+                };
+                Some(acquired.map_err(|_| busy())?)
+            }
+            None => None,
+        };
 
-        client
-            .call_tool(tool.to_string(), arguments, timeout)
+        let remaining = timeout.map(|timeout| timeout.saturating_sub(start.elapsed()));
+        let result = client
+            .call_tool(tool.to_string(), arguments, remaining, on_progress)
             .await
-            .with_context(|| format!("tool call failed for `{server}/{tool}`"))
+            .map_err(|error| match timeout {
+                Some(timeout) if start.elapsed() >= timeout => McpCallError::TimedOut {
+                    server: server.to_string(),
+                    tool: tool.to_string(),
+                    timeout,
+                },
+                _ => McpCallError::Failed {
+                    server: server.to_string(),
+                    tool: tool.to_string(),
+                    error,
+                },
+            })?;
+
+        if let Some(limit) = managed.max_result_bytes {
+            let size = serde_json::to_vec(&result).map_or(0, |bytes| bytes.len());
+            if size > limit {
+                return Err(McpCallError::ResultTooLarge {
+                    server: server.to_string(),
+                    tool: tool.to_string(),
+                    size,
+                    limit,
+                });
+            }
+        }
+        Ok(result)
     }
 
     pub fn parse_tool_name(&self, tool_name: &str) -> Option<(String, String)> {
@@ -346,6 +718,279 @@ impl McpConnectionManager {
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Names of the servers that offer resources, sorted.
+    pub fn resource_servers(&self) -> Vec<String> {
+        let mut servers: Vec<String> = self
+            .clients
+            .iter()
+            .filter(|(_, managed)| managed.supports_resources)
+            .map(|(name, _)| name.clone())
+            .collect();
+        servers.sort();
+        servers
+    }
+
+    /// The resources `server` offers, following `nextCursor` for at most
+    /// [`MAX_RESOURCE_PAGES`] pages.
+    pub async fn list_resources(&self, server: &str) -> Result<Vec<Resource>> {
+        let managed = self.resource_client(server)?;
+        let mut resources = Vec::new();
+        let mut cursor = None;
+        for _ in 0..MAX_RESOURCE_PAGES {
+            let params = cursor.take().map(|cursor| ListResourcesRequestParams {
+                cursor: Some(cursor),
+            });
+            let page = managed
+                .client
+                .list_resources(params, managed.tool_timeout)
+                .await
+                .with_context(|| format!("listing resources failed for `{server}`"))?;
+            resources.extend(page.resources);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        Ok(resources)
+    }
+
+    /// Read the resource at `uri` from `server`.
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<ReadResourceResult> {
+        let managed = self.resource_client(server)?;
+        let params = ReadResourceRequestParams {
+            uri: uri.to_string(),
+        };
+        managed
+            .client
+            .read_resource(params, managed.tool_timeout)
+            .await
+            .with_context(|| format!("reading resource `{uri}` failed for `{server}`"))
+    }
+
+    /// The prompts of every server that offers them, sorted by server and
+    /// prompt name. Servers whose listing fails are skipped with a warning.
+    pub async fn list_all_prompts(&self) -> Vec<(String, Prompt)> {
+        let mut servers: Vec<(&String, &ManagedClient)> = self
+            .clients
+            .iter()
+            .filter(|(_, managed)| managed.supports_prompts)
+            .collect();
+        servers.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut prompts = Vec::new();
+        for (server, managed) in servers {
+            match list_server_prompts(managed).await {
+                Ok(listed) => {
+                    let mut listed: Vec<(String, Prompt)> = listed
+                        .into_iter()
+                        .map(|prompt| (server.clone(), prompt))
+                        .collect();
+                    listed.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+                    prompts.extend(listed);
+                }
+                Err(e) => warn!("listing prompts failed for `{server}`: {e:#}"),
+            }
+        }
+        prompts
+    }
+
+    /// Expand the prompt `name` of `server` with `arguments`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<GetPromptResult> {
+        let managed = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        if !managed.supports_prompts {
+            return Err(anyhow!("MCP server '{server}' does not offer prompts"));
+        }
+        let params = GetPromptRequestParams {
+            arguments,
+            name: name.to_string(),
+        };
+        managed
+            .client
+            .get_prompt(params, managed.tool_timeout)
+            .await
+            .with_context(|| format!("getting prompt `{name}` failed for `{server}`"))
+    }
+
+    /// Tell every server that the roots changed so it lists them again.
+    pub async fn notify_roots_list_changed(&self) {
+        for (server, managed) in &self.clients {
+            if let Err(e) = managed.client.notify_roots_list_changed().await {
+                warn!("notifying `{server}` of changed roots failed: {e:#}");
+            }
+        }
+    }
+
+    fn resource_client(&self, server: &str) -> Result<&ManagedClient> {
+        let managed = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        if !managed.supports_resources {
+            return Err(anyhow!("MCP server '{server}' does not offer resources"));
+        }
+        Ok(managed)
+    }
+}
+
+/// Start the client of `server_name` and initialize it.
+async fn connect_server(
+    server_name: &str,
+    cfg: McpServerConfig,
+    options: &McpConnectOptions,
+) -> Result<ManagedClient> {
+    let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+    let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
+    let sampling_handler = options
+        .sampler
+        .as_ref()
+        .map(|sampler| sampler.handler(server_name.to_string()));
+    let roots_handler = options.roots.handler();
+    let elicitation_handler = options.elicitor.handler(server_name.to_string());
+    let call_slots = cfg.max_concurrent_calls.map(|limit| {
+        (
+            limit,
+            Arc::new(Semaphore::new(limit.min(Semaphore::MAX_PERMITS))),
+        )
+    });
+    let max_result_bytes = cfg.max_result_bytes;
+
+    let McpServerConfig { transport, .. } = cfg;
+    let params = mcp_types::InitializeRequestParams {
+        capabilities: ClientCapabilities {
+            experimental: None,
+            roots: Some(ClientCapabilitiesRoots {
+                list_changed: Some(true),
+            }),
+            sampling: sampling_handler.as_ref().map(|_| json!({})),
+            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+            // indicates this should be an empty object.
+            elicitation: Some(json!({})),
+        },
+        client_info: Implementation {
+            name: "codex-mcp-client".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            title: Some("Codex".into()),
+            // This field is used by Codex when it is an MCP
+            // server: it should not be used when Codex is
+            // an MCP client.
+            user_agent: None,
+        },
+        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+    };
+
+    let (client, initialized) = match transport {
+        McpServerTransportConfig::Stdio { command, args, env } => {
+            let command_os: OsString = command.into();
+            let args_os: Vec<OsString> = args.into_iter().map(Into::into).collect();
+            McpClientAdapter::new_stdio_client(
+                options.use_rmcp_client,
+                command_os,
+                args_os,
+                env,
+                params,
+                startup_timeout,
+                sampling_handler,
+                roots_handler,
+                elicitation_handler,
+            )
+            .await?
+        }
+        // Only the rmcp client speaks streamable HTTP, so it is used for
+        // these servers whatever `use_rmcp_client` says.
+        McpServerTransportConfig::StreamableHttp {
+            url,
+            bearer_token,
+            bearer_token_env_var,
+            http_headers,
+        } => {
+            let token = match resolve_bearer_token(bearer_token, bearer_token_env_var.as_deref())? {
+                Some(token) => Some(token),
+                None => {
+                    oauth_access_token(&options.codex_home, options.oauth_store, server_name, &url)
+                        .await?
+                }
+            };
+            let authenticated = token.is_some();
+            McpClientAdapter::new_streamable_http_client(
+                url,
+                token,
+                http_headers,
+                params,
+                startup_timeout,
+                sampling_handler,
+                roots_handler,
+                elicitation_handler,
+            )
+            .await
+            .map_err(|e| {
+                if authenticated {
+                    e
+                } else {
+                    e.context(format!(
+                        "if the server requires authorization, run `codex mcp login {server_name}`"
+                    ))
+                }
+            })?
+        }
+    };
+
+    let capabilities = &initialized.capabilities;
+    Ok(ManagedClient {
+        client,
+        startup_timeout,
+        tool_timeout: Some(tool_timeout),
+        supports_resources: capabilities.resources.is_some(),
+        supports_prompts: capabilities.prompts.is_some(),
+        call_slots,
+        max_result_bytes,
+    })
+}
+
+/// The bearer token of a streamable HTTP server: the value of
+/// `bearer_token_env_var` when that is set, otherwise `bearer_token`.
+fn resolve_bearer_token(
+    bearer_token: Option<String>,
+    bearer_token_env_var: Option<&str>,
+) -> Result<Option<String>> {
+    let Some(env_var) = bearer_token_env_var else {
+        return Ok(bearer_token);
+    };
+    match std::env::var(env_var) {
+        Ok(token) if !token.is_empty() => Ok(Some(token)),
+        _ => Err(anyhow!(
+            "environment variable `{env_var}` with the bearer token is not set"
+        )),
+    }
+}
+
+/// Follow `nextCursor` of `prompts/list` for at most [`MAX_PROMPT_PAGES`] pages.
+async fn list_server_prompts(managed: &ManagedClient) -> Result<Vec<Prompt>> {
+    let mut prompts = Vec::new();
+    let mut cursor = None;
+    for _ in 0..MAX_PROMPT_PAGES {
+        let params = cursor.take().map(|cursor| ListPromptsRequestParams {
+            cursor: Some(cursor),
+        });
+        let page = managed
+            .client
+            .list_prompts(params, managed.tool_timeout)
+            .await?;
+        prompts.extend(page.prompts);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    Ok(prompts)
 }
 
 /// Query every server for its available tools and return a single map that
@@ -413,6 +1058,7 @@ fn is_valid_mcp_server_name(server_name: &str) -> bool {
 mod tests {
     use super::*;
     use mcp_types::ToolInputSchema;
+    use pretty_assertions::assert_eq;
 
     fn create_test_tool(server_name: &str, tool_name: &str) -> ToolInfo {
         ToolInfo {
@@ -440,7 +1086,7 @@ mod tests {
             create_test_tool("server1", "tool2"),
         ];
 
-        let qualified_tools = qualify_tools(tools);
+        let qualified_tools = qualify_tools(tools, &HashMap::new());
 
         assert_eq!(qualified_tools.len(), 2);
         assert!(qualified_tools.contains_key("server1__tool1"));
@@ -454,7 +1100,7 @@ mod tests {
             create_test_tool("server1", "duplicate_tool"),
         ];
 
-        let qualified_tools = qualify_tools(tools);
+        let qualified_tools = qualify_tools(tools, &HashMap::new());
 
         // Only the first tool should remain, the second is skipped
         assert_eq!(qualified_tools.len(), 1);
@@ -476,7 +1122,7 @@ mod tests {
             ),
         ];
 
-        let qualified_tools = qualify_tools(tools);
+        let qualified_tools = qualify_tools(tools, &HashMap::new());
 
         assert_eq!(qualified_tools.len(), 2);
 
@@ -495,4 +1141,129 @@ mod tests {
             "my_server__yet_another_e1c3987bd9c50b826cbe1687966f79f0c602d19ca"
         );
     }
+
+    #[test]
+    fn test_qualify_tools_applies_prefix_and_aliases() {
+        let tools = vec![
+            create_test_tool("github", "search_repositories"),
+            create_test_tool("github", "get_issue"),
+            create_test_tool("linear", "get_issue"),
+            create_test_tool("docs", "search"),
+        ];
+        let naming = HashMap::from([
+            (
+                "github".to_string(),
+                ToolNaming {
+                    prefix: Some("gh".to_string()),
+                    aliases: HashMap::from([(
+                        "search_repositories".to_string(),
+                        "repo_search".to_string(),
+                    )]),
+                    filter: ToolFilter::default(),
+                },
+            ),
+            (
+                "docs".to_string(),
+                ToolNaming {
+                    prefix: Some(String::new()),
+                    aliases: HashMap::new(),
+                    filter: ToolFilter::default(),
+                },
+            ),
+        ]);
+
+        let qualified_tools = qualify_tools(tools, &naming);
+
+        let mut names: Vec<(&str, &str, &str)> = qualified_tools
+            .iter()
+            .map(|(name, tool)| {
+                (
+                    name.as_str(),
+                    tool.server_name.as_str(),
+                    tool.tool_name.as_str(),
+                )
+            })
+            .collect();
+        names.sort();
+        assert_eq!(
+            vec![
+                ("gh__get_issue", "github", "get_issue"),
+                ("linear__get_issue", "linear", "get_issue"),
+                ("repo_search", "github", "search_repositories"),
+                ("search", "docs", "search"),
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn test_qualify_tools_colliding_names_fall_back_to_canonical() {
+        let tools = vec![
+            create_test_tool("bing", "search"),
+            create_test_tool("brave", "search"),
+            create_test_tool("docs", "lookup"),
+        ];
+        let bare = ToolNaming {
+            prefix: Some(String::new()),
+            aliases: HashMap::new(),
+            filter: ToolFilter::default(),
+        };
+        let naming = HashMap::from([
+            ("bing".to_string(), bare.clone()),
+            ("brave".to_string(), bare),
+            (
+                "docs".to_string(),
+                ToolNaming {
+                    prefix: None,
+                    aliases: HashMap::from([("lookup".to_string(), "search".to_string())]),
+                    filter: ToolFilter::default(),
+                },
+            ),
+        ]);
+
+        let qualified_tools = qualify_tools(tools, &naming);
+
+        let mut names: Vec<&str> = qualified_tools.keys().map(String::as_str).collect();
+        names.sort();
+        // The alias wins the bare name; the others keep their canonical names.
+        assert_eq!(vec!["bing__search", "brave__search", "search"], names);
+        assert_eq!("docs", qualified_tools["search"].server_name);
+    }
+
+    #[test]
+    fn test_qualify_tools_applies_tool_filters() {
+        let tools = vec![
+            create_test_tool("github", "get_issue"),
+            create_test_tool("github", "search_issues"),
+            create_test_tool("github", "create_issue"),
+            create_test_tool("github", "delete_repo"),
+        ];
+        let naming = HashMap::from([(
+            "github".to_string(),
+            ToolNaming {
+                prefix: None,
+                aliases: HashMap::new(),
+                filter: ToolFilter::new(
+                    Some(&["get_*".to_string(), "search_*".to_string()]),
+                    &["search_issues".to_string()],
+                ),
+            },
+        )]);
+
+        let qualified_tools = qualify_tools(tools, &naming);
+
+        let names: Vec<&str> = qualified_tools.keys().map(String::as_str).collect();
+        assert_eq!(vec!["github__get_issue"], names);
+    }
+
+    #[test]
+    fn bearer_token_env_var_must_be_set() {
+        assert_eq!(
+            Some("plain".to_string()),
+            resolve_bearer_token(Some("plain".to_string()), None).expect("token")
+        );
+        assert!(
+            resolve_bearer_token(None, Some("CODEX_TEST_MCP_TOKEN_THAT_IS_NEVER_SET")).is_err()
+        );
+    }
 }
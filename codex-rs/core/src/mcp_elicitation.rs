@@ -0,0 +1,227 @@
+//! Elicitation: MCP servers asking the user for structured input with
+//! `elicitation/create`, usually in the middle of one of their tool calls.
+//! Requests are forwarded to the client as `EventMsg::ElicitationRequest` and
+//! answered with `Op::ResolveElicitation`.
+//!
+//! A request is only forwarded while the session is working on a turn whose
+//! user can be asked, i.e. the client answers interactive requests and the
+//! approval policy is not `never`; otherwise it is declined. Requests still
+//! waiting when the turn is aborted are cancelled.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::Weak;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use codex_mcp_client::ElicitationHandler;
+use mcp_types::ElicitRequestParams;
+use mcp_types::ElicitResult;
+use tokio::sync::oneshot;
+
+use crate::codex::Session;
+use crate::protocol::AskForApproval;
+use crate::protocol::ElicitationAction;
+use crate::protocol::ElicitationRequestEvent;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+
+/// Forwards the elicitation requests of a session's MCP servers to its
+/// client. It is created before the MCP servers are started and bound to the
+/// session afterwards.
+pub(crate) struct McpElicitor {
+    next_id: AtomicU64,
+    session: StdMutex<Option<Weak<Session>>>,
+    /// Submission id and approval policy of the latest turn; requests are
+    /// reported under the id.
+    turn: StdMutex<Option<(String, AskForApproval)>>,
+    /// Request id -> where the client's answer goes.
+    pending: StdMutex<HashMap<String, oneshot::Sender<ElicitResult>>>,
+}
+
+impl McpElicitor {
+    pub(crate) fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(0),
+            session: StdMutex::new(None),
+            turn: StdMutex::new(None),
+            pending: StdMutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn set_session(&self, session: Weak<Session>) {
+        if let Ok(mut guard) = self.session.lock() {
+            *guard = Some(session);
+        }
+    }
+
+    /// Record the turn that requests are reported under.
+    pub(crate) fn set_turn(&self, sub_id: String, approval_policy: AskForApproval) {
+        if let Ok(mut guard) = self.turn.lock() {
+            *guard = Some((sub_id, approval_policy));
+        }
+    }
+
+    /// The handler given to the client of the MCP server `server`.
+    pub(crate) fn handler(self: &Arc<Self>, server: String) -> ElicitationHandler {
+        let elicitor = Arc::clone(self);
+        Arc::new(move |params| {
+            let elicitor = Arc::clone(&elicitor);
+            let server = server.clone();
+            Box::pin(async move { elicitor.elicit(&server, params).await })
+        })
+    }
+
+    async fn elicit(
+        &self,
+        server: &str,
+        params: ElicitRequestParams,
+    ) -> Result<ElicitResult, String> {
+        let session = self
+            .session
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().and_then(Weak::upgrade))
+            .ok_or_else(|| "the Codex session has ended".to_string())?;
+        let Some((sub_id, approval_policy)) = self.turn.lock().ok().and_then(|guard| guard.clone())
+        else {
+            return Ok(answer(ElicitationAction::Decline, None));
+        };
+        let approval_policy = session
+            .client_capabilities()
+            .effective_approval_policy(approval_policy);
+        if approval_policy == AskForApproval::Never || session.active_turn.lock().await.is_none() {
+            return Ok(answer(ElicitationAction::Decline, None));
+        }
+
+        let id = format!(
+            "elicitation-{}",
+            self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+        );
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id.clone(), tx);
+        }
+        session
+            .send_event(Event {
+                id: sub_id,
+                msg: EventMsg::ElicitationRequest(ElicitationRequestEvent {
+                    id,
+                    server: server.to_string(),
+                    message: params.message,
+                    requested_schema: serde_json::to_value(params.requested_schema)
+                        .unwrap_or_default(),
+                }),
+            })
+            .await;
+        Ok(rx
+            .await
+            .unwrap_or_else(|_| answer(ElicitationAction::Cancel, None)))
+    }
+
+    /// Answer the request `id` with the client's decision. Returns false when
+    /// no such request is waiting.
+    pub(crate) fn resolve(
+        &self,
+        id: &str,
+        action: ElicitationAction,
+        content: Option<serde_json::Value>,
+    ) -> bool {
+        let Some(tx) = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(id))
+        else {
+            return false;
+        };
+        tx.send(answer(action, content)).is_ok()
+    }
+
+    /// Cancel every request still waiting for an answer.
+    pub(crate) fn cancel_all(&self) {
+        let pending = self
+            .pending
+            .lock()
+            .map(|mut pending| std::mem::take(&mut *pending))
+            .unwrap_or_default();
+        for (_, tx) in pending {
+            let _ = tx.send(answer(ElicitationAction::Cancel, None));
+        }
+    }
+}
+
+/// The result sent to the server. Content only goes with an accepted request.
+fn answer(action: ElicitationAction, content: Option<serde_json::Value>) -> ElicitResult {
+    let content = match action {
+        ElicitationAction::Accept => content,
+        ElicitationAction::Decline | ElicitationAction::Cancel => None,
+    };
+    ElicitResult {
+        action: action.to_string(),
+        content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn waiting(elicitor: &McpElicitor, id: &str) -> oneshot::Receiver<ElicitResult> {
+        let (tx, rx) = oneshot::channel();
+        elicitor
+            .pending
+            .lock()
+            .expect("pending lock")
+            .insert(id.to_string(), tx);
+        rx
+    }
+
+    #[test]
+    fn resolve_answers_the_waiting_request() {
+        let elicitor = McpElicitor::new();
+        let mut accepted = waiting(&elicitor, "elicitation-1");
+        let mut declined = waiting(&elicitor, "elicitation-2");
+
+        assert!(elicitor.resolve(
+            "elicitation-1",
+            ElicitationAction::Accept,
+            Some(json!({"name": "codex"}))
+        ));
+        assert!(elicitor.resolve(
+            "elicitation-2",
+            ElicitationAction::Decline,
+            Some(json!({"name": "ignored"}))
+        ));
+        assert!(!elicitor.resolve("elicitation-1", ElicitationAction::Accept, None));
+
+        assert_eq!(
+            accepted.try_recv().expect("accepted answer"),
+            ElicitResult {
+                action: "accept".to_string(),
+                content: Some(json!({"name": "codex"})),
+            }
+        );
+        assert_eq!(
+            declined.try_recv().expect("declined answer"),
+            ElicitResult {
+                action: "decline".to_string(),
+                content: None,
+            }
+        );
+    }
+
+    #[test]
+    fn cancel_all_cancels_waiting_requests() {
+        let elicitor = McpElicitor::new();
+        let mut rx = waiting(&elicitor, "elicitation-1");
+
+        elicitor.cancel_all();
+
+        assert_eq!(rx.try_recv().expect("answer").action, "cancel");
+        assert!(!elicitor.resolve("elicitation-1", ElicitationAction::Accept, None));
+    }
+}
@@ -0,0 +1,194 @@
+//! Health monitoring of MCP servers. Every `[mcp_health].check_interval_sec`
+//! the connected servers are pinged; one that does not answer, because its
+//! process died or hangs, is reported with `EventMsg::McpServerHealth` and
+//! reconnected with exponential backoff. A reconnected server has its tools
+//! listed again, so the next turn sees them; one that cannot be reconnected is
+//! dropped with its tools instead of failing every call.
+
+use std::sync::Arc;
+use std::sync::Weak;
+use std::time::Duration;
+
+use futures::future::join_all;
+use tracing::info;
+use tracing::warn;
+
+use crate::codex::INITIAL_SUBMIT_ID;
+use crate::codex::Session;
+use crate::config::Config;
+use crate::config_types::McpHealth;
+use crate::mcp_connection_manager::McpConnectionManager;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::McpServerHealthEvent;
+use crate::protocol::McpServerHealthStatus;
+
+/// Delay before the second reconnect attempt; it doubles for every further
+/// attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Start monitoring the MCP servers of `sess` in the background. The monitor
+/// stops once the session is dropped.
+pub(crate) fn spawn_mcp_health_monitor(sess: &Arc<Session>, config: Arc<Config>) {
+    let settings = config.mcp_health.clone();
+    // Servers can be connected later with `Op::ConnectMcpServer`, so the
+    // monitor runs even when none are configured.
+    if !settings.enabled {
+        return;
+    }
+    let sess = Arc::downgrade(sess);
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(settings.check_interval_sec.max(1)));
+        // The first tick completes immediately; the servers were just started.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let Some(session) = sess.upgrade() else {
+                break;
+            };
+            let failures = session
+                .mcp_connection_manager()
+                .read()
+                .await
+                .ping_all(Duration::from_secs(settings.ping_timeout_sec))
+                .await;
+            drop(session);
+            join_all(failures.into_iter().map(|(server, error)| {
+                reconnect(&sess, &config, &settings, server, format!("{error:#}"))
+            }))
+            .await;
+        }
+    });
+}
+
+/// Reconnect `server` with backoff, reporting the outcome.
+async fn reconnect(
+    sess: &Weak<Session>,
+    config: &Config,
+    settings: &McpHealth,
+    server: String,
+    error: String,
+) {
+    let Some(session) = sess.upgrade() else {
+        return;
+    };
+    let Some(cfg) = session.mcp_server_config(&server).await else {
+        return;
+    };
+    drop(session);
+    warn!("MCP server `{server}` is not responding: {error}");
+    send_health_event(sess, &server, McpServerHealthStatus::Unresponsive { error }).await;
+
+    let mut last_error = String::new();
+    let max_attempts = settings.max_reconnect_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        let delay = backoff_delay(attempt, Duration::from_secs(settings.max_backoff_sec));
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let Some(session) = sess.upgrade() else {
+            return;
+        };
+        let options = session.mcp_connect_options(config);
+        match McpConnectionManager::reconnect_server(&server, cfg.clone(), &options).await {
+            Ok(reconnected) => {
+                let tools = session
+                    .mcp_connection_manager()
+                    .write()
+                    .await
+                    .replace_server(&server, reconnected);
+                // `None` means the connections were released while the
+                // session was idle; they are restored on the next request.
+                if let Some(tools) = tools {
+                    info!("reconnected MCP server `{server}` after {attempt} attempt(s)");
+                    send_health_event(
+                        sess,
+                        &server,
+                        McpServerHealthStatus::Reconnected {
+                            attempts: attempt,
+                            tools: tools as u64,
+                        },
+                    )
+                    .await;
+                }
+                return;
+            }
+            Err(e) => {
+                warn!("reconnecting MCP server `{server}` failed (attempt {attempt}): {e:#}");
+                last_error = format!("{e:#}");
+            }
+        }
+    }
+
+    let Some(session) = sess.upgrade() else {
+        return;
+    };
+    if session
+        .mcp_connection_manager()
+        .write()
+        .await
+        .remove_server(&server)
+    {
+        drop(session);
+        send_health_event(
+            sess,
+            &server,
+            McpServerHealthStatus::Failed {
+                attempts: max_attempts,
+                error: last_error,
+            },
+        )
+        .await;
+    }
+}
+
+async fn send_health_event(sess: &Weak<Session>, server: &str, status: McpServerHealthStatus) {
+    let Some(session) = sess.upgrade() else {
+        return;
+    };
+    session
+        .send_event(Event {
+            id: INITIAL_SUBMIT_ID.to_string(),
+            msg: EventMsg::McpServerHealth(McpServerHealthEvent {
+                server: server.to_string(),
+                status,
+            }),
+        })
+        .await;
+}
+
+/// Delay before reconnect attempt `attempt` (1-based): none before the first,
+/// then [`INITIAL_BACKOFF`] doubling with every attempt, capped at `max`.
+fn backoff_delay(attempt: u32, max: Duration) -> Duration {
+    if attempt <= 1 {
+        return Duration::ZERO;
+    }
+    let factor = 2u32.saturating_pow(attempt - 2);
+    INITIAL_BACKOFF.saturating_mul(factor).min(max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let max = Duration::from_secs(5);
+        let delays: Vec<Duration> = (1..=6).map(|attempt| backoff_delay(attempt, max)).collect();
+
+        assert_eq!(
+            vec![
+                Duration::ZERO,
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(5),
+                Duration::from_secs(5),
+            ],
+            delays
+        );
+        assert_eq!(max, backoff_delay(u32::MAX, max));
+    }
+}
@@ -0,0 +1,175 @@
+//! Prompt templates offered by MCP servers. Frontends list them through
+//! `Op::ListMcpPrompts`, typically as slash commands, and expand one with
+//! `Op::GetMcpPrompt` into the text of the next user turn. The server
+//! substitutes the arguments.
+
+use std::collections::HashMap;
+
+use codex_protocol::custom_prompts::McpPrompt;
+use codex_protocol::custom_prompts::McpPromptArgument;
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::Prompt;
+
+use crate::mcp_connection_manager::McpConnectionManager;
+
+/// The prompts of every configured MCP server, in the form sent to frontends.
+pub(crate) async fn list_mcp_prompts(manager: &McpConnectionManager) -> Vec<McpPrompt> {
+    manager
+        .list_all_prompts()
+        .await
+        .into_iter()
+        .map(|(server, prompt)| to_protocol_prompt(server, prompt))
+        .collect()
+}
+
+/// Expand the prompt `name` of `server` into the text of a user turn.
+pub(crate) async fn expand_mcp_prompt(
+    manager: &McpConnectionManager,
+    server: &str,
+    name: &str,
+    arguments: HashMap<String, String>,
+) -> Result<String, String> {
+    let arguments = if arguments.is_empty() {
+        None
+    } else {
+        Some(serde_json::json!(arguments))
+    };
+    let result = manager
+        .get_prompt(server, name, arguments)
+        .await
+        .map_err(|e| format!("{e:#}"))?;
+    let text = prompt_text(result);
+    if text.is_empty() {
+        return Err(format!("MCP prompt `{name}` of `{server}` has no text"));
+    }
+    Ok(text)
+}
+
+fn to_protocol_prompt(server: String, prompt: Prompt) -> McpPrompt {
+    McpPrompt {
+        server,
+        name: prompt.name,
+        title: prompt.title,
+        description: prompt.description,
+        arguments: prompt
+            .arguments
+            .unwrap_or_default()
+            .into_iter()
+            .map(|argument| McpPromptArgument {
+                name: argument.name,
+                description: argument.description,
+                required: argument.required.unwrap_or(false),
+            })
+            .collect(),
+    }
+}
+
+/// The text of the prompt's messages separated by blank lines. Text embedded
+/// resources are inlined and resource links given by their URI; images, audio
+/// and other binary content are skipped because a turn's text cannot carry
+/// them.
+fn prompt_text(result: GetPromptResult) -> String {
+    let parts: Vec<String> = result
+        .messages
+        .into_iter()
+        .filter_map(|message| match message.content {
+            ContentBlock::TextContent(text) => Some(text.text),
+            ContentBlock::EmbeddedResource(embedded) => match embedded.resource {
+                EmbeddedResourceResource::TextResourceContents(contents) => Some(contents.text),
+                EmbeddedResourceResource::BlobResourceContents(_) => None,
+            },
+            ContentBlock::ResourceLink(link) => Some(link.uri),
+            ContentBlock::ImageContent(_) | ContentBlock::AudioContent(_) => None,
+        })
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    parts.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::EmbeddedResource;
+    use mcp_types::ImageContent;
+    use mcp_types::PromptArgument;
+    use mcp_types::PromptMessage;
+    use mcp_types::Role;
+    use mcp_types::TextContent;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    fn message(content: ContentBlock) -> PromptMessage {
+        PromptMessage {
+            content,
+            role: Role::User,
+        }
+    }
+
+    #[test]
+    fn prompt_text_joins_text_and_embedded_resources() {
+        let result = GetPromptResult {
+            description: None,
+            messages: vec![
+                message(ContentBlock::TextContent(TextContent {
+                    annotations: None,
+                    text: "Review the changes to src/lib.rs.".to_string(),
+                    r#type: "text".to_string(),
+                })),
+                message(ContentBlock::ImageContent(ImageContent {
+                    annotations: None,
+                    data: "AAAA".to_string(),
+                    mime_type: "image/png".to_string(),
+                    r#type: "image".to_string(),
+                })),
+                message(ContentBlock::EmbeddedResource(EmbeddedResource {
+                    annotations: None,
+                    resource: EmbeddedResourceResource::TextResourceContents(
+                        TextResourceContents {
+                            mime_type: None,
+                            text: "Style guide: prefer early returns.".to_string(),
+                            uri: "docs://style".to_string(),
+                        },
+                    ),
+                    r#type: "resource".to_string(),
+                })),
+            ],
+        };
+
+        assert_eq!(
+            "Review the changes to src/lib.rs.\n\nStyle guide: prefer early returns.",
+            prompt_text(result)
+        );
+    }
+
+    #[test]
+    fn converts_prompt_arguments() {
+        let prompt = Prompt {
+            arguments: Some(vec![PromptArgument {
+                description: Some("Path to review".to_string()),
+                name: "path".to_string(),
+                required: Some(true),
+                title: None,
+            }]),
+            description: Some("Review a file".to_string()),
+            name: "review".to_string(),
+            title: None,
+        };
+
+        assert_eq!(
+            McpPrompt {
+                server: "team".to_string(),
+                name: "review".to_string(),
+                title: None,
+                description: Some("Review a file".to_string()),
+                arguments: vec![McpPromptArgument {
+                    name: "path".to_string(),
+                    description: Some("Path to review".to_string()),
+                    required: true,
+                }],
+            },
+            to_protocol_prompt("team".to_string(), prompt)
+        );
+    }
+}
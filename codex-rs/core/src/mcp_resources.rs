@@ -0,0 +1,241 @@
+//! The `mcp_resources` tool, which lets the model list the resources that
+//! configured MCP servers expose, such as files or documentation pages, and
+//! pull one into the conversation when it needs it.
+//!
+//! Only text contents are returned, capped by `[mcp_resources].max_bytes`;
+//! binary contents are described but left out.
+
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Resource;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::config_types::McpResources;
+use crate::function_tool::FunctionCallError;
+use crate::mcp_connection_manager::McpConnectionManager;
+use crate::truncate::truncate_to_bytes;
+
+pub(crate) const MCP_RESOURCES_TOOL_NAME: &str = "mcp_resources";
+
+/// Maximum number of resources returned by a single `list`.
+const MAX_LISTED_RESOURCES: usize = 200;
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum McpResourcesArgs {
+    List {
+        #[serde(default)]
+        server: Option<String>,
+    },
+    Read {
+        server: String,
+        uri: String,
+    },
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ListedResource {
+    server: String,
+    uri: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<i64>,
+}
+
+impl ListedResource {
+    fn new(server: &str, resource: Resource) -> Self {
+        Self {
+            server: server.to_string(),
+            uri: resource.uri,
+            name: resource.name,
+            title: resource.title,
+            description: resource.description,
+            mime_type: resource.mime_type,
+            size: resource.size,
+        }
+    }
+}
+
+pub(crate) async fn handle_mcp_resources_tool(
+    manager: &McpConnectionManager,
+    config: &McpResources,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: McpResourcesArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    match args {
+        McpResourcesArgs::List { server } => list_resources(manager, server).await,
+        McpResourcesArgs::Read { server, uri } => {
+            let result = manager
+                .read_resource(&server, &uri)
+                .await
+                .map_err(|e| FunctionCallError::RespondToModel(format!("{e:#}")))?;
+            Ok(format_read_result(&server, &uri, result, config.max_bytes))
+        }
+    }
+}
+
+async fn list_resources(
+    manager: &McpConnectionManager,
+    server: Option<String>,
+) -> Result<String, FunctionCallError> {
+    let servers = match server {
+        Some(server) => vec![server],
+        None => manager.resource_servers(),
+    };
+    let listing_one = servers.len() == 1;
+
+    let mut resources = Vec::new();
+    let mut errors = Vec::new();
+    for server in &servers {
+        match manager.list_resources(server).await {
+            Ok(listed) => resources.extend(
+                listed
+                    .into_iter()
+                    .map(|resource| ListedResource::new(server, resource)),
+            ),
+            // A named server that fails is the answer; when listing every
+            // server the others are still worth returning.
+            Err(e) if listing_one => {
+                return Err(FunctionCallError::RespondToModel(format!("{e:#}")));
+            }
+            Err(e) => {
+                tracing::warn!("listing MCP resources failed: {e:#}");
+                errors.push(format!("{e:#}"));
+            }
+        }
+    }
+
+    let truncated = resources.len() > MAX_LISTED_RESOURCES;
+    resources.truncate(MAX_LISTED_RESOURCES);
+    let mut output = json!({
+        "servers": servers,
+        "resources": resources,
+        "truncated": truncated,
+    });
+    if !errors.is_empty() {
+        output["errors"] = json!(errors);
+    }
+    Ok(output.to_string())
+}
+
+/// Render the text contents of `result`, keeping at most `max_bytes` of text
+/// in total.
+fn format_read_result(
+    server: &str,
+    uri: &str,
+    result: ReadResourceResult,
+    max_bytes: usize,
+) -> String {
+    let mut remaining = max_bytes;
+    let mut truncated = false;
+    let contents: Vec<serde_json::Value> = result
+        .contents
+        .into_iter()
+        .map(|contents| match contents {
+            ReadResourceResultContents::TextResourceContents(text) => {
+                let (kept, cut) = truncate_to_bytes(&text.text, remaining);
+                // Once one text is cut, later ones are dropped rather than
+                // filling the leftover bytes.
+                remaining = if cut { 0 } else { remaining - kept.len() };
+                truncated |= cut;
+                json!({
+                    "uri": text.uri,
+                    "mime_type": text.mime_type,
+                    "text": kept,
+                })
+            }
+            ReadResourceResultContents::BlobResourceContents(blob) => json!({
+                "uri": blob.uri,
+                "mime_type": blob.mime_type,
+                "omitted": format!("binary contents ({} base64 bytes)", blob.blob.len()),
+            }),
+        })
+        .collect();
+    json!({
+        "server": server,
+        "uri": uri,
+        "contents": contents,
+        "truncated": truncated,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::BlobResourceContents;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_actions() {
+        assert_eq!(
+            McpResourcesArgs::List { server: None },
+            serde_json::from_str(r#"{"action":"list"}"#).expect("list parses")
+        );
+        assert_eq!(
+            McpResourcesArgs::Read {
+                server: "docs".to_string(),
+                uri: "docs://guide".to_string(),
+            },
+            serde_json::from_str(r#"{"action":"read","server":"docs","uri":"docs://guide"}"#)
+                .expect("read parses")
+        );
+        assert!(serde_json::from_str::<McpResourcesArgs>(r#"{"action":"read"}"#).is_err());
+    }
+
+    #[test]
+    fn read_result_is_capped_and_omits_blobs() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: Some("text/plain".to_string()),
+                    text: "héllo world".to_string(),
+                    uri: "file:///a.txt".to_string(),
+                }),
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: None,
+                    text: "more".to_string(),
+                    uri: "file:///b.txt".to_string(),
+                }),
+                ReadResourceResultContents::BlobResourceContents(BlobResourceContents {
+                    blob: "AAAA".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uri: "file:///c.png".to_string(),
+                }),
+            ],
+        };
+
+        let output: serde_json::Value =
+            serde_json::from_str(&format_read_result("fs", "file:///", result, 2))
+                .expect("output is JSON");
+
+        assert_eq!(
+            json!({
+                "server": "fs",
+                "uri": "file:///",
+                "contents": [
+                    {"uri": "file:///a.txt", "mime_type": "text/plain", "text": "h"},
+                    {"uri": "file:///b.txt", "mime_type": null, "text": ""},
+                    {
+                        "uri": "file:///c.png",
+                        "mime_type": "image/png",
+                        "omitted": "binary contents (4 base64 bytes)"
+                    },
+                ],
+                "truncated": true,
+            }),
+            output
+        );
+    }
+}
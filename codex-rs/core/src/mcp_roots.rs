@@ -0,0 +1,134 @@
+//! Roots: the directories a session works in, advertised to MCP servers so
+//! that filesystem servers scope themselves to the workspace. The roots are
+//! the working directory followed by the extra writable roots of a
+//! `workspace-write` sandbox. Servers are notified with
+//! `notifications/roots/list_changed` when a new turn context changes them.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+
+use codex_mcp_client::RootsHandler;
+use mcp_types::Root;
+use url::Url;
+
+use crate::protocol::SandboxPolicy;
+
+/// The roots currently advertised to a session's MCP servers.
+pub(crate) struct McpRoots {
+    roots: StdMutex<Vec<Root>>,
+}
+
+impl McpRoots {
+    pub(crate) fn new(roots: Vec<Root>) -> Self {
+        Self {
+            roots: StdMutex::new(roots),
+        }
+    }
+
+    /// The handler given to the MCP clients for `roots/list` requests.
+    pub(crate) fn handler(self: &Arc<Self>) -> RootsHandler {
+        let roots = Arc::clone(self);
+        Arc::new(move || roots.get())
+    }
+
+    fn get(&self) -> Vec<Root> {
+        self.roots
+            .lock()
+            .map(|roots| roots.clone())
+            .unwrap_or_default()
+    }
+
+    /// Replace the roots. Returns whether they changed.
+    pub(crate) fn update(&self, roots: Vec<Root>) -> bool {
+        let Ok(mut current) = self.roots.lock() else {
+            return false;
+        };
+        if *current == roots {
+            return false;
+        }
+        *current = roots;
+        true
+    }
+}
+
+/// The roots of a session working in `cwd` under `sandbox_policy`, without
+/// duplicates.
+pub(crate) fn workspace_roots(cwd: &Path, sandbox_policy: &SandboxPolicy) -> Vec<Root> {
+    let writable_roots = match sandbox_policy {
+        SandboxPolicy::WorkspaceWrite { writable_roots, .. } => writable_roots.as_slice(),
+        SandboxPolicy::DangerFullAccess | SandboxPolicy::ReadOnly => &[],
+    };
+    let mut roots: Vec<Root> = Vec::new();
+    for path in std::iter::once(cwd).chain(writable_roots.iter().map(PathBuf::as_path)) {
+        if let Some(root) = root_for(path)
+            && !roots.iter().any(|existing| existing.uri == root.uri)
+        {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// A root for the directory `path`, named after its last component. Relative
+/// paths cannot be given as `file://` URIs and are skipped.
+fn root_for(path: &Path) -> Option<Root> {
+    let uri = Url::from_directory_path(path).ok()?;
+    Some(Root {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned()),
+        uri: uri.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn root(name: &str, uri: &str) -> Root {
+        Root {
+            name: Some(name.to_string()),
+            uri: uri.to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn roots_are_cwd_then_writable_roots() {
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![
+                PathBuf::from("/work/shared lib"),
+                PathBuf::from("/work/app"),
+                PathBuf::from("relative"),
+            ],
+            network_access: false,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        };
+
+        assert_eq!(
+            vec![
+                root("app", "file:///work/app/"),
+                root("shared lib", "file:///work/shared%20lib/"),
+            ],
+            workspace_roots(Path::new("/work/app"), &policy)
+        );
+        assert_eq!(
+            vec![root("app", "file:///work/app/")],
+            workspace_roots(Path::new("/work/app"), &SandboxPolicy::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn update_reports_changes() {
+        let roots = McpRoots::new(vec![root("app", "file:///work/app/")]);
+
+        assert!(!roots.update(vec![root("app", "file:///work/app/")]));
+        assert!(roots.update(vec![root("lib", "file:///work/lib/")]));
+        assert_eq!(vec![root("lib", "file:///work/lib/")], roots.get());
+    }
+}
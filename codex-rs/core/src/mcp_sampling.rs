@@ -0,0 +1,395 @@
+//! Sampling: MCP servers asking the client for a completion with
+//! `sampling/createMessage`. With `[mcp_sampling] enabled = true` the
+//! requests are answered by a separate call to the model, without the
+//! conversation's history or tools.
+//!
+//! A request is only answered while the session is working on a turn, since
+//! that is when a server can be acting on the model's behalf. It is approved
+//! like a command under the turn's approval policy, refused once the session
+//! budget or `max_requests` is used up, and never sees the conversation.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::sync::Weak;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use codex_mcp_client::SamplingHandler;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use futures::prelude::*;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
+use mcp_types::CreateMessageResultContent;
+use mcp_types::Role;
+use mcp_types::SamplingMessage;
+use mcp_types::SamplingMessageContent;
+use mcp_types::TextContent;
+
+use crate::Prompt;
+use crate::client::ModelClient;
+use crate::client_common::ResponseEvent;
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::codex::compact::content_items_to_text;
+use crate::config_types::McpSampling;
+use crate::model_family::find_family_for_model;
+use crate::protocol::AskForApproval;
+use crate::protocol::ReviewDecision;
+use crate::truncate::truncate_middle;
+
+/// Instructions used when the server does not send a system prompt.
+const DEFAULT_SAMPLING_INSTRUCTIONS: &str = "You answer requests sent by a tool \
+that assists a coding agent. Reply to the last message directly and concisely.";
+
+/// Bytes of the request shown in the approval prompt.
+const MAX_REASON_EXCERPT_BYTES: usize = 300;
+
+/// Answers the sampling requests of a session's MCP servers. It is created
+/// before the MCP servers are started and bound to the session afterwards.
+pub(crate) struct McpSampler {
+    settings: McpSampling,
+    answered: AtomicU32,
+    next_call_id: AtomicU64,
+    session: StdMutex<Option<Weak<Session>>>,
+    /// Submission id and context of the latest turn.
+    turn: StdMutex<Option<(String, Arc<TurnContext>)>>,
+    /// Servers whose requests the user approved for the rest of the session.
+    approved_servers: StdMutex<HashSet<String>>,
+}
+
+impl McpSampler {
+    pub(crate) fn new(settings: McpSampling) -> Self {
+        Self {
+            settings,
+            answered: AtomicU32::new(0),
+            next_call_id: AtomicU64::new(0),
+            session: StdMutex::new(None),
+            turn: StdMutex::new(None),
+            approved_servers: StdMutex::new(HashSet::new()),
+        }
+    }
+
+    pub(crate) fn set_session(&self, session: Weak<Session>) {
+        if let Ok(mut guard) = self.session.lock() {
+            *guard = Some(session);
+        }
+    }
+
+    /// Record the turn that requests are approved and answered under.
+    pub(crate) fn set_turn(&self, sub_id: String, turn_context: Arc<TurnContext>) {
+        if let Ok(mut guard) = self.turn.lock() {
+            *guard = Some((sub_id, turn_context));
+        }
+    }
+
+    /// The handler given to the client of the MCP server `server`.
+    pub(crate) fn handler(self: &Arc<Self>, server: String) -> SamplingHandler {
+        let sampler = Arc::clone(self);
+        Arc::new(move |params| {
+            let sampler = Arc::clone(&sampler);
+            let server = server.clone();
+            Box::pin(async move { sampler.create_message(&server, params).await })
+        })
+    }
+
+    async fn create_message(
+        &self,
+        server: &str,
+        params: CreateMessageRequestParams,
+    ) -> Result<CreateMessageResult, String> {
+        let session = self
+            .session
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().and_then(Weak::upgrade))
+            .ok_or_else(|| "the Codex session has ended".to_string())?;
+        let turn = self.turn.lock().ok().and_then(|guard| guard.clone());
+        let Some((sub_id, turn_context)) = turn else {
+            return Err("Codex only answers sampling requests during a turn".to_string());
+        };
+        if session.active_turn.lock().await.is_none() {
+            return Err("Codex only answers sampling requests during a turn".to_string());
+        }
+        if self.answered.load(Ordering::SeqCst) >= self.settings.max_requests {
+            return Err(format!(
+                "the session's limit of {} sampling requests was reached",
+                self.settings.max_requests
+            ));
+        }
+        if session.session_budget_reached(&turn_context).await {
+            return Err("the session's budget is used up".to_string());
+        }
+
+        self.approve(&session, &sub_id, &turn_context, server, &params)
+            .await?;
+        self.answered.fetch_add(1, Ordering::SeqCst);
+        session
+            .notify_background_event(&sub_id, format!("MCP server `{server}` sampled the model"))
+            .await;
+
+        let client = self.sampling_client(&turn_context, &session);
+        let prompt = sampling_prompt(params);
+        let text = sample(&client, &prompt)
+            .await
+            .map_err(|e| format!("model request failed: {e}"))?;
+        Ok(CreateMessageResult {
+            content: CreateMessageResultContent::TextContent(TextContent {
+                annotations: None,
+                text,
+                r#type: "text".to_string(),
+            }),
+            model: client.get_model(),
+            role: Role::Assistant,
+            stop_reason: Some("endTurn".to_string()),
+        })
+    }
+
+    /// Ask the user to approve the request unless the approval policy never
+    /// asks or the server was approved for the session.
+    async fn approve(
+        &self,
+        session: &Session,
+        sub_id: &str,
+        turn_context: &TurnContext,
+        server: &str,
+        params: &CreateMessageRequestParams,
+    ) -> Result<(), String> {
+        if turn_context.approval_policy == AskForApproval::Never
+            || self
+                .approved_servers
+                .lock()
+                .is_ok_and(|servers| servers.contains(server))
+        {
+            return Ok(());
+        }
+        let call_id = format!(
+            "mcp_sampling-{}",
+            self.next_call_id.fetch_add(1, Ordering::SeqCst) + 1
+        );
+        let decision = session
+            .request_command_approval(
+                sub_id.to_string(),
+                call_id,
+                vec!["mcp_sampling".to_string(), server.to_string()],
+                turn_context.cwd.clone(),
+                Some(approval_reason(server, params)),
+            )
+            .await;
+        match decision {
+            ReviewDecision::Approved => Ok(()),
+            ReviewDecision::ApprovedForSession => {
+                if let Ok(mut servers) = self.approved_servers.lock() {
+                    servers.insert(server.to_string());
+                }
+                Ok(())
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                Err("the user declined the sampling request".to_string())
+            }
+        }
+    }
+
+    /// Client for the model that answers: `[mcp_sampling] model` if set,
+    /// otherwise the turn's model.
+    fn sampling_client(&self, turn_context: &TurnContext, session: &Session) -> ModelClient {
+        let client = &turn_context.client;
+        let mut config = (*client.get_config()).clone();
+        if let Some(model) = self.settings.model.clone() {
+            config.model_family =
+                find_family_for_model(&model).unwrap_or_else(|| client.get_model_family());
+            config.model = model;
+        }
+        let otel_event_manager = client
+            .get_otel_event_manager()
+            .with_model(config.model.as_str(), config.model_family.slug.as_str());
+        let config = Arc::new(config);
+        ModelClient::new(
+            config.clone(),
+            client.get_auth_manager(),
+            otel_event_manager,
+            client.get_provider(),
+            config.model_reasoning_effort,
+            config.model_reasoning_summary,
+            session.conversation_id(),
+        )
+    }
+}
+
+async fn sample(client: &ModelClient, prompt: &Prompt) -> crate::error::Result<String> {
+    let mut stream = client.stream(prompt).await?;
+    let mut reply = None;
+    while let Some(event) = stream.next().await {
+        match event? {
+            ResponseEvent::OutputItemDone(ResponseItem::Message { role, content, .. })
+                if role == "assistant" =>
+            {
+                reply = content_items_to_text(&content);
+            }
+            ResponseEvent::Completed { .. } => break,
+            _ => {}
+        }
+    }
+    Ok(reply.unwrap_or_default())
+}
+
+fn sampling_prompt(params: CreateMessageRequestParams) -> Prompt {
+    Prompt {
+        input: params
+            .messages
+            .into_iter()
+            .filter_map(to_response_item)
+            .collect(),
+        base_instructions_override: Some(
+            params
+                .system_prompt
+                .unwrap_or_else(|| DEFAULT_SAMPLING_INSTRUCTIONS.to_string()),
+        ),
+        ..Default::default()
+    }
+}
+
+/// The model input for a sampling message. Images are only sent from the
+/// user; audio is dropped because the model cannot take it.
+fn to_response_item(message: SamplingMessage) -> Option<ResponseItem> {
+    let (role, content) = match (message.role, message.content) {
+        (Role::User, SamplingMessageContent::TextContent(text)) => {
+            ("user", ContentItem::InputText { text: text.text })
+        }
+        (Role::User, SamplingMessageContent::ImageContent(image)) => (
+            "user",
+            ContentItem::InputImage {
+                image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+            },
+        ),
+        (Role::Assistant, SamplingMessageContent::TextContent(text)) => {
+            ("assistant", ContentItem::OutputText { text: text.text })
+        }
+        (_, SamplingMessageContent::ImageContent(_) | SamplingMessageContent::AudioContent(_)) => {
+            return None;
+        }
+    };
+    Some(ResponseItem::Message {
+        id: None,
+        role: role.to_string(),
+        content: vec![content],
+    })
+}
+
+/// The reason shown when asking to approve a request: the server and the
+/// start of its last text message.
+fn approval_reason(server: &str, params: &CreateMessageRequestParams) -> String {
+    let last_text = params
+        .messages
+        .iter()
+        .rev()
+        .find_map(|message| match &message.content {
+            SamplingMessageContent::TextContent(text) => Some(text.text.as_str()),
+            _ => None,
+        });
+    match last_text {
+        Some(text) => {
+            let (excerpt, _) = truncate_middle(text, MAX_REASON_EXCERPT_BYTES);
+            format!("MCP server `{server}` asks the model: {excerpt}")
+        }
+        None => format!("MCP server `{server}` asks the model for a completion"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::AudioContent;
+    use mcp_types::ImageContent;
+    use pretty_assertions::assert_eq;
+
+    fn text(role: Role, text: &str) -> SamplingMessage {
+        SamplingMessage {
+            content: SamplingMessageContent::TextContent(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                r#type: "text".to_string(),
+            }),
+            role,
+        }
+    }
+
+    #[test]
+    fn converts_sampling_messages() {
+        let messages = vec![
+            text(Role::User, "Summarize this diff."),
+            SamplingMessage {
+                content: SamplingMessageContent::ImageContent(ImageContent {
+                    annotations: None,
+                    data: "AAAA".to_string(),
+                    mime_type: "image/png".to_string(),
+                    r#type: "image".to_string(),
+                }),
+                role: Role::User,
+            },
+            SamplingMessage {
+                content: SamplingMessageContent::AudioContent(AudioContent {
+                    annotations: None,
+                    data: "AAAA".to_string(),
+                    mime_type: "audio/wav".to_string(),
+                    r#type: "audio".to_string(),
+                }),
+                role: Role::User,
+            },
+            text(Role::Assistant, "It renames a field."),
+        ];
+
+        let items: Vec<ResponseItem> = messages.into_iter().filter_map(to_response_item).collect();
+
+        assert_eq!(
+            vec![
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputText {
+                        text: "Summarize this diff.".to_string(),
+                    }],
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputImage {
+                        image_url: "data:image/png;base64,AAAA".to_string(),
+                    }],
+                },
+                ResponseItem::Message {
+                    id: None,
+                    role: "assistant".to_string(),
+                    content: vec![ContentItem::OutputText {
+                        text: "It renames a field.".to_string(),
+                    }],
+                },
+            ],
+            items
+        );
+    }
+
+    #[test]
+    fn approval_reason_quotes_the_last_text_message() {
+        let params = CreateMessageRequestParams {
+            include_context: None,
+            max_tokens: 100,
+            messages: vec![
+                text(Role::User, "First question"),
+                text(Role::User, "Which test covers this?"),
+            ],
+            metadata: None,
+            model_preferences: None,
+            stop_sequences: None,
+            system_prompt: None,
+            temperature: None,
+        };
+
+        assert_eq!(
+            "MCP server `tests` asks the model: Which test covers this?",
+            approval_reason("tests", &params)
+        );
+    }
+}
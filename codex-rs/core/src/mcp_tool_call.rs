@@ -1,25 +1,53 @@
+use std::sync::Arc;
+use std::time::Duration;
 use std::time::Instant;
 
+use base64::Engine;
+use codex_mcp_client::ProgressHandler;
+use mcp_types::CallToolResult;
+use mcp_types::ContentBlock;
+use mcp_types::ProgressNotificationParams;
+use mcp_types::TextContent;
+use serde_json::json;
+use tokio::sync::mpsc;
 use tracing::error;
 
 use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config_types::McpServerConfig;
+use crate::config_types::McpToolApproval;
+use crate::mcp_connection_manager::McpCallError;
+use crate::protocol::AskForApproval;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::McpInvocation;
 use crate::protocol::McpToolCallBeginEvent;
 use crate::protocol::McpToolCallEndEvent;
+use crate::protocol::McpToolCallProgressEvent;
+use crate::protocol::ReviewDecision;
+use crate::tool_timeout::timed_out;
 use codex_protocol::models::FunctionCallOutputPayload;
 use codex_protocol::models::ResponseInputItem;
+use codex_protocol::payload::DEFAULT_MAX_FRAME_LEN;
+use codex_protocol::payload::frame_payload;
+use codex_protocol::payload::payload_ref;
+
+/// Longest excerpt of the arguments shown when asking to approve a call.
+const APPROVAL_ARGUMENTS_PREVIEW_CHARS: usize = 500;
 
 /// Handles the specified tool call dispatches the appropriate
 /// `McpToolCallBegin` and `McpToolCallEnd` events to the `Session`.
+/// `timeout` replaces the server's `tool_timeout_sec` for this call.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn handle_mcp_tool_call(
     sess: &Session,
+    turn_context: &TurnContext,
     sub_id: &str,
     call_id: String,
     server: String,
     tool_name: String,
     arguments: String,
+    timeout: Option<Duration>,
 ) -> ResponseInputItem {
     // Parse the `arguments` as JSON. An empty string is OK, but invalid JSON
     // is not.
@@ -41,6 +69,23 @@ pub(crate) async fn handle_mcp_tool_call(
         }
     };
 
+    if let Err(message) = approve_mcp_tool_call(
+        sess,
+        turn_context,
+        sub_id,
+        &call_id,
+        &server,
+        &tool_name,
+        &arguments,
+    )
+    .await
+    {
+        return ResponseInputItem::McpToolCallOutput {
+            call_id,
+            result: Err(message),
+        };
+    }
+
     let invocation = McpInvocation {
         server: server.clone(),
         tool: tool_name.clone(),
@@ -54,16 +99,47 @@ pub(crate) async fn handle_mcp_tool_call(
     notify_mcp_tool_call_event(sess, sub_id, tool_call_begin_event).await;
 
     let start = Instant::now();
-    // Perform the tool call.
-    let result = sess
-        .call_tool(&server, &tool_name, arguments_value.clone())
-        .await
-        .map_err(|e| format!("tool call error: {e}"));
+    // Perform the tool call, forwarding the progress the server reports
+    // while it runs.
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let on_progress: ProgressHandler = Arc::new(move |params| {
+        let _ = progress_tx.send(params);
+    });
+    let call = sess.call_tool(
+        &server,
+        &tool_name,
+        arguments_value.clone(),
+        timeout,
+        on_progress,
+    );
+    tokio::pin!(call);
+    let result = loop {
+        tokio::select! {
+            result = &mut call => break result,
+            Some(params) = progress_rx.recv() => {
+                notify_mcp_tool_call_progress(sess, sub_id, &call_id, params).await;
+            }
+        }
+    };
+    while let Ok(params) = progress_rx.try_recv() {
+        notify_mcp_tool_call_progress(sess, sub_id, &call_id, params).await;
+    }
+    let result = result.map_err(|e| call_error_output(&e));
+    let duration = start.elapsed();
+    let event_result = match &result {
+        Ok(tool_result) if !sess.client_capabilities().render_images => {
+            Ok(describe_binary_content(tool_result.clone()))
+        }
+        Ok(tool_result) if sess.binary_payload_framing() => {
+            Ok(send_binary_content_as_frames(sess, sub_id, &call_id, tool_result.clone()).await)
+        }
+        _ => result.clone(),
+    };
     let tool_call_end_event = EventMsg::McpToolCallEnd(McpToolCallEndEvent {
         call_id: call_id.clone(),
         invocation,
-        duration: start.elapsed(),
-        result: result.clone(),
+        duration,
+        result: event_result,
     });
 
     notify_mcp_tool_call_event(sess, sub_id, tool_call_end_event.clone()).await;
@@ -71,6 +147,171 @@ pub(crate) async fn handle_mcp_tool_call(
     ResponseInputItem::McpToolCallOutput { call_id, result }
 }
 
+/// Ask the user to approve a call to a tool whose server marks it `ask`,
+/// unless it was approved for the session. The approval policy `never` cannot
+/// ask, so such calls are refused under it.
+async fn approve_mcp_tool_call(
+    sess: &Session,
+    turn_context: &TurnContext,
+    sub_id: &str,
+    call_id: &str,
+    server: &str,
+    tool: &str,
+    arguments: &str,
+) -> Result<(), String> {
+    let Some(cfg) = sess.mcp_server_config(server).await else {
+        return Ok(());
+    };
+    if tool_approval(&cfg, tool) == McpToolApproval::Auto
+        || sess.is_mcp_tool_approved(server, tool).await
+    {
+        return Ok(());
+    }
+    if turn_context.approval_policy == AskForApproval::Never {
+        return Err(format!(
+            "calls to `{tool}` of MCP server `{server}` need the user's approval, which the approval policy `never` does not allow asking for"
+        ));
+    }
+
+    let mut preview: String = arguments
+        .trim()
+        .chars()
+        .take(APPROVAL_ARGUMENTS_PREVIEW_CHARS)
+        .collect();
+    if preview.len() < arguments.trim().len() {
+        preview.push('…');
+    }
+    let reason = if preview.is_empty() {
+        format!("call `{tool}` of MCP server `{server}`")
+    } else {
+        format!("call `{tool}` of MCP server `{server}` with {preview}")
+    };
+    let decision = sess
+        .request_command_approval(
+            sub_id.to_string(),
+            call_id.to_string(),
+            vec!["mcp".to_string(), server.to_string(), tool.to_string()],
+            turn_context.cwd.clone(),
+            Some(reason),
+        )
+        .await;
+    match decision {
+        ReviewDecision::Approved => Ok(()),
+        ReviewDecision::ApprovedForSession => {
+            sess.add_approved_mcp_tool(server, tool).await;
+            Ok(())
+        }
+        ReviewDecision::Denied | ReviewDecision::Abort => {
+            Err(format!("the user declined the call to `{tool}`"))
+        }
+    }
+}
+
+/// Whether calls to `tool`, named as its server names it, need approval.
+fn tool_approval(cfg: &McpServerConfig, tool: &str) -> McpToolApproval {
+    cfg.tool_approvals
+        .get(tool)
+        .copied()
+        .or(cfg.tool_approval)
+        .unwrap_or_default()
+}
+
+/// What the model is told about a call that did not return a result. Hitting
+/// one of the server's limits gives a structured result like
+/// [`timed_out`], so the model can tell it apart from the tool failing.
+fn call_error_output(error: &McpCallError) -> String {
+    match error {
+        McpCallError::TimedOut { tool, timeout, .. } => timed_out(tool, *timeout, &[]),
+        McpCallError::Busy { server, limit } => json!({
+            "error": "server_busy",
+            "server": server,
+            "max_concurrent_calls": limit,
+            "message": format!(
+                "MCP server `{server}` is already handling {limit} tool calls; wait for them to finish before calling its tools again"
+            ),
+        })
+        .to_string(),
+        McpCallError::ResultTooLarge {
+            server,
+            tool,
+            size,
+            limit,
+        } => json!({
+            "error": "result_too_large",
+            "tool": tool,
+            "size_bytes": size,
+            "max_result_bytes": limit,
+            "message": format!(
+                "the result of `{tool}` was {size} bytes, more than the {limit} bytes allowed for MCP server `{server}`; ask for less, e.g. with narrower arguments"
+            ),
+        })
+        .to_string(),
+        McpCallError::UnknownServer(_) | McpCallError::Failed { .. } => {
+            format!("tool call error: {error}")
+        }
+    }
+}
+
+/// Replace image and audio blocks with a short text description for clients
+/// that cannot render them.
+fn describe_binary_content(mut result: CallToolResult) -> CallToolResult {
+    for block in result.content.iter_mut() {
+        let text = match block {
+            ContentBlock::ImageContent(image) => format!("[image: {}]", image.mime_type),
+            ContentBlock::AudioContent(audio) => format!("[audio: {}]", audio.mime_type),
+            _ => continue,
+        };
+        *block = ContentBlock::TextContent(TextContent {
+            r#type: "text".to_string(),
+            text,
+            annotations: None,
+        });
+    }
+    result
+}
+
+/// Emit the image and audio blocks of `result` as `PayloadFrame` events and
+/// return a copy of the result whose blocks reference those payloads instead
+/// of inlining them. Blocks whose data is not valid base64 are left as-is.
+async fn send_binary_content_as_frames(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    mut result: CallToolResult,
+) -> CallToolResult {
+    for (index, block) in result.content.iter_mut().enumerate() {
+        let (data, mime_type) = match block {
+            ContentBlock::ImageContent(image) => (&mut image.data, image.mime_type.as_str()),
+            ContentBlock::AudioContent(audio) => (&mut audio.data, audio.mime_type.as_str()),
+            _ => continue,
+        };
+        let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data.as_bytes()) else {
+            continue;
+        };
+        let payload_id = format!("{call_id}-{index}");
+        for frame in frame_payload(&payload_id, mime_type, &bytes, DEFAULT_MAX_FRAME_LEN) {
+            notify_mcp_tool_call_event(sess, sub_id, EventMsg::PayloadFrame(frame)).await;
+        }
+        *data = payload_ref(&payload_id);
+    }
+    result
+}
+
+async fn notify_mcp_tool_call_progress(
+    sess: &Session,
+    sub_id: &str,
+    call_id: &str,
+    params: ProgressNotificationParams,
+) {
+    let event = EventMsg::McpToolCallProgress(McpToolCallProgressEvent {
+        call_id: call_id.to_string(),
+        progress: params.progress,
+        total: params.total,
+        message: params.message,
+    });
+    notify_mcp_tool_call_event(sess, sub_id, event).await;
+}
+
 async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMsg) {
     sess.send_event(Event {
         id: sub_id.to_string(),
@@ -78,3 +319,73 @@ async fn notify_mcp_tool_call_event(sess: &Session, sub_id: &str, event: EventMs
     })
     .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn per_tool_approval_overrides_the_server_default() {
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "github-mcp-server"
+            tool_approval = "ask"
+
+            [tool_approvals]
+            get_issue = "auto"
+        "#,
+        )
+        .expect("config parses");
+        assert_eq!(McpToolApproval::Auto, tool_approval(&cfg, "get_issue"));
+        assert_eq!(McpToolApproval::Ask, tool_approval(&cfg, "create_issue"));
+
+        let cfg: McpServerConfig = toml::from_str(
+            r#"
+            command = "github-mcp-server"
+
+            [tool_approvals]
+            create_issue = "ask"
+        "#,
+        )
+        .expect("config parses");
+        assert_eq!(McpToolApproval::Auto, tool_approval(&cfg, "get_issue"));
+        assert_eq!(McpToolApproval::Ask, tool_approval(&cfg, "create_issue"));
+    }
+
+    #[test]
+    fn server_limits_give_structured_errors() {
+        let busy: serde_json::Value =
+            serde_json::from_str(&call_error_output(&McpCallError::Busy {
+                server: "docs".to_string(),
+                limit: 2,
+            }))
+            .expect("busy output is json");
+        assert_eq!(Some("server_busy"), busy["error"].as_str());
+        assert_eq!(Some(2), busy["max_concurrent_calls"].as_u64());
+
+        let too_large: serde_json::Value =
+            serde_json::from_str(&call_error_output(&McpCallError::ResultTooLarge {
+                server: "docs".to_string(),
+                tool: "search".to_string(),
+                size: 2048,
+                limit: 1024,
+            }))
+            .expect("too large output is json");
+        assert_eq!(Some("result_too_large"), too_large["error"].as_str());
+        assert_eq!(Some(2048), too_large["size_bytes"].as_u64());
+
+        assert_eq!(
+            timed_out("search", Duration::from_secs(5), &[]),
+            call_error_output(&McpCallError::TimedOut {
+                server: "docs".to_string(),
+                tool: "search".to_string(),
+                timeout: Duration::from_secs(5),
+            })
+        );
+        assert_eq!(
+            "tool call error: unknown MCP server 'docs'",
+            call_error_output(&McpCallError::UnknownServer("docs".to_string()))
+        );
+    }
+}
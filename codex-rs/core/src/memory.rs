@@ -0,0 +1,461 @@
+//! Persistent per-project memory.
+//!
+//! Each project (the git repository containing the session's `cwd`, or the
+//! `cwd` itself outside a repository) has a small store of key/value entries
+//! and free-text notes under `$CODEX_HOME/memory/`. The model maintains it
+//! with the `memory` tool, and new sessions in the same project see it next
+//! to the `AGENTS.md` instructions, so conventions learned in one session
+//! carry over to the next. Only what fits in a byte budget is shown up
+//! front; the model finds the rest with the tool's `recall` action, a
+//! keyword search that ranks entries and notes with BM25 by the words they
+//! share with a query. It does not match synonyms or paraphrases.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::error;
+
+use crate::config::Config;
+use crate::function_tool::FunctionCallError;
+use crate::git_info::get_git_repo_root;
+
+pub(crate) const MEMORY_TOOL_NAME: &str = "memory";
+
+const MEMORY_SUBDIR: &str = "memory";
+
+/// Separates the project memory from the instructions that precede it.
+const PROJECT_MEMORY_SEPARATOR: &str = "\n\n--- project-memory ---\n\n";
+
+const PROJECT_MEMORY_PREAMBLE: &str = "Saved by earlier sessions in this project with the `memory` tool. \
+Update it when you learn something future sessions should know.";
+
+/// Number of memories `recall` returns unless asked for another number.
+const DEFAULT_RECALL_LIMIT: usize = 5;
+
+/// BM25 parameters used to rank memories against a query.
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+/// Words too common to tell memories apart.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "do", "does", "for", "from", "how", "in",
+    "is", "it", "of", "on", "or", "that", "the", "this", "to", "use", "uses", "we", "what", "when",
+    "with",
+];
+
+/// Serializes read-modify-write cycles of sessions in this process.
+static STORE_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ProjectMemory {
+    pub(crate) entries: BTreeMap<String, String>,
+    /// Oldest first.
+    pub(crate) notes: Vec<String>,
+}
+
+impl ProjectMemory {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.notes.is_empty()
+    }
+
+    /// The memory as shown to a new session: every entry and then the most
+    /// recent notes, keeping only what fits in `max_bytes`.
+    fn render(&self, max_bytes: usize) -> Option<String> {
+        let mut remaining = max_bytes;
+        let mut lines = Vec::new();
+        let entries = self
+            .entries
+            .iter()
+            .map(|(key, value)| format!("- {key}: {value}"));
+        let notes = self.notes.iter().rev().map(|note| format!("- {note}"));
+        for line in entries.chain(notes) {
+            if line.len() < remaining {
+                remaining -= line.len() + 1;
+                lines.push(line);
+            }
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        Some(format!("{PROJECT_MEMORY_PREAMBLE}\n\n{}", lines.join("\n")))
+    }
+
+    /// The full memory, with note indices, as returned by the `list` action.
+    fn list(&self) -> String {
+        if self.is_empty() {
+            return "Memory is empty.".to_string();
+        }
+        let mut out = String::new();
+        if !self.entries.is_empty() {
+            out.push_str("Entries:\n");
+            for (key, value) in &self.entries {
+                out.push_str(&format!("- {key}: {value}\n"));
+            }
+        }
+        if !self.notes.is_empty() {
+            out.push_str("Notes:\n");
+            for (index, note) in self.notes.iter().enumerate() {
+                out.push_str(&format!("[{index}] {note}\n"));
+            }
+        }
+        out
+    }
+
+    /// Entries and notes matching `query`, best first, as returned by the
+    /// `recall` action. Notes keep their indices so they can be forgotten.
+    fn recall(&self, query: &str, limit: usize) -> String {
+        let query: HashSet<String> = terms(query).into_iter().collect();
+        let memories: Vec<(String, Vec<String>)> = self
+            .entries
+            .iter()
+            .map(|(key, value)| {
+                (
+                    format!("- {key}: {value}"),
+                    terms(&format!("{key} {value}")),
+                )
+            })
+            .chain(
+                self.notes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, note)| (format!("[{index}] {note}"), terms(note))),
+            )
+            .collect();
+        let documents: Vec<&[String]> =
+            memories.iter().map(|(_, terms)| terms.as_slice()).collect();
+        let mut ranked: Vec<(f64, &str)> = bm25_scores(&query, &documents)
+            .into_iter()
+            .zip(&memories)
+            .filter(|(score, _)| *score > 0.0)
+            .map(|(score, (line, _))| (score, line.as_str()))
+            .collect();
+        if ranked.is_empty() {
+            return "No memories match the query.".to_string();
+        }
+        // Stable, so equally relevant memories stay in store order.
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, line)| format!("{line}\n"))
+            .collect()
+    }
+}
+
+/// Lowercased words of `text` without stop words, with common English
+/// suffixes removed so that e.g. "migrations" matches "migration".
+fn terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .map(stem)
+        .collect()
+}
+
+fn stem(word: String) -> String {
+    if word.len() > 4
+        && let Some(stem) = word.strip_suffix("ies")
+    {
+        return format!("{stem}y");
+    }
+    for suffix in ["ing", "ed"] {
+        if word.len() > suffix.len() + 3
+            && let Some(stem) = word.strip_suffix(suffix)
+        {
+            // "running" -> "run"
+            let mut chars = stem.chars().rev();
+            return match (chars.next(), chars.next()) {
+                (Some(last), Some(before)) if last == before && !"aeiouls".contains(last) => {
+                    stem[..stem.len() - last.len_utf8()].to_string()
+                }
+                _ => stem.to_string(),
+            };
+        }
+    }
+    if word.len() > 4 && word.ends_with('s') && !word.ends_with("ss") {
+        return word[..word.len() - 1].to_string();
+    }
+    word
+}
+
+/// BM25 score of each document for `query`.
+fn bm25_scores(query: &HashSet<String>, documents: &[&[String]]) -> Vec<f64> {
+    if documents.is_empty() {
+        return Vec::new();
+    }
+    let count = documents.len() as f64;
+    let average_len = documents.iter().map(|terms| terms.len()).sum::<usize>() as f64 / count;
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for terms in documents {
+        let unique: HashSet<&str> = terms.iter().map(String::as_str).collect();
+        for term in unique {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+    documents
+        .iter()
+        .map(|terms| {
+            let len_norm = 1.0 - BM25_B + BM25_B * terms.len() as f64 / average_len.max(1.0);
+            query
+                .iter()
+                .map(|term| {
+                    let frequency = terms.iter().filter(|t| *t == term).count() as f64;
+                    if frequency == 0.0 {
+                        return 0.0;
+                    }
+                    let containing = document_frequency[term.as_str()] as f64;
+                    let idf = (1.0 + (count - containing + 0.5) / (containing + 0.5)).ln();
+                    idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * len_norm)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Location of the memory of the project containing `cwd`.
+fn memory_path(codex_home: &Path, cwd: &Path) -> PathBuf {
+    let project = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+    let name: String = project
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    codex_home
+        .join(MEMORY_SUBDIR)
+        .join(format!("{}.json", name.trim_matches('-')))
+}
+
+async fn load(path: &Path) -> io::Result<ProjectMemory> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(text) => serde_json::from_str(&text).map_err(io::Error::other),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(ProjectMemory::default()),
+        Err(e) => Err(e),
+    }
+}
+
+async fn save(path: &Path, memory: &ProjectMemory) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let text = serde_json::to_string_pretty(memory).map_err(io::Error::other)?;
+    // Write to a sibling file first so readers never see a partial store.
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, text).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// Append the project memory to `instructions` when memory is enabled.
+pub(crate) async fn with_project_memory(
+    config: &Config,
+    instructions: Option<String>,
+) -> Option<String> {
+    if !config.memory.enabled {
+        return instructions;
+    }
+    let memory = match load(&memory_path(&config.codex_home, &config.cwd)).await {
+        Ok(memory) => memory,
+        Err(e) => {
+            error!("failed to load project memory: {e:#}");
+            return instructions;
+        }
+    };
+    let Some(rendered) = memory.render(config.memory.max_injected_bytes) else {
+        return instructions;
+    };
+    Some(match instructions {
+        Some(instructions) => format!("{instructions}{PROJECT_MEMORY_SEPARATOR}{rendered}"),
+        None => rendered,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum MemoryArgs {
+    List,
+    Recall {
+        query: String,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    Set {
+        key: String,
+        value: String,
+    },
+    Delete {
+        key: String,
+    },
+    #[serde(alias = "store")]
+    Note {
+        text: String,
+    },
+    #[serde(alias = "forget")]
+    ForgetNote {
+        index: usize,
+    },
+}
+
+/// Handle a call to the `memory` tool for the project containing `cwd`.
+pub(crate) async fn handle_memory_tool(
+    codex_home: &Path,
+    cwd: &Path,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: MemoryArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e}"))
+    })?;
+    let path = memory_path(codex_home, cwd);
+    let _guard = STORE_LOCK.lock().await;
+    let mut memory = load(&path).await.map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to read project memory: {e}"))
+    })?;
+
+    let message = match args {
+        MemoryArgs::List => return Ok(memory.list()),
+        MemoryArgs::Recall { query, limit } => {
+            return Ok(memory.recall(&query, limit.unwrap_or(DEFAULT_RECALL_LIMIT)));
+        }
+        MemoryArgs::Set { key, value } => {
+            let message = format!("Saved {key}.");
+            memory.entries.insert(key, value);
+            message
+        }
+        MemoryArgs::Delete { key } => match memory.entries.remove(&key) {
+            Some(_) => format!("Deleted {key}."),
+            None => return Err(FunctionCallError::RespondToModel(format!("no entry {key}"))),
+        },
+        MemoryArgs::Note { text } => {
+            memory.notes.push(text);
+            format!("Saved note {}.", memory.notes.len() - 1)
+        }
+        MemoryArgs::ForgetNote { index } => {
+            if index >= memory.notes.len() {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "no note with index {index}"
+                )));
+            }
+            memory.notes.remove(index);
+            format!("Removed note {index}.")
+        }
+    };
+    save(&path, &memory).await.map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to save project memory: {e}"))
+    })?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    async fn call(home: &Path, cwd: &Path, args: serde_json::Value) -> String {
+        handle_memory_tool(home, cwd, &args.to_string())
+            .await
+            .expect("memory call succeeds")
+    }
+
+    #[tokio::test]
+    async fn tool_updates_the_project_store() {
+        let home = TempDir::new().expect("tempdir");
+        let project = TempDir::new().expect("tempdir");
+        let (home, cwd) = (home.path(), project.path());
+
+        call(
+            home,
+            cwd,
+            serde_json::json!({"action": "set", "key": "test", "value": "cargo nextest run"}),
+        )
+        .await;
+        call(
+            home,
+            cwd,
+            serde_json::json!({"action": "note", "text": "snapshots live in tests/snapshots"}),
+        )
+        .await;
+        call(
+            home,
+            cwd,
+            serde_json::json!({"action": "note", "text": "stale"}),
+        )
+        .await;
+        call(
+            home,
+            cwd,
+            serde_json::json!({"action": "forget_note", "index": 1}),
+        )
+        .await;
+
+        assert_eq!(
+            "Entries:\n- test: cargo nextest run\nNotes:\n[0] snapshots live in tests/snapshots\n",
+            call(home, cwd, serde_json::json!({"action": "list"})).await
+        );
+        assert!(
+            handle_memory_tool(home, cwd, r#"{"action":"delete","key":"missing"}"#)
+                .await
+                .is_err()
+        );
+
+        // Another project has its own store.
+        let other = TempDir::new().expect("tempdir");
+        assert_eq!(
+            "Memory is empty.",
+            call(home, other.path(), serde_json::json!({"action": "list"})).await
+        );
+    }
+
+    #[test]
+    fn recall_ranks_matching_memories() {
+        let memory = ProjectMemory {
+            entries: BTreeMap::from([
+                ("test_command".to_string(), "cargo nextest run".to_string()),
+                (
+                    "style".to_string(),
+                    "rustfmt with imports one per line".to_string(),
+                ),
+            ]),
+            notes: vec![
+                "The CI runs on Ubuntu 22.04".to_string(),
+                "This repo uses sqlx offline mode; run cargo sqlx prepare after changing queries"
+                    .to_string(),
+                "Database migrations live in crates/db/migrations".to_string(),
+            ],
+        };
+
+        assert_eq!(
+            "[2] Database migrations live in crates/db/migrations\n[1] This repo uses sqlx offline mode; run cargo sqlx prepare after changing queries\n",
+            memory.recall("how do I add a database migration for a sqlx query?", 5)
+        );
+        assert_eq!(
+            "- test_command: cargo nextest run\n",
+            memory.recall("running tests", 1)
+        );
+        assert_eq!(
+            "No memories match the query.",
+            memory.recall("kubernetes deployment", 5)
+        );
+    }
+
+    #[test]
+    fn render_keeps_entries_and_newest_notes_within_budget() {
+        let memory = ProjectMemory {
+            entries: BTreeMap::from([("build".to_string(), "make".to_string())]),
+            notes: vec!["oldest note".to_string(), "newest note".to_string()],
+        };
+
+        let rendered = memory.render(30).expect("something fits");
+        assert_eq!(
+            format!("{PROJECT_MEMORY_PREAMBLE}\n\n- build: make\n- newest note"),
+            rendered
+        );
+        assert_eq!(None, ProjectMemory::default().render(1024));
+    }
+}
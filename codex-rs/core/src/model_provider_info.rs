@@ -7,10 +7,16 @@
 
 use crate::CodexAuth;
 use codex_app_server_protocol::AuthMode;
+use codex_keyring_store::KeyringStore;
+use codex_keyring_store::OsKeyring;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::env::VarError;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::PoisonError;
 use std::time::Duration;
 
 use crate::error::EnvVarError;
@@ -28,7 +34,7 @@ const MAX_REQUEST_MAX_RETRIES: u64 = 100;
 /// *Responses* API. The two protocols use different request/response shapes
 /// and *cannot* be auto-detected at runtime, therefore each provider entry
 /// must declare which one it expects.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
     /// The Responses API exposed by OpenAI at `/v1/responses`.
@@ -37,16 +43,134 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// Anthropic's Messages API at `/v1/messages`.
+    Anthropic,
+
+    /// The Converse API of Amazon Bedrock, with SigV4-signed requests to
+    /// `/model/<model id>/converse-stream`.
+    Bedrock,
+
+    /// Google's Gemini API at `/v1beta/models/<model>:streamGenerateContent`.
+    Gemini,
+}
+
+/// How tools are offered to the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCalling {
+    /// Tool definitions are sent with the request and the model answers with
+    /// native tool calls. When a Chat Completions server answers that the
+    /// model does not support tools, Codex switches to `Prompt`.
+    #[default]
+    Native,
+
+    /// Tools are described in the instructions and the model calls them by
+    /// replying with `<tool_call>` blocks, for local models without native
+    /// tool calling. Only used with the Chat Completions API.
+    Prompt,
+}
+
+/// Azure OpenAI settings of a provider, from its `azure` table, from which the
+/// request URL, `api-version` and authentication are derived.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AzureProviderConfig {
+    /// Name of the Azure OpenAI resource, for the endpoint
+    /// `https://<resource>.openai.azure.com`. Not needed when `base_url` is
+    /// set to the endpoint.
+    pub resource: Option<String>,
+
+    /// Deployment to use. Chat Completions requests go to
+    /// `/openai/deployments/<deployment>`; Responses requests name it as the
+    /// model. Defaults to the configured model.
+    pub deployment: Option<String>,
+
+    /// `api-version` query parameter (default: [`DEFAULT_AZURE_API_VERSION`]).
+    pub api_version: Option<String>,
+
+    /// How requests are authenticated.
+    #[serde(default)]
+    pub auth: AzureAuth,
+}
+
+/// Authentication of Azure OpenAI requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum AzureAuth {
+    /// An `api-key` header with the key from `env_key`, or from
+    /// `AZURE_OPENAI_API_KEY` when `env_key` is not set.
+    #[default]
+    ApiKey,
+
+    /// A Microsoft Entra ID bearer token, from `AZURE_OPENAI_AD_TOKEN` or the
+    /// Azure CLI.
+    Entra,
+}
+
+/// Amazon Bedrock settings of a provider, from its `bedrock` table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct BedrockProviderConfig {
+    /// AWS region of the Bedrock runtime endpoint. Defaults to `AWS_REGION`,
+    /// then `AWS_DEFAULT_REGION`.
+    pub region: Option<String>,
+
+    /// Profile of the shared credentials file to sign requests with, when the
+    /// credentials are not in the environment. Defaults to `AWS_PROFILE`, then
+    /// `default`.
+    pub profile: Option<String>,
+
+    /// Bedrock model IDs (or inference profile IDs and ARNs) of Codex model
+    /// names, e.g. `claude-sonnet-4-5` to
+    /// `us.anthropic.claude-sonnet-4-5-20250929-v1:0`. Models without an entry
+    /// are sent as they are named.
+    #[serde(default)]
+    pub model_ids: HashMap<String, String>,
+}
+
+impl BedrockProviderConfig {
+    /// The Bedrock model ID of `model`.
+    pub(crate) fn model_id<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_ids
+            .get(model)
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+}
+
+/// `api-version` of Azure OpenAI requests when none is configured.
+pub const DEFAULT_AZURE_API_VERSION: &str = "2025-04-01-preview";
+
+/// Variable holding the Azure OpenAI key when the provider has no `env_key`.
+const AZURE_API_KEY_ENV_VAR: &str = "AZURE_OPENAI_API_KEY";
+
+/// `anthropic-version` header sent to providers speaking the Messages API.
+const ANTHROPIC_API_VERSION: &str = "2023-06-01";
+
+/// The API key stored as `env_key` in the OS credential store, for when the
+/// environment variable is not set. Looked up once per process.
+fn keychain_api_key(env_key: &str) -> Option<String> {
+    static KEYS: LazyLock<Mutex<HashMap<String, Option<String>>>> = LazyLock::new(Mutex::default);
+    let mut keys = KEYS.lock().unwrap_or_else(PoisonError::into_inner);
+    keys.entry(env_key.to_string())
+        .or_insert_with(|| {
+            OsKeyring.load(env_key).unwrap_or_else(|e| {
+                tracing::debug!("failed to look up {env_key}: {e}");
+                None
+            })
+        })
+        .clone()
 }
 
 /// Serializable representation of a provider definition.
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct ModelProviderInfo {
     /// Friendly display name.
     pub name: String,
     /// Base URL for the provider's OpenAI-compatible API.
     pub base_url: Option<String>,
     /// Environment variable that stores the user's API key for this provider.
+    /// When it is not set, the key stored under the same name in the OS
+    /// credential store is used.
     pub env_key: Option<String>,
 
     /// Optional instructions to help the user get a valid value for the
@@ -86,14 +210,32 @@ pub struct ModelProviderInfo {
     /// and API key (if needed) comes from the "env_key" environment variable.
     #[serde(default)]
     pub requires_openai_auth: bool,
+
+    /// How tools are offered to the model.
+    #[serde(default)]
+    pub tool_calling: ToolCalling,
+
+    /// Azure OpenAI settings, for a provider that is an Azure OpenAI
+    /// resource.
+    pub azure: Option<AzureProviderConfig>,
+
+    /// Amazon Bedrock settings, for `wire_api = "bedrock"`.
+    pub bedrock: Option<BedrockProviderConfig>,
+
+    /// Whether to mark the stable prefix of requests (tools, instructions and
+    /// earlier turns) for caching, for the APIs that cache only what is
+    /// marked: Anthropic Messages and Bedrock Converse. Defaults to `true`.
+    pub prompt_caching: Option<bool>,
 }
 
 impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available, or the
+    ///     `x-api-key` header for the Anthropic Messages API.
     ///   • Auth token for OAuth.
+    ///   • The `api-key` header or Entra ID token of Azure OpenAI providers.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
     /// one produced by [`ModelProviderInfo::api_key`].
@@ -102,6 +244,15 @@ impl ModelProviderInfo {
         client: &'a reqwest::Client,
         auth: &Option<CodexAuth>,
     ) -> crate::error::Result<reqwest::RequestBuilder> {
+        if let Some(azure) = self.azure_config() {
+            let builder = client.post(self.get_full_url(&None));
+            let builder = match azure.auth {
+                AzureAuth::ApiKey => builder.header("api-key", self.azure_api_key()?),
+                AzureAuth::Entra => builder.bearer_auth(crate::azure_auth::entra_token().await?),
+            };
+            return Ok(self.apply_http_headers(builder));
+        }
+
         let effective_auth = match self.api_key() {
             Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
             Ok(None) => auth.clone(),
@@ -118,7 +269,12 @@ impl ModelProviderInfo {
 
         let mut builder = client.post(url);
 
-        if let Some(auth) = effective_auth.as_ref() {
+        if self.wire_api == WireApi::Anthropic {
+            builder = builder.header("anthropic-version", ANTHROPIC_API_VERSION);
+            if let Some(auth) = effective_auth.as_ref() {
+                builder = builder.header("x-api-key", auth.get_token().await?);
+            }
+        } else if let Some(auth) = effective_auth.as_ref() {
             builder = builder.bearer_auth(auth.get_token().await?);
         }
 
@@ -139,27 +295,144 @@ impl ModelProviderInfo {
     }
 
     pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+        if let Some(azure) = self.azure_config() {
+            return self.azure_url(azure);
+        }
+
+        let default_base_url = if self.wire_api == WireApi::Anthropic {
+            "https://api.anthropic.com/v1".to_string()
+        } else if self.wire_api == WireApi::Gemini {
+            "https://generativelanguage.googleapis.com/v1beta".to_string()
+        } else if self.wire_api == WireApi::Bedrock {
+            format!(
+                "https://bedrock-runtime.{}.amazonaws.com",
+                self.bedrock_region().unwrap_or_default()
+            )
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
                 ..
             })
         ) {
-            "https://chatgpt.com/backend-api/codex"
+            "https://chatgpt.com/backend-api/codex".to_string()
         } else {
-            "https://api.openai.com/v1"
+            "https://api.openai.com/v1".to_string()
         };
         let query_string = self.get_query_string();
-        let base_url = self
-            .base_url
-            .clone()
-            .unwrap_or(default_base_url.to_string());
+        let base_url = self.base_url.clone().unwrap_or(default_base_url);
 
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Anthropic => format!("{base_url}/messages{query_string}"),
+            // The model ID and operation follow, see `bedrock_converse_url`.
+            WireApi::Bedrock => format!("{base_url}/model"),
+            // The model and method follow, see `gemini_stream_url`.
+            WireApi::Gemini => format!("{base_url}/models"),
+        }
+    }
+
+    /// URL of the streaming `streamGenerateContent` method of the Gemini
+    /// model `model`, with the SSE response format selected.
+    pub(crate) fn gemini_stream_url(&self, model: &str) -> String {
+        let mut params = vec!["alt=sse".to_string()];
+        if let Some(query_params) = &self.query_params {
+            params.extend(query_params.iter().map(|(k, v)| format!("{k}={v}")));
+        }
+        format!(
+            "{}/{model}:streamGenerateContent?{}",
+            self.get_full_url(&None),
+            params.join("&")
+        )
+    }
+
+    /// URL of the `converse-stream` operation of the Bedrock model `model_id`.
+    pub(crate) fn bedrock_converse_url(&self, model_id: &str) -> String {
+        format!(
+            "{}/{}/converse-stream",
+            self.get_full_url(&None),
+            crate::aws_sigv4::uri_encode(model_id)
+        )
+    }
+
+    /// AWS region of a Bedrock provider, from its settings or the environment.
+    pub(crate) fn bedrock_region(&self) -> Option<String> {
+        self.bedrock
+            .as_ref()
+            .and_then(|bedrock| bedrock.region.clone())
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .filter(|region| !region.trim().is_empty())
+    }
+
+    /// The Azure settings of this provider, unless it speaks an API Azure
+    /// OpenAI does not serve.
+    fn azure_config(&self) -> Option<&AzureProviderConfig> {
+        self.azure.as_ref().filter(|_| {
+            !matches!(
+                self.wire_api,
+                WireApi::Anthropic | WireApi::Bedrock | WireApi::Gemini
+            )
+        })
+    }
+
+    /// URL of an Azure OpenAI request: the endpoint from `base_url` or the
+    /// resource name, the deployment in the path for Chat Completions, and the
+    /// `api-version` ahead of any other query parameters.
+    fn azure_url(&self, azure: &AzureProviderConfig) -> String {
+        let endpoint = match (&self.base_url, &azure.resource) {
+            (Some(base_url), _) => base_url.trim_end_matches('/').to_string(),
+            (None, Some(resource)) => format!("https://{resource}.openai.azure.com"),
+            (None, None) => String::new(),
+        };
+        let endpoint = endpoint.strip_suffix("/openai").unwrap_or(&endpoint);
+        let path = match (self.wire_api, &azure.deployment) {
+            (WireApi::Chat, Some(deployment)) => {
+                format!("openai/deployments/{deployment}/chat/completions")
+            }
+            (WireApi::Chat, None) => "openai/chat/completions".to_string(),
+            _ => "openai/responses".to_string(),
+        };
+        let api_version = azure
+            .api_version
+            .as_deref()
+            .unwrap_or(DEFAULT_AZURE_API_VERSION);
+        let mut params = vec![format!("api-version={api_version}")];
+        if let Some(query_params) = &self.query_params {
+            params.extend(
+                query_params
+                    .iter()
+                    .filter(|(k, _)| k.as_str() != "api-version")
+                    .map(|(k, v)| format!("{k}={v}")),
+            );
         }
+        format!("{endpoint}/{path}?{}", params.join("&"))
+    }
+
+    /// The key of an Azure OpenAI provider authenticated with `api-key`.
+    fn azure_api_key(&self) -> crate::error::Result<String> {
+        if let Some(key) = self.api_key()? {
+            return Ok(key);
+        }
+        std::env::var(AZURE_API_KEY_ENV_VAR)
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .or_else(|| keychain_api_key(AZURE_API_KEY_ENV_VAR))
+            .ok_or_else(|| {
+                crate::error::CodexErr::EnvVar(EnvVarError {
+                    var: AZURE_API_KEY_ENV_VAR.to_string(),
+                    instructions: self.env_key_instructions.clone(),
+                })
+            })
+    }
+
+    /// The model to name in requests: the Azure deployment when one is
+    /// configured, `model` otherwise.
+    pub(crate) fn request_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.azure_config()
+            .and_then(|azure| azure.deployment.as_deref())
+            .unwrap_or(model)
     }
 
     pub(crate) fn is_azure_responses_endpoint(&self) -> bool {
@@ -167,7 +440,7 @@ impl ModelProviderInfo {
             return false;
         }
 
-        if self.name.eq_ignore_ascii_case("azure") {
+        if self.azure.is_some() || self.name.eq_ignore_ascii_case("azure") {
             return true;
         }
 
@@ -180,7 +453,10 @@ impl ModelProviderInfo {
     /// Apply provider-specific HTTP headers (both static and environment-based)
     /// onto an existing `reqwest::RequestBuilder` and return the updated
     /// builder.
-    fn apply_http_headers(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    pub(crate) fn apply_http_headers(
+        &self,
+        mut builder: reqwest::RequestBuilder,
+    ) -> reqwest::RequestBuilder {
         if let Some(extra) = &self.http_headers {
             for (k, v) in extra {
                 builder = builder.header(k, v);
@@ -214,6 +490,7 @@ impl ModelProviderInfo {
                             Ok(Some(v))
                         }
                     })
+                    .or_else(|err| keychain_api_key(env_key).map(Some).ok_or(err))
                     .map_err(|_| {
                         crate::error::CodexErr::EnvVar(EnvVarError {
                             var: env_key.clone(),
@@ -225,6 +502,11 @@ impl ModelProviderInfo {
         }
     }
 
+    /// Whether requests mark their stable prefix for caching.
+    pub fn prompt_caching(&self) -> bool {
+        self.prompt_caching.unwrap_or(true)
+    }
+
     /// Effective maximum number of request retries for this provider.
     pub fn request_max_retries(&self) -> u64 {
         self.request_max_retries
@@ -297,6 +579,10 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: true,
+                tool_calling: ToolCalling::Native,
+                azure: None,
+                bedrock: None,
+                prompt_caching: None,
             },
         ),
         (BUILT_IN_OSS_MODEL_PROVIDER_ID, create_oss_provider()),
@@ -341,6 +627,10 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     }
 }
 
@@ -380,6 +670,10 @@ base_url = "http://localhost:11434/v1"
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -409,6 +703,10 @@ query_params = { api-version = "2025-04-01-preview" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -441,12 +739,110 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
         assert_eq!(expected_provider, provider);
     }
 
+    #[test]
+    fn anthropic_provider_posts_to_messages() {
+        let provider: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Anthropic"
+env_key = "ANTHROPIC_API_KEY"
+wire_api = "anthropic"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(provider.wire_api, WireApi::Anthropic);
+        assert_eq!(
+            provider.get_full_url(&None),
+            "https://api.anthropic.com/v1/messages"
+        );
+    }
+
+    #[test]
+    fn gemini_provider_streams_generate_content() {
+        let provider: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Gemini"
+env_key = "GEMINI_API_KEY"
+wire_api = "gemini"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            provider.gemini_stream_url("gemini-2.5-pro"),
+            "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.5-pro:streamGenerateContent?alt=sse"
+        );
+    }
+
+    #[test]
+    fn bedrock_provider_posts_to_converse_stream() {
+        let provider: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Amazon Bedrock"
+wire_api = "bedrock"
+
+[bedrock]
+region = "eu-west-1"
+model_ids = { "claude-sonnet-4-5" = "eu.anthropic.claude-sonnet-4-5-20250929-v1:0" }
+        "#,
+        )
+        .unwrap();
+
+        let model_id = provider
+            .bedrock
+            .as_ref()
+            .map(|bedrock| bedrock.model_id("claude-sonnet-4-5"))
+            .unwrap_or_default();
+        assert_eq!(model_id, "eu.anthropic.claude-sonnet-4-5-20250929-v1:0");
+        assert_eq!(
+            provider.bedrock_converse_url(model_id),
+            "https://bedrock-runtime.eu-west-1.amazonaws.com/model/eu.anthropic.claude-sonnet-4-5-20250929-v1%3A0/converse-stream"
+        );
+    }
+
+    #[test]
+    fn azure_provider_builds_deployment_urls() {
+        let mut provider: ModelProviderInfo = toml::from_str(
+            r#"
+name = "Azure"
+wire_api = "chat"
+
+[azure]
+resource = "contoso"
+deployment = "gpt-4o"
+auth = "entra"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            provider.get_full_url(&None),
+            "https://contoso.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2025-04-01-preview"
+        );
+        assert_eq!(provider.request_model("gpt-4o-2024-08-06"), "gpt-4o");
+
+        provider.wire_api = WireApi::Responses;
+        provider.base_url = Some("https://contoso.cognitiveservices.azure.com/openai/".into());
+        if let Some(azure) = provider.azure.as_mut() {
+            azure.api_version = Some("preview".into());
+        }
+        assert_eq!(
+            provider.get_full_url(&None),
+            "https://contoso.cognitiveservices.azure.com/openai/responses?api-version=preview"
+        );
+        assert!(provider.is_azure_responses_endpoint());
+    }
+
     #[test]
     fn detects_azure_responses_base_urls() {
         fn provider_for(base_url: &str) -> ModelProviderInfo {
@@ -463,6 +859,10 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: false,
+                tool_calling: ToolCalling::Native,
+                azure: None,
+                bedrock: None,
+                prompt_caching: None,
             }
         }
 
@@ -495,6 +895,10 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            tool_calling: ToolCalling::Native,
+            azure: None,
+            bedrock: None,
+            prompt_caching: None,
         };
         assert!(named_provider.is_azure_responses_endpoint());
 
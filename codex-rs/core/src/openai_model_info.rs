@@ -1,10 +1,9 @@
+use crate::config_types::ModelPricing;
 use crate::model_family::ModelFamily;
 
 /// Metadata about a model, particularly OpenAI models.
-/// We may want to consider including details like the pricing for
-/// input tokens, output tokens, etc., though users will need to be able to
-/// override this in config.toml, as this information can get out of date.
-/// Though this would help present more accurate pricing information in the UI.
+/// Pricing lives in [`get_model_pricing`] since it goes out of date and can be
+/// overridden in config.toml.
 #[derive(Debug)]
 pub(crate) struct ModelInfo {
     /// Size of the context window in tokens. This is the maximum size of the input context.
@@ -75,3 +74,58 @@ pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
         _ => None,
     }
 }
+
+/// Published prices of OpenAI, Anthropic, Gemini and Amazon Nova models, used
+/// to estimate turn costs when config.toml does not set `model_pricing` for
+/// the model. Models other than OpenAI's are matched anywhere in the slug, so
+/// that Bedrock and Vertex AI model ids such as
+/// `us.anthropic.claude-sonnet-4-5-20250929-v1:0` or
+/// `publishers/google/models/gemini-2.0-flash` are priced too.
+pub(crate) fn get_model_pricing(model_family: &ModelFamily) -> Option<ModelPricing> {
+    let pricing = |input_per_million, cached_input_per_million, output_per_million| {
+        Some(ModelPricing {
+            input_per_million,
+            cached_input_per_million: Some(cached_input_per_million),
+            output_per_million,
+        })
+    };
+    let slug = model_family.slug.as_str();
+    match slug {
+        // https://platform.openai.com/docs/pricing
+        "o3" | "gpt-4.1" | "gpt-4.1-2025-04-14" => pricing(2.0, 0.5, 8.0),
+        "o4-mini" => pricing(1.1, 0.275, 4.4),
+        "codex-mini-latest" => pricing(1.5, 0.375, 6.0),
+        "gpt-4o" | "gpt-4o-2024-08-06" | "gpt-4o-2024-11-20" => pricing(2.5, 1.25, 10.0),
+        _ if slug.starts_with("gpt-5-mini") => pricing(0.25, 0.025, 2.0),
+        _ if slug.starts_with("gpt-5-nano") => pricing(0.05, 0.005, 0.4),
+        _ if slug.starts_with("gpt-5") || slug.starts_with("codex-") => pricing(1.25, 0.125, 10.0),
+        // https://www.anthropic.com/pricing#api
+        _ if slug.contains("claude-opus-4-5") => pricing(5.0, 0.5, 25.0),
+        _ if slug.contains("claude-opus-4") || slug.contains("claude-3-opus") => {
+            pricing(15.0, 1.5, 75.0)
+        }
+        _ if slug.contains("claude-sonnet-4")
+            || slug.contains("claude-3-7-sonnet")
+            || slug.contains("claude-3-5-sonnet") =>
+        {
+            pricing(3.0, 0.3, 15.0)
+        }
+        _ if slug.contains("claude-haiku-4") => pricing(1.0, 0.1, 5.0),
+        _ if slug.contains("claude-3-5-haiku") => pricing(0.8, 0.08, 4.0),
+        _ if slug.contains("claude-3-haiku") => pricing(0.25, 0.03, 1.25),
+        // https://ai.google.dev/gemini-api/docs/pricing
+        _ if slug.contains("gemini-2.5-pro") => pricing(1.25, 0.125, 10.0),
+        _ if slug.contains("gemini-2.5-flash-lite") => pricing(0.1, 0.01, 0.4),
+        _ if slug.contains("gemini-2.5-flash") => pricing(0.3, 0.03, 2.5),
+        _ if slug.contains("gemini-2.0-flash-lite") => pricing(0.075, 0.075, 0.3),
+        _ if slug.contains("gemini-2.0-flash") => pricing(0.1, 0.025, 0.4),
+        _ if slug.contains("gemini-1.5-pro") => pricing(1.25, 0.3125, 5.0),
+        _ if slug.contains("gemini-1.5-flash") => pricing(0.075, 0.01875, 0.3),
+        // https://aws.amazon.com/bedrock/pricing/
+        _ if slug.contains("nova-premier") => pricing(2.5, 0.625, 12.5),
+        _ if slug.contains("nova-pro") => pricing(0.8, 0.2, 3.2),
+        _ if slug.contains("nova-lite") => pricing(0.06, 0.015, 0.24),
+        _ if slug.contains("nova-micro") => pricing(0.035, 0.00875, 0.14),
+        _ => None,
+    }
+}
@@ -5,11 +5,39 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::batch_edit::BATCH_EDIT_TOOL_NAME;
+use crate::browser::BROWSER_TOOL_NAME;
+use crate::code_search::CODE_SEARCH_TOOL_NAME;
+use crate::code_symbols::CODE_SYMBOLS_TOOL_NAME;
+use crate::config::Config;
+use crate::config_types::CustomToolConfig;
+use crate::config_types::ToolFilter;
+use crate::database_query::DATABASE_QUERY_TOOL_NAME;
+use crate::dependency_audit::AUDIT_DEPENDENCIES_TOOL_NAME;
+use crate::extract_archive::EXTRACT_ARCHIVE_TOOL_NAME;
+use crate::git_tool::GIT_TOOL_NAME;
+use crate::http_request::HTTP_REQUEST_TOOL_NAME;
+use crate::lint::LINT_TOOL_NAME;
+use crate::list_ports::LIST_PORTS_TOOL_NAME;
+use crate::mcp_resources::MCP_RESOURCES_TOOL_NAME;
+use crate::memory::MEMORY_TOOL_NAME;
 use crate::model_family::ModelFamily;
+use crate::pinned_context::PIN_CONTEXT_TOOL_NAME;
 use crate::plan_tool::PLAN_TOOL;
+use crate::plugins::PluginManager;
+use crate::python_kernel::PYTHON_TOOL_NAME;
+use crate::read_file::READ_FILE_TOOL_NAME;
+use crate::secret_scan::SCAN_SECRETS_TOOL_NAME;
+use crate::session_diff::REVIEW_CHANGES_TOOL_NAME;
+use crate::sub_agent::DELEGATE_TASK_TOOL_NAME;
+use crate::system_info::SYSTEM_INFO_TOOL_NAME;
+use crate::test_runner::RUN_TESTS_TOOL_NAME;
 use crate::tool_apply_patch::ApplyPatchToolType;
 use crate::tool_apply_patch::create_apply_patch_freeform_tool;
 use crate::tool_apply_patch::create_apply_patch_json_tool;
+use crate::web_fetch::WEB_FETCH_TOOL_NAME;
+use crate::web_search::WEB_SEARCH_TOOL_NAME;
+use crate::write_file::WRITE_FILE_TOOL_NAME;
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
 pub struct ResponsesApiTool {
@@ -68,6 +96,30 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    pub delegate_tool: bool,
+    pub memory_tool: bool,
+    pub pin_context_tool: bool,
+    pub web_fetch_tool: bool,
+    pub web_search_tool: bool,
+    pub code_search_tool: bool,
+    pub read_file_tool: bool,
+    pub write_file_tool: bool,
+    pub git_tool: bool,
+    pub database_tool: bool,
+    pub http_request_tool: bool,
+    pub browser_tool: bool,
+    pub python_tool: bool,
+    pub system_info_tool: bool,
+    pub code_symbols_tool: bool,
+    pub run_tests_tool: bool,
+    pub lint_tool: bool,
+    pub audit_dependencies_tool: bool,
+    pub review_changes_tool: bool,
+    pub scan_secrets_tool: bool,
+    pub list_ports_tool: bool,
+    pub extract_archive_tool: bool,
+    pub batch_edit_tool: bool,
+    pub mcp_resources_tool: bool,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -78,6 +130,108 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) include_delegate_tool: bool,
+    pub(crate) include_memory_tool: bool,
+    pub(crate) include_pin_context_tool: bool,
+    pub(crate) include_web_fetch_tool: bool,
+    pub(crate) include_web_search_tool: bool,
+    pub(crate) include_code_search_tool: bool,
+    pub(crate) include_read_file_tool: bool,
+    pub(crate) include_write_file_tool: bool,
+    pub(crate) include_git_tool: bool,
+    pub(crate) include_database_tool: bool,
+    pub(crate) include_http_request_tool: bool,
+    pub(crate) include_browser_tool: bool,
+    pub(crate) include_python_tool: bool,
+    pub(crate) include_system_info_tool: bool,
+    pub(crate) include_code_symbols_tool: bool,
+    pub(crate) include_run_tests_tool: bool,
+    pub(crate) include_lint_tool: bool,
+    pub(crate) include_audit_dependencies_tool: bool,
+    pub(crate) include_review_changes_tool: bool,
+    pub(crate) include_scan_secrets_tool: bool,
+    pub(crate) include_list_ports_tool: bool,
+    pub(crate) include_extract_archive_tool: bool,
+    pub(crate) include_batch_edit_tool: bool,
+    pub(crate) include_mcp_resources_tool: bool,
+}
+
+impl<'a> ToolsConfigParams<'a> {
+    /// The params of a model of `model_family` offered none of the optional
+    /// tools.
+    pub(crate) fn new(model_family: &'a ModelFamily) -> Self {
+        Self {
+            model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        }
+    }
+
+    /// The params of a model of `model_family` offered the tools `config`
+    /// enables.
+    pub(crate) fn from_config(config: &'a Config, model_family: &'a ModelFamily) -> Self {
+        Self {
+            model_family,
+            include_plan_tool: config.include_plan_tool,
+            include_apply_patch_tool: config.include_apply_patch_tool,
+            include_web_search_request: config.tools_web_search_request,
+            use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+            include_view_image_tool: config.include_view_image_tool,
+            experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            include_delegate_tool: config.sub_agents.enabled,
+            include_memory_tool: config.memory.enabled,
+            include_pin_context_tool: config.include_pin_context_tool,
+            include_web_fetch_tool: config.web_fetch.enabled,
+            include_web_search_tool: config.web_search.provider.is_some(),
+            include_code_search_tool: config.include_code_search_tool,
+            include_read_file_tool: config.include_read_file_tool,
+            include_write_file_tool: config.include_write_file_tool,
+            include_git_tool: config.include_git_tool,
+            include_database_tool: config.include_database_tool,
+            include_http_request_tool: !config.http_request.allowed_hosts.is_empty(),
+            include_browser_tool: config.include_browser_tool,
+            include_python_tool: config.include_python_tool,
+            include_system_info_tool: config.include_system_info_tool,
+            include_code_symbols_tool: config.include_code_symbols_tool,
+            include_run_tests_tool: config.include_run_tests_tool,
+            include_lint_tool: !config.linters.is_empty(),
+            include_audit_dependencies_tool: config.include_audit_dependencies_tool,
+            include_review_changes_tool: config.include_review_changes_tool,
+            include_scan_secrets_tool: config.include_scan_secrets_tool,
+            include_list_ports_tool: config.include_list_ports_tool,
+            include_extract_archive_tool: config.extract_archive.enabled,
+            include_batch_edit_tool: config.include_batch_edit_tool,
+            include_mcp_resources_tool: config.mcp_resources.enabled,
+        }
+    }
 }
 
 impl ToolsConfig {
@@ -90,6 +244,30 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            include_delegate_tool,
+            include_memory_tool,
+            include_pin_context_tool,
+            include_web_fetch_tool,
+            include_web_search_tool,
+            include_code_search_tool,
+            include_read_file_tool,
+            include_write_file_tool,
+            include_git_tool,
+            include_database_tool,
+            include_http_request_tool,
+            include_browser_tool,
+            include_python_tool,
+            include_system_info_tool,
+            include_code_symbols_tool,
+            include_run_tests_tool,
+            include_lint_tool,
+            include_audit_dependencies_tool,
+            include_review_changes_tool,
+            include_scan_secrets_tool,
+            include_list_ports_tool,
+            include_extract_archive_tool,
+            include_batch_edit_tool,
+            include_mcp_resources_tool,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::Streamable
@@ -118,6 +296,30 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            delegate_tool: *include_delegate_tool,
+            memory_tool: *include_memory_tool,
+            pin_context_tool: *include_pin_context_tool,
+            web_fetch_tool: *include_web_fetch_tool,
+            web_search_tool: *include_web_search_tool,
+            code_search_tool: *include_code_search_tool,
+            read_file_tool: *include_read_file_tool,
+            write_file_tool: *include_write_file_tool,
+            git_tool: *include_git_tool,
+            database_tool: *include_database_tool,
+            http_request_tool: *include_http_request_tool,
+            browser_tool: *include_browser_tool,
+            python_tool: *include_python_tool,
+            system_info_tool: *include_system_info_tool,
+            code_symbols_tool: *include_code_symbols_tool,
+            run_tests_tool: *include_run_tests_tool,
+            lint_tool: *include_lint_tool,
+            audit_dependencies_tool: *include_audit_dependencies_tool,
+            review_changes_tool: *include_review_changes_tool,
+            scan_secrets_tool: *include_scan_secrets_tool,
+            list_ports_tool: *include_list_ports_tool,
+            extract_archive_tool: *include_extract_archive_tool,
+            batch_edit_tool: *include_batch_edit_tool,
+            mcp_resources_tool: *include_mcp_resources_tool,
         }
     }
 }
@@ -353,18 +555,978 @@ fn create_view_image_tool() -> OpenAiTool {
     );
 
     OpenAiTool::Function(ResponsesApiTool {
-        name: "view_image".to_string(),
-        description:
-            "Attach a local image (by filesystem path) to the conversation context for this turn."
-                .to_string(),
+        name: "view_image".to_string(),
+        description:
+            "Attach a local image (by filesystem path), such as a screenshot or design mock, to the conversation context for this turn. Large images are downscaled to fit the size limits."
+                .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_delegate_task_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "task".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Self-contained description of the task, including any context the sub-agent needs."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "allow_writes".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Let the sub-agent modify files within the current sandbox. Defaults to read-only."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_turns".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Optional cap on the number of model requests the sub-agent may make.".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: DELEGATE_TASK_TOOL_NAME.to_string(),
+        description: "Delegate a focused task (for example, investigating a failing test) to a sub-agent with its own context. Returns the sub-agent's final summary.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["task".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_memory_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Action to perform. Supported actions: list, recall, set, delete, note (alias: store), forget_note (alias: forget)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "key".to_string(),
+        JsonSchema::String {
+            description: Some("Entry name for set and delete, e.g. \"test_command\".".to_string()),
+        },
+    );
+    properties.insert(
+        "value".to_string(),
+        JsonSchema::String {
+            description: Some("Entry value for set.".to_string()),
+        },
+    );
+    properties.insert(
+        "text".to_string(),
+        JsonSchema::String {
+            description: Some("Free-text note for note.".to_string()),
+        },
+    );
+    properties.insert(
+        "index".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Index of the note to remove with forget_note, as shown by list and recall."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Keywords to look for with recall, e.g. \"database migrations\". Memories are matched by the words they share with the query, not by meaning."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Maximum number of memories recall returns (default: 5).".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: MEMORY_TOOL_NAME.to_string(),
+        description: "Read and update the persistent memory for this project. Memory survives across sessions and is shown to future sessions, so record durable project conventions (build and test commands, code style, gotchas), not task progress. Only part of a large memory is shown up front; use recall to search the rest before relying on your own guesses.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_web_fetch_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some("The http or https URL to fetch.".to_string()),
+        },
+    );
+    properties.insert(
+        "raw".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Return the body as is instead of the readable text extracted from HTML."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: WEB_FETCH_TOOL_NAME.to_string(),
+        description: "Fetch a web page or other text resource, such as documentation or an API reference, and return its readable text. Prefer this over running curl.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["url".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_web_search_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some("The search query.".to_string()),
+        },
+    );
+    properties.insert(
+        "count".to_string(),
+        JsonSchema::Number {
+            description: Some("Maximum number of results to return.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: WEB_SEARCH_TOOL_NAME.to_string(),
+        description: "Search the web and return ranked results with their URLs and snippets. Use web_fetch to read a result in full.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["query".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_code_search_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some("Regular expression matched against each line.".to_string()),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "File or directory to search, relative to the working directory (default: the working directory)."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "globs".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Only search files matching these globs, such as \"*.rs\". Prefix a glob with ! to exclude matching files."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "case_insensitive".to_string(),
+        JsonSchema::Boolean {
+            description: Some("Match case-insensitively.".to_string()),
+        },
+    );
+    properties.insert(
+        "context_lines".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Number of lines to include before and after each match (max 10).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_results".to_string(),
+        JsonSchema::Number {
+            description: Some("Maximum number of matches to return (default: 100).".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: CODE_SEARCH_TOOL_NAME.to_string(),
+        description: "Search file contents with a regular expression, like ripgrep. Files ignored by .gitignore and hidden files are skipped. Returns JSON matches with their path, line number and context.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["pattern".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_read_file_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Path of the file, absolute or relative to the working directory.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "offset".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "1-based line number to start reading from (default: 1).".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "limit".to_string(),
+        JsonSchema::Number {
+            description: Some("Maximum number of lines to return (default: 500).".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_FILE_TOOL_NAME.to_string(),
+        description: "Read a range of lines from a text file. Returns the lines with their line numbers, the file's total line count and its encoding. Prefer this over printing whole files with cat.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_write_file_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Path of the file, absolute or relative to the working directory.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "content".to_string(),
+        JsonSchema::String {
+            description: Some("The complete new content of the file.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: WRITE_FILE_TOOL_NAME.to_string(),
+        description: "Create a file or replace its entire content. Use this for new or generated files; prefer apply_patch for edits to existing files.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string(), "content".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_git_tool() -> OpenAiTool {
+    let string = |description: &str| JsonSchema::String {
+        description: Some(description.to_string()),
+    };
+    let boolean = |description: &str| JsonSchema::Boolean {
+        description: Some(description.to_string()),
+    };
+    let number = |description: &str| JsonSchema::Number {
+        description: Some(description.to_string()),
+    };
+    let properties = BTreeMap::from([
+        (
+            "action".to_string(),
+            string("One of: status, diff, log, blame, branch, stage_hunks, commit, push, reset."),
+        ),
+        ("staged".to_string(), boolean("diff: show staged changes.")),
+        (
+            "paths".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::String { description: None }),
+                description: Some("diff: limit the diff to these paths.".to_string()),
+            },
+        ),
+        (
+            "path".to_string(),
+            string("log: only commits touching this path. blame, stage_hunks: the file."),
+        ),
+        (
+            "max_count".to_string(),
+            number("log: number of commits (default: 20)."),
+        ),
+        ("start_line".to_string(), number("blame: first line.")),
+        ("end_line".to_string(), number("blame: last line.")),
+        (
+            "hunks".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::Number { description: None }),
+                description: Some(
+                    "stage_hunks: indices of the hunks, as returned by diff, to stage.".to_string(),
+                ),
+            },
+        ),
+        (
+            "message".to_string(),
+            string("commit: the message. Omit to generate one from the staged changes."),
+        ),
+        ("remote".to_string(), string("push: the remote.")),
+        ("branch".to_string(), string("push: the branch.")),
+        ("force".to_string(), boolean("push: force with lease.")),
+        (
+            "target".to_string(),
+            string("reset: the commit to reset to (default: HEAD)."),
+        ),
+        (
+            "hard".to_string(),
+            boolean("reset: also discard working tree changes."),
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: GIT_TOOL_NAME.to_string(),
+        description: "Run a git operation in the current repository and get a structured JSON result. push and reset with hard need the user's approval.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_database_query_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "query".to_string(),
+        JsonSchema::String {
+            description: Some("A single read-only SQL statement.".to_string()),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Path of a SQLite database file, absolute or relative to the working directory."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "database".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Name of a database connection configured by the user, instead of path."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "max_rows".to_string(),
+        JsonSchema::Number {
+            description: Some("Maximum number of rows to return (default: 200).".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: DATABASE_QUERY_TOOL_NAME.to_string(),
+        description: "Run a read-only SQL query against a SQLite file or a configured database and get the columns and typed rows as JSON. Use it to explore schemas, e.g. with sqlite_master or information_schema.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["query".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_http_request_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The http or https URL. Its host must be one the user allowed.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "method".to_string(),
+        JsonSchema::String {
+            description: Some("The HTTP method (default: GET).".to_string()),
+        },
+    );
+    properties.insert(
+        "headers".to_string(),
+        JsonSchema::Object {
+            properties: BTreeMap::new(),
+            required: None,
+            additional_properties: Some(JsonSchema::String { description: None }.into()),
+        },
+    );
+    properties.insert(
+        "body".to_string(),
+        JsonSchema::String {
+            description: Some("The request body.".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: HTTP_REQUEST_TOOL_NAME.to_string(),
+        description: "Send an HTTP request to an allowed host, such as a local dev server, and get the status, headers and body as JSON. The body is cut at a size limit.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["url".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_browser_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "action".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "One of: navigate, snapshot, click, screenshot.".to_string(),
+                ),
+            },
+        ),
+        (
+            "url".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "navigate: the http or https URL. Only localhost unless network access is enabled."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "selector".to_string(),
+            JsonSchema::String {
+                description: Some("click: CSS selector of the element to click.".to_string()),
+            },
+        ),
+        (
+            "full_page".to_string(),
+            JsonSchema::Boolean {
+                description: Some(
+                    "screenshot: capture the whole page instead of the viewport.".to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: BROWSER_TOOL_NAME.to_string(),
+        description: "Drive a headless browser to check that a page renders, e.g. one served by a dev server you started. navigate opens a URL, snapshot returns the page's accessibility tree, click clicks an element, and screenshot attaches an image of the page. The page stays open between calls.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_python_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "action".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "execute runs code; reset restarts the kernel, clearing its state.".to_string(),
+                ),
+            },
+        ),
+        (
+            "code".to_string(),
+            JsonSchema::String {
+                description: Some("execute: the Python code of the cell.".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: PYTHON_TOOL_NAME.to_string(),
+        description: "Run Python code in a kernel that keeps its variables, imports and loaded data between calls, like a notebook. Returns stdout, stderr, the value of the last expression and values passed to display(). matplotlib figures are attached as images. Prefer it over `python -c` for exploring data step by step.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_system_info_tool() -> OpenAiTool {
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SYSTEM_INFO_TOOL_NAME.to_string(),
+        description: "Describe the host: OS, architecture, CPU count, the versions of node, npm, python, rustc, cargo, go, java, docker and git (null when not installed), and the free memory and disk space of the working directory. Call it once instead of running `--version` commands.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties: BTreeMap::new(),
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_code_symbols_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "action".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "find_definition locates where `name` is defined; outline lists the symbols of the file `path`; enclosing returns the innermost symbol of `path` spanning `line`."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "name".to_string(),
+            JsonSchema::String {
+                description: Some("find_definition: the symbol name.".to_string()),
+            },
+        ),
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "File, or for find_definition a directory to search (default: the working directory), relative to the working directory."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "line".to_string(),
+            JsonSchema::Number {
+                description: Some("enclosing: the 1-based line number.".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: CODE_SYMBOLS_TOOL_NAME.to_string(),
+        description: "Parse Rust, Python, JavaScript, TypeScript and Go sources to find symbol definitions, outline a file or find the function enclosing a line. Returns symbol names, kinds and exact line ranges. Prefer it over grep for questions about where code is defined.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_run_tests_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "filter".to_string(),
+            JsonSchema::String {
+                description: Some("Only run tests whose name contains this.".to_string()),
+            },
+        ),
+        (
+            "tests".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::String { description: None }),
+                description: Some(
+                    "Run exactly these tests, named as in a previous report, e.g. to re-run its failures."
+                        .to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: RUN_TESTS_TOOL_NAME.to_string(),
+        description: "Run the project's tests (cargo test, pytest or jest) and return a JSON report: counts of passed, failed and skipped tests, each failure with its message, and the slowest tests. Prefer it over running the test command in the shell.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_lint_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "paths".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::String { description: None }),
+                description: Some(
+                    "Files to check, relative to the working directory. Defaults to the files changed in the git working tree."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "fix".to_string(),
+            JsonSchema::Boolean {
+                description: Some(
+                    "Apply the configured formatters' fixes as a patch before checking."
+                        .to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: LINT_TOOL_NAME.to_string(),
+        description: "Run the project's configured formatters and linters on the changed files and return their diagnostics as JSON, grouped by file and rule.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_audit_dependencies_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "manifest".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Cargo.toml, package.json or pyproject.toml to audit, relative to the working directory. Defaults to those in the working directory."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "include_up_to_date".to_string(),
+            JsonSchema::Boolean {
+                description: Some("Also list the dependencies that are up to date.".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: AUDIT_DEPENDENCIES_TOOL_NAME.to_string(),
+        description: "Audit the project's dependencies: returns JSON listing, for each manifest, the dependencies with a newer release (and whether it is a breaking upgrade) or known vulnerabilities (with the lowest version that fixes them). Use it before upgrading dependencies.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_review_changes_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([(
+        "paths".to_string(),
+        JsonSchema::Array {
+            items: Box::new(JsonSchema::String { description: None }),
+            description: Some(
+                "Only report these files, relative to the working directory.".to_string(),
+            ),
+        },
+    )]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: REVIEW_CHANGES_TOOL_NAME.to_string(),
+        description: "Return every change this session has made to the working tree as JSON: per file its status, added and removed line counts and unified-diff hunks. Use it to review your own work before finishing or committing.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_scan_secrets_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([(
+        "include_unstaged".to_string(),
+        JsonSchema::Boolean {
+            description: Some(
+                "Scan every uncommitted change instead of only the staged ones.".to_string(),
+            ),
+        },
+    )]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: SCAN_SECRETS_TOOL_NAME.to_string(),
+        description: "Scan the lines added by the staged changes for credentials: known API key and token formats, private keys, hard-coded passwords and high-entropy strings. Returns JSON findings with the path, line, rule and a masked excerpt. Run it before committing.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_list_ports_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([(
+        "port".to_string(),
+        JsonSchema::Number {
+            description: Some("Only report this port.".to_string()),
+        },
+    )]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: LIST_PORTS_TOOL_NAME.to_string(),
+        description: "List the local TCP ports that are listening and the UDP ports that are bound, as JSON with the protocol, address, port and the pid, name and command line of the owning process. Ports owned by one of your background processes name its process_id. Use it to find what holds a port when a server fails with \"address already in use\".".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: None,
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_extract_archive_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "The zip, tar or tar.gz archive, relative to the working directory."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "destination".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Directory to extract into, relative to the working directory. Defaults to the working directory."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "overwrite".to_string(),
+            JsonSchema::Boolean {
+                description: Some("Replace files that already exist.".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: EXTRACT_ARCHIVE_TOOL_NAME.to_string(),
+        description: "Extract a zip, tar or tar.gz archive and return the extracted files as JSON. Entries that would land outside the destination, archives over the size or file limits and links are refused. Use it instead of running `unzip` or `tar xf`.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["path".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_batch_edit_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "mode".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "\"regex\" to replace matches of a pattern, or \"rename\" to rename an identifier in code."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "pattern".to_string(),
+            JsonSchema::String {
+                description: Some("Regular expression to replace, for regex.".to_string()),
+            },
+        ),
+        (
+            "replacement".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Replacement text for regex. Refer to capture groups as $1 or ${name}."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "case_insensitive".to_string(),
+            JsonSchema::Boolean {
+                description: Some("Match the pattern case-insensitively.".to_string()),
+            },
+        ),
+        (
+            "from".to_string(),
+            JsonSchema::String {
+                description: Some("Identifier to rename, for rename.".to_string()),
+            },
+        ),
+        (
+            "to".to_string(),
+            JsonSchema::String {
+                description: Some("New name of the identifier, for rename.".to_string()),
+            },
+        ),
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "File or directory to edit, relative to the working directory (default: the working directory)."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "globs".to_string(),
+            JsonSchema::Array {
+                items: Box::new(JsonSchema::String { description: None }),
+                description: Some(
+                    "Only edit files matching these globs, such as \"*.rs\". Prefix a glob with ! to exclude matching files."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "dry_run".to_string(),
+            JsonSchema::Boolean {
+                description: Some(
+                    "Return the diff of every file without changing anything.".to_string(),
+                ),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: BATCH_EDIT_TOOL_NAME.to_string(),
+        description: "Make the same edit across many files in one call, approved once as a single patch. Rename mode renames a Rust, Python, JavaScript, TypeScript or Go identifier and leaves strings, comments and other names alone. Ignored and hidden files are skipped. Returns JSON with the replacements per file. Use it for mechanical refactors instead of patching each file.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["mode".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_mcp_resources_tool() -> OpenAiTool {
+    let properties = BTreeMap::from([
+        (
+            "action".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "\"list\" to list the resources of MCP servers, or \"read\" to read one."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "server".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "MCP server name. Required for read; list covers every server when omitted."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "uri".to_string(),
+            JsonSchema::String {
+                description: Some("URI of the resource to read, as listed.".to_string()),
+            },
+        ),
+    ]);
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: MCP_RESOURCES_TOOL_NAME.to_string(),
+        description: "List and read the resources, such as files or documentation pages, that configured MCP servers expose. List first, then read the ones you need. Returns JSON; text is capped and binary contents are omitted.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["action".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_pin_context_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "action".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Action to perform. Supported actions: list, pin_file, pin_note, unpin."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "path".to_string(),
+        JsonSchema::String {
+            description: Some("File to pin with pin_file.".to_string()),
+        },
+    );
+    properties.insert(
+        "text".to_string(),
+        JsonSchema::String {
+            description: Some("Note to pin with pin_note.".to_string()),
+        },
+    );
+    properties.insert(
+        "index".to_string(),
+        JsonSchema::Number {
+            description: Some(
+                "Index of the pin to remove with unpin, as shown by list.".to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: PIN_CONTEXT_TOOL_NAME.to_string(),
+        description: "Keep files or notes in your context for the rest of this session. Pinned files are re-read before every turn and pins survive history compaction, so pin the few documents you must keep consulting (design docs, specs), not every file you touch.".to_string(),
         strict: false,
         parameters: JsonSchema::Object {
             properties,
-            required: Some(vec!["path".to_string()]),
+            required: Some(vec!["action".to_string()]),
             additional_properties: Some(false.into()),
         },
     })
 }
+
 /// TODO(dylan): deprecate once we get rid of json tool
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
@@ -452,6 +1614,92 @@ pub(crate) fn mcp_tool_to_openai_tool(
     })
 }
 
+pub(crate) fn custom_tool_to_openai_tool(
+    name: &str,
+    tool: &CustomToolConfig,
+) -> Result<ResponsesApiTool, serde_json::Error> {
+    schema_to_openai_tool(name, &tool.description, tool.parameters.clone())
+}
+
+fn schema_to_openai_tool(
+    name: &str,
+    description: &str,
+    mut parameters: JsonValue,
+) -> Result<ResponsesApiTool, serde_json::Error> {
+    if let Some(map) = parameters.as_object_mut() {
+        map.entry("properties")
+            .or_insert_with(|| JsonValue::Object(serde_json::Map::new()));
+    }
+    sanitize_json_schema(&mut parameters);
+    Ok(ResponsesApiTool {
+        name: name.to_string(),
+        description: description.to_string(),
+        strict: false,
+        parameters: serde_json::from_value::<JsonSchema>(parameters)?,
+    })
+}
+
+/// Appends the user's `[custom_tools]`. A custom tool whose name is taken by
+/// one of the built-in tools in `tools` is left out, so that the built-in one
+/// keeps its name; see [`is_builtin_tool`].
+pub(crate) fn add_custom_tools(
+    tools: &mut Vec<OpenAiTool>,
+    custom_tools: &BTreeMap<String, CustomToolConfig>,
+) {
+    for (name, tool) in custom_tools {
+        if tools.iter().any(|t| tool_name(t) == name) {
+            tracing::warn!("custom tool {name:?} is shadowed by a built-in tool");
+            continue;
+        }
+        match custom_tool_to_openai_tool(name, tool) {
+            Ok(converted_tool) => tools.push(OpenAiTool::Function(converted_tool)),
+            Err(e) => {
+                tracing::error!("Failed to convert {name:?} custom tool to OpenAI tool: {e:?}");
+            }
+        }
+    }
+}
+
+/// Appends the tools of the session's WebAssembly plugins, leaving out those
+/// whose name is already in `tools`.
+pub(crate) fn add_plugin_tools(tools: &mut Vec<OpenAiTool>, plugins: &PluginManager) {
+    for (name, tool) in plugins.tools() {
+        if tools.iter().any(|t| tool_name(t) == name) {
+            tracing::warn!("plugin tool {name:?} is shadowed by another tool");
+            continue;
+        }
+        match schema_to_openai_tool(name, &tool.description, tool.parameters.clone()) {
+            Ok(converted_tool) => tools.push(OpenAiTool::Function(converted_tool)),
+            Err(e) => {
+                tracing::error!("Failed to convert {name:?} plugin tool to OpenAI tool: {e:?}");
+            }
+        }
+    }
+}
+
+/// Drops the tools the user's tool set or `disabled_tools` rule out, so the
+/// model never sees them.
+pub(crate) fn filter_tools(tools: &mut Vec<OpenAiTool>, filter: &ToolFilter) {
+    tools.retain(|tool| filter.allows(tool_name(tool)));
+}
+
+/// Whether `name` is one of the built-in tools of the turn, which take
+/// precedence over custom and plugin tools of the same name.
+pub(crate) fn is_builtin_tool(config: &ToolsConfig, name: &str) -> bool {
+    get_openai_tools(config, None)
+        .iter()
+        .any(|tool| tool_name(tool) == name)
+}
+
+fn tool_name(tool: &OpenAiTool) -> &str {
+    match tool {
+        OpenAiTool::Function(ResponsesApiTool { name, .. }) => name,
+        OpenAiTool::LocalShell {} => "local_shell",
+        OpenAiTool::WebSearch {} => "web_search",
+        OpenAiTool::Freeform(FreeformTool { name, .. }) => name,
+    }
+}
+
 /// Sanitize a JSON Schema (as serde_json::Value) so it can fit our limited
 /// JsonSchema enum. This function:
 /// - Ensures every schema object has a "type". If missing, infers it from
@@ -610,7 +1858,10 @@ pub(crate) fn get_openai_tools(
         }
     }
 
-    if config.web_search_request {
+    // A configured search provider takes the place of the hosted web search.
+    if config.web_search_tool {
+        tools.push(create_web_search_tool());
+    } else if config.web_search_request {
         tools.push(OpenAiTool::WebSearch {});
     }
 
@@ -618,12 +1869,107 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if config.delegate_tool {
+        tools.push(create_delegate_task_tool());
+    }
+
+    if config.memory_tool {
+        tools.push(create_memory_tool());
+    }
+
+    if config.pin_context_tool {
+        tools.push(create_pin_context_tool());
+    }
+
+    if config.web_fetch_tool {
+        tools.push(create_web_fetch_tool());
+    }
+
+    if config.code_search_tool {
+        tools.push(create_code_search_tool());
+    }
+
+    if config.read_file_tool {
+        tools.push(create_read_file_tool());
+    }
+
+    if config.write_file_tool {
+        tools.push(create_write_file_tool());
+    }
+
+    if config.git_tool {
+        tools.push(create_git_tool());
+    }
+
+    if config.database_tool {
+        tools.push(create_database_query_tool());
+    }
+
+    if config.http_request_tool {
+        tools.push(create_http_request_tool());
+    }
+
+    if config.browser_tool {
+        tools.push(create_browser_tool());
+    }
+
+    if config.python_tool {
+        tools.push(create_python_tool());
+    }
+
+    if config.system_info_tool {
+        tools.push(create_system_info_tool());
+    }
+
+    if config.code_symbols_tool {
+        tools.push(create_code_symbols_tool());
+    }
+
+    if config.run_tests_tool {
+        tools.push(create_run_tests_tool());
+    }
+
+    if config.lint_tool {
+        tools.push(create_lint_tool());
+    }
+
+    if config.audit_dependencies_tool {
+        tools.push(create_audit_dependencies_tool());
+    }
+
+    if config.review_changes_tool {
+        tools.push(create_review_changes_tool());
+    }
+
+    if config.scan_secrets_tool {
+        tools.push(create_scan_secrets_tool());
+    }
+
+    if config.list_ports_tool {
+        tools.push(create_list_ports_tool());
+    }
+
+    if config.extract_archive_tool {
+        tools.push(create_extract_archive_tool());
+    }
+    if config.batch_edit_tool {
+        tools.push(create_batch_edit_tool());
+    }
+    if config.mcp_resources_tool {
+        tools.push(create_mcp_resources_tool());
+    }
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
         entries.sort_by(|a, b| a.0.cmp(&b.0));
 
         for (name, tool) in entries.into_iter() {
+            // Aliased or unprefixed MCP tools can take the name of a built-in.
+            if tools.iter().any(|t| tool_name(t) == name) {
+                tracing::warn!("MCP tool {name:?} is shadowed by a built-in tool");
+                continue;
+            }
             match mcp_tool_to_openai_tool(name.clone(), tool.clone()) {
                 Ok(converted_tool) => tools.push(OpenAiTool::Function(converted_tool)),
                 Err(e) => {
@@ -645,15 +1991,7 @@ mod tests {
     use super::*;
 
     fn assert_eq_tool_names(tools: &[OpenAiTool], expected_names: &[&str]) {
-        let tool_names = tools
-            .iter()
-            .map(|tool| match tool {
-                OpenAiTool::Function(ResponsesApiTool { name, .. }) => name,
-                OpenAiTool::LocalShell {} => "local_shell",
-                OpenAiTool::WebSearch {} => "web_search",
-                OpenAiTool::Freeform(FreeformTool { name, .. }) => name,
-            })
-            .collect::<Vec<_>>();
+        let tool_names = tools.iter().map(tool_name).collect::<Vec<_>>();
 
         assert_eq!(
             tool_names.len(),
@@ -680,6 +2018,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -695,6 +2057,441 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_openai_tools_includes_delegate_task() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: true,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "background_process", "delegate_task"],
+        );
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_memory() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: true,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(&tools, &["unified_exec", "background_process", "memory"]);
+    }
+
+    #[test]
+    fn test_filter_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: true,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: true,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: true,
+            include_write_file_tool: true,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let all_tools = get_openai_tools(&config, None);
+
+        let mut tools = all_tools.clone();
+        filter_tools(
+            &mut tools,
+            &ToolFilter::new(
+                Some(&["read_*".to_string(), "update_plan".to_string()]),
+                &[],
+            ),
+        );
+        assert_eq_tool_names(&tools, &["update_plan", "read_file"]);
+
+        let mut tools = all_tools;
+        filter_tools(
+            &mut tools,
+            &ToolFilter::new(None, &["*_file".to_string(), "memory".to_string()]),
+        );
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "background_process", "update_plan"],
+        );
+    }
+
+    #[test]
+    fn test_add_custom_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: true,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let custom_tool = |parameters: JsonValue| CustomToolConfig {
+            description: "Apply pending migrations".to_string(),
+            parameters,
+            command: vec!["./migrate".to_string()],
+            cwd: None,
+            sandbox_mode: None,
+            timeout_sec: None,
+            output_schema: None,
+            config_schema: None,
+        };
+        let custom_tools = BTreeMap::from([
+            (
+                "memory".to_string(),
+                custom_tool(json!({ "type": "object" })),
+            ),
+            (
+                "run_migrations".to_string(),
+                custom_tool(json!({
+                    "type": "object",
+                    "properties": { "steps": { "type": "integer" } },
+                    "required": ["steps"],
+                })),
+            ),
+        ]);
+        let mut tools = get_openai_tools(&config, None);
+        add_custom_tools(&mut tools, &custom_tools);
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "unified_exec",
+                "background_process",
+                "memory",
+                "run_migrations",
+            ],
+        );
+        assert_eq!(
+            tools[3],
+            OpenAiTool::Function(ResponsesApiTool {
+                name: "run_migrations".to_string(),
+                description: "Apply pending migrations".to_string(),
+                strict: false,
+                parameters: JsonSchema::Object {
+                    properties: BTreeMap::from([(
+                        "steps".to_string(),
+                        JsonSchema::Number { description: None }
+                    )]),
+                    required: Some(vec!["steps".to_string()]),
+                    additional_properties: None,
+                },
+            })
+        );
+        assert!(!is_builtin_tool(&config, "run_migrations"));
+        assert!(is_builtin_tool(&config, "memory"));
+        assert!(!is_builtin_tool(&config, "shell"));
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_pin_context() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: true,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "background_process", "pin_context"],
+        );
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_web_fetch() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: true,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(&tools, &["unified_exec", "background_process", "web_fetch"]);
+    }
+
+    #[test]
+    fn test_get_openai_tools_includes_file_tools() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: true,
+            include_read_file_tool: true,
+            include_write_file_tool: true,
+            include_git_tool: true,
+            include_database_tool: true,
+            include_http_request_tool: false,
+            include_browser_tool: true,
+            include_python_tool: true,
+            include_system_info_tool: true,
+            include_code_symbols_tool: true,
+            include_run_tests_tool: true,
+            include_lint_tool: true,
+            include_audit_dependencies_tool: true,
+            include_review_changes_tool: true,
+            include_scan_secrets_tool: true,
+            include_list_ports_tool: true,
+            include_extract_archive_tool: true,
+            include_batch_edit_tool: true,
+            include_mcp_resources_tool: true,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(
+            &tools,
+            &[
+                "unified_exec",
+                "background_process",
+                "search_code",
+                "read_file",
+                "write_file",
+                "git",
+                "query_database",
+                "browser",
+                "python",
+                "system_info",
+                "code_symbols",
+                "run_tests",
+                "lint",
+                "audit_dependencies",
+                "review_changes",
+                "scan_secrets",
+                "list_ports",
+                "extract_archive",
+                "batch_edit",
+                "mcp_resources",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_web_search_provider_replaces_hosted_web_search() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: true,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: true,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+        let tools = get_openai_tools(&config, None);
+
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "background_process", "web_search"],
+        );
+        assert!(matches!(
+            tools.last(),
+            Some(OpenAiTool::Function(ResponsesApiTool { name, .. })) if name == WEB_SEARCH_TOOL_NAME
+        ));
+    }
+
     #[test]
     fn test_get_openai_tools_default_shell() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -706,6 +2503,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -732,6 +2553,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -837,6 +2682,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -961,6 +2830,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -1027,6 +2920,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_openai_tools_mcp_tools_shadowed_by_builtins_are_skipped() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: true,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
+        });
+
+        let tool = |description: &str| mcp_types::Tool {
+            name: "tool".to_string(),
+            input_schema: ToolInputSchema {
+                properties: Some(serde_json::json!({})),
+                required: None,
+                r#type: "object".to_string(),
+            },
+            output_schema: None,
+            title: None,
+            annotations: None,
+            description: Some(description.to_string()),
+        };
+        let tools_map: HashMap<String, mcp_types::Tool> = HashMap::from([
+            ("view_image".to_string(), tool("aliased over a built-in")),
+            ("search".to_string(), tool("unprefixed")),
+        ]);
+
+        let tools = get_openai_tools(&config, Some(tools_map));
+        assert_eq_tool_names(
+            &tools,
+            &["unified_exec", "background_process", "view_image", "search"],
+        );
+        let OpenAiTool::Function(view_image) = &tools[2] else {
+            panic!("view_image should be a function tool");
+        };
+        assert_ne!("aliased over a built-in", view_image.description);
+    }
+
     #[test]
     fn test_mcp_tool_property_missing_type_defaults_to_string() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -1038,6 +2996,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -1105,6 +3087,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -1167,6 +3173,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -1232,6 +3262,30 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
+            include_memory_tool: false,
+            include_pin_context_tool: false,
+            include_web_fetch_tool: false,
+            include_web_search_tool: false,
+            include_code_search_tool: false,
+            include_read_file_tool: false,
+            include_write_file_tool: false,
+            include_git_tool: false,
+            include_database_tool: false,
+            include_http_request_tool: false,
+            include_browser_tool: false,
+            include_python_tool: false,
+            include_system_info_tool: false,
+            include_code_symbols_tool: false,
+            include_run_tests_tool: false,
+            include_lint_tool: false,
+            include_audit_dependencies_tool: false,
+            include_review_changes_tool: false,
+            include_scan_secrets_tool: false,
+            include_list_ports_tool: false,
+            include_extract_archive_tool: false,
+            include_batch_edit_tool: false,
+            include_mcp_resources_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -0,0 +1,209 @@
+//! Files and notes pinned into the model's context.
+//!
+//! Pins are kept outside the conversation history. Before every turn they are
+//! rendered into a message at the start of the prompt, re-reading pinned
+//! files, so the model always sees their current contents and compaction can
+//! never drop them.
+
+use std::path::Path;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::ContextPin;
+use crate::truncate::truncate_middle;
+
+pub(crate) const PIN_CONTEXT_TOOL_NAME: &str = "pin_context";
+
+const PINNED_CONTEXT_OPEN_TAG: &str = "<pinned_context>";
+const PINNED_CONTEXT_CLOSE_TAG: &str = "</pinned_context>";
+
+/// Pinned files larger than this are shown truncated in the middle.
+const MAX_PINNED_FILE_BYTES: usize = 32 * 1024;
+
+/// `pin` with a relative file path resolved against `cwd`.
+pub(crate) fn resolve_pin(pin: ContextPin, cwd: &Path) -> ContextPin {
+    match pin {
+        ContextPin::File { path } => ContextPin::File {
+            path: cwd.join(path),
+        },
+        note @ ContextPin::Note { .. } => note,
+    }
+}
+
+/// Add `pin` unless it is already pinned. Returns whether it was added.
+pub(crate) fn add_pin(pins: &mut Vec<ContextPin>, pin: ContextPin) -> bool {
+    if pins.contains(&pin) {
+        return false;
+    }
+    pins.push(pin);
+    true
+}
+
+/// The message showing `pins` to the model, or `None` when nothing is pinned.
+pub(crate) async fn render_pinned_context(pins: &[ContextPin]) -> Option<ResponseItem> {
+    if pins.is_empty() {
+        return None;
+    }
+    let mut text = format!("{PINNED_CONTEXT_OPEN_TAG}\n");
+    for pin in pins {
+        match pin {
+            ContextPin::File { path } => match tokio::fs::read(path).await {
+                Ok(bytes) => {
+                    let contents = String::from_utf8_lossy(&bytes);
+                    let (contents, _) = truncate_middle(&contents, MAX_PINNED_FILE_BYTES);
+                    text.push_str(&format!(
+                        "<file path=\"{}\">\n{contents}\n</file>\n",
+                        path.display()
+                    ));
+                }
+                Err(e) => text.push_str(&format!(
+                    "<file path=\"{}\" error=\"{e}\" />\n",
+                    path.display()
+                )),
+            },
+            ContextPin::Note { text: note } => {
+                text.push_str(&format!("<note>\n{note}\n</note>\n"));
+            }
+        }
+    }
+    text.push_str(PINNED_CONTEXT_CLOSE_TAG);
+    Some(ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText { text }],
+    })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PinContextArgs {
+    List,
+    PinFile { path: String },
+    PinNote { text: String },
+    Unpin { index: usize },
+}
+
+/// Handle a call to the `pin_context` tool, updating `pins` in place.
+pub(crate) fn handle_pin_context_tool(
+    pins: &mut Vec<ContextPin>,
+    cwd: &Path,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: PinContextArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e}"))
+    })?;
+    match args {
+        PinContextArgs::List => Ok(list_pins(pins)),
+        PinContextArgs::PinFile { path } => {
+            let pin = resolve_pin(ContextPin::File { path: path.into() }, cwd);
+            let ContextPin::File { path } = &pin else {
+                unreachable!("resolve_pin keeps the pin kind");
+            };
+            if !path.is_file() {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{} is not a file",
+                    path.display()
+                )));
+            }
+            let message = format!("Pinned {}.", path.display());
+            add_pin(pins, pin);
+            Ok(message)
+        }
+        PinContextArgs::PinNote { text } => {
+            add_pin(pins, ContextPin::Note { text });
+            Ok("Pinned note.".to_string())
+        }
+        PinContextArgs::Unpin { index } => {
+            if index >= pins.len() {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "no pin with index {index}"
+                )));
+            }
+            pins.remove(index);
+            Ok(format!("Removed pin {index}."))
+        }
+    }
+}
+
+fn list_pins(pins: &[ContextPin]) -> String {
+    if pins.is_empty() {
+        return "Nothing is pinned.".to_string();
+    }
+    pins.iter()
+        .enumerate()
+        .map(|(index, pin)| match pin {
+            ContextPin::File { path } => format!("{index}: file {}", path.display()),
+            ContextPin::Note { text } => format!("{index}: note {text}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn call(pins: &mut Vec<ContextPin>, cwd: &Path, args: serde_json::Value) -> String {
+        handle_pin_context_tool(pins, cwd, &args.to_string()).expect("pin_context call succeeds")
+    }
+
+    #[tokio::test]
+    async fn pinned_files_are_reread_on_every_render() {
+        let cwd = TempDir::new().expect("tempdir");
+        let design = cwd.path().join("DESIGN.md");
+        std::fs::write(&design, "v1").expect("write");
+
+        let mut pins = Vec::new();
+        call(
+            &mut pins,
+            cwd.path(),
+            serde_json::json!({"action": "pin_file", "path": "DESIGN.md"}),
+        );
+        call(
+            &mut pins,
+            cwd.path(),
+            serde_json::json!({"action": "pin_note", "text": "keep the API stable"}),
+        );
+        // Pinning the same file again is a no-op.
+        call(
+            &mut pins,
+            cwd.path(),
+            serde_json::json!({"action": "pin_file", "path": "DESIGN.md"}),
+        );
+        assert_eq!(
+            format!("0: file {}\n1: note keep the API stable", design.display()),
+            call(&mut pins, cwd.path(), serde_json::json!({"action": "list"}))
+        );
+
+        std::fs::write(&design, "v2").expect("write");
+        let Some(ResponseItem::Message { content, .. }) = render_pinned_context(&pins).await else {
+            panic!("expected a pinned context message");
+        };
+        assert_eq!(
+            vec![ContentItem::InputText {
+                text: format!(
+                    "<pinned_context>\n<file path=\"{}\">\nv2\n</file>\n<note>\nkeep the API stable\n</note>\n</pinned_context>",
+                    design.display()
+                ),
+            }],
+            content
+        );
+
+        call(
+            &mut pins,
+            cwd.path(),
+            serde_json::json!({"action": "unpin", "index": 0}),
+        );
+        call(
+            &mut pins,
+            cwd.path(),
+            serde_json::json!({"action": "unpin", "index": 0}),
+        );
+        assert!(render_pinned_context(&pins).await.is_none());
+    }
+}
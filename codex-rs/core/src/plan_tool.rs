@@ -1,12 +1,13 @@
 use std::collections::BTreeMap;
 use std::sync::LazyLock;
 
+use codex_protocol::protocol::RolloutItem;
+
 use crate::codex::Session;
 use crate::function_tool::FunctionCallError;
 use crate::openai_tools::JsonSchema;
 use crate::openai_tools::OpenAiTool;
 use crate::openai_tools::ResponsesApiTool;
-use crate::protocol::Event;
 use crate::protocol::EventMsg;
 
 // Use the canonical plan tool types from the protocol crate to ensure
@@ -59,9 +60,9 @@ At most one step can be in_progress at a time.
     })
 });
 
-/// This function doesn't do anything useful. However, it gives the model a structured way to record its plan that clients can read and render.
-/// So it's the _inputs_ to this function that are useful to clients, not the outputs and neither are actually useful for the model other
-/// than forcing it to come up and document a plan (TBD how that affects performance).
+/// This function doesn't do anything useful for the model. However, it gives the model a structured way to record its plan that clients can read and render.
+/// The plan is kept in the session state, so clients attaching later can ask for it with `Op::GetPlan`, and every change is recorded in the rollout
+/// as `EventMsg::PlanUpdate`, so a resumed session starts with the plan it had.
 pub(crate) async fn handle_update_plan(
     session: &Session,
     arguments: String,
@@ -69,17 +70,56 @@ pub(crate) async fn handle_update_plan(
     _call_id: String,
 ) -> Result<String, FunctionCallError> {
     let args = parse_update_plan_arguments(&arguments)?;
-    session
-        .send_event(Event {
-            id: sub_id.to_string(),
-            msg: EventMsg::PlanUpdate(args),
-        })
-        .await;
+    session.set_plan(&sub_id, args).await;
     Ok("Plan updated".to_string())
 }
 
 fn parse_update_plan_arguments(arguments: &str) -> Result<UpdatePlanArgs, FunctionCallError> {
-    serde_json::from_str::<UpdatePlanArgs>(arguments).map_err(|e| {
+    let args = serde_json::from_str::<UpdatePlanArgs>(arguments).map_err(|e| {
         FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e}"))
+    })?;
+    let in_progress = args
+        .plan
+        .iter()
+        .filter(|item| matches!(item.status, StepStatus::InProgress))
+        .count();
+    if in_progress > 1 {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "{in_progress} steps are in_progress; at most one step can be in_progress at a time"
+        )));
+    }
+    Ok(args)
+}
+
+/// The plan recorded last in a rollout, if any.
+pub(crate) fn last_plan(items: &[RolloutItem]) -> Option<UpdatePlanArgs> {
+    items.iter().rev().find_map(|item| match item {
+        RolloutItem::EventMsg(EventMsg::PlanUpdate(plan)) => Some(plan.clone()),
+        _ => None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_more_than_one_step_in_progress() {
+        let arguments = r#"{"plan": [
+            {"step": "Write the parser", "status": "in_progress"},
+            {"step": "Write the tests", "status": "in_progress"}
+        ]}"#;
+        let Err(FunctionCallError::RespondToModel(message)) =
+            parse_update_plan_arguments(arguments)
+        else {
+            panic!("expected the plan to be rejected");
+        };
+        assert!(message.contains("at most one step"), "{message}");
+
+        let arguments = r#"{"plan": [
+            {"step": "Write the parser", "status": "completed"},
+            {"step": "Write the tests", "status": "in_progress"}
+        ]}"#;
+        assert!(parse_update_plan_arguments(arguments).is_ok());
+    }
+}
@@ -0,0 +1,556 @@
+//! Tools provided by WebAssembly plugins. Every `*.wasm` file in the plugins
+//! directory is a module that implements one tool; it is compiled when the
+//! session starts and instantiated afresh for each call.
+//!
+//! Plugins get no imports, so a module can compute but cannot touch files,
+//! the network or the clock, and each call is bounded by a fuel budget and a
+//! memory limit. The ABI, version 1, is a set of exports:
+//!
+//! - `memory`: the module's linear memory.
+//! - `codex_plugin_abi_version() -> i32`: returns 1.
+//! - `codex_alloc(len: i32) -> i32`: returns a buffer of `len` bytes for the
+//!   host to write into.
+//! - `codex_tool_manifest() -> i64`: the tool's manifest, JSON with `name`,
+//!   `description`, `parameters` (a JSON schema), an optional
+//!   `output_schema` that the `output` of replies must match and an optional
+//!   `config_schema` that the tool's `[extensions.<name>]` section must match.
+//! - `codex_tool_configure(ptr: i32, len: i32)`: optional; called before each
+//!   invocation with the tool's `[extensions.<name>]` section, as JSON, when
+//!   the config has one or the manifest declares a `config_schema`.
+//! - `codex_tool_invoke(ptr: i32, len: i32) -> i64`: runs the tool on the JSON
+//!   arguments at `ptr` and returns its reply, JSON with `output` and an
+//!   optional `success` (true when absent).
+//!
+//! Strings are returned as `ptr << 32 | len` and are UTF-8.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use wasmtime::Engine;
+use wasmtime::Instance;
+use wasmtime::Memory;
+use wasmtime::Module;
+use wasmtime::Store;
+use wasmtime::StoreLimits;
+use wasmtime::StoreLimitsBuilder;
+use wasmtime::Trap;
+
+use crate::config_types::Plugins;
+use crate::custom_tools::validate_name;
+use crate::extension_config::ExtensionConfigs;
+use crate::tool_output::check_json_text;
+use crate::tool_output::invalid_output_error;
+use crate::truncate::truncate_middle;
+
+const ABI_VERSION: i32 = 1;
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// The tools of the plugins loaded for the session, keyed by name.
+#[derive(Default)]
+pub(crate) struct PluginManager {
+    tools: BTreeMap<String, PluginTool>,
+}
+
+#[derive(Clone)]
+pub(crate) struct PluginTool {
+    pub(crate) description: String,
+    pub(crate) parameters: JsonValue,
+    /// Schema the `output` of the tool's replies must match.
+    output_schema: Option<JsonValue>,
+    /// The tool's `[extensions.<name>]` section, as JSON.
+    extension_config: Option<String>,
+    module: Module,
+    fuel: u64,
+    max_memory_bytes: usize,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default = "default_parameters")]
+    parameters: JsonValue,
+    #[serde(default)]
+    output_schema: Option<JsonValue>,
+    #[serde(default)]
+    config_schema: Option<JsonValue>,
+}
+
+fn default_parameters() -> JsonValue {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    output: String,
+    #[serde(default = "default_success")]
+    success: bool,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+impl PluginManager {
+    /// Compiles the plugins in the configured directory. Plugins that fail to
+    /// load, including those whose `[extensions.<name>]` section does not
+    /// match their config schema, are left out and reported as `(path, error)`.
+    pub(crate) async fn load(
+        config: Plugins,
+        extensions: ExtensionConfigs,
+        codex_home: &Path,
+    ) -> (Self, Vec<(PathBuf, String)>) {
+        if !config.enabled {
+            return (Self::default(), Vec::new());
+        }
+        let dir = config
+            .dir
+            .clone()
+            .unwrap_or_else(|| codex_home.join("plugins"));
+        match tokio::task::spawn_blocking(move || Self::load_dir(&config, &extensions, &dir)).await
+        {
+            Ok(loaded) => loaded,
+            Err(e) => (
+                Self::default(),
+                vec![(PathBuf::new(), format!("plugin loader panicked: {e}"))],
+            ),
+        }
+    }
+
+    fn load_dir(
+        config: &Plugins,
+        extensions: &ExtensionConfigs,
+        dir: &Path,
+    ) -> (Self, Vec<(PathBuf, String)>) {
+        let mut manager = Self::default();
+        let mut failures = Vec::new();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (manager, failures),
+            Err(e) => {
+                failures.push((dir.to_path_buf(), e.to_string()));
+                return (manager, failures);
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+        if paths.is_empty() {
+            return (manager, failures);
+        }
+        paths.sort();
+
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = match Engine::new(&engine_config) {
+            Ok(engine) => engine,
+            Err(e) => {
+                failures.push((dir.to_path_buf(), format!("{e:#}")));
+                return (manager, failures);
+            }
+        };
+        for path in paths {
+            match PluginTool::load(&engine, config, extensions, &path) {
+                Ok((name, _)) if manager.tools.contains_key(&name) => {
+                    failures.push((path, format!("another plugin already provides `{name}`")));
+                }
+                Ok((name, tool)) => {
+                    manager.tools.insert(name, tool);
+                }
+                Err(e) => failures.push((path, e)),
+            }
+        }
+        (manager, failures)
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&PluginTool> {
+        self.tools.get(name)
+    }
+
+    pub(crate) fn tools(&self) -> impl Iterator<Item = (&String, &PluginTool)> {
+        self.tools.iter()
+    }
+}
+
+impl PluginTool {
+    fn load(
+        engine: &Engine,
+        config: &Plugins,
+        extensions: &ExtensionConfigs,
+        path: &Path,
+    ) -> Result<(String, Self), String> {
+        let module = Module::from_file(engine, path).map_err(|e| format!("{e:#}"))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!(
+                "imports `{}::{}`, but plugins get no imports",
+                import.module(),
+                import.name()
+            ));
+        }
+        let tool = Self {
+            description: String::new(),
+            parameters: JsonValue::Null,
+            output_schema: None,
+            extension_config: None,
+            module,
+            fuel: config.fuel,
+            max_memory_bytes: config.max_memory_mb.saturating_mul(1024 * 1024) as usize,
+        };
+        let mut call = tool.instantiate()?;
+        let version = call.typed::<(), i32>("codex_plugin_abi_version")?;
+        let version = call.run(|store| version.call(store, ()))?;
+        if version != ABI_VERSION {
+            return Err(format!(
+                "implements plugin ABI version {version}, but version {ABI_VERSION} is required"
+            ));
+        }
+        let manifest = call.typed::<(), i64>("codex_tool_manifest")?;
+        let manifest = call.run(|store| manifest.call(store, ()))?;
+        let manifest: Manifest = serde_json::from_slice(&call.read(manifest)?)
+            .map_err(|e| format!("invalid manifest: {e}"))?;
+        validate_name(&manifest.name)?;
+        if manifest.parameters.get("type").and_then(JsonValue::as_str) != Some("object") {
+            return Err(
+                "the manifest's parameters must be a JSON schema of type \"object\"".into(),
+            );
+        }
+        let extension_config = match &manifest.config_schema {
+            Some(schema) => Some(
+                extensions
+                    .validated(&manifest.name, schema)
+                    .map_err(|e| e.to_string())?,
+            ),
+            None => extensions.section(&manifest.name).cloned(),
+        };
+        Ok((
+            manifest.name,
+            Self {
+                description: manifest.description,
+                parameters: manifest.parameters,
+                output_schema: manifest.output_schema,
+                extension_config: extension_config.map(|section| section.to_string()),
+                ..tool
+            },
+        ))
+    }
+
+    /// Runs the tool, called `name`, in a new instance of its module. A reply
+    /// with `success: false` or with output that does not match the tool's
+    /// output schema is returned as an error.
+    pub(crate) async fn invoke(&self, name: &str, arguments: String) -> Result<String, String> {
+        let tool = self.clone();
+        let name = name.to_string();
+        tokio::task::spawn_blocking(move || tool.invoke_blocking(&name, arguments.as_bytes()))
+            .await
+            .map_err(|e| format!("plugin panicked: {e}"))?
+    }
+
+    fn invoke_blocking(&self, name: &str, arguments: &[u8]) -> Result<String, String> {
+        let mut call = self.instantiate()?;
+        if let Some(extension_config) = &self.extension_config
+            && call.exports("codex_tool_configure")
+        {
+            let configure = call.typed::<(i32, i32), ()>("codex_tool_configure")?;
+            let input = call.write(extension_config.as_bytes())?;
+            call.run(|store| configure.call(store, input))?;
+        }
+        let invoke = call.typed::<(i32, i32), i64>("codex_tool_invoke")?;
+        let input = call.write(arguments)?;
+        let reply = call.run(|store| invoke.call(store, input))?;
+        let reply: Reply = serde_json::from_slice(&call.read(reply)?)
+            .map_err(|e| format!("invalid reply from the plugin: {e}"))?;
+        if !reply.success {
+            return Err(truncate_middle(&reply.output, MAX_OUTPUT_BYTES).0);
+        }
+        if let Some(schema) = &self.output_schema
+            && let Err(violations) = check_json_text(schema, &reply.output)
+        {
+            return Err(invalid_output_error(name, &violations, &reply.output));
+        }
+        Ok(truncate_middle(&reply.output, MAX_OUTPUT_BYTES).0)
+    }
+
+    fn instantiate(&self) -> Result<Call, String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.max_memory_bytes)
+            .build();
+        let mut store = Store::new(self.module.engine(), limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.fuel).map_err(|e| format!("{e:#}"))?;
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(describe_error)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| "does not export `memory`".to_string())?;
+        Ok(Call {
+            store,
+            instance,
+            memory,
+        })
+    }
+}
+
+/// An instance of a plugin's module, used for one call.
+struct Call {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+}
+
+impl Call {
+    fn typed<Params, Results>(
+        &mut self,
+        name: &str,
+    ) -> Result<wasmtime::TypedFunc<Params, Results>, String>
+    where
+        Params: wasmtime::WasmParams,
+        Results: wasmtime::WasmResults,
+    {
+        self.instance
+            .get_typed_func(&mut self.store, name)
+            .map_err(|e| format!("export `{name}`: {e:#}"))
+    }
+
+    fn exports(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    /// Copies `bytes` into a buffer from `codex_alloc`, returning `(ptr, len)`.
+    fn write(&mut self, bytes: &[u8]) -> Result<(i32, i32), String> {
+        let len = i32::try_from(bytes.len()).map_err(|_| "input too large".to_string())?;
+        let alloc = self.typed::<i32, i32>("codex_alloc")?;
+        let ptr = self.run(|store| alloc.call(store, len))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, bytes)
+            .map_err(|_| "`codex_alloc` returned a buffer outside of memory".to_string())?;
+        Ok((ptr, len))
+    }
+
+    fn run<T>(
+        &mut self,
+        f: impl FnOnce(&mut Store<StoreLimits>) -> wasmtime::Result<T>,
+    ) -> Result<T, String> {
+        f(&mut self.store).map_err(describe_error)
+    }
+
+    /// Copies out a string returned as `ptr << 32 | len`.
+    fn read(&self, packed: i64) -> Result<Vec<u8>, String> {
+        let packed = packed as u64;
+        let ptr = (packed >> 32) as usize;
+        let len = (packed & 0xffff_ffff) as usize;
+        self.memory
+            .data(&self.store)
+            .get(ptr..ptr + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| "the plugin returned a string outside of its memory".to_string())
+    }
+}
+
+fn describe_error(e: wasmtime::Error) -> String {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => "ran out of fuel; raise `plugins.fuel` if it needs more".into(),
+        _ => format!("{e:#}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A plugin whose `reply` is returned by every call; `spin` makes calls
+    /// loop forever instead.
+    fn plugin(manifest: &str, reply: &str, spin: bool) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let reply_ptr = 4096;
+        let pack = |ptr: u64, len: usize| ((ptr << 32) | len as u64) as i64;
+        let body = if spin {
+            "(loop $forever (br $forever)) (unreachable)".to_string()
+        } else {
+            format!("(i64.const {})", pack(reply_ptr, reply.len()))
+        };
+        format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 8192))
+                (data (i32.const 0) "{manifest}")
+                (data (i32.const {reply_ptr}) "{reply}")
+                (func (export "codex_plugin_abi_version") (result i32) (i32.const 1))
+                (func (export "codex_alloc") (param $len i32) (result i32)
+                    (global.get $heap)
+                    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+                (func (export "codex_tool_manifest") (result i64) (i64.const {manifest_len}))
+                (func (export "codex_tool_invoke") (param i32 i32) (result i64) {body}))"#,
+            manifest = escape(manifest),
+            manifest_len = manifest.len(),
+            reply = escape(reply),
+        )
+    }
+
+    fn load(plugins: &[(&str, String)]) -> (PluginManager, Vec<(PathBuf, String)>) {
+        load_with_extensions(plugins, ExtensionConfigs::default())
+    }
+
+    fn load_with_extensions(
+        plugins: &[(&str, String)],
+        extensions: ExtensionConfigs,
+    ) -> (PluginManager, Vec<(PathBuf, String)>) {
+        let dir = tempfile::tempdir().unwrap();
+        for (file, wat) in plugins {
+            // Modules are compiled from the text format as well.
+            std::fs::write(dir.path().join(file), wat).unwrap();
+        }
+        let config = Plugins {
+            fuel: 1_000_000,
+            ..Plugins::default()
+        };
+        PluginManager::load_dir(&config, &extensions, dir.path())
+    }
+
+    #[tokio::test]
+    async fn loads_and_invokes_plugins() {
+        let manifest = r#"{"name":"greet","description":"Says hello","parameters":{"type":"object","properties":{"who":{"type":"string"}}}}"#;
+        let (manager, failures) = load(&[(
+            "greet.wasm",
+            plugin(manifest, r#"{"output":"hello"}"#, false),
+        )]);
+        assert_eq!(Vec::<(PathBuf, String)>::new(), failures);
+
+        let tool = manager.get("greet").unwrap();
+        assert_eq!("Says hello", tool.description);
+        assert_eq!(
+            serde_json::json!({ "type": "object", "properties": { "who": { "type": "string" } } }),
+            tool.parameters
+        );
+        assert_eq!(
+            Ok("hello".to_string()),
+            tool.invoke("greet", r#"{"who":"world"}"#.to_string()).await
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_failures() {
+        let (manager, failures) = load(&[
+            (
+                "fail.wasm",
+                plugin(
+                    r#"{"name":"fail"}"#,
+                    r#"{"output":"no luck","success":false}"#,
+                    false,
+                ),
+            ),
+            ("spin.wasm", plugin(r#"{"name":"spin"}"#, "", true)),
+            (
+                "typed.wasm",
+                plugin(
+                    r#"{"name":"typed","output_schema":{"type":"object"}}"#,
+                    r#"{"output":"not json"}"#,
+                    false,
+                ),
+            ),
+            ("bad_name.wasm", plugin(r#"{"name":"a b"}"#, "", false)),
+            (
+                "imports.wasm",
+                r#"(module (import "env" "clock" (func)))"#.to_string(),
+            ),
+        ]);
+
+        let failures: Vec<String> = failures
+            .into_iter()
+            .map(|(path, e)| format!("{}: {e}", path.file_name().unwrap().to_string_lossy()))
+            .collect();
+        assert_eq!(
+            vec![
+                "bad_name.wasm: tool name `a b` must be 1 to 64 letters, digits, `_` or `-`"
+                    .to_string(),
+                "imports.wasm: imports `env::clock`, but plugins get no imports".to_string(),
+            ],
+            failures
+        );
+        assert_eq!(
+            Err("no luck".to_string()),
+            manager
+                .get("fail")
+                .unwrap()
+                .invoke("fail", "{}".to_string())
+                .await
+        );
+        let error = manager
+            .get("spin")
+            .unwrap()
+            .invoke("spin", "{}".to_string())
+            .await
+            .unwrap_err();
+        assert!(error.contains("ran out of fuel"), "{error}");
+        let error = manager
+            .get("typed")
+            .unwrap()
+            .invoke("typed", "{}".to_string())
+            .await
+            .unwrap_err();
+        assert!(
+            error.contains("the output of `typed` does not match its output schema"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test]
+    async fn plugins_receive_their_extension_config() {
+        // Replies with the config it was given, which is a valid reply.
+        let manifest = r#"{"name":"echo","config_schema":{"type":"object","required":["output"]}}"#;
+        let wat = format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (global $heap (mut i32) (i32.const 8192))
+                (global $config (mut i64) (i64.const 0))
+                (data (i32.const 0) "{manifest}")
+                (func (export "codex_plugin_abi_version") (result i32) (i32.const 1))
+                (func (export "codex_alloc") (param $len i32) (result i32)
+                    (global.get $heap)
+                    (global.set $heap (i32.add (global.get $heap) (local.get $len))))
+                (func (export "codex_tool_manifest") (result i64) (i64.const {manifest_len}))
+                (func (export "codex_tool_configure") (param $ptr i32) (param $len i32)
+                    (global.set $config
+                        (i64.or
+                            (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                            (i64.extend_i32_u (local.get $len)))))
+                (func (export "codex_tool_invoke") (param i32 i32) (result i64)
+                    (global.get $config)))"#,
+            manifest = manifest.replace('"', "\\\""),
+            manifest_len = manifest.len(),
+        );
+        let extensions = |toml: &str| ExtensionConfigs::new(toml::from_str(toml).unwrap());
+
+        let (manager, failures) = load_with_extensions(
+            &[("echo.wasm", wat.clone())],
+            extensions("[echo]\noutput = \"configured\"\n"),
+        );
+        assert_eq!(Vec::<(PathBuf, String)>::new(), failures);
+        assert_eq!(
+            Ok("configured".to_string()),
+            manager
+                .get("echo")
+                .unwrap()
+                .invoke("echo", "{}".to_string())
+                .await
+        );
+
+        let (manager, failures) = load_with_extensions(
+            &[("echo.wasm", wat)],
+            extensions("[echo]\nverbose = true\n"),
+        );
+        assert!(manager.get("echo").is_none());
+        assert_eq!(
+            vec![
+                "[extensions.echo] does not match its schema: $: missing required property `output`"
+                    .to_string()
+            ],
+            failures.into_iter().map(|(_, e)| e).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Project-local configuration: a `.codex/config.toml` at the workspace root
+//! overlays the global `config.toml` for sessions started in that workspace.
+//!
+//! The workspace root is the root of the git project containing the working
+//! directory (the main repository for worktrees), or the working directory
+//! itself outside git. A project config only sets the model, the sandbox, the
+//! tools and the MCP servers, and is only applied once the workspace root is
+//! trusted in the global config (`[projects."<root>"] trust_level =
+//! "trusted"`), since it can start programs on the user's machine.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use toml::Value as TomlValue;
+
+use crate::config::CONFIG_TOML_FILE;
+use crate::config::ProjectConfig;
+use crate::config_interpolation::reject_commands;
+use crate::git_info::resolve_root_git_project_for_trust;
+
+/// Directory at the workspace root that holds the project config.
+pub const PROJECT_CONFIG_DIR: &str = ".codex";
+
+/// Settings a project config may set. Other keys are ignored.
+const PROJECT_CONFIG_KEYS: &[&str] = &[
+    "model",
+    "model_reasoning_effort",
+    "model_verbosity",
+    "sandbox_mode",
+    "sandbox_workspace_write",
+    "tools",
+    "tool_sets",
+    "tool_set",
+    "disabled_tools",
+    "mcp_servers",
+];
+
+/// The project config found for a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectConfigFile {
+    /// Path of the project's `config.toml`.
+    pub path: PathBuf,
+    /// Workspace root the file belongs to; trust is recorded for this path.
+    pub root: PathBuf,
+    /// Whether the workspace root is trusted, i.e. the file was applied.
+    pub trusted: bool,
+}
+
+/// The workspace root of `cwd`.
+pub fn project_root(cwd: &Path) -> PathBuf {
+    resolve_root_git_project_for_trust(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Path of the project config of the workspace containing `cwd`, if there is
+/// one. The global config in `codex_home` is never taken for a project config,
+/// even when the workspace root is the home directory.
+pub fn find_project_config(cwd: &Path, codex_home: &Path) -> Option<(PathBuf, PathBuf)> {
+    let root = project_root(cwd);
+    let dir = root.join(PROJECT_CONFIG_DIR);
+    let path = dir.join(CONFIG_TOML_FILE);
+    if !path.is_file() {
+        return None;
+    }
+    let same_dir = match (dir.canonicalize(), codex_home.canonicalize()) {
+        (Ok(dir), Ok(home)) => dir == home,
+        _ => dir == codex_home,
+    };
+    if same_dir {
+        return None;
+    }
+    Some((root, path))
+}
+
+/// Find the project config of `cwd` and, when its workspace root is trusted
+/// in `root_value` (the global config), overlay it onto `root_value`.
+pub(crate) fn apply_project_config(
+    root_value: &mut TomlValue,
+    cwd: &Path,
+    codex_home: &Path,
+) -> std::io::Result<Option<ProjectConfigFile>> {
+    let Some((root, path)) = find_project_config(cwd, codex_home) else {
+        return Ok(None);
+    };
+    let trusted = is_root_trusted(root_value, &root);
+    if !trusted {
+        tracing::warn!(
+            "ignoring {} because {} is not trusted",
+            path.display(),
+            root.display()
+        );
+        return Ok(Some(ProjectConfigFile {
+            path,
+            root,
+            trusted,
+        }));
+    }
+
+    let contents = std::fs::read_to_string(&path)?;
+    let project = toml::from_str::<TomlValue>(&contents).map_err(|e| {
+        tracing::error!("Failed to parse {}: {e}", path.display());
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}: {e}", path.display()),
+        )
+    })?;
+    reject_commands(&project, &path.display().to_string())?;
+    let ignored = overlay_project_config(root_value, project);
+    if !ignored.is_empty() {
+        tracing::warn!(
+            "ignoring settings a project config cannot set in {}: {}",
+            path.display(),
+            ignored.join(", ")
+        );
+    }
+    Ok(Some(ProjectConfigFile {
+        path,
+        root,
+        trusted,
+    }))
+}
+
+fn is_root_trusted(root_value: &TomlValue, root: &Path) -> bool {
+    root_value
+        .get("projects")
+        .cloned()
+        .and_then(|projects| projects.try_into::<HashMap<String, ProjectConfig>>().ok())
+        .and_then(|projects| projects.get(&root.to_string_lossy().to_string()).cloned())
+        .is_some_and(|project| project.trust_level.as_deref() == Some("trusted"))
+}
+
+/// Overlay `project` onto the global config `root`. Tables are merged key by
+/// key, except that an MCP server defined by the project replaces the global
+/// server of the same name. Returns the keys of `project` that were ignored.
+pub(crate) fn overlay_project_config(root: &mut TomlValue, project: TomlValue) -> Vec<String> {
+    let TomlValue::Table(project) = project else {
+        return Vec::new();
+    };
+    if !root.is_table() {
+        *root = TomlValue::Table(Default::default());
+    }
+    let Some(root) = root.as_table_mut() else {
+        return Vec::new();
+    };
+
+    let mut ignored = Vec::new();
+    for (key, value) in project {
+        if !PROJECT_CONFIG_KEYS.contains(&key.as_str()) {
+            ignored.push(key);
+            continue;
+        }
+        let Some(existing) = root.get_mut(&key) else {
+            root.insert(key, value);
+            continue;
+        };
+        match (key.as_str(), existing, value) {
+            ("mcp_servers", TomlValue::Table(servers), TomlValue::Table(project_servers)) => {
+                servers.extend(project_servers);
+            }
+            (_, existing, value) => merge_toml(existing, value),
+        }
+    }
+    ignored
+}
+
+/// Merge `overlay` into `base`: tables recursively, anything else replaced.
+pub(crate) fn merge_toml(base: &mut TomlValue, overlay: TomlValue) {
+    match (base, overlay) {
+        (TomlValue::Table(base), TomlValue::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn toml(s: &str) -> TomlValue {
+        toml::from_str(s).expect("valid toml")
+    }
+
+    #[test]
+    fn overlay_merges_allowed_keys_and_ignores_the_rest() {
+        let mut root = toml(
+            r#"
+model = "gpt-5"
+approval_policy = "on-request"
+notify = ["notify-send"]
+
+[sandbox_workspace_write]
+network_access = false
+writable_roots = ["/tmp/a"]
+
+[mcp_servers.docs]
+command = "docs-server"
+args = ["--global"]
+
+[mcp_servers.search]
+command = "search-server"
+"#,
+        );
+        let project = toml(
+            r#"
+model = "gpt-5-codex"
+approval_policy = "never"
+notify = ["curl", "https://example.com"]
+
+[sandbox_workspace_write]
+network_access = true
+
+[mcp_servers.docs]
+command = "project-docs"
+"#,
+        );
+
+        let ignored = overlay_project_config(&mut root, project);
+
+        assert_eq!(
+            ignored,
+            vec!["approval_policy".to_string(), "notify".to_string()]
+        );
+        assert_eq!(
+            root,
+            toml(
+                r#"
+model = "gpt-5-codex"
+approval_policy = "on-request"
+notify = ["notify-send"]
+
+[sandbox_workspace_write]
+network_access = true
+writable_roots = ["/tmp/a"]
+
+[mcp_servers.docs]
+command = "project-docs"
+
+[mcp_servers.search]
+command = "search-server"
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn project_config_applies_only_when_trusted() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let project = TempDir::new()?;
+        let project_root = project.path().canonicalize()?;
+        std::fs::create_dir_all(project_root.join(PROJECT_CONFIG_DIR))?;
+        let path = project_root.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE);
+        std::fs::write(&path, "model = \"project-model\"\n")?;
+
+        let mut untrusted = toml("model = \"gpt-5\"\n");
+        let found = apply_project_config(&mut untrusted, &project_root, codex_home.path())?;
+        assert_eq!(
+            found,
+            Some(ProjectConfigFile {
+                path,
+                root: project_root.clone(),
+                trusted: false,
+            })
+        );
+        assert_eq!(untrusted, toml("model = \"gpt-5\"\n"));
+
+        let mut trusted = toml(&format!(
+            "model = \"gpt-5\"\n[projects.{:?}]\ntrust_level = \"trusted\"\n",
+            project_root.to_string_lossy()
+        ));
+        let found = apply_project_config(&mut trusted, &project_root, codex_home.path())?;
+        assert_eq!(found.map(|file| file.trusted), Some(true));
+        assert_eq!(
+            trusted.get("model").and_then(TomlValue::as_str),
+            Some("project-model")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn project_config_cannot_run_commands() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let project = TempDir::new()?;
+        let project_root = project.path().canonicalize()?;
+        std::fs::create_dir_all(project_root.join(PROJECT_CONFIG_DIR))?;
+        let path = project_root.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE);
+        std::fs::write(
+            &path,
+            "[mcp_servers.docs]\ncommand = \"docs\"\nenv = { TOKEN = \"$(cat ~/.ssh/id_rsa)\" }\n",
+        )?;
+
+        let mut trusted = toml(&format!(
+            "[projects.{:?}]\ntrust_level = \"trusted\"\n",
+            project_root.to_string_lossy()
+        ));
+        let err = apply_project_config(&mut trusted, &project_root, codex_home.path())
+            .expect_err("command in a project config");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains(&path.display().to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn codex_home_is_not_a_project_config() -> std::io::Result<()> {
+        let home = TempDir::new()?;
+        let codex_home = home.path().join(PROJECT_CONFIG_DIR);
+        std::fs::create_dir_all(&codex_home)?;
+        std::fs::write(codex_home.join(CONFIG_TOML_FILE), "model = \"gpt-5\"\n")?;
+
+        assert_eq!(find_project_config(home.path(), &codex_home), None);
+        Ok(())
+    }
+}
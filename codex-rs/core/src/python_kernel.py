@@ -0,0 +1,119 @@
+# Driver of the `python` tool's kernel. Reads one JSON request per line on
+# stdin, runs it in a namespace kept between requests and writes one JSON reply
+# per line. Replies go to a duplicate of the original stdout; file descriptor 1
+# is pointed at stderr so output written below `sys.stdout` cannot corrupt
+# them.
+
+import ast
+import base64
+import io
+import json
+import linecache
+import os
+import sys
+import traceback
+
+protocol = os.fdopen(os.dup(1), "w", encoding="utf-8")
+os.dup2(2, 1)
+sys.stdout = io.TextIOWrapper(os.fdopen(1, "wb"), encoding="utf-8", line_buffering=True)
+
+namespace = {"__name__": "__main__", "__builtins__": __builtins__}
+outputs = []
+
+
+def rich_output(value):
+    for method, key in (("_repr_png_", "png"), ("_repr_markdown_", "text")):
+        render = getattr(value, method, None)
+        if render is None:
+            continue
+        try:
+            data = render()
+        except Exception:
+            continue
+        if data is None:
+            continue
+        if key == "png":
+            if isinstance(data, tuple):
+                data = data[0]
+            return {"png": base64.b64encode(data).decode("ascii")}
+        return {"text": data}
+    return {"text": repr(value)}
+
+
+def display(*values):
+    """Show values as outputs of the running cell, like IPython's display()."""
+    for value in values:
+        outputs.append(rich_output(value))
+
+
+namespace["display"] = display
+
+
+def collect_figures():
+    pyplot = sys.modules.get("matplotlib.pyplot")
+    if pyplot is None:
+        return
+    for number in pyplot.get_fignums():
+        buffer = io.BytesIO()
+        pyplot.figure(number).savefig(buffer, format="png", bbox_inches="tight")
+        outputs.append({"png": base64.b64encode(buffer.getvalue()).decode("ascii")})
+    pyplot.close("all")
+
+
+def execute(code):
+    # Lets tracebacks show the lines of the cell.
+    linecache.cache["<cell>"] = (len(code), None, code.splitlines(True), "<cell>")
+    tree = ast.parse(code, "<cell>", "exec")
+    last = None
+    if tree.body and isinstance(tree.body[-1], ast.Expr):
+        last = ast.Expression(tree.body.pop().value)
+    exec(compile(tree, "<cell>", "exec"), namespace)
+    if last is not None:
+        value = eval(compile(last, "<cell>", "eval"), namespace)
+        if value is not None:
+            namespace["_"] = value
+            outputs.append(rich_output(value))
+
+
+def run(code):
+    stdout, stderr = io.StringIO(), io.StringIO()
+    saved = sys.stdout, sys.stderr
+    sys.stdout, sys.stderr = stdout, stderr
+    error = None
+    try:
+        execute(code)
+    except BaseException as exc:  # KeyboardInterrupt included: the kernel lives on.
+        if isinstance(exc, SystemExit):
+            raise
+        # Leave the driver's own frames out of the traceback.
+        frames = exc.__traceback__
+        while frames is not None and frames.tb_frame.f_code.co_filename != "<cell>":
+            frames = frames.tb_next
+        error = {
+            "name": type(exc).__name__,
+            "message": str(exc),
+            "traceback": "".join(traceback.format_exception(type(exc), exc, frames)),
+        }
+    finally:
+        sys.stdout, sys.stderr = saved
+    try:
+        collect_figures()
+    except Exception:
+        pass
+    return {
+        "stdout": stdout.getvalue(),
+        "stderr": stderr.getvalue(),
+        "outputs": outputs,
+        "error": error,
+    }
+
+
+for line in sys.stdin:
+    try:
+        request = json.loads(line)
+    except ValueError:
+        continue
+    outputs = []
+    reply = run(request.get("code", ""))
+    protocol.write(json.dumps(reply) + "\n")
+    protocol.flush()
@@ -0,0 +1,477 @@
+//! The `python` tool, which runs code in a Python process kept for the whole
+//! session so that variables, imports and loaded data survive between calls.
+//! The process runs `python_kernel.py` under the session's sandbox and reports
+//! each cell's output, including the value of its last expression and any
+//! matplotlib figures, which are attached to the conversation as images.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::Deserialize;
+use serde::Serialize;
+use tempfile::TempDir;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::process::Child;
+use tokio::process::ChildStdin;
+use tokio::process::ChildStdout;
+use tokio::sync::Mutex;
+
+use crate::config_types::PythonKernel;
+use crate::exec::SandboxType;
+use crate::landlock::spawn_command_under_linux_sandbox;
+use crate::protocol::SandboxPolicy;
+use crate::safety::get_platform_sandbox;
+use crate::seatbelt::spawn_command_under_seatbelt;
+use crate::spawn::StdioPolicy;
+use crate::spawn::spawn_child_async;
+use crate::truncate::truncate_middle;
+use crate::util::find_executable;
+use crate::util::is_zero;
+
+pub(crate) const PYTHON_TOOL_NAME: &str = "python";
+
+const DRIVER: &str = include_str!("python_kernel.py");
+/// Looked up on `PATH` when `python.executable` is not set.
+const EXECUTABLE_CANDIDATES: &[&str] = &["python3", "python"];
+/// Time a cell is given to stop after being interrupted.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(5);
+const MAX_STREAM_BYTES: usize = 32 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum PythonArgs {
+    Execute { code: String },
+    Reset,
+}
+
+/// Where and how the kernel process is started.
+pub(crate) struct KernelSpawn<'a> {
+    pub(crate) config: &'a PythonKernel,
+    pub(crate) sandbox_policy: &'a SandboxPolicy,
+    pub(crate) cwd: &'a Path,
+    pub(crate) env: HashMap<String, String>,
+    pub(crate) codex_linux_sandbox_exe: Option<&'a PathBuf>,
+}
+
+pub(crate) struct PythonOutput {
+    /// JSON summary of the cell for the model.
+    pub(crate) text: String,
+    /// PNG files to attach to the conversation.
+    pub(crate) images: Vec<PathBuf>,
+}
+
+/// The session's kernel, started on first use.
+#[derive(Default)]
+pub(crate) struct PythonKernelManager {
+    kernel: Mutex<Option<Kernel>>,
+}
+
+impl PythonKernelManager {
+    pub(crate) async fn execute(
+        &self,
+        spawn: KernelSpawn<'_>,
+        code: &str,
+    ) -> Result<PythonOutput, String> {
+        let mut kernel = self.kernel.lock().await;
+        let mut notice = None;
+        if kernel
+            .as_ref()
+            .is_some_and(|kernel| &kernel.sandbox_policy != spawn.sandbox_policy)
+        {
+            *kernel = None;
+            notice = Some(
+                "the sandbox policy changed, so the kernel was restarted and its state is lost"
+                    .to_string(),
+            );
+        }
+        let running = match &mut *kernel {
+            Some(running) => running,
+            None => kernel.insert(Kernel::start(spawn).await?),
+        };
+        let result = running.execute(code, notice).await;
+        if running.exited {
+            *kernel = None;
+        }
+        result
+    }
+
+    /// Stop the kernel, if it is running. Returns whether it was.
+    pub(crate) async fn close(&self) -> bool {
+        self.kernel.lock().await.take().is_some()
+    }
+}
+
+#[derive(Deserialize)]
+struct KernelReply {
+    stdout: String,
+    stderr: String,
+    outputs: Vec<KernelOutput>,
+    error: Option<KernelError>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KernelOutput {
+    Text { text: String },
+    Png { png: String },
+}
+
+#[derive(Deserialize, Serialize)]
+struct KernelError {
+    name: String,
+    message: String,
+    traceback: String,
+}
+
+#[derive(Serialize)]
+struct CellOutput {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    stdout: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    stderr: String,
+    /// Displayed values, the cell's last expression included.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    outputs: Vec<String>,
+    #[serde(skip_serializing_if = "is_zero")]
+    images: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<KernelError>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notice: Option<String>,
+}
+
+/// Schema of the JSON a cell returns to the model, which [`CellOutput`]
+/// serializes to.
+pub(crate) fn python_output_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "stdout": { "type": "string" },
+            "stderr": { "type": "string" },
+            "outputs": { "type": "array", "items": { "type": "string" } },
+            "images": { "type": "integer", "minimum": 1 },
+            "error": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "message": { "type": "string" },
+                    "traceback": { "type": "string" },
+                },
+                "required": ["name", "message", "traceback"],
+            },
+            "notice": { "type": "string" },
+        },
+        "additionalProperties": false,
+    })
+}
+
+/// A running kernel process and the pipes to its driver.
+struct Kernel {
+    child: Child,
+    stdin: ChildStdin,
+    replies: Lines<BufReader<ChildStdout>>,
+    /// What the process wrote to its stderr outside of a cell's `sys.stderr`,
+    /// such as the output of subprocesses.
+    stray_output: Arc<StdMutex<String>>,
+    /// Policy the process was sandboxed with.
+    sandbox_policy: SandboxPolicy,
+    timeout: Duration,
+    exited: bool,
+    images: usize,
+    /// Figures of the session; removed when the kernel stops.
+    dir: TempDir,
+}
+
+impl Kernel {
+    async fn start(spawn: KernelSpawn<'_>) -> Result<Self, String> {
+        let executable = match &spawn.config.executable {
+            Some(executable) => executable.clone(),
+            None => find_executable(EXECUTABLE_CANDIDATES)
+                .ok_or_else(|| "no Python executable found; set `python.executable`".to_string())?,
+        };
+        let dir = tempfile::Builder::new()
+            .prefix("codex-python-")
+            .tempdir()
+            .map_err(|e| format!("failed to create the kernel's directory: {e}"))?;
+        let mut env = spawn.env;
+        // Render figures to images rather than windows.
+        env.insert("MPLBACKEND".to_string(), "Agg".to_string());
+        let command = vec![
+            executable.to_string_lossy().into_owned(),
+            "-c".to_string(),
+            DRIVER.to_string(),
+        ];
+        let cwd = spawn.cwd.to_path_buf();
+        let sandbox_type = match spawn.sandbox_policy {
+            SandboxPolicy::DangerFullAccess => SandboxType::None,
+            _ => get_platform_sandbox().ok_or_else(|| {
+                "the Python kernel runs sandboxed, but there is no sandbox on this platform"
+                    .to_string()
+            })?,
+        };
+        let child = match sandbox_type {
+            SandboxType::None => {
+                spawn_child_async(
+                    executable.clone(),
+                    command[1..].to_vec(),
+                    None,
+                    cwd,
+                    spawn.sandbox_policy,
+                    StdioPolicy::Piped,
+                    env,
+                )
+                .await
+            }
+            SandboxType::MacosSeatbelt => {
+                spawn_command_under_seatbelt(
+                    command,
+                    cwd,
+                    spawn.sandbox_policy,
+                    spawn.cwd,
+                    StdioPolicy::Piped,
+                    env,
+                )
+                .await
+            }
+            SandboxType::LinuxSeccomp => {
+                let Some(exe) = spawn.codex_linux_sandbox_exe else {
+                    return Err("codex-linux-sandbox executable not configured".to_string());
+                };
+                spawn_command_under_linux_sandbox(
+                    exe,
+                    command,
+                    cwd,
+                    spawn.sandbox_policy,
+                    spawn.cwd,
+                    StdioPolicy::Piped,
+                    env,
+                )
+                .await
+            }
+        };
+        let mut child =
+            child.map_err(|e| format!("failed to start {}: {e}", executable.display()))?;
+
+        let (Some(stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err("failed to connect to the Python kernel".to_string());
+        };
+        let stray_output = Arc::new(StdMutex::new(String::new()));
+        let sink = Arc::clone(&stray_output);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Ok(mut output) = sink.lock()
+                    && output.len() < MAX_STREAM_BYTES
+                {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            stdin,
+            replies: BufReader::new(stdout).lines(),
+            stray_output,
+            sandbox_policy: spawn.sandbox_policy.clone(),
+            timeout: Duration::from_secs(spawn.config.timeout_sec),
+            exited: false,
+            images: 0,
+            dir,
+        })
+    }
+
+    async fn execute(
+        &mut self,
+        code: &str,
+        mut notice: Option<String>,
+    ) -> Result<PythonOutput, String> {
+        let request = format!("{}\n", serde_json::json!({ "code": code }));
+        if let Err(e) = self.stdin.write_all(request.as_bytes()).await {
+            return Err(self.exited_error(&e.to_string()));
+        }
+        if self.stdin.flush().await.is_err() {
+            return Err(self.exited_error("its input was closed"));
+        }
+
+        let line = match tokio::time::timeout(self.timeout, self.replies.next_line()).await {
+            Ok(line) => line,
+            Err(_) => {
+                // Raise KeyboardInterrupt in the cell, keeping the kernel's state.
+                self.interrupt();
+                let secs = self.timeout.as_secs();
+                match tokio::time::timeout(INTERRUPT_GRACE, self.replies.next_line()).await {
+                    Ok(line) => {
+                        notice = Some(format!("the cell was interrupted after {secs}s"));
+                        line
+                    }
+                    Err(_) => {
+                        self.exited = true;
+                        return Err(format!(
+                            "the cell did not finish within {secs}s and ignored the interrupt; the kernel was stopped and its state is lost"
+                        ));
+                    }
+                }
+            }
+        };
+        let line = match line {
+            Ok(Some(line)) => line,
+            Ok(None) => return Err(self.exited_error("it closed its output")),
+            Err(e) => return Err(self.exited_error(&e.to_string())),
+        };
+        let reply: KernelReply = serde_json::from_str(&line)
+            .map_err(|e| format!("invalid reply from the Python kernel: {e}"))?;
+        self.render(reply, notice).await
+    }
+
+    async fn render(
+        &mut self,
+        reply: KernelReply,
+        notice: Option<String>,
+    ) -> Result<PythonOutput, String> {
+        let stray_output = self
+            .stray_output
+            .lock()
+            .map(|mut output| std::mem::take(&mut *output))
+            .unwrap_or_default();
+        let mut outputs = Vec::new();
+        let mut images = Vec::new();
+        for output in reply.outputs {
+            match output {
+                KernelOutput::Text { text } => {
+                    outputs.push(truncate_middle(&text, MAX_STREAM_BYTES).0)
+                }
+                KernelOutput::Png { png } => {
+                    let png = base64::engine::general_purpose::STANDARD
+                        .decode(png)
+                        .map_err(|e| format!("invalid image from the Python kernel: {e}"))?;
+                    self.images += 1;
+                    let path = self.dir.path().join(format!("figure-{}.png", self.images));
+                    tokio::fs::write(&path, png)
+                        .await
+                        .map_err(|e| format!("failed to save the figure: {e}"))?;
+                    images.push(path);
+                }
+            }
+        }
+        let cell = CellOutput {
+            stdout: truncate_middle(&reply.stdout, MAX_STREAM_BYTES).0,
+            stderr: truncate_middle(&(reply.stderr + &stray_output), MAX_STREAM_BYTES).0,
+            outputs,
+            images: images.len(),
+            error: reply.error,
+            notice,
+        };
+        let text = serde_json::to_string(&cell)
+            .map_err(|e| format!("failed to encode the cell output: {e}"))?;
+        Ok(PythonOutput { text, images })
+    }
+
+    fn interrupt(&self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            // SAFETY: signals a process this kernel owns and has not reaped.
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGINT);
+            }
+        }
+    }
+
+    fn exited_error(&mut self, reason: &str) -> String {
+        self.exited = true;
+        format!("the Python kernel exited ({reason}); its state is lost")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool_output::check_json_text;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use serde_json::json;
+
+    async fn run(manager: &PythonKernelManager, code: &str) -> Result<PythonOutput, String> {
+        let config = PythonKernel::default();
+        let cwd = std::env::temp_dir();
+        let env = std::env::vars().collect();
+        manager
+            .execute(
+                KernelSpawn {
+                    config: &config,
+                    sandbox_policy: &SandboxPolicy::DangerFullAccess,
+                    cwd: &cwd,
+                    env,
+                    codex_linux_sandbox_exe: None,
+                },
+                code,
+            )
+            .await
+    }
+
+    fn parse(output: &PythonOutput) -> Value {
+        serde_json::from_str(&output.text).unwrap()
+    }
+
+    #[tokio::test]
+    async fn keeps_state_between_cells() {
+        if find_executable(EXECUTABLE_CANDIDATES).is_none() {
+            return;
+        }
+        let manager = PythonKernelManager::default();
+
+        let output = run(&manager, "total = 40\nprint('set')\ntotal + 2")
+            .await
+            .unwrap();
+        assert_eq!(
+            json!({ "stdout": "set\n", "outputs": ["42"] }),
+            parse(&output)
+        );
+        assert_eq!(
+            Ok(()),
+            check_json_text(&python_output_schema(), &output.text)
+        );
+
+        let output = run(&manager, "total / 0").await.unwrap();
+        let error = &parse(&output)["error"];
+        assert_eq!("ZeroDivisionError", error["name"]);
+        assert!(
+            error["traceback"]
+                .as_str()
+                .unwrap()
+                .contains("File \"<cell>\", line 1"),
+            "{error}"
+        );
+
+        let output = run(&manager, "display('a', 1)\ntotal").await.unwrap();
+        assert_eq!(json!({ "outputs": ["'a'", "1", "40"] }), parse(&output));
+
+        assert!(manager.close().await);
+        let output = run(&manager, "'total' in globals()").await.unwrap();
+        assert_eq!(json!({ "outputs": ["False"] }), parse(&output));
+    }
+
+    #[tokio::test]
+    async fn reports_an_exited_kernel() {
+        if find_executable(EXECUTABLE_CANDIDATES).is_none() {
+            return;
+        }
+        let manager = PythonKernelManager::default();
+
+        let error = run(&manager, "import os\nos._exit(3)").await.err().unwrap();
+        assert!(error.contains("state is lost"), "{error}");
+        let output = run(&manager, "1 + 1").await.unwrap();
+        assert_eq!(json!({ "outputs": ["2"] }), parse(&output));
+    }
+}
@@ -0,0 +1,224 @@
+//! The `read_file` tool, which returns a numbered range of lines from a text
+//! file together with the file's line count and encoding, so the model can
+//! page through a large file instead of printing all of it.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const READ_FILE_TOOL_NAME: &str = "read_file";
+
+const DEFAULT_LIMIT: usize = 500;
+const MAX_LIMIT: usize = 2_000;
+/// Longer lines are cut to this many characters.
+const MAX_LINE_CHARS: usize = 2_000;
+/// Files with a NUL byte in this prefix are treated as binary.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct ReadFileArgs {
+    path: String,
+    /// 1-based line to start from.
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+pub(crate) async fn handle_read_file_tool(
+    cwd: &Path,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: ReadFileArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let path = cwd.join(&args.path);
+    if !is_readable(sandbox_policy, cwd, &path) {
+        return Err(FunctionCallError::RespondToModel(format!(
+            "reading {} is not allowed by the sandbox policy",
+            path.display()
+        )));
+    }
+    let bytes = tokio::fs::read(&path).await.map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to read {}: {e}", path.display()))
+    })?;
+    let (text, encoding) = decode(&bytes).ok_or_else(|| {
+        FunctionCallError::RespondToModel(format!("{} is a binary file", path.display()))
+    })?;
+    let offset = args.offset.unwrap_or(1).max(1);
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    Ok(render_range(&args.path, &text, encoding, offset, limit))
+}
+
+/// Whether the sandbox lets the agent read `path`. Without full disk read
+/// access, only the working directory and the writable roots are readable.
+pub(crate) fn is_readable(sandbox_policy: &SandboxPolicy, cwd: &Path, path: &Path) -> bool {
+    if sandbox_policy.has_full_disk_read_access() {
+        return true;
+    }
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    let cwd = cwd.canonicalize().unwrap_or_else(|_| cwd.to_path_buf());
+    path.starts_with(&cwd)
+        || sandbox_policy
+            .get_writable_roots_with_cwd(&cwd)
+            .iter()
+            .any(|root| path.starts_with(&root.root))
+}
+
+/// The text of `bytes` and the name of its encoding, or `None` for binary
+/// content.
+fn decode(bytes: &[u8]) -> Option<(String, &'static str)> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return Some((String::from_utf8_lossy(rest).into_owned(), "utf-8 with BOM"));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return Some((decode_utf16(rest, u16::from_le_bytes), "utf-16le"));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return Some((decode_utf16(rest, u16::from_be_bytes), "utf-16be"));
+    }
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some((text.to_string(), "utf-8")),
+        Err(_) => Some((
+            String::from_utf8_lossy(bytes).into_owned(),
+            "unknown, decoded as utf-8 with replacement characters",
+        )),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn render_range(path: &str, text: &str, encoding: &str, offset: usize, limit: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let total = lines.len();
+    if total == 0 {
+        return format!("{path}: empty file ({encoding})");
+    }
+    if offset > total {
+        return format!("{path}: offset {offset} is past the end of the file ({total} lines)");
+    }
+    let end = (offset - 1 + limit).min(total);
+    let width = end.to_string().len();
+    let mut output = format!("{path}: lines {offset}-{end} of {total} ({encoding})\n");
+    for (index, line) in lines[offset - 1..end].iter().enumerate() {
+        let number = offset + index;
+        match line.char_indices().nth(MAX_LINE_CHARS) {
+            Some((cut, _)) => output.push_str(&format!("{number:>width$}\t{}…\n", &line[..cut])),
+            None => output.push_str(&format!("{number:>width$}\t{line}\n")),
+        }
+    }
+    if end < total {
+        output.push_str(&format!(
+            "({} more lines; continue with offset {})\n",
+            total - end,
+            end + 1
+        ));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn renders_numbered_ranges() {
+        let text = (1..=12)
+            .map(|n| format!("line {n}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(
+            "a.txt: lines 9-10 of 12 (utf-8)\n 9\tline 9\n10\tline 10\n\
+(2 more lines; continue with offset 11)\n",
+            render_range("a.txt", &text, "utf-8", 9, 2)
+        );
+        assert_eq!(
+            "a.txt: lines 11-12 of 12 (utf-8)\n11\tline 11\n12\tline 12\n",
+            render_range("a.txt", &text, "utf-8", 11, 500)
+        );
+        assert_eq!(
+            "a.txt: offset 13 is past the end of the file (12 lines)",
+            render_range("a.txt", &text, "utf-8", 13, 500)
+        );
+        assert_eq!(
+            "a.txt: empty file (utf-8)",
+            render_range("a.txt", "", "utf-8", 1, 500)
+        );
+    }
+
+    #[test]
+    fn detects_encodings_and_binary_files() {
+        assert_eq!(
+            Some(("héllo".to_string(), "utf-8")),
+            decode("héllo".as_bytes())
+        );
+        assert_eq!(
+            Some(("hi".to_string(), "utf-8 with BOM")),
+            decode(b"\xEF\xBB\xBFhi")
+        );
+        assert_eq!(
+            Some(("hi".to_string(), "utf-16le")),
+            decode(b"\xFF\xFEh\0i\0")
+        );
+        assert_eq!(
+            Some(("hi".to_string(), "utf-16be")),
+            decode(b"\xFE\xFF\0h\0i")
+        );
+        assert_eq!(None, decode(b"\x7FELF\0\0"));
+        assert_eq!(
+            Some((
+                "caf\u{FFFD}".to_string(),
+                "unknown, decoded as utf-8 with replacement characters"
+            )),
+            decode(b"caf\xE9")
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_files_relative_to_cwd() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "first\nsecond\nthird\n").unwrap();
+
+        let output = handle_read_file_tool(
+            dir.path(),
+            &SandboxPolicy::ReadOnly,
+            r#"{"path": "notes.txt", "offset": 2}"#,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            "notes.txt: lines 2-3 of 3 (utf-8)\n2\tsecond\n3\tthird\n",
+            output
+        );
+
+        let err = handle_read_file_tool(
+            dir.path(),
+            &SandboxPolicy::ReadOnly,
+            r#"{"path": "missing.txt"}"#,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, FunctionCallError::RespondToModel(message) if message.starts_with("failed to read"))
+        );
+    }
+}
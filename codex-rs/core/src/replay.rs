@@ -0,0 +1,454 @@
+//! Deterministic replay of recorded sessions, for debugging regressions in
+//! agent behavior.
+//!
+//! A replay feeds the user messages of a recorded rollout to a fresh session.
+//! The model is queried as usual, but tool calls are never executed: they are
+//! answered by [`ReplayExecBackend`] with the outputs recorded for the same
+//! call in the original session. Comparing the tool calls of both sessions
+//! turn by turn shows where the current build's decisions diverge.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseInputItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use serde::Deserialize;
+use similar::TextDiff;
+
+use crate::AuthManager;
+use crate::RolloutRecorder;
+use crate::codex::Codex;
+use crate::codex::CodexSpawnOk;
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result as CodexResult;
+use crate::file_locks::FileLocks;
+use crate::protocol::AskForApproval;
+use crate::protocol::EventMsg;
+use crate::protocol::InitialHistory;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SessionSource;
+use crate::util::join_command;
+
+/// Answers tool calls with the outputs recorded in a rollout.
+#[derive(Debug, Default)]
+pub(crate) struct ReplayExecBackend {
+    outputs: Mutex<HashMap<String, VecDeque<RecordedOutput>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecordedOutput {
+    Function(FunctionCallOutputPayload),
+    Custom(String),
+}
+
+impl ReplayExecBackend {
+    pub(crate) async fn load(path: &Path) -> std::io::Result<Self> {
+        let items = RolloutRecorder::get_rollout_history(path)
+            .await?
+            .get_rollout_items();
+        Ok(Self::from_items(&items))
+    }
+
+    fn from_items(items: &[RolloutItem]) -> Self {
+        let mut decisions_by_call_id = HashMap::new();
+        let mut outputs: HashMap<String, VecDeque<RecordedOutput>> = HashMap::new();
+        for item in items {
+            let RolloutItem::ResponseItem(item) = item else {
+                continue;
+            };
+            if let (Some(call_id), Some(decision)) = (call_id(item), decision(item)) {
+                decisions_by_call_id.insert(call_id.to_string(), decision);
+                continue;
+            }
+            let (call_id, output) = match item {
+                ResponseItem::FunctionCallOutput { call_id, output } => {
+                    (call_id, RecordedOutput::Function(output.clone()))
+                }
+                ResponseItem::CustomToolCallOutput { call_id, output } => {
+                    (call_id, RecordedOutput::Custom(output.clone()))
+                }
+                _ => continue,
+            };
+            if let Some(decision) = decisions_by_call_id.remove(call_id) {
+                outputs.entry(decision).or_default().push_back(output);
+            }
+        }
+        Self {
+            outputs: Mutex::new(outputs),
+        }
+    }
+
+    /// The recorded output for tool call `item`, or `None` when `item` is not
+    /// a tool call. Calls that were never made in the recorded session get an
+    /// error output so the model can carry on.
+    pub(crate) fn respond(&self, item: &ResponseItem) -> Option<ResponseInputItem> {
+        let call_id = call_id(item)?.to_string();
+        let decision = decision(item)?;
+        let recorded = self
+            .outputs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get_mut(&decision)
+            .and_then(VecDeque::pop_front);
+        let recorded = recorded.unwrap_or_else(|| {
+            RecordedOutput::Function(FunctionCallOutputPayload {
+                content: "replay: the recorded session made no such call".to_string(),
+                success: Some(false),
+            })
+        });
+        Some(match (item, recorded) {
+            (ResponseItem::CustomToolCall { .. }, RecordedOutput::Custom(output)) => {
+                ResponseInputItem::CustomToolCallOutput { call_id, output }
+            }
+            (ResponseItem::CustomToolCall { .. }, RecordedOutput::Function(output)) => {
+                ResponseInputItem::CustomToolCallOutput {
+                    call_id,
+                    output: output.content,
+                }
+            }
+            (_, RecordedOutput::Function(output)) => {
+                ResponseInputItem::FunctionCallOutput { call_id, output }
+            }
+            (_, RecordedOutput::Custom(content)) => ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: None,
+                },
+            },
+        })
+    }
+}
+
+fn call_id(item: &ResponseItem) -> Option<&str> {
+    match item {
+        ResponseItem::FunctionCall { call_id, .. }
+        | ResponseItem::CustomToolCall { call_id, .. } => Some(call_id),
+        ResponseItem::LocalShellCall { call_id, id, .. } => call_id.as_deref().or(id.as_deref()),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct ShellArgs {
+    command: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ApplyPatchArgs {
+    input: String,
+}
+
+/// A one-line (patches aside) description of the tool call `item`, used both
+/// to match calls across sessions and to diff them.
+fn decision(item: &ResponseItem) -> Option<String> {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => {
+            let parsed = match name.as_str() {
+                "shell" | "container.exec" => serde_json::from_str::<ShellArgs>(arguments)
+                    .ok()
+                    .map(|args| format!("shell: {}", join_command(&args.command))),
+                "apply_patch" => serde_json::from_str::<ApplyPatchArgs>(arguments)
+                    .ok()
+                    .map(|args| format!("apply_patch:\n{}", args.input.trim_end())),
+                _ => None,
+            };
+            Some(parsed.unwrap_or_else(|| {
+                let arguments = serde_json::from_str::<serde_json::Value>(arguments)
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|_| arguments.clone());
+                format!("{name}: {arguments}")
+            }))
+        }
+        ResponseItem::CustomToolCall { name, input, .. } => {
+            Some(format!("{name}:\n{}", input.trim_end()))
+        }
+        ResponseItem::LocalShellCall { action, .. } => {
+            let LocalShellAction::Exec(exec) = action;
+            Some(format!("shell: {}", join_command(&exec.command)))
+        }
+        _ => None,
+    }
+}
+
+/// A user message and the tool calls the agent made in response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordedTurn {
+    user_message: String,
+    decisions: Vec<String>,
+}
+
+fn recorded_turns(items: &[RolloutItem]) -> Vec<RecordedTurn> {
+    let mut turns: Vec<RecordedTurn> = Vec::new();
+    for item in items {
+        let RolloutItem::ResponseItem(item) = item else {
+            continue;
+        };
+        if let Some(user_message) = user_message(item) {
+            turns.push(RecordedTurn {
+                user_message,
+                decisions: Vec::new(),
+            });
+        } else if let (Some(turn), Some(decision)) = (turns.last_mut(), decision(item)) {
+            turn.decisions.push(decision);
+        }
+    }
+    turns
+}
+
+/// Text typed by the user, excluding the context messages Codex injects.
+fn user_message(item: &ResponseItem) -> Option<String> {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return None;
+    };
+    if role != "user" {
+        return None;
+    }
+    let text = content
+        .iter()
+        .filter_map(|c| match c {
+            ContentItem::InputText { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+    let trimmed = text.trim_start();
+    if trimmed.starts_with("<environment_context>") || trimmed.starts_with("<user_instructions>") {
+        return None;
+    }
+    Some(text)
+}
+
+/// How one replayed turn compares with the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnComparison {
+    pub user_message: String,
+    /// Unified diff from the recorded tool calls to the replayed ones; empty
+    /// when they match.
+    pub diff: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Rollout file of the replayed session.
+    pub rollout_path: PathBuf,
+    pub turns: Vec<TurnComparison>,
+}
+
+impl ReplayReport {
+    /// Index of the first turn whose tool calls differ from the recording.
+    pub fn first_divergence(&self) -> Option<usize> {
+        self.turns.iter().position(|turn| !turn.diff.is_empty())
+    }
+}
+
+fn compare_turns(recorded: &[RecordedTurn], replayed: &[RecordedTurn]) -> Vec<TurnComparison> {
+    recorded
+        .iter()
+        .enumerate()
+        .map(|(index, turn)| {
+            let replayed = replayed
+                .get(index)
+                .map(|t| t.decisions.as_slice())
+                .unwrap_or_default();
+            let before = turn.decisions.join("\n");
+            let after = replayed.join("\n");
+            let diff = if before == after {
+                String::new()
+            } else {
+                TextDiff::from_lines(&format!("{before}\n"), &format!("{after}\n"))
+                    .unified_diff()
+                    .header("recorded", "replayed")
+                    .to_string()
+            };
+            TurnComparison {
+                user_message: turn.user_message.clone(),
+                diff,
+            }
+        })
+        .collect()
+}
+
+/// Replay the session recorded at `rollout_path` against the current build
+/// and compare its tool calls with the recording.
+pub async fn replay_session(
+    mut config: Config,
+    auth_manager: Arc<AuthManager>,
+    rollout_path: &Path,
+) -> CodexResult<ReplayReport> {
+    let items = RolloutRecorder::get_rollout_history(rollout_path)
+        .await?
+        .get_rollout_items();
+    let recorded = recorded_turns(&items);
+
+    config.replay_rollout = Some(rollout_path.to_path_buf());
+    // Nothing is executed, so there is nothing to approve.
+    config.approval_policy = AskForApproval::Never;
+    config.notify = None;
+    let CodexSpawnOk { codex, .. } = Codex::spawn(
+        config,
+        auth_manager,
+        InitialHistory::New,
+        SessionSource::Exec,
+        Arc::new(FileLocks::default()),
+    )
+    .await?;
+    let EventMsg::SessionConfigured(session_configured) = codex.next_event().await?.msg else {
+        return Err(CodexErr::SessionConfiguredNotFirstEvent);
+    };
+
+    for turn in &recorded {
+        codex
+            .submit(Op::UserInput {
+                items: vec![InputItem::Text {
+                    text: turn.user_message.clone(),
+                }],
+            })
+            .await?;
+        loop {
+            match codex.next_event().await?.msg {
+                EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => break,
+                _ => {}
+            }
+        }
+    }
+    codex.submit(Op::Shutdown).await?;
+    while !matches!(codex.next_event().await?.msg, EventMsg::ShutdownComplete) {}
+
+    let replayed_items = RolloutRecorder::get_rollout_history(&session_configured.rollout_path)
+        .await?
+        .get_rollout_items();
+    Ok(ReplayReport {
+        rollout_path: session_configured.rollout_path,
+        turns: compare_turns(&recorded, &recorded_turns(&replayed_items)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn user(text: &str) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: "user".into(),
+            content: vec![ContentItem::InputText { text: text.into() }],
+        })
+    }
+
+    fn shell_call(call_id: &str, command: &str) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".into(),
+            arguments: serde_json::json!({ "command": ["bash", "-lc", command] }).to_string(),
+            call_id: call_id.into(),
+        })
+    }
+
+    fn shell_output(call_id: &str, content: &str) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+            call_id: call_id.into(),
+            output: FunctionCallOutputPayload {
+                content: content.into(),
+                success: Some(true),
+            },
+        })
+    }
+
+    fn recording() -> Vec<RolloutItem> {
+        vec![
+            user("<environment_context>cwd</environment_context>"),
+            user("list files"),
+            shell_call("a", "ls"),
+            shell_output("a", "first ls"),
+            shell_call("b", "ls"),
+            shell_output("b", "second ls"),
+            user("now test"),
+            shell_call("c", "cargo test"),
+            shell_output("c", "ok"),
+        ]
+    }
+
+    #[test]
+    fn backend_answers_matching_calls_in_recorded_order() {
+        let backend = ReplayExecBackend::from_items(&recording());
+        let RolloutItem::ResponseItem(ls) = shell_call("new", "ls") else {
+            unreachable!()
+        };
+        let RolloutItem::ResponseItem(rm) = shell_call("other", "rm -rf target") else {
+            unreachable!()
+        };
+        let content = |item: Option<ResponseInputItem>| match item {
+            Some(ResponseInputItem::FunctionCallOutput { call_id, output }) => {
+                (call_id, output.content)
+            }
+            other => panic!("unexpected {other:?}"),
+        };
+
+        assert_eq!(
+            ("new".to_string(), "first ls".to_string()),
+            content(backend.respond(&ls))
+        );
+        assert_eq!(
+            ("new".to_string(), "second ls".to_string()),
+            content(backend.respond(&ls))
+        );
+        assert_eq!(
+            (
+                "other".to_string(),
+                "replay: the recorded session made no such call".to_string()
+            ),
+            content(backend.respond(&rm))
+        );
+        let RolloutItem::ResponseItem(message) = user("hi") else {
+            unreachable!()
+        };
+        assert_eq!(None, backend.respond(&message));
+    }
+
+    #[test]
+    fn turns_group_tool_calls_under_user_messages() {
+        assert_eq!(
+            vec![
+                RecordedTurn {
+                    user_message: "list files".into(),
+                    decisions: vec!["shell: bash -lc ls".into(), "shell: bash -lc ls".into()],
+                },
+                RecordedTurn {
+                    user_message: "now test".into(),
+                    decisions: vec!["shell: bash -lc 'cargo test'".into()],
+                },
+            ],
+            recorded_turns(&recording())
+        );
+    }
+
+    #[test]
+    fn comparison_reports_first_divergent_turn() {
+        let recorded = recorded_turns(&recording());
+        let mut replayed = recorded.clone();
+        replayed[1].decisions = vec!["shell: bash -lc 'cargo test --all'".into()];
+
+        let report = ReplayReport {
+            rollout_path: PathBuf::new(),
+            turns: compare_turns(&recorded, &replayed),
+        };
+        assert_eq!(Some(1), report.first_divergence());
+        assert_eq!(
+            "--- recorded\n+++ replayed\n@@ -1 +1 @@\n-shell: bash -lc 'cargo test'\n+shell: bash -lc 'cargo test --all'\n",
+            report.turns[1].diff
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Opt-in cache of model responses (`[response_cache]`), for replaying
+//! sessions offline and running agent integration tests without the API.
+//!
+//! A response is stored as a JSON file named after the SHA-256 of the request
+//! that produced it: the provider, the model and its reasoning settings, the
+//! instructions, the input, the tools and the output schema. Only responses
+//! that complete are stored, and replaying one yields the same events the
+//! model streamed, minus rate limit snapshots.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use codex_protocol::models::ResponseItem;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::config_types::ResponseCache;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::protocol::TokenUsage;
+
+/// Bumped when the key or the stored format changes, so that older entries
+/// are no longer used.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResponse {
+    version: u32,
+    model: String,
+    events: Vec<CachedEvent>,
+}
+
+/// A [`ResponseEvent`] as stored in the cache.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum CachedEvent {
+    Created,
+    OutputItemDone {
+        item: ResponseItem,
+    },
+    Completed {
+        response_id: String,
+        token_usage: Option<TokenUsage>,
+    },
+    OutputTextDelta {
+        delta: String,
+    },
+    ReasoningSummaryDelta {
+        delta: String,
+    },
+    ReasoningContentDelta {
+        delta: String,
+    },
+    ReasoningSummaryPartAdded,
+    WebSearchCallBegin {
+        call_id: String,
+    },
+    ToolCallArgumentsDelta {
+        call_id: String,
+        name: String,
+        delta: String,
+    },
+    ModelFailover {
+        model: String,
+        model_provider: String,
+        reason: String,
+    },
+}
+
+impl CachedEvent {
+    fn from_event(event: &ResponseEvent) -> Option<Self> {
+        Some(match event {
+            ResponseEvent::Created => Self::Created,
+            ResponseEvent::OutputItemDone(item) => Self::OutputItemDone { item: item.clone() },
+            ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            } => Self::Completed {
+                response_id: response_id.clone(),
+                token_usage: token_usage.clone(),
+            },
+            ResponseEvent::OutputTextDelta(delta) => Self::OutputTextDelta {
+                delta: delta.clone(),
+            },
+            ResponseEvent::ReasoningSummaryDelta(delta) => Self::ReasoningSummaryDelta {
+                delta: delta.clone(),
+            },
+            ResponseEvent::ReasoningContentDelta(delta) => Self::ReasoningContentDelta {
+                delta: delta.clone(),
+            },
+            ResponseEvent::ReasoningSummaryPartAdded => Self::ReasoningSummaryPartAdded,
+            ResponseEvent::WebSearchCallBegin { call_id } => Self::WebSearchCallBegin {
+                call_id: call_id.clone(),
+            },
+            ResponseEvent::ToolCallArgumentsDelta {
+                call_id,
+                name,
+                delta,
+            } => Self::ToolCallArgumentsDelta {
+                call_id: call_id.clone(),
+                name: name.clone(),
+                delta: delta.clone(),
+            },
+            ResponseEvent::ModelFailover {
+                model,
+                model_provider,
+                reason,
+            } => Self::ModelFailover {
+                model: model.clone(),
+                model_provider: model_provider.clone(),
+                reason: reason.clone(),
+            },
+            // Rate limits describe the account at the time of the request,
+            // not the response.
+            ResponseEvent::RateLimits(_) => return None,
+        })
+    }
+
+    fn into_event(self) -> ResponseEvent {
+        match self {
+            Self::Created => ResponseEvent::Created,
+            Self::OutputItemDone { item } => ResponseEvent::OutputItemDone(item),
+            Self::Completed {
+                response_id,
+                token_usage,
+            } => ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            },
+            Self::OutputTextDelta { delta } => ResponseEvent::OutputTextDelta(delta),
+            Self::ReasoningSummaryDelta { delta } => ResponseEvent::ReasoningSummaryDelta(delta),
+            Self::ReasoningContentDelta { delta } => ResponseEvent::ReasoningContentDelta(delta),
+            Self::ReasoningSummaryPartAdded => ResponseEvent::ReasoningSummaryPartAdded,
+            Self::WebSearchCallBegin { call_id } => ResponseEvent::WebSearchCallBegin { call_id },
+            Self::ToolCallArgumentsDelta {
+                call_id,
+                name,
+                delta,
+            } => ResponseEvent::ToolCallArgumentsDelta {
+                call_id,
+                name,
+                delta,
+            },
+            Self::ModelFailover {
+                model,
+                model_provider,
+                reason,
+            } => ResponseEvent::ModelFailover {
+                model,
+                model_provider,
+                reason,
+            },
+        }
+    }
+}
+
+/// The key of the response to `prompt`: a hash of everything that shapes the
+/// request, but not of the conversation id, so that new sessions reuse it.
+pub(crate) fn request_key(
+    model: &str,
+    model_family: &ModelFamily,
+    provider: &ModelProviderInfo,
+    effort: Option<ReasoningEffortConfig>,
+    summary: ReasoningSummaryConfig,
+    prompt: &Prompt,
+) -> String {
+    let request = json!({
+        "version": FORMAT_VERSION,
+        "provider": {
+            "name": provider.name,
+            "base_url": provider.base_url,
+            "wire_api": provider.wire_api,
+        },
+        "model": model,
+        "effort": effort,
+        "summary": summary,
+        "instructions": prompt.get_full_instructions(model_family),
+        "input": prompt.get_formatted_input(),
+        "tools": prompt.tools,
+        "output_schema": prompt.output_schema,
+    });
+    format!("{:x}", Sha256::digest(request.to_string().as_bytes()))
+}
+
+fn entry_path(cache: &ResponseCache, key: &str) -> PathBuf {
+    cache.dir.join(format!("{key}.json"))
+}
+
+/// The cached response for `key` as a stream, or `None` when there is none.
+pub(crate) async fn replay(cache: &ResponseCache, key: &str) -> Option<ResponseStream> {
+    let path = entry_path(cache, key);
+    let contents = tokio::fs::read(&path).await.ok()?;
+    let response = match serde_json::from_slice::<CachedResponse>(&contents) {
+        Ok(response) if response.version == FORMAT_VERSION => response,
+        Ok(_) => return None,
+        Err(e) => {
+            warn!(
+                "ignoring unreadable cached response {}: {e}",
+                path.display()
+            );
+            return None;
+        }
+    };
+
+    let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(response.events.len().max(1));
+    for event in response.events {
+        // The channel has room for every event.
+        let _ = tx.try_send(Ok(event.into_event()));
+    }
+    Some(ResponseStream { rx_event: rx })
+}
+
+/// `stream`, stored under `key` once it completes.
+pub(crate) fn record(
+    cache: &ResponseCache,
+    key: String,
+    model: String,
+    mut stream: ResponseStream,
+) -> ResponseStream {
+    let path = entry_path(cache, &key);
+    let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+    tokio::spawn(async move {
+        let mut events = Vec::new();
+        while let Some(event) = stream.rx_event.recv().await {
+            if let Ok(event) = &event {
+                events.extend(CachedEvent::from_event(event));
+                if matches!(event, ResponseEvent::Completed { .. }) {
+                    let response = CachedResponse {
+                        version: FORMAT_VERSION,
+                        model: model.clone(),
+                        events: std::mem::take(&mut events),
+                    };
+                    if let Err(e) = store(&path, &response).await {
+                        warn!("failed to cache response in {}: {e}", path.display());
+                    }
+                }
+            }
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    });
+    ResponseStream { rx_event: rx }
+}
+
+async fn store(path: &Path, response: &CachedResponse) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    // Write next to the entry and rename, so that concurrent readers never
+    // see a partial file.
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(response)?).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config_types::ResponseCacheMode;
+    use crate::model_family::derive_default_model_family;
+    use crate::model_provider_info::built_in_model_providers;
+    use codex_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn message(text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    fn key_of(prompt: &Prompt) -> String {
+        let provider = built_in_model_providers()["openai"].clone();
+        request_key(
+            "gpt-5-codex",
+            &derive_default_model_family("gpt-5-codex"),
+            &provider,
+            None,
+            ReasoningSummaryConfig::Auto,
+            prompt,
+        )
+    }
+
+    #[test]
+    fn keys_depend_on_the_request() {
+        let prompt = Prompt {
+            input: vec![message("hello")],
+            ..Default::default()
+        };
+        let other = Prompt {
+            input: vec![message("goodbye")],
+            ..Default::default()
+        };
+
+        assert_eq!(key_of(&prompt), key_of(&prompt.clone()));
+        assert_ne!(key_of(&prompt), key_of(&other));
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses() {
+        let dir = TempDir::new().expect("tempdir");
+        let cache = ResponseCache {
+            dir: dir.path().to_path_buf(),
+            mode: ResponseCacheMode::ReadWrite,
+        };
+        let events = vec![
+            CachedEvent::Created,
+            CachedEvent::OutputItemDone {
+                item: message("hi"),
+            },
+            CachedEvent::Completed {
+                response_id: "resp_1".to_string(),
+                token_usage: None,
+            },
+        ];
+
+        assert!(replay(&cache, "key").await.is_none());
+
+        let (tx, rx) = mpsc::channel::<Result<ResponseEvent>>(16);
+        for event in events.clone() {
+            tx.send(Ok(event.into_event())).await.expect("send");
+        }
+        drop(tx);
+        let mut recorded = record(
+            &cache,
+            "key".to_string(),
+            "gpt-5-codex".to_string(),
+            ResponseStream { rx_event: rx },
+        );
+        while recorded.rx_event.recv().await.is_some() {}
+
+        let mut replayed = replay(&cache, "key").await.expect("cached response");
+        let mut got = Vec::new();
+        while let Some(Ok(event)) = replayed.rx_event.recv().await {
+            got.extend(CachedEvent::from_event(&event));
+        }
+        assert_eq!(got, events);
+    }
+}
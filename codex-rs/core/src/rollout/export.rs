@@ -0,0 +1,713 @@
+//! Render a recorded session as a self-contained Markdown or HTML report, e.g.
+//! to attach to a pull request or share with teammates.
+
+use std::io;
+use std::path::Path;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::WebSearchAction;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::SessionMetaLine;
+use codex_protocol::protocol::TurnGitStateEvent;
+use codex_protocol::protocol::TurnStats;
+use serde::Deserialize;
+
+use super::RolloutRecorder;
+use crate::cost::costs_by_model;
+use crate::util::join_command;
+
+/// Tool outputs longer than this are cut in the report.
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Uncommitted files listed by name when a turn started; the rest are counted.
+const MAX_DIRTY_FILES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+}
+
+/// Render the rollout file at `path` in the given format.
+pub async fn export_session(path: &Path, format: ExportFormat) -> io::Result<String> {
+    let items = RolloutRecorder::get_rollout_history(path)
+        .await?
+        .get_rollout_items();
+    Ok(render_session(&items, format))
+}
+
+/// Render already-loaded rollout items in the given format.
+pub fn render_session(items: &[RolloutItem], format: ExportFormat) -> String {
+    let blocks = collect_blocks(items);
+    match format {
+        ExportFormat::Markdown => render_markdown(&blocks),
+        ExportFormat::Html => render_html(&blocks),
+    }
+}
+
+/// Format-independent pieces of the report, in transcript order.
+#[derive(Debug, PartialEq)]
+enum Block {
+    Header(Vec<(&'static str, String)>),
+    User(String),
+    Assistant(String),
+    Command(String),
+    Patch(String),
+    ToolCall {
+        name: String,
+        arguments: String,
+    },
+    Output {
+        text: String,
+        exit_code: Option<i32>,
+    },
+    Note(String),
+    /// Time and cost of the whole session.
+    Totals(Vec<(&'static str, String)>),
+}
+
+/// Output of the `shell` tool as returned to the model.
+#[derive(Deserialize)]
+struct ExecOutput {
+    output: String,
+    metadata: ExecMetadata,
+}
+
+#[derive(Deserialize)]
+struct ExecMetadata {
+    exit_code: i32,
+}
+
+#[derive(Deserialize)]
+struct ShellArgs {
+    command: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ApplyPatchArgs {
+    input: String,
+}
+
+fn collect_blocks(items: &[RolloutItem]) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut seen_meta = false;
+    let mut turn_stats = Vec::new();
+    for item in items {
+        match item {
+            RolloutItem::SessionMeta(meta) if !seen_meta => {
+                seen_meta = true;
+                blocks.push(header(meta));
+            }
+            RolloutItem::ResponseItem(item) => push_response_item(&mut blocks, item),
+            RolloutItem::Compacted(_) => {
+                blocks.push(Block::Note("Earlier conversation was compacted.".into()));
+            }
+            RolloutItem::HistoryRewound(_) => {
+                blocks.push(Block::Note("Conversation was rewound.".into()));
+            }
+            RolloutItem::EventMsg(EventMsg::TurnAborted(_)) => {
+                blocks.push(Block::Note("Turn interrupted.".into()));
+            }
+            RolloutItem::EventMsg(EventMsg::SubAgentEnd(ev)) => {
+                blocks.push(Block::Note(format!(
+                    "Sub-agent finished; transcript: {}",
+                    ev.rollout_path.display()
+                )));
+            }
+            RolloutItem::EventMsg(EventMsg::TurnGitState(state)) => {
+                blocks.push(Block::Note(describe_git_state(state)));
+            }
+            RolloutItem::EventMsg(EventMsg::TurnStats(stats)) => {
+                blocks.push(Block::Note(describe_turn(stats)));
+                turn_stats.push(stats);
+            }
+            _ => {}
+        }
+    }
+    if !turn_stats.is_empty() {
+        blocks.push(totals(&turn_stats));
+    }
+    blocks
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
+
+fn describe_turn(stats: &TurnStats) -> String {
+    let mut note = format!(
+        "Turn took {}: model {} over {} request(s), tools {} over {} call(s); {} tokens",
+        seconds(stats.duration_ms),
+        seconds(stats.model_latency_ms),
+        stats.model_requests,
+        seconds(stats.tool_time_ms),
+        stats.tool_calls,
+        stats.token_usage.total_tokens,
+    );
+    if let Some(cost) = stats.estimated_cost_usd {
+        note.push_str(&format!(", about ${cost:.4}"));
+    }
+    note.push('.');
+    note
+}
+
+fn describe_git_state(state: &TurnGitStateEvent) -> String {
+    let mut note = match (&state.git_head, &state.git_branch) {
+        (Some(head), Some(branch)) => format!("Turn started at {head} on {branch}"),
+        (Some(head), None) => format!("Turn started at {head} (detached HEAD)"),
+        (None, _) => "Turn started before the first commit".to_string(),
+    };
+    if state.dirty_files.is_empty() {
+        note.push_str(", working tree clean");
+    } else {
+        let listed = state
+            .dirty_files
+            .iter()
+            .take(MAX_DIRTY_FILES)
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        note.push_str(&format!(
+            ", {} uncommitted file(s): {listed}",
+            state.dirty_files.len()
+        ));
+        if state.dirty_files.len() > MAX_DIRTY_FILES {
+            note.push_str(&format!(
+                " and {} more",
+                state.dirty_files.len() - MAX_DIRTY_FILES
+            ));
+        }
+    }
+    if !state.stashes.is_empty() {
+        note.push_str(&format!("; {} stash(es)", state.stashes.len()));
+    }
+    note.push('.');
+    note
+}
+
+fn totals(turns: &[&TurnStats]) -> Block {
+    let sum = |field: fn(&TurnStats) -> u64| turns.iter().map(|t| field(t)).sum::<u64>();
+    let mut fields = vec![
+        ("Turns", turns.len().to_string()),
+        ("Time", seconds(sum(|t| t.duration_ms))),
+        ("Model latency", seconds(sum(|t| t.model_latency_ms))),
+        ("Tool time", seconds(sum(|t| t.tool_time_ms))),
+        (
+            "Tokens",
+            format!(
+                "{} input ({} cached), {} output",
+                sum(|t| t.token_usage.input_tokens),
+                sum(|t| t.token_usage.cached_input_tokens),
+                sum(|t| t.token_usage.output_tokens),
+            ),
+        ),
+    ];
+    let priced = turns.iter().filter_map(|t| t.estimated_cost_usd);
+    let unpriced = turns
+        .iter()
+        .filter(|t| t.estimated_cost_usd.is_none())
+        .count();
+    let mut cost = format!("${:.4}", priced.sum::<f64>());
+    if unpriced > 0 {
+        cost.push_str(&format!(
+            " (excluding {unpriced} turn(s) without model pricing)"
+        ));
+    }
+    fields.push(("Estimated cost", cost));
+    let by_model = costs_by_model(turns.iter().copied());
+    if by_model.len() > 1 {
+        let costs = by_model
+            .iter()
+            .map(|cost| {
+                format!(
+                    "{} ${:.4} over {} turn(s)",
+                    cost.model, cost.estimated_cost_usd, cost.turns
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        fields.push(("Cost by model", costs));
+    }
+    Block::Totals(fields)
+}
+
+fn header(meta: &SessionMetaLine) -> Block {
+    let mut fields = vec![
+        ("Session", meta.meta.id.to_string()),
+        ("Started", meta.meta.timestamp.clone()),
+        ("Directory", meta.meta.cwd.display().to_string()),
+        ("Codex version", meta.meta.cli_version.clone()),
+    ];
+    if let Some(git) = &meta.git {
+        if let Some(branch) = &git.branch {
+            fields.push(("Branch", branch.clone()));
+        }
+        if let Some(commit) = &git.commit_hash {
+            fields.push(("Commit", commit.clone()));
+        }
+    }
+    Block::Header(fields)
+}
+
+fn push_response_item(blocks: &mut Vec<Block>, item: &ResponseItem) {
+    match item {
+        ResponseItem::Message { role, content, .. } => {
+            let text = content
+                .iter()
+                .filter_map(|c| match c {
+                    ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                        Some(text.as_str())
+                    }
+                    ContentItem::InputImage { .. } => None,
+                })
+                .collect::<String>();
+            let trimmed = text.trim_start();
+            if trimmed.is_empty()
+                || trimmed.starts_with("<environment_context>")
+                || trimmed.starts_with("<user_instructions>")
+            {
+                return;
+            }
+            match role.as_str() {
+                "user" => blocks.push(Block::User(text)),
+                "assistant" => blocks.push(Block::Assistant(text)),
+                _ => {}
+            }
+        }
+        ResponseItem::LocalShellCall { action, .. } => {
+            let LocalShellAction::Exec(exec) = action;
+            blocks.push(Block::Command(join_command(&exec.command)));
+        }
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => blocks.push(function_call_block(name, arguments)),
+        ResponseItem::CustomToolCall { name, input, .. } => {
+            blocks.push(if name == "apply_patch" {
+                Block::Patch(input.clone())
+            } else {
+                Block::ToolCall {
+                    name: name.clone(),
+                    arguments: input.clone(),
+                }
+            });
+        }
+        ResponseItem::FunctionCallOutput { output, .. } => {
+            blocks.push(match serde_json::from_str::<ExecOutput>(&output.content) {
+                Ok(exec) => Block::Output {
+                    text: exec.output,
+                    exit_code: Some(exec.metadata.exit_code),
+                },
+                Err(_) => Block::Output {
+                    text: output.content.clone(),
+                    exit_code: None,
+                },
+            });
+        }
+        ResponseItem::CustomToolCallOutput { output, .. } => blocks.push(Block::Output {
+            text: output.clone(),
+            exit_code: None,
+        }),
+        ResponseItem::WebSearchCall { action, .. } => {
+            let WebSearchAction::Search { query } = action else {
+                return;
+            };
+            blocks.push(Block::Note(format!("Searched the web for “{query}”.")));
+        }
+        ResponseItem::Reasoning { .. } | ResponseItem::Other => {}
+    }
+}
+
+fn function_call_block(name: &str, arguments: &str) -> Block {
+    match name {
+        "shell" | "container.exec" => {
+            if let Ok(args) = serde_json::from_str::<ShellArgs>(arguments) {
+                return Block::Command(join_command(&args.command));
+            }
+        }
+        "apply_patch" => {
+            if let Ok(args) = serde_json::from_str::<ApplyPatchArgs>(arguments) {
+                return Block::Patch(args.input);
+            }
+        }
+        _ => {}
+    }
+    let arguments = serde_json::from_str::<serde_json::Value>(arguments)
+        .and_then(|v| serde_json::to_string_pretty(&v))
+        .unwrap_or_else(|_| arguments.to_string());
+    Block::ToolCall {
+        name: name.to_string(),
+        arguments,
+    }
+}
+
+/// Cut `text` to at most `MAX_OUTPUT_LINES` lines.
+fn truncate_output(text: &str) -> String {
+    let total = text.lines().count();
+    if total <= MAX_OUTPUT_LINES {
+        return text.trim_end().to_string();
+    }
+    let kept = text
+        .lines()
+        .take(MAX_OUTPUT_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let omitted = total - MAX_OUTPUT_LINES;
+    format!("{kept}\n[... {omitted} more lines omitted]")
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut out = String::from("# Codex session\n");
+    for block in blocks {
+        out.push('\n');
+        match block {
+            Block::Header(fields) => {
+                for (label, value) in fields {
+                    out.push_str(&format!("- **{label}:** `{value}`\n"));
+                }
+            }
+            Block::User(text) => out.push_str(&format!("## User\n\n{}\n", text.trim())),
+            Block::Assistant(text) => out.push_str(&format!("## Codex\n\n{}\n", text.trim())),
+            Block::Command(command) => {
+                out.push_str(&code_block("sh", &format!("$ {command}")));
+            }
+            Block::Patch(patch) => out.push_str(&code_block("diff", patch.trim_end())),
+            Block::ToolCall { name, arguments } => {
+                out.push_str(&format!("Tool call `{name}`:\n\n"));
+                out.push_str(&code_block("json", arguments));
+            }
+            Block::Output { text, exit_code } => {
+                let label = match exit_code {
+                    Some(code) => format!("Output (exit code {code}):"),
+                    None => "Output:".to_string(),
+                };
+                out.push_str(&format!(
+                    "<details>\n<summary>{label}</summary>\n\n{}</details>\n",
+                    code_block("text", &truncate_output(text))
+                ));
+            }
+            Block::Note(note) => out.push_str(&format!("> {note}\n")),
+            Block::Totals(fields) => {
+                out.push_str("## Totals\n\n");
+                for (label, value) in fields {
+                    out.push_str(&format!("- **{label}:** {value}\n"));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// A fenced code block whose fence is longer than any backtick run in `body`.
+fn code_block(lang: &str, body: &str) -> String {
+    let longest_run = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{fence}{lang}\n{body}\n{fence}\n")
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,\"Segoe UI\",sans-serif;max-width:960px;margin:2em auto;padding:0 1em;color:#1f2328;line-height:1.5}\
+h2{font-size:1.1em;margin:1.5em 0 .5em}\
+.message{white-space:pre-wrap}\
+pre{background:#f6f8fa;padding:.75em;overflow-x:auto;border-radius:6px;font-size:.85em}\
+.add{color:#1a7f37}.del{color:#cf222e}.hunk{color:#8250df}\
+.note{color:#59636e;font-style:italic}\
+table{border-collapse:collapse}td{padding:.1em 1em .1em 0}";
+
+fn render_html(blocks: &[Block]) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Codex session</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>Codex session</h1>\n"
+    );
+    for block in blocks {
+        match block {
+            Block::Header(fields) => {
+                out.push_str("<table>\n");
+                for (label, value) in fields {
+                    out.push_str(&format!(
+                        "<tr><td><strong>{label}</strong></td><td><code>{}</code></td></tr>\n",
+                        escape_html(value)
+                    ));
+                }
+                out.push_str("</table>\n");
+            }
+            Block::User(text) => out.push_str(&format!(
+                "<h2>User</h2>\n<div class=\"message\">{}</div>\n",
+                escape_html(text.trim())
+            )),
+            Block::Assistant(text) => out.push_str(&format!(
+                "<h2>Codex</h2>\n<div class=\"message\">{}</div>\n",
+                escape_html(text.trim())
+            )),
+            Block::Command(command) => out.push_str(&format!(
+                "<pre><code>$ {}</code></pre>\n",
+                escape_html(command)
+            )),
+            Block::Patch(patch) => {
+                out.push_str("<pre><code>");
+                for line in patch.trim_end().lines() {
+                    let class = if line.starts_with("***") || line.starts_with("@@") {
+                        Some("hunk")
+                    } else if line.starts_with('+') {
+                        Some("add")
+                    } else if line.starts_with('-') {
+                        Some("del")
+                    } else {
+                        None
+                    };
+                    let line = escape_html(line);
+                    match class {
+                        Some(class) => {
+                            out.push_str(&format!("<span class=\"{class}\">{line}</span>\n"));
+                        }
+                        None => out.push_str(&format!("{line}\n")),
+                    }
+                }
+                out.push_str("</code></pre>\n");
+            }
+            Block::ToolCall { name, arguments } => out.push_str(&format!(
+                "<p>Tool call <code>{}</code>:</p>\n<pre><code>{}</code></pre>\n",
+                escape_html(name),
+                escape_html(arguments)
+            )),
+            Block::Output { text, exit_code } => {
+                let label = match exit_code {
+                    Some(code) => format!("Output (exit code {code})"),
+                    None => "Output".to_string(),
+                };
+                out.push_str(&format!(
+                    "<details>\n<summary>{label}</summary>\n<pre><code>{}</code></pre>\n</details>\n",
+                    escape_html(&truncate_output(text))
+                ));
+            }
+            Block::Note(note) => {
+                out.push_str(&format!("<p class=\"note\">{}</p>\n", escape_html(note)))
+            }
+            Block::Totals(fields) => {
+                out.push_str("<h2>Totals</h2>\n<table>\n");
+                for (label, value) in fields {
+                    out.push_str(&format!(
+                        "<tr><td><strong>{label}</strong></td><td>{}</td></tr>\n",
+                        escape_html(value)
+                    ));
+                }
+                out.push_str("</table>\n");
+            }
+        }
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> RolloutItem {
+        let content = if role == "user" {
+            ContentItem::InputText { text: text.into() }
+        } else {
+            ContentItem::OutputText { text: text.into() }
+        };
+        RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: role.into(),
+            content: vec![content],
+        })
+    }
+
+    fn transcript() -> Vec<RolloutItem> {
+        vec![
+            message("user", "<environment_context>cwd</environment_context>"),
+            message("user", "Fix the <bug>"),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".into(),
+                arguments: r#"{"command":["rg","-n","foo bar"]}"#.into(),
+                call_id: "c1".into(),
+            }),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: "c1".into(),
+                output: FunctionCallOutputPayload {
+                    content: r#"{"output":"src/lib.rs:1:foo bar\n","metadata":{"exit_code":0,"duration_seconds":0.1}}"#.into(),
+                    success: Some(true),
+                },
+            }),
+            RolloutItem::ResponseItem(ResponseItem::CustomToolCall {
+                id: None,
+                status: None,
+                call_id: "c2".into(),
+                name: "apply_patch".into(),
+                input: "*** Begin Patch\n-old\n+new\n*** End Patch".into(),
+            }),
+            message("assistant", "Done."),
+        ]
+    }
+
+    #[test]
+    fn collects_transcript_blocks_and_skips_context_messages() {
+        assert_eq!(
+            vec![
+                Block::User("Fix the <bug>".into()),
+                Block::Command("rg -n 'foo bar'".into()),
+                Block::Output {
+                    text: "src/lib.rs:1:foo bar\n".into(),
+                    exit_code: Some(0),
+                },
+                Block::Patch("*** Begin Patch\n-old\n+new\n*** End Patch".into()),
+                Block::Assistant("Done.".into()),
+            ],
+            collect_blocks(&transcript())
+        );
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let markdown = render_session(&transcript(), ExportFormat::Markdown);
+        assert!(markdown.contains("## User\n\nFix the <bug>\n"));
+        assert!(markdown.contains("```sh\n$ rg -n 'foo bar'\n```\n"));
+        assert!(markdown.contains("<summary>Output (exit code 0):</summary>"));
+        assert!(markdown.contains("```diff\n*** Begin Patch\n-old\n+new\n*** End Patch\n```\n"));
+        assert!(markdown.contains("## Codex\n\nDone.\n"));
+    }
+
+    #[test]
+    fn renders_escaped_html() {
+        let html = render_session(&transcript(), ExportFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Fix the &lt;bug&gt;"));
+        assert!(html.contains("<span class=\"add\">+new</span>"));
+        assert!(html.contains("<span class=\"del\">-old</span>"));
+    }
+
+    #[test]
+    fn reports_turn_stats_and_totals() {
+        let stats = |cost| {
+            RolloutItem::EventMsg(EventMsg::TurnStats(TurnStats {
+                turn_id: "1".into(),
+                model: "o3".into(),
+                duration_ms: 2_500,
+                model_requests: 2,
+                model_latency_ms: 2_000,
+                tool_calls: 1,
+                tool_time_ms: 500,
+                token_usage: codex_protocol::protocol::TokenUsage {
+                    input_tokens: 1_000,
+                    cached_input_tokens: 200,
+                    output_tokens: 50,
+                    reasoning_output_tokens: 0,
+                    total_tokens: 1_050,
+                },
+                estimated_cost_usd: cost,
+            }))
+        };
+        let blocks = collect_blocks(&[stats(Some(0.0025)), stats(None)]);
+        assert_eq!(
+            vec![
+                Block::Note(
+                    "Turn took 2.5s: model 2.0s over 2 request(s), tools 0.5s over 1 call(s); 1050 tokens, about $0.0025."
+                        .into()
+                ),
+                Block::Note(
+                    "Turn took 2.5s: model 2.0s over 2 request(s), tools 0.5s over 1 call(s); 1050 tokens."
+                        .into()
+                ),
+                Block::Totals(vec![
+                    ("Turns", "2".into()),
+                    ("Time", "5.0s".into()),
+                    ("Model latency", "4.0s".into()),
+                    ("Tool time", "1.0s".into()),
+                    ("Tokens", "2000 input (400 cached), 100 output".into()),
+                    (
+                        "Estimated cost",
+                        "$0.0025 (excluding 1 turn(s) without model pricing)".into()
+                    ),
+                ]),
+            ],
+            blocks
+        );
+    }
+
+    #[test]
+    fn breaks_down_the_cost_of_several_models() {
+        let stats = |model: &str, cost| {
+            RolloutItem::EventMsg(EventMsg::TurnStats(TurnStats {
+                turn_id: "1".into(),
+                model: model.into(),
+                estimated_cost_usd: Some(cost),
+                ..Default::default()
+            }))
+        };
+        let blocks = collect_blocks(&[
+            stats("o3", 0.0025),
+            stats("gpt-5-codex", 0.01),
+            stats("o3", 0.0025),
+        ]);
+        let Some(Block::Totals(fields)) = blocks.last() else {
+            panic!("expected totals, got {blocks:?}");
+        };
+        assert_eq!(
+            fields.last(),
+            Some(&(
+                "Cost by model",
+                "gpt-5-codex $0.0100 over 1 turn(s), o3 $0.0050 over 2 turn(s)".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_git_state_at_turn_start() {
+        let state = |git_head: Option<&str>, dirty: &[&str], stashes: usize| {
+            RolloutItem::EventMsg(EventMsg::TurnGitState(TurnGitStateEvent {
+                turn_id: "1".into(),
+                git_head: git_head.map(str::to_string),
+                git_branch: Some("main".into()),
+                dirty_files: dirty
+                    .iter()
+                    .map(|path| codex_protocol::protocol::GitDirtyFile {
+                        status: " M".into(),
+                        path: (*path).into(),
+                    })
+                    .collect(),
+                stashes: vec!["stash@{0}: On main: wip".into(); stashes],
+            }))
+        };
+        assert_eq!(
+            vec![
+                Block::Note("Turn started at abc123 on main, working tree clean.".into()),
+                Block::Note(
+                    "Turn started at abc123 on main, 2 uncommitted file(s): a.rs, b.rs; 1 stash(es)."
+                        .into()
+                ),
+                Block::Note("Turn started before the first commit, working tree clean.".into()),
+            ],
+            collect_blocks(&[
+                state(Some("abc123"), &[], 0),
+                state(Some("abc123"), &["a.rs", "b.rs"], 1),
+                state(None, &[], 0),
+            ])
+        );
+    }
+
+    #[test]
+    fn code_block_fence_outgrows_backticks_in_body() {
+        assert_eq!("````\n```\n````\n", code_block("", "```"));
+    }
+}
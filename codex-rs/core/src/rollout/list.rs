@@ -1,4 +1,5 @@
 use std::cmp::Reverse;
+use std::collections::BTreeMap;
 use std::io::{self};
 use std::path::Path;
 use std::path::PathBuf;
@@ -15,6 +16,7 @@ use uuid::Uuid;
 
 use super::SESSIONS_SUBDIR;
 use crate::protocol::EventMsg;
+use crate::session_tags::matches_tags;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::SessionSource;
@@ -45,6 +47,10 @@ pub struct ConversationItem {
     pub created_at: Option<String>,
     /// RFC3339 timestamp string for the most recent response in the tail, if available.
     pub updated_at: Option<String>,
+    /// Most recent session title found in the head or tail, if any.
+    pub title: Option<String>,
+    /// Most recent session tags found in the head or tail.
+    pub tags: BTreeMap<String, String>,
 }
 
 #[derive(Default)]
@@ -56,12 +62,17 @@ struct HeadTailSummary {
     source: Option<SessionSource>,
     created_at: Option<String>,
     updated_at: Option<String>,
+    title: Option<String>,
+    tags: Option<BTreeMap<String, String>>,
 }
 
 /// Hard cap to bound worst‑case work per request.
 const MAX_SCAN_FILES: usize = 10000;
 const HEAD_RECORD_LIMIT: usize = 10;
 const TAIL_RECORD_LIMIT: usize = 10;
+/// Records read from the start of a file while looking for the title written
+/// after the first turn and the tags written when the session starts.
+const METADATA_SCAN_RECORD_LIMIT: usize = 200;
 
 /// Pagination cursor identifying a file by timestamp and UUID.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -109,6 +120,25 @@ pub(crate) async fn get_conversations(
     page_size: usize,
     cursor: Option<&Cursor>,
     allowed_sources: &[SessionSource],
+) -> io::Result<ConversationsPage> {
+    get_conversations_with_tags(
+        codex_home,
+        page_size,
+        cursor,
+        allowed_sources,
+        &BTreeMap::new(),
+    )
+    .await
+}
+
+/// Like [`get_conversations`], keeping only conversations whose latest tags
+/// include every entry of `required_tags`.
+pub(crate) async fn get_conversations_with_tags(
+    codex_home: &Path,
+    page_size: usize,
+    cursor: Option<&Cursor>,
+    allowed_sources: &[SessionSource],
+    required_tags: &BTreeMap<String, String>,
 ) -> io::Result<ConversationsPage> {
     let mut root = codex_home.to_path_buf();
     root.push(SESSIONS_SUBDIR);
@@ -124,8 +154,14 @@ pub(crate) async fn get_conversations(
 
     let anchor = cursor.cloned();
 
-    let result =
-        traverse_directories_for_paths(root.clone(), page_size, anchor, allowed_sources).await?;
+    let result = traverse_directories_for_paths(
+        root.clone(),
+        page_size,
+        anchor,
+        allowed_sources,
+        required_tags,
+    )
+    .await?;
     Ok(result)
 }
 
@@ -145,6 +181,7 @@ async fn traverse_directories_for_paths(
     page_size: usize,
     anchor: Option<Cursor>,
     allowed_sources: &[SessionSource],
+    required_tags: &BTreeMap<String, String>,
 ) -> io::Result<ConversationsPage> {
     let mut items: Vec<ConversationItem> = Vec::with_capacity(page_size);
     let mut scanned_files = 0usize;
@@ -208,6 +245,10 @@ async fn traverse_directories_for_paths(
                     {
                         continue;
                     }
+                    let tags = summary.tags.clone().unwrap_or_default();
+                    if !matches_tags(&tags, required_tags) {
+                        continue;
+                    }
                     // Apply filters: must have session meta and at least one user message event
                     if summary.saw_session_meta && summary.saw_user_event {
                         let HeadTailSummary {
@@ -215,6 +256,7 @@ async fn traverse_directories_for_paths(
                             tail,
                             created_at,
                             mut updated_at,
+                            title,
                             ..
                         } = summary;
                         updated_at = updated_at.or_else(|| created_at.clone());
@@ -224,6 +266,8 @@ async fn traverse_directories_for_paths(
                             tail,
                             created_at,
                             updated_at,
+                            title,
+                            tags,
                         });
                     }
                 }
@@ -339,8 +383,13 @@ async fn read_head_and_tail(
     let reader = tokio::io::BufReader::new(file);
     let mut lines = reader.lines();
     let mut summary = HeadTailSummary::default();
+    let mut records = 0;
 
-    while summary.head.len() < head_limit {
+    // Past the head, keep reading only to find the session title and tags.
+    while summary.head.len() < head_limit
+        || ((summary.title.is_none() || summary.tags.is_none())
+            && records < METADATA_SCAN_RECORD_LIMIT)
+    {
         let line_opt = lines.next_line().await?;
         let Some(line) = line_opt else { break };
         let trimmed = line.trim();
@@ -350,6 +399,20 @@ async fn read_head_and_tail(
 
         let parsed: Result<RolloutLine, _> = serde_json::from_str(trimmed);
         let Ok(rollout_line) = parsed else { continue };
+        records += 1;
+
+        match &rollout_line.item {
+            RolloutItem::EventMsg(EventMsg::SessionTitle(event)) => {
+                summary.title = Some(event.title.clone());
+            }
+            RolloutItem::EventMsg(EventMsg::SessionTags(event)) => {
+                summary.tags = Some(event.tags.clone());
+            }
+            _ => {}
+        }
+        if summary.head.len() >= head_limit {
+            continue;
+        }
 
         match rollout_line.item {
             RolloutItem::SessionMeta(session_meta_line) => {
@@ -375,7 +438,7 @@ async fn read_head_and_tail(
             RolloutItem::TurnContext(_) => {
                 // Not included in `head`; skip.
             }
-            RolloutItem::Compacted(_) => {
+            RolloutItem::Compacted(_) | RolloutItem::HistoryRewound(_) => {
                 // Not included in `head`; skip.
             }
             RolloutItem::EventMsg(ev) => {
@@ -387,23 +450,32 @@ async fn read_head_and_tail(
     }
 
     if tail_limit != 0 {
-        let (tail, updated_at) = read_tail_records(path, tail_limit).await?;
+        let (tail, updated_at, title, tags) = read_tail_records(path, tail_limit).await?;
         summary.tail = tail;
         summary.updated_at = updated_at;
+        // A title or tags near the end are later updates.
+        summary.title = title.or(summary.title);
+        summary.tags = tags.or(summary.tags);
     }
     Ok(summary)
 }
 
-async fn read_tail_records(
-    path: &Path,
-    max_records: usize,
-) -> io::Result<(Vec<serde_json::Value>, Option<String>)> {
+/// Last response records, the timestamp of the newest one and the newest
+/// session title and tags among the records read.
+type TailRecords = (
+    Vec<serde_json::Value>,
+    Option<String>,
+    Option<String>,
+    Option<BTreeMap<String, String>>,
+);
+
+async fn read_tail_records(path: &Path, max_records: usize) -> io::Result<TailRecords> {
     use std::io::SeekFrom;
     use tokio::io::AsyncReadExt;
     use tokio::io::AsyncSeekExt;
 
     if max_records == 0 {
-        return Ok((Vec::new(), None));
+        return Ok((Vec::new(), None, None, None));
     }
 
     const CHUNK_SIZE: usize = 8192;
@@ -411,7 +483,7 @@ async fn read_tail_records(
     let mut file = tokio::fs::File::open(path).await?;
     let mut pos = file.seek(SeekFrom::End(0)).await?;
     if pos == 0 {
-        return Ok((Vec::new(), None));
+        return Ok((Vec::new(), None, None, None));
     }
 
     let mut buffer: Vec<u8> = Vec::new();
@@ -422,17 +494,18 @@ async fn read_tail_records(
             (true, Some(idx)) => idx + 1,
             _ => 0,
         };
-        let (tail, newest_ts) = collect_last_response_values(&buffer[slice_start..], max_records);
+        let (tail, newest_ts, title, tags) =
+            collect_last_response_values(&buffer[slice_start..], max_records);
         if latest_timestamp.is_none() {
             latest_timestamp = newest_ts.clone();
         }
         if tail.len() >= max_records || pos == 0 {
-            return Ok((tail, latest_timestamp.or(newest_ts)));
+            return Ok((tail, latest_timestamp.or(newest_ts), title, tags));
         }
 
         let read_size = CHUNK_SIZE.min(pos as usize);
         if read_size == 0 {
-            return Ok((tail, latest_timestamp.or(newest_ts)));
+            return Ok((tail, latest_timestamp.or(newest_ts), title, tags));
         }
         pos -= read_size as u64;
         file.seek(SeekFrom::Start(pos)).await?;
@@ -443,19 +516,18 @@ async fn read_tail_records(
     }
 }
 
-fn collect_last_response_values(
-    buffer: &[u8],
-    max_records: usize,
-) -> (Vec<serde_json::Value>, Option<String>) {
+fn collect_last_response_values(buffer: &[u8], max_records: usize) -> TailRecords {
     use std::borrow::Cow;
 
     if buffer.is_empty() || max_records == 0 {
-        return (Vec::new(), None);
+        return (Vec::new(), None, None, None);
     }
 
     let text: Cow<'_, str> = String::from_utf8_lossy(buffer);
     let mut collected_rev: Vec<serde_json::Value> = Vec::new();
     let mut latest_timestamp: Option<String> = None;
+    let mut latest_title: Option<String> = None;
+    let mut latest_tags: Option<BTreeMap<String, String>> = None;
     for line in text.lines().rev() {
         let trimmed = line.trim();
         if trimmed.is_empty() {
@@ -464,6 +536,15 @@ fn collect_last_response_values(
         let parsed: serde_json::Result<RolloutLine> = serde_json::from_str(trimmed);
         let Ok(rollout_line) = parsed else { continue };
         let RolloutLine { timestamp, item } = rollout_line;
+        match &item {
+            RolloutItem::EventMsg(EventMsg::SessionTitle(event)) if latest_title.is_none() => {
+                latest_title = Some(event.title.clone());
+            }
+            RolloutItem::EventMsg(EventMsg::SessionTags(event)) if latest_tags.is_none() => {
+                latest_tags = Some(event.tags.clone());
+            }
+            _ => {}
+        }
         if let RolloutItem::ResponseItem(item) = item
             && let Ok(val) = serde_json::to_value(&item)
         {
@@ -477,7 +558,7 @@ fn collect_last_response_values(
         }
     }
     collected_rev.reverse();
-    (collected_rev, latest_timestamp)
+    (collected_rev, latest_timestamp, latest_title, latest_tags)
 }
 
 /// Locate a recorded conversation rollout file by its UUID string using the existing
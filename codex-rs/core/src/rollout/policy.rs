@@ -9,9 +9,10 @@ pub(crate) fn is_persisted_response_item(item: &RolloutItem) -> bool {
         RolloutItem::ResponseItem(item) => should_persist_response_item(item),
         RolloutItem::EventMsg(ev) => should_persist_event_msg(ev),
         // Persist Codex executive markers so we can analyze flows (e.g., compaction, API turns).
-        RolloutItem::Compacted(_) | RolloutItem::TurnContext(_) | RolloutItem::SessionMeta(_) => {
-            true
-        }
+        RolloutItem::Compacted(_)
+        | RolloutItem::HistoryRewound(_)
+        | RolloutItem::TurnContext(_)
+        | RolloutItem::SessionMeta(_) => true,
     }
 }
 
@@ -42,26 +43,56 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::TokenCount(_)
         | EventMsg::EnteredReviewMode(_)
         | EventMsg::ExitedReviewMode(_)
-        | EventMsg::TurnAborted(_) => true,
+        | EventMsg::TurnAborted(_)
+        | EventMsg::SubAgentEnd(_)
+        | EventMsg::ModelFailover(_)
+        | EventMsg::TurnStats(_)
+        | EventMsg::WorkspaceState(_)
+        | EventMsg::TurnGitState(_)
+        | EventMsg::SessionTitle(_)
+        | EventMsg::SessionTags(_)
+        | EventMsg::PlanUpdate(_) => true,
         EventMsg::Error(_)
         | EventMsg::TaskStarted(_)
         | EventMsg::TaskComplete(_)
         | EventMsg::AgentMessageDelta(_)
+        | EventMsg::ToolCallArgumentsDelta(_)
+        | EventMsg::PayloadFrame(_)
+        | EventMsg::UserInputQueue(_)
+        | EventMsg::SessionBusy(_)
+        | EventMsg::SessionEnv(_)
+        | EventMsg::ContextCompacted(_)
+        | EventMsg::ContextBudget(_)
+        | EventMsg::CostSummary(_)
+        | EventMsg::CheckpointCreated(_)
+        | EventMsg::CheckpointRestored(_)
+        | EventMsg::TurnUndone(_)
+        | EventMsg::SessionDiff(_)
+        | EventMsg::BackgroundProcessResponse(_)
+        | EventMsg::TurnStatsResponse(_)
+        | EventMsg::PinnedContext(_)
+        | EventMsg::SubAgentBegin(_)
         | EventMsg::AgentReasoningDelta(_)
         | EventMsg::AgentReasoningRawContentDelta(_)
         | EventMsg::AgentReasoningSectionBreak(_)
         | EventMsg::SessionConfigured(_)
         | EventMsg::McpToolCallBegin(_)
+        | EventMsg::McpToolCallProgress(_)
         | EventMsg::McpToolCallEnd(_)
+        | EventMsg::ElicitationRequest(_)
         | EventMsg::WebSearchBegin(_)
         | EventMsg::WebSearchEnd(_)
         | EventMsg::ExecCommandBegin(_)
         | EventMsg::ExecCommandOutputDelta(_)
         | EventMsg::ExecCommandEnd(_)
         | EventMsg::ExecApprovalRequest(_)
+        | EventMsg::ApprovalBatchRequest(_)
         | EventMsg::ApplyPatchApprovalRequest(_)
+        | EventMsg::BudgetExceeded(_)
         | EventMsg::BackgroundEvent(_)
         | EventMsg::BackgroundProcessStatus(_)
+        | EventMsg::SandboxDenied(_)
+        | EventMsg::SessionSettingsUpdated(_)
         | EventMsg::StreamError(_)
         | EventMsg::PatchApplyBegin(_)
         | EventMsg::PatchApplyEnd(_)
@@ -69,7 +100,11 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::GetHistoryEntryResponse(_)
         | EventMsg::McpListToolsResponse(_)
         | EventMsg::ListCustomPromptsResponse(_)
-        | EventMsg::PlanUpdate(_)
+        | EventMsg::McpListPromptsResponse(_)
+        | EventMsg::McpGetPromptResponse(_)
+        | EventMsg::McpServerHealth(_)
+        | EventMsg::McpServerConnection(_)
+        | EventMsg::ConfigReloaded(_)
         | EventMsg::ShutdownComplete
         | EventMsg::ViewImageToolCall(_)
         | EventMsg::ConversationPath(_) => false,
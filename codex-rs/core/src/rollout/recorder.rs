@@ -1,5 +1,6 @@
 //! Persist Codex session rollouts (.jsonl) so sessions can be replayed or inspected later.
 
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::fs::{self};
 use std::io::Error as IoError;
@@ -22,7 +23,11 @@ use super::SESSIONS_SUBDIR;
 use super::list::ConversationsPage;
 use super::list::Cursor;
 use super::list::get_conversations;
+use super::list::get_conversations_with_tags;
 use super::policy::is_persisted_response_item;
+use super::redact::Redactor;
+use super::search::SessionSearchHit;
+use super::search::search_sessions;
 use crate::config::Config;
 use crate::default_client::originator;
 use crate::git_info::collect_git_info;
@@ -101,6 +106,35 @@ impl RolloutRecorder {
         get_conversations(codex_home, page_size, cursor, allowed_sources).await
     }
 
+    /// Search the text of recorded conversations for `query`, best matches
+    /// first.
+    pub async fn search_conversations(
+        codex_home: &Path,
+        query: &str,
+        limit: usize,
+        allowed_sources: &[SessionSource],
+    ) -> std::io::Result<Vec<SessionSearchHit>> {
+        search_sessions(codex_home, query, limit, allowed_sources).await
+    }
+
+    /// List conversations whose tags include every entry of `required_tags`.
+    pub async fn list_conversations_with_tags(
+        codex_home: &Path,
+        page_size: usize,
+        cursor: Option<&Cursor>,
+        allowed_sources: &[SessionSource],
+        required_tags: &BTreeMap<String, String>,
+    ) -> std::io::Result<ConversationsPage> {
+        get_conversations_with_tags(
+            codex_home,
+            page_size,
+            cursor,
+            allowed_sources,
+            required_tags,
+        )
+        .await
+    }
+
     /// Attempt to create a new [`RolloutRecorder`]. If the sessions directory
     /// cannot be created or the rollout file cannot be opened we return the
     /// error so the caller can decide whether to disable persistence.
@@ -152,6 +186,8 @@ impl RolloutRecorder {
 
         // Clone the cwd for the spawned task to collect git info asynchronously
         let cwd = config.cwd.clone();
+        let redactor = Redactor::new(&config.rollout_redaction)
+            .map_err(|e| IoError::other(format!("invalid rollout_redaction pattern: {e}")))?;
 
         // A reasonably-sized bounded channel. If the buffer fills up the send
         // future will yield, which is fine – we only need to ensure we do not
@@ -161,7 +197,7 @@ impl RolloutRecorder {
         // Spawn a Tokio task that owns the file handle and performs async
         // writes. Using `tokio::fs::File` keeps everything on the async I/O
         // driver instead of blocking the runtime.
-        tokio::task::spawn(rollout_writer(file, rx, meta, cwd));
+        tokio::task::spawn(rollout_writer(file, rx, meta, cwd, redactor));
 
         Ok(Self { tx, rollout_path })
     }
@@ -234,6 +270,9 @@ impl RolloutRecorder {
                     RolloutItem::Compacted(item) => {
                         items.push(RolloutItem::Compacted(item));
                     }
+                    RolloutItem::HistoryRewound(item) => {
+                        items.push(RolloutItem::HistoryRewound(item));
+                    }
                     RolloutItem::TurnContext(item) => {
                         items.push(RolloutItem::TurnContext(item));
                     }
@@ -344,6 +383,7 @@ async fn rollout_writer(
     mut rx: mpsc::Receiver<RolloutCmd>,
     mut meta: Option<SessionMeta>,
     cwd: std::path::PathBuf,
+    redactor: Option<Redactor>,
 ) -> std::io::Result<()> {
     let mut writer = JsonlWriter { file };
 
@@ -365,8 +405,11 @@ async fn rollout_writer(
     while let Some(cmd) = rx.recv().await {
         match cmd {
             RolloutCmd::AddItems(items) => {
-                for item in items {
+                for mut item in items {
                     if is_persisted_response_item(&item) {
+                        if let Some(redactor) = &redactor {
+                            redactor.redact_item(&mut item);
+                        }
                         writer.write_rollout_item(item).await?;
                     }
                 }
@@ -0,0 +1,480 @@
+//! Full-text search over recorded sessions.
+//!
+//! The text of every turn (the user's message and the assistant's replies) is
+//! kept in an index file under `CODEX_HOME` so searches do not re-parse every
+//! rollout. Entries are refreshed when a rollout's size or modification time
+//! changes and dropped when the rollout is gone.
+//!
+//! Queries are split into words and ranked like a tiny search engine: words
+//! that occur in few sessions weigh more than words that occur everywhere, so
+//! natural-language queries such as "where I fixed the migration deadlock"
+//! work without any query syntax.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use codex_protocol::ConversationId;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use codex_protocol::protocol::SessionSource;
+use codex_protocol::protocol::USER_MESSAGE_BEGIN;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+use crate::codex::compact::content_items_to_text;
+use crate::codex::compact::is_session_prefix_message;
+use crate::truncate::truncate_middle;
+
+/// Index file, relative to `CODEX_HOME`.
+const SEARCH_INDEX_FILE: &str = "session_index.json";
+
+/// Bumped whenever the layout of the index changes; older indexes are rebuilt.
+const SEARCH_INDEX_VERSION: u32 = 1;
+
+/// Text of a single turn kept in the index, in bytes.
+const MAX_TURN_TEXT_BYTES: usize = 16 * 1024;
+
+/// Excerpts returned per matching session.
+const MAX_EXCERPTS_PER_SESSION: usize = 3;
+
+/// Characters shown around the first match in an excerpt.
+const EXCERPT_CHARS: usize = 160;
+
+/// A recorded session matching a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSearchHit {
+    /// Absolute path to the rollout file.
+    pub path: PathBuf,
+    pub conversation_id: ConversationId,
+    /// Session title, if one was recorded.
+    pub title: Option<String>,
+    /// RFC3339 timestamp string for when the session was created.
+    pub timestamp: String,
+    /// Best matching turns, best first.
+    pub excerpts: Vec<TurnExcerpt>,
+}
+
+/// Part of a turn that matched a search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnExcerpt {
+    /// 0-based index of the turn among the session's user turns.
+    pub turn: usize,
+    /// Text around the first match.
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SearchIndex {
+    version: u32,
+    sessions: BTreeMap<PathBuf, IndexedSession>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct IndexedSession {
+    len: u64,
+    modified_ms: u64,
+    conversation_id: ConversationId,
+    source: SessionSource,
+    timestamp: String,
+    title: Option<String>,
+    turns: Vec<String>,
+}
+
+/// Search the sessions recorded under `codex_home` for `query`, best matches
+/// first. Only sessions from `allowed_sources` are searched; an empty slice
+/// searches all of them.
+pub(crate) async fn search_sessions(
+    codex_home: &Path,
+    query: &str,
+    limit: usize,
+    allowed_sources: &[SessionSource],
+) -> io::Result<Vec<SessionSearchHit>> {
+    let terms = query_terms(query);
+    if terms.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    let codex_home = codex_home.to_path_buf();
+    let allowed_sources = allowed_sources.to_vec();
+    tokio::task::spawn_blocking(move || {
+        let index = refresh_index(&codex_home)?;
+        let sessions = index
+            .sessions
+            .into_iter()
+            .filter(|(_, session)| {
+                allowed_sources.is_empty() || allowed_sources.contains(&session.source)
+            })
+            .collect();
+        Ok(rank_sessions(sessions, &terms, limit))
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Lowercased words of `query`.
+fn query_terms(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| seen.insert(word.clone()))
+        .collect()
+}
+
+/// Bring the index on disk up to date with the rollouts and return it.
+fn refresh_index(codex_home: &Path) -> io::Result<SearchIndex> {
+    let index_path = codex_home.join(SEARCH_INDEX_FILE);
+    let mut index = std::fs::read(&index_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<SearchIndex>(&bytes).ok())
+        .filter(|index| index.version == SEARCH_INDEX_VERSION)
+        .unwrap_or_default();
+    let mut changed = index.version != SEARCH_INDEX_VERSION;
+    index.version = SEARCH_INDEX_VERSION;
+
+    let mut sessions = BTreeMap::new();
+    for path in rollout_paths(&codex_home.join(SESSIONS_SUBDIR)) {
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let len = metadata.len();
+        let modified_ms = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        match index.sessions.remove(&path) {
+            Some(session) if session.len == len && session.modified_ms == modified_ms => {
+                sessions.insert(path, session);
+            }
+            _ => {
+                changed = true;
+                if let Some(session) = index_rollout(&path, len, modified_ms) {
+                    sessions.insert(path, session);
+                }
+            }
+        }
+    }
+    // Anything left over belongs to deleted or archived rollouts.
+    changed |= !index.sessions.is_empty();
+    index.sessions = sessions;
+
+    if changed && let Err(e) = write_index(codex_home, &index_path, &index) {
+        warn!("failed to write session search index: {e}");
+    }
+    Ok(index)
+}
+
+fn write_index(codex_home: &Path, index_path: &Path, index: &SearchIndex) -> io::Result<()> {
+    let mut file = tempfile::NamedTempFile::new_in(codex_home)?;
+    file.write_all(&serde_json::to_vec(index)?)?;
+    file.persist(index_path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Rollout files under `root`, in any order.
+fn rollout_paths(root: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with("rollout-") && name.ends_with(".jsonl"))
+            {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+/// Index entry for the rollout at `path`, or `None` if it is not a session.
+fn index_rollout(path: &Path, len: u64, modified_ms: u64) -> Option<IndexedSession> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let items: Vec<RolloutItem> = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RolloutLine>(line).ok())
+        .map(|line| line.item)
+        .collect();
+    let meta = items.iter().find_map(|item| match item {
+        RolloutItem::SessionMeta(meta_line) => Some(&meta_line.meta),
+        _ => None,
+    })?;
+    let title = items.iter().rev().find_map(|item| match item {
+        RolloutItem::EventMsg(EventMsg::SessionTitle(event)) => Some(event.title.clone()),
+        _ => None,
+    });
+    Some(IndexedSession {
+        len,
+        modified_ms,
+        conversation_id: meta.id,
+        source: meta.source,
+        timestamp: meta.timestamp.clone(),
+        title,
+        turns: collect_turns(&items),
+    })
+}
+
+/// Text of each user turn: the user's message followed by the assistant's
+/// replies. Turns are counted the same way as when forking a conversation.
+fn collect_turns(items: &[RolloutItem]) -> Vec<String> {
+    let mut turns: Vec<String> = Vec::new();
+    for item in items {
+        let RolloutItem::ResponseItem(ResponseItem::Message { role, content, .. }) = item else {
+            continue;
+        };
+        let Some(text) = content_items_to_text(content) else {
+            continue;
+        };
+        match role.as_str() {
+            "user" if !is_session_prefix_message(&text) => {
+                let text = match text.find(USER_MESSAGE_BEGIN) {
+                    Some(idx) => text[idx + USER_MESSAGE_BEGIN.len()..].trim().to_string(),
+                    None => text,
+                };
+                turns.push(text);
+            }
+            "assistant" => {
+                if let Some(turn) = turns.last_mut() {
+                    turn.push_str("\n\n");
+                    turn.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    for turn in &mut turns {
+        if turn.len() > MAX_TURN_TEXT_BYTES {
+            *turn = truncate_middle(turn, MAX_TURN_TEXT_BYTES).0;
+        }
+    }
+    turns
+}
+
+/// Score every session against `terms` and keep the `limit` best.
+fn rank_sessions(
+    sessions: BTreeMap<PathBuf, IndexedSession>,
+    terms: &[String],
+    limit: usize,
+) -> Vec<SessionSearchHit> {
+    // Lowercased once; matching is by substring so "deadlock" also finds
+    // "deadlocks".
+    let lowered: Vec<(PathBuf, IndexedSession, Option<String>, Vec<String>)> = sessions
+        .into_iter()
+        .map(|(path, session)| {
+            let title = session.title.as_deref().map(str::to_lowercase);
+            let turns = session
+                .turns
+                .iter()
+                .map(|turn| turn.to_lowercase())
+                .collect();
+            (path, session, title, turns)
+        })
+        .collect();
+
+    // Words found in few sessions say more about a session than common ones;
+    // words found in none count for nothing.
+    let total = lowered.len() as f64;
+    let weights: Vec<f64> = terms
+        .iter()
+        .map(|term| {
+            let containing = lowered
+                .iter()
+                .filter(|(_, _, title, turns)| {
+                    title
+                        .as_deref()
+                        .is_some_and(|title| title.contains(term.as_str()))
+                        || turns.iter().any(|turn| turn.contains(term.as_str()))
+                })
+                .count();
+            if containing == 0 {
+                0.0
+            } else {
+                ((total + 1.0) / containing as f64).ln()
+            }
+        })
+        .collect();
+    let score = |text: &str| -> f64 {
+        terms
+            .iter()
+            .zip(&weights)
+            .filter(|(term, _)| text.contains(term.as_str()))
+            .map(|(_, weight)| weight)
+            .sum()
+    };
+
+    let mut hits: Vec<(f64, SessionSearchHit)> = lowered
+        .into_iter()
+        .filter_map(|(path, session, title, turns)| {
+            let mut scored_turns: Vec<(f64, usize)> = turns
+                .iter()
+                .enumerate()
+                .map(|(idx, turn)| (score(turn), idx))
+                .filter(|(turn_score, _)| *turn_score > 0.0)
+                .collect();
+            let title_score = title.as_deref().map_or(0.0, score);
+            let best_turn = scored_turns.iter().map(|(s, _)| *s).fold(0.0, f64::max);
+            let session_score = best_turn.max(title_score);
+            if session_score <= 0.0 {
+                return None;
+            }
+            scored_turns.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+            let excerpts = scored_turns
+                .into_iter()
+                .take(MAX_EXCERPTS_PER_SESSION)
+                .map(|(_, turn)| TurnExcerpt {
+                    turn,
+                    text: excerpt(&session.turns[turn], &turns[turn], terms),
+                })
+                .collect();
+            Some((
+                session_score,
+                SessionSearchHit {
+                    path,
+                    conversation_id: session.conversation_id,
+                    title: session.title,
+                    timestamp: session.timestamp,
+                    excerpts,
+                },
+            ))
+        })
+        .collect();
+    // Best first, then newest first.
+    hits.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .total_cmp(a_score)
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+    });
+    hits.into_iter().take(limit).map(|(_, hit)| hit).collect()
+}
+
+/// Up to [`EXCERPT_CHARS`] characters of `text` around the first term found
+/// in `lowered` (its lowercase form), on a single line.
+fn excerpt(text: &str, lowered: &str, terms: &[String]) -> String {
+    let text: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = lowered.chars().collect();
+    // Lowercasing can change the number of characters; fall back to the
+    // start of the turn in that case.
+    let first_match = if text.len() == lowered.len() {
+        let lowered: String = lowered.iter().collect();
+        terms
+            .iter()
+            .filter_map(|term| lowered.find(term.as_str()))
+            .min()
+            .map(|byte_idx| lowered[..byte_idx].chars().count())
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    let start = first_match.saturating_sub(EXCERPT_CHARS / 4);
+    let end = (start + EXCERPT_CHARS).min(text.len());
+    let start = end.saturating_sub(EXCERPT_CHARS);
+    let snippet: String = text[start..end].iter().collect();
+    let mut snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn session(timestamp: &str, title: Option<&str>, turns: &[&str]) -> IndexedSession {
+        IndexedSession {
+            len: 0,
+            modified_ms: 0,
+            conversation_id: ConversationId::default(),
+            source: SessionSource::Cli,
+            timestamp: timestamp.to_string(),
+            title: title.map(str::to_string),
+            turns: turns.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn rare_words_outrank_common_ones() {
+        let sessions = BTreeMap::from([
+            (
+                PathBuf::from("a.jsonl"),
+                session(
+                    "2025-01-01T00:00:00Z",
+                    None,
+                    &["Add the retry to the uploader", "Run the tests"],
+                ),
+            ),
+            (
+                PathBuf::from("b.jsonl"),
+                session(
+                    "2025-01-02T00:00:00Z",
+                    Some("Fix migration deadlock"),
+                    &[
+                        "The nightly migration hangs",
+                        "Found it: the Deadlock comes from two locks taken in the wrong order.",
+                    ],
+                ),
+            ),
+            (
+                PathBuf::from("c.jsonl"),
+                session("2025-01-03T00:00:00Z", None, &["Explain the codebase"]),
+            ),
+        ]);
+
+        let hits = rank_sessions(
+            sessions,
+            &query_terms("where I fixed the migration deadlock"),
+            10,
+        );
+
+        assert_eq!(hits[0].path, PathBuf::from("b.jsonl"));
+        assert_eq!(
+            hits[0].excerpts,
+            vec![
+                TurnExcerpt {
+                    turn: 0,
+                    text: "The nightly migration hangs".to_string(),
+                },
+                TurnExcerpt {
+                    turn: 1,
+                    text: "Found it: the Deadlock comes from two locks taken in the wrong order."
+                        .to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn excerpt_centers_on_the_first_match() {
+        let text = format!("{} needle {}", "a ".repeat(200), "b ".repeat(200));
+        let excerpt = excerpt(&text, &text.to_lowercase(), &["needle".to_string()]);
+        assert!(excerpt.starts_with('…'));
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.contains("needle"));
+        assert!(excerpt.chars().count() <= EXCERPT_CHARS + 2);
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::fs::{self};
 use std::io::Write;
@@ -18,6 +19,7 @@ use crate::rollout::list::ConversationsPage;
 use crate::rollout::list::Cursor;
 use crate::rollout::list::get_conversation;
 use crate::rollout::list::get_conversations;
+use crate::rollout::list::get_conversations_with_tags;
 use anyhow::Result;
 use codex_protocol::ConversationId;
 use codex_protocol::models::ContentItem;
@@ -30,6 +32,8 @@ use codex_protocol::protocol::RolloutLine;
 use codex_protocol::protocol::SessionMeta;
 use codex_protocol::protocol::SessionMetaLine;
 use codex_protocol::protocol::SessionSource;
+use codex_protocol::protocol::SessionTagsEvent;
+use codex_protocol::protocol::SessionTitleEvent;
 use codex_protocol::protocol::UserMessageEvent;
 
 const NO_SOURCE_FILTER: &[SessionSource] = &[];
@@ -198,6 +202,8 @@ async fn test_list_conversations_latest_first() {
                 tail: Vec::new(),
                 created_at: Some("2025-01-03T12-00-00".into()),
                 updated_at: Some("2025-01-03T12-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
             ConversationItem {
                 path: p2,
@@ -205,6 +211,8 @@ async fn test_list_conversations_latest_first() {
                 tail: Vec::new(),
                 created_at: Some("2025-01-02T12-00-00".into()),
                 updated_at: Some("2025-01-02T12-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
             ConversationItem {
                 path: p3,
@@ -212,6 +220,8 @@ async fn test_list_conversations_latest_first() {
                 tail: Vec::new(),
                 created_at: Some("2025-01-01T12-00-00".into()),
                 updated_at: Some("2025-01-01T12-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
         ],
         next_cursor: Some(expected_cursor),
@@ -319,6 +329,8 @@ async fn test_pagination_cursor() {
                 tail: Vec::new(),
                 created_at: Some("2025-03-05T09-00-00".into()),
                 updated_at: Some("2025-03-05T09-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
             ConversationItem {
                 path: p4,
@@ -326,6 +338,8 @@ async fn test_pagination_cursor() {
                 tail: Vec::new(),
                 created_at: Some("2025-03-04T09-00-00".into()),
                 updated_at: Some("2025-03-04T09-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
         ],
         next_cursor: Some(expected_cursor1.clone()),
@@ -382,6 +396,8 @@ async fn test_pagination_cursor() {
                 tail: Vec::new(),
                 created_at: Some("2025-03-03T09-00-00".into()),
                 updated_at: Some("2025-03-03T09-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
             ConversationItem {
                 path: p2,
@@ -389,6 +405,8 @@ async fn test_pagination_cursor() {
                 tail: Vec::new(),
                 created_at: Some("2025-03-02T09-00-00".into()),
                 updated_at: Some("2025-03-02T09-00-00".into()),
+                title: None,
+                tags: BTreeMap::new(),
             },
         ],
         next_cursor: Some(expected_cursor2.clone()),
@@ -429,6 +447,8 @@ async fn test_pagination_cursor() {
             tail: Vec::new(),
             created_at: Some("2025-03-01T09-00-00".into()),
             updated_at: Some("2025-03-01T09-00-00".into()),
+            title: None,
+            tags: BTreeMap::new(),
         }],
         next_cursor: Some(expected_cursor3),
         num_scanned_files: 5, // scanned 05, 04 (anchor), 03, 02 (anchor), 01
@@ -477,6 +497,8 @@ async fn test_get_conversation_contents() {
             tail: Vec::new(),
             created_at: Some(ts.into()),
             updated_at: Some(ts.into()),
+            title: None,
+            tags: BTreeMap::new(),
         }],
         next_cursor: Some(expected_cursor),
         num_scanned_files: 1,
@@ -593,6 +615,144 @@ async fn test_tail_includes_last_response_items() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_title_is_found_past_the_head_and_renames_win() -> Result<()> {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+
+    let ts = "2025-06-03T08-00-00";
+    let uuid = Uuid::from_u128(43);
+    let day_dir = home.join("sessions").join("2025").join("06").join("03");
+    fs::create_dir_all(&day_dir)?;
+    let file_path = day_dir.join(format!("rollout-{ts}-{uuid}.jsonl"));
+    let mut file = File::create(&file_path)?;
+
+    let conversation_id = ConversationId::from_string(&uuid.to_string())?;
+    let mut lines = vec![
+        RolloutItem::SessionMeta(SessionMetaLine {
+            meta: SessionMeta {
+                id: conversation_id,
+                timestamp: ts.to_string(),
+                instructions: None,
+                cwd: ".".into(),
+                originator: "test_originator".into(),
+                cli_version: "test_version".into(),
+                source: SessionSource::VSCode,
+            },
+            git: None,
+        }),
+        RolloutItem::EventMsg(EventMsg::UserMessage(UserMessageEvent {
+            message: "hello".into(),
+            kind: Some(InputMessageKind::Plain),
+            images: None,
+        })),
+    ];
+    let reply = |idx: usize| {
+        RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: "assistant".into(),
+            content: vec![ContentItem::OutputText {
+                text: format!("reply-{idx}"),
+            }],
+        })
+    };
+    // The generated title lands after the head and before the tail.
+    lines.extend((0..15).map(reply));
+    lines.push(RolloutItem::EventMsg(EventMsg::SessionTitle(
+        SessionTitleEvent {
+            title: "Fix flaky websocket test".into(),
+        },
+    )));
+    lines.extend((15..30).map(reply));
+    for item in lines {
+        let line = RolloutLine {
+            timestamp: ts.to_string(),
+            item,
+        };
+        writeln!(file, "{}", serde_json::to_string(&line)?)?;
+    }
+
+    let page = get_conversations(home, 1, None, INTERACTIVE_SESSION_SOURCES).await?;
+    assert_eq!(
+        page.items[0].title.as_deref(),
+        Some("Fix flaky websocket test")
+    );
+
+    let rename = RolloutLine {
+        timestamp: ts.to_string(),
+        item: RolloutItem::EventMsg(EventMsg::SessionTitle(SessionTitleEvent {
+            title: "Websocket test fix".into(),
+        })),
+    };
+    writeln!(file, "{}", serde_json::to_string(&rename)?)?;
+    drop(file);
+
+    let page = get_conversations(home, 1, None, INTERACTIVE_SESSION_SOURCES).await?;
+    assert_eq!(page.items[0].title.as_deref(), Some("Websocket test fix"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tag_filter_uses_latest_tags() -> Result<()> {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+
+    let tagged = |pairs: &[(&str, &str)]| {
+        let tags: BTreeMap<String, String> = pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        RolloutItem::EventMsg(EventMsg::SessionTags(SessionTagsEvent { tags }))
+    };
+    let append = |ts: &str, uuid: Uuid, item: RolloutItem| -> Result<()> {
+        let path = home
+            .join("sessions/2025/06/04")
+            .join(format!("rollout-{ts}-{uuid}.jsonl"));
+        let mut file = fs::OpenOptions::new().append(true).open(path)?;
+        let line = RolloutLine {
+            timestamp: ts.to_string(),
+            item,
+        };
+        writeln!(file, "{}", serde_json::to_string(&line)?)?;
+        Ok(())
+    };
+
+    let (u1, u2) = (Uuid::from_u128(51), Uuid::from_u128(52));
+    write_session_file(home, "2025-06-04T08-00-00", u1, 2, Some(SessionSource::Cli))?;
+    append("2025-06-04T08-00-00", u1, tagged(&[("ticket", "ABC-1")]))?;
+    write_session_file(home, "2025-06-04T09-00-00", u2, 2, Some(SessionSource::Cli))?;
+    append("2025-06-04T09-00-00", u2, tagged(&[("ticket", "ABC-1")]))?;
+    // The newer session moved on to another ticket.
+    append(
+        "2025-06-04T09-00-00",
+        u2,
+        tagged(&[("ticket", "ABC-2"), ("urgent", "")]),
+    )?;
+
+    let required = [("ticket".to_string(), "ABC-1".to_string())].into();
+    let page =
+        get_conversations_with_tags(home, 10, None, INTERACTIVE_SESSION_SOURCES, &required).await?;
+    assert_eq!(page.items.len(), 1);
+    assert!(
+        page.items[0]
+            .path
+            .ends_with(format!("rollout-2025-06-04T08-00-00-{u1}.jsonl"))
+    );
+
+    let page = get_conversations(home, 10, None, INTERACTIVE_SESSION_SOURCES).await?;
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(
+        page.items[0].tags,
+        BTreeMap::from([
+            ("ticket".to_string(), "ABC-2".to_string()),
+            ("urgent".to_string(), String::new()),
+        ])
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_tail_handles_short_sessions() -> Result<()> {
     let temp = TempDir::new().unwrap();
@@ -736,6 +896,7 @@ async fn test_tail_skips_trailing_non_responses() -> Result<()> {
         timestamp: format!("{ts}-compacted"),
         item: RolloutItem::Compacted(CompactedItem {
             message: "compacted".into(),
+            replacement_history: None,
         }),
     };
     writeln!(file, "{}", serde_json::to_string(&compacted_line)?)?;
@@ -825,6 +986,8 @@ async fn test_stable_ordering_same_second_pagination() {
                 tail: Vec::new(),
                 created_at: Some(ts.to_string()),
                 updated_at: Some(ts.to_string()),
+                title: None,
+                tags: BTreeMap::new(),
             },
             ConversationItem {
                 path: p2,
@@ -832,6 +995,8 @@ async fn test_stable_ordering_same_second_pagination() {
                 tail: Vec::new(),
                 created_at: Some(ts.to_string()),
                 updated_at: Some(ts.to_string()),
+                title: None,
+                tags: BTreeMap::new(),
             },
         ],
         next_cursor: Some(expected_cursor1.clone()),
@@ -862,6 +1027,8 @@ async fn test_stable_ordering_same_second_pagination() {
             tail: Vec::new(),
             created_at: Some(ts.to_string()),
             updated_at: Some(ts.to_string()),
+            title: None,
+            tags: BTreeMap::new(),
         }],
         next_cursor: Some(expected_cursor2),
         num_scanned_files: 3, // scanned u3, u2 (anchor), u1
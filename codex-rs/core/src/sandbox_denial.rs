@@ -0,0 +1,201 @@
+//! Best-effort identification of what a sandboxed command was blocked from
+//! doing, based on the error messages it printed to stderr.
+
+use std::path::Path;
+
+use crate::exec::ExecToolCallOutput;
+use crate::protocol::SandboxDeniedEvent;
+use crate::protocol::SandboxOperation;
+
+/// Error strings emitted by common tools when the sandbox refuses a write.
+const FILE_DENIAL_MARKERS: &[&str] = &[
+    "Operation not permitted",
+    "Permission denied",
+    "Read-only file system",
+];
+
+/// Error strings emitted when outbound network access is unavailable but the
+/// host cannot be recovered from the message.
+const NETWORK_DENIAL_MARKERS: &[&str] = &[
+    "Temporary failure in name resolution",
+    "Name or service not known",
+    "nodename nor servname provided",
+    "Network is unreachable",
+];
+
+/// Inspect the output of a command that failed under the sandbox and describe
+/// the blocked operation, if it can be recognized.
+pub(crate) fn detect_sandbox_denial(
+    call_id: &str,
+    cwd: &Path,
+    output: &ExecToolCallOutput,
+) -> Option<SandboxDeniedEvent> {
+    let stderr = &output.stderr.text;
+
+    if let Some(host) = stderr.lines().find_map(extract_host) {
+        return Some(SandboxDeniedEvent {
+            call_id: call_id.to_string(),
+            operation: SandboxOperation::Network,
+            path: None,
+            host: Some(host),
+        });
+    }
+    if stderr
+        .lines()
+        .any(|line| NETWORK_DENIAL_MARKERS.iter().any(|m| line.contains(m)))
+    {
+        return Some(SandboxDeniedEvent {
+            call_id: call_id.to_string(),
+            operation: SandboxOperation::Network,
+            path: None,
+            host: None,
+        });
+    }
+
+    let line = stderr
+        .lines()
+        .find(|line| FILE_DENIAL_MARKERS.iter().any(|m| line.contains(m)))?;
+    Some(SandboxDeniedEvent {
+        call_id: call_id.to_string(),
+        operation: SandboxOperation::FileWrite,
+        path: extract_path(line).map(|path| cwd.join(path)),
+        host: None,
+    })
+}
+
+/// Recover the host from the network errors printed by curl, git and node.
+fn extract_host(line: &str) -> Option<String> {
+    const HOST_PREFIXES: &[&str] = &[
+        "Could not resolve host: ",
+        "Failed to connect to ",
+        "getaddrinfo ENOTFOUND ",
+        "getaddrinfo EAI_AGAIN ",
+    ];
+    for prefix in HOST_PREFIXES {
+        if let Some((_, rest)) = line.split_once(prefix) {
+            let host = rest
+                .split(|c: char| c.is_whitespace() || c == ':' || c == '\'')
+                .next()
+                .unwrap_or_default();
+            if !host.is_empty() {
+                return Some(host.to_string());
+            }
+        }
+    }
+
+    // git: "fatal: unable to access 'https://github.com/openai/codex/': ..."
+    let (_, rest) = line.split_once("unable to access '")?;
+    let (_, rest) = rest.split_once("://")?;
+    let host = rest
+        .split(['/', ':', '\''])
+        .next()
+        .filter(|host| !host.is_empty())?;
+    Some(host.to_string())
+}
+
+/// Recover the offending path from messages such as
+/// `touch: cannot touch '/etc/foo': Permission denied` or
+/// `bash: /etc/foo: Read-only file system`.
+fn extract_path(line: &str) -> Option<String> {
+    let prefix = FILE_DENIAL_MARKERS
+        .iter()
+        .find_map(|marker| line.split_once(&format!(": {marker}")))
+        .map(|(prefix, _)| prefix)?;
+
+    for (open, close) in [('\'', '\''), ('‘', '’'), ('"', '"')] {
+        if let Some(end) = prefix.rfind(close)
+            && let Some(start) = prefix[..end].rfind(open)
+        {
+            let quoted = &prefix[start + open.len_utf8()..end];
+            if !quoted.is_empty() {
+                return Some(quoted.to_string());
+            }
+        }
+    }
+
+    let candidate = prefix.rsplit(": ").next()?.trim();
+    if candidate.is_empty() || candidate.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::StreamOutput;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn output_with_stderr(stderr: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(stderr.to_string()),
+            aggregated_output: StreamOutput::new(stderr.to_string()),
+            duration: Duration::from_millis(1),
+            timed_out: false,
+        }
+    }
+
+    fn detect(stderr: &str) -> Option<(SandboxOperation, Option<PathBuf>, Option<String>)> {
+        detect_sandbox_denial("call", Path::new("/work"), &output_with_stderr(stderr))
+            .map(|ev| (ev.operation, ev.path, ev.host))
+    }
+
+    #[test]
+    fn detects_file_writes() {
+        assert_eq!(
+            Some((
+                SandboxOperation::FileWrite,
+                Some(PathBuf::from("/etc/hosts")),
+                None
+            )),
+            detect("touch: cannot touch '/etc/hosts': Permission denied")
+        );
+        assert_eq!(
+            Some((
+                SandboxOperation::FileWrite,
+                Some(PathBuf::from("/work/out.txt")),
+                None
+            )),
+            detect("bash: line 1: out.txt: Read-only file system")
+        );
+        assert_eq!(
+            Some((SandboxOperation::FileWrite, None, None)),
+            detect("Operation not permitted")
+        );
+    }
+
+    #[test]
+    fn detects_network_access() {
+        assert_eq!(
+            Some((
+                SandboxOperation::Network,
+                None,
+                Some("example.com".to_string())
+            )),
+            detect("curl: (6) Could not resolve host: example.com")
+        );
+        assert_eq!(
+            Some((
+                SandboxOperation::Network,
+                None,
+                Some("github.com".to_string())
+            )),
+            detect(
+                "fatal: unable to access 'https://github.com/openai/codex/': Could not resolve host"
+            )
+        );
+        assert_eq!(
+            Some((SandboxOperation::Network, None, None)),
+            detect("ping: Temporary failure in name resolution")
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_failures() {
+        assert_eq!(None, detect("grep: no matches"));
+    }
+}
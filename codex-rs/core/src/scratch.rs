@@ -0,0 +1,144 @@
+//! Per-session scratch directory: a private place outside the workspace where
+//! the agent can put temporary artifacts. It is writable under the
+//! `workspace-write` sandbox, its path is exported to commands through
+//! [`CODEX_SCRATCH_DIR_ENV_VAR`], and it is deleted when the session ends.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tempfile::TempDir;
+use tracing::warn;
+
+use crate::codex::TurnContext;
+use crate::config_types::ShellEnvironmentPolicy;
+use crate::protocol::SandboxPolicy;
+
+/// Environment variable holding the session's scratch directory.
+pub const CODEX_SCRATCH_DIR_ENV_VAR: &str = "CODEX_SCRATCH_DIR";
+
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    path: PathBuf,
+    /// Removes the directory when dropped, should the session end without
+    /// an explicit shutdown.
+    dir: Mutex<Option<TempDir>>,
+}
+
+impl ScratchDir {
+    pub(crate) fn create() -> std::io::Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("codex-scratch-")
+            .tempdir()?;
+        // Canonicalize so the path matches what sandboxes see (e.g. macOS
+        // resolves /var to /private/var).
+        let path = dunce::canonicalize(dir.path())?;
+        Ok(Self {
+            path,
+            dir: Mutex::new(Some(dir)),
+        })
+    }
+
+    /// `policy` with the scratch directory added to its writable roots. Other
+    /// policies either already allow writing it or deliberately forbid it.
+    pub(crate) fn apply_to_sandbox(&self, policy: SandboxPolicy) -> SandboxPolicy {
+        match policy {
+            SandboxPolicy::WorkspaceWrite {
+                mut writable_roots,
+                network_access,
+                exclude_tmpdir_env_var,
+                exclude_slash_tmp,
+            } => {
+                if !writable_roots.contains(&self.path) {
+                    writable_roots.push(self.path.clone());
+                }
+                SandboxPolicy::WorkspaceWrite {
+                    writable_roots,
+                    network_access,
+                    exclude_tmpdir_env_var,
+                    exclude_slash_tmp,
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// `policy` with [`CODEX_SCRATCH_DIR_ENV_VAR`] set for spawned commands.
+    pub(crate) fn apply_to_env(
+        &self,
+        mut policy: ShellEnvironmentPolicy,
+    ) -> ShellEnvironmentPolicy {
+        policy.r#set.insert(
+            CODEX_SCRATCH_DIR_ENV_VAR.to_string(),
+            self.path.to_string_lossy().into_owned(),
+        );
+        policy
+    }
+
+    /// Delete the directory and everything in it.
+    pub(crate) fn remove(&self) {
+        let dir = self
+            .dir
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(dir) = dir
+            && let Err(e) = dir.close()
+        {
+            warn!(
+                "failed to remove scratch directory {}: {e}",
+                self.path.display()
+            );
+        }
+    }
+}
+
+/// The turn's sandbox policy without the scratch directory, as described to
+/// the model. The path differs between sessions, so leaving it out keeps the
+/// environment context stable when a conversation is resumed or forked.
+pub(crate) fn model_visible_sandbox_policy(turn_context: &TurnContext) -> SandboxPolicy {
+    let mut policy = turn_context.sandbox_policy.clone();
+    let scratch_dir = turn_context
+        .shell_environment_policy
+        .r#set
+        .get(CODEX_SCRATCH_DIR_ENV_VAR);
+    if let (SandboxPolicy::WorkspaceWrite { writable_roots, .. }, Some(scratch_dir)) =
+        (&mut policy, scratch_dir)
+    {
+        writable_roots.retain(|root| root.as_os_str() != scratch_dir.as_str());
+    }
+    policy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scratch_dir_is_writable_exported_and_removed() {
+        let scratch = ScratchDir::create().expect("create scratch dir");
+        assert!(scratch.path.is_dir());
+
+        let policy = scratch.apply_to_sandbox(SandboxPolicy::new_workspace_write_policy());
+        // Applying twice does not duplicate the root.
+        let policy = scratch.apply_to_sandbox(policy);
+        let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = &policy else {
+            panic!("expected workspace-write policy");
+        };
+        assert_eq!(&vec![scratch.path.clone()], writable_roots);
+        assert_eq!(
+            SandboxPolicy::ReadOnly,
+            scratch.apply_to_sandbox(SandboxPolicy::ReadOnly)
+        );
+
+        let env = scratch.apply_to_env(ShellEnvironmentPolicy::default());
+        assert_eq!(
+            Some(&scratch.path.to_string_lossy().into_owned()),
+            env.r#set.get(CODEX_SCRATCH_DIR_ENV_VAR)
+        );
+
+        std::fs::write(scratch.path.join("artifact.txt"), "tmp").expect("write");
+        scratch.remove();
+        assert!(!scratch.path.exists());
+    }
+}
@@ -0,0 +1,384 @@
+//! Changes the session has made to the working tree, reported as hunks per
+//! file by the `review_changes` tool and `Op::GetSessionDiff`.
+//!
+//! Each file is compared against the contents it had before the session
+//! first changed it, which is taken from the turns' file logs. Frontends can
+//! resolve individual hunks with `Op::ResolveHunks`: a reverted hunk is undone
+//! on disk, an accepted one is folded into the file's baseline so it is no
+//! longer reported.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::json;
+use similar::ChangeTag;
+use similar::DiffTag;
+use similar::TextDiff;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::DiffHunk;
+use crate::protocol::FileDiff;
+use crate::protocol::FileDiffStatus;
+use crate::protocol::HunkAction;
+
+pub(crate) const REVIEW_CHANGES_TOOL_NAME: &str = "review_changes";
+
+/// Lines of unchanged context around each hunk.
+const HUNK_CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Deserialize)]
+struct ReviewChangesArgs {
+    /// Only report these files, relative to the working directory.
+    #[serde(default)]
+    paths: Vec<String>,
+}
+
+/// The contents files had before the session first changed them.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SessionChanges {
+    /// `None` for files the session created.
+    baselines: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+impl SessionChanges {
+    /// Record the contents of files from before a turn changed them. Files
+    /// already recorded keep their older baseline.
+    pub(crate) fn record(
+        &mut self,
+        originals: impl IntoIterator<Item = (PathBuf, Option<Vec<u8>>)>,
+    ) {
+        for (path, original) in originals {
+            self.baselines.entry(path).or_insert(original);
+        }
+    }
+
+    /// The files that still differ from their baseline, ordered by path.
+    pub(crate) async fn diff(&self) -> Vec<FileDiff> {
+        let mut files = Vec::new();
+        for (path, baseline) in &self.baselines {
+            let current = tokio::fs::read(path).await.ok();
+            if let Some(diff) = diff_file(path, baseline.as_deref(), current.as_deref()) {
+                files.push(diff);
+            }
+        }
+        files
+    }
+
+    /// Accept or revert `hunks` of `path`, numbered as reported by
+    /// [`SessionChanges::diff`].
+    pub(crate) async fn resolve(
+        &mut self,
+        path: &Path,
+        hunks: &[usize],
+        action: HunkAction,
+    ) -> Result<(), String> {
+        let Some(baseline) = self.baselines.get(path) else {
+            return Err(format!("the session has not changed {}", path.display()));
+        };
+        let current = tokio::fs::read(path).await.ok();
+        let (Ok(old), Ok(new)) = (
+            std::str::from_utf8(baseline.as_deref().unwrap_or_default()),
+            std::str::from_utf8(current.as_deref().unwrap_or_default()),
+        ) else {
+            return Err(format!(
+                "{} is binary and cannot be resolved by hunk",
+                path.display()
+            ));
+        };
+        let count = hunk_count(old, new);
+        if let Some(hunk) = hunks.iter().find(|hunk| **hunk >= count) {
+            return Err(format!(
+                "{} has {count} hunks; there is no hunk {hunk}",
+                path.display()
+            ));
+        }
+        let all = (0..count).all(|hunk| hunks.contains(&hunk));
+
+        match action {
+            HunkAction::Accept => {
+                if all {
+                    self.baselines.remove(path);
+                } else {
+                    let merged = merge_hunks(old, new, |hunk| hunks.contains(&hunk));
+                    self.baselines
+                        .insert(path.to_path_buf(), Some(merged.into_bytes()));
+                }
+            }
+            HunkAction::Revert => {
+                let reverted = if all {
+                    baseline.clone()
+                } else {
+                    Some(merge_hunks(old, new, |hunk| !hunks.contains(&hunk)).into_bytes())
+                };
+                let result = match reverted {
+                    Some(contents) => {
+                        if let Some(parent) = path.parent() {
+                            tokio::fs::create_dir_all(parent)
+                                .await
+                                .map_err(|e| format!("failed to revert {}: {e}", path.display()))?;
+                        }
+                        tokio::fs::write(path, contents).await
+                    }
+                    None => match tokio::fs::remove_file(path).await {
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                        result => result,
+                    },
+                };
+                result.map_err(|e| format!("failed to revert {}: {e}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Handle a `review_changes` call with the session's changes, including
+/// those of the running turn.
+pub(crate) async fn handle_review_changes_tool(
+    changes: &SessionChanges,
+    cwd: &Path,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: ReviewChangesArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    let filter: Vec<PathBuf> = args.paths.iter().map(|path| cwd.join(path)).collect();
+    let mut files = changes.diff().await;
+    if !filter.is_empty() {
+        files.retain(|file| filter.contains(&file.path));
+    }
+    let additions: usize = files.iter().map(|file| file.additions).sum();
+    let deletions: usize = files.iter().map(|file| file.deletions).sum();
+    for file in &mut files {
+        if let Ok(relative) = file.path.strip_prefix(cwd) {
+            file.path = relative.to_path_buf();
+        }
+    }
+    Ok(json!({
+        "files": files,
+        "additions": additions,
+        "deletions": deletions,
+    })
+    .to_string())
+}
+
+fn diff_file(path: &Path, before: Option<&[u8]>, after: Option<&[u8]>) -> Option<FileDiff> {
+    let status = match (before, after) {
+        (None, None) => return None,
+        (Some(before), Some(after)) if before == after => return None,
+        (None, Some(_)) => FileDiffStatus::Added,
+        (Some(_), None) => FileDiffStatus::Deleted,
+        (Some(_), Some(_)) => FileDiffStatus::Modified,
+    };
+    let mut diff = FileDiff {
+        path: path.to_path_buf(),
+        status,
+        additions: 0,
+        deletions: 0,
+        binary: false,
+        hunks: Vec::new(),
+    };
+    let (Ok(old), Ok(new)) = (
+        std::str::from_utf8(before.unwrap_or_default()),
+        std::str::from_utf8(after.unwrap_or_default()),
+    ) else {
+        diff.binary = true;
+        return Some(diff);
+    };
+    diff.hunks = diff_hunks(old, new);
+    for line in diff.hunks.iter().flat_map(|hunk| &hunk.lines) {
+        if line.starts_with('+') {
+            diff.additions += 1;
+        } else if line.starts_with('-') {
+            diff.deletions += 1;
+        }
+    }
+    Some(diff)
+}
+
+fn diff_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    diff.grouped_ops(HUNK_CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let (first, last) = (group.first()?, group.last()?);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| {
+                    let prefix = match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    };
+                    let value = change.value();
+                    let value = value.strip_suffix('\n').unwrap_or(value);
+                    format!("{prefix}{}", value.strip_suffix('\r').unwrap_or(value))
+                })
+                .collect();
+            // Unified diffs number an empty range after the line it follows.
+            let start = |range: &std::ops::Range<usize>| {
+                if range.is_empty() {
+                    range.start
+                } else {
+                    range.start + 1
+                }
+            };
+            Some(DiffHunk {
+                old_start: start(&old_range),
+                old_lines: old_range.len(),
+                new_start: start(&new_range),
+                new_lines: new_range.len(),
+                lines,
+            })
+        })
+        .collect()
+}
+
+fn hunk_count(old: &str, new: &str) -> usize {
+    TextDiff::from_lines(old, new)
+        .grouped_ops(HUNK_CONTEXT_LINES)
+        .len()
+}
+
+/// `old` with the changes of the hunks for which `take_new` holds applied.
+fn merge_hunks(old: &str, new: &str, take_new: impl Fn(usize) -> bool) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let groups = diff.grouped_ops(HUNK_CONTEXT_LINES);
+    let (old_lines, new_lines) = (diff.old_slices(), diff.new_slices());
+    let mut merged = String::with_capacity(new.len());
+    for op in diff.ops() {
+        // Grouping trims unchanged runs but keeps every change intact.
+        let take = op.tag() != DiffTag::Equal
+            && groups
+                .iter()
+                .position(|group| group.contains(op))
+                .is_some_and(&take_new);
+        let lines = if take {
+            &new_lines[op.new_range()]
+        } else {
+            &old_lines[op.old_range()]
+        };
+        lines.iter().for_each(|line| merged.push_str(line));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn numbered(lines: std::ops::RangeInclusive<usize>) -> String {
+        lines.map(|n| format!("line {n}\n")).collect()
+    }
+
+    #[test]
+    fn reports_hunks_with_line_numbers_and_stats() {
+        let old = numbered(1..=20);
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+        let diff = diff_file(
+            Path::new("a.txt"),
+            Some(old.as_bytes()),
+            Some(new.as_bytes()),
+        )
+        .expect("diff");
+
+        assert_eq!(FileDiffStatus::Modified, diff.status);
+        assert_eq!((1, 2), (diff.additions, diff.deletions));
+        assert_eq!(
+            vec![
+                DiffHunk {
+                    old_start: 1,
+                    old_lines: 5,
+                    new_start: 1,
+                    new_lines: 5,
+                    lines: vec![
+                        " line 1".to_string(),
+                        "-line 2".to_string(),
+                        "+line two".to_string(),
+                        " line 3".to_string(),
+                        " line 4".to_string(),
+                        " line 5".to_string(),
+                    ],
+                },
+                DiffHunk {
+                    old_start: 15,
+                    old_lines: 6,
+                    new_start: 15,
+                    new_lines: 5,
+                    lines: vec![
+                        " line 15".to_string(),
+                        " line 16".to_string(),
+                        " line 17".to_string(),
+                        "-line 18".to_string(),
+                        " line 19".to_string(),
+                        " line 20".to_string(),
+                    ],
+                },
+            ],
+            diff.hunks
+        );
+        assert_eq!(None, diff_file(Path::new("a.txt"), Some(b"x"), Some(b"x")));
+    }
+
+    #[tokio::test]
+    async fn resolves_individual_hunks() {
+        let temp = TempDir::new().expect("tempdir");
+        let path = temp.path().join("a.txt");
+        let created = temp.path().join("new.txt");
+        let old = numbered(1..=20);
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+        std::fs::write(&path, &new).expect("write");
+        std::fs::write(&created, "fresh\n").expect("write");
+        let mut changes = SessionChanges::default();
+        changes.record([
+            (path.clone(), Some(old.clone().into_bytes())),
+            (created.clone(), None),
+        ]);
+
+        // Reverting the second hunk leaves the first on disk.
+        changes
+            .resolve(&path, &[1], HunkAction::Revert)
+            .await
+            .expect("revert");
+        assert_eq!(
+            old.replace("line 2\n", "line two\n"),
+            std::fs::read_to_string(&path).expect("read")
+        );
+
+        // Accepting the remaining hunk settles the file.
+        changes
+            .resolve(&path, &[0], HunkAction::Accept)
+            .await
+            .expect("accept");
+        let files = changes.diff().await;
+        assert_eq!(
+            vec![created.clone()],
+            files.iter().map(|f| f.path.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(FileDiffStatus::Added, files[0].status);
+
+        // Reverting every hunk of a created file removes it.
+        changes
+            .resolve(&created, &[0], HunkAction::Revert)
+            .await
+            .expect("revert");
+        assert!(!created.exists());
+        assert!(changes.diff().await.is_empty());
+        assert!(
+            changes
+                .resolve(&created, &[3], HunkAction::Accept)
+                .await
+                .is_err()
+        );
+    }
+}
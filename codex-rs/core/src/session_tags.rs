@@ -0,0 +1,100 @@
+//! Tags attached to a session, such as a ticket id, repository or branch.
+//!
+//! Tags come from the `session_tags` configuration (and the `--tag` CLI flag
+//! that sets it) and from `Op::UpdateSessionTags`. Every change is recorded in
+//! the rollout as `EventMsg::SessionTags` carrying the full set, so the latest
+//! such event describes the session and listed sessions can be filtered by it.
+
+use std::collections::BTreeMap;
+
+use codex_protocol::protocol::RolloutItem;
+
+use crate::protocol::EventMsg;
+
+/// Longest accepted tag key, in bytes.
+const MAX_TAG_KEY_LEN: usize = 64;
+
+/// Whether `key` can be used as a tag key: ASCII letters, digits, `-` and `_`.
+/// Keys end up in `-c session_tags.<key>=...` overrides, so dots and `=` are
+/// not allowed.
+pub fn is_valid_tag_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_TAG_KEY_LEN
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Apply an update to `tags`. Nothing is changed when a key is invalid.
+/// Returns whether the tags changed.
+pub(crate) fn update_tags(
+    tags: &mut BTreeMap<String, String>,
+    set: BTreeMap<String, String>,
+    remove: &[String],
+) -> Result<bool, String> {
+    if let Some(key) = set.keys().find(|key| !is_valid_tag_key(key)) {
+        return Err(format!(
+            "invalid tag key `{key}`: use letters, digits, `-` and `_`"
+        ));
+    }
+    let before = tags.clone();
+    for key in remove {
+        tags.remove(key);
+    }
+    tags.extend(set);
+    Ok(*tags != before)
+}
+
+/// Whether `tags` contains every entry of `required`.
+pub(crate) fn matches_tags(
+    tags: &BTreeMap<String, String>,
+    required: &BTreeMap<String, String>,
+) -> bool {
+    required
+        .iter()
+        .all(|(key, value)| tags.get(key) == Some(value))
+}
+
+/// The tags recorded last in a rollout.
+pub(crate) fn last_tags(items: &[RolloutItem]) -> Option<BTreeMap<String, String>> {
+    items.iter().rev().find_map(|item| match item {
+        RolloutItem::EventMsg(EventMsg::SessionTags(event)) => Some(event.tags.clone()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn tags(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn update_tags_sets_removes_and_rejects_bad_keys() {
+        let mut current = tags(&[("ticket", "ABC-1"), ("branch", "main")]);
+
+        assert_eq!(
+            Ok(true),
+            update_tags(
+                &mut current,
+                tags(&[("ticket", "ABC-2"), ("urgent", "")]),
+                &["branch".to_string()],
+            )
+        );
+        assert_eq!(tags(&[("ticket", "ABC-2"), ("urgent", "")]), current);
+        assert_eq!(Ok(false), update_tags(&mut current, BTreeMap::new(), &[]));
+
+        assert!(update_tags(&mut current, tags(&[("repo.name", "codex")]), &[]).is_err());
+        assert_eq!(tags(&[("ticket", "ABC-2"), ("urgent", "")]), current);
+
+        assert!(matches_tags(&current, &tags(&[("ticket", "ABC-2")])));
+        assert!(!matches_tags(&current, &tags(&[("ticket", "ABC-1")])));
+        assert!(matches_tags(&current, &BTreeMap::new()));
+    }
+}
@@ -0,0 +1,176 @@
+//! Automatic session titles.
+//!
+//! After the first turn of a session, a separate, cheap model call writes a
+//! short title from the user's messages and the final answer. The title is
+//! recorded in the rollout with `EventMsg::SessionTitle` so session pickers can
+//! show it instead of the first message. `Op::SetSessionTitle` replaces it.
+
+use std::sync::Arc;
+
+use codex_protocol::ConversationId;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::config_types::ReasoningSummary as ReasoningSummaryConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::RolloutItem;
+use futures::prelude::*;
+
+use crate::Prompt;
+use crate::client::ModelClient;
+use crate::client_common::ResponseEvent;
+use crate::codex::TurnContext;
+use crate::codex::compact::collect_user_messages;
+use crate::codex::compact::content_items_to_text;
+use crate::codex::get_last_assistant_message_from_turn;
+use crate::error::Result as CodexResult;
+use crate::model_family::find_family_for_model;
+use crate::protocol::EventMsg;
+use crate::truncate::truncate_middle;
+
+const TITLE_INSTRUCTIONS: &str = "You write titles for coding sessions. \
+Reply with a title of at most eight words saying what the user wanted done, \
+such as \"Fix flaky websocket test\". \
+Reply with the title only, without quotes or trailing punctuation.";
+
+/// Longer titles are cut short, in characters.
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Bytes of the conversation shown to the model writing the title.
+const MAX_EXCERPT_BYTES: usize = 4 * 1024;
+
+/// Client for the model that writes titles: `[session_titles] model` if set,
+/// otherwise the turn's model, with low reasoning effort.
+pub(crate) fn title_client(
+    turn_context: &TurnContext,
+    conversation_id: ConversationId,
+) -> ModelClient {
+    let client = &turn_context.client;
+    let mut config = (*client.get_config()).clone();
+    if let Some(model) = config.session_titles.model.clone() {
+        config.model_family =
+            find_family_for_model(&model).unwrap_or_else(|| client.get_model_family());
+        config.model = model;
+    }
+    config.model_reasoning_effort = Some(ReasoningEffortConfig::Low);
+    config.model_reasoning_summary = ReasoningSummaryConfig::None;
+    let otel_event_manager = client
+        .get_otel_event_manager()
+        .with_model(config.model.as_str(), config.model_family.slug.as_str());
+    let config = Arc::new(config);
+    ModelClient::new(
+        config.clone(),
+        client.get_auth_manager(),
+        otel_event_manager,
+        client.get_provider(),
+        config.model_reasoning_effort,
+        config.model_reasoning_summary,
+        conversation_id,
+    )
+}
+
+/// Ask the model for a title for the conversation in `history`. Returns
+/// `None` when there is nothing to title or the reply is unusable.
+pub(crate) async fn generate_title(
+    client: &ModelClient,
+    history: &[ResponseItem],
+) -> CodexResult<Option<String>> {
+    let Some(request) = title_request(history) else {
+        return Ok(None);
+    };
+    let prompt = Prompt {
+        input: vec![request],
+        base_instructions_override: Some(TITLE_INSTRUCTIONS.to_string()),
+        ..Default::default()
+    };
+    let mut stream = client.stream(&prompt).await?;
+    let mut reply = None;
+    while let Some(event) = stream.next().await {
+        match event? {
+            ResponseEvent::OutputItemDone(ResponseItem::Message { role, content, .. })
+                if role == "assistant" =>
+            {
+                reply = content_items_to_text(&content);
+            }
+            ResponseEvent::Completed { .. } => break,
+            _ => {}
+        }
+    }
+    Ok(reply.as_deref().and_then(clean_reply))
+}
+
+/// The message asking for a title, or `None` if the user has not said anything.
+fn title_request(history: &[ResponseItem]) -> Option<ResponseItem> {
+    let user_messages = collect_user_messages(history);
+    if user_messages.is_empty() {
+        return None;
+    }
+    let mut excerpt = format!("User:\n{}", user_messages.join("\n\n"));
+    if let Some(answer) = get_last_assistant_message_from_turn(history) {
+        excerpt.push_str(&format!("\n\nAssistant:\n{answer}"));
+    }
+    let (excerpt, _) = truncate_middle(&excerpt, MAX_EXCERPT_BYTES);
+    Some(ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: format!("Write a title for this session.\n\n{excerpt}"),
+        }],
+    })
+}
+
+/// The title in a model reply, without the quotes and markup models tend to
+/// add.
+fn clean_reply(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.strip_prefix("Title:").unwrap_or(line);
+    let line = line
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | '*' | '#'))
+        .trim_end_matches('.');
+    normalize_title(line)
+}
+
+/// `title` on a single line and at most [`MAX_TITLE_CHARS`] long, or `None`
+/// if it is blank.
+pub(crate) fn normalize_title(title: &str) -> Option<String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return None;
+    }
+    match title.char_indices().nth(MAX_TITLE_CHARS) {
+        Some((end, _)) => Some(format!("{}…", title[..end].trim_end())),
+        None => Some(title),
+    }
+}
+
+/// The most recent title recorded in a rollout.
+pub(crate) fn last_title(items: &[RolloutItem]) -> Option<String> {
+    items.iter().rev().find_map(|item| match item {
+        RolloutItem::EventMsg(EventMsg::SessionTitle(event)) => Some(event.title.clone()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn clean_reply_strips_decoration_and_caps_length() {
+        assert_eq!(
+            Some("Fix flaky websocket test".to_string()),
+            clean_reply("\n  \"Fix flaky websocket test.\"\nBecause the test is flaky.")
+        );
+        assert_eq!(
+            Some("Add retry to uploads".to_string()),
+            clean_reply("Title: **Add retry to uploads**")
+        );
+        assert_eq!(None, clean_reply(" \"\" \n"));
+
+        let long = "word ".repeat(40);
+        assert_eq!(
+            Some(format!("{}…", ["word"; 16].join(" "))),
+            normalize_title(&long)
+        );
+    }
+}
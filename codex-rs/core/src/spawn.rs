@@ -26,6 +26,9 @@ pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 pub enum StdioPolicy {
     RedirectForShellTool,
     Inherit,
+    /// Pipe stdin as well as stdout and stderr, for long-lived processes that
+    /// Codex exchanges messages with.
+    Piped,
 }
 
 /// Spawns the appropriate child process for the ExecParams and SandboxPolicy,
@@ -101,6 +104,11 @@ pub(crate) async fn spawn_child_async(
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit());
         }
+        StdioPolicy::Piped => {
+            cmd.stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+        }
     }
 
     cmd.kill_on_drop(true).spawn()
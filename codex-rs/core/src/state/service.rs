@@ -1,14 +1,42 @@
 use crate::RolloutRecorder;
 use crate::background_process::BackgroundProcessManager;
+use crate::browser::BrowserManager;
+use crate::config_types::BusySessionPolicy;
+use crate::config_types::McpServerConfig;
 use crate::exec_command::ExecSessionManager;
+use crate::file_locks::FileLocks;
 use crate::mcp_connection_manager::McpConnectionManager;
+use crate::mcp_elicitation::McpElicitor;
+use crate::mcp_roots::McpRoots;
+use crate::mcp_sampling::McpSampler;
+use crate::plugins::PluginManager;
+use crate::protocol::ClientCapabilities;
+use crate::python_kernel::PythonKernelManager;
+use crate::replay::ReplayExecBackend;
+use crate::scratch::ScratchDir;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_notification::UserNotifier;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 
 pub(crate) struct SessionServices {
-    pub(crate) mcp_connection_manager: McpConnectionManager,
+    /// Swapped for an empty manager while the session is idle and reconnected
+    /// on the next submission.
+    pub(crate) mcp_connection_manager: RwLock<McpConnectionManager>,
+    /// The MCP servers of the session: the configured ones, updated by
+    /// `Op::ConnectMcpServer` and `Op::DisconnectMcpServer`. Reconnecting
+    /// goes by these.
+    pub(crate) mcp_servers: Mutex<HashMap<String, McpServerConfig>>,
+    /// Answers the sampling requests of the MCP servers when
+    /// `[mcp_sampling]` is enabled.
+    pub(crate) mcp_sampler: Option<Arc<McpSampler>>,
+    /// Roots advertised to the MCP servers.
+    pub(crate) mcp_roots: Arc<McpRoots>,
+    /// Forwards the elicitation requests of the MCP servers to the client.
+    pub(crate) mcp_elicitor: Arc<McpElicitor>,
     pub(crate) session_manager: ExecSessionManager,
     pub(crate) unified_exec_manager: UnifiedExecSessionManager,
     pub(crate) notifier: UserNotifier,
@@ -16,5 +44,23 @@ pub(crate) struct SessionServices {
     pub(crate) codex_linux_sandbox_exe: Option<PathBuf>,
     pub(crate) user_shell: crate::shell::Shell,
     pub(crate) show_raw_agent_reasoning: bool,
+    pub(crate) approval_batching: bool,
+    pub(crate) busy_session_policy: BusySessionPolicy,
+    /// Title the session after its first turn. Only sessions listed by
+    /// session pickers are titled.
+    pub(crate) generate_title: bool,
+    pub(crate) binary_payload_framing: bool,
+    pub(crate) client_capabilities: ClientCapabilities,
     pub(crate) background_process_manager: BackgroundProcessManager,
+    /// Headless browser of the `browser` tool, started on first use.
+    pub(crate) browser_manager: BrowserManager,
+    /// Python process of the `python` tool, started on first use.
+    pub(crate) python_kernel_manager: PythonKernelManager,
+    /// Tools of the WebAssembly plugins loaded when the session started.
+    pub(crate) plugin_manager: PluginManager,
+    pub(crate) file_locks: Arc<FileLocks>,
+    /// Answers tool calls from a recording instead of executing them.
+    pub(crate) replay: Option<ReplayExecBackend>,
+    /// Per-session directory for temporary artifacts; removed on shutdown.
+    pub(crate) scratch_dir: Option<ScratchDir>,
 }
@@ -1,21 +1,83 @@
 //! Session-wide mutable state.
 
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
 
 use codex_protocol::models::ResponseItem;
 
+use crate::budget::BudgetUsage;
+use crate::checkpoint::Checkpoint;
+use crate::codex::TurnContext;
 use crate::conversation_history::ConversationHistory;
+use crate::plan_tool::UpdatePlanArgs;
+use crate::protocol::ContextPin;
+use crate::protocol::InputItem;
+use crate::protocol::QueuedUserInput;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
+use crate::protocol::TurnStats;
+use crate::session_diff::SessionChanges;
+use crate::tool_quota::ToolUsage;
+use crate::undo::MAX_UNDO_TURNS;
+use crate::undo::TurnFileLog;
 
 /// Persistent, session-scoped state previously stored directly on `Session`.
 #[derive(Default)]
 pub(crate) struct SessionState {
     pub(crate) approved_commands: HashSet<Vec<String>>,
+    /// MCP tools the user approved for the session, as (server, tool).
+    pub(crate) approved_mcp_tools: HashSet<(String, String)>,
     pub(crate) history: ConversationHistory,
     pub(crate) token_info: Option<TokenUsageInfo>,
     pub(crate) latest_rate_limits: Option<RateLimitSnapshot>,
+    pub(crate) checkpoints: HashMap<String, Checkpoint>,
+    pub(crate) queued_input: VecDeque<QueuedInput>,
+    /// File changes of the most recent turns that wrote files, oldest first.
+    pub(crate) undo_stack: Vec<TurnFileLog>,
+    /// What the files changed by finished turns contained before the
+    /// session, for `Op::GetSessionDiff` and the `review_changes` tool.
+    pub(crate) session_changes: SessionChanges,
+    /// Accounting of every finished turn, oldest first.
+    pub(crate) turn_stats: Vec<TurnStats>,
+    /// Files and notes shown to the model on every turn.
+    pub(crate) pinned_context: Vec<ContextPin>,
+    /// The plan last recorded with the `update_plan` tool.
+    pub(crate) plan: Option<UpdatePlanArgs>,
+    /// Title shown by session pickers, once generated or set.
+    pub(crate) title: Option<String>,
+    /// Whether a title is being generated.
+    pub(crate) title_pending: bool,
+    /// Tags attached to the session.
+    pub(crate) tags: BTreeMap<String, String>,
+    /// Environment variables set with `Op::UpdateSessionEnv`.
+    pub(crate) env: BTreeMap<String, String>,
+    /// Usage at the point the user last chose to continue past the session
+    /// budget; only usage since then counts against it.
+    pub(crate) budget_baseline: BudgetUsage,
+    /// The most recent regular turn, while it can still be retried.
+    pub(crate) last_turn: Option<LastTurn>,
+    /// Tool calls and output counted against `tool_quotas`.
+    pub(crate) tool_usage: ToolUsage,
+}
+
+/// What is needed to run a turn again with `Op::RetryTurn`.
+pub(crate) struct LastTurn {
+    pub(crate) sub_id: String,
+    pub(crate) items: Vec<InputItem>,
+    /// Length of the history before the turn recorded anything.
+    pub(crate) history_len: usize,
+}
+
+/// User input waiting for the running turn to finish, together with the turn
+/// context that was current when it was submitted.
+pub(crate) struct QueuedInput {
+    pub(crate) sub_id: String,
+    pub(crate) items: Vec<InputItem>,
+    pub(crate) turn_context: Arc<TurnContext>,
 }
 
 impl SessionState {
@@ -42,6 +104,24 @@ impl SessionState {
 
     pub(crate) fn replace_history(&mut self, items: Vec<ResponseItem>) {
         self.history.replace(items);
+        // The last turn's entries can no longer be told apart.
+        self.last_turn = None;
+    }
+
+    /// Remember the input of a turn that is about to be recorded.
+    pub(crate) fn begin_turn(&mut self, sub_id: &str, items: &[InputItem]) {
+        self.last_turn = Some(LastTurn {
+            sub_id: sub_id.to_string(),
+            items: items.to_vec(),
+            history_len: self.history.len(),
+        });
+    }
+
+    /// Discard the history entries of the last turn and return it.
+    pub(crate) fn rewind_last_turn(&mut self) -> Option<LastTurn> {
+        let last_turn = self.last_turn.take()?;
+        self.history.truncate(last_turn.history_len);
+        Some(last_turn)
     }
 
     // Approved command helpers
@@ -53,6 +133,59 @@ impl SessionState {
         &self.approved_commands
     }
 
+    // Checkpoint helpers
+    pub(crate) fn insert_checkpoint(&mut self, name: String, checkpoint: Checkpoint) {
+        self.checkpoints.insert(name, checkpoint);
+    }
+
+    pub(crate) fn checkpoint(&self, name: &str) -> Option<Checkpoint> {
+        self.checkpoints.get(name).cloned()
+    }
+
+    // Undo helpers
+    pub(crate) fn push_turn_file_log(&mut self, log: TurnFileLog) {
+        self.session_changes.record(
+            log.originals()
+                .map(|(path, original)| (path.clone(), original.clone())),
+        );
+        self.undo_stack.push(log);
+        if self.undo_stack.len() > MAX_UNDO_TURNS {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub(crate) fn pop_turn_file_log(&mut self) -> Option<TurnFileLog> {
+        self.undo_stack.pop()
+    }
+
+    /// Pop the file changes of turn `turn_id` if they are the most recent.
+    pub(crate) fn pop_turn_file_log_of(&mut self, turn_id: &str) -> Option<TurnFileLog> {
+        if self.undo_stack.last()?.turn_id == turn_id {
+            self.undo_stack.pop()
+        } else {
+            None
+        }
+    }
+
+    // Queued input helpers
+    pub(crate) fn queued_input_summary(&self) -> Vec<QueuedUserInput> {
+        self.queued_input
+            .iter()
+            .map(|queued| QueuedUserInput {
+                id: queued.sub_id.clone(),
+                text: queued
+                    .items
+                    .iter()
+                    .filter_map(|item| match item {
+                        InputItem::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            })
+            .collect()
+    }
+
     // Token/rate limit helpers
     pub(crate) fn update_token_info_from_usage(
         &mut self,
@@ -9,6 +9,7 @@ use tokio::task::AbortHandle;
 use codex_protocol::models::ResponseInputItem;
 use tokio::sync::oneshot;
 
+use crate::protocol::BudgetDecision;
 use crate::protocol::ReviewDecision;
 use crate::tasks::SessionTask;
 
@@ -56,11 +57,21 @@ impl ActiveTurn {
     }
 }
 
+/// An outstanding `ApprovalBatchRequest` that still needs a decision for
+/// at least one of its items.
+struct PendingApprovalBatch {
+    call_ids: Vec<String>,
+    tx: oneshot::Sender<()>,
+}
+
 /// Mutable state for a single turn.
 #[derive(Default)]
 pub(crate) struct TurnState {
     pending_approvals: HashMap<String, oneshot::Sender<ReviewDecision>>,
+    pending_approval_batches: HashMap<String, PendingApprovalBatch>,
+    batch_decisions: HashMap<String, ReviewDecision>,
     pending_input: Vec<ResponseInputItem>,
+    pending_budget_decisions: HashMap<String, oneshot::Sender<BudgetDecision>>,
 }
 
 impl TurnState {
@@ -79,8 +90,96 @@ impl TurnState {
         self.pending_approvals.remove(key)
     }
 
+    pub(crate) fn insert_pending_approval_batch(
+        &mut self,
+        key: String,
+        call_ids: Vec<String>,
+        tx: oneshot::Sender<()>,
+    ) {
+        self.pending_approval_batches
+            .insert(key, PendingApprovalBatch { call_ids, tx });
+    }
+
+    /// Record the decision for a single batch item. Returns `false` when no
+    /// pending batch contains `call_id`.
+    pub(crate) fn record_batch_item_decision(
+        &mut self,
+        call_id: &str,
+        decision: ReviewDecision,
+    ) -> bool {
+        let Some(key) = self
+            .pending_approval_batches
+            .iter()
+            .find(|(_, batch)| batch.call_ids.iter().any(|id| id == call_id))
+            .map(|(key, _)| key.clone())
+        else {
+            return false;
+        };
+        self.batch_decisions.insert(call_id.to_string(), decision);
+
+        let complete = self.pending_approval_batches[&key]
+            .call_ids
+            .iter()
+            .all(|id| self.batch_decisions.contains_key(id));
+        if complete && let Some(batch) = self.pending_approval_batches.remove(&key) {
+            batch.tx.send(()).ok();
+        }
+        true
+    }
+
+    /// Resolve every item of the batch registered under `key`. Items missing
+    /// from `decisions` keep any decision recorded individually, then fall
+    /// back to `default_decision`, then to `ReviewDecision::default()`.
+    pub(crate) fn resolve_approval_batch(
+        &mut self,
+        key: &str,
+        mut decisions: HashMap<String, ReviewDecision>,
+        default_decision: Option<ReviewDecision>,
+    ) -> bool {
+        let Some(batch) = self.pending_approval_batches.remove(key) else {
+            return false;
+        };
+        for call_id in batch.call_ids {
+            match decisions.remove(&call_id) {
+                Some(decision) => {
+                    self.batch_decisions.insert(call_id, decision);
+                }
+                None => {
+                    self.batch_decisions
+                        .entry(call_id)
+                        .or_insert_with(|| default_decision.unwrap_or_default());
+                }
+            }
+        }
+        batch.tx.send(()).ok();
+        true
+    }
+
+    /// Take the decision made for `call_id` as part of a resolved batch.
+    pub(crate) fn take_batch_decision(&mut self, call_id: &str) -> Option<ReviewDecision> {
+        self.batch_decisions.remove(call_id)
+    }
+
+    pub(crate) fn insert_pending_budget_decision(
+        &mut self,
+        key: String,
+        tx: oneshot::Sender<BudgetDecision>,
+    ) {
+        self.pending_budget_decisions.insert(key, tx);
+    }
+
+    pub(crate) fn remove_pending_budget_decision(
+        &mut self,
+        key: &str,
+    ) -> Option<oneshot::Sender<BudgetDecision>> {
+        self.pending_budget_decisions.remove(key)
+    }
+
     pub(crate) fn clear_pending(&mut self) {
         self.pending_approvals.clear();
+        self.pending_budget_decisions.clear();
+        self.pending_approval_batches.clear();
+        self.batch_decisions.clear();
         self.pending_input.clear();
     }
 
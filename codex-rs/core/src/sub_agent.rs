@@ -0,0 +1,337 @@
+//! Delegation of self-contained tasks to sub-agents.
+//!
+//! A sub-agent is a separate Codex session with its own context, a restricted
+//! tool set and a turn/token budget. It runs the delegated task to completion
+//! without user interaction and its final message is handed back to the
+//! parent as the output of the `delegate_task` tool call. Sub-agent sessions
+//! are recorded like any other session, so their full transcript can be
+//! inspected afterwards.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_protocol::ConversationId;
+use serde::Deserialize;
+
+use crate::AuthManager;
+use crate::codex::Codex;
+use crate::codex::CodexSpawnOk;
+use crate::config::Config;
+use crate::error::CodexErr;
+use crate::error::Result as CodexResult;
+use crate::file_locks::FileLocks;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_family_for_model;
+use crate::openai_model_info::get_model_info;
+use crate::protocol::AskForApproval;
+use crate::protocol::EventMsg;
+use crate::protocol::InitialHistory;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::ReviewDecision;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SessionSource;
+use crate::protocol::SubAgentOutcome;
+
+pub(crate) const DELEGATE_TASK_TOOL_NAME: &str = "delegate_task";
+
+const SUB_AGENT_PREAMBLE: &str = "You are a sub-agent carrying out a task delegated by another agent. \
+Work autonomously: nobody will answer questions or approve commands. \
+When you are done, reply with a concise summary of what you found or changed; \
+that reply is all the delegating agent will see.";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DelegateTaskArgs {
+    pub(crate) task: String,
+    #[serde(default)]
+    pub(crate) allow_writes: bool,
+    #[serde(default)]
+    pub(crate) max_turns: Option<u32>,
+}
+
+/// Limits on how much work a sub-agent may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Budget {
+    pub(crate) max_turns: u32,
+    pub(crate) max_tokens: Option<u64>,
+}
+
+impl Budget {
+    /// The configured budget, optionally tightened (never loosened) by the
+    /// model's request.
+    pub(crate) fn new(config: &Config, requested_turns: Option<u32>) -> Self {
+        let max_turns = config.sub_agents.max_turns;
+        Self {
+            max_turns: requested_turns.map_or(max_turns, |turns| turns.clamp(1, max_turns)),
+            max_tokens: config.sub_agents.max_tokens,
+        }
+    }
+
+    fn is_exhausted(&self, turns: u32, tokens: u64) -> bool {
+        turns >= self.max_turns || self.max_tokens.is_some_and(|max| tokens >= max)
+    }
+}
+
+/// Derive the configuration of a sub-agent from its parent's.
+pub(crate) fn sub_agent_config(
+    parent: &Config,
+    cwd: PathBuf,
+    parent_sandbox: &SandboxPolicy,
+    allow_writes: bool,
+) -> Config {
+    let mut config = parent.clone();
+    config.cwd = cwd;
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = if allow_writes {
+        parent_sandbox.clone()
+    } else {
+        SandboxPolicy::new_read_only_policy()
+    };
+    // Sub-agents cannot delegate further and keep to the core tool set.
+    config.sub_agents.enabled = false;
+    config.include_plan_tool = false;
+    config.tools_web_search_request = false;
+    config.notify = None;
+    if let Some(model) = parent.sub_agents.model.clone() {
+        config.model_family =
+            find_family_for_model(&model).unwrap_or_else(|| derive_default_model_family(&model));
+        if let Some(info) = get_model_info(&config.model_family) {
+            config.model_context_window = Some(info.context_window);
+            config.model_max_output_tokens = Some(info.max_output_tokens);
+            config.model_auto_compact_token_limit = info.auto_compact_token_limit;
+        }
+        config.model = model;
+    }
+    config
+}
+
+/// How a sub-agent run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SubAgentReport {
+    pub(crate) outcome: SubAgentOutcome,
+    pub(crate) message: Option<String>,
+}
+
+impl SubAgentReport {
+    /// Text returned to the parent model as the tool output.
+    pub(crate) fn to_tool_output(&self, rollout_path: &std::path::Path) -> String {
+        let message = self.message.as_deref().unwrap_or("(no final message)");
+        let transcript = rollout_path.display();
+        match self.outcome {
+            SubAgentOutcome::Completed => {
+                format!("{message}\n\n[sub-agent transcript: {transcript}]")
+            }
+            SubAgentOutcome::BudgetExhausted => format!(
+                "Sub-agent stopped after exhausting its budget. Last message:\n{message}\n\n[sub-agent transcript: {transcript}]"
+            ),
+            SubAgentOutcome::Failed => {
+                format!("Sub-agent failed: {message}\n\n[sub-agent transcript: {transcript}]")
+            }
+        }
+    }
+}
+
+pub(crate) struct SubAgent {
+    codex: Codex,
+    file_locks: Arc<FileLocks>,
+    pub(crate) conversation_id: ConversationId,
+    pub(crate) rollout_path: PathBuf,
+}
+
+impl SubAgent {
+    /// Spawn a sub-agent of session `parent`, sharing its file locks.
+    pub(crate) async fn spawn(
+        config: Config,
+        auth_manager: Arc<AuthManager>,
+        file_locks: Arc<FileLocks>,
+        parent: ConversationId,
+    ) -> CodexResult<Self> {
+        let CodexSpawnOk {
+            codex,
+            conversation_id,
+        } = Codex::spawn(
+            config,
+            auth_manager,
+            InitialHistory::New,
+            SessionSource::SubAgent,
+            file_locks.clone(),
+        )
+        .await?;
+        file_locks.register_child(conversation_id, parent);
+        let event = codex.next_event().await?;
+        let EventMsg::SessionConfigured(session_configured) = event.msg else {
+            file_locks.forget(conversation_id);
+            return Err(CodexErr::SessionConfiguredNotFirstEvent);
+        };
+        Ok(Self {
+            codex,
+            file_locks,
+            conversation_id,
+            rollout_path: session_configured.rollout_path,
+        })
+    }
+
+    /// Run `task` until the sub-agent finishes or its budget runs out, then
+    /// shut the session down so its transcript is flushed.
+    pub(crate) async fn run(self, task: String, budget: Budget) -> SubAgentReport {
+        let report = self.drive(task, budget).await;
+        if self.codex.submit(Op::Shutdown).await.is_ok() {
+            while let Ok(event) = self.codex.next_event().await {
+                if matches!(event.msg, EventMsg::ShutdownComplete) {
+                    break;
+                }
+            }
+        }
+        self.file_locks.forget(self.conversation_id);
+        report
+    }
+
+    async fn drive(&self, task: String, budget: Budget) -> SubAgentReport {
+        let failed = |message: String| SubAgentReport {
+            outcome: SubAgentOutcome::Failed,
+            message: Some(message),
+        };
+        let input = Op::UserInput {
+            items: vec![InputItem::Text {
+                text: format!("{SUB_AGENT_PREAMBLE}\n\nTask: {task}"),
+            }],
+        };
+        if let Err(e) = self.codex.submit(input).await {
+            return failed(e.to_string());
+        }
+
+        let mut turns = 0;
+        let mut exhausted = false;
+        let mut error = None;
+        let mut last_message = None;
+        loop {
+            let event = match self.codex.next_event().await {
+                Ok(event) => event,
+                Err(e) => return failed(e.to_string()),
+            };
+            match event.msg {
+                EventMsg::TokenCount(ev) => {
+                    let Some(info) = ev.info else { continue };
+                    turns += 1;
+                    if !exhausted && budget.is_exhausted(turns, info.total_token_usage.total_tokens)
+                    {
+                        exhausted = true;
+                        if let Err(e) = self.codex.submit(Op::Interrupt).await {
+                            return failed(e.to_string());
+                        }
+                    }
+                }
+                EventMsg::AgentMessage(ev) => last_message = Some(ev.message),
+                EventMsg::Error(ev) => error = Some(ev.message),
+                // Approvals are disabled for sub-agents; refuse anything that
+                // still asks rather than stalling.
+                EventMsg::ExecApprovalRequest(_) => {
+                    let _ = self
+                        .codex
+                        .submit(Op::ExecApproval {
+                            id: event.id,
+                            decision: ReviewDecision::Denied,
+                        })
+                        .await;
+                }
+                EventMsg::ApplyPatchApprovalRequest(_) => {
+                    let _ = self
+                        .codex
+                        .submit(Op::PatchApproval {
+                            id: event.id,
+                            decision: ReviewDecision::Denied,
+                        })
+                        .await;
+                }
+                // The session budget counts as the sub-agent's budget too.
+                EventMsg::BudgetExceeded(_) => {
+                    exhausted = true;
+                    if let Err(e) = self.codex.submit(Op::Interrupt).await {
+                        return failed(e.to_string());
+                    }
+                }
+                EventMsg::TaskComplete(ev) => {
+                    let message = ev.last_agent_message.or(last_message);
+                    return match error {
+                        Some(error) if message.is_none() => failed(error),
+                        _ => SubAgentReport {
+                            outcome: SubAgentOutcome::Completed,
+                            message,
+                        },
+                    };
+                }
+                EventMsg::TurnAborted(_) => {
+                    return SubAgentReport {
+                        outcome: if exhausted {
+                            SubAgentOutcome::BudgetExhausted
+                        } else {
+                            SubAgentOutcome::Failed
+                        },
+                        message: last_message.or(error),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn parent_config(codex_home: &TempDir) -> Config {
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load default config");
+        config.sub_agents.enabled = true;
+        config.sub_agents.max_turns = 10;
+        config.include_plan_tool = true;
+        config
+    }
+
+    #[test]
+    fn sub_agent_config_is_restricted() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let parent = parent_config(&codex_home);
+        let workspace_write = SandboxPolicy::new_workspace_write_policy();
+
+        let config = sub_agent_config(&parent, PathBuf::from("/work"), &workspace_write, false);
+        assert_eq!(PathBuf::from("/work"), config.cwd);
+        assert_eq!(AskForApproval::Never, config.approval_policy);
+        assert_eq!(SandboxPolicy::new_read_only_policy(), config.sandbox_policy);
+        assert!(!config.sub_agents.enabled);
+        assert!(!config.include_plan_tool);
+
+        let config = sub_agent_config(&parent, PathBuf::from("/work"), &workspace_write, true);
+        assert_eq!(workspace_write, config.sandbox_policy);
+    }
+
+    #[test]
+    fn requested_turns_cannot_exceed_configured_budget() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let config = parent_config(&codex_home);
+
+        assert_eq!(10, Budget::new(&config, None).max_turns);
+        assert_eq!(3, Budget::new(&config, Some(3)).max_turns);
+        assert_eq!(10, Budget::new(&config, Some(50)).max_turns);
+    }
+
+    #[test]
+    fn budget_is_exhausted_by_turns_or_tokens() {
+        let budget = Budget {
+            max_turns: 3,
+            max_tokens: Some(1_000),
+        };
+        assert!(!budget.is_exhausted(2, 999));
+        assert!(budget.is_exhausted(3, 0));
+        assert!(budget.is_exhausted(1, 1_000));
+    }
+}
@@ -0,0 +1,235 @@
+//! The `system_info` tool, which describes the host in one call: the OS and
+//! architecture, the versions of common toolchains and the free disk space
+//! and memory, so the model does not probe them one `--version` at a time.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde::Serialize;
+use tokio::process::Command;
+
+use crate::function_tool::FunctionCallError;
+
+pub(crate) const SYSTEM_INFO_TOOL_NAME: &str = "system_info";
+
+/// How long a single version probe may take before it is reported as
+/// unavailable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Toolchains probed by the tool and the arguments that print their version.
+const TOOLCHAINS: &[(&str, &[&str])] = &[
+    ("node", &["--version"]),
+    ("npm", &["--version"]),
+    ("python3", &["--version"]),
+    ("python", &["--version"]),
+    ("rustc", &["--version"]),
+    ("cargo", &["--version"]),
+    ("go", &["version"]),
+    ("java", &["-version"]),
+    ("docker", &["--version"]),
+    ("git", &["--version"]),
+];
+
+#[derive(Debug, Serialize)]
+struct SystemInfo {
+    os: String,
+    os_version: String,
+    arch: String,
+    cpus: usize,
+    /// Version of each probed toolchain, `null` when it is not installed.
+    toolchains: BTreeMap<&'static str, Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<MemoryInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disk: Option<DiskInfo>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct MemoryInfo {
+    total_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    available_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct DiskInfo {
+    /// The directory the figures are for: the session's working directory.
+    path: String,
+    total_bytes: u64,
+    free_bytes: u64,
+}
+
+pub(crate) async fn handle_system_info_tool(cwd: &Path) -> Result<String, FunctionCallError> {
+    let versions = join_all(
+        TOOLCHAINS
+            .iter()
+            .map(|(program, args)| probe_version(program, args)),
+    )
+    .await;
+    let os = os_info::get();
+    let info = SystemInfo {
+        os: os.os_type().to_string(),
+        os_version: os.version().to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        cpus: std::thread::available_parallelism().map_or(1, std::num::NonZero::get),
+        toolchains: TOOLCHAINS
+            .iter()
+            .map(|(program, _)| *program)
+            .zip(versions)
+            .collect(),
+        memory: memory_info(),
+        disk: disk_info(cwd),
+    };
+    serde_json::to_string_pretty(&info).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to serialize system info: {e}"))
+    })
+}
+
+/// The version line `program` prints, or `None` when it is not on `PATH`,
+/// fails or does not answer within [`PROBE_TIMEOUT`].
+async fn probe_version(program: &str, args: &[&str]) -> Option<String> {
+    let path = which::which(program).ok()?;
+    let output = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(PROBE_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Some tools, such as `java` and old Pythons, print their version on
+    // stderr.
+    first_line(&String::from_utf8_lossy(&output.stdout))
+        .or_else(|| first_line(&String::from_utf8_lossy(&output.stderr)))
+}
+
+fn first_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(target_os = "linux")]
+fn memory_info() -> Option<MemoryInfo> {
+    parse_meminfo(&std::fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn memory_info() -> Option<MemoryInfo> {
+    // SAFETY: sysconf only reads system configuration values.
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    if pages <= 0 || page_size <= 0 {
+        return None;
+    }
+    Some(MemoryInfo {
+        total_bytes: pages as u64 * page_size as u64,
+        available_bytes: None,
+    })
+}
+
+#[cfg(not(unix))]
+fn memory_info() -> Option<MemoryInfo> {
+    None
+}
+
+/// Total and available memory from the contents of `/proc/meminfo`, whose
+/// figures are in kB.
+#[cfg(any(target_os = "linux", test))]
+fn parse_meminfo(meminfo: &str) -> Option<MemoryInfo> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            let kb: u64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kb * 1024)
+        })
+    };
+    Some(MemoryInfo {
+        total_bytes: field("MemTotal")?,
+        available_bytes: field("MemAvailable"),
+    })
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+fn disk_info(cwd: &Path) -> Option<DiskInfo> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(cwd.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is only
+    // read after statvfs reports success.
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+    // The statvfs field types differ between platforms.
+    let fragment_size = stat.f_frsize as u64;
+    Some(DiskInfo {
+        path: cwd.display().to_string(),
+        total_bytes: stat.f_blocks as u64 * fragment_size,
+        free_bytes: stat.f_bavail as u64 * fragment_size,
+    })
+}
+
+#[cfg(not(unix))]
+fn disk_info(_cwd: &Path) -> Option<DiskInfo> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_meminfo() {
+        let meminfo = "MemTotal:       16303428 kB\nMemFree:         1234567 kB\nMemAvailable:    8151714 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some(MemoryInfo {
+                total_bytes: 16_303_428 * 1024,
+                available_bytes: Some(8_151_714 * 1024),
+            })
+        );
+        assert_eq!(parse_meminfo("MemFree: 12 kB\n"), None);
+    }
+
+    #[test]
+    fn first_line_skips_blank_lines() {
+        assert_eq!(
+            first_line("\n  openjdk version \"21\"\nmore\n"),
+            Some("openjdk version \"21\"".to_string())
+        );
+        assert_eq!(first_line(" \n"), None);
+    }
+
+    #[tokio::test]
+    async fn reports_host_and_toolchains() {
+        let cwd = tempfile::tempdir().expect("tempdir");
+        let output = handle_system_info_tool(cwd.path())
+            .await
+            .expect("system info");
+        let info: serde_json::Value = serde_json::from_str(&output).expect("json");
+
+        assert_eq!(info["arch"], std::env::consts::ARCH);
+        let toolchains = info["toolchains"].as_object().expect("toolchains");
+        assert_eq!(toolchains.len(), TOOLCHAINS.len());
+        assert!(toolchains.contains_key("rustc"));
+    }
+}
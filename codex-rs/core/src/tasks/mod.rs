@@ -65,6 +65,7 @@ impl Session {
         task: T,
     ) {
         self.abort_all_tasks(TurnAbortReason::Replaced).await;
+        self.set_mcp_turn(&sub_id, &turn_context);
 
         let task: Arc<dyn SessionTask> = Arc::new(task);
         let task_kind = task.kind();
@@ -94,6 +95,7 @@ impl Session {
     }
 
     pub async fn abort_all_tasks(self: &Arc<Self>, reason: TurnAbortReason) {
+        self.cancel_mcp_elicitations();
         for (sub_id, task) in self.take_all_running_tasks().await {
             self.handle_task_abort(sub_id, task, reason.clone()).await;
         }
@@ -111,11 +113,13 @@ impl Session {
             *active = None;
         }
         drop(active);
+        self.release_file_locks();
         let event = Event {
             id: sub_id,
             msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
         };
         self.send_event(event).await;
+        self.start_next_queued_input().await;
     }
 
     async fn register_new_active_task(&self, sub_id: String, task: RunningTask) {
@@ -153,6 +157,7 @@ impl Session {
         handle.abort();
         let session_ctx = Arc::new(SessionTaskContext::new(Arc::clone(self)));
         session_task.abort(session_ctx, &sub_id).await;
+        self.release_file_locks();
 
         let event = Event {
             id: sub_id.clone(),
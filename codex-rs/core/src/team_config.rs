@@ -0,0 +1,465 @@
+//! Team config: organization defaults, such as approved tools, sandbox
+//! settings and provider endpoints, loaded from a URL or a file tracked by
+//! git and placed beneath the user's `config.toml`, which overrides them.
+//!
+//! `[team_config]` in `config.toml` says where the team config is and how it
+//! is checked before it is applied: a file must be tracked by git and have no
+//! uncommitted changes, a URL must use HTTPS, and either can be pinned to a
+//! SHA-256 or required to carry an Ed25519 signature. A team config that
+//! fails a check stops Codex with an error; one that cannot be fetched is
+//! replaced by the last copy fetched, or skipped with a warning.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use sha2::Digest;
+use sha2::Sha256;
+use toml::Value as TomlValue;
+
+use crate::config_conditions::Host;
+use crate::config_conditions::apply_conditional_sections;
+use crate::config_interpolation::reject_commands;
+use crate::config_types::TeamConfigToml;
+use crate::project_config::merge_toml;
+use crate::util::hex;
+use crate::util::invalid_data;
+
+/// Directory of `$CODEX_HOME` caching team configs fetched from URLs.
+const TEAM_CONFIG_DIR: &str = "team_config";
+
+/// Suffix of the signature next to a signed team config.
+const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// How long a fetched team config is used before it is fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings a team config cannot set: where the team config comes from, and
+/// which workspaces the user trusts.
+const IGNORED_KEYS: &[&str] = &["team_config", "projects"];
+
+/// Tables whose entries a user's entry of the same name replaces instead of
+/// being merged into.
+const REPLACED_TABLES: &[&str] = &["mcp_servers", "model_providers"];
+
+/// Load the team config `[team_config]` of the user config `root` points at,
+/// and place it beneath `root`.
+pub(crate) fn apply_team_config(root: &mut TomlValue, codex_home: &Path) -> std::io::Result<()> {
+    let Some(settings) = root.get("team_config").cloned() else {
+        return Ok(());
+    };
+    let settings: TeamConfigToml = settings
+        .try_into()
+        .map_err(|e| invalid_data(format!("team_config: {e}")))?;
+    let Some(team) = load_team_config(&settings, codex_home)? else {
+        return Ok(());
+    };
+    let user = std::mem::replace(root, team);
+    let ignored = overlay_user_config(root, user);
+    if !ignored.is_empty() {
+        tracing::warn!(
+            "ignoring settings a team config cannot set: {}",
+            ignored.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn load_team_config(
+    settings: &TeamConfigToml,
+    codex_home: &Path,
+) -> std::io::Result<Option<TomlValue>> {
+    let signed = settings.public_key.is_some();
+    let source = match (&settings.url, &settings.path) {
+        (Some(url), None) => match read_url(url, signed, codex_home)? {
+            Some(source) => source,
+            None => return Ok(None),
+        },
+        (None, Some(path)) => read_git_file(&codex_home.join(path), signed)?,
+        _ => return Err(invalid_data("team_config: set either `url` or `path`")),
+    };
+    verify(settings, &source)?;
+    let mut team = toml::from_str(&source.contents)
+        .map_err(|e| invalid_data(format!("team config {}: {e}", source.origin)))?;
+    reject_commands(&team, &format!("team config {}", source.origin))?;
+    apply_conditional_sections(&mut team, &Host::current());
+    Ok(Some(team))
+}
+
+/// A team config as read, before it is checked.
+#[derive(Debug, Clone, PartialEq)]
+struct TeamConfigSource {
+    /// URL or path, for messages.
+    origin: String,
+    contents: String,
+    signature: Option<String>,
+}
+
+/// Read the team config at `url`, from the cache while it is fresh.
+fn read_url(
+    url: &str,
+    signed: bool,
+    codex_home: &Path,
+) -> std::io::Result<Option<TeamConfigSource>> {
+    if !url.starts_with("https://") {
+        return Err(invalid_data(format!(
+            "team_config: `{url}` is not an HTTPS URL"
+        )));
+    }
+    let cache = cache_path(codex_home, url);
+    let cache_signature = with_suffix(&cache, SIGNATURE_SUFFIX);
+    let read_cache = || -> std::io::Result<TeamConfigSource> {
+        Ok(TeamConfigSource {
+            origin: url.to_string(),
+            contents: std::fs::read_to_string(&cache)?,
+            signature: signed
+                .then(|| std::fs::read_to_string(&cache_signature))
+                .transpose()?,
+        })
+    };
+
+    let age = std::fs::metadata(&cache)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    if age.is_some_and(|age| age < CACHE_TTL) {
+        return read_cache().map(Some);
+    }
+
+    match fetch(url, signed) {
+        Ok(source) => {
+            if let Some(dir) = cache.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&cache, &source.contents)?;
+            if let Some(signature) = &source.signature {
+                std::fs::write(&cache_signature, signature)?;
+            }
+            Ok(Some(source))
+        }
+        Err(e) if age.is_some() => {
+            tracing::warn!("failed to fetch the team config {url}, using the cached copy: {e}");
+            read_cache().map(Some)
+        }
+        Err(e) => {
+            tracing::warn!("failed to fetch the team config {url}, continuing without it: {e}");
+            Ok(None)
+        }
+    }
+}
+
+fn cache_path(codex_home: &Path, url: &str) -> PathBuf {
+    let digest = hex(&Sha256::digest(url.as_bytes()));
+    codex_home
+        .join(TEAM_CONFIG_DIR)
+        .join(format!("{}.toml", &digest[..16]))
+}
+
+/// Fetch the team config at `url`, and its signature when `signed`. Config
+/// loading is synchronous and may run inside a Tokio runtime, so the
+/// requests run on a thread of their own.
+fn fetch(url: &str, signed: bool) -> Result<TeamConfigSource, String> {
+    let url = url.to_string();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        runtime.block_on(async move {
+            let client = crate::default_client::create_client();
+            let contents = fetch_text(&client, &url).await?;
+            let signature = if signed {
+                Some(fetch_text(&client, &format!("{url}{SIGNATURE_SUFFIX}")).await?)
+            } else {
+                None
+            };
+            Ok::<_, String>(TeamConfigSource {
+                origin: url,
+                contents,
+                signature,
+            })
+        })
+    })
+    .join()
+    .map_err(|_| "the team config fetch panicked".to_string())?
+}
+
+async fn fetch_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let response = client
+        .get(url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?;
+    response.text().await.map_err(|e| e.to_string())
+}
+
+/// Read the team config at `path`, which must be tracked by git and have
+/// no uncommitted changes, so it is what was committed.
+fn read_git_file(path: &Path, signed: bool) -> std::io::Result<TeamConfigSource> {
+    let origin = path.display().to_string();
+    let (Some(dir), Some(file)) = (path.parent(), path.file_name()) else {
+        return Err(invalid_data(format!("team config {origin}: not a file")));
+    };
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .arg("--")
+            .arg(file)
+            .current_dir(dir)
+            .output()
+            .map(|output| output.status.success())
+    };
+    if !git(&["ls-files", "--error-unmatch"])? {
+        return Err(invalid_data(format!(
+            "team config {origin} is not tracked by git"
+        )));
+    }
+    if !git(&["diff", "--quiet", "HEAD"])? {
+        return Err(invalid_data(format!(
+            "team config {origin} has uncommitted changes"
+        )));
+    }
+    Ok(TeamConfigSource {
+        contents: std::fs::read_to_string(path)?,
+        signature: signed
+            .then(|| std::fs::read_to_string(with_suffix(path, SIGNATURE_SUFFIX)))
+            .transpose()?,
+        origin,
+    })
+}
+
+/// Check `source` against the SHA-256 and public key of `settings`.
+fn verify(settings: &TeamConfigToml, source: &TeamConfigSource) -> std::io::Result<()> {
+    let origin = &source.origin;
+    if let Some(expected) = &settings.sha256 {
+        let actual = hex(&Sha256::digest(source.contents.as_bytes()));
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(invalid_data(format!(
+                "team config {origin} has SHA-256 {actual}, expected {expected}"
+            )));
+        }
+    }
+    if let Some(public_key) = &settings.public_key {
+        let public_key = BASE64
+            .decode(public_key.trim())
+            .map_err(|e| invalid_data(format!("team_config.public_key: {e}")))?;
+        let signature = source
+            .signature
+            .as_deref()
+            .map(|signature| BASE64.decode(signature.trim()))
+            .transpose()
+            .map_err(|e| invalid_data(format!("signature of team config {origin}: {e}")))?
+            .unwrap_or_default();
+        ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+            .verify(source.contents.as_bytes(), &signature)
+            .map_err(|_| {
+                invalid_data(format!(
+                    "signature of team config {origin} does not match team_config.public_key"
+                ))
+            })?;
+    }
+    Ok(())
+}
+
+/// Overlay the user config `user` onto the team config `team`. Tables are
+/// merged key by key, except that a user's MCP server or model provider
+/// replaces the team's one of the same name. Returns the keys of the team
+/// config that were ignored.
+fn overlay_user_config(team: &mut TomlValue, user: TomlValue) -> Vec<String> {
+    if !team.is_table() {
+        *team = TomlValue::Table(Default::default());
+    }
+    let Some(table) = team.as_table_mut() else {
+        return Vec::new();
+    };
+    let ignored = IGNORED_KEYS
+        .iter()
+        .filter(|key| table.remove(**key).is_some())
+        .map(ToString::to_string)
+        .collect();
+
+    let TomlValue::Table(user) = user else {
+        return ignored;
+    };
+    for (key, value) in user {
+        let Some(existing) = table.get_mut(&key) else {
+            table.insert(key, value);
+            continue;
+        };
+        match (existing, value) {
+            (TomlValue::Table(entries), TomlValue::Table(user_entries))
+                if REPLACED_TABLES.contains(&key.as_str()) =>
+            {
+                entries.extend(user_entries);
+            }
+            (existing, value) => merge_toml(existing, value),
+        }
+    }
+    ignored
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ring::signature::Ed25519KeyPair;
+    use ring::signature::KeyPair;
+    use tempfile::TempDir;
+
+    fn toml(s: &str) -> TomlValue {
+        toml::from_str(s).expect("valid toml")
+    }
+
+    fn source(contents: &str, signature: Option<String>) -> TeamConfigSource {
+        TeamConfigSource {
+            origin: "team.toml".to_string(),
+            contents: contents.to_string(),
+            signature,
+        }
+    }
+
+    #[test]
+    fn user_config_overrides_team_defaults() {
+        let mut team = toml(
+            r#"
+approval_policy = "on-request"
+sandbox_mode = "workspace-write"
+disabled_tools = ["browser"]
+
+[projects."/tmp/trusted"]
+trust_level = "trusted"
+
+[sandbox_workspace_write]
+network_access = false
+writable_roots = ["/opt/cache"]
+
+[mcp_servers.docs]
+url = "https://docs.example.com/mcp"
+
+[mcp_servers.tickets]
+command = "tickets-server"
+
+[model_providers.proxy]
+name = "Proxy"
+base_url = "https://proxy.example.com/v1"
+"#,
+        );
+        let user = toml(
+            r#"
+approval_policy = "never"
+
+[sandbox_workspace_write]
+network_access = true
+
+[mcp_servers.docs]
+command = "local-docs"
+"#,
+        );
+
+        let ignored = overlay_user_config(&mut team, user);
+
+        assert_eq!(ignored, vec!["projects".to_string()]);
+        assert_eq!(
+            team,
+            toml(
+                r#"
+approval_policy = "never"
+sandbox_mode = "workspace-write"
+disabled_tools = ["browser"]
+
+[sandbox_workspace_write]
+network_access = true
+writable_roots = ["/opt/cache"]
+
+[mcp_servers.docs]
+command = "local-docs"
+
+[mcp_servers.tickets]
+command = "tickets-server"
+
+[model_providers.proxy]
+name = "Proxy"
+base_url = "https://proxy.example.com/v1"
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn team_config_must_match_its_pin_and_signature() {
+        let contents = "approval_policy = \"on-request\"\n";
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).expect("generate key");
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).expect("parse key");
+        let settings = TeamConfigToml {
+            sha256: Some(hex(&Sha256::digest(contents.as_bytes()))),
+            public_key: Some(BASE64.encode(key.public_key().as_ref())),
+            ..Default::default()
+        };
+        let signature = BASE64.encode(key.sign(contents.as_bytes()).as_ref());
+
+        assert!(verify(&settings, &source(contents, Some(signature.clone()))).is_ok());
+        let tampered = "approval_policy = \"never\"\n";
+        let err = verify(&settings, &source(tampered, Some(signature.clone())))
+            .expect_err("pinned digest");
+        assert!(err.to_string().contains("expected"));
+        let unpinned = TeamConfigToml {
+            sha256: None,
+            ..settings
+        };
+        assert!(verify(&unpinned, &source(tampered, Some(signature))).is_err());
+        assert!(verify(&unpinned, &source(contents, None)).is_err());
+    }
+
+    #[test]
+    fn git_file_must_be_committed() -> std::io::Result<()> {
+        let repo = TempDir::new()?;
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(repo.path())
+                .output()
+                .map(|output| output.status.success())
+        };
+        assert!(git(&["init", "--quiet"])?);
+        let path = repo.path().join("team.toml");
+        std::fs::write(&path, "model = \"gpt-5-codex\"\n")?;
+
+        assert!(read_git_file(&path, false).is_err());
+
+        assert!(git(&["add", "team.toml"])?);
+        assert!(git(&[
+            "-c",
+            "user.name=Codex",
+            "-c",
+            "user.email=codex@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "team config",
+        ])?);
+        assert_eq!(
+            read_git_file(&path, false)?.contents,
+            "model = \"gpt-5-codex\"\n"
+        );
+
+        std::fs::write(&path, "model = \"o3\"\n")?;
+        let err = read_git_file(&path, false).expect_err("modified file");
+        assert!(err.to_string().ends_with("has uncommitted changes"));
+        Ok(())
+    }
+}
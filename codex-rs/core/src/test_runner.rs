@@ -0,0 +1,641 @@
+//! The `run_tests` tool, which runs the project's tests with cargo, pytest or
+//! jest and parses their output into a report of passed, failed and skipped
+//! tests with failure messages and durations, so the model can re-run just
+//! the failures instead of reading the raw output.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config_types::RunTests;
+use crate::config_types::TestFramework;
+
+pub(crate) const RUN_TESTS_TOOL_NAME: &str = "run_tests";
+
+/// Failure messages are cut to this many characters.
+const MAX_MESSAGE_CHARS: usize = 2_000;
+const MAX_FAILURES: usize = 50;
+const MAX_SKIPPED: usize = 50;
+const SLOWEST_COUNT: usize = 5;
+/// When no test results are recognized, this much of the end of the output
+/// is returned instead, which usually holds the build or collection error.
+const OUTPUT_TAIL_CHARS: usize = 4_000;
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RunTestsArgs {
+    /// Only run tests whose name contains this.
+    #[serde(default)]
+    filter: Option<String>,
+    /// Run exactly these tests, named as in a previous report.
+    #[serde(default)]
+    tests: Vec<String>,
+}
+
+/// The command a `run_tests` call runs.
+#[derive(Debug, PartialEq)]
+pub(crate) struct TestRun {
+    pub(crate) framework: TestFramework,
+    pub(crate) command: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, PartialEq)]
+struct TestResult {
+    name: String,
+    status: TestStatus,
+    duration: Option<Duration>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestReport {
+    framework: TestFramework,
+    command: Vec<String>,
+    exit_code: i32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    timed_out: bool,
+    duration_seconds: f32,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failures: Vec<TestFailure>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_tests: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slowest: Vec<TestTiming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestFailure {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct TestTiming {
+    name: String,
+    duration_seconds: f32,
+}
+
+/// Resolves the framework and builds the command for a call.
+pub(crate) fn plan_test_run(
+    config: &RunTests,
+    cwd: &Path,
+    args: &RunTestsArgs,
+) -> Result<TestRun, String> {
+    let framework = match config.framework {
+        Some(framework) => framework,
+        None => detect_framework(cwd).ok_or_else(|| {
+            "could not detect the test framework; set `run_tests.framework`".to_string()
+        })?,
+    };
+    let mut command = match &config.command {
+        Some(command) if !command.is_empty() => command.clone(),
+        _ => default_command(framework),
+    };
+    command.extend(framework_args(framework, args));
+    Ok(TestRun { framework, command })
+}
+
+/// The framework of the project in `cwd`, from its manifest and config files.
+fn detect_framework(cwd: &Path) -> Option<TestFramework> {
+    if cwd.join("Cargo.toml").is_file() {
+        return Some(TestFramework::Cargo);
+    }
+    if let Ok(package) = std::fs::read_to_string(cwd.join("package.json"))
+        && package.contains("jest")
+    {
+        return Some(TestFramework::Jest);
+    }
+    let pytest_files = [
+        "pytest.ini",
+        "pyproject.toml",
+        "setup.cfg",
+        "tox.ini",
+        "conftest.py",
+    ];
+    if pytest_files.iter().any(|file| cwd.join(file).is_file()) {
+        return Some(TestFramework::Pytest);
+    }
+    None
+}
+
+fn default_command(framework: TestFramework) -> Vec<String> {
+    let command: &[&str] = match framework {
+        TestFramework::Cargo => &["cargo", "test"],
+        TestFramework::Pytest if which::which("pytest").is_ok() => &["pytest"],
+        TestFramework::Pytest => &["python3", "-m", "pytest"],
+        TestFramework::Jest => &["npx", "jest"],
+    };
+    command.iter().map(ToString::to_string).collect()
+}
+
+/// Arguments that make the framework print parseable results, followed by
+/// those selecting the tests to run.
+fn framework_args(framework: TestFramework, args: &RunTestsArgs) -> Vec<String> {
+    let mut out = Vec::new();
+    match framework {
+        TestFramework::Cargo => {
+            if let Some(filter) = &args.filter
+                && args.tests.is_empty()
+            {
+                out.push(filter.clone());
+            }
+            if !args.tests.is_empty() {
+                out.push("--".to_string());
+                out.extend(args.tests.iter().cloned());
+                out.push("--exact".to_string());
+            }
+        }
+        TestFramework::Pytest => {
+            out.extend(["-v", "--tb=short", "--durations=0"].map(String::from));
+            if let Some(filter) = &args.filter
+                && args.tests.is_empty()
+            {
+                out.extend(["-k".to_string(), filter.clone()]);
+            }
+            out.extend(args.tests.iter().cloned());
+        }
+        TestFramework::Jest => {
+            out.extend(["--ci", "--json"].map(String::from));
+            if !args.tests.is_empty() {
+                let names: Vec<String> = args.tests.iter().map(|t| escape_regex(t)).collect();
+                out.extend(["-t".to_string(), format!("^({})$", names.join("|"))]);
+            } else if let Some(filter) = &args.filter {
+                out.extend(["-t".to_string(), escape_regex(filter)]);
+            }
+        }
+    }
+    out
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The JSON report of a finished run.
+pub(crate) fn render_report(
+    run: TestRun,
+    exit_code: i32,
+    timed_out: bool,
+    duration: Duration,
+    stdout: &str,
+    aggregated_output: &str,
+) -> String {
+    let results = match run.framework {
+        TestFramework::Cargo => parse_cargo(aggregated_output),
+        TestFramework::Pytest => parse_pytest(aggregated_output),
+        TestFramework::Jest => parse_jest(stdout),
+    };
+    let count = |status: TestStatus| results.iter().filter(|r| r.status == status).count();
+    let mut timed: Vec<&TestResult> = results.iter().filter(|r| r.duration.is_some()).collect();
+    timed.sort_by_key(|r| std::cmp::Reverse(r.duration));
+    let report = TestReport {
+        framework: run.framework,
+        command: run.command,
+        exit_code,
+        timed_out,
+        duration_seconds: round_seconds(duration),
+        passed: count(TestStatus::Passed),
+        failed: count(TestStatus::Failed),
+        skipped: count(TestStatus::Skipped),
+        failures: results
+            .iter()
+            .filter(|r| r.status == TestStatus::Failed)
+            .take(MAX_FAILURES)
+            .map(|r| TestFailure {
+                name: r.name.clone(),
+                message: r.message.as_deref().map(truncate_message),
+                duration_seconds: r.duration.map(round_seconds),
+            })
+            .collect(),
+        skipped_tests: results
+            .iter()
+            .filter(|r| r.status == TestStatus::Skipped)
+            .take(MAX_SKIPPED)
+            .map(|r| r.name.clone())
+            .collect(),
+        slowest: timed
+            .into_iter()
+            .take(SLOWEST_COUNT)
+            .filter_map(|r| {
+                Some(TestTiming {
+                    name: r.name.clone(),
+                    duration_seconds: round_seconds(r.duration?),
+                })
+            })
+            .collect(),
+        output: results
+            .is_empty()
+            .then(|| tail_chars(aggregated_output, OUTPUT_TAIL_CHARS)),
+    };
+    serde_json::to_string(&report).unwrap_or_else(|e| format!("failed to encode test report: {e}"))
+}
+
+fn round_seconds(duration: Duration) -> f32 {
+    (duration.as_secs_f32() * 100.0).round() / 100.0
+}
+
+fn truncate_message(message: &str) -> String {
+    let message = message.trim();
+    match message.char_indices().nth(MAX_MESSAGE_CHARS) {
+        Some((end, _)) => format!("{}…", &message[..end]),
+        None => message.to_string(),
+    }
+}
+
+fn tail_chars(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let skip = count - max;
+    format!("…{}", text.chars().skip(skip).collect::<String>())
+}
+
+/// Results from libtest's `test <name> ... ok` lines, with the captured
+/// output of each failure as its message.
+fn parse_cargo(output: &str) -> Vec<TestResult> {
+    let mut results = Vec::new();
+    for line in output.lines() {
+        let Some((name, outcome)) = line
+            .strip_prefix("test ")
+            .and_then(|rest| rest.split_once(" ... "))
+        else {
+            continue;
+        };
+        let status = match outcome.trim() {
+            "ok" => TestStatus::Passed,
+            "FAILED" => TestStatus::Failed,
+            outcome if outcome.starts_with("ignored") => TestStatus::Skipped,
+            _ => continue,
+        };
+        results.push(TestResult {
+            name: name.to_string(),
+            status,
+            duration: None,
+            message: None,
+        });
+    }
+
+    // Failures are followed by blocks such as `---- name stdout ----`.
+    let mut current: Option<(String, String)> = None;
+    let mut messages = Vec::new();
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" ----"))
+            .and_then(|rest| rest.rsplit_once(' ').map(|(name, _stream)| name))
+        {
+            messages.extend(current.take());
+            current = Some((name.to_string(), String::new()));
+        } else if line == "failures:" || line.starts_with("test result:") {
+            messages.extend(current.take());
+        } else if let Some((_, message)) = current.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(current);
+    for (name, message) in messages {
+        if let Some(result) = results
+            .iter_mut()
+            .find(|r| r.name == name && r.status == TestStatus::Failed && r.message.is_none())
+        {
+            result.message = Some(message);
+        }
+    }
+    results
+}
+
+/// Results from pytest's `-v` lines, with messages from the FAILURES and
+/// ERRORS sections and durations from `--durations`.
+fn parse_pytest(output: &str) -> Vec<TestResult> {
+    let mut results: Vec<TestResult> = Vec::new();
+    for line in output.lines() {
+        let Some((node_id, rest)) = line.split_once(' ') else {
+            continue;
+        };
+        if !node_id.contains("::") {
+            continue;
+        }
+        let status = match rest.split_whitespace().next() {
+            Some("PASSED" | "XFAIL") => TestStatus::Passed,
+            Some("FAILED" | "ERROR" | "XPASS") => TestStatus::Failed,
+            Some("SKIPPED") => TestStatus::Skipped,
+            _ => continue,
+        };
+        // A test that fails in teardown is reported a second time.
+        if let Some(existing) = results.iter_mut().find(|r| r.name == node_id) {
+            if status == TestStatus::Failed {
+                existing.status = status;
+            }
+            continue;
+        }
+        results.push(TestResult {
+            name: node_id.to_string(),
+            status,
+            duration: None,
+            message: None,
+        });
+    }
+
+    let mut in_details = false;
+    let mut current: Option<(String, String)> = None;
+    let mut messages = Vec::new();
+    for line in output.lines() {
+        if line.starts_with("===") {
+            messages.extend(current.take());
+            in_details = line.contains(" FAILURES ") || line.contains(" ERRORS ");
+            continue;
+        }
+        if !in_details {
+            continue;
+        }
+        if line.starts_with("___") && line.ends_with("___") {
+            messages.extend(current.take());
+            let title = line.trim_matches(|c| c == '_' || c == ' ');
+            let title = title.strip_prefix("ERROR at teardown of ").unwrap_or(title);
+            let title = title.strip_prefix("ERROR at setup of ").unwrap_or(title);
+            current = Some((title.to_string(), String::new()));
+        } else if let Some((_, message)) = current.as_mut() {
+            message.push_str(line);
+            message.push('\n');
+        }
+    }
+    messages.extend(current);
+    for (title, message) in messages {
+        // Titles are `test_name` or `Class.test_name`, without the file.
+        let suffix = format!("::{}", title.replace('.', "::"));
+        if let Some(result) = results
+            .iter_mut()
+            .find(|r| r.name.ends_with(&suffix) && r.message.is_none())
+        {
+            result.message = Some(message);
+        }
+    }
+
+    // `--durations` lines look like `0.52s call     tests/test_a.py::test_x`.
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(seconds), Some("call"), Some(node_id)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let Some(seconds) = seconds
+            .strip_suffix('s')
+            .and_then(|s| s.parse::<f64>().ok())
+        else {
+            continue;
+        };
+        if let Some(result) = results.iter_mut().find(|r| r.name == node_id) {
+            result.duration = Some(Duration::from_secs_f64(seconds));
+        }
+    }
+    results
+}
+
+/// Results from the report `jest --json` prints on stdout.
+fn parse_jest(stdout: &str) -> Vec<TestResult> {
+    let Some(start) = stdout.find('{') else {
+        return Vec::new();
+    };
+    let Ok(report) = serde_json::from_str::<Value>(&stdout[start..]) else {
+        return Vec::new();
+    };
+    let mut results = Vec::new();
+    for file in report["testResults"].as_array().into_iter().flatten() {
+        let assertions = file["assertionResults"].as_array();
+        if assertions.is_none_or(Vec::is_empty) {
+            // The file failed before running any test, e.g. on a syntax error.
+            if file["status"] == "failed" {
+                results.push(TestResult {
+                    name: file["name"].as_str().unwrap_or_default().to_string(),
+                    status: TestStatus::Failed,
+                    duration: None,
+                    message: file["message"].as_str().map(str::to_string),
+                });
+            }
+            continue;
+        }
+        for assertion in assertions.into_iter().flatten() {
+            let status = match assertion["status"].as_str() {
+                Some("passed") => TestStatus::Passed,
+                Some("failed") => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            let messages: Vec<&str> = assertion["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            results.push(TestResult {
+                name: assertion["fullName"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                status,
+                duration: assertion["duration"].as_u64().map(Duration::from_millis),
+                message: (!messages.is_empty()).then(|| messages.join("\n")),
+            });
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn summary(results: &[TestResult]) -> Vec<(&str, TestStatus)> {
+        results
+            .iter()
+            .map(|r| (r.name.as_str(), r.status))
+            .collect()
+    }
+
+    #[test]
+    fn detects_framework_and_builds_targeted_commands() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(None, detect_framework(dir.path()));
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"devDependencies": {"jest": "^29"}}"#,
+        )
+        .unwrap();
+        assert_eq!(Some(TestFramework::Jest), detect_framework(dir.path()));
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+
+        let run = plan_test_run(
+            &RunTests::default(),
+            dir.path(),
+            &RunTestsArgs {
+                filter: None,
+                tests: vec!["a::b".to_string(), "c".to_string()],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            TestRun {
+                framework: TestFramework::Cargo,
+                command: ["cargo", "test", "--", "a::b", "c", "--exact"]
+                    .map(String::from)
+                    .to_vec(),
+            },
+            run
+        );
+
+        let config = RunTests {
+            framework: Some(TestFramework::Jest),
+            command: Some(vec!["yarn".to_string(), "jest".to_string()]),
+            ..RunTests::default()
+        };
+        let run = plan_test_run(
+            &config,
+            dir.path(),
+            &RunTestsArgs {
+                filter: None,
+                tests: vec!["math adds (1+1)".to_string()],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            [
+                "yarn",
+                "jest",
+                "--ci",
+                "--json",
+                "-t",
+                r"^(math adds \(1\+1\))$"
+            ]
+            .map(String::from)
+            .to_vec(),
+            run.command
+        );
+    }
+
+    #[test]
+    fn parses_cargo_output() {
+        let output = "\
+running 3 tests
+test tests::adds ... ok
+test tests::slow ... ignored
+test tests::divides ... FAILED
+
+failures:
+
+---- tests::divides stdout ----
+thread 'tests::divides' panicked at src/lib.rs:10:9:
+attempt to divide by zero
+
+failures:
+    tests::divides
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+";
+        let results = parse_cargo(output);
+        assert_eq!(
+            vec![
+                ("tests::adds", TestStatus::Passed),
+                ("tests::slow", TestStatus::Skipped),
+                ("tests::divides", TestStatus::Failed),
+            ],
+            summary(&results)
+        );
+        assert_eq!(
+            Some(
+                "thread 'tests::divides' panicked at src/lib.rs:10:9:\nattempt to divide by zero\n\n"
+            ),
+            results[2].message.as_deref()
+        );
+    }
+
+    #[test]
+    fn parses_pytest_output() {
+        let output = "\
+tests/test_math.py::test_add PASSED                                      [ 33%]
+tests/test_math.py::TestDiv::test_zero FAILED                            [ 66%]
+tests/test_math.py::test_skip SKIPPED (not ready)                        [100%]
+
+=================================== FAILURES ===================================
+______________________________ TestDiv.test_zero _______________________________
+tests/test_math.py:9: in test_zero
+    1 / 0
+E   ZeroDivisionError: division by zero
+============================= slowest durations ==============================
+0.52s call     tests/test_math.py::TestDiv::test_zero
+0.01s call     tests/test_math.py::test_add
+=========================== short test summary info ============================
+FAILED tests/test_math.py::TestDiv::test_zero - ZeroDivisionError: division by zero
+";
+        let results = parse_pytest(output);
+        assert_eq!(
+            vec![
+                ("tests/test_math.py::test_add", TestStatus::Passed),
+                ("tests/test_math.py::TestDiv::test_zero", TestStatus::Failed),
+                ("tests/test_math.py::test_skip", TestStatus::Skipped),
+            ],
+            summary(&results)
+        );
+        assert!(
+            results[1]
+                .message
+                .as_deref()
+                .is_some_and(|m| m.contains("ZeroDivisionError"))
+        );
+        assert_eq!(Some(Duration::from_millis(520)), results[1].duration);
+    }
+
+    #[test]
+    fn parses_jest_report_and_renders_summary() {
+        let stdout = r#"{"testResults": [
+            {"name": "/repo/math.test.js", "status": "failed", "assertionResults": [
+                {"fullName": "math adds", "status": "passed", "duration": 3, "failureMessages": []},
+                {"fullName": "math divides", "status": "failed", "duration": 12, "failureMessages": ["Expected 1, got 2"]},
+                {"fullName": "math later", "status": "pending", "duration": null, "failureMessages": []}
+            ]},
+            {"name": "/repo/broken.test.js", "status": "failed", "message": "SyntaxError", "assertionResults": []}
+        ]}"#;
+        let run = TestRun {
+            framework: TestFramework::Jest,
+            command: vec!["npx".to_string(), "jest".to_string()],
+        };
+        let report = render_report(run, 1, false, Duration::from_secs(2), stdout, stdout);
+        let report: Value = serde_json::from_str(&report).unwrap();
+
+        assert_eq!(1, report["passed"]);
+        assert_eq!(2, report["failed"]);
+        assert_eq!(1, report["skipped"]);
+        assert_eq!("math divides", report["failures"][0]["name"]);
+        assert_eq!("Expected 1, got 2", report["failures"][0]["message"]);
+        assert_eq!("/repo/broken.test.js", report["failures"][1]["name"]);
+        assert_eq!("math divides", report["slowest"][0]["name"]);
+        assert!(report.get("output").is_none());
+    }
+}
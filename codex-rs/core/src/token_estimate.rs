@@ -0,0 +1,152 @@
+//! Estimates of the size of a prompt in tokens, computed before it is sent so
+//! that a conversation about to overflow the context window is compacted
+//! instead of being rejected by the provider.
+//!
+//! Text is counted with the byte-pair encoder of the model, so the estimate
+//! is off only by the framing the provider adds around messages and tools.
+
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::cl100k_base_singleton;
+use tiktoken_rs::o200k_base_singleton;
+use tiktoken_rs::p50k_base_singleton;
+use tiktoken_rs::p50k_edit_singleton;
+use tiktoken_rs::r50k_base_singleton;
+use tiktoken_rs::tokenizer::Tokenizer;
+use tiktoken_rs::tokenizer::get_tokenizer;
+
+use crate::client_common::Prompt;
+use crate::model_family::ModelFamily;
+
+/// Tokens a message costs beyond its content (role and separators).
+const TOKENS_PER_ITEM: u64 = 4;
+
+/// Tokens charged for an image, whatever its size.
+const TOKENS_PER_IMAGE: u64 = 765;
+
+/// Estimated tokens of the request `prompt` turns into: the instructions, the
+/// conversation, the tools and the output schema.
+pub(crate) fn estimate_prompt_tokens(prompt: &Prompt, model_family: &ModelFamily) -> u64 {
+    let bpe = encoder_for(model_family);
+    let instructions = text_tokens(bpe, &prompt.get_full_instructions(model_family));
+    let input: u64 = prompt
+        .get_formatted_input()
+        .iter()
+        .map(|item| {
+            TOKENS_PER_ITEM
+                + serde_json::to_value(item)
+                    .map(|value| value_tokens(bpe, &value))
+                    .unwrap_or_default()
+        })
+        .sum();
+    let tools: u64 = prompt
+        .tools
+        .iter()
+        .filter_map(|tool| serde_json::to_string(tool).ok())
+        .map(|tool| text_tokens(bpe, &tool))
+        .sum();
+    let schema = prompt
+        .output_schema
+        .as_ref()
+        .map(|schema| text_tokens(bpe, &schema.to_string()))
+        .unwrap_or_default();
+    instructions + input + tools + schema
+}
+
+/// The byte-pair encoder of `model_family`. Models missing from the
+/// tokenizer table, such as the GPT-5 family, use `o200k_base`.
+fn encoder_for(model_family: &ModelFamily) -> &'static CoreBPE {
+    match get_tokenizer(&model_family.slug) {
+        Some(Tokenizer::Cl100kBase) => cl100k_base_singleton(),
+        Some(Tokenizer::P50kBase) => p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase | Tokenizer::Gpt2) => r50k_base_singleton(),
+        Some(Tokenizer::O200kBase) | None => o200k_base_singleton(),
+    }
+}
+
+/// Builds the encoder of `model_family` ahead of the first estimate, which
+/// would otherwise hold up the first request of the session.
+pub(crate) fn load_encoder(model_family: &ModelFamily) {
+    encoder_for(model_family);
+}
+
+/// Tokens of the strings in `value`, with images at a flat rate. Keys and
+/// the JSON syntax around the strings are not part of the model's input.
+fn value_tokens(bpe: &CoreBPE, value: &Value) -> u64 {
+    match value {
+        Value::String(s) if s.starts_with("data:image/") => TOKENS_PER_IMAGE,
+        Value::String(s) => text_tokens(bpe, s),
+        Value::Array(values) => values.iter().map(|value| value_tokens(bpe, value)).sum(),
+        Value::Object(map) => map
+            .iter()
+            .filter(|(key, _)| !matches!(key.as_str(), "type" | "id" | "call_id" | "status"))
+            .map(|(_, value)| value_tokens(bpe, value))
+            .sum(),
+        Value::Number(n) => text_tokens(bpe, &n.to_string()),
+        Value::Bool(_) | Value::Null => 0,
+    }
+}
+
+/// Tokens of `text`. Special tokens in it are counted as plain text, as the
+/// provider does.
+fn text_tokens(bpe: &CoreBPE, text: &str) -> u64 {
+    bpe.encode_ordinary(text).len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use codex_protocol::models::ContentItem;
+    use codex_protocol::models::ResponseItem;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn counts_text_with_the_model_tokenizer() {
+        let bpe = encoder_for(&find_family_for_model("gpt-5-codex").expect("known model"));
+        assert_eq!(text_tokens(bpe, ""), 0);
+        assert_eq!(text_tokens(bpe, "hello world"), 2);
+        assert_eq!(text_tokens(bpe, "getModelContextWindow();"), 5);
+        assert_eq!(text_tokens(bpe, "    let x = 1;\n"), 7);
+        assert_eq!(text_tokens(bpe, "日本語"), 2);
+        assert_eq!(text_tokens(bpe, "<|endoftext|>"), 7);
+    }
+
+    #[test]
+    fn counts_every_part_of_the_prompt() {
+        let model_family = find_family_for_model("gpt-5-codex").expect("known model");
+        let message = |text: &str| ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        };
+        let empty = Prompt {
+            base_instructions_override: Some(String::new()),
+            ..Default::default()
+        };
+        let prompt = Prompt {
+            input: vec![
+                message("hello world"),
+                ResponseItem::Message {
+                    id: None,
+                    role: "user".to_string(),
+                    content: vec![ContentItem::InputImage {
+                        image_url: "data:image/png;base64,AAAA".to_string(),
+                    }],
+                },
+            ],
+            ..empty.clone()
+        };
+
+        assert_eq!(estimate_prompt_tokens(&empty, &model_family), 0);
+        // Both messages cost their overhead and the role; the text and the
+        // image their content.
+        assert_eq!(
+            estimate_prompt_tokens(&prompt, &model_family),
+            2 * (TOKENS_PER_ITEM + 1) + 2 + TOKENS_PER_IMAGE
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Tool calls for models without native tool calling, as many models served
+//! by Ollama or llama.cpp are (`tool_calling = "prompt"`).
+//!
+//! Instead of sending tool definitions, the tools are described in the
+//! instructions and the model is asked to reply with `<tool_call>` blocks,
+//! which are parsed out of its final message into function calls. Earlier
+//! calls and their outputs are replayed to the model in the same textual
+//! form.
+
+use std::collections::HashSet;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use serde_json::Value;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::OpenAiTool;
+
+const TOOL_CALL_OPEN: &str = "<tool_call>";
+const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+/// Name of the single string argument freeform tools take when emulated.
+const FREEFORM_INPUT_ARG: &str = "input";
+
+/// Text of an error some servers answer with when the model cannot take
+/// tool definitions, e.g. Ollama's `<model> does not support tools`.
+const NO_TOOL_SUPPORT_MARKER: &str = "does not support tools";
+
+/// Whether the error `body` of a rejected request says the model does not
+/// support tools.
+pub(crate) fn is_no_tool_support_error(body: &str) -> bool {
+    body.contains(NO_TOOL_SUPPORT_MARKER)
+}
+
+/// `prompt` rewritten for a model without native tool calling: no tool
+/// definitions, the tools described in the instructions, and earlier tool
+/// calls and outputs as plain messages.
+pub(crate) fn emulate_tool_calls(prompt: &Prompt, model_family: &ModelFamily) -> Prompt {
+    let mut instructions = prompt.get_full_instructions(model_family).into_owned();
+    let tools = describe_tools(&prompt.tools);
+    if !tools.is_empty() {
+        instructions.push_str("\n\n");
+        instructions.push_str(&tools);
+    }
+    Prompt {
+        input: prompt
+            .get_formatted_input()
+            .into_iter()
+            .filter_map(replay_item)
+            .collect(),
+        tools: Vec::new(),
+        base_instructions_override: Some(instructions),
+        output_schema: prompt.output_schema.clone(),
+    }
+}
+
+/// Names of the freeform tools of `tools`, whose calls carry a string input.
+pub(crate) fn freeform_tool_names(tools: &[OpenAiTool]) -> HashSet<String> {
+    tools
+        .iter()
+        .filter_map(|tool| match tool {
+            OpenAiTool::Freeform(tool) => Some(tool.name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn describe_tools(tools: &[OpenAiTool]) -> String {
+    let mut sections = Vec::new();
+    for tool in tools {
+        match tool {
+            OpenAiTool::Function(tool) => sections.push(format!(
+                "## {}\n{}\nArguments (JSON Schema): {}",
+                tool.name,
+                tool.description,
+                serde_json::to_string(&tool.parameters).unwrap_or_default()
+            )),
+            OpenAiTool::Freeform(tool) => sections.push(format!(
+                "## {}\n{}\nArguments: {{\"{FREEFORM_INPUT_ARG}\": string}}, the input in the \
+                 following {} grammar:\n{}",
+                tool.name, tool.description, tool.format.syntax, tool.format.definition
+            )),
+            OpenAiTool::LocalShell {} | OpenAiTool::WebSearch {} => {}
+        }
+    }
+    if sections.is_empty() {
+        return String::new();
+    }
+    format!(
+        "# Tools\n\nYou can call the tools below. To call one, reply with a block like this \
+         one, with the arguments as a JSON object, and wait for its result:\n\n\
+         {TOOL_CALL_OPEN}\n{{\"name\": \"<tool name>\", \"arguments\": {{...}}}}\n{TOOL_CALL_CLOSE}\n\n\
+         You can make several calls in one reply, one block each. The result of each call \
+         comes back in a <tool_result> block.\n\n{}",
+        sections.join("\n\n")
+    )
+}
+
+/// An item of the conversation as a model without tool calling sees it.
+fn replay_item(item: ResponseItem) -> Option<ResponseItem> {
+    let (role, text) = match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => {
+            let arguments =
+                serde_json::from_str::<Value>(&arguments).unwrap_or(Value::String(arguments));
+            ("assistant", tool_call_block(&name, arguments))
+        }
+        ResponseItem::CustomToolCall { name, input, .. } => (
+            "assistant",
+            tool_call_block(&name, json!({ FREEFORM_INPUT_ARG: input })),
+        ),
+        ResponseItem::FunctionCallOutput { output, .. } => (
+            "user",
+            format!("<tool_result>\n{}\n</tool_result>", output.content),
+        ),
+        ResponseItem::CustomToolCallOutput { output, .. } => {
+            ("user", format!("<tool_result>\n{output}\n</tool_result>"))
+        }
+        ResponseItem::LocalShellCall { .. } | ResponseItem::WebSearchCall { .. } => return None,
+        item => return Some(item),
+    };
+    let content = if role == "assistant" {
+        ContentItem::OutputText { text }
+    } else {
+        ContentItem::InputText { text }
+    };
+    Some(ResponseItem::Message {
+        id: None,
+        role: role.to_string(),
+        content: vec![content],
+    })
+}
+
+fn tool_call_block(name: &str, arguments: Value) -> String {
+    format!(
+        "{TOOL_CALL_OPEN}\n{}\n{TOOL_CALL_CLOSE}",
+        json!({"name": name, "arguments": arguments})
+    )
+}
+
+/// Replace a final assistant message that contains `<tool_call>` blocks with
+/// the message without them, if any text remains, followed by the calls.
+/// Other events are kept as they are.
+pub(crate) fn extract_tool_calls(
+    event: ResponseEvent,
+    freeform_tools: &HashSet<String>,
+) -> Vec<ResponseEvent> {
+    let ResponseEvent::OutputItemDone(ResponseItem::Message { id, role, content }) = event else {
+        return vec![event];
+    };
+    if role != "assistant" {
+        return vec![ResponseEvent::OutputItemDone(ResponseItem::Message {
+            id,
+            role,
+            content,
+        })];
+    }
+
+    let mut calls = Vec::new();
+    let mut kept = Vec::new();
+    for item in content {
+        match item {
+            ContentItem::OutputText { text } => {
+                let (text, found) = parse_tool_calls(&text, freeform_tools);
+                calls.extend(found);
+                if !text.is_empty() {
+                    kept.push(ContentItem::OutputText { text });
+                }
+            }
+            other => kept.push(other),
+        }
+    }
+
+    let mut events = Vec::new();
+    if !kept.is_empty() {
+        events.push(ResponseEvent::OutputItemDone(ResponseItem::Message {
+            id,
+            role,
+            content: kept,
+        }));
+    }
+    events.extend(calls.into_iter().map(ResponseEvent::OutputItemDone));
+    events
+}
+
+/// Split `text` into the text outside `<tool_call>` blocks and the calls the
+/// blocks hold. Blocks that are not a JSON object with a `name` are kept as
+/// text.
+fn parse_tool_calls(text: &str, freeform_tools: &HashSet<String>) -> (String, Vec<ResponseItem>) {
+    let mut remaining = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(TOOL_CALL_OPEN) {
+        let after = &rest[start + TOOL_CALL_OPEN.len()..];
+        let (body, next) = match after.find(TOOL_CALL_CLOSE) {
+            Some(end) => (&after[..end], &after[end + TOOL_CALL_CLOSE.len()..]),
+            None => (after, ""),
+        };
+        match parse_call(body, freeform_tools) {
+            Some(call) => {
+                remaining.push_str(&rest[..start]);
+                calls.push(call);
+            }
+            None => remaining.push_str(&rest[..rest.len() - next.len()]),
+        }
+        rest = next;
+    }
+    remaining.push_str(rest);
+    (remaining.trim().to_string(), calls)
+}
+
+fn parse_call(body: &str, freeform_tools: &HashSet<String>) -> Option<ResponseItem> {
+    let body = body.trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.strip_suffix("```"))
+        .unwrap_or(body);
+    let value: Value = serde_json::from_str(body.trim()).ok()?;
+    let name = value.get("name")?.as_str()?.to_string();
+    let arguments = value
+        .get("arguments")
+        .or_else(|| value.get("parameters"))
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let call_id = format!("call_{}", Uuid::new_v4().simple());
+    if freeform_tools.contains(&name) {
+        let input = match arguments {
+            Value::String(input) => input,
+            arguments => arguments
+                .get(FREEFORM_INPUT_ARG)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+        };
+        return Some(ResponseItem::CustomToolCall {
+            id: None,
+            status: None,
+            call_id,
+            name,
+            input,
+        });
+    }
+    let arguments = match arguments {
+        Value::String(arguments) => arguments,
+        arguments => arguments.to_string(),
+    };
+    Some(ResponseItem::FunctionCall {
+        id: None,
+        name,
+        arguments,
+        call_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::derive_default_model_family;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn message(role: &str, text: &str) -> ResponseItem {
+        let text = text.to_string();
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![if role == "assistant" {
+                ContentItem::OutputText { text }
+            } else {
+                ContentItem::InputText { text }
+            }],
+        }
+    }
+
+    #[test]
+    fn extracts_tool_calls_from_the_final_message() {
+        let event = ResponseEvent::OutputItemDone(message(
+            "assistant",
+            "Let me look.\n<tool_call>\n{\"name\": \"shell\", \"arguments\": {\"command\": [\"ls\"]}}\n</tool_call>\n\
+             <tool_call>{\"name\": \"apply_patch\", \"arguments\": {\"input\": \"*** Begin Patch\"}}</tool_call>\n\
+             <tool_call>not json</tool_call>",
+        ));
+
+        let items: Vec<ResponseItem> =
+            extract_tool_calls(event, &HashSet::from(["apply_patch".to_string()]))
+                .into_iter()
+                .map(|event| match event {
+                    ResponseEvent::OutputItemDone(item) => item,
+                    other => panic!("unexpected event {other:?}"),
+                })
+                .collect();
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0],
+            message(
+                "assistant",
+                "Let me look.\n\n\n<tool_call>not json</tool_call>"
+            )
+        );
+        let ResponseItem::FunctionCall {
+            name, arguments, ..
+        } = &items[1]
+        else {
+            panic!("expected a function call, got {:?}", items[1]);
+        };
+        assert_eq!(name, "shell");
+        assert_eq!(arguments, r#"{"command":["ls"]}"#);
+        let ResponseItem::CustomToolCall { name, input, .. } = &items[2] else {
+            panic!("expected a custom tool call, got {:?}", items[2]);
+        };
+        assert_eq!(name, "apply_patch");
+        assert_eq!(input, "*** Begin Patch");
+    }
+
+    #[test]
+    fn replays_tool_calls_as_messages() {
+        let prompt = Prompt {
+            input: vec![
+                message("user", "list files"),
+                ResponseItem::FunctionCall {
+                    id: None,
+                    name: "shell".to_string(),
+                    arguments: r#"{"command":["ls"]}"#.to_string(),
+                    call_id: "call_1".to_string(),
+                },
+                ResponseItem::FunctionCallOutput {
+                    call_id: "call_1".to_string(),
+                    output: FunctionCallOutputPayload {
+                        content: "README.md".to_string(),
+                        success: Some(true),
+                    },
+                },
+            ],
+            ..Default::default()
+        };
+
+        let emulated = emulate_tool_calls(&prompt, &derive_default_model_family("llama3"));
+
+        assert_eq!(
+            emulated.input,
+            vec![
+                message("user", "list files"),
+                message(
+                    "assistant",
+                    &tool_call_block("shell", json!({"command": ["ls"]}))
+                ),
+                message("user", "<tool_result>\nREADME.md\n</tool_result>"),
+            ]
+        );
+        assert!(emulated.tools.is_empty());
+    }
+}
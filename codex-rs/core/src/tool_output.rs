@@ -0,0 +1,353 @@
+//! Validation of tool outputs against the JSON schemas tools declare for
+//! them. An output that does not match is replaced by a structured error, so
+//! the model is told what went wrong instead of reasoning over a malformed
+//! result.
+//!
+//! The validator covers the JSON Schema keywords tools use in practice:
+//! `type`, `enum`, `const`, `properties`, `required`, `additionalProperties`,
+//! `items`, `allOf`, `anyOf`, `oneOf`, the length and item-count bounds and
+//! `minimum`/`maximum`. Other keywords are ignored.
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::python_kernel::PYTHON_TOOL_NAME;
+use crate::python_kernel::python_output_schema;
+use crate::truncate::truncate_middle;
+
+/// Most violations listed in an error.
+const MAX_VIOLATIONS: usize = 10;
+/// Most of the rejected output echoed back in an error.
+const MAX_ECHOED_OUTPUT_BYTES: usize = 2 * 1024;
+
+/// The output schema of a built-in tool, if it declares one.
+pub(crate) fn builtin_output_schema(name: &str) -> Option<Value> {
+    match name {
+        PYTHON_TOOL_NAME => Some(python_output_schema()),
+        _ => None,
+    }
+}
+
+/// Returns `output` unchanged when it is JSON matching `schema`, and a
+/// structured error for the model otherwise.
+pub(crate) fn check_output(tool: &str, schema: &Value, output: String) -> Result<String, String> {
+    match check_json_text(schema, &output) {
+        Ok(()) => Ok(output),
+        Err(violations) => Err(invalid_output_error(tool, &violations, &output)),
+    }
+}
+
+/// Checks that `text` is JSON matching `schema`, returning the violations.
+pub(crate) fn check_json_text(schema: &Value, text: &str) -> Result<(), Vec<String>> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| vec![format!("$: the output is not valid JSON: {e}")])?;
+    check_json(schema, &value)
+}
+
+/// Checks that `value` matches `schema`, returning the violations.
+pub(crate) fn check_json(schema: &Value, value: &Value) -> Result<(), Vec<String>> {
+    let mut violations = Vec::new();
+    validate(schema, value, "$", &mut violations);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// The error returned to the model in place of an output that does not
+/// match the tool's schema.
+pub(crate) fn invalid_output_error(tool: &str, violations: &[String], output: &str) -> String {
+    let shown: Vec<&String> = violations.iter().take(MAX_VIOLATIONS).collect();
+    json!({
+        "error": format!("the output of `{tool}` does not match its output schema"),
+        "violations": shown,
+        "omitted_violations": violations.len() - shown.len(),
+        "output": truncate_middle(output, MAX_ECHOED_OUTPUT_BYTES).0,
+    })
+    .to_string()
+}
+
+/// Appends to `violations` the ways `value`, found at `path`, breaks
+/// `schema`.
+fn validate(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true`, `false` and malformed schemas. Only `false` rejects.
+        if schema == &Value::Bool(false) {
+            violations.push(format!("{path}: no value is allowed here"));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(kind) => vec![kind.as_str()],
+            Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|kind| has_type(value, kind)) {
+            violations.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum")
+        && !options.contains(value)
+    {
+        violations.push(format!(
+            "{path}: {value} is not one of {}",
+            Value::Array(options.clone())
+        ));
+    }
+    if let Some(expected) = schema.get("const")
+        && expected != value
+    {
+        violations.push(format!("{path}: expected {expected}, got {value}"));
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        violations.push(format!("{path}: missing required property `{key}`"));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in map {
+                let item_path = format!("{path}.{key}");
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(property) => validate(property, item, &item_path, violations),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            violations.push(format!("{path}: unexpected property `{key}`"));
+                        }
+                        Some(additional) => validate(additional, item, &item_path, violations),
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            check_bound(schema, "minItems", items.len(), path, "items", violations);
+            check_bound(schema, "maxItems", items.len(), path, "items", violations);
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{path}[{index}]"), violations);
+                }
+            }
+        }
+        Value::String(text) => {
+            let len = text.chars().count();
+            check_bound(schema, "minLength", len, path, "characters", violations);
+            check_bound(schema, "maxLength", len, path, "characters", violations);
+        }
+        Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64)
+                    && number < minimum
+                {
+                    violations.push(format!("{path}: {number} is less than {minimum}"));
+                }
+                if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64)
+                    && number > maximum
+                {
+                    violations.push(format!("{path}: {number} is greater than {maximum}"));
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+
+    if let Some(Value::Array(all)) = schema.get("allOf") {
+        for sub_schema in all {
+            validate(sub_schema, value, path, violations);
+        }
+    }
+    if let Some(Value::Array(any)) = schema.get("anyOf")
+        && !any.iter().any(|sub_schema| matches(sub_schema, value))
+    {
+        violations.push(format!("{path}: matches none of the allowed schemas"));
+    }
+    if let Some(Value::Array(one)) = schema.get("oneOf") {
+        let matching = one
+            .iter()
+            .filter(|sub_schema| matches(sub_schema, value))
+            .count();
+        if matching != 1 {
+            violations.push(format!(
+                "{path}: matches {matching} of the schemas, but exactly one is required"
+            ));
+        }
+    }
+}
+
+fn matches(schema: &Value, value: &Value) -> bool {
+    let mut violations = Vec::new();
+    validate(schema, value, "", &mut violations);
+    violations.is_empty()
+}
+
+fn check_bound(
+    schema: &serde_json::Map<String, Value>,
+    keyword: &str,
+    actual: usize,
+    path: &str,
+    unit: &str,
+    violations: &mut Vec<String>,
+) {
+    let Some(bound) = schema.get(keyword).and_then(Value::as_u64) else {
+        return;
+    };
+    let actual = actual as u64;
+    if keyword.starts_with("min") && actual < bound {
+        violations.push(format!("{path}: {actual} {unit}, fewer than {bound}"));
+    } else if keyword.starts_with("max") && actual > bound {
+        violations.push(format!("{path}: {actual} {unit}, more than {bound}"));
+    }
+}
+
+fn has_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => match value {
+            Value::Number(number) => {
+                number.is_i64()
+                    || number.is_u64()
+                    || number.as_f64().is_some_and(|n| n.fract() == 0.0)
+            }
+            _ => false,
+        },
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn violations(schema: Value, value: Value) -> Vec<String> {
+        let mut violations = Vec::new();
+        validate(&schema, &value, "$", &mut violations);
+        violations
+    }
+
+    #[test]
+    fn accepts_matching_values() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "tags": { "type": "array", "items": { "type": "string" }, "maxItems": 3 },
+                "status": { "enum": ["ok", "failed"] },
+                "note": { "type": ["string", "null"] },
+            },
+            "required": ["id", "status"],
+            "additionalProperties": false,
+        });
+        let value = json!({ "id": 7, "tags": ["a", "b"], "status": "ok", "note": null });
+        assert_eq!(Vec::<String>::new(), violations(schema, value));
+    }
+
+    #[test]
+    fn reports_each_violation_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer" },
+                "items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "name": { "type": "string", "minLength": 1 } },
+                    },
+                },
+            },
+            "required": ["id", "status"],
+            "additionalProperties": false,
+        });
+        let value = json!({ "id": "7", "items": [{ "name": "" }, { "name": 3 }], "extra": true });
+        // Sorted, as the order of properties depends on serde_json's features.
+        let mut found = violations(schema, value);
+        found.sort();
+        assert_eq!(
+            vec![
+                "$.id: expected integer, got string",
+                "$.items[0].name: 0 characters, fewer than 1",
+                "$.items[1].name: expected string, got number",
+                "$: missing required property `status`",
+                "$: unexpected property `extra`",
+            ],
+            found
+        );
+    }
+
+    #[test]
+    fn combinators() {
+        let schema = json!({ "oneOf": [{ "type": "integer" }, { "type": "number" }] });
+        assert_eq!(
+            vec!["$: matches 2 of the schemas, but exactly one is required"],
+            violations(schema, json!(3))
+        );
+        let schema = json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] });
+        assert_eq!(
+            Vec::<String>::new(),
+            violations(schema.clone(), json!(null))
+        );
+        assert_eq!(
+            vec!["$: matches none of the allowed schemas"],
+            violations(schema, json!(false))
+        );
+    }
+
+    #[test]
+    fn malformed_output_becomes_a_structured_error() {
+        let schema = json!({ "type": "object", "required": ["rows"] });
+        assert_eq!(
+            Ok(r#"{"rows":[]}"#.to_string()),
+            check_output("query", &schema, r#"{"rows":[]}"#.to_string())
+        );
+
+        let error = check_output("query", &schema, "{}".to_string()).unwrap_err();
+        assert_eq!(
+            json!({
+                "error": "the output of `query` does not match its output schema",
+                "violations": ["$: missing required property `rows`"],
+                "omitted_violations": 0,
+                "output": "{}",
+            }),
+            serde_json::from_str::<Value>(&error).unwrap()
+        );
+
+        let error = check_output("query", &schema, "rows: none".to_string()).unwrap_err();
+        let error: Value = serde_json::from_str(&error).unwrap();
+        assert!(
+            error["violations"][0]
+                .as_str()
+                .unwrap()
+                .starts_with("$: the output is not valid JSON"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Per-tool quotas from `[tool_quotas]`. They cap how often a tool may be
+//! called in a turn or a session, how often with the same arguments, and how
+//! much output it may return over the session, so a model stuck repeating a
+//! call is stopped with an explanation instead of looping until the turn
+//! runs out of context.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::config_types::ToolNamePattern;
+use crate::config_types::ToolQuota;
+
+/// Calls and output of each tool, counted against the quotas.
+#[derive(Default)]
+pub(crate) struct ToolUsage {
+    /// Submission of the turn the `turn_*` counters belong to.
+    turn: String,
+    turn_calls: HashMap<String, u64>,
+    /// Calls of the turn by tool name and arguments.
+    identical_calls: HashMap<(String, String), u64>,
+    session_calls: HashMap<String, u64>,
+    output_bytes: HashMap<String, u64>,
+}
+
+/// The entry of a per-tool table that applies to `name`: the one keyed by the
+/// exact name, or else the one with the longest key matching it as a pattern.
+pub(crate) fn setting_for<'a, T>(settings: &'a BTreeMap<String, T>, name: &str) -> Option<&'a T> {
+    settings.get(name).or_else(|| {
+        settings
+            .iter()
+            .filter(|(pattern, _)| ToolNamePattern::new(pattern).matches(name))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, setting)| setting)
+    })
+}
+
+impl ToolUsage {
+    /// Counts a call of `name` made in the turn of submission `turn`, or
+    /// returns the structured error for the model when the call would exceed
+    /// the tool's quota. Refused calls are not counted.
+    pub(crate) fn begin_call(
+        &mut self,
+        quotas: &BTreeMap<String, ToolQuota>,
+        turn: &str,
+        name: &str,
+        arguments: &str,
+    ) -> Result<(), String> {
+        if self.turn != turn {
+            self.turn = turn.to_string();
+            self.turn_calls.clear();
+            self.identical_calls.clear();
+        }
+        let Some(quota) = setting_for(quotas, name) else {
+            return Ok(());
+        };
+        let identical_key = (name.to_string(), arguments.to_string());
+        let counts = [
+            (
+                "max_calls_per_turn",
+                quota.max_calls_per_turn,
+                self.turn_calls.get(name).copied().unwrap_or(0),
+                "calls in this turn",
+            ),
+            (
+                "max_identical_calls_per_turn",
+                quota.max_identical_calls_per_turn,
+                self.identical_calls
+                    .get(&identical_key)
+                    .copied()
+                    .unwrap_or(0),
+                "calls with these arguments in this turn",
+            ),
+            (
+                "max_calls_per_session",
+                quota.max_calls_per_session,
+                self.session_calls.get(name).copied().unwrap_or(0),
+                "calls in this session",
+            ),
+            (
+                "max_output_bytes",
+                quota.max_output_bytes,
+                self.output_bytes.get(name).copied().unwrap_or(0),
+                "bytes of output in this session",
+            ),
+        ];
+        for (limit, max, used, what) in counts {
+            if let Some(max) = max
+                && used >= max
+            {
+                return Err(quota_exceeded(name, limit, max, what));
+            }
+        }
+        *self.turn_calls.entry(name.to_string()).or_default() += 1;
+        *self.identical_calls.entry(identical_key).or_default() += 1;
+        *self.session_calls.entry(name.to_string()).or_default() += 1;
+        Ok(())
+    }
+
+    /// Adds the output of a finished call of `name`.
+    pub(crate) fn record_output(&mut self, name: &str, bytes: usize) {
+        *self.output_bytes.entry(name.to_string()).or_default() += bytes as u64;
+    }
+}
+
+fn quota_exceeded(name: &str, limit: &str, max: u64, what: &str) -> String {
+    json!({
+        "error": "quota_exceeded",
+        "tool": name,
+        "limit": limit,
+        "max": max,
+        "message": format!(
+            "`{name}` is limited to {max} {what}; do not call it again, and continue with what you have or another approach"
+        ),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    fn limit_of(result: Result<(), String>) -> String {
+        let error: Value = serde_json::from_str(&result.unwrap_err()).unwrap();
+        assert_eq!("quota_exceeded", error["error"]);
+        error["limit"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn exact_names_take_precedence_over_patterns() {
+        let quotas = BTreeMap::from([
+            ("*".to_string(), ToolQuota::default()),
+            (
+                "github__*".to_string(),
+                ToolQuota {
+                    max_calls_per_turn: Some(1),
+                    ..Default::default()
+                },
+            ),
+            (
+                "github__search".to_string(),
+                ToolQuota {
+                    max_calls_per_turn: Some(2),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        assert_eq!(
+            Some(1),
+            setting_for(&quotas, "github__get_issue").and_then(|q| q.max_calls_per_turn)
+        );
+        assert_eq!(
+            Some(2),
+            setting_for(&quotas, "github__search").and_then(|q| q.max_calls_per_turn)
+        );
+        assert_eq!(
+            None,
+            setting_for(&quotas, "shell").and_then(|q| q.max_calls_per_turn)
+        );
+    }
+
+    #[test]
+    fn counts_calls_per_turn_and_session() {
+        let quotas = BTreeMap::from([(
+            "web_fetch".to_string(),
+            ToolQuota {
+                max_calls_per_turn: Some(2),
+                max_identical_calls_per_turn: Some(1),
+                max_calls_per_session: Some(3),
+                max_output_bytes: None,
+            },
+        )]);
+        let mut usage = ToolUsage::default();
+
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "1", "web_fetch", "a"));
+        assert_eq!(
+            "max_identical_calls_per_turn",
+            limit_of(usage.begin_call(&quotas, "1", "web_fetch", "a"))
+        );
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "1", "web_fetch", "b"));
+        assert_eq!(
+            "max_calls_per_turn",
+            limit_of(usage.begin_call(&quotas, "1", "web_fetch", "c"))
+        );
+        // Other tools are not limited.
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "1", "shell", "a"));
+
+        // A new turn resets the turn's counters but not the session's.
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "2", "web_fetch", "a"));
+        assert_eq!(
+            "max_calls_per_session",
+            limit_of(usage.begin_call(&quotas, "2", "web_fetch", "d"))
+        );
+    }
+
+    #[test]
+    fn limits_cumulative_output() {
+        let quotas = BTreeMap::from([(
+            "*".to_string(),
+            ToolQuota {
+                max_output_bytes: Some(100),
+                ..Default::default()
+            },
+        )]);
+        let mut usage = ToolUsage::default();
+
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "1", "read_file", "a"));
+        usage.record_output("read_file", 150);
+        assert_eq!(
+            "max_output_bytes",
+            limit_of(usage.begin_call(&quotas, "1", "read_file", "b"))
+        );
+        // Each tool has its own allowance.
+        assert_eq!(Ok(()), usage.begin_call(&quotas, "1", "git", "a"));
+    }
+}
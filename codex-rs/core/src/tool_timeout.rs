@@ -0,0 +1,118 @@
+//! Per-tool time limits from `[tool_timeouts]`, keyed by tool name or pattern
+//! like `[tool_quotas]`. A call may ask for its own limit with a top-level
+//! `timeout_ms` argument. A call that runs out of time is dropped, which
+//! kills the processes it spawned, and the model gets a structured result
+//! instead of the tool's output.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::tool_quota::setting_for;
+
+/// Tools that enforce `timeout_ms` themselves, killing the command and
+/// returning the output it produced. For them a configured limit only
+/// becomes the default `timeout_ms`.
+const SELF_TIMED_TOOLS: &[&str] = &["shell", "container.exec", "local_shell"];
+
+/// Tools whose `timeout_ms` argument means how long to wait for output, not
+/// a limit on the call.
+const OUTPUT_WAIT_TOOLS: &[&str] = &["unified_exec"];
+
+/// The configured limit of `name`.
+pub(crate) fn configured_timeout(timeouts: &BTreeMap<String, u64>, name: &str) -> Option<Duration> {
+    setting_for(timeouts, name).map(|secs| Duration::from_secs(*secs))
+}
+
+/// The limit to run a call of `name` under: the call's own `timeout_ms`, or
+/// else the configured one. `None` for tools that time themselves.
+pub(crate) fn call_timeout(
+    timeouts: &BTreeMap<String, u64>,
+    name: &str,
+    arguments: &str,
+) -> Option<Duration> {
+    if SELF_TIMED_TOOLS.contains(&name) {
+        return None;
+    }
+    let requested = if OUTPUT_WAIT_TOOLS.contains(&name) {
+        None
+    } else {
+        requested_timeout(arguments)
+    };
+    requested.or_else(|| configured_timeout(timeouts, name))
+}
+
+/// The default `timeout_ms` of a self-timed tool.
+pub(crate) fn default_exec_timeout_ms(timeouts: &BTreeMap<String, u64>, name: &str) -> Option<u64> {
+    if !SELF_TIMED_TOOLS.contains(&name) {
+        return None;
+    }
+    configured_timeout(timeouts, name).map(|limit| limit.as_millis() as u64)
+}
+
+fn requested_timeout(arguments: &str) -> Option<Duration> {
+    let arguments: serde_json::Value = serde_json::from_str(arguments).ok()?;
+    arguments
+        .get("timeout_ms")?
+        .as_u64()
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// The result of a call of `name` that ran out of time, listing what was
+/// cleaned up after it.
+pub(crate) fn timed_out(name: &str, limit: Duration, cleaned_up: &[String]) -> String {
+    json!({
+        "error": "timed_out",
+        "tool": name,
+        "timeout_ms": limit.as_millis() as u64,
+        "cleaned_up": cleaned_up,
+        "message": format!(
+            "`{name}` did not finish within {}s and was stopped; retry with a larger `timeout_ms` only if the work can take that long",
+            limit.as_secs_f64()
+        ),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn calls_override_configured_limits() {
+        let timeouts = BTreeMap::from([
+            ("*".to_string(), 60),
+            ("github__*".to_string(), 120),
+            ("shell".to_string(), 600),
+        ]);
+        assert_eq!(
+            Some(Duration::from_secs(120)),
+            call_timeout(&timeouts, "github__search", "{}")
+        );
+        assert_eq!(
+            Some(Duration::from_millis(1500)),
+            call_timeout(
+                &timeouts,
+                "web_fetch",
+                r#"{"url": "x", "timeout_ms": 1500}"#
+            )
+        );
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            call_timeout(&timeouts, "web_fetch", r#"{"timeout_ms": 0}"#)
+        );
+        assert_eq!(None, call_timeout(&BTreeMap::new(), "web_fetch", "{}"));
+        assert_eq!(
+            Some(Duration::from_secs(60)),
+            call_timeout(&timeouts, "unified_exec", r#"{"timeout_ms": 1000}"#)
+        );
+
+        // Shell commands keep timing themselves.
+        assert_eq!(None, call_timeout(&timeouts, "shell", "{}"));
+        assert_eq!(Some(600_000), default_exec_timeout_ms(&timeouts, "shell"));
+        assert_eq!(None, default_exec_timeout_ms(&timeouts, "web_fetch"));
+    }
+}
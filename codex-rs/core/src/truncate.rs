@@ -1,6 +1,19 @@
 //! Utilities for truncating large chunks of output while preserving a prefix
 //! and suffix on UTF-8 boundaries.
 
+/// The longest prefix of `text` that fits in `max` bytes without splitting a
+/// character, and whether anything was cut.
+pub(crate) fn truncate_to_bytes(text: &str, max: usize) -> (&str, bool) {
+    if text.len() <= max {
+        return (text, false);
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
 /// Truncate the middle of a UTF-8 string to at most `max_bytes` bytes,
 /// preserving the beginning and the end. Returns the possibly truncated
 /// string and `Some(original_token_count)` (estimated at 4 bytes/token)
@@ -106,6 +119,14 @@ pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>
 #[cfg(test)]
 mod tests {
     use super::truncate_middle;
+    use super::truncate_to_bytes;
+
+    #[test]
+    fn truncate_to_bytes_keeps_whole_characters() {
+        assert_eq!(truncate_to_bytes("héllo", 10), ("héllo", false));
+        assert_eq!(truncate_to_bytes("héllo", 2), ("h", true));
+        assert_eq!(truncate_to_bytes("héllo", 3), ("hé", true));
+    }
 
     #[test]
     fn truncate_middle_no_newlines_fallback() {
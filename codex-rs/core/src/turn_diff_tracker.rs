@@ -127,6 +127,23 @@ impl TurnDiffTracker {
         }
     }
 
+    /// Every path touched by the tracked patches with its contents before the
+    /// first patch, or `None` when it did not exist yet.
+    pub(crate) fn original_contents(&self) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+        let mut originals = Vec::new();
+        for (internal, baseline) in &self.baseline_file_info {
+            let content = (baseline.oid != ZERO_OID).then(|| baseline.content.clone());
+            originals.push((baseline.path.clone(), content));
+            // A move leaves a file at a path that was not there before.
+            if let Some(current) = self.temp_name_to_current_path.get(internal)
+                && *current != baseline.path
+            {
+                originals.push((current.clone(), None));
+            }
+        }
+        originals
+    }
+
     fn get_path_for_internal(&self, internal: &str) -> Option<PathBuf> {
         self.temp_name_to_current_path
             .get(internal)
@@ -0,0 +1,136 @@
+//! Per-turn latency, token and cost accounting, reported with
+//! `EventMsg::TurnStats` when a turn finishes and on `Op::GetTurnStats`.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::codex::TurnContext;
+use crate::config_types::ModelPricing;
+use crate::openai_model_info::get_model_pricing;
+use crate::protocol::TokenUsage;
+use crate::protocol::TurnStats;
+
+/// Tool calls made while handling one model response.
+#[derive(Debug, Default)]
+pub(crate) struct ToolTime {
+    calls: u32,
+    elapsed: Duration,
+}
+
+impl ToolTime {
+    pub(crate) fn record(&mut self, calls: u32, elapsed: Duration) {
+        self.calls += calls;
+        self.elapsed += elapsed;
+    }
+}
+
+/// Accumulates the [`TurnStats`] of a running turn.
+pub(crate) struct TurnStatsRecorder {
+    stats: TurnStats,
+    started: Instant,
+    model_latency: Duration,
+    tool_time: Duration,
+    pricing: Option<ModelPricing>,
+}
+
+impl TurnStatsRecorder {
+    pub(crate) fn start(turn_id: &str, turn_context: &TurnContext) -> Self {
+        let model = turn_context.client.get_model();
+        let pricing = turn_context
+            .client
+            .get_config()
+            .model_pricing
+            .get(&model)
+            .copied()
+            .or_else(|| get_model_pricing(&turn_context.client.get_model_family()));
+        Self {
+            stats: TurnStats {
+                turn_id: turn_id.to_string(),
+                model,
+                ..Default::default()
+            },
+            started: Instant::now(),
+            model_latency: Duration::ZERO,
+            tool_time: Duration::ZERO,
+            pricing,
+        }
+    }
+
+    /// Account for one model request that took `elapsed` in total, of which
+    /// `tools` was spent running the tool calls it asked for.
+    pub(crate) fn record_model_request(
+        &mut self,
+        elapsed: Duration,
+        tools: &ToolTime,
+        token_usage: Option<&TokenUsage>,
+    ) {
+        self.stats.model_requests += 1;
+        self.model_latency += elapsed.saturating_sub(tools.elapsed);
+        self.tool_time += tools.elapsed;
+        self.stats.tool_calls += tools.calls;
+        if let Some(token_usage) = token_usage {
+            self.stats.token_usage.add_assign(token_usage);
+        }
+    }
+
+    /// The stats of the turn so far.
+    pub(crate) fn current(&self) -> TurnStats {
+        let mut stats = self.stats.clone();
+        stats.duration_ms = millis(self.started.elapsed());
+        stats.model_latency_ms = millis(self.model_latency);
+        stats.tool_time_ms = millis(self.tool_time);
+        stats.estimated_cost_usd = self
+            .pricing
+            .map(|pricing| estimate_cost(&pricing, &stats.token_usage));
+        stats
+    }
+
+    pub(crate) fn finish(self) -> TurnStats {
+        self.current()
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Cost of `usage` in US dollars.
+fn estimate_cost(pricing: &ModelPricing, usage: &TokenUsage) -> f64 {
+    let cached_price = pricing
+        .cached_input_per_million
+        .unwrap_or(pricing.input_per_million);
+    let dollars = usage.non_cached_input() as f64 * pricing.input_per_million
+        + usage.cached_input() as f64 * cached_price
+        + usage.output_tokens as f64 * pricing.output_per_million;
+    dollars / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn estimate_cost_prices_cached_input_separately() {
+        let pricing = ModelPricing {
+            input_per_million: 2.0,
+            cached_input_per_million: Some(0.5),
+            output_per_million: 8.0,
+        };
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 400_000,
+            output_tokens: 250_000,
+            reasoning_output_tokens: 100_000,
+            total_tokens: 1_250_000,
+        };
+        // 600k uncached at $2 + 400k cached at $0.5 + 250k output at $8.
+        assert_eq!(3.4, estimate_cost(&pricing, &usage));
+
+        let without_cache_price = ModelPricing {
+            cached_input_per_million: None,
+            ..pricing
+        };
+        assert_eq!(4.0, estimate_cost(&without_cache_price, &usage));
+    }
+}
@@ -0,0 +1,175 @@
+//! Turn-level undo.
+//!
+//! At the end of every turn that changed files, the contents those files had
+//! before the turn are kept so `Op::UndoTurn` can put them back. Files edited
+//! with `apply_patch` are known from the turn's [`TurnDiffTracker`]; writes
+//! made by shell commands are found by comparing the repository against a
+//! snapshot taken when the turn started, so they are only caught inside a git
+//! repository.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tracing::warn;
+
+use crate::checkpoint;
+use crate::checkpoint::WorktreeSnapshot;
+use crate::turn_diff_tracker::TurnDiffTracker;
+
+/// Number of turns that can be undone, most recent first.
+pub(crate) const MAX_UNDO_TURNS: usize = 20;
+
+/// Files written during one turn and what they contained before it.
+#[derive(Debug, Default)]
+pub(crate) struct TurnFileLog {
+    /// Id of the submission that started the turn.
+    pub(crate) turn_id: String,
+    /// `None` for files the turn created.
+    originals: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+/// Files put back by [`TurnFileLog::undo`].
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct UndoneFiles {
+    pub(crate) restored: Vec<PathBuf>,
+    pub(crate) removed: Vec<PathBuf>,
+}
+
+/// Snapshot of the repository containing `cwd` taken before a turn runs, or
+/// `None` outside a repository or when the snapshot fails.
+pub(crate) async fn snapshot_before_turn(cwd: &Path) -> Option<WorktreeSnapshot> {
+    match checkpoint::snapshot_worktree(cwd).await {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!("failed to snapshot working tree for undo: {e}");
+            None
+        }
+    }
+}
+
+impl TurnFileLog {
+    /// Collect the files changed by a finished turn.
+    pub(crate) async fn collect(
+        turn_id: &str,
+        tracker: &TurnDiffTracker,
+        before: Option<&WorktreeSnapshot>,
+    ) -> Self {
+        let mut log = Self {
+            turn_id: turn_id.to_string(),
+            ..Default::default()
+        };
+        // The snapshot predates every write of the turn, so it wins over the
+        // patch baselines, which are taken when a file is first patched.
+        if let Some(before) = before {
+            match checkpoint::changes_since(before).await {
+                Ok(changes) => changes
+                    .into_iter()
+                    .for_each(|(path, original)| log.record(path, original)),
+                Err(e) => warn!("failed to find files changed by the turn: {e}"),
+            }
+        }
+        for (path, original) in tracker.original_contents() {
+            log.record(path, original);
+        }
+        log
+    }
+
+    fn record(&mut self, path: PathBuf, original: Option<Vec<u8>>) {
+        self.originals.entry(path).or_insert(original);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    /// The files the turn changed and what they contained before it.
+    pub(crate) fn originals(&self) -> impl Iterator<Item = (&PathBuf, &Option<Vec<u8>>)> {
+        self.originals.iter()
+    }
+
+    /// Write back the original contents, removing files the turn created.
+    pub(crate) async fn undo(self) -> io::Result<UndoneFiles> {
+        let mut undone = UndoneFiles::default();
+        for (path, original) in self.originals {
+            match original {
+                Some(contents) => {
+                    if let Some(parent) = path.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(&path, contents).await?;
+                    undone.restored.push(path);
+                }
+                None => match tokio::fs::remove_file(&path).await {
+                    Ok(()) => undone.removed.push(path),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                },
+            }
+        }
+        Ok(undone)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::FileChange;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn undo_restores_patched_files_and_removes_created_ones() {
+        let temp = TempDir::new().expect("tempdir");
+        let edited = temp.path().join("edited.txt");
+        let deleted = temp.path().join("deleted.txt");
+        let created = temp.path().join("created.txt");
+        std::fs::write(&edited, "before\n").expect("write");
+        std::fs::write(&deleted, "keep me\n").expect("write");
+
+        let mut tracker = TurnDiffTracker::new();
+        tracker.on_patch_begin(&HashMap::from([
+            (
+                edited.clone(),
+                FileChange::Update {
+                    unified_diff: String::new(),
+                    move_path: None,
+                },
+            ),
+            (
+                deleted.clone(),
+                FileChange::Delete {
+                    content: "keep me\n".to_string(),
+                },
+            ),
+            (
+                created.clone(),
+                FileChange::Add {
+                    content: "new\n".to_string(),
+                },
+            ),
+        ]));
+        std::fs::write(&edited, "after\n").expect("write");
+        std::fs::remove_file(&deleted).expect("remove");
+        std::fs::write(&created, "new\n").expect("write");
+
+        let log = TurnFileLog::collect("turn-1", &tracker, None).await;
+        let undone = log.undo().await.expect("undo");
+
+        assert_eq!(
+            UndoneFiles {
+                restored: vec![deleted.clone(), edited.clone()],
+                removed: vec![created.clone()],
+            },
+            undone
+        );
+        assert_eq!("before\n", std::fs::read_to_string(&edited).expect("read"));
+        assert_eq!(
+            "keep me\n",
+            std::fs::read_to_string(&deleted).expect("read")
+        );
+        assert!(!created.exists());
+    }
+}
@@ -296,6 +296,13 @@ impl UnifiedExecSessionManager {
             })
         }
     }
+
+    /// Close every open session, killing its process. Returns how many were
+    /// closed.
+    pub(crate) async fn close_all(&self) -> usize {
+        let sessions = std::mem::take(&mut *self.sessions.lock().await);
+        sessions.len()
+    }
 }
 
 async fn create_unified_exec_session(
@@ -652,4 +659,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn close_all_drops_open_sessions() -> Result<(), UnifiedExecError> {
+        skip_if_sandbox!(Ok(()));
+
+        let manager = UnifiedExecSessionManager::default();
+        let open_shell = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: None,
+                input_chunks: &["bash".to_string(), "-i".to_string()],
+                timeout_ms: Some(2_500),
+            })
+            .await?;
+        let session_id = open_shell.session_id.expect("expected session_id");
+
+        assert_eq!(manager.close_all().await, 1);
+        assert_eq!(manager.close_all().await, 0);
+
+        let err = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: Some(session_id),
+                input_chunks: &[],
+                timeout_ms: Some(100),
+            })
+            .await
+            .expect_err("expected unknown session error");
+        assert!(matches!(err, UnifiedExecError::UnknownSessionId { .. }));
+
+        Ok(())
+    }
 }
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use rand::Rng;
@@ -11,3 +12,29 @@ pub(crate) fn backoff(attempt: u64) -> Duration {
     let jitter = rand::rng().random_range(0.9..1.1);
     Duration::from_millis((base as f64 * jitter) as u64)
 }
+
+/// Lowercase hexadecimal encoding of `bytes`.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn invalid_data(message: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+/// The first of `candidates` found on `PATH`.
+pub(crate) fn find_executable(candidates: &[&str]) -> Option<PathBuf> {
+    candidates.iter().find_map(|name| which::which(name).ok())
+}
+
+/// `command` quoted the way a shell would read it, for display.
+pub(crate) fn join_command(command: &[String]) -> String {
+    shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "))
+}
+
+/// For `#[serde(skip_serializing_if = "is_zero")]` on counts; serde passes the
+/// field by reference.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub(crate) fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
@@ -0,0 +1,208 @@
+//! Loading of the local images the `view_image` tool attaches. Images that
+//! exceed the `[view_image]` limits are downscaled and recompressed, so a 5K
+//! screenshot costs the model a readable image instead of the turn's budget.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use base64::Engine;
+use image::DynamicImage;
+use image::GenericImageView;
+use image::ImageFormat;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+
+use crate::config_types::ViewImage;
+
+/// Formats the model APIs accept as they are.
+const PASSTHROUGH_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+/// JPEG qualities tried, best first, when a PNG is over the byte limit.
+const JPEG_QUALITIES: [u8; 4] = [85, 70, 55, 40];
+/// Smallest longest side an image is shrunk to before giving up.
+const MIN_DIMENSION: u32 = 256;
+
+/// An image ready to be attached to the conversation.
+#[derive(Debug)]
+pub(crate) struct PreparedImage {
+    /// `data:` URL with the encoded image.
+    pub(crate) image_url: String,
+    /// What was attached, for the model, e.g. `3024x1964 PNG, sent as
+    /// 2048x1330 JPEG (412 KiB)`.
+    pub(crate) summary: String,
+}
+
+/// Reads the image at `path` and encodes it within `limits`.
+pub(crate) fn prepare_image(path: &Path, limits: &ViewImage) -> Result<PreparedImage, String> {
+    let bytes =
+        std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    let format = image::guess_format(&bytes)
+        .map_err(|_| format!("{} is not an image in a supported format", path.display()))?;
+    let image = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| format!("failed to decode {}: {e}", path.display()))?;
+    let (width, height) = image.dimensions();
+    let original = format!("{width}x{height} {}", format_name(format));
+    let max_bytes = usize::try_from(limits.max_bytes).unwrap_or(usize::MAX);
+    let max_dimension = limits.max_dimension.max(1);
+
+    if width.max(height) <= max_dimension
+        && bytes.len() <= max_bytes
+        && PASSTHROUGH_FORMATS.contains(&format)
+    {
+        return Ok(PreparedImage {
+            image_url: data_url(format, &bytes),
+            summary: format!("{original} ({})", format_size(bytes.len())),
+        });
+    }
+
+    let mut bound = max_dimension.min(width.max(height));
+    loop {
+        let scaled = if width.max(height) > bound {
+            image.resize(bound, bound, FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        if let Some((format, encoded)) = encode_within(&scaled, max_bytes)? {
+            let (width, height) = scaled.dimensions();
+            return Ok(PreparedImage {
+                image_url: data_url(format, &encoded),
+                summary: format!(
+                    "{original}, sent as {width}x{height} {} ({})",
+                    format_name(format),
+                    format_size(encoded.len())
+                ),
+            });
+        }
+        if bound <= MIN_DIMENSION {
+            return Err(format!(
+                "{} cannot be compressed below `view_image.max_bytes` ({} bytes)",
+                path.display(),
+                limits.max_bytes
+            ));
+        }
+        bound = (bound * 3 / 4).max(MIN_DIMENSION);
+    }
+}
+
+/// Encodes `image` as PNG, which keeps text in screenshots sharp, or else as
+/// JPEG at the best quality that fits in `max_bytes`.
+fn encode_within(
+    image: &DynamicImage,
+    max_bytes: usize,
+) -> Result<Option<(ImageFormat, Vec<u8>)>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("failed to encode the image: {e}"))?;
+    if png.len() <= max_bytes {
+        return Ok(Some((ImageFormat::Png, png)));
+    }
+    // JPEG has no alpha channel.
+    let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+    for quality in JPEG_QUALITIES {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, quality)
+            .encode_image(&rgb)
+            .map_err(|e| format!("failed to encode the image: {e}"))?;
+        if jpeg.len() <= max_bytes {
+            return Ok(Some((ImageFormat::Jpeg, jpeg)));
+        }
+    }
+    Ok(None)
+}
+
+fn data_url(format: ImageFormat, bytes: &[u8]) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    format!("data:{};base64,{encoded}", format.to_mime_type())
+}
+
+fn format_name(format: ImageFormat) -> String {
+    format!("{format:?}").to_uppercase()
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else {
+        format!("{} KiB", bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageBuffer;
+    use image::Rgb;
+    use pretty_assertions::assert_eq;
+
+    /// A noisy image, which compresses poorly.
+    fn write_image(path: &Path, width: u32, height: u32) {
+        let mut state = 0x2545_f491_u32;
+        let image = ImageBuffer::from_fn(width, height, |_, _| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let [r, g, b, _] = state.to_le_bytes();
+            Rgb([r, g, b])
+        });
+        image.save(path).unwrap();
+    }
+
+    fn decode(image_url: &str) -> (String, DynamicImage) {
+        let (header, data) = image_url.split_once(";base64,").unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .unwrap();
+        (
+            header.trim_start_matches("data:").to_string(),
+            image::load_from_memory(&bytes).unwrap(),
+        )
+    }
+
+    #[test]
+    fn small_images_are_attached_as_they_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mock.png");
+        write_image(&path, 64, 32);
+
+        let prepared = prepare_image(&path, &ViewImage::default()).unwrap();
+        let (mime, image) = decode(&prepared.image_url);
+        assert_eq!("image/png", mime);
+        assert_eq!((64, 32), image.dimensions());
+        assert!(prepared.summary.starts_with("64x32 PNG ("), "{prepared:?}");
+    }
+
+    #[test]
+    fn large_images_are_downscaled_and_compressed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("screenshot.png");
+        write_image(&path, 1200, 600);
+        let limits = ViewImage {
+            max_dimension: 800,
+            max_bytes: 200 * 1024,
+        };
+
+        let prepared = prepare_image(&path, &limits).unwrap();
+        let (mime, image) = decode(&prepared.image_url);
+        assert_eq!("image/jpeg", mime);
+        let (width, height) = image.dimensions();
+        assert!(width <= 800 && height <= 400, "{width}x{height}");
+        assert!(
+            prepared.summary.starts_with("1200x600 PNG, sent as "),
+            "{prepared:?}"
+        );
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.png");
+        std::fs::write(&path, "not an image").unwrap();
+        let error = prepare_image(&path, &ViewImage::default()).unwrap_err();
+        assert!(error.contains("is not an image"), "{error}");
+    }
+}
@@ -0,0 +1,585 @@
+//! The `web_fetch` tool, which fetches a URL and returns its readable text so
+//! the model can read documentation without shelling out to `curl`.
+//!
+//! Requests are only made when the sandbox policy allows network access, and
+//! by default URLs that the site's `robots.txt` disallows are refused. HTML is
+//! reduced to its text, and both the download and the text returned to the
+//! model are capped by the `[web_fetch]` settings.
+
+use std::borrow::Cow;
+use std::time::Duration;
+
+use reqwest::Client;
+use reqwest::Url;
+use reqwest::header::CONTENT_TYPE;
+use serde::Deserialize;
+
+use crate::config_types::WebFetch;
+use crate::default_client::create_client;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::truncate::truncate_to_bytes;
+
+pub(crate) const WEB_FETCH_TOOL_NAME: &str = "web_fetch";
+
+/// Product token matched against `User-agent` lines of `robots.txt`.
+const ROBOTS_USER_AGENT: &str = "codex";
+
+/// Elements whose content is not part of the readable text.
+const SKIPPED_ELEMENTS: &[&str] = &["head", "noscript", "script", "style", "svg", "template"];
+
+/// Elements that start a new line in the readable text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+#[derive(Debug, Deserialize)]
+struct WebFetchArgs {
+    url: String,
+    #[serde(default)]
+    raw: bool,
+}
+
+pub(crate) async fn handle_web_fetch_tool(
+    config: &WebFetch,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: WebFetchArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    fetch(config, sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+async fn fetch(
+    config: &WebFetch,
+    sandbox_policy: &SandboxPolicy,
+    args: WebFetchArgs,
+) -> Result<String, String> {
+    if !sandbox_policy.has_full_network_access() {
+        return Err("network access is disabled by the sandbox policy".to_string());
+    }
+    let url = Url::parse(&args.url).map_err(|e| format!("invalid URL `{}`: {e}", args.url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "cannot fetch `{url}`: only http and https URLs are supported"
+        ));
+    }
+
+    let client = create_client();
+    let timeout = Duration::from_secs(config.timeout_sec);
+    if config.respect_robots_txt && !robots_txt_allows(&client, &url, timeout).await {
+        return Err(format!(
+            "fetching {url} is disallowed by the site's robots.txt"
+        ));
+    }
+
+    let mut response = client
+        .get(url.clone())
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| format!("failed to fetch {url}: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("{url} returned HTTP {status}"));
+    }
+    let mime = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if !is_text_mime(&mime) {
+        return Err(format!("{url} is `{mime}`, which is not text"));
+    }
+    let final_url = response.url().clone();
+
+    let mut body = Vec::new();
+    let mut download_truncated = false;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("failed to read {url}: {e}"))?
+    {
+        let remaining = config.max_download_bytes - body.len();
+        if chunk.len() > remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            download_truncated = true;
+            break;
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = String::from_utf8_lossy(&body);
+
+    let is_html = matches!(mime.as_str(), "text/html" | "application/xhtml+xml");
+    let (title, text) = if is_html && !args.raw {
+        (extract_title(&body), html_to_text(&body))
+    } else {
+        (None, body.into_owned())
+    };
+
+    let mut output = format!("URL: {final_url}\n");
+    if let Some(title) = title {
+        output.push_str(&format!("Title: {title}\n"));
+    }
+    output.push('\n');
+    let (text, output_truncated) = truncate_to_bytes(&text, config.max_output_bytes);
+    output.push_str(text);
+    if download_truncated {
+        output.push_str(&format!(
+            "\n\n[download stopped after {} bytes]",
+            config.max_download_bytes
+        ));
+    } else if output_truncated {
+        output.push_str(&format!(
+            "\n\n[output truncated to {} bytes]",
+            config.max_output_bytes
+        ));
+    }
+    Ok(output)
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.is_empty()
+        || mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-javascript"
+                | "application/yaml"
+                | "application/x-yaml"
+                | "application/toml"
+        )
+}
+
+/// Whether the `robots.txt` of `url`'s site lets us fetch it. A missing or
+/// unreadable `robots.txt` allows everything.
+async fn robots_txt_allows(client: &Client, url: &Url, timeout: Duration) -> bool {
+    let Ok(robots_url) = url.join("/robots.txt") else {
+        return true;
+    };
+    let response = match client.get(robots_url).timeout(timeout).send().await {
+        Ok(response) if response.status().is_success() => response,
+        _ => return true,
+    };
+    let Ok(robots) = response.text().await else {
+        return true;
+    };
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    robots_allows(&robots, &path)
+}
+
+/// The rules of one `User-agent` group of a `robots.txt` file.
+struct RobotsGroup {
+    agents: Vec<String>,
+    /// `(allow, pattern)` pairs; an empty pattern matches nothing.
+    rules: Vec<(bool, String)>,
+}
+
+/// Whether `robots` lets [`ROBOTS_USER_AGENT`] fetch `path`. Groups naming
+/// the agent take precedence over the `*` group. Within the rules that apply,
+/// the longest matching pattern wins, and `Allow` wins ties.
+fn robots_allows(robots: &str, path: &str) -> bool {
+    let mut groups: Vec<RobotsGroup> = Vec::new();
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => match groups.last_mut() {
+                Some(group) if group.rules.is_empty() => {
+                    group.agents.push(value.to_ascii_lowercase());
+                }
+                _ => groups.push(RobotsGroup {
+                    agents: vec![value.to_ascii_lowercase()],
+                    rules: Vec::new(),
+                }),
+            },
+            // An empty `Disallow:` allows everything but still ends the
+            // group's list of user agents.
+            key @ ("allow" | "disallow") => {
+                if let Some(group) = groups.last_mut() {
+                    group.rules.push((key == "allow", value.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let names = |group: &RobotsGroup, agent: &str| group.agents.iter().any(|a| a == agent);
+    let agent = if groups.iter().any(|group| names(group, ROBOTS_USER_AGENT)) {
+        ROBOTS_USER_AGENT
+    } else {
+        "*"
+    };
+    groups
+        .iter()
+        .filter(|group| names(group, agent))
+        .flat_map(|group| &group.rules)
+        .filter(|(_, pattern)| !pattern.is_empty() && robots_pattern_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// Match a `robots.txt` path pattern, where `*` matches any characters and a
+/// trailing `$` anchors the pattern at the end of the path.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = path.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i + 1 == parts.len() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+fn extract_title(html: &str) -> Option<String> {
+    let start = find_ignore_ascii_case(html, "<title")?;
+    let after_tag = &html[start..];
+    let content = &after_tag[after_tag.find('>')? + 1..];
+    let end = find_ignore_ascii_case(content, "</title").unwrap_or(content.len());
+    let title = decode_entities(&content[..end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then_some(title)
+}
+
+/// The readable text of an HTML document: markup, scripts and styles are
+/// dropped, block elements start new lines and list items become bullets.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut preformatted = 0usize;
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &rest[..start], preformatted > 0);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let is_tag = rest[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!');
+        let Some(end) = rest.find('>').filter(|_| is_tag) else {
+            push_text(&mut text, "<", preformatted > 0);
+            rest = &rest[1..];
+            continue;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !closing && !tag.ends_with('/') && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            rest = match find_ignore_ascii_case(rest, &format!("</{name}")) {
+                Some(close) => rest[close..]
+                    .find('>')
+                    .map_or("", |e| &rest[close + e + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        if name == "pre" {
+            preformatted = if closing {
+                preformatted.saturating_sub(1)
+            } else {
+                preformatted + 1
+            };
+        }
+        if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            while text.ends_with(' ') {
+                text.pop();
+            }
+            text.push('\n');
+            if name == "li" && !closing {
+                text.push_str("- ");
+            }
+        }
+    }
+    push_text(&mut text, rest, preformatted > 0);
+    collapse_blank_lines(&text)
+}
+
+/// Append the text between two tags, collapsing whitespace outside `<pre>`.
+fn push_text(text: &mut String, segment: &str, preformatted: bool) {
+    let segment = decode_entities(segment);
+    if preformatted {
+        text.push_str(&segment);
+        return;
+    }
+    let mut after_space = text.is_empty() || text.ends_with([' ', '\n']);
+    for c in segment.chars() {
+        if c.is_whitespace() {
+            if !after_space {
+                text.push(' ');
+                after_space = true;
+            }
+        } else {
+            text.push(c);
+            after_space = false;
+        }
+    }
+}
+
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..=end])?, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Decode a named or numeric character reference without its `&` and `;`.
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[test]
+    fn extracts_readable_text_from_html() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Getting
+  started &amp; more</title><style>body { color: red }</style></head>
+<body>
+  <nav><a href="/">Home</a></nav>
+  <script>alert("hi")</script>
+  <h1>Install</h1>
+  <p>Run the   installer,
+     then <b>restart</b>.<br/>Done &lt;3 &#8212; &#x41;.</p>
+  <!-- hidden -->
+  <ul><li>one</li><li>two</li></ul>
+  <pre>fn main() {
+    println!("a < b");
+}</pre>
+</body></html>"#;
+
+        assert_eq!(
+            Some("Getting started & more".to_string()),
+            extract_title(html)
+        );
+        assert_eq!(
+            "Home\n\nInstall\n\nRun the installer, then restart.\nDone <3 \u{2014} A.\n\n- one\n\n- two\n\nfn main() {\n    println!(\"a < b\");\n}",
+            html_to_text(html)
+        );
+    }
+
+    #[test]
+    fn robots_rules_for_codex_take_precedence() {
+        let robots = "\
+User-agent: *
+Disallow: /private
+Allow: /private/docs
+Disallow: /*.pdf$
+
+User-agent: other
+User-agent: codex # us
+Disallow: /codex-only
+";
+        assert!(!robots_allows(robots, "/codex-only/page"));
+        assert!(robots_allows(robots, "/private/page"));
+
+        let wildcard_only =
+            "User-agent: *\nDisallow: /private\nAllow: /private/docs\nDisallow: /*.pdf$\n";
+        assert!(!robots_allows(wildcard_only, "/private/page"));
+        assert!(robots_allows(wildcard_only, "/private/docs/page"));
+        assert!(!robots_allows(wildcard_only, "/files/manual.pdf"));
+        assert!(robots_allows(wildcard_only, "/files/manual.pdf?download=1"));
+        assert!(robots_allows(wildcard_only, "/public"));
+        assert!(robots_allows("User-agent: *\nDisallow:\n", "/anything"));
+    }
+
+    #[tokio::test]
+    async fn fetches_text_and_respects_robots_and_network_policy() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/robots.txt"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string("User-agent: *\nDisallow: /private\n"),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/docs"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><head><title>Docs</title></head><body><p>Hello docs</p></body></html>",
+                "text/html; charset=utf-8",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0u8; 4], "image/png"))
+            .mount(&server)
+            .await;
+
+        let config = WebFetch {
+            max_output_bytes: 4,
+            ..Default::default()
+        };
+        let fetch = |url: String, policy: SandboxPolicy| {
+            let config = config.clone();
+            async move {
+                let arguments = serde_json::json!({ "url": url }).to_string();
+                handle_web_fetch_tool(&config, &policy, &arguments)
+                    .await
+                    .map_err(|FunctionCallError::RespondToModel(message)| message)
+            }
+        };
+        let uri = server.uri();
+
+        assert_eq!(
+            Ok(format!(
+                "URL: {uri}/docs\nTitle: Docs\n\nHell\n\n[output truncated to 4 bytes]"
+            )),
+            fetch(format!("{uri}/docs"), SandboxPolicy::DangerFullAccess).await
+        );
+        assert_eq!(
+            Err(format!(
+                "fetching {uri}/private/notes is disallowed by the site's robots.txt"
+            )),
+            fetch(
+                format!("{uri}/private/notes"),
+                SandboxPolicy::DangerFullAccess
+            )
+            .await
+        );
+        assert_eq!(
+            Err(format!("{uri}/logo.png is `image/png`, which is not text")),
+            fetch(format!("{uri}/logo.png"), SandboxPolicy::DangerFullAccess).await
+        );
+        assert_eq!(
+            Err("network access is disabled by the sandbox policy".to_string()),
+            fetch(format!("{uri}/docs"), SandboxPolicy::ReadOnly).await
+        );
+    }
+}
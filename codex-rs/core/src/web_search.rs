@@ -0,0 +1,457 @@
+//! The `web_search` tool, which queries the provider configured in the
+//! `[web_search]` table and returns ranked results with snippets. When a
+//! provider is configured the tool takes the place of the Responses API's
+//! hosted web search, so it also works with other model providers.
+
+use std::time::Duration;
+
+use reqwest::Client;
+use reqwest::RequestBuilder;
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+
+use crate::config_types::WebSearch;
+use crate::config_types::WebSearchProvider;
+use crate::default_client::create_client;
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+use crate::web_fetch::html_to_text;
+
+pub(crate) const WEB_SEARCH_TOOL_NAME: &str = "web_search";
+
+const BING_ENDPOINT: &str = "https://api.bing.microsoft.com/v7.0/search";
+const BRAVE_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+#[derive(Debug, Deserialize)]
+struct WebSearchArgs {
+    query: String,
+    #[serde(default)]
+    count: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct SearchResult {
+    title: String,
+    url: String,
+    snippet: String,
+}
+
+#[derive(Deserialize)]
+struct BingResponse {
+    #[serde(rename = "webPages")]
+    web_pages: Option<BingWebPages>,
+}
+
+#[derive(Deserialize)]
+struct BingWebPages {
+    value: Vec<BingPage>,
+}
+
+#[derive(Deserialize)]
+struct BingPage {
+    name: String,
+    url: String,
+    #[serde(default)]
+    snippet: String,
+}
+
+#[derive(Deserialize)]
+struct BraveResponse {
+    web: Option<BraveWeb>,
+}
+
+#[derive(Deserialize)]
+struct BraveWeb {
+    results: Vec<BravePage>,
+}
+
+#[derive(Deserialize)]
+struct BravePage {
+    title: String,
+    url: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct SearxngResponse {
+    results: Vec<SearxngPage>,
+}
+
+#[derive(Deserialize)]
+struct SearxngPage {
+    title: String,
+    url: String,
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct CustomResponse {
+    results: Vec<CustomPage>,
+}
+
+#[derive(Deserialize)]
+struct CustomPage {
+    title: String,
+    url: String,
+    #[serde(default)]
+    snippet: String,
+}
+
+pub(crate) async fn handle_web_search_tool(
+    config: &WebSearch,
+    sandbox_policy: &SandboxPolicy,
+    arguments: &str,
+) -> Result<String, FunctionCallError> {
+    let args: WebSearchArgs = serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })?;
+    search(config, sandbox_policy, args)
+        .await
+        .map_err(FunctionCallError::RespondToModel)
+}
+
+async fn search(
+    config: &WebSearch,
+    sandbox_policy: &SandboxPolicy,
+    args: WebSearchArgs,
+) -> Result<String, String> {
+    if !sandbox_policy.has_full_network_access() {
+        return Err("network access is disabled by the sandbox policy".to_string());
+    }
+    let Some(provider) = config.provider else {
+        return Err("no web search provider is configured".to_string());
+    };
+    let query = args.query.trim();
+    if query.is_empty() {
+        return Err("query must not be empty".to_string());
+    }
+    let max_results = config.max_results.max(1);
+    let count = args.count.unwrap_or(max_results).clamp(1, max_results);
+
+    let response = build_request(&create_client(), provider, config, query, count)?
+        .timeout(Duration::from_secs(config.timeout_sec))
+        .send()
+        .await
+        .map_err(|e| format!("web search failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("web search failed: HTTP {status}"));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("web search failed: {e}"))?;
+    let mut results = parse_results(provider, &body)
+        .map_err(|e| format!("web search returned an unexpected response: {e}"))?;
+    results.truncate(count);
+    Ok(format_results(query, &results))
+}
+
+fn build_request(
+    client: &Client,
+    provider: WebSearchProvider,
+    config: &WebSearch,
+    query: &str,
+    count: usize,
+) -> Result<RequestBuilder, String> {
+    let count = count.to_string();
+    let base_url = config.base_url.as_deref();
+    let request = match provider {
+        WebSearchProvider::Bing => client
+            .get(base_url.unwrap_or(BING_ENDPOINT))
+            .query(&[("q", query), ("count", &count)])
+            .header(
+                "Ocp-Apim-Subscription-Key",
+                read_api_key(
+                    config
+                        .api_key_env
+                        .as_deref()
+                        .unwrap_or("BING_SEARCH_API_KEY"),
+                )?,
+            ),
+        WebSearchProvider::Brave => client
+            .get(base_url.unwrap_or(BRAVE_ENDPOINT))
+            .query(&[("q", query), ("count", &count)])
+            .header(
+                "X-Subscription-Token",
+                read_api_key(
+                    config
+                        .api_key_env
+                        .as_deref()
+                        .unwrap_or("BRAVE_SEARCH_API_KEY"),
+                )?,
+            )
+            .header(ACCEPT, "application/json"),
+        WebSearchProvider::Searxng | WebSearchProvider::Custom => {
+            let Some(base_url) = base_url else {
+                return Err("web_search.base_url is not configured".to_string());
+            };
+            let request = if provider == WebSearchProvider::Searxng {
+                client
+                    .get(format!("{}/search", base_url.trim_end_matches('/')))
+                    .query(&[("q", query), ("format", "json")])
+            } else {
+                client
+                    .get(base_url)
+                    .query(&[("q", query), ("count", &count)])
+            };
+            match config.api_key_env.as_deref() {
+                Some(var) => request.bearer_auth(read_api_key(var)?),
+                None => request,
+            }
+        }
+    };
+    Ok(request)
+}
+
+fn read_api_key(var: &str) -> Result<String, String> {
+    match std::env::var(var) {
+        Ok(api_key) if !api_key.is_empty() => Ok(api_key),
+        _ => Err(format!(
+            "web search is not available: environment variable `{var}` is not set"
+        )),
+    }
+}
+
+fn parse_results(
+    provider: WebSearchProvider,
+    body: &str,
+) -> Result<Vec<SearchResult>, serde_json::Error> {
+    let results: Vec<(String, String, String)> = match provider {
+        WebSearchProvider::Bing => serde_json::from_str::<BingResponse>(body)?
+            .web_pages
+            .map(|pages| pages.value)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|page| (page.name, page.url, page.snippet))
+            .collect(),
+        WebSearchProvider::Brave => serde_json::from_str::<BraveResponse>(body)?
+            .web
+            .map(|web| web.results)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|page| (page.title, page.url, page.description))
+            .collect(),
+        WebSearchProvider::Searxng => serde_json::from_str::<SearxngResponse>(body)?
+            .results
+            .into_iter()
+            .map(|page| (page.title, page.url, page.content))
+            .collect(),
+        WebSearchProvider::Custom => serde_json::from_str::<CustomResponse>(body)?
+            .results
+            .into_iter()
+            .map(|page| (page.title, page.url, page.snippet))
+            .collect(),
+    };
+    // Titles and snippets may contain highlighting markup.
+    Ok(results
+        .into_iter()
+        .map(|(title, url, snippet)| SearchResult {
+            title: html_to_text(&title),
+            url,
+            snippet: html_to_text(&snippet),
+        })
+        .collect())
+}
+
+fn format_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!("No results for \"{query}\".");
+    }
+    let mut output = format!("Results for \"{query}\":\n");
+    for (rank, result) in results.iter().enumerate() {
+        output.push_str(&format!(
+            "\n{}. {}\n   {}\n",
+            rank + 1,
+            result.title,
+            result.url
+        ));
+        if !result.snippet.is_empty() {
+            output.push_str(&format!("   {}\n", result.snippet.replace('\n', " ")));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::header;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+    use wiremock::matchers::query_param;
+
+    fn result(title: &str, url: &str, snippet: &str) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            url: url.to_string(),
+            snippet: snippet.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_each_provider_response() {
+        let bing = json!({
+            "webPages": { "value": [
+                { "name": "Tokio <b>docs</b>", "url": "https://tokio.rs", "snippet": "An async runtime &amp; more" }
+            ] }
+        });
+        let brave = json!({
+            "web": { "results": [
+                { "title": "Tokio", "url": "https://tokio.rs", "description": "An <strong>async</strong> runtime" }
+            ] }
+        });
+        let searxng = json!({
+            "results": [{ "title": "Tokio", "url": "https://tokio.rs", "content": "An async runtime" }]
+        });
+        let custom = json!({
+            "results": [{ "title": "Tokio", "url": "https://tokio.rs" }]
+        });
+
+        assert_eq!(
+            vec![result(
+                "Tokio docs",
+                "https://tokio.rs",
+                "An async runtime & more"
+            )],
+            parse_results(WebSearchProvider::Bing, &bing.to_string()).unwrap()
+        );
+        assert_eq!(
+            vec![result("Tokio", "https://tokio.rs", "An async runtime")],
+            parse_results(WebSearchProvider::Brave, &brave.to_string()).unwrap()
+        );
+        assert_eq!(
+            vec![result("Tokio", "https://tokio.rs", "An async runtime")],
+            parse_results(WebSearchProvider::Searxng, &searxng.to_string()).unwrap()
+        );
+        assert_eq!(
+            vec![result("Tokio", "https://tokio.rs", "")],
+            parse_results(WebSearchProvider::Custom, &custom.to_string()).unwrap()
+        );
+        // Bing and Brave omit the section entirely when nothing matched.
+        assert_eq!(
+            Vec::<SearchResult>::new(),
+            parse_results(WebSearchProvider::Bing, "{}").unwrap()
+        );
+        assert!(parse_results(WebSearchProvider::Searxng, "<html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn searches_searxng_and_custom_endpoints() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("q", "rust async"))
+            .and(query_param("format", "json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [
+                    { "title": "Tokio", "url": "https://tokio.rs", "content": "An async\nruntime" },
+                    { "title": "async-std", "url": "https://async.rs", "content": "" },
+                    { "title": "smol", "url": "https://github.com/smol-rs/smol", "content": "" }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/custom"))
+            .and(query_param("count", "5"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "results": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let searxng = WebSearch {
+            provider: Some(WebSearchProvider::Searxng),
+            base_url: Some(format!("{}/", server.uri())),
+            ..Default::default()
+        };
+        let output = search(
+            &searxng,
+            &SandboxPolicy::DangerFullAccess,
+            WebSearchArgs {
+                query: " rust async ".to_string(),
+                count: Some(2),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            "Results for \"rust async\":\n\n\
+1. Tokio\n   https://tokio.rs\n   An async runtime\n\n\
+2. async-std\n   https://async.rs\n",
+            output
+        );
+
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("CODEX_TEST_CUSTOM_SEARCH_KEY", "secret") };
+        let custom = WebSearch {
+            provider: Some(WebSearchProvider::Custom),
+            base_url: Some(format!("{}/custom", server.uri())),
+            api_key_env: Some("CODEX_TEST_CUSTOM_SEARCH_KEY".to_string()),
+            ..Default::default()
+        };
+        let output = search(
+            &custom,
+            &SandboxPolicy::DangerFullAccess,
+            WebSearchArgs {
+                query: "nothing".to_string(),
+                count: Some(50),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!("No results for \"nothing\".", output);
+    }
+
+    #[tokio::test]
+    async fn missing_api_key_is_reported_to_the_model() {
+        let config = WebSearch {
+            provider: Some(WebSearchProvider::Brave),
+            api_key_env: Some("CODEX_TEST_UNSET_SEARCH_KEY".to_string()),
+            ..Default::default()
+        };
+        let err = search(
+            &config,
+            &SandboxPolicy::DangerFullAccess,
+            WebSearchArgs {
+                query: "rust".to_string(),
+                count: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            "web search is not available: environment variable `CODEX_TEST_UNSET_SEARCH_KEY` is not set",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn searching_needs_network_access() {
+        let config = WebSearch {
+            provider: Some(WebSearchProvider::Searxng),
+            base_url: Some("http://127.0.0.1:9/".to_string()),
+            ..Default::default()
+        };
+        let err = search(
+            &config,
+            &SandboxPolicy::ReadOnly,
+            WebSearchArgs {
+                query: "rust".to_string(),
+                count: None,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!("network access is disabled by the sandbox policy", err);
+    }
+}
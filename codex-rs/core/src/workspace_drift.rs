@@ -0,0 +1,251 @@
+//! Detection of workspace changes made while a session was inactive.
+//!
+//! When a turn finishes the session records a [`WorkspaceStateEvent`] with
+//! the git HEAD, a snapshot of the working tree and the background processes
+//! still running. When the session is resumed, the last recorded state is
+//! compared with the workspace and a summary of what changed is added to the
+//! conversation, so the model does not act on stale assumptions.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::ENVIRONMENT_CONTEXT_CLOSE_TAG;
+use codex_protocol::protocol::ENVIRONMENT_CONTEXT_OPEN_TAG;
+use tracing::warn;
+
+use crate::background_process::BackgroundProcessManager;
+use crate::background_process::BackgroundProcessState;
+use crate::checkpoint;
+use crate::checkpoint::WorktreeSnapshot;
+use crate::git_info::collect_git_info;
+use crate::git_info::get_git_repo_root;
+use crate::protocol::WorkspaceStateEvent;
+
+/// Changed files listed individually; the rest are only counted.
+const MAX_LISTED_FILES: usize = 50;
+
+/// Record the state of the workspace around `cwd`.
+pub(crate) async fn capture_workspace_state(
+    cwd: &Path,
+    background_processes: &BackgroundProcessManager,
+) -> WorkspaceStateEvent {
+    let (git_head, git_branch) = match collect_git_info(cwd).await {
+        Some(info) => (info.commit_hash, info.branch),
+        None => (None, None),
+    };
+    let worktree_tree = match checkpoint::snapshot_worktree(cwd).await {
+        Ok(snapshot) => snapshot.map(|snapshot| snapshot.tree),
+        Err(e) => {
+            warn!("failed to snapshot working tree: {e}");
+            None
+        }
+    };
+    let background_processes = background_processes
+        .list()
+        .await
+        .into_iter()
+        .filter(|process| matches!(process.state, BackgroundProcessState::Running))
+        .map(|process| format!("{}: {}", process.id, process.command.join(" ")))
+        .collect();
+    WorkspaceStateEvent {
+        cwd: cwd.to_path_buf(),
+        git_head,
+        git_branch,
+        worktree_tree,
+        background_processes,
+    }
+}
+
+/// What changed between a recorded [`WorkspaceStateEvent`] and now.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct WorkspaceChanges {
+    previous_cwd: Option<PathBuf>,
+    /// Previous and current HEAD, each with its branch.
+    git_head: Option<(String, String)>,
+    /// Git status letter and path relative to the repository root.
+    changed_files: Vec<(String, String)>,
+    stopped_processes: Vec<String>,
+}
+
+impl WorkspaceChanges {
+    /// Compare `previous` with the workspace around `cwd`. Returns `None` when
+    /// nothing changed.
+    pub(crate) async fn detect(previous: &WorkspaceStateEvent, cwd: &Path) -> Option<Self> {
+        let mut changes = Self::default();
+        if previous.cwd != cwd {
+            changes.previous_cwd = Some(previous.cwd.clone());
+        }
+
+        let (git_head, git_branch) = match collect_git_info(cwd).await {
+            Some(info) => (info.commit_hash, info.branch),
+            None => (None, None),
+        };
+        if git_head != previous.git_head {
+            changes.git_head = Some((
+                describe_head(previous.git_head.as_deref(), previous.git_branch.as_deref()),
+                describe_head(git_head.as_deref(), git_branch.as_deref()),
+            ));
+        }
+
+        if let Some(tree) = &previous.worktree_tree
+            && let Some(repo_root) = get_git_repo_root(&previous.cwd)
+        {
+            let snapshot = WorktreeSnapshot {
+                repo_root,
+                tree: tree.clone(),
+            };
+            match checkpoint::status_since(&snapshot).await {
+                Ok(files) => changes.changed_files = files,
+                Err(e) => warn!("failed to compare working tree with the last turn: {e}"),
+            }
+        }
+
+        // Background processes do not outlive the session that started them.
+        changes.stopped_processes = previous.background_processes.clone();
+
+        (changes != Self::default()).then_some(changes)
+    }
+
+    /// Serializes the changes to XML inside an `<environment_context>` block,
+    /// so clients classify the message as context rather than user input.
+    fn serialize_to_xml(&self) -> String {
+        let mut lines = vec![
+            ENVIRONMENT_CONTEXT_OPEN_TAG.to_string(),
+            "  <workspace_changes>".to_string(),
+        ];
+        if let Some(previous_cwd) = &self.previous_cwd {
+            lines.push(format!(
+                "    <previous_cwd>{}</previous_cwd>",
+                previous_cwd.display()
+            ));
+        }
+        if let Some((previous, current)) = &self.git_head {
+            lines.push(format!(
+                "    <git_head previous=\"{previous}\">{current}</git_head>"
+            ));
+        }
+        if !self.changed_files.is_empty() {
+            lines.push("    <changed_files>".to_string());
+            for (status, path) in self.changed_files.iter().take(MAX_LISTED_FILES) {
+                lines.push(format!("      <file status=\"{status}\">{path}</file>"));
+            }
+            if self.changed_files.len() > MAX_LISTED_FILES {
+                lines.push(format!(
+                    "      <more>{}</more>",
+                    self.changed_files.len() - MAX_LISTED_FILES
+                ));
+            }
+            lines.push("    </changed_files>".to_string());
+        }
+        if !self.stopped_processes.is_empty() {
+            lines.push("    <stopped_background_processes>".to_string());
+            for process in &self.stopped_processes {
+                lines.push(format!("      <process>{process}</process>"));
+            }
+            lines.push("    </stopped_background_processes>".to_string());
+        }
+        lines.push("  </workspace_changes>".to_string());
+        lines.push(ENVIRONMENT_CONTEXT_CLOSE_TAG.to_string());
+        lines.join("\n")
+    }
+}
+
+fn describe_head(head: Option<&str>, branch: Option<&str>) -> String {
+    match (head, branch) {
+        (Some(head), Some(branch)) => format!("{head} ({branch})"),
+        (Some(head), None) => head.to_string(),
+        (None, _) => "none".to_string(),
+    }
+}
+
+impl From<WorkspaceChanges> for ResponseItem {
+    fn from(changes: WorkspaceChanges) -> Self {
+        ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: changes.serialize_to_xml(),
+            }],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+    use tokio::process::Command;
+
+    async fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .await
+            .expect("run git");
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[tokio::test]
+    async fn detects_head_moves_file_changes_and_stopped_processes() {
+        let repo = TempDir::new().expect("tempdir");
+        let root = dunce::canonicalize(repo.path()).expect("canonicalize");
+        git(&root, &["init", "-q", "-b", "main"]).await;
+        git(&root, &["config", "user.email", "codex@example.com"]).await;
+        git(&root, &["config", "user.name", "Codex"]).await;
+        std::fs::write(root.join("kept.txt"), "kept\n").expect("write");
+        std::fs::write(root.join("edited.txt"), "before\n").expect("write");
+        git(&root, &["add", "."]).await;
+        git(&root, &["commit", "-q", "-m", "initial"]).await;
+        let first = git(&root, &["rev-parse", "HEAD"]).await;
+
+        let mut state =
+            capture_workspace_state(&root, &BackgroundProcessManager::new(Default::default()))
+                .await;
+        assert_eq!(Some(first.clone()), state.git_head);
+        assert!(WorkspaceChanges::detect(&state, &root).await.is_none());
+
+        std::fs::write(root.join("edited.txt"), "after\n").expect("write");
+        git(&root, &["commit", "-q", "-am", "edit"]).await;
+        std::fs::write(root.join("new.txt"), "new\n").expect("write");
+        let second = git(&root, &["rev-parse", "HEAD"]).await;
+        state.background_processes = vec!["bg-0: npm run dev".to_string()];
+
+        let changes = WorkspaceChanges::detect(&state, &root)
+            .await
+            .expect("changes detected");
+        assert_eq!(
+            WorkspaceChanges {
+                previous_cwd: None,
+                git_head: Some((format!("{first} (main)"), format!("{second} (main)"))),
+                changed_files: vec![
+                    ("M".to_string(), "edited.txt".to_string()),
+                    ("A".to_string(), "new.txt".to_string()),
+                ],
+                stopped_processes: vec!["bg-0: npm run dev".to_string()],
+            },
+            changes
+        );
+        assert_eq!(
+            format!(
+                "<environment_context>
+  <workspace_changes>
+    <git_head previous=\"{first} (main)\">{second} (main)</git_head>
+    <changed_files>
+      <file status=\"M\">edited.txt</file>
+      <file status=\"A\">new.txt</file>
+    </changed_files>
+    <stopped_background_processes>
+      <process>bg-0: npm run dev</process>
+    </stopped_background_processes>
+  </workspace_changes>
+</environment_context>"
+            ),
+            changes.serialize_to_xml()
+        );
+    }
+}
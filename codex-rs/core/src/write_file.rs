@@ -0,0 +1,164 @@
+//! The `write_file` tool, which replaces the whole content of a file. Writes
+//! are presented and approved like `apply_patch` changes, recorded by the
+//! turn's diff tracker so `Op::UndoTurn` can restore the previous content,
+//! and made atomically by renaming a temporary file over the target.
+
+use std::io;
+use std::path::Path;
+
+use codex_apply_patch::ApplyPatchAction;
+use serde::Deserialize;
+
+use crate::function_tool::FunctionCallError;
+use crate::protocol::SandboxPolicy;
+
+pub(crate) const WRITE_FILE_TOOL_NAME: &str = "write_file";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct WriteFileArgs {
+    pub(crate) path: String,
+    pub(crate) content: String,
+}
+
+pub(crate) fn parse_write_file_arguments(
+    arguments: &str,
+) -> Result<WriteFileArgs, FunctionCallError> {
+    serde_json::from_str(arguments).map_err(|e| {
+        FunctionCallError::RespondToModel(format!("failed to parse function arguments: {e:?}"))
+    })
+}
+
+/// The change that writing `content` to `path` makes, or `None` when the file
+/// already has that content.
+pub(crate) async fn write_file_action(
+    path: &Path,
+    content: String,
+) -> Result<Option<ApplyPatchAction>, FunctionCallError> {
+    let original = match tokio::fs::read(path).await {
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(original) => Some(original),
+            Err(_) => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "{} is not a UTF-8 text file; refusing to overwrite it",
+                    path.display()
+                )));
+            }
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "failed to read {}: {e}",
+                path.display()
+            )));
+        }
+    };
+    if original.as_deref() == Some(content.as_str()) {
+        return Ok(None);
+    }
+    Ok(Some(ApplyPatchAction::new_write(
+        path,
+        original.as_deref(),
+        content,
+    )))
+}
+
+/// Whether the sandbox lets the agent write `path` without approval.
+pub(crate) fn is_path_writable(sandbox_policy: &SandboxPolicy, cwd: &Path, path: &Path) -> bool {
+    sandbox_policy.has_full_disk_write_access()
+        || sandbox_policy
+            .get_writable_roots_with_cwd(cwd)
+            .iter()
+            .any(|root| root.is_path_writable(path))
+}
+
+/// Write `content` to `path` through a temporary file in the same directory,
+/// so readers never see a partially written file. Missing parent directories
+/// are created and the permissions of an existing file are kept.
+pub(crate) async fn write_atomically(path: &Path, content: String) -> io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let parent = path
+            .parent()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no parent"))?;
+        std::fs::create_dir_all(parent)?;
+        let temp = tempfile::NamedTempFile::new_in(parent)?;
+        std::fs::write(temp.path(), content)?;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            std::fs::set_permissions(temp.path(), metadata.permissions())?;
+        }
+        temp.persist(&path).map_err(|e| e.error)?;
+        Ok(())
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_apply_patch::ApplyPatchFileChange;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn describes_and_writes_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("gen/schema.json");
+
+        let action = write_file_action(&path, "{}\n".to_string())
+            .await
+            .unwrap()
+            .expect("new file");
+        assert_eq!(
+            Some(&ApplyPatchFileChange::Add {
+                content: "{}\n".to_string()
+            }),
+            action.changes().get(&path)
+        );
+
+        write_atomically(&path, "{}\n".to_string()).await.unwrap();
+        assert_eq!("{}\n", std::fs::read_to_string(&path).unwrap());
+        assert!(
+            write_file_action(&path, "{}\n".to_string())
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let action = write_file_action(&path, "{\"a\": 1}\n".to_string())
+            .await
+            .unwrap()
+            .expect("changed file");
+        assert!(matches!(
+            action.changes().get(&path),
+            Some(ApplyPatchFileChange::Update { new_content, .. }) if new_content == "{\"a\": 1}\n"
+        ));
+
+        std::fs::write(dir.path().join("blob.bin"), b"\xFF\xFE\x00").unwrap();
+        assert!(
+            write_file_action(&dir.path().join("blob.bin"), String::new())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn writable_paths_follow_the_sandbox_policy() {
+        let dir = TempDir::new().unwrap();
+        let cwd = dir.path();
+        let policy = SandboxPolicy::new_workspace_write_policy();
+
+        assert!(is_path_writable(&policy, cwd, &cwd.join("src/lib.rs")));
+        assert!(!is_path_writable(&policy, cwd, Path::new("/etc/hosts")));
+        assert!(!is_path_writable(
+            &SandboxPolicy::ReadOnly,
+            cwd,
+            &cwd.join("src/lib.rs")
+        ));
+        assert!(is_path_writable(
+            &SandboxPolicy::DangerFullAccess,
+            cwd,
+            Path::new("/etc/hosts")
+        ));
+    }
+}
@@ -10,10 +10,12 @@ use codex_core::ModelProviderInfo;
 use codex_core::Prompt;
 use codex_core::ReasoningItemContent;
 use codex_core::ResponseItem;
+use codex_core::ToolCalling;
 use codex_core::WireApi;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use codex_otel::otel_event_manager::OtelEventManager;
 use codex_protocol::ConversationId;
+use codex_protocol::models::FunctionCallOutputPayload;
 use core_test_support::load_default_config_for_test;
 use futures::StreamExt;
 use serde_json::Value;
@@ -58,6 +60,10 @@ async fn run_request(input: Vec<ResponseItem>) -> Value {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     let codex_home = match TempDir::new() {
@@ -358,3 +364,50 @@ async fn suppresses_duplicate_assistant_messages() {
         Value::String("dup".into())
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn groups_parallel_tool_calls_into_one_assistant_message() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let second_call = ResponseItem::FunctionCall {
+        id: None,
+        name: "g".to_string(),
+        arguments: "{}".to_string(),
+        call_id: "c2".to_string(),
+    };
+    let output = |call_id: &str| ResponseItem::FunctionCallOutput {
+        call_id: call_id.to_string(),
+        output: FunctionCallOutputPayload {
+            content: "ok".to_string(),
+            success: Some(true),
+        },
+    };
+
+    let body = run_request(vec![
+        user_message("u1"),
+        function_call(),
+        second_call,
+        output("c1"),
+        output("c2"),
+    ])
+    .await;
+    let messages = messages_from(&body);
+    let roles: Vec<&str> = messages
+        .iter()
+        .filter_map(|msg| msg["role"].as_str())
+        .collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "tool", "tool"]);
+
+    let call_ids: Vec<&str> = first_assistant(&messages)["tool_calls"]
+        .as_array()
+        .expect("tool_calls array")
+        .iter()
+        .filter_map(|call| call["id"].as_str())
+        .collect();
+    assert_eq!(call_ids, vec!["c1", "c2"]);
+}
@@ -8,6 +8,7 @@ use codex_core::ModelProviderInfo;
 use codex_core::Prompt;
 use codex_core::ResponseEvent;
 use codex_core::ResponseItem;
+use codex_core::ToolCalling;
 use codex_core::WireApi;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
 use codex_otel::otel_event_manager::OtelEventManager;
@@ -56,6 +57,10 @@ async fn run_stream_with_bytes(sse_body: &[u8]) -> Vec<ResponseEvent> {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     let codex_home = match TempDir::new() {
@@ -311,19 +316,24 @@ async fn streams_reasoning_before_tool_call() {
     );
 
     let events = run_stream(sse).await;
-    assert_eq!(events.len(), 4, "unexpected events: {events:?}");
+    assert_eq!(events.len(), 5, "unexpected events: {events:?}");
 
     match &events[0] {
         ResponseEvent::ReasoningContentDelta(text) => assert_eq!(text, "pre-tool"),
         other => panic!("expected reasoning delta, got {other:?}"),
     }
 
-    match &events[1] {
+    assert!(matches!(
+        events[1],
+        ResponseEvent::ToolCallArgumentsDelta { .. }
+    ));
+
+    match &events[2] {
         ResponseEvent::OutputItemDone(item) => assert_reasoning(item, "pre-tool"),
         other => panic!("expected reasoning item, got {other:?}"),
     }
 
-    match &events[2] {
+    match &events[3] {
         ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
             name,
             arguments,
@@ -337,7 +347,53 @@ async fn streams_reasoning_before_tool_call() {
         other => panic!("expected function call, got {other:?}"),
     }
 
-    assert!(matches!(events[3], ResponseEvent::Completed { .. }));
+    assert!(matches!(events[4], ResponseEvent::Completed { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_parallel_tool_calls() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",\"function\":{\"name\":\"read_file\",\"arguments\":\"\"}},{\"index\":1,\"id\":\"call_2\",\"type\":\"function\",\"function\":{\"name\":\"search_code\",\"arguments\":\"\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":1,\"function\":{\"arguments\":\"{\\\"query\\\":\\\"fn main\\\"}\"}}]}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"{\\\"path\\\":\\\"a.rs\\\"}\"}}]},\"finish_reason\":\"tool_calls\"}]}\n\n",
+    );
+
+    let calls: Vec<(String, String, String)> = run_stream(sse)
+        .await
+        .into_iter()
+        .filter_map(|event| match event {
+            ResponseEvent::OutputItemDone(ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            }) => Some((call_id, name, arguments)),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        calls,
+        vec![
+            (
+                "call_1".to_string(),
+                "read_file".to_string(),
+                r#"{"path":"a.rs"}"#.to_string()
+            ),
+            (
+                "call_2".to_string(),
+                "search_code".to_string(),
+                r#"{"query":"fn main"}"#.to_string()
+            ),
+        ]
+    );
 }
 
 #[tokio::test]
@@ -0,0 +1,80 @@
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn batched_calls_run_in_the_order_the_model_made_them() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    let echo = json!({ "command": ["echo", "hi"] }).to_string();
+    let plan = json!({ "plan": [{ "step": "say hi", "status": "completed" }] }).to_string();
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call-echo", "shell", &echo),
+                ev_function_call("call-plan", "update_plan", &plan),
+                ev_completed("r1"),
+            ]),
+            sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+        ],
+    )
+    .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    config.approval_batching = true;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "say hi, then record it in the plan".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    // The shell call is held back until the response completes; the plan
+    // update the model made after it must wait for it.
+    let mut order = Vec::new();
+    loop {
+        let event = codex.next_event().await.expect("event");
+        match event.msg {
+            EventMsg::ExecCommandBegin(_) => order.push("exec"),
+            EventMsg::PlanUpdate(_) => order.push("plan"),
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(order, vec!["exec", "plan"]);
+}
@@ -12,6 +12,7 @@ use codex_core::Prompt;
 use codex_core::ReasoningItemContent;
 use codex_core::ResponseEvent;
 use codex_core::ResponseItem;
+use codex_core::ToolCalling;
 use codex_core::WireApi;
 use codex_core::built_in_model_providers;
 use codex_core::protocol::EventMsg;
@@ -657,6 +658,10 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     let codex_home = TempDir::new().unwrap();
@@ -1047,6 +1052,10 @@ async fn azure_overrides_assign_properties_used_for_responses_url() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     // Init session
@@ -1124,6 +1133,10 @@ async fn env_var_overrides_loaded_auth() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     // Init session
@@ -3,10 +3,12 @@ use codex_core::ConversationManager;
 use codex_core::ModelProviderInfo;
 use codex_core::NewConversation;
 use codex_core::built_in_model_providers;
+use codex_core::protocol::ContextPin;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::PinnedContextEvent;
 use codex_core::protocol::RolloutItem;
 use codex_core::protocol::RolloutLine;
 use core_test_support::load_default_config_for_test;
@@ -828,3 +830,139 @@ async fn auto_compact_allows_multiple_attempts_when_interleaved_with_other_turn_
         "second auto compact request should include the summarization prompt"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn pinned_context_survives_compaction() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("\"text\":\"hello world\"")
+            && !body.contains("You have exceeded the maximum number of tokens")
+    };
+    mount_sse_once_match(
+        &server,
+        first_matcher,
+        sse(vec![
+            ev_assistant_message("m1", FIRST_REPLY),
+            ev_completed("r1"),
+        ]),
+    )
+    .await;
+    let second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("You have exceeded the maximum number of tokens")
+    };
+    mount_sse_once_match(
+        &server,
+        second_matcher,
+        sse(vec![
+            ev_assistant_message("m2", SUMMARY_TEXT),
+            ev_completed("r2"),
+        ]),
+    )
+    .await;
+    let third_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(&format!("\"text\":\"{THIRD_USER_MSG}\""))
+    };
+    mount_sse_once_match(&server, third_matcher, sse(vec![ev_completed("r3")])).await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().unwrap();
+    let cwd = TempDir::new().unwrap();
+    let design = cwd.path().join("DESIGN.md");
+    std::fs::write(&design, "design v1").unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.cwd = cwd.path().to_path_buf();
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    codex
+        .submit(Op::PinContext {
+            pin: ContextPin::File {
+                path: "DESIGN.md".into(),
+            },
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::PinnedContext(_))).await;
+    codex
+        .submit(Op::PinContext {
+            pin: ContextPin::Note {
+                text: "keep the API stable".to_string(),
+            },
+        })
+        .await
+        .unwrap();
+    let EventMsg::PinnedContext(PinnedContextEvent { pins }) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::PinnedContext(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        vec![
+            ContextPin::File {
+                path: design.clone()
+            },
+            ContextPin::Note {
+                text: "keep the API stable".to_string()
+            },
+        ],
+        pins
+    );
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello world".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    codex.submit(Op::Compact).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    std::fs::write(&design, "design v2").unwrap();
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: THIRD_USER_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3, "expected exactly three requests");
+    let pinned_text = |request: &wiremock::Request| {
+        let body = request.body_json::<serde_json::Value>().unwrap();
+        body["input"][0]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let pinned = |contents: &str| {
+        format!(
+            "<pinned_context>\n<file path=\"{}\">\n{contents}\n</file>\n<note>\nkeep the API stable\n</note>\n</pinned_context>",
+            design.display()
+        )
+    };
+    assert_eq!(pinned("design v1"), pinned_text(&requests[0]));
+    // The pins are not part of the history, so the compacted history still
+    // gets them, with the file read afresh.
+    assert_eq!(pinned("design v2"), pinned_text(&requests[2]));
+}
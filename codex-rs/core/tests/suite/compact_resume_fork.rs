@@ -22,10 +22,14 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
 use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::config_types::CompactionStrategy;
 use core_test_support::load_default_config_for_test;
 use core_test_support::responses::ev_assistant_message;
 use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_completed_with_tokens;
+use core_test_support::responses::ev_function_call;
 use core_test_support::responses::mount_sse_once_match;
+use core_test_support::responses::mount_sse_sequence;
 use core_test_support::responses::sse;
 use core_test_support::wait_for_event;
 use pretty_assertions::assert_eq;
@@ -36,6 +40,7 @@ use tempfile::TempDir;
 use wiremock::MockServer;
 
 const AFTER_SECOND_RESUME: &str = "AFTER_SECOND_RESUME";
+const DROPPED_TOOL_OUTPUT: &str = "[output removed during context compaction]";
 
 fn network_disabled() -> bool {
     std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
@@ -642,6 +647,126 @@ async fn compact_resume_after_second_compaction_preserves_history() {
     assert_eq!(expected, last_request_after_2_compacts);
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+/// Scenario: automatic compaction drops the output of an old tool call; after
+/// resuming, the model sees the same history as right after the compaction.
+async fn resume_after_dropping_tool_outputs_preserves_history() {
+    if network_disabled() {
+        println!("Skipping test because network is disabled in this sandbox");
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call-old", "unsupported_tool", "{}"),
+                ev_completed_with_tokens("r1", 10),
+            ]),
+            sse(vec![
+                ev_assistant_message("m2", FIRST_REPLY),
+                ev_completed_with_tokens("r2", 10),
+            ]),
+            sse(vec![
+                ev_assistant_message("m3", "SECOND_REPLY"),
+                ev_completed_with_tokens("r3", 500),
+            ]),
+            sse(vec![
+                ev_assistant_message("m4", "AFTER_COMPACT_REPLY"),
+                ev_completed_with_tokens("r4", 10),
+            ]),
+            sse(vec![ev_completed("r5")]),
+        ],
+    )
+    .await;
+
+    let (_home, config, manager, base) =
+        start_auto_compact_conversation(&server, CompactionStrategy::DropToolOutputs).await;
+    user_turn(&base, "hello world").await;
+    user_turn(&base, "AFTER_TOOL_CALL").await;
+    let base_path = fetch_conversation_path(&base, "base conversation").await;
+
+    let resumed = resume_conversation(&manager, &config, base_path).await;
+    user_turn(&resumed, "AFTER_RESUME").await;
+
+    let requests = gather_request_bodies(&server).await;
+    assert_eq!(requests.len(), 5);
+    let after_compact = requests[3]["input"].as_array().expect("input array");
+    let after_resume = requests[4]["input"].as_array().expect("input array");
+    let output = after_compact
+        .iter()
+        .find(|item| item["type"] == "function_call_output")
+        .expect("tool output kept in history");
+    assert_eq!(output["output"], DROPPED_TOOL_OUTPUT);
+    assert!(after_compact.len() < after_resume.len());
+    assert_eq!(
+        after_compact.as_slice(),
+        &after_resume[..after_compact.len()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+/// Scenario: automatic compaction summarizes the oldest turn and keeps the
+/// most recent one; after resuming, the kept turn is still there.
+async fn resume_after_summarizing_oldest_turns_preserves_history() {
+    if network_disabled() {
+        println!("Skipping test because network is disabled in this sandbox");
+        return;
+    }
+
+    let server = MockServer::start().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_assistant_message("m1", FIRST_REPLY),
+                ev_completed_with_tokens("r1", 10),
+            ]),
+            sse(vec![
+                ev_assistant_message("m2", "SECOND_REPLY"),
+                ev_completed_with_tokens("r2", 500),
+            ]),
+            sse(vec![
+                ev_assistant_message("m3", SUMMARY_TEXT),
+                ev_completed_with_tokens("r3", 10),
+            ]),
+            sse(vec![
+                ev_assistant_message("m4", "AFTER_COMPACT_REPLY"),
+                ev_completed_with_tokens("r4", 10),
+            ]),
+            sse(vec![ev_completed("r5")]),
+        ],
+    )
+    .await;
+
+    let (_home, config, manager, base) =
+        start_auto_compact_conversation(&server, CompactionStrategy::SummarizeOldest).await;
+    user_turn(&base, "hello world").await;
+    user_turn(&base, "AFTER_FIRST_REPLY").await;
+    let base_path = fetch_conversation_path(&base, "base conversation").await;
+
+    let resumed = resume_conversation(&manager, &config, base_path).await;
+    user_turn(&resumed, "AFTER_RESUME").await;
+
+    let requests = gather_request_bodies(&server).await;
+    assert_eq!(requests.len(), 5);
+    let after_compact = requests[3]["input"].as_array().expect("input array");
+    let after_resume = requests[4]["input"].as_array().expect("input array");
+    let texts: Vec<&str> = after_compact
+        .iter()
+        .filter_map(|item| item["content"][0]["text"].as_str())
+        .collect();
+    assert!(texts.iter().any(|text| text.contains(SUMMARY_TEXT)));
+    assert!(texts.contains(&"SECOND_REPLY"));
+    assert!(!texts.contains(&FIRST_REPLY));
+    assert!(after_compact.len() < after_resume.len());
+    assert_eq!(
+        after_compact.as_slice(),
+        &after_resume[..after_compact.len()]
+    );
+}
+
 fn normalize_line_endings(value: &mut Value) {
     match value {
         Value::String(text) => {
@@ -772,6 +897,31 @@ async fn start_test_conversation(
     (home, config, manager, conversation)
 }
 
+async fn start_auto_compact_conversation(
+    server: &MockServer,
+    strategy: CompactionStrategy,
+) -> (TempDir, Config, ConversationManager, Arc<CodexConversation>) {
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let home = TempDir::new().expect("create temp dir");
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_auto_compact_token_limit = Some(200);
+    config.auto_compaction.strategy = strategy;
+    config.auto_compaction.keep_recent_turns = 1;
+    config.session_titles.enabled = false;
+
+    let manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let NewConversation { conversation, .. } = manager
+        .new_conversation(config.clone())
+        .await
+        .expect("create conversation");
+
+    (home, config, manager, conversation)
+}
+
 async fn user_turn(conversation: &Arc<CodexConversation>, text: &str) {
     conversation
         .submit(Op::UserInput {
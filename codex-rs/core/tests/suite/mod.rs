@@ -2,6 +2,8 @@
 
 #[cfg(not(target_os = "windows"))]
 mod abort_tasks;
+#[cfg(not(target_os = "windows"))]
+mod approval_batching;
 mod cli_stream;
 mod client;
 mod compact;
@@ -13,11 +15,20 @@ mod json_result;
 mod live_cli;
 mod model_overrides;
 mod otel;
+mod parallel_tool_calls;
 mod prompt_caching;
+mod queued_user_input;
+mod retry_turn;
 mod review;
 mod rmcp_client;
 mod rollout_list_find;
+mod rollout_redaction;
 mod seatbelt;
+mod session_budget;
+mod session_env;
+mod session_tags;
+mod session_title;
 mod stream_error_allows_next_turn;
 mod stream_no_completed;
 mod user_notification;
+mod write_file;
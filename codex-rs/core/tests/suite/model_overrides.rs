@@ -1,7 +1,9 @@
 use codex_core::CodexAuth;
 use codex_core::ConversationManager;
+use codex_core::protocol::AskForApproval;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort;
 use core_test_support::load_default_config_for_test;
 use core_test_support::wait_for_event;
@@ -90,3 +92,72 @@ async fn override_turn_context_does_not_create_config_file() {
         "override should not create config.toml"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn update_session_settings_acknowledges_effective_settings() {
+    let codex_home = TempDir::new().unwrap();
+    let config = load_default_config_for_test(&codex_home);
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UpdateSessionSettings {
+            model: Some("o3".to_string()),
+            approval_policy: Some(AskForApproval::Never),
+            sandbox_policy: Some(SandboxPolicy::ReadOnly),
+            effort: Some(Some(ReasoningEffort::High)),
+        })
+        .await
+        .expect("submit settings update");
+
+    let EventMsg::SessionSettingsUpdated(ack) = wait_for_event(&codex, |ev| {
+        matches!(ev, EventMsg::SessionSettingsUpdated(_))
+    })
+    .await
+    else {
+        unreachable!()
+    };
+    assert_eq!(ack.model, "o3");
+    assert_eq!(ack.approval_policy, AskForApproval::Never);
+    assert_eq!(ack.sandbox_policy, SandboxPolicy::ReadOnly);
+    assert_eq!(ack.reasoning_effort, Some(ReasoningEffort::High));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn update_session_settings_rejects_invalid_update() {
+    let codex_home = TempDir::new().unwrap();
+    let config = load_default_config_for_test(&codex_home);
+
+    let conversation_manager =
+        ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UpdateSessionSettings {
+            model: Some("gpt-4.1".to_string()),
+            approval_policy: None,
+            sandbox_policy: None,
+            effort: Some(Some(ReasoningEffort::High)),
+        })
+        .await
+        .expect("submit settings update");
+
+    let EventMsg::Error(err) = wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        err.message,
+        "model gpt-4.1 does not support reasoning effort (requested high)"
+    );
+}
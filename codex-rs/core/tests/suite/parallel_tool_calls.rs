@@ -0,0 +1,100 @@
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn records_each_parallel_call_next_to_its_output() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    let cwd = TempDir::new().unwrap();
+    std::fs::write(cwd.path().join("a.txt"), "alpha\n").unwrap();
+    std::fs::write(cwd.path().join("b.txt"), "beta\n").unwrap();
+    let read = |path: &str| json!({ "path": path }).to_string();
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_function_call("call-a", "read_file", &read("a.txt")),
+                ev_function_call("call-b", "read_file", &read("b.txt")),
+                ev_completed("r1"),
+            ]),
+            sse(vec![ev_assistant_message("m2", "done"), ev_completed("r2")]),
+        ],
+    )
+    .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.cwd = cwd.path().to_path_buf();
+    config.parallel_tool_calls = true;
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "read both files".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    // The two reads run side by side, but the follow-up request still pairs
+    // each call with its output, in the order the model made the calls.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let body: Value = requests[1].body_json().unwrap();
+    let tool_items: Vec<(&str, &str)> = body["input"]
+        .as_array()
+        .expect("input array")
+        .iter()
+        .filter(|item| {
+            matches!(
+                item["type"].as_str(),
+                Some("function_call" | "function_call_output")
+            )
+        })
+        .map(|item| {
+            (
+                item["type"].as_str().unwrap_or_default(),
+                item["call_id"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        tool_items,
+        vec![
+            ("function_call", "call-a"),
+            ("function_call_output", "call-a"),
+            ("function_call", "call-b"),
+            ("function_call_output", "call-b"),
+        ]
+    );
+}
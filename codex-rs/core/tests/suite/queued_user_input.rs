@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use codex_core::config_types::BusySessionPolicy;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::QueuedUserInput;
+use codex_core::protocol::SessionBusyEvent;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn text(text: &str) -> Vec<InputItem> {
+    vec![InputItem::Text {
+        text: text.to_string(),
+    }]
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn queued_input_runs_after_the_current_turn() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    // Delay the first turn so the second message is queued while it runs.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("first message"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse(vec![
+                    ev_assistant_message("m1", "first reply"),
+                    ev_completed("r1"),
+                ]))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("second message"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse(vec![
+                    ev_assistant_message("m2", "second reply"),
+                    ev_completed("r2"),
+                ])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let TestCodex { codex, .. } = test_codex().build(&server).await.unwrap();
+
+    codex
+        .submit(Op::QueueUserInput {
+            items: text("first message"),
+        })
+        .await
+        .unwrap();
+    codex
+        .submit(Op::QueueUserInput {
+            items: text("second message"),
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::UserInputQueue(queued) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::UserInputQueue(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        vec!["second message".to_string()],
+        queued
+            .pending
+            .into_iter()
+            .map(|QueuedUserInput { text, .. }| text)
+            .collect::<Vec<_>>()
+    );
+
+    let EventMsg::TaskComplete(first) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(Some("first reply".to_string()), first.last_agent_message);
+
+    let EventMsg::UserInputQueue(queued) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::UserInputQueue(_))).await
+    else {
+        unreachable!()
+    };
+    assert!(queued.pending.is_empty());
+
+    let EventMsg::TaskComplete(second) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(Some("second reply".to_string()), second.last_agent_message);
+
+    // The second turn sees the first one in its history.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(2, requests.len());
+    let body = String::from_utf8_lossy(&requests[1].body);
+    assert!(body.contains("first reply"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn busy_session_rejects_overlapping_turns() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    // Delay the first turn so the second submission arrives while it runs.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse(vec![
+                    ev_assistant_message("m1", "first reply"),
+                    ev_completed("r1"),
+                ]))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(|config| config.busy_session_policy = BusySessionPolicy::Reject)
+        .build(&server)
+        .await
+        .unwrap();
+
+    let first_id = codex
+        .submit(Op::UserInput {
+            items: text("first message"),
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskStarted(_))).await;
+    codex
+        .submit(Op::UserInput {
+            items: text("second message"),
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::SessionBusy(busy) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionBusy(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        SessionBusyEvent {
+            running: vec![first_id],
+            queued: 0,
+        },
+        busy
+    );
+
+    let EventMsg::TaskComplete(first) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(Some("first reply".to_string()), first.last_agent_message);
+
+    // The rejected input never reached the model.
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(1, requests.len());
+    assert!(!String::from_utf8_lossy(&requests[0].body).contains("second message"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn busy_session_queues_overlapping_turns() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("first message"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse(vec![
+                    ev_assistant_message("m1", "first reply"),
+                    ev_completed("r1"),
+                ]))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("second message"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse(vec![
+                    ev_assistant_message("m2", "second reply"),
+                    ev_completed("r2"),
+                ])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(|config| config.busy_session_policy = BusySessionPolicy::Queue)
+        .build(&server)
+        .await
+        .unwrap();
+
+    codex
+        .submit(Op::UserInput {
+            items: text("first message"),
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskStarted(_))).await;
+    codex
+        .submit(Op::UserInput {
+            items: text("second message"),
+        })
+        .await
+        .unwrap();
+
+    let EventMsg::TaskComplete(first) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!()
+    };
+    // Without the lock the second message would have joined the first turn.
+    assert_eq!(Some("first reply".to_string()), first.last_agent_message);
+
+    let EventMsg::TaskComplete(second) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!()
+    };
+    assert_eq!(Some("second reply".to_string()), second.last_agent_message);
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(2, requests.len());
+    assert!(!String::from_utf8_lossy(&requests[0].body).contains("second message"));
+}
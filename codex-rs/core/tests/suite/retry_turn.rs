@@ -0,0 +1,204 @@
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::ConversationPathResponseEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::mount_sse_sequence;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn retry_replaces_last_attempt_with_corrected_turn() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("use pnpm, not npm"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m2", "Installed with pnpm."),
+            ev_completed("r2"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "Installed with npm."),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    // Nothing has run yet.
+    codex
+        .submit(Op::RetryTurn {
+            correction: String::new(),
+            revert_changes: false,
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "install the dependencies".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    codex
+        .submit(Op::RetryTurn {
+            correction: "use pnpm, not npm".into(),
+            revert_changes: true,
+        })
+        .await
+        .unwrap();
+    let EventMsg::TaskComplete(complete) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        complete.last_agent_message.as_deref(),
+        Some("Installed with pnpm.")
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let retry_body = String::from_utf8(requests[1].body.clone()).unwrap();
+    assert!(retry_body.contains("install the dependencies"));
+    assert!(!retry_body.contains("Installed with npm."));
+    assert_eq!(retry_body.matches("install the dependencies").count(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn resumed_session_keeps_only_the_retried_attempt() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    mount_sse_sequence(
+        &server,
+        vec![
+            sse(vec![
+                ev_assistant_message("m1", "Installed with npm."),
+                ev_completed("r1"),
+            ]),
+            sse(vec![
+                ev_assistant_message("m2", "Installed with pnpm."),
+                ev_completed("r2"),
+            ]),
+            sse(vec![
+                ev_assistant_message("m3", "Tests pass."),
+                ev_completed("r3"),
+            ]),
+        ],
+    )
+    .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config.clone())
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "install the dependencies".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    codex
+        .submit(Op::RetryTurn {
+            correction: "use pnpm, not npm".into(),
+            revert_changes: false,
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    codex.submit(Op::GetPath).await.unwrap();
+    let EventMsg::ConversationPath(ConversationPathResponseEvent { path, .. }) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::ConversationPath(_))).await
+    else {
+        unreachable!();
+    };
+    let resumed = conversation_manager
+        .resume_conversation_from_rollout(
+            config,
+            path,
+            AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        )
+        .await
+        .expect("resume conversation")
+        .conversation;
+    resumed
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run the tests".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&resumed, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let resumed_body = String::from_utf8(requests[2].body.clone()).unwrap();
+    assert!(resumed_body.contains("Installed with pnpm."));
+    assert!(!resumed_body.contains("Installed with npm."));
+    assert_eq!(resumed_body.matches("install the dependencies").count(), 1);
+}
@@ -84,6 +84,14 @@ async fn stdio_server_round_trip() -> anyhow::Result<()> {
                     },
                     startup_timeout_sec: Some(Duration::from_secs(10)),
                     tool_timeout_sec: None,
+                    tool_prefix: None,
+                    tool_aliases: HashMap::new(),
+                    max_concurrent_calls: None,
+                    max_result_bytes: None,
+                    enabled_tools: None,
+                    disabled_tools: Vec::new(),
+                    tool_approval: None,
+                    tool_approvals: HashMap::new(),
                 },
             );
         })
@@ -232,9 +240,19 @@ async fn streamable_http_tool_call_round_trip() -> anyhow::Result<()> {
                     transport: McpServerTransportConfig::StreamableHttp {
                         url: server_url,
                         bearer_token: None,
+                        bearer_token_env_var: None,
+                        http_headers: None,
                     },
                     startup_timeout_sec: Some(Duration::from_secs(10)),
                     tool_timeout_sec: None,
+                    tool_prefix: None,
+                    tool_aliases: HashMap::new(),
+                    max_concurrent_calls: None,
+                    max_result_bytes: None,
+                    enabled_tools: None,
+                    disabled_tools: Vec::new(),
+                    tool_approval: None,
+                    tool_approvals: HashMap::new(),
                 },
             );
         })
@@ -0,0 +1,96 @@
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::NewConversation;
+use codex_core::built_in_model_providers;
+use codex_core::config_types::RolloutRedaction;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn rollout_is_redacted_but_model_sees_original_text() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    // The request still carries the address; only the rollout is redacted.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("jane@example.com"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message(
+                "m1",
+                "Sent the invite to jane@example.com with TICKET-4711.",
+            ),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    config.rollout_redaction = RolloutRedaction {
+        enabled: true,
+        patterns: vec![r"TICKET-\d+".to_string()],
+        ..Default::default()
+    };
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let NewConversation {
+        conversation: codex,
+        session_configured,
+        ..
+    } = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation");
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "invite jane@example.com using key sk-abcdefghijklmnopqrstuvwxyz123456"
+                    .into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+
+    let rollout = std::fs::read_to_string(&session_configured.rollout_path).unwrap();
+    for secret in [
+        "jane@example.com",
+        "sk-abcdefghijklmnopqrstuvwxyz123456",
+        "TICKET-4711",
+    ] {
+        assert!(
+            !rollout.contains(secret),
+            "{secret} leaked into the rollout"
+        );
+    }
+    assert!(rollout.contains("Sent the invite to [REDACTED] with [REDACTED]."));
+}
@@ -0,0 +1,96 @@
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::config_types::SessionBudget;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed_with_tokens;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn turn_pauses_at_budget_until_user_decides() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    // Two model requests: the first turn and the turn continued past the
+    // budget. The stopped turn never reaches the model.
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "Done."),
+            ev_completed_with_tokens("r1", 100),
+        ])))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    config.session_budget = SessionBudget {
+        max_tokens: Some(50),
+        ..Default::default()
+    };
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    let submit_turn = |text: &str| {
+        codex.submit(Op::UserInput {
+            items: vec![InputItem::Text { text: text.into() }],
+        })
+    };
+
+    submit_turn("first").await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    for decision in [BudgetDecision::Stop, BudgetDecision::Continue] {
+        let id = submit_turn("again").await.unwrap();
+        let EventMsg::BudgetExceeded(exceeded) =
+            wait_for_event(&codex, |ev| matches!(ev, EventMsg::BudgetExceeded(_))).await
+        else {
+            unreachable!();
+        };
+        assert_eq!(
+            exceeded,
+            BudgetExceededEvent {
+                budget: BudgetKind::Tokens,
+                limit: 50.0,
+                used: 100.0,
+            }
+        );
+        codex
+            .submit(Op::BudgetDecision { id, decision })
+            .await
+            .unwrap();
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn env(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_env_reaches_shell_commands() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("postgres://test-db"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "Using the test database."),
+            ev_completed("r2"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let args = json!({ "command": ["sh", "-c", "echo $DATABASE_URL"] }).to_string();
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_function_call("call-1", "shell", &args),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UpdateSessionEnv {
+            set: env(&[("DATABASE_URL", "postgres://test-db")]),
+            remove: Vec::new(),
+        })
+        .await
+        .unwrap();
+    let EventMsg::SessionEnv(updated) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionEnv(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(updated.env, env(&[("DATABASE_URL", "postgres://test-db")]));
+
+    codex
+        .submit(Op::UpdateSessionEnv {
+            set: env(&[("A=B", "x")]),
+            remove: Vec::new(),
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "which database?".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::ExecCommandBegin(begin) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::ExecCommandBegin(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        begin.session_env,
+        env(&[("DATABASE_URL", "postgres://test-db")])
+    );
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+}
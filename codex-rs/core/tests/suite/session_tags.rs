@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+
+fn tags(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn tags_from_config_and_op_survive_resume() {
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.session_tags = tags(&[("team", "payments")]);
+    let auth_manager = AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy"));
+    let conversation_manager = ConversationManager::new(auth_manager.clone(), SessionSource::Exec);
+
+    let NewConversation {
+        conversation: codex,
+        session_configured,
+        ..
+    } = conversation_manager
+        .new_conversation(config.clone())
+        .await
+        .expect("create new conversation");
+    let EventMsg::SessionTags(initial) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionTags(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(initial.tags, tags(&[("team", "payments")]));
+
+    codex
+        .submit(Op::UpdateSessionTags {
+            set: tags(&[("ticket", "ABC-1")]),
+            remove: vec!["team".to_string()],
+        })
+        .await
+        .unwrap();
+    let EventMsg::SessionTags(updated) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionTags(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(updated.tags, tags(&[("ticket", "ABC-1")]));
+
+    codex
+        .submit(Op::UpdateSessionTags {
+            set: tags(&[("bad key", "x")]),
+            remove: Vec::new(),
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+
+    // The resumed session keeps its tags; configured tags are added again.
+    let resumed = conversation_manager
+        .resume_conversation_from_rollout(config, session_configured.rollout_path, auth_manager)
+        .await
+        .expect("resume conversation")
+        .conversation;
+    let EventMsg::SessionTags(restored) =
+        wait_for_event(&resumed, |ev| matches!(ev, EventMsg::SessionTags(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(
+        restored.tags,
+        tags(&[("team", "payments"), ("ticket", "ABC-1")])
+    );
+}
@@ -0,0 +1,116 @@
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::INTERACTIVE_SESSION_SOURCES;
+use codex_core::ModelProviderInfo;
+use codex_core::RolloutRecorder;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn session_is_titled_after_first_turn_and_can_be_renamed() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("You write titles for coding sessions"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("t1", "\"Fix flaky websocket test.\""),
+            ev_completed("title"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "The reconnect races the close handler."),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "the websocket test fails every other run".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    let EventMsg::SessionTitle(generated) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionTitle(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(generated.title, "Fix flaky websocket test");
+
+    codex
+        .submit(Op::SetSessionTitle {
+            title: "  Websocket \n flake ".into(),
+        })
+        .await
+        .unwrap();
+    let EventMsg::SessionTitle(renamed) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::SessionTitle(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(renamed.title, "Websocket flake");
+
+    codex
+        .submit(Op::SetSessionTitle { title: " ".into() })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::Error(_))).await;
+
+    codex.submit(Op::Shutdown).await.unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::ShutdownComplete)).await;
+
+    let page = RolloutRecorder::list_conversations(
+        codex_home.path(),
+        10,
+        None,
+        INTERACTIVE_SESSION_SOURCES,
+    )
+    .await
+    .expect("list conversations");
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].title.as_deref(), Some("Websocket flake"));
+}
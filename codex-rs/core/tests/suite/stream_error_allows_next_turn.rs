@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use codex_core::ModelProviderInfo;
+use codex_core::ToolCalling;
 use codex_core::WireApi;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -74,6 +75,10 @@ async fn continue_after_stream_error() {
         stream_max_retries: Some(1),
         stream_idle_timeout_ms: Some(2_000),
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     let TestCodex { codex, .. } = test_codex()
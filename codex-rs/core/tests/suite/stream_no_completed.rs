@@ -4,6 +4,7 @@
 use std::time::Duration;
 
 use codex_core::ModelProviderInfo;
+use codex_core::ToolCalling;
 use codex_core::WireApi;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -82,6 +83,10 @@ async fn retries_on_early_close() {
         stream_max_retries: Some(1),
         stream_idle_timeout_ms: Some(2000),
         requires_openai_auth: false,
+        tool_calling: ToolCalling::Native,
+        azure: None,
+        bedrock: None,
+        prompt_caching: None,
     };
 
     let TestCodex { codex, .. } = test_codex()
@@ -0,0 +1,111 @@
+use codex_core::AuthManager;
+use codex_core::CodexAuth;
+use codex_core::ConversationManager;
+use codex_core::ModelProviderInfo;
+use codex_core::built_in_model_providers;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SessionSource;
+use core_test_support::load_default_config_for_test;
+use core_test_support::responses::ev_assistant_message;
+use core_test_support::responses::ev_completed;
+use core_test_support::responses::ev_function_call;
+use core_test_support::responses::sse;
+use core_test_support::responses::sse_response;
+use core_test_support::responses::start_mock_server;
+use core_test_support::skip_if_no_network;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn write_file_reports_patch_events_and_can_be_undone() {
+    skip_if_no_network!();
+
+    let server = start_mock_server().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains("function_call_output"))
+        .respond_with(sse_response(sse(vec![
+            ev_assistant_message("m1", "Regenerated."),
+            ev_completed("r2"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let args = json!({ "path": "gen.txt", "content": "new\n" }).to_string();
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(sse_response(sse(vec![
+            ev_function_call("call-1", "write_file", &args),
+            ev_completed("r1"),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let codex_home = TempDir::new().unwrap();
+    let workspace = TempDir::new().unwrap();
+    let target = workspace.path().join("gen.txt");
+    std::fs::write(&target, "old\n").unwrap();
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+    config.session_titles.enabled = false;
+    config.cwd = workspace.path().to_path_buf();
+    config.approval_policy = AskForApproval::Never;
+    config.sandbox_policy = SandboxPolicy::DangerFullAccess;
+    config.include_write_file_tool = true;
+    let conversation_manager = ConversationManager::new(
+        AuthManager::from_auth_for_testing(CodexAuth::from_api_key("dummy")),
+        SessionSource::Cli,
+    );
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .expect("create new conversation")
+        .conversation;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "regenerate gen.txt".into(),
+            }],
+        })
+        .await
+        .unwrap();
+    let EventMsg::PatchApplyBegin(begin) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::PatchApplyBegin(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(begin.auto_approved);
+    assert!(begin.changes.contains_key(&target));
+    let EventMsg::PatchApplyEnd(end) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::PatchApplyEnd(_))).await
+    else {
+        unreachable!();
+    };
+    assert!(end.success);
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    assert_eq!("new\n", std::fs::read_to_string(&target).unwrap());
+
+    codex.submit(Op::UndoTurn).await.unwrap();
+    let EventMsg::TurnUndone(undone) =
+        wait_for_event(&codex, |ev| matches!(ev, EventMsg::TurnUndone(_))).await
+    else {
+        unreachable!();
+    };
+    assert_eq!(vec![target.clone()], undone.restored);
+    assert_eq!("old\n", std::fs::read_to_string(&target).unwrap());
+}
@@ -56,6 +56,11 @@ pub struct Cli {
     #[arg(long = "output-schema", value_name = "FILE")]
     pub output_schema: Option<PathBuf>,
 
+    /// Tag the session, e.g. `--tag ticket=ABC-123`. May be repeated. With
+    /// `resume --last`, only sessions carrying these tags are considered.
+    #[arg(long = "tag", value_name = "KEY=VALUE", global = true)]
+    pub tags: Vec<codex_common::SessionTagCliArg>,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 
@@ -5,6 +5,7 @@ use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::BackgroundEventEvent;
+use codex_core::protocol::ContextCompactedEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -12,12 +13,19 @@ use codex_core::protocol::ExecCommandBeginEvent;
 use codex_core::protocol::ExecCommandEndEvent;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpServerHealthStatus;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::ModelFailoverEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::SandboxDeniedEvent;
+use codex_core::protocol::SessionBusyEvent;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubAgentBeginEvent;
+use codex_core::protocol::SubAgentEndEvent;
+use codex_core::protocol::SubAgentOutcome;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
@@ -161,9 +169,72 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
                 ts_println!(self, "{}", message.style(self.dimmed));
             }
+            EventMsg::ContextCompacted(ContextCompactedEvent {
+                strategy,
+                items_before,
+                items_after,
+                ..
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!(
+                        "context compacted ({strategy}): {items_before} -> {items_after} items"
+                    )
+                    .style(self.dimmed)
+                );
+            }
+            EventMsg::SubAgentBegin(SubAgentBeginEvent { task, .. }) => {
+                ts_println!(self, "{} {task}", "sub-agent".style(self.magenta));
+            }
+            EventMsg::SubAgentEnd(SubAgentEndEvent {
+                outcome,
+                rollout_path,
+                ..
+            }) => {
+                let status = match outcome {
+                    SubAgentOutcome::Completed => "completed".style(self.green),
+                    SubAgentOutcome::BudgetExhausted => "budget exhausted".style(self.red),
+                    SubAgentOutcome::Failed => "failed".style(self.red),
+                };
+                ts_println!(
+                    self,
+                    "{} {status}: {}",
+                    "sub-agent".style(self.magenta),
+                    rollout_path.display()
+                );
+            }
             EventMsg::StreamError(StreamErrorEvent { message }) => {
                 ts_println!(self, "{}", message.style(self.dimmed));
             }
+            EventMsg::ModelFailover(ModelFailoverEvent {
+                model,
+                model_provider,
+                reason,
+            }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    format!("falling back to {model} ({model_provider}): {reason}")
+                        .style(self.dimmed)
+                );
+            }
+            EventMsg::SandboxDenied(SandboxDeniedEvent {
+                call_id: _,
+                operation,
+                path,
+                host,
+            }) => {
+                let target = path
+                    .map(|p| p.display().to_string())
+                    .or(host)
+                    .unwrap_or_else(|| "(unknown)".to_string());
+                ts_println!(
+                    self,
+                    "{}",
+                    format!("sandbox denied {operation}: {target}").style(self.red)
+                );
+            }
             EventMsg::TaskStarted(_) => {
                 // Ignore.
             }
@@ -246,6 +317,9 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                     format_mcp_invocation(&invocation).style(self.bold),
                 );
             }
+            EventMsg::McpToolCallProgress(progress) => {
+                ts_println!(self, "{}", progress.to_string().style(self.dimmed));
+            }
             EventMsg::McpToolCallEnd(tool_call_end_event) => {
                 let is_success = tool_call_end_event.is_success();
                 let McpToolCallEndEvent {
@@ -407,9 +481,15 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ExecApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::ApprovalBatchRequest(_) => {
+                // Should we exit?
+            }
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::ElicitationRequest(_) => {
+                // Should we exit?
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     ts_println!(
@@ -481,9 +561,29 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::McpListToolsResponse(_) => {
                 // Currently ignored in exec output.
             }
-            EventMsg::ListCustomPromptsResponse(_) => {
+            EventMsg::ListCustomPromptsResponse(_)
+            | EventMsg::McpListPromptsResponse(_)
+            | EventMsg::McpGetPromptResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::McpServerHealth(event) => match event.status {
+                McpServerHealthStatus::Reconnected { .. } => {
+                    ts_println!(self, "{}", event.to_string().style(self.dimmed));
+                }
+                McpServerHealthStatus::Unresponsive { .. }
+                | McpServerHealthStatus::Failed { .. } => {
+                    let prefix = "ERROR:".style(self.red);
+                    ts_println!(self, "{prefix} {event}");
+                }
+            },
+            EventMsg::McpServerConnection(event) => {
+                ts_println!(self, "{}", event.to_string().style(self.dimmed));
+            }
+            EventMsg::ConfigReloaded(event) => {
+                if !event.applied.is_empty() || !event.requires_restart.is_empty() {
+                    ts_println!(self, "{}", event.to_string().style(self.dimmed));
+                }
+            }
             EventMsg::ViewImageToolCall(view) => {
                 ts_println!(
                     self,
@@ -506,12 +606,45 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::BackgroundProcessStatus(_) => {
                 // Background process counts are not currently surfaced in the exec view.
             }
+            EventMsg::SessionSettingsUpdated(_) => {}
+            EventMsg::UserInputQueue(_) => {}
+            EventMsg::SessionBusy(SessionBusyEvent { running, queued }) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(
+                    self,
+                    "{prefix} session busy: turn {} is running with {queued} queued",
+                    running.join(", ")
+                );
+            }
+            EventMsg::CheckpointCreated(_) => {}
+            EventMsg::CheckpointRestored(_) => {}
+            EventMsg::TurnUndone(_) => {}
+            EventMsg::SessionDiff(_) => {}
+            EventMsg::BackgroundProcessResponse(_) => {}
+            EventMsg::TurnStats(_) => {}
+            EventMsg::ContextBudget(_) => {}
+            EventMsg::CostSummary(_) => {}
+            EventMsg::WorkspaceState(_) => {}
+            EventMsg::TurnGitState(_) => {}
+            EventMsg::SessionTitle(_) => {}
+            EventMsg::SessionTags(_) => {}
+            EventMsg::SessionEnv(_) => {}
+            EventMsg::TurnStatsResponse(_) => {}
+            EventMsg::BudgetExceeded(event) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(self, "{prefix} session budget reached: {event}");
+            }
+            EventMsg::PinnedContext(_) => {}
+            EventMsg::PayloadFrame(_) => {
+                // Binary payloads are not rendered in exec output.
+            }
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
             EventMsg::AgentMessageDelta(_) => {}
+            EventMsg::ToolCallArgumentsDelta(_) => {}
             EventMsg::AgentReasoningDelta(_) => {}
             EventMsg::AgentReasoningRawContentDelta(_) => {}
         }
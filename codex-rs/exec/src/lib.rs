@@ -5,6 +5,7 @@ pub mod event_processor_with_jsonl_output;
 pub mod exec_events;
 
 pub use cli::Cli;
+use codex_common::SessionTagCliArg;
 use codex_core::AuthManager;
 use codex_core::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 use codex_core::ConversationManager;
@@ -13,6 +14,7 @@ use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::git_info::get_git_repo_root;
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::BudgetDecision;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -25,6 +27,7 @@ use event_processor_with_human_output::EventProcessorWithHumanOutput;
 use event_processor_with_jsonl_output::EventProcessorWithJsonOutput;
 use opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::io::IsTerminal;
 use std::io::Read;
 use std::path::PathBuf;
@@ -62,6 +65,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         sandbox_mode: sandbox_mode_cli_arg,
         prompt,
         output_schema: output_schema_path,
+        tags,
         include_plan_tool,
         config_overrides,
     } = cli;
@@ -175,15 +179,19 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         include_view_image_tool: None,
         show_raw_agent_reasoning: oss.then_some(true),
         tools_web_search_request: None,
+        client_capabilities: None,
     };
     // Parse `-c` overrides.
-    let cli_kv_overrides = match config_overrides.parse_overrides() {
+    let mut cli_kv_overrides = match config_overrides.parse_overrides() {
         Ok(v) => v,
         Err(e) => {
             eprintln!("Error parsing -c overrides: {e}");
             std::process::exit(1);
         }
     };
+    cli_kv_overrides.extend(tags.iter().map(SessionTagCliArg::to_config_override));
+    let required_tags: BTreeMap<String, String> =
+        tags.into_iter().map(|tag| (tag.key, tag.value)).collect();
 
     let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
 
@@ -247,7 +255,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         conversation,
         session_configured,
     } = if let Some(ExecCommand::Resume(args)) = command {
-        let resume_path = resolve_resume_path(&config, &args).await?;
+        let resume_path = resolve_resume_path(&config, &args, &required_tags).await?;
 
         if let Some(path) = resume_path {
             conversation_manager
@@ -354,6 +362,17 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         if matches!(event.msg, EventMsg::Error(_)) {
             error_seen = true;
         }
+        // Nobody can be asked whether to go on, so a turn that reaches the
+        // session budget stops.
+        if matches!(event.msg, EventMsg::BudgetExceeded(_)) {
+            error_seen = true;
+            conversation
+                .submit(Op::BudgetDecision {
+                    id: event.id.clone(),
+                    decision: BudgetDecision::Stop,
+                })
+                .await?;
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -376,10 +395,17 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
 async fn resolve_resume_path(
     config: &Config,
     args: &crate::cli::ResumeArgs,
+    required_tags: &BTreeMap<String, String>,
 ) -> anyhow::Result<Option<PathBuf>> {
     if args.last {
-        match codex_core::RolloutRecorder::list_conversations(&config.codex_home, 1, None, &[])
-            .await
+        match codex_core::RolloutRecorder::list_conversations_with_tags(
+            &config.codex_home,
+            1,
+            None,
+            &[],
+            required_tags,
+        )
+        .await
         {
             Ok(page) => Ok(page.items.first().map(|it| it.path.clone())),
             Err(e) => {
@@ -39,6 +39,7 @@ use codex_exec::exec_events::Usage;
 use codex_exec::exec_events::WebSearchItem;
 use mcp_types::CallToolResult;
 use pretty_assertions::assert_eq;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -511,6 +512,7 @@ fn exec_command_end_success_produces_completed_command_item() {
             command: vec!["bash".to_string(), "-lc".to_string(), "echo hi".to_string()],
             cwd: std::env::current_dir().unwrap(),
             parsed_cmd: Vec::new(),
+            session_env: BTreeMap::new(),
         }),
     );
     let out_begin = ep.collect_thread_events(&begin);
@@ -571,6 +573,7 @@ fn exec_command_end_failure_produces_failed_command_item() {
             command: vec!["sh".to_string(), "-c".to_string(), "exit 1".to_string()],
             cwd: std::env::current_dir().unwrap(),
             parsed_cmd: Vec::new(),
+            session_env: BTreeMap::new(),
         }),
     );
     assert_eq!(
@@ -0,0 +1,114 @@
+//! Secrets kept in the OS credential store: the macOS Keychain, the Secret
+//! Service on Linux and the Windows Credential Manager.
+//!
+//! Every entry Codex stores belongs to the [`KEYRING_SERVICE`] service and is
+//! named by its account, e.g. `OPENROUTER_API_KEY` for an API key stored with
+//! `codex secret set` or `mcp_oauth/docs` for the OAuth tokens of the MCP
+//! server `docs`.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+/// Service name of the entries Codex stores.
+pub const KEYRING_SERVICE: &str = "codex";
+
+#[derive(Debug, thiserror::Error)]
+#[error("OS credential store: {0}")]
+pub struct KeyringError(#[from] keyring::Error);
+
+/// A store of secrets by account name.
+pub trait KeyringStore: Debug + Send + Sync {
+    /// The secret stored for `account`, if there is one.
+    fn load(&self, account: &str) -> Result<Option<String>, KeyringError>;
+
+    /// Store `value` for `account`, replacing any previous secret.
+    fn save(&self, account: &str, value: &str) -> Result<(), KeyringError>;
+
+    /// Remove the secret of `account`. Returns whether there was one.
+    fn delete(&self, account: &str) -> Result<bool, KeyringError>;
+}
+
+/// The credential store of the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(account: &str) -> Result<keyring::Entry, KeyringError> {
+        Ok(keyring::Entry::new(KEYRING_SERVICE, account)?)
+    }
+}
+
+impl KeyringStore for OsKeyring {
+    fn load(&self, account: &str) -> Result<Option<String>, KeyringError> {
+        match Self::entry(account)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, account: &str, value: &str) -> Result<(), KeyringError> {
+        Ok(Self::entry(account)?.set_password(value)?)
+    }
+
+    fn delete(&self, account: &str) -> Result<bool, KeyringError> {
+        match Self::entry(account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// A store kept in memory, for tests.
+#[derive(Debug, Default)]
+pub struct MemoryKeyring {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl MemoryKeyring {
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, String>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl KeyringStore for MemoryKeyring {
+    fn load(&self, account: &str) -> Result<Option<String>, KeyringError> {
+        Ok(self.entries().get(account).cloned())
+    }
+
+    fn save(&self, account: &str, value: &str) -> Result<(), KeyringError> {
+        self.entries()
+            .insert(account.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, account: &str) -> Result<bool, KeyringError> {
+        Ok(self.entries().remove(account).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn memory_keyring_stores_by_account() -> Result<(), KeyringError> {
+        let keyring = MemoryKeyring::default();
+        keyring.save("OPENROUTER_API_KEY", "first")?;
+        keyring.save("OPENROUTER_API_KEY", "second")?;
+
+        assert_eq!(
+            keyring.load("OPENROUTER_API_KEY")?,
+            Some("second".to_string())
+        );
+        assert_eq!(keyring.load("mcp_oauth/docs")?, None);
+        assert!(keyring.delete("OPENROUTER_API_KEY")?);
+        assert!(!keyring.delete("OPENROUTER_API_KEY")?);
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
 mod mcp_client;
 
+pub use mcp_client::ElicitationHandler;
 pub use mcp_client::McpClient;
+pub use mcp_client::ProgressHandler;
+pub use mcp_client::RootsHandler;
+pub use mcp_client::SamplingHandler;
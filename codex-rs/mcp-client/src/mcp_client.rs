@@ -6,6 +6,9 @@
 //!   2. Sending MCP requests and pairing them with their corresponding
 //!      responses.
 //!   3. Offering a convenience helper for the common `tools/list` request.
+//!   4. Answering `sampling/createMessage`, `roots/list` and
+//!      `elicitation/create` requests from the server through a
+//!      [`SamplingHandler`], a [`RootsHandler`] and an [`ElicitationHandler`].
 //!
 //! The crate hides all JSON‐RPC framing details behind a typed API. Users
 //! interact with the [`ModelContextProtocolRequest`] trait from `mcp-types` to
@@ -13,7 +16,10 @@
 
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
 use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -23,20 +29,48 @@ use anyhow::Result;
 use anyhow::anyhow;
 use mcp_types::CallToolRequest;
 use mcp_types::CallToolRequestParams;
+use mcp_types::CreateMessageRequest;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
+use mcp_types::ElicitRequest;
+use mcp_types::ElicitRequestParams;
+use mcp_types::ElicitResult;
+use mcp_types::GetPromptRequest;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequest;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializedNotification;
 use mcp_types::JSONRPC_VERSION;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
 use mcp_types::JSONRPCMessage;
 use mcp_types::JSONRPCNotification;
 use mcp_types::JSONRPCRequest;
 use mcp_types::JSONRPCResponse;
+use mcp_types::ListPromptsRequest;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequest;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
+use mcp_types::ListRootsRequest;
+use mcp_types::ListRootsResult;
 use mcp_types::ListToolsRequest;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
 use mcp_types::ModelContextProtocolNotification;
 use mcp_types::ModelContextProtocolRequest;
+use mcp_types::PingRequest;
+use mcp_types::ProgressNotification;
+use mcp_types::ProgressNotificationParams;
+use mcp_types::ProgressToken;
+use mcp_types::ReadResourceRequest;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
 use mcp_types::RequestId;
+use mcp_types::Root;
+use mcp_types::RootsListChangedNotification;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use tokio::io::AsyncBufReadExt;
@@ -56,9 +90,56 @@ use tracing::warn;
 /// client API and the IO tasks.
 const CHANNEL_CAPACITY: usize = 128;
 
+/// JSON-RPC error code for requests the client does not handle.
+const METHOD_NOT_FOUND_ERROR_CODE: i64 = -32601;
+
+/// JSON-RPC error code for requests the client failed to answer.
+const INTERNAL_ERROR_CODE: i64 = -32603;
+
 /// Internal representation of a pending request sender.
 type PendingSender = oneshot::Sender<JSONRPCMessage>;
 
+/// Answers the server's `sampling/createMessage` requests. An error is
+/// returned to the server as a JSON-RPC error with the given message.
+pub type SamplingHandler = Arc<
+    dyn Fn(
+            CreateMessageRequestParams,
+        )
+            -> Pin<Box<dyn Future<Output = std::result::Result<CreateMessageResult, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Answers the server's `elicitation/create` requests, usually by asking the
+/// user. An error is returned to the server as a JSON-RPC error with the
+/// given message.
+pub type ElicitationHandler = Arc<
+    dyn Fn(
+            ElicitRequestParams,
+        )
+            -> Pin<Box<dyn Future<Output = std::result::Result<ElicitResult, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Lists the client's roots for the server's `roots/list` requests.
+pub type RootsHandler = Arc<dyn Fn() -> Vec<Root> + Send + Sync>;
+
+/// Receives the `notifications/progress` the server sends for a request.
+pub type ProgressHandler = Arc<dyn Fn(ProgressNotificationParams) + Send + Sync>;
+
+/// Progress token -> handler of the request it was sent with.
+type ProgressHandlers = Arc<StdMutex<HashMap<ProgressToken, ProgressHandler>>>;
+
+/// Handlers for the requests a server sends to the client. Requests without
+/// a handler are refused.
+#[derive(Clone, Default)]
+struct ServerRequestHandlers {
+    sampling: Option<SamplingHandler>,
+    roots: Option<RootsHandler>,
+    elicitation: Option<ElicitationHandler>,
+}
+
 /// A running MCP client instance.
 pub struct McpClient {
     /// Retain this child process until the client is dropped. The Tokio runtime
@@ -76,6 +157,12 @@ pub struct McpClient {
 
     /// Monotonically increasing counter used to generate request IDs.
     id_counter: AtomicI64,
+
+    /// Answers the requests the server sends to the client.
+    request_handlers: Arc<StdMutex<ServerRequestHandlers>>,
+
+    /// Handlers of the progress of in-flight requests.
+    progress_handlers: ProgressHandlers,
 }
 
 impl McpClient {
@@ -137,10 +224,17 @@ impl McpClient {
             })
         };
 
+        let request_handlers = Arc::new(StdMutex::new(ServerRequestHandlers::default()));
+        let progress_handlers: ProgressHandlers = Arc::new(StdMutex::new(HashMap::new()));
+
         // Spawn reader task. It reads line-delimited JSON from the child's
-        // STDOUT and dispatches responses to the pending map.
+        // STDOUT, dispatches responses to the pending map and answers the
+        // server's requests.
         let reader_handle = {
             let pending = pending.clone();
+            let request_handlers = request_handlers.clone();
+            let progress_handlers = progress_handlers.clone();
+            let outgoing_tx = outgoing_tx.clone();
             let mut lines = BufReader::new(stdout).lines();
 
             tokio::spawn(async move {
@@ -153,14 +247,19 @@ impl McpClient {
                         Ok(JSONRPCMessage::Error(err)) => {
                             Self::dispatch_error(err, &pending).await;
                         }
+                        Ok(JSONRPCMessage::Notification(JSONRPCNotification {
+                            method,
+                            params,
+                            ..
+                        })) if method == ProgressNotification::METHOD => {
+                            Self::dispatch_progress(params, &progress_handlers);
+                        }
                         Ok(JSONRPCMessage::Notification(JSONRPCNotification { .. })) => {
-                            // For now we only log server-initiated notifications.
+                            // Other server-initiated notifications are only logged.
                             info!("<- notification: {}", line);
                         }
-                        Ok(other) => {
-                            // Batch responses and requests are currently not
-                            // expected from the server – log and ignore.
-                            info!("<- unhandled message: {:?}", other);
+                        Ok(JSONRPCMessage::Request(request)) => {
+                            Self::dispatch_request(request, &request_handlers, &outgoing_tx);
                         }
                         Err(e) => {
                             error!("failed to deserialize JSONRPCMessage: {e}; line = {}", line)
@@ -181,9 +280,44 @@ impl McpClient {
             outgoing_tx,
             pending,
             id_counter: AtomicI64::new(1),
+            request_handlers,
+            progress_handlers,
         })
     }
 
+    /// Answer the server's `sampling/createMessage` requests with `handler`.
+    /// Set it before [`initialize`](Self::initialize), whose parameters should
+    /// then advertise the `sampling` capability.
+    pub fn set_sampling_handler(&self, handler: SamplingHandler) {
+        if let Ok(mut guard) = self.request_handlers.lock() {
+            guard.sampling = Some(handler);
+        }
+    }
+
+    /// Answer the server's `roots/list` requests with `handler`. Set it
+    /// before [`initialize`](Self::initialize), whose parameters should then
+    /// advertise the `roots` capability.
+    pub fn set_roots_handler(&self, handler: RootsHandler) {
+        if let Ok(mut guard) = self.request_handlers.lock() {
+            guard.roots = Some(handler);
+        }
+    }
+
+    /// Answer the server's `elicitation/create` requests with `handler`. Set
+    /// it before [`initialize`](Self::initialize), whose parameters should
+    /// then advertise the `elicitation` capability.
+    pub fn set_elicitation_handler(&self, handler: ElicitationHandler) {
+        if let Ok(mut guard) = self.request_handlers.lock() {
+            guard.elicitation = Some(handler);
+        }
+    }
+
+    /// Tell the server that the list of roots changed.
+    pub async fn notify_roots_list_changed(&self) -> Result<()> {
+        self.send_notification::<RootsListChangedNotification>(None)
+            .await
+    }
+
     /// Send an arbitrary MCP request and await the typed result.
     ///
     /// If `timeout` is `None` the call waits indefinitely. If `Some(duration)`
@@ -199,10 +333,6 @@ impl McpClient {
         R::Params: Serialize,
         R::Result: DeserializeOwned,
     {
-        // Create a new unique ID.
-        let id = self.id_counter.fetch_add(1, Ordering::SeqCst);
-        let request_id = RequestId::Integer(id);
-
         // Serialize params -> JSON. For many request types `Params` is
         // `Option<T>` and `None` should be encoded as *absence* of the field.
         let params_json = serde_json::to_value(&params)?;
@@ -212,11 +342,30 @@ impl McpClient {
             Some(params_json)
         };
 
+        let result = self
+            .send_raw_request(R::METHOD, params_field, timeout)
+            .await?;
+        let typed: R::Result = serde_json::from_value(result)?;
+        Ok(typed)
+    }
+
+    /// Send a request whose params are already serialized and await its
+    /// untyped result.
+    async fn send_raw_request(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value> {
+        // Create a new unique ID.
+        let id = self.id_counter.fetch_add(1, Ordering::SeqCst);
+        let request_id = RequestId::Integer(id);
+
         let jsonrpc_request = JSONRPCRequest {
             id: request_id.clone(),
             jsonrpc: JSONRPC_VERSION.to_string(),
-            method: R::METHOD.to_string(),
-            params: params_field,
+            method: method.to_string(),
+            params,
         };
 
         let message = JSONRPCMessage::Request(jsonrpc_request);
@@ -265,10 +414,7 @@ impl McpClient {
         };
 
         match msg {
-            JSONRPCMessage::Response(JSONRPCResponse { result, .. }) => {
-                let typed: R::Result = serde_json::from_value(result)?;
-                Ok(typed)
-            }
+            JSONRPCMessage::Response(JSONRPCResponse { result, .. }) => Ok(result),
             JSONRPCMessage::Error(err) => Err(anyhow!(format!(
                 "server returned JSON-RPC error: code = {}, message = {}",
                 err.error.code, err.error.message
@@ -345,6 +491,79 @@ impl McpClient {
         self.send_request::<CallToolRequest>(params, timeout).await
     }
 
+    /// Like [`call_tool`](Self::call_tool), but asks the server to report
+    /// progress and passes every `notifications/progress` it sends for the
+    /// call to `on_progress`.
+    pub async fn call_tool_with_progress(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+        on_progress: ProgressHandler,
+    ) -> Result<mcp_types::CallToolResult> {
+        let params = CallToolRequestParams { name, arguments };
+        debug!("MCP tool call with progress: {params:?}");
+        let token = ProgressToken::Integer(self.id_counter.fetch_add(1, Ordering::SeqCst));
+        let mut params_json = serde_json::to_value(params)?;
+        params_json["_meta"] = serde_json::json!({ "progressToken": token });
+
+        if let Ok(mut guard) = self.progress_handlers.lock() {
+            guard.insert(token.clone(), on_progress);
+        }
+        let result = self
+            .send_raw_request(CallToolRequest::METHOD, Some(params_json), timeout)
+            .await;
+        if let Ok(mut guard) = self.progress_handlers.lock() {
+            guard.remove(&token);
+        }
+        Ok(serde_json::from_value(result?)?)
+    }
+
+    /// Convenience wrapper around `resources/list`.
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        self.send_request::<ListResourcesRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `resources/read`.
+    pub async fn read_resource(
+        &self,
+        params: ReadResourceRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        self.send_request::<ReadResourceRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/list`.
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        self.send_request::<ListPromptsRequest>(params, timeout)
+            .await
+    }
+
+    /// Convenience wrapper around `prompts/get`.
+    pub async fn get_prompt(
+        &self,
+        params: GetPromptRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        self.send_request::<GetPromptRequest>(params, timeout).await
+    }
+
+    /// Send `ping` to check that the server is alive and responsive.
+    pub async fn ping(&self, timeout: Option<Duration>) -> Result<()> {
+        self.send_request::<PingRequest>(None, timeout).await?;
+        Ok(())
+    }
+
     /// Internal helper: route a JSON-RPC *response* object to the pending map.
     async fn dispatch_response(
         resp: JSONRPCResponse,
@@ -372,11 +591,104 @@ impl McpClient {
         }
     }
 
-    /// Internal helper: route a JSON-RPC *error* object to the pending map.
-    async fn dispatch_error(
-        err: mcp_types::JSONRPCError,
-        pending: &Arc<Mutex<HashMap<i64, PendingSender>>>,
+    /// Internal helper: answer a request from the server.
+    /// `sampling/createMessage`, `roots/list` and `elicitation/create` are
+    /// supported when their handler is set; anything else is refused with "method not found".
+    fn dispatch_request(
+        request: JSONRPCRequest,
+        request_handlers: &Arc<StdMutex<ServerRequestHandlers>>,
+        outgoing_tx: &mpsc::Sender<JSONRPCMessage>,
     ) {
+        let handlers = request_handlers
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default();
+        let outgoing_tx = outgoing_tx.clone();
+        tokio::spawn(async move {
+            let JSONRPCRequest {
+                id, method, params, ..
+            } = request;
+            let sampling = handlers
+                .sampling
+                .filter(|_| method == CreateMessageRequest::METHOD);
+            let roots = handlers
+                .roots
+                .filter(|_| method == ListRootsRequest::METHOD);
+            let elicitation = handlers
+                .elicitation
+                .filter(|_| method == ElicitRequest::METHOD);
+            let outcome = if let Some(handler) = sampling {
+                match serde_json::from_value::<CreateMessageRequestParams>(
+                    params.unwrap_or_default(),
+                ) {
+                    Ok(params) => handler(params)
+                        .await
+                        .map(|result| serde_json::to_value(result).unwrap_or_default())
+                        .map_err(|message| (INTERNAL_ERROR_CODE, message)),
+                    Err(e) => Err((INTERNAL_ERROR_CODE, format!("invalid params: {e}"))),
+                }
+            } else if let Some(handler) = roots {
+                Ok(serde_json::to_value(ListRootsResult { roots: handler() }).unwrap_or_default())
+            } else if let Some(handler) = elicitation {
+                match serde_json::from_value::<ElicitRequestParams>(params.unwrap_or_default()) {
+                    Ok(params) => handler(params)
+                        .await
+                        .map(|result| serde_json::to_value(result).unwrap_or_default())
+                        .map_err(|message| (INTERNAL_ERROR_CODE, message)),
+                    Err(e) => Err((INTERNAL_ERROR_CODE, format!("invalid params: {e}"))),
+                }
+            } else {
+                Err((
+                    METHOD_NOT_FOUND_ERROR_CODE,
+                    format!("method not supported: {method}"),
+                ))
+            };
+            let message = match outcome {
+                Ok(result) => JSONRPCMessage::Response(JSONRPCResponse {
+                    id,
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                    result,
+                }),
+                Err((code, message)) => JSONRPCMessage::Error(JSONRPCError {
+                    error: JSONRPCErrorError {
+                        code,
+                        data: None,
+                        message,
+                    },
+                    id,
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                }),
+            };
+            if outgoing_tx.send(message).await.is_err() {
+                error!("failed to send response to server request");
+            }
+        });
+    }
+
+    /// Internal helper: pass a `notifications/progress` to the handler of the
+    /// request it reports on. Progress for finished requests is dropped.
+    fn dispatch_progress(params: Option<serde_json::Value>, progress_handlers: &ProgressHandlers) {
+        let params = match serde_json::from_value::<ProgressNotificationParams>(
+            params.unwrap_or_default(),
+        ) {
+            Ok(params) => params,
+            Err(e) => {
+                warn!("invalid progress notification: {e}");
+                return;
+            }
+        };
+        let handler = progress_handlers
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(&params.progress_token).cloned());
+        match handler {
+            Some(handler) => handler(params),
+            None => debug!("progress for unknown token {:?}", params.progress_token),
+        }
+    }
+
+    /// Internal helper: route a JSON-RPC *error* object to the pending map.
+    async fn dispatch_error(err: JSONRPCError, pending: &Arc<Mutex<HashMap<i64, PendingSender>>>) {
         let id = match err.id {
             RequestId::Integer(i) => i,
             RequestId::String(_) => return, // see comment above
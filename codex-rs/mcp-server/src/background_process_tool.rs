@@ -0,0 +1,342 @@
+//! The `background_process_*` tool-calls, which let a client such as an IDE
+//! start, list, read the logs of and kill the background processes of a
+//! Codex conversation, like the model does with its `background_process`
+//! tool. Starting a process goes through the session's approval and sandbox
+//! checks; approval requests are elicited from the client.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use codex_core::CodexConversation;
+use codex_core::protocol::BackgroundProcessRequest;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::ExecApprovalRequestEvent;
+use codex_core::protocol::Op;
+use codex_core::protocol::Submission;
+use mcp_types::CallToolResult;
+use mcp_types::ContentBlock;
+use mcp_types::RequestId;
+use mcp_types::TextContent;
+use mcp_types::Tool;
+use mcp_types::ToolInputSchema;
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::exec_approval::handle_exec_approval_request;
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::outgoing_message::OutgoingNotificationMeta;
+
+pub(crate) const BACKGROUND_PROCESS_START_TOOL_NAME: &str = "background_process_start";
+pub(crate) const BACKGROUND_PROCESS_LIST_TOOL_NAME: &str = "background_process_list";
+pub(crate) const BACKGROUND_PROCESS_LOGS_TOOL_NAME: &str = "background_process_logs";
+pub(crate) const BACKGROUND_PROCESS_KILL_TOOL_NAME: &str = "background_process_kill";
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundProcessStartParam {
+    /// The conversation whose session runs the process.
+    pub conversation_id: String,
+
+    /// The command and its arguments.
+    pub command: Vec<String>,
+
+    /// Working directory, relative to the session's. Defaults to the
+    /// session's working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    /// Environment variables set on top of the session's environment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundProcessListParam {
+    /// The conversation whose background processes to list.
+    pub conversation_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundProcessIdParam {
+    /// The conversation that started the process.
+    pub conversation_id: String,
+
+    /// The process id returned by `background_process_start` or listed by
+    /// `background_process_list`, such as "bg-1".
+    pub process_id: String,
+}
+
+/// Builds the `Tool` definitions of the `background_process_*` tool-calls.
+pub(crate) fn create_background_process_tools() -> Vec<Tool> {
+    vec![
+        create_tool::<BackgroundProcessStartParam>(
+            BACKGROUND_PROCESS_START_TOOL_NAME,
+            "Start Background Process",
+            "Start a long-running command, such as a dev server, in a Codex conversation's session. It runs under the session's sandbox and approval policy.",
+        ),
+        create_tool::<BackgroundProcessListParam>(
+            BACKGROUND_PROCESS_LIST_TOOL_NAME,
+            "List Background Processes",
+            "List the background processes of a Codex conversation with their state.",
+        ),
+        create_tool::<BackgroundProcessIdParam>(
+            BACKGROUND_PROCESS_LOGS_TOOL_NAME,
+            "Background Process Logs",
+            "Read the recent stdout and stderr of a background process.",
+        ),
+        create_tool::<BackgroundProcessIdParam>(
+            BACKGROUND_PROCESS_KILL_TOOL_NAME,
+            "Kill Background Process",
+            "Stop a background process.",
+        ),
+    ]
+}
+
+fn create_tool<T: JsonSchema>(name: &str, title: &str, description: &str) -> Tool {
+    let schema = SchemaSettings::draft2019_09()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.option_add_null_type = false;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+
+    #[expect(clippy::expect_used)]
+    let schema_value = serde_json::to_value(&schema)
+        .expect("background process tool schema should serialise to JSON");
+
+    let tool_input_schema =
+        serde_json::from_value::<ToolInputSchema>(schema_value).unwrap_or_else(|e| {
+            panic!("failed to create Tool from schema: {e}");
+        });
+
+    Tool {
+        name: name.to_string(),
+        title: Some(title.to_string()),
+        input_schema: tool_input_schema,
+        output_schema: None,
+        description: Some(description.to_string()),
+        annotations: None,
+    }
+}
+
+/// The conversation id and request of a `background_process_*` tool-call.
+pub(crate) fn parse_background_process_call(
+    name: &str,
+    arguments: Option<serde_json::Value>,
+) -> Result<(String, BackgroundProcessRequest), String> {
+    let arguments = arguments.ok_or_else(|| format!("Missing arguments for {name} tool-call."))?;
+    let parse_error = |e: serde_json::Error| format!("Failed to parse arguments for {name}: {e}");
+    match name {
+        BACKGROUND_PROCESS_START_TOOL_NAME => {
+            let BackgroundProcessStartParam {
+                conversation_id,
+                command,
+                cwd,
+                env,
+            } = serde_json::from_value(arguments).map_err(parse_error)?;
+            Ok((
+                conversation_id,
+                BackgroundProcessRequest::Start { command, cwd, env },
+            ))
+        }
+        BACKGROUND_PROCESS_LIST_TOOL_NAME => {
+            let BackgroundProcessListParam { conversation_id } =
+                serde_json::from_value(arguments).map_err(parse_error)?;
+            Ok((conversation_id, BackgroundProcessRequest::List))
+        }
+        BACKGROUND_PROCESS_LOGS_TOOL_NAME | BACKGROUND_PROCESS_KILL_TOOL_NAME => {
+            let BackgroundProcessIdParam {
+                conversation_id,
+                process_id,
+            } = serde_json::from_value(arguments).map_err(parse_error)?;
+            let request = if name == BACKGROUND_PROCESS_LOGS_TOOL_NAME {
+                BackgroundProcessRequest::Logs { process_id }
+            } else {
+                BackgroundProcessRequest::Kill { process_id }
+            };
+            Ok((conversation_id, request))
+        }
+        _ => Err(format!("Unknown tool '{name}'")),
+    }
+}
+
+/// Submit `request` to `conversation` and answer the tool-call with the
+/// session's response, eliciting any approval the request needs on the way.
+pub(crate) async fn run_background_process_tool(
+    conversation: Arc<CodexConversation>,
+    outgoing: Arc<OutgoingMessageSender>,
+    request_id: RequestId,
+    request: BackgroundProcessRequest,
+) {
+    // Use the MCP request id as the submission id so the response can be
+    // told apart from the conversation's other events.
+    let sub_id = match &request_id {
+        RequestId::String(s) => s.clone(),
+        RequestId::Integer(n) => n.to_string(),
+    };
+    let submission = Submission {
+        id: sub_id.clone(),
+        op: Op::BackgroundProcess { request },
+    };
+    if let Err(e) = conversation.submit_with_id(submission).await {
+        let result = error_result(format!("Failed to submit background process request: {e}"));
+        outgoing.send_response(request_id, result).await;
+        return;
+    }
+
+    loop {
+        let event = match conversation.next_event().await {
+            Ok(event) => event,
+            Err(e) => {
+                let result = error_result(format!("Codex runtime error: {e}"));
+                outgoing.send_response(request_id, result).await;
+                return;
+            }
+        };
+        outgoing
+            .send_event_as_notification(
+                &event,
+                Some(OutgoingNotificationMeta::new(Some(request_id.clone()))),
+            )
+            .await;
+        if event.id != sub_id {
+            continue;
+        }
+        match event.msg {
+            EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+                command,
+                cwd,
+                call_id,
+                reason: _,
+            }) => {
+                handle_exec_approval_request(
+                    command,
+                    cwd,
+                    outgoing.clone(),
+                    conversation.clone(),
+                    request_id.clone(),
+                    sub_id.clone(),
+                    event.id.clone(),
+                    call_id,
+                )
+                .await;
+            }
+            EventMsg::BackgroundProcessResponse(response) => {
+                let result = match response.result {
+                    Ok(output) => CallToolResult {
+                        structured_content: serde_json::from_str(&output).ok(),
+                        content: vec![ContentBlock::TextContent(TextContent {
+                            r#type: "text".to_string(),
+                            text: output,
+                            annotations: None,
+                        })],
+                        is_error: None,
+                    },
+                    Err(message) => error_result(message),
+                };
+                outgoing.send_response(request_id, result).await;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn error_result(text: String) -> CallToolResult {
+    CallToolResult {
+        content: vec![ContentBlock::TextContent(TextContent {
+            r#type: "text".to_string(),
+            text,
+            annotations: None,
+        })],
+        is_error: Some(true),
+        structured_content: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn parses_tool_calls_into_requests() {
+        assert_eq!(
+            Ok((
+                "c1".to_string(),
+                BackgroundProcessRequest::Start {
+                    command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+                    cwd: Some("web".to_string()),
+                    env: None,
+                }
+            )),
+            parse_background_process_call(
+                BACKGROUND_PROCESS_START_TOOL_NAME,
+                Some(json!({
+                    "conversationId": "c1",
+                    "command": ["npm", "run", "dev"],
+                    "cwd": "web",
+                })),
+            )
+        );
+        assert_eq!(
+            Ok((
+                "c1".to_string(),
+                BackgroundProcessRequest::Kill {
+                    process_id: "bg-1".to_string()
+                }
+            )),
+            parse_background_process_call(
+                BACKGROUND_PROCESS_KILL_TOOL_NAME,
+                Some(json!({"conversationId": "c1", "processId": "bg-1"})),
+            )
+        );
+        assert!(
+            parse_background_process_call(
+                BACKGROUND_PROCESS_LOGS_TOOL_NAME,
+                Some(json!({"conversationId": "c1"})),
+            )
+            .is_err()
+        );
+        assert!(parse_background_process_call(BACKGROUND_PROCESS_LIST_TOOL_NAME, None).is_err());
+    }
+
+    #[test]
+    fn verify_background_process_tool_json_schemas() {
+        let tools = create_background_process_tools();
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(
+            vec![
+                "background_process_start",
+                "background_process_list",
+                "background_process_logs",
+                "background_process_kill",
+            ],
+            names
+        );
+        let schema = serde_json::to_value(&tools[2].input_schema).expect("schema serializes");
+        assert_eq!(
+            json!({
+                "type": "object",
+                "properties": {
+                    "conversationId": {
+                        "description": "The conversation that started the process.",
+                        "type": "string"
+                    },
+                    "processId": {
+                        "description": "The process id returned by `background_process_start` or listed by `background_process_list`, such as \"bg-1\".",
+                        "type": "string"
+                    }
+                },
+                "required": ["conversationId", "processId"]
+            }),
+            schema
+        );
+    }
+}
@@ -15,6 +15,9 @@ use codex_core::NewConversation;
 use codex_core::config::Config as CodexConfig;
 use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalBatchRequestEvent;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::ElicitationAction;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
@@ -192,6 +195,59 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::ApprovalBatchRequest(ApprovalBatchRequestEvent { items }) => {
+                        // Elicit each item separately; answering with the
+                        // item's call_id resolves that entry of the batch.
+                        for ExecApprovalRequestEvent {
+                            call_id,
+                            command,
+                            cwd,
+                            reason: _,
+                        } in items
+                        {
+                            handle_exec_approval_request(
+                                command,
+                                cwd,
+                                outgoing.clone(),
+                                codex.clone(),
+                                request_id.clone(),
+                                request_id_str.clone(),
+                                call_id.clone(),
+                                call_id,
+                            )
+                            .await;
+                        }
+                        continue;
+                    }
+                    EventMsg::BudgetExceeded(_) => {
+                        // A tool call has no way to ask whether to go on, so
+                        // the turn ends here.
+                        if let Err(err) = codex
+                            .submit(Op::BudgetDecision {
+                                id: event.id.clone(),
+                                decision: BudgetDecision::Stop,
+                            })
+                            .await
+                        {
+                            tracing::error!("failed to submit BudgetDecision: {err}");
+                        }
+                        continue;
+                    }
+                    EventMsg::ElicitationRequest(request) => {
+                        // The form is not relayed to this tool call's
+                        // client, so the request is declined.
+                        if let Err(err) = codex
+                            .submit(Op::ResolveElicitation {
+                                id: request.id,
+                                action: ElicitationAction::Decline,
+                                content: None,
+                            })
+                            .await
+                        {
+                            tracing::error!("failed to submit ResolveElicitation: {err}");
+                        }
+                        continue;
+                    }
                     EventMsg::Error(err_event) => {
                         // Return a response to conclude the tool call when the Codex session reports an error (e.g., interruption).
                         let result = json!({
@@ -251,6 +307,9 @@ async fn run_codex_tool_session_inner(
                     EventMsg::AgentReasoningDelta(_) => {
                         // TODO: think how we want to support this in the MCP
                     }
+                    EventMsg::ToolCallArgumentsDelta(_) => {
+                        // TODO: think how we want to support this in the MCP
+                    }
                     EventMsg::AgentMessage(AgentMessageEvent { .. }) => {
                         // TODO: think how we want to support this in the MCP
                     }
@@ -261,15 +320,26 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::AgentReasoning(_)
                     | EventMsg::AgentReasoningSectionBreak(_)
                     | EventMsg::McpToolCallBegin(_)
+                    | EventMsg::McpToolCallProgress(_)
                     | EventMsg::McpToolCallEnd(_)
                     | EventMsg::McpListToolsResponse(_)
                     | EventMsg::ListCustomPromptsResponse(_)
+                    | EventMsg::McpListPromptsResponse(_)
+                    | EventMsg::McpGetPromptResponse(_)
+                    | EventMsg::McpServerHealth(_)
+                    | EventMsg::McpServerConnection(_)
+                    | EventMsg::ConfigReloaded(_)
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
                     | EventMsg::BackgroundEvent(_)
                     | EventMsg::BackgroundProcessStatus(_)
+                    | EventMsg::SandboxDenied(_)
+                    | EventMsg::SessionSettingsUpdated(_)
                     | EventMsg::StreamError(_)
+                    | EventMsg::ModelFailover(_)
+                    | EventMsg::ContextBudget(_)
+                    | EventMsg::CostSummary(_)
                     | EventMsg::PatchApplyBegin(_)
                     | EventMsg::PatchApplyEnd(_)
                     | EventMsg::TurnDiff(_)
@@ -282,6 +352,25 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::ViewImageToolCall(_)
+                    | EventMsg::PayloadFrame(_)
+                    | EventMsg::UserInputQueue(_)
+                    | EventMsg::SessionBusy(_)
+                    | EventMsg::ContextCompacted(_)
+                    | EventMsg::CheckpointCreated(_)
+                    | EventMsg::CheckpointRestored(_)
+                    | EventMsg::TurnUndone(_)
+                    | EventMsg::SessionDiff(_)
+                    | EventMsg::BackgroundProcessResponse(_)
+                    | EventMsg::TurnStats(_)
+                    | EventMsg::WorkspaceState(_)
+                    | EventMsg::TurnGitState(_)
+                    | EventMsg::SessionTitle(_)
+                    | EventMsg::SessionTags(_)
+                    | EventMsg::SessionEnv(_)
+                    | EventMsg::TurnStatsResponse(_)
+                    | EventMsg::PinnedContext(_)
+                    | EventMsg::SubAgentBegin(_)
+                    | EventMsg::SubAgentEnd(_)
                     | EventMsg::EnteredReviewMode(_)
                     | EventMsg::ExitedReviewMode(_) => {
                         // For now, we do not do anything extra for these
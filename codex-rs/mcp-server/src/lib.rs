@@ -20,6 +20,7 @@ use tracing::error;
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
+mod background_process_tool;
 mod codex_tool_config;
 mod codex_tool_runner;
 mod error_code;
@@ -27,17 +28,28 @@ mod exec_approval;
 pub(crate) mod message_processor;
 mod outgoing_message;
 mod patch_approval;
+mod session_tool;
 
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
 use crate::outgoing_message::OutgoingMessageSender;
 
+pub use crate::background_process_tool::BackgroundProcessIdParam;
+pub use crate::background_process_tool::BackgroundProcessListParam;
+pub use crate::background_process_tool::BackgroundProcessStartParam;
 pub use crate::codex_tool_config::CodexToolCallParam;
 pub use crate::codex_tool_config::CodexToolCallReplyParam;
 pub use crate::exec_approval::ExecApprovalElicitRequestParams;
 pub use crate::exec_approval::ExecApprovalResponse;
 pub use crate::patch_approval::PatchApprovalElicitRequestParams;
 pub use crate::patch_approval::PatchApprovalResponse;
+pub use crate::session_tool::CodexSessionApprovalKind;
+pub use crate::session_tool::CodexSessionApprovalParam;
+pub use crate::session_tool::CodexSessionCreateParam;
+pub use crate::session_tool::CodexSessionDecision;
+pub use crate::session_tool::CodexSessionListParam;
+pub use crate::session_tool::CodexSessionResumeParam;
+pub use crate::session_tool::CodexSessionSendInputParam;
 
 /// Size of the bounded channels used to communicate between tasks. The value
 /// is a balance between throughput and memory usage – 128 messages should be
@@ -1,12 +1,29 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::path::PathBuf;
 
+use crate::background_process_tool::BACKGROUND_PROCESS_KILL_TOOL_NAME;
+use crate::background_process_tool::BACKGROUND_PROCESS_LIST_TOOL_NAME;
+use crate::background_process_tool::BACKGROUND_PROCESS_LOGS_TOOL_NAME;
+use crate::background_process_tool::BACKGROUND_PROCESS_START_TOOL_NAME;
+use crate::background_process_tool::create_background_process_tools;
+use crate::background_process_tool::parse_background_process_call;
+use crate::background_process_tool::run_background_process_tool;
 use crate::codex_tool_config::CodexToolCallParam;
 use crate::codex_tool_config::CodexToolCallReplyParam;
 use crate::codex_tool_config::create_tool_for_codex_tool_call_param;
 use crate::codex_tool_config::create_tool_for_codex_tool_call_reply_param;
 use crate::error_code::INVALID_REQUEST_ERROR_CODE;
 use crate::outgoing_message::OutgoingMessageSender;
+use crate::session_tool::SESSION_APPROVAL_TOOL_NAME;
+use crate::session_tool::SESSION_CREATE_TOOL_NAME;
+use crate::session_tool::SESSION_LIST_TOOL_NAME;
+use crate::session_tool::SESSION_RESUME_TOOL_NAME;
+use crate::session_tool::SESSION_SEND_INPUT_TOOL_NAME;
+use crate::session_tool::SessionToolContext;
+use crate::session_tool::create_session_tools;
+use crate::session_tool::parse_session_tool_call;
+use crate::session_tool::run_session_tool;
 use codex_protocol::ConversationId;
 use codex_protocol::protocol::SessionSource;
 
@@ -41,7 +58,10 @@ pub(crate) struct MessageProcessor {
     initialized: bool,
     codex_linux_sandbox_exe: Option<PathBuf>,
     conversation_manager: Arc<ConversationManager>,
+    auth_manager: Arc<AuthManager>,
+    codex_home: PathBuf,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, ConversationId>>>,
+    attached_conversations: Arc<Mutex<HashSet<ConversationId>>>,
 }
 
 impl MessageProcessor {
@@ -54,14 +74,19 @@ impl MessageProcessor {
     ) -> Self {
         let outgoing = Arc::new(outgoing);
         let auth_manager = AuthManager::shared(config.codex_home.clone(), false);
-        let conversation_manager =
-            Arc::new(ConversationManager::new(auth_manager, SessionSource::Mcp));
+        let conversation_manager = Arc::new(ConversationManager::new(
+            auth_manager.clone(),
+            SessionSource::Mcp,
+        ));
         Self {
             outgoing,
             initialized: false,
             codex_linux_sandbox_exe,
             conversation_manager,
+            auth_manager,
+            codex_home: config.codex_home.clone(),
             running_requests_id_to_codex_uuid: Arc::new(Mutex::new(HashMap::new())),
+            attached_conversations: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -299,10 +324,14 @@ impl MessageProcessor {
     ) {
         tracing::trace!("tools/list -> {params:?}");
         let result = ListToolsResult {
-            tools: vec![
+            tools: [
                 create_tool_for_codex_tool_call_param(),
                 create_tool_for_codex_tool_call_reply_param(),
-            ],
+            ]
+            .into_iter()
+            .chain(create_background_process_tools())
+            .chain(create_session_tools())
+            .collect(),
             next_cursor: None,
         };
 
@@ -324,6 +353,20 @@ impl MessageProcessor {
                 self.handle_tool_call_codex_session_reply(id, arguments)
                     .await
             }
+            BACKGROUND_PROCESS_START_TOOL_NAME
+            | BACKGROUND_PROCESS_LIST_TOOL_NAME
+            | BACKGROUND_PROCESS_LOGS_TOOL_NAME
+            | BACKGROUND_PROCESS_KILL_TOOL_NAME => {
+                self.handle_tool_call_background_process(id, &name, arguments)
+                    .await
+            }
+            SESSION_CREATE_TOOL_NAME
+            | SESSION_RESUME_TOOL_NAME
+            | SESSION_LIST_TOOL_NAME
+            | SESSION_SEND_INPUT_TOOL_NAME
+            | SESSION_APPROVAL_TOOL_NAME => {
+                self.handle_tool_call_session(id, &name, arguments).await
+            }
             _ => {
                 let result = CallToolResult {
                     content: vec![ContentBlock::TextContent(TextContent {
@@ -528,6 +571,109 @@ impl MessageProcessor {
         });
     }
 
+    async fn handle_tool_call_background_process(
+        &self,
+        request_id: RequestId,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) {
+        let parsed = parse_background_process_call(name, arguments).and_then(
+            |(conversation_id, request)| {
+                ConversationId::from_string(&conversation_id)
+                    .map(|conversation_id| (conversation_id, request))
+                    .map_err(|e| format!("Failed to parse conversation_id: {e}"))
+            },
+        );
+        let (conversation_id, request) = match parsed {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                tracing::error!("{message}");
+                let result = CallToolResult {
+                    content: vec![ContentBlock::TextContent(TextContent {
+                        r#type: "text".to_owned(),
+                        text: message,
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                    structured_content: None,
+                };
+                self.send_response::<mcp_types::CallToolRequest>(request_id, result)
+                    .await;
+                return;
+            }
+        };
+
+        let conversation = match self
+            .conversation_manager
+            .get_conversation(conversation_id)
+            .await
+        {
+            Ok(c) => c,
+            Err(_) => {
+                tracing::warn!("Session not found for conversation_id: {conversation_id}");
+                let result = CallToolResult {
+                    content: vec![ContentBlock::TextContent(TextContent {
+                        r#type: "text".to_owned(),
+                        text: format!("Session not found for conversation_id: {conversation_id}"),
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                    structured_content: None,
+                };
+                self.send_response::<mcp_types::CallToolRequest>(request_id, result)
+                    .await;
+                return;
+            }
+        };
+
+        // Starting a process may wait for the client to approve it.
+        let outgoing = self.outgoing.clone();
+        task::spawn(async move {
+            run_background_process_tool(conversation, outgoing, request_id, request).await;
+        });
+    }
+
+    async fn handle_tool_call_session(
+        &self,
+        request_id: RequestId,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) {
+        let call = match parse_session_tool_call(name, arguments) {
+            Ok(call) => call,
+            Err(message) => {
+                tracing::error!("{message}");
+                let result = CallToolResult {
+                    content: vec![ContentBlock::TextContent(TextContent {
+                        r#type: "text".to_owned(),
+                        text: message,
+                        annotations: None,
+                    })],
+                    is_error: Some(true),
+                    structured_content: None,
+                };
+                self.send_response::<mcp_types::CallToolRequest>(request_id, result)
+                    .await;
+                return;
+            }
+        };
+
+        let ctx = SessionToolContext {
+            conversation_manager: self.conversation_manager.clone(),
+            auth_manager: self.auth_manager.clone(),
+            outgoing: self.outgoing.clone(),
+            codex_home: self.codex_home.clone(),
+            codex_linux_sandbox_exe: self.codex_linux_sandbox_exe.clone(),
+            attached_conversations: self.attached_conversations.clone(),
+        };
+        // Starting a session loads its configuration and may take a while.
+        task::spawn(async move {
+            let outgoing = ctx.outgoing.clone();
+            let result = run_session_tool(call, ctx).await;
+            outgoing.send_response(request_id, result).await;
+        });
+    }
+
     fn handle_set_level(
         &self,
         params: <mcp_types::SetLevelRequest as mcp_types::ModelContextProtocolRequest>::Params,
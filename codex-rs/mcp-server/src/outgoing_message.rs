@@ -3,6 +3,7 @@ use std::sync::atomic::AtomicI64;
 use std::sync::atomic::Ordering;
 
 use codex_core::protocol::Event;
+use codex_protocol::ConversationId;
 use mcp_types::JSONRPC_VERSION;
 use mcp_types::JSONRPCError;
 use mcp_types::JSONRPCErrorError;
@@ -209,11 +210,26 @@ pub(crate) struct OutgoingNotificationParams {
 #[serde(rename_all = "camelCase")]
 pub(crate) struct OutgoingNotificationMeta {
     pub request_id: Option<RequestId>,
+
+    /// Set on the events of a session driven by the `codex-session-*`
+    /// tool-calls, which are not tied to a single request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<ConversationId>,
 }
 
 impl OutgoingNotificationMeta {
     pub(crate) fn new(request_id: Option<RequestId>) -> Self {
-        Self { request_id }
+        Self {
+            request_id,
+            conversation_id: None,
+        }
+    }
+
+    pub(crate) fn for_conversation(conversation_id: ConversationId) -> Self {
+        Self {
+            request_id: None,
+            conversation_id: Some(conversation_id),
+        }
     }
 }
 
@@ -301,6 +317,7 @@ mod tests {
         };
         let meta = OutgoingNotificationMeta {
             request_id: Some(RequestId::String("123".to_string())),
+            conversation_id: None,
         };
 
         outgoing_message_sender
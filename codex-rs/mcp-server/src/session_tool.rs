@@ -0,0 +1,671 @@
+//! The `codex-session-*` tool-calls, which let a client such as an IDE drive a
+//! Codex conversation interactively: create or resume a session, list the
+//! recorded ones, send input and answer approval requests. The events of a
+//! session attached this way are streamed as `codex/event` notifications for
+//! as long as it runs, rather than for the duration of one tool-call.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_core::AuthManager;
+use codex_core::CodexConversation;
+use codex_core::ConversationManager;
+use codex_core::Cursor;
+use codex_core::NewConversation;
+use codex_core::RolloutRecorder;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_protocol::ConversationId;
+use codex_protocol::protocol::SessionMeta;
+use codex_protocol::protocol::SessionSource;
+use mcp_types::CallToolResult;
+use mcp_types::ContentBlock;
+use mcp_types::TextContent;
+use mcp_types::Tool;
+use mcp_types::ToolInputSchema;
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::codex_tool_config::CodexToolCallApprovalPolicy;
+use crate::codex_tool_config::CodexToolCallParam;
+use crate::codex_tool_config::CodexToolCallSandboxMode;
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::outgoing_message::OutgoingNotificationMeta;
+
+pub(crate) const SESSION_CREATE_TOOL_NAME: &str = "codex-session-create";
+pub(crate) const SESSION_RESUME_TOOL_NAME: &str = "codex-session-resume";
+pub(crate) const SESSION_LIST_TOOL_NAME: &str = "codex-session-list";
+pub(crate) const SESSION_SEND_INPUT_TOOL_NAME: &str = "codex-session-send-input";
+pub(crate) const SESSION_APPROVAL_TOOL_NAME: &str = "codex-session-approval";
+
+/// Sessions recorded by these sources are listed by `codex-session-list`.
+const LISTED_SESSION_SOURCES: &[SessionSource] = &[
+    SessionSource::Cli,
+    SessionSource::VSCode,
+    SessionSource::Mcp,
+];
+
+const DEFAULT_LIST_PAGE_SIZE: usize = 25;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexSessionCreateParam {
+    /// Optional override for the model name (e.g. "o3", "o4-mini").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Configuration profile from config.toml to specify default options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Working directory for the session. If relative, it is resolved against
+    /// the server process's current working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    /// Approval policy for shell commands generated by the model:
+    /// `untrusted`, `on-failure`, `on-request`, `never`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<CodexToolCallApprovalPolicy>,
+
+    /// Sandbox mode: `read-only`, `workspace-write`, or `danger-full-access`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<CodexToolCallSandboxMode>,
+
+    /// Individual config settings that will override what is in
+    /// CODEX_HOME/config.toml.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexSessionResumeParam {
+    /// Path of the session's rollout file, as listed by `codex-session-list`.
+    pub path: String,
+
+    /// Configuration profile from config.toml to specify default options.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Working directory for the resumed session. Defaults to the server
+    /// process's current working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+
+    /// Individual config settings that will override what is in
+    /// CODEX_HOME/config.toml.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<HashMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexSessionListParam {
+    /// Maximum number of sessions to return. Defaults to 25.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+
+    /// The `nextCursor` of the previous page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexSessionSendInputParam {
+    /// The conversation returned by `codex-session-create` or
+    /// `codex-session-resume`.
+    pub conversation_id: String,
+
+    /// The user input to send. It starts a turn, or is queued if one is
+    /// running.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexSessionApprovalParam {
+    /// The conversation that asked for approval.
+    pub conversation_id: String,
+
+    /// The kind of request being answered.
+    pub kind: CodexSessionApprovalKind,
+
+    /// The `id` of the `codex/event` notification carrying the request; for
+    /// an item of an approval batch, the item's `call_id`.
+    pub id: String,
+
+    /// The decision. Budget requests continue when approved and stop
+    /// otherwise.
+    pub decision: CodexSessionDecision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CodexSessionApprovalKind {
+    /// An `exec_approval_request` or an item of an `approval_batch_request`.
+    Exec,
+    /// An `apply_patch_approval_request`.
+    Patch,
+    /// A `budget_exceeded` event.
+    Budget,
+}
+
+/// Custom enum mirroring [`ReviewDecision`], but with `JsonSchema` support.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CodexSessionDecision {
+    Approved,
+    ApprovedForSession,
+    Denied,
+    Abort,
+}
+
+impl From<CodexSessionDecision> for ReviewDecision {
+    fn from(value: CodexSessionDecision) -> Self {
+        match value {
+            CodexSessionDecision::Approved => ReviewDecision::Approved,
+            CodexSessionDecision::ApprovedForSession => ReviewDecision::ApprovedForSession,
+            CodexSessionDecision::Denied => ReviewDecision::Denied,
+            CodexSessionDecision::Abort => ReviewDecision::Abort,
+        }
+    }
+}
+
+/// A parsed `codex-session-*` tool-call.
+#[derive(Debug, PartialEq)]
+pub(crate) enum SessionToolCall {
+    Create(CodexSessionCreateParam),
+    Resume(CodexSessionResumeParam),
+    List(CodexSessionListParam),
+    SendInput(CodexSessionSendInputParam),
+    Approval(CodexSessionApprovalParam),
+}
+
+/// What the `codex-session-*` tool-calls need from the message processor.
+#[derive(Clone)]
+pub(crate) struct SessionToolContext {
+    pub conversation_manager: Arc<ConversationManager>,
+    pub auth_manager: Arc<AuthManager>,
+    pub outgoing: Arc<OutgoingMessageSender>,
+    pub codex_home: PathBuf,
+    pub codex_linux_sandbox_exe: Option<PathBuf>,
+    /// Conversations whose events are being streamed to the client.
+    pub attached_conversations: Arc<Mutex<HashSet<ConversationId>>>,
+}
+
+/// Builds the `Tool` definitions of the `codex-session-*` tool-calls.
+pub(crate) fn create_session_tools() -> Vec<Tool> {
+    vec![
+        create_tool::<CodexSessionCreateParam>(
+            SESSION_CREATE_TOOL_NAME,
+            "Create Codex Session",
+            "Start an interactive Codex session without running a turn. Its events are streamed as codex/event notifications until it shuts down.",
+        ),
+        create_tool::<CodexSessionResumeParam>(
+            SESSION_RESUME_TOOL_NAME,
+            "Resume Codex Session",
+            "Resume a recorded Codex session from its rollout file as an interactive session.",
+        ),
+        create_tool::<CodexSessionListParam>(
+            SESSION_LIST_TOOL_NAME,
+            "List Codex Sessions",
+            "List recorded Codex sessions, newest first.",
+        ),
+        create_tool::<CodexSessionSendInputParam>(
+            SESSION_SEND_INPUT_TOOL_NAME,
+            "Send Input to Codex Session",
+            "Send user input to an interactive Codex session. Returns once the input is submitted; the turn's progress arrives as notifications.",
+        ),
+        create_tool::<CodexSessionApprovalParam>(
+            SESSION_APPROVAL_TOOL_NAME,
+            "Answer Codex Approval Request",
+            "Answer an approval or budget request raised by an interactive Codex session.",
+        ),
+    ]
+}
+
+fn create_tool<T: JsonSchema>(name: &str, title: &str, description: &str) -> Tool {
+    let schema = SchemaSettings::draft2019_09()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.option_add_null_type = false;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+
+    #[expect(clippy::expect_used)]
+    let schema_value =
+        serde_json::to_value(&schema).expect("session tool schema should serialise to JSON");
+
+    let tool_input_schema =
+        serde_json::from_value::<ToolInputSchema>(schema_value).unwrap_or_else(|e| {
+            panic!("failed to create Tool from schema: {e}");
+        });
+
+    Tool {
+        name: name.to_string(),
+        title: Some(title.to_string()),
+        input_schema: tool_input_schema,
+        output_schema: None,
+        description: Some(description.to_string()),
+        annotations: None,
+    }
+}
+
+pub(crate) fn parse_session_tool_call(
+    name: &str,
+    arguments: Option<serde_json::Value>,
+) -> Result<SessionToolCall, String> {
+    // Creating and listing sessions take no required arguments.
+    let arguments = match (name, arguments) {
+        (SESSION_CREATE_TOOL_NAME | SESSION_LIST_TOOL_NAME, None) => json!({}),
+        (_, Some(arguments)) => arguments,
+        (_, None) => return Err(format!("Missing arguments for {name} tool-call.")),
+    };
+    let parse_error = |e: serde_json::Error| format!("Failed to parse arguments for {name}: {e}");
+    match name {
+        SESSION_CREATE_TOOL_NAME => serde_json::from_value(arguments)
+            .map(SessionToolCall::Create)
+            .map_err(parse_error),
+        SESSION_RESUME_TOOL_NAME => serde_json::from_value(arguments)
+            .map(SessionToolCall::Resume)
+            .map_err(parse_error),
+        SESSION_LIST_TOOL_NAME => serde_json::from_value(arguments)
+            .map(SessionToolCall::List)
+            .map_err(parse_error),
+        SESSION_SEND_INPUT_TOOL_NAME => serde_json::from_value(arguments)
+            .map(SessionToolCall::SendInput)
+            .map_err(parse_error),
+        SESSION_APPROVAL_TOOL_NAME => serde_json::from_value(arguments)
+            .map(SessionToolCall::Approval)
+            .map_err(parse_error),
+        _ => Err(format!("Unknown tool '{name}'")),
+    }
+}
+
+/// Run a `codex-session-*` tool-call and build its result.
+pub(crate) async fn run_session_tool(
+    call: SessionToolCall,
+    ctx: SessionToolContext,
+) -> CallToolResult {
+    let result = match call {
+        SessionToolCall::Create(param) => create_session(param, &ctx).await,
+        SessionToolCall::Resume(param) => resume_session(param, &ctx).await,
+        SessionToolCall::List(param) => list_sessions(param, &ctx).await,
+        SessionToolCall::SendInput(param) => send_input(param, &ctx).await,
+        SessionToolCall::Approval(param) => answer_approval(param, &ctx).await,
+    };
+    match result {
+        Ok(value) => CallToolResult {
+            content: vec![ContentBlock::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text: value.to_string(),
+                annotations: None,
+            })],
+            is_error: None,
+            structured_content: Some(value),
+        },
+        Err(text) => CallToolResult {
+            content: vec![ContentBlock::TextContent(TextContent {
+                r#type: "text".to_string(),
+                text,
+                annotations: None,
+            })],
+            is_error: Some(true),
+            structured_content: None,
+        },
+    }
+}
+
+async fn create_session(
+    param: CodexSessionCreateParam,
+    ctx: &SessionToolContext,
+) -> Result<serde_json::Value, String> {
+    let CodexSessionCreateParam {
+        model,
+        profile,
+        cwd,
+        approval_policy,
+        sandbox,
+        config,
+    } = param;
+    let (_, config) = CodexToolCallParam {
+        model,
+        profile,
+        cwd,
+        approval_policy,
+        sandbox,
+        config,
+        ..Default::default()
+    }
+    .into_config(ctx.codex_linux_sandbox_exe.clone())
+    .map_err(|e| format!("Failed to load Codex configuration from overrides: {e}"))?;
+    let new_conversation = ctx
+        .conversation_manager
+        .new_conversation(config)
+        .await
+        .map_err(|e| format!("Failed to start Codex session: {e}"))?;
+    Ok(attach_session(new_conversation, ctx).await)
+}
+
+async fn resume_session(
+    param: CodexSessionResumeParam,
+    ctx: &SessionToolContext,
+) -> Result<serde_json::Value, String> {
+    let CodexSessionResumeParam {
+        path,
+        profile,
+        cwd,
+        config,
+    } = param;
+    let (_, config) = CodexToolCallParam {
+        profile,
+        cwd,
+        config,
+        ..Default::default()
+    }
+    .into_config(ctx.codex_linux_sandbox_exe.clone())
+    .map_err(|e| format!("Failed to load Codex configuration from overrides: {e}"))?;
+    let new_conversation = ctx
+        .conversation_manager
+        .resume_conversation_from_rollout(config, PathBuf::from(&path), ctx.auth_manager.clone())
+        .await
+        .map_err(|e| format!("Failed to resume Codex session from {path}: {e}"))?;
+    Ok(attach_session(new_conversation, ctx).await)
+}
+
+/// Start streaming the events of a new session and describe it.
+async fn attach_session(
+    new_conversation: NewConversation,
+    ctx: &SessionToolContext,
+) -> serde_json::Value {
+    let NewConversation {
+        conversation_id,
+        conversation,
+        session_configured,
+    } = new_conversation;
+    let description = json!({
+        "conversationId": conversation_id,
+        "model": session_configured.model,
+        "rolloutPath": session_configured.rollout_path,
+    });
+
+    ctx.attached_conversations
+        .lock()
+        .await
+        .insert(conversation_id);
+    let session_configured_event = Event {
+        id: "".to_string(),
+        msg: EventMsg::SessionConfigured(session_configured),
+    };
+    ctx.outgoing
+        .send_event_as_notification(
+            &session_configured_event,
+            Some(OutgoingNotificationMeta::for_conversation(conversation_id)),
+        )
+        .await;
+    tokio::spawn(stream_session_events(
+        conversation_id,
+        conversation,
+        ctx.outgoing.clone(),
+        ctx.attached_conversations.clone(),
+    ));
+    description
+}
+
+/// Forward every event of an attached session to the client until it shuts
+/// down. Approval requests are left to `codex-session-approval`.
+async fn stream_session_events(
+    conversation_id: ConversationId,
+    conversation: Arc<CodexConversation>,
+    outgoing: Arc<OutgoingMessageSender>,
+    attached_conversations: Arc<Mutex<HashSet<ConversationId>>>,
+) {
+    loop {
+        let event = match conversation.next_event().await {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::error!("stopped streaming events of {conversation_id}: {e}");
+                break;
+            }
+        };
+        outgoing
+            .send_event_as_notification(
+                &event,
+                Some(OutgoingNotificationMeta::for_conversation(conversation_id)),
+            )
+            .await;
+        if matches!(event.msg, EventMsg::ShutdownComplete) {
+            break;
+        }
+    }
+    attached_conversations.lock().await.remove(&conversation_id);
+}
+
+async fn list_sessions(
+    param: CodexSessionListParam,
+    ctx: &SessionToolContext,
+) -> Result<serde_json::Value, String> {
+    let CodexSessionListParam { page_size, cursor } = param;
+    // The cursor is the string form `Cursor` serializes to.
+    let cursor = cursor
+        .map(|cursor| {
+            serde_json::from_value::<Cursor>(serde_json::Value::String(cursor))
+                .map_err(|e| format!("Invalid cursor: {e}"))
+        })
+        .transpose()?;
+    let page = RolloutRecorder::list_conversations(
+        &ctx.codex_home,
+        page_size.unwrap_or(DEFAULT_LIST_PAGE_SIZE),
+        cursor.as_ref(),
+        LISTED_SESSION_SOURCES,
+    )
+    .await
+    .map_err(|e| format!("Failed to list Codex sessions: {e}"))?;
+
+    let attached = ctx.attached_conversations.lock().await.clone();
+    let sessions: Vec<serde_json::Value> = page
+        .items
+        .into_iter()
+        .map(|item| {
+            let conversation_id = item
+                .head
+                .first()
+                .and_then(|meta| serde_json::from_value::<SessionMeta>(meta.clone()).ok())
+                .map(|meta| meta.id);
+            json!({
+                "conversationId": conversation_id,
+                "path": item.path,
+                "title": item.title,
+                "createdAt": item.created_at,
+                "updatedAt": item.updated_at,
+                "attached": conversation_id.is_some_and(|id| attached.contains(&id)),
+            })
+        })
+        .collect();
+    let next_cursor = page
+        .next_cursor
+        .and_then(|cursor| serde_json::to_value(cursor).ok());
+    Ok(json!({
+        "sessions": sessions,
+        "nextCursor": next_cursor,
+    }))
+}
+
+async fn send_input(
+    param: CodexSessionSendInputParam,
+    ctx: &SessionToolContext,
+) -> Result<serde_json::Value, String> {
+    let CodexSessionSendInputParam {
+        conversation_id,
+        text,
+    } = param;
+    let conversation = attached_conversation(&conversation_id, ctx).await?;
+    let submission_id = conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text }],
+        })
+        .await
+        .map_err(|e| format!("Failed to submit user input: {e}"))?;
+    Ok(json!({ "submissionId": submission_id }))
+}
+
+async fn answer_approval(
+    param: CodexSessionApprovalParam,
+    ctx: &SessionToolContext,
+) -> Result<serde_json::Value, String> {
+    let CodexSessionApprovalParam {
+        conversation_id,
+        kind,
+        id,
+        decision,
+    } = param;
+    let conversation = attached_conversation(&conversation_id, ctx).await?;
+    let op = approval_op(kind, id, decision);
+    conversation
+        .submit(op)
+        .await
+        .map_err(|e| format!("Failed to submit approval: {e}"))?;
+    Ok(json!({}))
+}
+
+fn approval_op(kind: CodexSessionApprovalKind, id: String, decision: CodexSessionDecision) -> Op {
+    match kind {
+        CodexSessionApprovalKind::Exec => Op::ExecApproval {
+            id,
+            decision: decision.into(),
+        },
+        CodexSessionApprovalKind::Patch => Op::PatchApproval {
+            id,
+            decision: decision.into(),
+        },
+        CodexSessionApprovalKind::Budget => Op::BudgetDecision {
+            id,
+            decision: match decision {
+                CodexSessionDecision::Approved | CodexSessionDecision::ApprovedForSession => {
+                    BudgetDecision::Continue
+                }
+                CodexSessionDecision::Denied | CodexSessionDecision::Abort => BudgetDecision::Stop,
+            },
+        },
+    }
+}
+
+/// The conversation `conversation_id` if its events are streamed to the
+/// client; input sent to any other conversation would go unobserved.
+async fn attached_conversation(
+    conversation_id: &str,
+    ctx: &SessionToolContext,
+) -> Result<Arc<CodexConversation>, String> {
+    let conversation_id = ConversationId::from_string(conversation_id)
+        .map_err(|e| format!("Failed to parse conversation_id: {e}"))?;
+    if !ctx
+        .attached_conversations
+        .lock()
+        .await
+        .contains(&conversation_id)
+    {
+        return Err(format!(
+            "Conversation {conversation_id} is not an interactive session; start it with {SESSION_CREATE_TOOL_NAME} or {SESSION_RESUME_TOOL_NAME}"
+        ));
+    }
+    ctx.conversation_manager
+        .get_conversation(conversation_id)
+        .await
+        .map_err(|_| format!("Session not found for conversation_id: {conversation_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_tool_calls() {
+        assert_eq!(
+            Ok(SessionToolCall::Create(CodexSessionCreateParam::default())),
+            parse_session_tool_call(SESSION_CREATE_TOOL_NAME, None)
+        );
+        assert_eq!(
+            Ok(SessionToolCall::Approval(CodexSessionApprovalParam {
+                conversation_id: "c1".to_string(),
+                kind: CodexSessionApprovalKind::Exec,
+                id: "call-1".to_string(),
+                decision: CodexSessionDecision::ApprovedForSession,
+            })),
+            parse_session_tool_call(
+                SESSION_APPROVAL_TOOL_NAME,
+                Some(json!({
+                    "conversationId": "c1",
+                    "kind": "exec",
+                    "id": "call-1",
+                    "decision": "approved_for_session",
+                })),
+            )
+        );
+        assert!(parse_session_tool_call(SESSION_SEND_INPUT_TOOL_NAME, None).is_err());
+        assert!(
+            parse_session_tool_call(
+                SESSION_SEND_INPUT_TOOL_NAME,
+                Some(json!({"conversationId": "c1"})),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn budget_approvals_map_to_budget_decisions() {
+        assert_eq!(
+            Op::BudgetDecision {
+                id: "3".to_string(),
+                decision: BudgetDecision::Continue,
+            },
+            approval_op(
+                CodexSessionApprovalKind::Budget,
+                "3".to_string(),
+                CodexSessionDecision::Approved,
+            )
+        );
+        assert_eq!(
+            Op::PatchApproval {
+                id: "4".to_string(),
+                decision: ReviewDecision::Abort,
+            },
+            approval_op(
+                CodexSessionApprovalKind::Patch,
+                "4".to_string(),
+                CodexSessionDecision::Abort,
+            )
+        );
+    }
+
+    #[test]
+    fn lists_session_tools() {
+        let names: Vec<String> = create_session_tools()
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+        assert_eq!(
+            vec![
+                "codex-session-create",
+                "codex-session-resume",
+                "codex-session-list",
+                "codex-session-send-input",
+                "codex-session-approval",
+            ],
+            names
+        );
+    }
+}
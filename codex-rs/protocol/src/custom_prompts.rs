@@ -9,6 +9,10 @@ use ts_rs::TS;
 /// - Full slash prefix: `"/{PROMPTS_CMD_PREFIX}:"`
 pub const PROMPTS_CMD_PREFIX: &str = "prompts";
 
+/// Namespace of MCP prompt slash commands (without trailing colon). The
+/// command token after '/' is `"{MCP_PROMPTS_CMD_PREFIX}:server:prompt"`.
+pub const MCP_PROMPTS_CMD_PREFIX: &str = "mcp";
+
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 pub struct CustomPrompt {
     pub name: String,
@@ -17,3 +21,22 @@ pub struct CustomPrompt {
     pub description: Option<String>,
     pub argument_hint: Option<String>,
 }
+
+/// A prompt template offered by a configured MCP server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct McpPrompt {
+    /// Name of the MCP server, as configured in `mcp_servers`.
+    pub server: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub arguments: Vec<McpPromptArgument>,
+}
+
+/// An argument substituted into an [`McpPrompt`] by its server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, TS)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+}
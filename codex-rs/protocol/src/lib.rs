@@ -6,5 +6,6 @@ pub mod message_history;
 pub mod models;
 pub mod num_format;
 pub mod parse_command;
+pub mod payload;
 pub mod plan_tool;
 pub mod protocol;
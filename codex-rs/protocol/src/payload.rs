@@ -0,0 +1,172 @@
+//! Framing for binary payloads (tool-produced images, audio, …) that would
+//! otherwise have to travel inside JSON strings of other events.
+//!
+//! A payload is split into [`PayloadFrameEvent`]s that are emitted ahead of
+//! the event that uses it; that event then carries a reference of the form
+//! `codex-payload:<payload_id>` in place of the inline data.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+use serde_with::serde_as;
+use ts_rs::TS;
+
+/// Prefix of the string that replaces inline data once it has been framed.
+pub const PAYLOAD_REF_PREFIX: &str = "codex-payload:";
+
+/// Maximum number of raw bytes carried by a single frame.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 64 * 1024;
+
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct PayloadFrameEvent {
+    /// Identifier shared by all frames of the same payload.
+    pub payload_id: String,
+    /// MIME type of the reassembled payload.
+    pub mime_type: String,
+    /// Size of the complete payload in bytes.
+    pub total_len: u64,
+    /// Position of `data` within the complete payload.
+    pub offset: u64,
+    /// Raw bytes of this frame.
+    #[serde_as(as = "serde_with::base64::Base64")]
+    #[ts(type = "string")]
+    pub data: Vec<u8>,
+}
+
+impl PayloadFrameEvent {
+    /// Whether this frame completes its payload.
+    pub fn is_last(&self) -> bool {
+        self.offset + self.data.len() as u64 >= self.total_len
+    }
+}
+
+/// Build the reference string that stands in for a framed payload.
+pub fn payload_ref(payload_id: &str) -> String {
+    format!("{PAYLOAD_REF_PREFIX}{payload_id}")
+}
+
+/// Extract the payload id from a reference produced by [`payload_ref`].
+pub fn parse_payload_ref(value: &str) -> Option<&str> {
+    value.strip_prefix(PAYLOAD_REF_PREFIX)
+}
+
+/// Split `bytes` into frames of at most `max_frame_len` bytes. An empty
+/// payload still yields a single (empty) frame so receivers learn about it.
+pub fn frame_payload(
+    payload_id: &str,
+    mime_type: &str,
+    bytes: &[u8],
+    max_frame_len: usize,
+) -> Vec<PayloadFrameEvent> {
+    let max_frame_len = max_frame_len.max(1);
+    let total_len = bytes.len() as u64;
+    let frame = |offset: usize, data: &[u8]| PayloadFrameEvent {
+        payload_id: payload_id.to_string(),
+        mime_type: mime_type.to_string(),
+        total_len,
+        offset: offset as u64,
+        data: data.to_vec(),
+    };
+
+    if bytes.is_empty() {
+        return vec![frame(0, bytes)];
+    }
+    bytes
+        .chunks(max_frame_len)
+        .enumerate()
+        .map(|(i, chunk)| frame(i * max_frame_len, chunk))
+        .collect()
+}
+
+/// A payload reassembled from its frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssembledPayload {
+    pub payload_id: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Collects frames until each payload is complete.
+#[derive(Debug, Default)]
+pub struct PayloadAssembler {
+    pending: HashMap<String, Vec<u8>>,
+}
+
+impl PayloadAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a frame, returning the payload once its last frame has arrived.
+    /// Frames must be pushed in order; an out-of-order frame discards the
+    /// partial payload.
+    pub fn push(&mut self, frame: PayloadFrameEvent) -> Option<AssembledPayload> {
+        let buf = self.pending.entry(frame.payload_id.clone()).or_default();
+        if buf.len() as u64 != frame.offset {
+            self.pending.remove(&frame.payload_id);
+            return None;
+        }
+        buf.extend_from_slice(&frame.data);
+        if !frame.is_last() {
+            return None;
+        }
+        let data = self.pending.remove(&frame.payload_id).unwrap_or_default();
+        Some(AssembledPayload {
+            payload_id: frame.payload_id,
+            mime_type: frame.mime_type,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn frames_round_trip_through_assembler() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let frames = frame_payload("img-1", "image/png", &bytes, 100);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames.iter().map(|f| f.offset).collect::<Vec<_>>(),
+            vec![0, 100, 200]
+        );
+
+        let mut assembler = PayloadAssembler::new();
+        let mut assembled = None;
+        for frame in frames {
+            assert!(assembled.is_none());
+            assembled = assembler.push(frame);
+        }
+        assert_eq!(
+            Some(AssembledPayload {
+                payload_id: "img-1".to_string(),
+                mime_type: "image/png".to_string(),
+                data: bytes,
+            }),
+            assembled
+        );
+    }
+
+    #[test]
+    fn empty_payload_yields_single_frame() {
+        let frames = frame_payload("empty", "application/octet-stream", &[], 16);
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].is_last());
+    }
+
+    #[test]
+    fn frame_data_serializes_as_base64() -> Result<()> {
+        let frame = frame_payload("p", "image/png", &[1, 2, 3], 16).remove(0);
+        assert_eq!(
+            r#"{"payload_id":"p","mime_type":"image/png","total_len":3,"offset":0,"data":"AQID"}"#,
+            serde_json::to_string(&frame)?
+        );
+        assert_eq!(Some("p"), parse_payload_ref(&payload_ref("p")));
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
 //! Uses a SQ (Submission Queue) / EQ (Event Queue) pattern to asynchronously communicate
 //! between user and agent.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt;
 use std::path::Path;
@@ -11,17 +12,21 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use crate::ConversationId;
+use crate::config_types::CompactionStrategy;
 use crate::config_types::ReasoningEffort as ReasoningEffortConfig;
 use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::custom_prompts::CustomPrompt;
+use crate::custom_prompts::McpPrompt;
 use crate::message_history::HistoryEntry;
 use crate::models::ContentItem;
 use crate::models::ResponseItem;
 use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
+use crate::payload::PayloadFrameEvent;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
 use mcp_types::Tool as McpTool;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -62,6 +67,19 @@ pub enum Op {
         items: Vec<InputItem>,
     },
 
+    /// Input from the user that runs as a turn of its own. While a turn is
+    /// running the input is queued instead of being added to that turn;
+    /// queued input starts a new turn, in order, each time the previous turn
+    /// completes. Interrupting the running turn discards the queue. Changes
+    /// to the queue are reported with [`EventMsg::UserInputQueue`].
+    QueueUserInput {
+        /// User input items, see `InputItem`
+        items: Vec<InputItem>,
+    },
+
+    /// Discard all queued user input.
+    ClearQueuedUserInput,
+
     /// Similar to [`Op::UserInput`], but contains additional context required
     /// for a turn of a [`crate::codex_conversation::CodexConversation`].
     UserTurn {
@@ -127,6 +145,36 @@ pub enum Op {
         summary: Option<ReasoningSummaryConfig>,
     },
 
+    /// Change select session settings mid-session. Unlike
+    /// `OverrideTurnContext`, the update is validated first: the session
+    /// replies with `EventMsg::SessionSettingsUpdated` carrying the effective
+    /// settings, or with `EventMsg::Error` (leaving every setting unchanged)
+    /// when the update is rejected.
+    UpdateSessionSettings {
+        /// Updated model slug.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+
+        /// Updated command approval policy.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        approval_policy: Option<AskForApproval>,
+
+        /// Updated sandbox policy for tool calls.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sandbox_policy: Option<SandboxPolicy>,
+
+        /// Updated reasoning effort. Use `Some(Some(_))` to set a specific
+        /// effort, `Some(None)` to clear it, or `None` to leave it unchanged.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        effort: Option<Option<ReasoningEffortConfig>>,
+    },
+
+    /// Switch to the profile `name` of the session's config (`[profiles]` in
+    /// `config.toml`), applying its model, reasoning effort, approval policy,
+    /// sandbox and tool set to subsequent turns. Replies like
+    /// `UpdateSessionSettings`.
+    SwitchProfile { name: String },
+
     /// Approve a command execution
     ExecApproval {
         /// The id of the submission we are approving
@@ -135,6 +183,20 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Answer every item of an `ApprovalBatchRequest` at once. Individual
+    /// items may also be answered with `Op::ExecApproval` using the item's
+    /// `call_id` as the id.
+    ApprovalBatch {
+        /// The id of the submission that produced the batch
+        id: String,
+        /// Per-item decisions keyed by `call_id`.
+        #[serde(default)]
+        decisions: HashMap<String, ReviewDecision>,
+        /// Decision applied to every item not listed in `decisions`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default_decision: Option<ReviewDecision>,
+    },
+
     /// Approve a code patch
     PatchApproval {
         /// The id of the submission we are approving
@@ -143,6 +205,14 @@ pub enum Op {
         decision: ReviewDecision,
     },
 
+    /// Answer an `EventMsg::BudgetExceeded` raised by the turn started by
+    /// submission `id`.
+    BudgetDecision {
+        /// The id of the submission whose turn is paused
+        id: String,
+        decision: BudgetDecision,
+    },
+
     /// Append an entry to the persistent cross-session message history.
     ///
     /// Note the entry is not guaranteed to be logged if the user has
@@ -159,6 +229,10 @@ pub enum Op {
     /// Reply is delivered via `EventMsg::ConversationHistory`.
     GetPath,
 
+    /// Request latency, token and cost accounting for every finished turn of
+    /// the session. Reply is delivered via `EventMsg::TurnStatsResponse`.
+    GetTurnStats,
+
     /// Request the list of MCP tools available across all configured servers.
     /// Reply is delivered via `EventMsg::McpListToolsResponse`.
     ListMcpTools,
@@ -166,6 +240,46 @@ pub enum Op {
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Request the prompts offered by the configured MCP servers.
+    /// Reply is delivered via `EventMsg::McpListPromptsResponse`.
+    ListMcpPrompts,
+
+    /// Expand an MCP prompt with `arguments` into the text of a user turn.
+    /// The server substitutes the arguments. Reply is delivered via
+    /// `EventMsg::McpGetPromptResponse`; the frontend submits the text.
+    GetMcpPrompt {
+        server: String,
+        name: String,
+        arguments: HashMap<String, String>,
+    },
+
+    /// Connect the MCP server `name` as it is configured in `config.toml`
+    /// now, or else as it was configured when the session started. A server
+    /// that is already connected is reconnected, picking up config changes.
+    /// Its tools are offered to the model from the next turn on. Reply is
+    /// delivered via `EventMsg::McpServerConnection`.
+    ConnectMcpServer { name: String },
+
+    /// Disconnect the MCP server `name` and stop offering its tools to the
+    /// model. Reply is delivered via `EventMsg::McpServerConnection`.
+    DisconnectMcpServer { name: String },
+
+    /// Re-read the config files and apply the settings a running session can
+    /// change: the approval policy, the tool set and disabled tools, and the
+    /// MCP servers. Sessions submit it themselves when `watch_config` is on
+    /// and a config file changes. Reply is delivered via
+    /// `EventMsg::ConfigReloaded`.
+    ReloadConfig,
+
+    /// Answer the `EventMsg::ElicitationRequest` with id `id`. `content`
+    /// holds the values of the requested fields when `action` is `accept`.
+    ResolveElicitation {
+        id: String,
+        action: ElicitationAction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<serde_json::Value>,
+    },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -174,13 +288,123 @@ pub enum Op {
     /// Request a code review from the agent.
     Review { review_request: ReviewRequest },
 
+    /// Save the conversation history and a snapshot of the working tree
+    /// under `name`, replacing any earlier checkpoint with the same name.
+    /// Reply is delivered via `EventMsg::CheckpointCreated`.
+    CreateCheckpoint { name: String },
+
+    /// Restore the conversation history and working tree saved by
+    /// `CreateCheckpoint`. Rejected with `EventMsg::Error` while a task is
+    /// running. Reply is delivered via `EventMsg::CheckpointRestored`.
+    RollbackToCheckpoint { name: String },
+
+    /// Revert the file changes made by the most recent turn that changed
+    /// files; repeat to step further back. Rejected with `EventMsg::Error`
+    /// while a task is running. Reply is delivered via
+    /// `EventMsg::TurnUndone`.
+    UndoTurn,
+
+    /// Run the most recent turn again with `correction` appended to its
+    /// input, such as "use pnpm, not npm". The conversation entries of the
+    /// previous attempt are discarded so the model does not see the dead end.
+    /// With `revert_changes`, the files it changed are restored first and
+    /// `EventMsg::TurnUndone` reports them. Rejected with `EventMsg::Error`
+    /// while a task is running.
+    RetryTurn {
+        #[serde(default)]
+        correction: String,
+        #[serde(default)]
+        revert_changes: bool,
+    },
+
+    /// Request the changes the session has made to the working tree, as
+    /// hunks per file. Reply is delivered via `EventMsg::SessionDiff`.
+    GetSessionDiff,
+
+    /// Accept or revert hunks of `path`, numbered as in the last
+    /// `EventMsg::SessionDiff`. Reverted hunks are undone on disk; accepted
+    /// hunks stay on disk and are no longer reported. Rejected with
+    /// `EventMsg::Error` while a task is running. Reply is delivered via
+    /// `EventMsg::SessionDiff`.
+    ResolveHunks {
+        path: PathBuf,
+        hunks: Vec<usize>,
+        action: HunkAction,
+    },
+
+    /// Keep a file or note in the model's context for the rest of the
+    /// session. Pinned files are re-read before every turn and pins are never
+    /// dropped by compaction. Reply is delivered via `EventMsg::PinnedContext`.
+    PinContext { pin: ContextPin },
+
+    /// Remove the pin at `index` in the list reported by
+    /// `EventMsg::PinnedContext`.
+    UnpinContext { index: usize },
+
+    /// Request the current pins. Reply is delivered via
+    /// `EventMsg::PinnedContext`.
+    ListPinnedContext,
+
+    /// Request the plan the model last recorded with the `update_plan` tool;
+    /// an empty plan when there is none. Reply is delivered via
+    /// `EventMsg::PlanUpdate`.
+    GetPlan,
+
+    /// Rename the session. The title is recorded in the rollout and shown by
+    /// session pickers instead of the first message. Reply is delivered via
+    /// `EventMsg::SessionTitle`.
+    SetSessionTitle { title: String },
+
+    /// Set and remove session tags, such as a ticket id or branch. Tags are
+    /// recorded in the rollout and can be used to filter listed sessions. An
+    /// empty update just reports the current tags. Reply is delivered via
+    /// `EventMsg::SessionTags`.
+    UpdateSessionTags {
+        #[serde(default)]
+        set: BTreeMap<String, String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+
+    /// Set and remove environment variables for the rest of the session.
+    /// They apply to every later shell command and background process, on
+    /// top of the shell environment policy, and are not recorded in the
+    /// rollout. An empty update just reports the current variables. Reply is
+    /// delivered via `EventMsg::SessionEnv`.
+    UpdateSessionEnv {
+        #[serde(default)]
+        set: BTreeMap<String, String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+
+    /// Start, list, read the logs of or kill the session's background
+    /// processes on behalf of the client, such as an IDE managing dev
+    /// servers. Starting a process goes through the same approval and sandbox
+    /// checks as when the model starts one with the `background_process`
+    /// tool. Reply is delivered via `EventMsg::BackgroundProcessResponse`.
+    BackgroundProcess { request: BackgroundProcessRequest },
+
     /// Request to shut down codex instance.
     Shutdown,
 }
 
 /// Determines the conditions under which the user is consulted to approve
 /// running the command proposed by Codex.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Display, TS)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Display,
+    TS,
+    JsonSchema,
+)]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum AskForApproval {
@@ -206,6 +430,43 @@ pub enum AskForApproval {
     Never,
 }
 
+/// Presentation features supported by the client driving a session. Clients
+/// declare these when the session starts so the agent can adapt what it sends
+/// them; anything left unspecified is assumed to be supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(default)]
+pub struct ClientCapabilities {
+    /// Whether images and other binary tool output can be displayed.
+    pub render_images: bool,
+    /// Whether `TurnDiff` events can be displayed.
+    pub show_diffs: bool,
+    /// Whether the client can answer approval requests.
+    pub interactive_approvals: bool,
+}
+
+impl Default for ClientCapabilities {
+    fn default() -> Self {
+        Self {
+            render_images: true,
+            show_diffs: true,
+            interactive_approvals: true,
+        }
+    }
+}
+
+impl ClientCapabilities {
+    /// Approval policy to use with this client: clients that cannot answer
+    /// approval requests never get asked, so failures go straight back to the
+    /// model.
+    pub fn effective_approval_policy(&self, policy: AskForApproval) -> AskForApproval {
+        if self.interactive_approvals {
+            policy
+        } else {
+            AskForApproval::Never
+        }
+    }
+}
+
 /// Determines execution restrictions for model shell commands.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Display, TS)]
 #[strum(serialize_all = "kebab-case")]
@@ -444,6 +705,10 @@ pub enum EventMsg {
     /// Agent text output delta message
     AgentMessageDelta(AgentMessageDeltaEvent),
 
+    /// Fragment of a tool call's arguments while the model is still
+    /// generating them (e.g. a large `apply_patch` body).
+    ToolCallArgumentsDelta(ToolCallArgumentsDeltaEvent),
+
     /// Reasoning event from agent.
     AgentReasoning(AgentReasoningEvent),
 
@@ -463,8 +728,15 @@ pub enum EventMsg {
 
     McpToolCallBegin(McpToolCallBeginEvent),
 
+    /// Progress reported by the server of a running MCP tool call.
+    McpToolCallProgress(McpToolCallProgressEvent),
+
     McpToolCallEnd(McpToolCallEndEvent),
 
+    /// An MCP server asks the user for structured input while one of its
+    /// tools runs. Answer with `Op::ResolveElicitation`.
+    ElicitationRequest(ElicitationRequestEvent),
+
     WebSearchBegin(WebSearchBeginEvent),
 
     WebSearchEnd(WebSearchEndEvent),
@@ -480,18 +752,108 @@ pub enum EventMsg {
     /// Notification that the agent attached a local image via the view_image tool.
     ViewImageToolCall(ViewImageToolCallEvent),
 
+    /// One frame of a binary payload referenced by a later event.
+    PayloadFrame(PayloadFrameEvent),
+
     ExecApprovalRequest(ExecApprovalRequestEvent),
 
+    /// Several commands from the same model response that all need approval.
+    ApprovalBatchRequest(ApprovalBatchRequestEvent),
+
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
     BackgroundEvent(BackgroundEventEvent),
 
     BackgroundProcessStatus(BackgroundProcessStatusEvent),
 
+    /// Response to `Op::BackgroundProcess`.
+    BackgroundProcessResponse(BackgroundProcessResponseEvent),
+
+    /// Notification that the sandbox blocked an operation attempted by a command.
+    SandboxDenied(SandboxDeniedEvent),
+
+    /// Ack for `Op::UpdateSessionSettings` with the settings now in effect.
+    SessionSettingsUpdated(SessionSettingsUpdatedEvent),
+
+    /// Notification that the queue of user input waiting for the running
+    /// turn to finish changed.
+    UserInputQueue(UserInputQueueEvent),
+
+    /// A submission that would have started a turn was turned away because
+    /// another turn is running. Only sent when the session is configured to
+    /// reject such submissions rather than add them to the running turn.
+    SessionBusy(SessionBusyEvent),
+
+    /// Notification that the conversation history was compacted.
+    ContextCompacted(ContextCompactedEvent),
+
+    /// Ack for `Op::CreateCheckpoint`.
+    CheckpointCreated(CheckpointCreatedEvent),
+
+    /// Ack for `Op::RollbackToCheckpoint`.
+    CheckpointRestored(CheckpointRestoredEvent),
+
+    /// Ack for `Op::UndoTurn`.
+    TurnUndone(TurnUndoneEvent),
+
+    /// Response to `Op::GetSessionDiff` and `Op::ResolveHunks`.
+    SessionDiff(SessionDiffEvent),
+
+    /// The pinned context after `Op::PinContext` or `Op::UnpinContext`, or in
+    /// response to `Op::ListPinnedContext`.
+    PinnedContext(PinnedContextEvent),
+
+    /// State of the workspace when a turn finished, recorded so a resumed
+    /// session can report what changed while it was inactive.
+    WorkspaceState(WorkspaceStateEvent),
+
+    /// Git state of the workspace when a turn started.
+    TurnGitState(TurnGitStateEvent),
+
+    /// The session was titled, either automatically after its first turn or
+    /// with `Op::SetSessionTitle`.
+    SessionTitle(SessionTitleEvent),
+
+    /// The session's tags after `Op::UpdateSessionTags`.
+    SessionTags(SessionTagsEvent),
+
+    /// The session's environment variables after `Op::UpdateSessionEnv`.
+    SessionEnv(SessionEnvEvent),
+
+    /// Latency, token and cost accounting of a turn that just finished.
+    TurnStats(TurnStats),
+
+    /// Response to `Op::GetTurnStats`.
+    TurnStatsResponse(TurnStatsResponseEvent),
+
+    /// Estimated cost per model of the session and of every session, sent
+    /// after each turn.
+    CostSummary(CostSummaryEvent),
+
+    /// The session used up one of its `[session_budget]` limits. The turn is
+    /// paused until the user answers with `Op::BudgetDecision`.
+    BudgetExceeded(BudgetExceededEvent),
+
+    /// Notification that a sub-agent was spawned to carry out a delegated
+    /// task.
+    SubAgentBegin(SubAgentBeginEvent),
+
+    /// Notification that a sub-agent finished; its final message is returned
+    /// to the model as the output of the delegating tool call.
+    SubAgentEnd(SubAgentEndEvent),
+
     /// Notification that a model stream experienced an error or disconnect
     /// and the system is handling it (e.g., retrying with backoff).
     StreamError(StreamErrorEvent),
 
+    /// Notification that the turn is served by a fallback model from
+    /// `model_fallbacks` because the configured one failed.
+    ModelFailover(ModelFailoverEvent),
+
+    /// Estimated size of the prompt about to be sent to the model, and the
+    /// room it leaves in the model's context window.
+    ContextBudget(ContextBudgetEvent),
+
     /// Notification that the agent is about to apply a code patch. Mirrors
     /// `ExecCommandBegin` so front‑ends can show progress indicators.
     PatchApplyBegin(PatchApplyBeginEvent),
@@ -510,6 +872,25 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// List of prompts offered by MCP servers.
+    McpListPromptsResponse(McpListPromptsResponseEvent),
+
+    /// An MCP prompt expanded in response to `Op::GetMcpPrompt`.
+    McpGetPromptResponse(McpGetPromptResponseEvent),
+
+    /// An MCP server stopped answering and is being reconnected, or the
+    /// reconnection finished.
+    McpServerHealth(McpServerHealthEvent),
+
+    /// An MCP server was connected or disconnected in response to
+    /// `Op::ConnectMcpServer` or `Op::DisconnectMcpServer`.
+    McpServerConnection(McpServerConnectionEvent),
+
+    /// The config files were re-read in response to `Op::ReloadConfig`.
+    ConfigReloaded(ConfigReloadedEvent),
+
+    /// The model's plan, sent whenever it changes and in response to
+    /// `Op::GetPlan`. Always carries the full plan.
     PlanUpdate(UpdatePlanArgs),
 
     TurnAborted(TurnAbortedEvent),
@@ -548,7 +929,7 @@ pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default, TS)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, TS)]
 pub struct TokenUsage {
     pub input_tokens: u64,
     pub cached_input_tokens: u64,
@@ -793,6 +1174,16 @@ pub struct AgentMessageDeltaEvent {
     pub delta: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ToolCallArgumentsDeltaEvent {
+    /// Identifier of the tool call being generated.
+    pub call_id: String,
+    /// Name of the tool being called.
+    pub tool_name: String,
+    /// Next fragment of the raw (possibly incomplete) arguments.
+    pub delta: String,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct AgentReasoningEvent {
     pub text: String,
@@ -833,6 +1224,62 @@ pub struct McpToolCallBeginEvent {
     pub invocation: McpInvocation,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct McpToolCallProgressEvent {
+    /// Identifier of the McpToolCallBegin event of the call.
+    pub call_id: String,
+    /// Work done so far. It increases with every event of a call.
+    pub progress: f64,
+    /// Total work, when the server knows it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<f64>,
+    /// What the server is doing, or a chunk of output it streams.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl fmt::Display for McpToolCallProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let done = match self.total {
+            Some(total) if total > 0.0 => {
+                format!("{:.0}%", (self.progress / total * 100.0).clamp(0.0, 100.0))
+            }
+            _ => self.progress.to_string(),
+        };
+        match (&self.message, self.total) {
+            (Some(message), Some(_)) => write!(f, "{done} {message}"),
+            (Some(message), None) => write!(f, "{message}"),
+            (None, _) => write!(f, "{done}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct ElicitationRequestEvent {
+    /// Identifies the request in `Op::ResolveElicitation`.
+    pub id: String,
+    /// Name of the MCP server as defined in the config.
+    pub server: String,
+    /// What the server asks for.
+    pub message: String,
+    /// JSON Schema of the requested object. Only top-level properties of
+    /// type string, number, integer or boolean, or string enums, are allowed.
+    pub requested_schema: serde_json::Value,
+}
+
+/// How the user answered an `ElicitationRequest`.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ElicitationAction {
+    /// Submitted the requested values.
+    Accept,
+    /// Explicitly refused to provide them.
+    Decline,
+    /// Dismissed the request without choosing.
+    Cancel,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct McpToolCallEndEvent {
     /// Identifier for the corresponding McpToolCallBegin that finished.
@@ -930,6 +1377,8 @@ pub enum SessionSource {
     VSCode,
     Exec,
     Mcp,
+    /// Session spawned by another session to carry out a delegated task.
+    SubAgent,
     #[serde(other)]
     Unknown,
 }
@@ -974,6 +1423,7 @@ pub enum RolloutItem {
     SessionMeta(SessionMetaLine),
     ResponseItem(ResponseItem),
     Compacted(CompactedItem),
+    HistoryRewound(HistoryRewoundItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
 }
@@ -981,6 +1431,10 @@ pub enum RolloutItem {
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
 pub struct CompactedItem {
     pub message: String,
+    /// History the compaction left behind. Rollouts written before it was
+    /// recorded rebuild the history from `message` instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_history: Option<Vec<ResponseItem>>,
 }
 
 impl From<CompactedItem> for ResponseItem {
@@ -995,6 +1449,14 @@ impl From<CompactedItem> for ResponseItem {
     }
 }
 
+/// The history was rewound to an earlier state, to retry the last turn or
+/// roll back to a checkpoint.
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+pub struct HistoryRewoundItem {
+    /// History left after the rewind.
+    pub history: Vec<ResponseItem>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
 pub struct TurnContextItem {
     pub cwd: PathBuf,
@@ -1086,6 +1548,9 @@ pub struct ExecCommandBeginEvent {
     /// The command's working directory if not the default cwd for the agent.
     pub cwd: PathBuf,
     pub parsed_cmd: Vec<ParsedCommand>,
+    /// Variables set with `Op::UpdateSessionEnv` that the command runs with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub session_env: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -1149,6 +1614,12 @@ pub struct ExecApprovalRequestEvent {
     pub reason: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ApprovalBatchRequestEvent {
+    /// Commands awaiting approval, in the order the model issued them.
+    pub items: Vec<ExecApprovalRequestEvent>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ApplyPatchApprovalRequestEvent {
     /// Responses API call id for the associated patch apply call, if available.
@@ -1172,11 +1643,98 @@ pub struct BackgroundProcessStatusEvent {
     pub running: u64,
 }
 
+/// An action of `Op::BackgroundProcess`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BackgroundProcessRequest {
+    /// Start `command` in `cwd` (default: the session's working directory)
+    /// with `env` on top of the session's environment.
+    Start {
+        command: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        env: Option<HashMap<String, String>>,
+    },
+    List,
+    Logs {
+        process_id: String,
+    },
+    Kill {
+        process_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct BackgroundProcessResponseEvent {
+    /// The JSON the `background_process` tool returns for the action, or why
+    /// it failed.
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SandboxDeniedEvent {
+    /// Identifier for the exec call that was blocked.
+    pub call_id: String,
+    /// The kind of operation the sandbox refused.
+    pub operation: SandboxOperation,
+    /// Filesystem path the command tried to access, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Network host the command tried to reach, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum SandboxOperation {
+    /// Writing to (or creating/removing) a file outside the writable roots.
+    FileWrite,
+    /// Opening an outbound network connection or resolving a host.
+    Network,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct StreamErrorEvent {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct ModelFailoverEvent {
+    /// Model that served the turn.
+    pub model: String,
+    /// Key of the provider of `model` in `model_providers`.
+    pub model_provider: String,
+    /// Error of the model tried before it.
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct ContextBudgetEvent {
+    /// Estimated tokens of the instructions, conversation and tools.
+    pub estimated_prompt_tokens: u64,
+    pub model_context_window: Option<u64>,
+    /// Tokens of the context window the prompt leaves for the response and
+    /// later turns, when the window is known.
+    pub remaining_tokens: Option<u64>,
+}
+
+impl ContextBudgetEvent {
+    /// Percentage (0-100) of the context window left, as shown for
+    /// [`TokenUsage::percent_of_context_window_remaining`].
+    pub fn percent_remaining(&self) -> Option<u8> {
+        let usage = TokenUsage {
+            input_tokens: self.estimated_prompt_tokens,
+            total_tokens: self.estimated_prompt_tokens,
+            ..Default::default()
+        };
+        self.model_context_window
+            .map(|window| usage.percent_of_context_window_remaining(window))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct PatchApplyBeginEvent {
     /// Identifier so this can be paired with the PatchApplyEnd event.
@@ -1226,6 +1784,117 @@ pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
 }
 
+/// Response payload for `Op::ListMcpPrompts`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpListPromptsResponseEvent {
+    pub prompts: Vec<McpPrompt>,
+}
+
+/// Response payload for `Op::GetMcpPrompt`.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpGetPromptResponseEvent {
+    pub server: String,
+    pub name: String,
+    /// The expanded text, or why the prompt could not be expanded.
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpServerHealthEvent {
+    pub server: String,
+    pub status: McpServerHealthStatus,
+}
+
+impl fmt::Display for McpServerHealthEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let server = &self.server;
+        match &self.status {
+            McpServerHealthStatus::Unresponsive { error } => {
+                write!(
+                    f,
+                    "MCP server `{server}` is not responding ({error}); reconnecting"
+                )
+            }
+            McpServerHealthStatus::Reconnected { attempts, tools } => write!(
+                f,
+                "MCP server `{server}` reconnected after {attempts} attempt(s) with {tools} tool(s)"
+            ),
+            McpServerHealthStatus::Failed { attempts, error } => write!(
+                f,
+                "MCP server `{server}` could not be reconnected after {attempts} attempt(s) ({error}); its tools are unavailable for this session"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum McpServerHealthStatus {
+    /// The server did not answer a ping; it is being reconnected.
+    Unresponsive { error: String },
+    /// The server was reconnected and its tools listed again.
+    Reconnected { attempts: u32, tools: u64 },
+    /// Every reconnect attempt failed. The server and its tools are dropped
+    /// for the rest of the session.
+    Failed { attempts: u32, error: String },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct McpServerConnectionEvent {
+    pub server: String,
+    pub connected: bool,
+    /// Number of the server's tools offered to the model; 0 once it is
+    /// disconnected.
+    pub tools: u64,
+}
+
+impl fmt::Display for McpServerConnectionEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let server = &self.server;
+        if self.connected {
+            write!(
+                f,
+                "MCP server `{server}` connected with {} tool(s)",
+                self.tools
+            )
+        } else {
+            write!(f, "MCP server `{server}` disconnected")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, TS)]
+pub struct ConfigReloadedEvent {
+    /// The changes applied to the session, e.g. "MCP server `docs`
+    /// connected with 3 tool(s)".
+    pub applied: Vec<String>,
+
+    /// Changed settings that only take effect in a new session.
+    pub requires_restart: Vec<String>,
+
+    /// The approval policy, when the reload changed it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<AskForApproval>,
+}
+
+impl fmt::Display for ConfigReloadedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.applied.is_empty() {
+            write!(f, "Config reloaded: no changes applied")?;
+        } else {
+            write!(f, "Config reloaded: {}", self.applied.join("; "))?;
+        }
+        if !self.requires_restart.is_empty() {
+            write!(
+                f,
+                " (start a new session to apply {})",
+                self.requires_restart.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 pub struct SessionConfiguredEvent {
     /// Name left as session_id instead of conversation_id for backwards compatibility.
@@ -1252,6 +1921,357 @@ pub struct SessionConfiguredEvent {
     pub rollout_path: PathBuf,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct UserInputQueueEvent {
+    /// Input still waiting to run, oldest first.
+    pub pending: Vec<QueuedUserInput>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct SessionBusyEvent {
+    /// Submission ids of the tasks of the running turn.
+    pub running: Vec<String>,
+    /// Number of queued submissions waiting to run after it.
+    pub queued: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct QueuedUserInput {
+    /// Id of the submission that queued the input; the turn it starts uses
+    /// the same id.
+    pub id: String,
+    /// Text of the input's text items.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct ContextCompactedEvent {
+    /// Strategy that was applied.
+    pub strategy: CompactionStrategy,
+    /// Number of history items before and after compaction.
+    pub items_before: usize,
+    pub items_after: usize,
+    /// Number of user turns folded into a summary.
+    pub turns_summarized: usize,
+    /// Number of tool outputs replaced with a placeholder.
+    pub tool_outputs_dropped: usize,
+    /// Number of pinned items kept verbatim.
+    pub pinned_items_kept: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct CheckpointCreatedEvent {
+    /// Name the checkpoint was saved under.
+    pub name: String,
+    /// Whether the working tree was captured; `false` when the session's
+    /// `cwd` is not inside a git repository, in which case only the
+    /// conversation can be rolled back.
+    pub files_captured: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct CheckpointRestoredEvent {
+    /// Name of the checkpoint that was restored.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct TurnUndoneEvent {
+    /// Files written back with the contents they had before the turn.
+    pub restored: Vec<PathBuf>,
+    /// Files the turn created, now removed.
+    pub removed: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum HunkAction {
+    /// Keep the hunk and stop reporting it.
+    Accept,
+    /// Undo the hunk on disk.
+    Revert,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct SessionDiffEvent {
+    /// Files the session changed that still differ from their contents
+    /// before the session, ordered by path.
+    pub files: Vec<FileDiff>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub status: FileDiffStatus,
+    /// Lines added and removed across all hunks.
+    pub additions: usize,
+    pub deletions: usize,
+    /// Binary files are reported without hunks and cannot be resolved by
+    /// hunk.
+    #[serde(default)]
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDiffStatus {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One hunk of a unified diff. Line numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// Lines of the hunk without their line endings, each prefixed with
+    /// ` `, `-` or `+`.
+    pub lines: Vec<String>,
+}
+
+/// A file or note kept in the model's context.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContextPin {
+    /// A file whose current contents are shown to the model on every turn.
+    /// Relative paths are resolved against the session's working directory
+    /// when pinned.
+    File { path: PathBuf },
+    /// Free text shown to the model on every turn.
+    Note { text: String },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct PinnedContextEvent {
+    /// Pins in the order they were added.
+    pub pins: Vec<ContextPin>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct WorkspaceStateEvent {
+    pub cwd: PathBuf,
+    /// Commit checked out in the repository containing `cwd`; `None` outside
+    /// a repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_head: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Git tree object with a snapshot of the repository's working tree.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_tree: Option<String>,
+    /// Background processes that were running, as `id: command`.
+    #[serde(default)]
+    pub background_processes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct TurnGitStateEvent {
+    /// Id of the submission that started the turn.
+    pub turn_id: String,
+    /// Commit checked out when the turn started; `None` in a repository
+    /// without commits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_head: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Files with uncommitted changes, as reported by `git status`.
+    #[serde(default)]
+    pub dirty_files: Vec<GitDirtyFile>,
+    /// Entries of `git stash list`, newest first.
+    #[serde(default)]
+    pub stashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct GitDirtyFile {
+    /// Two-letter `git status --porcelain` code, e.g. ` M` or `??`.
+    pub status: String,
+    /// Path relative to the repository root.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SessionTitleEvent {
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SessionTagsEvent {
+    /// Every tag of the session, not just the ones that changed.
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SessionEnvEvent {
+    /// Every variable set for the session, not just the ones that changed.
+    pub env: BTreeMap<String, String>,
+}
+
+/// Where the time and money of one turn went.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct TurnStats {
+    /// Id of the submission that started the turn.
+    pub turn_id: String,
+    pub model: String,
+    /// Wall time of the whole turn.
+    pub duration_ms: u64,
+    /// Number of model requests, including retries.
+    pub model_requests: u32,
+    /// Time spent waiting on the model.
+    pub model_latency_ms: u64,
+    pub tool_calls: u32,
+    /// Wall time spent running tool calls, including waiting for approval.
+    pub tool_time_ms: u64,
+    pub token_usage: TokenUsage,
+    /// Estimated cost in US dollars; `None` when the model's pricing is not
+    /// known.
+    pub estimated_cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct TurnStatsResponseEvent {
+    /// Finished turns, oldest first.
+    pub turns: Vec<TurnStats>,
+}
+
+/// Tokens and estimated cost of the turns served by one model.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, TS)]
+pub struct ModelCost {
+    pub model: String,
+    pub turns: u32,
+    pub token_usage: TokenUsage,
+    /// Estimated cost in US dollars of the turns whose pricing is known.
+    pub estimated_cost_usd: f64,
+    /// Turns left out of `estimated_cost_usd` because the model's pricing
+    /// was not known.
+    pub unpriced_turns: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct CostSummaryEvent {
+    /// Cost of this session per model, including the turns it had before it
+    /// was resumed.
+    pub session: Vec<ModelCost>,
+    /// Cost of every session per model, since the running total in
+    /// `$CODEX_HOME/costs.json` was started.
+    pub all_sessions: Vec<ModelCost>,
+}
+
+impl CostSummaryEvent {
+    /// Estimated cost of this session in US dollars.
+    pub fn session_cost_usd(&self) -> f64 {
+        self.session
+            .iter()
+            .map(|cost| cost.estimated_cost_usd)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, TS)]
+pub struct BudgetExceededEvent {
+    pub budget: BudgetKind,
+    /// The configured limit, in the budget's unit.
+    pub limit: f64,
+    /// Usage counted against the budget so far, in the same unit.
+    pub used: f64,
+}
+
+impl fmt::Display for BudgetExceededEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { limit, used, .. } = self;
+        match self.budget {
+            BudgetKind::Tokens => write!(f, "used {used:.0} of {limit:.0} tokens"),
+            BudgetKind::CostUsd => write!(f, "spent an estimated ${used:.2} of ${limit:.2}"),
+            BudgetKind::ToolCalls => write!(f, "made {used:.0} of {limit:.0} tool calls"),
+            BudgetKind::WallTime => write!(f, "ran for {used:.0}s of {limit:.0}s"),
+        }
+    }
+}
+
+/// A limit of `[session_budget]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Display, TS)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BudgetKind {
+    /// Total tokens.
+    Tokens,
+    /// Estimated cost in US dollars.
+    CostUsd,
+    ToolCalls,
+    /// Seconds spent running turns.
+    WallTime,
+}
+
+/// User's decision in response to a BudgetExceeded event.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetDecision {
+    /// Resume the turn with a fresh budget of the same size.
+    Continue,
+
+    /// End the turn.
+    #[default]
+    Stop,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubAgentBeginEvent {
+    /// Identifier of the tool call that delegated the task.
+    pub call_id: String,
+    /// Conversation id of the sub-agent's own session.
+    pub conversation_id: ConversationId,
+    pub task: String,
+    /// Rollout file holding the sub-agent's full transcript.
+    pub rollout_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubAgentEndEvent {
+    /// Identifier of the tool call that delegated the task.
+    pub call_id: String,
+    pub conversation_id: ConversationId,
+    pub outcome: SubAgentOutcome,
+    /// Final message of the sub-agent, or why it stopped.
+    pub message: Option<String>,
+    /// Rollout file holding the sub-agent's full transcript.
+    pub rollout_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum SubAgentOutcome {
+    /// The sub-agent finished its task.
+    Completed,
+    /// The sub-agent was stopped after using up its turn or token budget.
+    BudgetExhausted,
+    /// The sub-agent stopped because of an error.
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SessionSettingsUpdatedEvent {
+    /// Model used for subsequent turns.
+    pub model: String,
+
+    /// Approval policy used for subsequent turns.
+    pub approval_policy: AskForApproval,
+
+    /// Sandbox policy used for subsequent turns.
+    pub sandbox_policy: SandboxPolicy,
+
+    /// Reasoning effort used for subsequent turns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffortConfig>,
+
+    /// Profile switched to with `Op::SwitchProfile`, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
 /// User's decision in response to an ExecApprovalRequest.
 #[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
 #[serde(rename_all = "snake_case")]
@@ -1369,4 +2389,25 @@ mod tests {
         assert_eq!(deserialized, event);
         Ok(())
     }
+
+    #[test]
+    fn mcp_tool_call_progress_display() {
+        let progress = |progress, total, message: Option<&str>| McpToolCallProgressEvent {
+            call_id: "call1".to_string(),
+            progress,
+            total,
+            message: message.map(str::to_string),
+        };
+
+        assert_eq!(
+            "25% indexing",
+            progress(1.0, Some(4.0), Some("indexing")).to_string()
+        );
+        assert_eq!("100%", progress(5.0, Some(4.0), None).to_string());
+        assert_eq!(
+            "indexing",
+            progress(3.0, None, Some("indexing")).to_string()
+        );
+        assert_eq!("3", progress(3.0, None, None).to_string());
+    }
 }
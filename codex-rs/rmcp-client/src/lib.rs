@@ -1,5 +1,18 @@
 mod logging_client_handler;
+mod oauth;
 mod rmcp_client;
 mod utils;
 
+pub use oauth::OAuthCredentialsStore;
+pub use oauth::StoredOAuthTokens;
+pub use oauth::delete_oauth_tokens;
+pub use oauth::load_oauth_tokens;
+pub use oauth::oauth_access_token;
+pub use oauth::oauth_tokens_path;
+pub use oauth::perform_oauth_login;
+pub use oauth::save_oauth_tokens;
+pub use rmcp_client::ElicitationHandler;
+pub use rmcp_client::ProgressHandler;
 pub use rmcp_client::RmcpClient;
+pub use rmcp_client::RootsHandler;
+pub use rmcp_client::SamplingHandler;
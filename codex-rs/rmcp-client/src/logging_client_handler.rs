@@ -4,7 +4,11 @@ use rmcp::model::CancelledNotificationParam;
 use rmcp::model::ClientInfo;
 use rmcp::model::CreateElicitationRequestParam;
 use rmcp::model::CreateElicitationResult;
+use rmcp::model::CreateMessageRequestMethod;
+use rmcp::model::CreateMessageRequestParam;
+use rmcp::model::CreateMessageResult;
 use rmcp::model::ElicitationAction;
+use rmcp::model::ListRootsResult;
 use rmcp::model::LoggingLevel;
 use rmcp::model::LoggingMessageNotificationParam;
 use rmcp::model::ProgressNotificationParam;
@@ -16,32 +20,94 @@ use tracing::error;
 use tracing::info;
 use tracing::warn;
 
-#[derive(Debug, Clone)]
+use crate::rmcp_client::ElicitationHandler;
+use crate::rmcp_client::ProgressHandlers;
+use crate::rmcp_client::RootsHandler;
+use crate::rmcp_client::SamplingHandler;
+use crate::utils::convert_to_mcp;
+use crate::utils::convert_to_rmcp;
+
+#[derive(Clone)]
 pub(crate) struct LoggingClientHandler {
     client_info: ClientInfo,
+    sampling_handler: Option<SamplingHandler>,
+    roots_handler: Option<RootsHandler>,
+    elicitation_handler: Option<ElicitationHandler>,
+    progress_handlers: ProgressHandlers,
 }
 
 impl LoggingClientHandler {
-    pub(crate) fn new(client_info: ClientInfo) -> Self {
-        Self { client_info }
+    pub(crate) fn new(
+        client_info: ClientInfo,
+        sampling_handler: Option<SamplingHandler>,
+        roots_handler: Option<RootsHandler>,
+        elicitation_handler: Option<ElicitationHandler>,
+        progress_handlers: ProgressHandlers,
+    ) -> Self {
+        Self {
+            client_info,
+            sampling_handler,
+            roots_handler,
+            elicitation_handler,
+            progress_handlers,
+        }
     }
 }
 
 impl ClientHandler for LoggingClientHandler {
-    // TODO (CODEX-3571): support elicitations.
     async fn create_elicitation(
         &self,
         request: CreateElicitationRequestParam,
         _context: RequestContext<RoleClient>,
     ) -> Result<CreateElicitationResult, rmcp::ErrorData> {
-        info!(
-            "MCP server requested elicitation ({}). Elicitations are not supported yet. Declining.",
-            request.message
-        );
-        Ok(CreateElicitationResult {
-            action: ElicitationAction::Decline,
-            content: None,
-        })
+        let Some(handler) = &self.elicitation_handler else {
+            info!(
+                "MCP server requested elicitation ({}), which is not enabled. Declining.",
+                request.message
+            );
+            return Ok(CreateElicitationResult {
+                action: ElicitationAction::Decline,
+                content: None,
+            });
+        };
+        let params = convert_to_mcp(request)
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+        let result = handler(params)
+            .await
+            .map_err(|message| rmcp::ErrorData::internal_error(message, None))?;
+        convert_to_rmcp(result).map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))
+    }
+
+    async fn create_message(
+        &self,
+        params: CreateMessageRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateMessageResult, rmcp::ErrorData> {
+        let Some(handler) = &self.sampling_handler else {
+            info!("MCP server requested sampling, which is not enabled. Declining.");
+            return Err(rmcp::ErrorData::method_not_found::<
+                CreateMessageRequestMethod,
+            >());
+        };
+        let params = convert_to_mcp(params)
+            .map_err(|e| rmcp::ErrorData::invalid_params(e.to_string(), None))?;
+        let result = handler(params)
+            .await
+            .map_err(|message| rmcp::ErrorData::internal_error(message, None))?;
+        convert_to_rmcp(result).map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))
+    }
+
+    async fn list_roots(
+        &self,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<ListRootsResult, rmcp::ErrorData> {
+        let roots = self
+            .roots_handler
+            .as_ref()
+            .map(|handler| handler())
+            .unwrap_or_default();
+        convert_to_rmcp(mcp_types::ListRootsResult { roots })
+            .map_err(|e| rmcp::ErrorData::internal_error(e.to_string(), None))
     }
 
     async fn on_cancelled(
@@ -60,10 +126,25 @@ impl ClientHandler for LoggingClientHandler {
         params: ProgressNotificationParam,
         _context: NotificationContext<RoleClient>,
     ) {
-        info!(
+        debug!(
             "MCP server progress notification (token: {:?}, progress: {}, total: {:?}, message: {:?})",
             params.progress_token, params.progress, params.total, params.message
         );
+        let params: mcp_types::ProgressNotificationParams = match convert_to_mcp(params) {
+            Ok(params) => params,
+            Err(e) => {
+                warn!("invalid progress notification: {e}");
+                return;
+            }
+        };
+        let handler = self
+            .progress_handlers
+            .lock()
+            .ok()
+            .and_then(|guard| guard.get(&params.progress_token).cloned());
+        if let Some(handler) = handler {
+            handler(params);
+        }
     }
 
     async fn on_resource_updated(
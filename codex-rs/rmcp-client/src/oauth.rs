@@ -0,0 +1,825 @@
+//! OAuth for MCP servers that require authorization, following the MCP
+//! authorization spec.
+//!
+//! [`perform_oauth_login`] discovers the server's authorization server
+//! (protected resource metadata, then authorization server metadata),
+//! registers Codex as a client dynamically, and runs the authorization code
+//! flow with PKCE through the user's browser and a callback server on
+//! localhost. The resulting tokens are stored per server where
+//! [`OAuthCredentialsStore`] says: in the OS credential store, or under
+//! `$CODEX_HOME/mcp_oauth/` with owner-only permissions. [`oauth_access_token`]
+//! returns them, refreshing them first when they are about to expire.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use anyhow::bail;
+use base64::Engine;
+use codex_keyring_store::KeyringStore;
+use codex_keyring_store::OsKeyring;
+use rand::RngCore;
+use reqwest::header::ACCEPT;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use sha2::Digest;
+use sha2::Sha256;
+use url::Url;
+
+/// Directory of `$CODEX_HOME` holding the tokens, one file per server.
+const OAUTH_DIR: &str = "mcp_oauth";
+
+const CALLBACK_PATH: &str = "/callback";
+
+/// How long the user has to complete the authorization in the browser.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Access tokens this close to expiring are refreshed before use.
+const REFRESH_MARGIN_SECS: u64 = 60;
+
+/// Where the OAuth tokens of MCP servers are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthCredentialsStore {
+    /// The OS credential store, or a file when it is unavailable. Tokens
+    /// stored in a file earlier are still read.
+    #[default]
+    Auto,
+    /// Only the OS credential store.
+    Keychain,
+    /// Only files under `$CODEX_HOME/mcp_oauth/`.
+    File,
+}
+
+/// The tokens and client registration stored for one MCP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredOAuthTokens {
+    /// URL of the MCP server the tokens were issued for.
+    pub server_url: String,
+    pub client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub token_endpoint: String,
+    pub access_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Unix time, in seconds, at which the access token expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl StoredOAuthTokens {
+    fn needs_refresh(&self, now: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| now + REFRESH_MARGIN_SECS >= expires_at)
+    }
+}
+
+/// RFC 9728 protected resource metadata, as far as it is used here.
+#[derive(Debug, Deserialize)]
+struct ProtectedResourceMetadata {
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+    #[serde(default)]
+    scopes_supported: Vec<String>,
+}
+
+/// RFC 8414 authorization server metadata, as far as it is used here.
+#[derive(Debug, Deserialize, PartialEq)]
+struct AuthorizationServerMetadata {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    #[serde(default)]
+    registration_endpoint: Option<String>,
+}
+
+/// RFC 7591 client registration response.
+#[derive(Debug, Deserialize)]
+struct ClientRegistration {
+    client_id: String,
+    #[serde(default)]
+    client_secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+pub fn oauth_tokens_path(codex_home: &Path, server_name: &str) -> PathBuf {
+    codex_home
+        .join(OAUTH_DIR)
+        .join(format!("{server_name}.json"))
+}
+
+/// Account of the OS credential store entry holding the tokens of
+/// `server_name`.
+fn keyring_account(server_name: &str) -> String {
+    format!("{OAUTH_DIR}/{server_name}")
+}
+
+/// The tokens stored for `server_name`, or `None` if there are none or they
+/// were issued for a server at another URL.
+pub fn load_oauth_tokens(
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    server_url: &str,
+) -> Result<Option<StoredOAuthTokens>> {
+    load_tokens(&OsKeyring, codex_home, store, server_name, server_url)
+}
+
+/// Store `tokens` for `server_name`.
+pub fn save_oauth_tokens(
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    tokens: &StoredOAuthTokens,
+) -> Result<()> {
+    save_tokens(&OsKeyring, codex_home, store, server_name, tokens)
+}
+
+/// Remove the tokens of `server_name`, wherever they are stored. Returns
+/// whether there were any.
+pub fn delete_oauth_tokens(
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+) -> Result<bool> {
+    delete_tokens(&OsKeyring, codex_home, store, server_name)
+}
+
+fn load_tokens(
+    keyring: &dyn KeyringStore,
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    server_url: &str,
+) -> Result<Option<StoredOAuthTokens>> {
+    let tokens = match store {
+        OAuthCredentialsStore::File => read_tokens_file(codex_home, server_name)?,
+        OAuthCredentialsStore::Keychain => read_tokens_keyring(keyring, server_name)?,
+        OAuthCredentialsStore::Auto => match read_tokens_keyring(keyring, server_name) {
+            Ok(Some(tokens)) => Some(tokens),
+            Ok(None) => read_tokens_file(codex_home, server_name)?,
+            Err(e) => {
+                tracing::warn!("{e:#}; reading the OAuth tokens of `{server_name}` from a file");
+                read_tokens_file(codex_home, server_name)?
+            }
+        },
+    };
+    Ok(tokens.filter(|tokens| tokens.server_url == server_url))
+}
+
+fn save_tokens(
+    keyring: &dyn KeyringStore,
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    tokens: &StoredOAuthTokens,
+) -> Result<()> {
+    if store == OAuthCredentialsStore::File {
+        return write_tokens_file(codex_home, server_name, tokens);
+    }
+    let json = serde_json::to_string(tokens)?;
+    match keyring.save(&keyring_account(server_name), &json) {
+        Ok(()) => {
+            // Do not leave older tokens behind in a file.
+            remove_tokens_file(codex_home, server_name)?;
+            Ok(())
+        }
+        Err(e) if store == OAuthCredentialsStore::Auto => {
+            tracing::warn!("{e}; storing the OAuth tokens of `{server_name}` in a file");
+            write_tokens_file(codex_home, server_name, tokens)
+        }
+        Err(e) => Err(e).with_context(|| {
+            format!(
+                "failed to store the OAuth tokens of `{server_name}` in the OS credential store"
+            )
+        }),
+    }
+}
+
+fn delete_tokens(
+    keyring: &dyn KeyringStore,
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+) -> Result<bool> {
+    let in_file = remove_tokens_file(codex_home, server_name)?;
+    let in_keyring = match store {
+        OAuthCredentialsStore::File => false,
+        OAuthCredentialsStore::Keychain => keyring.delete(&keyring_account(server_name))?,
+        OAuthCredentialsStore::Auto => keyring
+            .delete(&keyring_account(server_name))
+            .unwrap_or_else(|e| {
+                tracing::warn!("{e}");
+                false
+            }),
+    };
+    Ok(in_file || in_keyring)
+}
+
+fn read_tokens_keyring(
+    keyring: &dyn KeyringStore,
+    server_name: &str,
+) -> Result<Option<StoredOAuthTokens>> {
+    let Some(json) = keyring.load(&keyring_account(server_name))? else {
+        return Ok(None);
+    };
+    let tokens = serde_json::from_str(&json).with_context(|| {
+        format!("failed to parse the OAuth tokens of `{server_name}` in the OS credential store")
+    })?;
+    Ok(Some(tokens))
+}
+
+fn read_tokens_file(codex_home: &Path, server_name: &str) -> Result<Option<StoredOAuthTokens>> {
+    let path = oauth_tokens_path(codex_home, server_name);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let tokens = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(Some(tokens))
+}
+
+/// Write `tokens` to the file of `server_name`, readable only by the current
+/// user.
+fn write_tokens_file(
+    codex_home: &Path,
+    server_name: &str,
+    tokens: &StoredOAuthTokens,
+) -> Result<()> {
+    let path = oauth_tokens_path(codex_home, server_name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(tokens)?;
+    let mut options = OpenOptions::new();
+    options.truncate(true).write(true).create(true);
+    #[cfg(unix)]
+    {
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    file.write_all(json.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+fn remove_tokens_file(codex_home: &Path, server_name: &str) -> Result<bool> {
+    let path = oauth_tokens_path(codex_home, server_name);
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e).with_context(|| format!("failed to remove {}", path.display())),
+    }
+}
+
+/// The access token for `server_name`, or `None` if the user has not logged
+/// in to it. Tokens about to expire are refreshed and stored again first.
+pub async fn oauth_access_token(
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    server_url: &str,
+) -> Result<Option<String>> {
+    let Some(tokens) = load_oauth_tokens(codex_home, store, server_name, server_url)? else {
+        return Ok(None);
+    };
+    let now = now_secs();
+    if !tokens.needs_refresh(now) {
+        return Ok(Some(tokens.access_token));
+    }
+    let Some(refresh_token) = tokens.refresh_token.clone() else {
+        bail!(
+            "the OAuth token of MCP server `{server_name}` expired; run `codex mcp login {server_name}`"
+        );
+    };
+
+    let client = reqwest::Client::new();
+    let response = request_token(
+        &client,
+        &tokens.token_endpoint,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &tokens.client_id),
+            ("resource", server_url),
+        ],
+        tokens.client_secret.as_deref(),
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "refreshing the OAuth token of MCP server `{server_name}` failed; run `codex mcp login {server_name}`"
+        )
+    })?;
+    let refreshed = StoredOAuthTokens {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token.or(Some(refresh_token)),
+        expires_at: response.expires_in.map(|secs| now + secs),
+        ..tokens
+    };
+    save_oauth_tokens(codex_home, store, server_name, &refreshed)?;
+    Ok(Some(refreshed.access_token))
+}
+
+/// Authorize Codex with the MCP server at `server_url` and store the tokens
+/// for `server_name`. `on_authorize_url` is given the URL the user has to
+/// open, typically to print it and open the browser.
+pub async fn perform_oauth_login(
+    codex_home: &Path,
+    store: OAuthCredentialsStore,
+    server_name: &str,
+    server_url: &str,
+    on_authorize_url: impl FnOnce(&str),
+) -> Result<()> {
+    let server = Url::parse(server_url).with_context(|| format!("invalid URL `{server_url}`"))?;
+    let client = reqwest::Client::new();
+
+    let resource = fetch_first_json::<ProtectedResourceMetadata>(
+        &client,
+        &well_known_urls(&server, "oauth-protected-resource"),
+    )
+    .await;
+    let issuer = match resource
+        .as_ref()
+        .and_then(|metadata| metadata.authorization_servers.first())
+    {
+        Some(issuer) => Url::parse(issuer)
+            .with_context(|| format!("invalid authorization server URL `{issuer}`"))?,
+        None => origin(&server),
+    };
+    let metadata = discover_authorization_server(&client, &issuer).await;
+    let Some(registration_endpoint) = metadata.registration_endpoint.as_deref() else {
+        bail!("the authorization server of `{server_name}` does not support client registration");
+    };
+
+    let callback_server = tiny_http::Server::http("127.0.0.1:0")
+        .map_err(|e| anyhow!("failed to start the OAuth callback server: {e}"))?;
+    let port = callback_server
+        .server_addr()
+        .to_ip()
+        .map(|addr| addr.port())
+        .ok_or_else(|| anyhow!("failed to determine the OAuth callback port"))?;
+    let redirect_uri = format!("http://127.0.0.1:{port}{CALLBACK_PATH}");
+
+    let registration = register_client(&client, registration_endpoint, &redirect_uri).await?;
+    let (code_verifier, code_challenge) = generate_pkce();
+    let state = random_token(32);
+    let scopes = resource
+        .map(|metadata| metadata.scopes_supported)
+        .unwrap_or_default();
+    let authorize_url = build_authorize_url(
+        &metadata.authorization_endpoint,
+        &registration.client_id,
+        &redirect_uri,
+        &code_challenge,
+        &state,
+        server_url,
+        &scopes,
+    )?;
+    on_authorize_url(authorize_url.as_str());
+
+    let code = tokio::task::spawn_blocking(move || wait_for_callback(&callback_server, &state))
+        .await
+        .context("OAuth callback server failed")??;
+
+    let response = request_token(
+        &client,
+        &metadata.token_endpoint,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", &registration.client_id),
+            ("code_verifier", &code_verifier),
+            ("resource", server_url),
+        ],
+        registration.client_secret.as_deref(),
+    )
+    .await?;
+    let tokens = StoredOAuthTokens {
+        server_url: server_url.to_string(),
+        client_id: registration.client_id,
+        client_secret: registration.client_secret,
+        token_endpoint: metadata.token_endpoint,
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: response.expires_in.map(|secs| now_secs() + secs),
+    };
+    save_oauth_tokens(codex_home, store, server_name, &tokens)
+}
+
+/// The metadata of the authorization server `issuer`. Servers without
+/// metadata get the default `/authorize`, `/token` and `/register` endpoints.
+async fn discover_authorization_server(
+    client: &reqwest::Client,
+    issuer: &Url,
+) -> AuthorizationServerMetadata {
+    let mut urls = well_known_urls(issuer, "oauth-authorization-server");
+    urls.extend(well_known_urls(issuer, "openid-configuration"));
+    if let Some(metadata) = fetch_first_json(client, &urls).await {
+        return metadata;
+    }
+    let base = origin(issuer);
+    let endpoint = |path: &str| {
+        base.join(path)
+            .map(|url| url.to_string())
+            .unwrap_or_default()
+    };
+    AuthorizationServerMetadata {
+        authorization_endpoint: endpoint("/authorize"),
+        token_endpoint: endpoint("/token"),
+        registration_endpoint: Some(endpoint("/register")),
+    }
+}
+
+/// The well-known URLs for `suffix`, most specific first: RFC 8414 and RFC
+/// 9728 insert the well-known segment between the host and the path.
+fn well_known_urls(url: &Url, suffix: &str) -> Vec<Url> {
+    let mut base = origin(url);
+    base.set_query(None);
+    let path = url.path().trim_end_matches('/');
+    let mut urls = Vec::new();
+    if !path.is_empty() {
+        let mut with_path = base.clone();
+        with_path.set_path(&format!("/.well-known/{suffix}{path}"));
+        urls.push(with_path);
+    }
+    let mut root = base;
+    root.set_path(&format!("/.well-known/{suffix}"));
+    urls.push(root);
+    urls
+}
+
+fn origin(url: &Url) -> Url {
+    let mut origin = url.clone();
+    origin.set_path("/");
+    origin.set_query(None);
+    origin.set_fragment(None);
+    origin
+}
+
+async fn fetch_first_json<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    urls: &[Url],
+) -> Option<T> {
+    for url in urls {
+        let response = match client
+            .get(url.clone())
+            .header(ACCEPT, "application/json")
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => response,
+            _ => continue,
+        };
+        if let Ok(value) = response.json::<T>().await {
+            return Some(value);
+        }
+    }
+    None
+}
+
+async fn register_client(
+    client: &reqwest::Client,
+    registration_endpoint: &str,
+    redirect_uri: &str,
+) -> Result<ClientRegistration> {
+    let response = client
+        .post(registration_endpoint)
+        .header(ACCEPT, "application/json")
+        .json(&serde_json::json!({
+            "client_name": "Codex",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        }))
+        .send()
+        .await
+        .context("client registration request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("client registration failed with {status}: {body}");
+    }
+    response
+        .json()
+        .await
+        .context("failed to parse the client registration")
+}
+
+async fn request_token(
+    client: &reqwest::Client,
+    token_endpoint: &str,
+    params: &[(&str, &str)],
+    client_secret: Option<&str>,
+) -> Result<TokenResponse> {
+    let mut form = params.to_vec();
+    if let Some(secret) = client_secret {
+        form.push(("client_secret", secret));
+    }
+    let response = client
+        .post(token_endpoint)
+        .header(ACCEPT, "application/json")
+        .form(&form)
+        .send()
+        .await
+        .context("token request failed")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("token request failed with {status}: {body}");
+    }
+    response
+        .json()
+        .await
+        .context("failed to parse the token response")
+}
+
+fn build_authorize_url(
+    authorization_endpoint: &str,
+    client_id: &str,
+    redirect_uri: &str,
+    code_challenge: &str,
+    state: &str,
+    resource: &str,
+    scopes: &[String],
+) -> Result<Url> {
+    let mut url = Url::parse(authorization_endpoint)
+        .with_context(|| format!("invalid authorization endpoint `{authorization_endpoint}`"))?;
+    {
+        let mut query = url.query_pairs_mut();
+        query
+            .append_pair("response_type", "code")
+            .append_pair("client_id", client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", state)
+            .append_pair("resource", resource);
+        if !scopes.is_empty() {
+            query.append_pair("scope", &scopes.join(" "));
+        }
+    }
+    Ok(url)
+}
+
+/// Wait for the browser to be redirected to the callback and return the
+/// authorization code.
+fn wait_for_callback(server: &tiny_http::Server, expected_state: &str) -> Result<String> {
+    let deadline = Instant::now() + LOGIN_TIMEOUT;
+    loop {
+        let remaining = deadline
+            .checked_duration_since(Instant::now())
+            .ok_or_else(|| anyhow!("timed out waiting for the OAuth authorization"))?;
+        let Some(request) = server.recv_timeout(remaining)? else {
+            bail!("timed out waiting for the OAuth authorization");
+        };
+        let Some(outcome) = parse_callback(request.url(), expected_state) else {
+            let _ = request.respond(tiny_http::Response::empty(404));
+            continue;
+        };
+        let body = match &outcome {
+            Ok(_) => {
+                "Authorization complete. You can close this window and return to Codex.".to_string()
+            }
+            Err(e) => format!("Authorization failed: {e}"),
+        };
+        let _ = request.respond(tiny_http::Response::from_string(body));
+        return outcome;
+    }
+}
+
+/// The authorization code of a request to the callback, or `None` for
+/// requests to other paths, such as the browser asking for a favicon.
+fn parse_callback(request_url: &str, expected_state: &str) -> Option<Result<String>> {
+    let url = Url::parse(&format!("http://127.0.0.1{request_url}")).ok()?;
+    if url.path() != CALLBACK_PATH {
+        return None;
+    }
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    if let Some(error) = params.get("error") {
+        let description = params
+            .get("error_description")
+            .map(|description| format!(": {description}"))
+            .unwrap_or_default();
+        return Some(Err(anyhow!(
+            "the authorization server returned `{error}`{description}"
+        )));
+    }
+    if params.get("state").map(String::as_str) != Some(expected_state) {
+        return Some(Err(anyhow!("the OAuth state did not match")));
+    }
+    Some(
+        params
+            .get("code")
+            .cloned()
+            .ok_or_else(|| anyhow!("the callback did not include an authorization code")),
+    )
+}
+
+/// A PKCE code verifier and its S256 challenge.
+fn generate_pkce() -> (String, String) {
+    let verifier = random_token(64);
+    let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(Sha256::digest(verifier.as_bytes()));
+    (verifier, challenge)
+}
+
+fn random_token(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::rng().fill_bytes(&mut bytes);
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_keyring_store::MemoryKeyring;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn tokens(server_url: &str) -> StoredOAuthTokens {
+        StoredOAuthTokens {
+            server_url: server_url.to_string(),
+            client_id: "client".to_string(),
+            client_secret: None,
+            token_endpoint: "https://auth.example.com/token".to_string(),
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_at: Some(1_000),
+        }
+    }
+
+    #[test]
+    fn well_known_urls_insert_the_segment_before_the_path() {
+        let url = Url::parse("https://example.com/v1/mcp?x=1").expect("url");
+        assert_eq!(
+            vec![
+                "https://example.com/.well-known/oauth-protected-resource/v1/mcp".to_string(),
+                "https://example.com/.well-known/oauth-protected-resource".to_string(),
+            ],
+            well_known_urls(&url, "oauth-protected-resource")
+                .iter()
+                .map(Url::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn authorize_url_carries_pkce_state_and_resource() {
+        let url = build_authorize_url(
+            "https://auth.example.com/authorize",
+            "client",
+            "http://127.0.0.1:1234/callback",
+            "challenge",
+            "state",
+            "https://example.com/mcp",
+            &["read".to_string(), "write".to_string()],
+        )
+        .expect("url");
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            Some(&"S256".to_string()),
+            params.get("code_challenge_method")
+        );
+        assert_eq!(
+            Some(&"https://example.com/mcp".to_string()),
+            params.get("resource")
+        );
+        assert_eq!(Some(&"read write".to_string()), params.get("scope"));
+    }
+
+    #[test]
+    fn parse_callback_checks_path_state_and_errors() {
+        assert!(parse_callback("/favicon.ico", "s").is_none());
+        assert_eq!(
+            "abc",
+            parse_callback("/callback?code=abc&state=s", "s")
+                .expect("callback")
+                .expect("code")
+        );
+        assert!(
+            parse_callback("/callback?code=abc&state=other", "s")
+                .expect("callback")
+                .is_err()
+        );
+        assert!(
+            parse_callback("/callback?error=access_denied&state=s", "s")
+                .expect("callback")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn tokens_round_trip_and_are_scoped_to_the_url() -> Result<()> {
+        let codex_home = TempDir::new()?;
+        let file = OAuthCredentialsStore::File;
+        let stored = tokens("https://example.com/mcp");
+        save_oauth_tokens(codex_home.path(), file, "docs", &stored)?;
+
+        assert_eq!(
+            Some(stored),
+            load_oauth_tokens(codex_home.path(), file, "docs", "https://example.com/mcp")?
+        );
+        assert_eq!(
+            None,
+            load_oauth_tokens(
+                codex_home.path(),
+                file,
+                "docs",
+                "https://other.example.com/mcp"
+            )?
+        );
+        assert!(delete_oauth_tokens(codex_home.path(), file, "docs")?);
+        assert!(!delete_oauth_tokens(codex_home.path(), file, "docs")?);
+        Ok(())
+    }
+
+    #[test]
+    fn keyring_tokens_replace_tokens_stored_in_a_file() -> Result<()> {
+        let codex_home = TempDir::new()?;
+        let keyring = MemoryKeyring::default();
+        let auto = OAuthCredentialsStore::Auto;
+        let url = "https://example.com/mcp";
+        let in_file = tokens(url);
+        write_tokens_file(codex_home.path(), "docs", &in_file)?;
+
+        assert_eq!(
+            Some(in_file),
+            load_tokens(&keyring, codex_home.path(), auto, "docs", url)?
+        );
+
+        let refreshed = StoredOAuthTokens {
+            access_token: "refreshed".to_string(),
+            ..tokens(url)
+        };
+        save_tokens(&keyring, codex_home.path(), auto, "docs", &refreshed)?;
+
+        assert!(!oauth_tokens_path(codex_home.path(), "docs").exists());
+        assert!(keyring.load("mcp_oauth/docs")?.is_some());
+        assert_eq!(
+            Some(refreshed),
+            load_tokens(
+                &keyring,
+                codex_home.path(),
+                OAuthCredentialsStore::Keychain,
+                "docs",
+                url
+            )?
+        );
+        assert!(delete_tokens(&keyring, codex_home.path(), auto, "docs")?);
+        assert_eq!(
+            None,
+            load_tokens(&keyring, codex_home.path(), auto, "docs", url)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokens_are_refreshed_shortly_before_expiry() {
+        let stored = tokens("https://example.com/mcp");
+        assert!(!stored.needs_refresh(900));
+        assert!(stored.needs_refresh(950));
+        assert!(
+            !StoredOAuthTokens {
+                expires_at: None,
+                ..stored
+            }
+            .needs_refresh(u64::MAX / 2)
+        );
+    }
+}
@@ -1,22 +1,48 @@
 use std::collections::HashMap;
 use std::ffi::OsString;
+use std::future::Future;
 use std::io;
+use std::pin::Pin;
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::Mutex as StdMutex;
 use std::time::Duration;
 
+use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use futures::FutureExt;
 use mcp_types::CallToolRequestParams;
 use mcp_types::CallToolResult;
+use mcp_types::CreateMessageRequestParams;
+use mcp_types::CreateMessageResult;
+use mcp_types::ElicitRequestParams;
+use mcp_types::ElicitResult;
+use mcp_types::GetPromptRequestParams;
+use mcp_types::GetPromptResult;
 use mcp_types::InitializeRequestParams;
 use mcp_types::InitializeResult;
+use mcp_types::ListPromptsRequestParams;
+use mcp_types::ListPromptsResult;
+use mcp_types::ListResourcesRequestParams;
+use mcp_types::ListResourcesResult;
 use mcp_types::ListToolsRequestParams;
 use mcp_types::ListToolsResult;
+use mcp_types::ProgressNotificationParams;
+use mcp_types::ProgressToken;
+use mcp_types::ReadResourceRequestParams;
+use mcp_types::ReadResourceResult;
+use mcp_types::Root;
+use rmcp::model::CallToolRequest;
 use rmcp::model::CallToolRequestParam;
+use rmcp::model::ClientRequest;
+use rmcp::model::GetPromptRequestParam;
 use rmcp::model::InitializeRequestParam;
 use rmcp::model::PaginatedRequestParam;
+use rmcp::model::PingRequest;
+use rmcp::model::ReadResourceRequestParam;
+use rmcp::model::ServerResult;
+use rmcp::service::PeerRequestOptions;
 use rmcp::service::RoleClient;
 use rmcp::service::RunningService;
 use rmcp::service::{self};
@@ -32,6 +58,7 @@ use tracing::info;
 use tracing::warn;
 
 use crate::logging_client_handler::LoggingClientHandler;
+use crate::utils::build_default_headers;
 use crate::utils::convert_call_tool_result;
 use crate::utils::convert_to_mcp;
 use crate::utils::convert_to_rmcp;
@@ -52,10 +79,45 @@ enum ClientState {
     },
 }
 
+/// Answers the server's `sampling/createMessage` requests. An error is
+/// returned to the server with the given message.
+pub type SamplingHandler = Arc<
+    dyn Fn(
+            CreateMessageRequestParams,
+        )
+            -> Pin<Box<dyn Future<Output = std::result::Result<CreateMessageResult, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Answers the server's `elicitation/create` requests, usually by asking the
+/// user. An error is returned to the server with the given message.
+pub type ElicitationHandler = Arc<
+    dyn Fn(
+            ElicitRequestParams,
+        )
+            -> Pin<Box<dyn Future<Output = std::result::Result<ElicitResult, String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Lists the client's roots for the server's `roots/list` requests.
+pub type RootsHandler = Arc<dyn Fn() -> Vec<Root> + Send + Sync>;
+
+/// Receives the `notifications/progress` the server sends for a request.
+pub type ProgressHandler = Arc<dyn Fn(ProgressNotificationParams) + Send + Sync>;
+
+/// Progress token -> handler of the request it was sent with.
+pub(crate) type ProgressHandlers = Arc<StdMutex<HashMap<ProgressToken, ProgressHandler>>>;
+
 /// MCP client implemented on top of the official `rmcp` SDK.
 /// https://github.com/modelcontextprotocol/rust-sdk
 pub struct RmcpClient {
     state: Mutex<ClientState>,
+    sampling_handler: StdMutex<Option<SamplingHandler>>,
+    roots_handler: StdMutex<Option<RootsHandler>>,
+    elicitation_handler: StdMutex<Option<ElicitationHandler>>,
+    progress_handlers: ProgressHandlers,
 }
 
 impl RmcpClient {
@@ -100,24 +162,69 @@ impl RmcpClient {
             state: Mutex::new(ClientState::Connecting {
                 transport: Some(PendingTransport::ChildProcess(transport)),
             }),
+            sampling_handler: StdMutex::new(None),
+            roots_handler: StdMutex::new(None),
+            elicitation_handler: StdMutex::new(None),
+            progress_handlers: ProgressHandlers::default(),
         })
     }
 
-    pub fn new_streamable_http_client(url: String, bearer_token: Option<String>) -> Result<Self> {
+    /// Connect to a server over the streamable HTTP transport. `http_headers`
+    /// are sent with every request.
+    pub fn new_streamable_http_client(
+        url: String,
+        bearer_token: Option<String>,
+        http_headers: Option<HashMap<String, String>>,
+    ) -> Result<Self> {
         let mut config = StreamableHttpClientTransportConfig::with_uri(url);
         if let Some(token) = bearer_token {
             config = config.auth_header(format!("Bearer {token}"));
         }
 
-        let transport = StreamableHttpClientTransport::from_config(config);
+        let http_client = reqwest::Client::builder()
+            .default_headers(build_default_headers(http_headers.unwrap_or_default())?)
+            .build()
+            .context("failed to build HTTP client")?;
+        let transport = StreamableHttpClientTransport::with_client(http_client, config);
 
         Ok(Self {
             state: Mutex::new(ClientState::Connecting {
                 transport: Some(PendingTransport::StreamableHttp(transport)),
             }),
+            sampling_handler: StdMutex::new(None),
+            roots_handler: StdMutex::new(None),
+            elicitation_handler: StdMutex::new(None),
+            progress_handlers: ProgressHandlers::default(),
         })
     }
 
+    /// Answer the server's `sampling/createMessage` requests with `handler`.
+    /// Set it before [`initialize`](Self::initialize), whose parameters should
+    /// then advertise the `sampling` capability.
+    pub fn set_sampling_handler(&self, handler: SamplingHandler) {
+        if let Ok(mut guard) = self.sampling_handler.lock() {
+            *guard = Some(handler);
+        }
+    }
+
+    /// Answer the server's `roots/list` requests with `handler`. Set it
+    /// before [`initialize`](Self::initialize), whose parameters should then
+    /// advertise the `roots` capability.
+    pub fn set_roots_handler(&self, handler: RootsHandler) {
+        if let Ok(mut guard) = self.roots_handler.lock() {
+            *guard = Some(handler);
+        }
+    }
+
+    /// Answer the server's `elicitation/create` requests with `handler`. Set
+    /// it before [`initialize`](Self::initialize), whose parameters should
+    /// then advertise the `elicitation` capability.
+    pub fn set_elicitation_handler(&self, handler: ElicitationHandler) {
+        if let Ok(mut guard) = self.elicitation_handler.lock() {
+            *guard = Some(handler);
+        }
+    }
+
     /// Perform the initialization handshake with the MCP server.
     /// https://modelcontextprotocol.io/specification/2025-06-18/basic/lifecycle#initialization
     pub async fn initialize(
@@ -138,7 +245,28 @@ impl RmcpClient {
         };
 
         let client_info = convert_to_rmcp::<_, InitializeRequestParam>(params.clone())?;
-        let client_handler = LoggingClientHandler::new(client_info);
+        let sampling_handler = self
+            .sampling_handler
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().cloned());
+        let roots_handler = self
+            .roots_handler
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().cloned());
+        let elicitation_handler = self
+            .elicitation_handler
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().cloned());
+        let client_handler = LoggingClientHandler::new(
+            client_info,
+            sampling_handler,
+            roots_handler,
+            elicitation_handler,
+            Arc::clone(&self.progress_handlers),
+        );
         let service_future = match transport {
             PendingTransport::ChildProcess(transport) => {
                 service::serve_client(client_handler.clone(), transport).boxed()
@@ -203,6 +331,113 @@ impl RmcpClient {
         convert_call_tool_result(rmcp_result)
     }
 
+    /// Like [`call_tool`](Self::call_tool), but passes every
+    /// `notifications/progress` the server sends for the call to
+    /// `on_progress`.
+    pub async fn call_tool_with_progress(
+        &self,
+        name: String,
+        arguments: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+        on_progress: ProgressHandler,
+    ) -> Result<CallToolResult> {
+        let service = self.service().await?;
+        let params = CallToolRequestParams { arguments, name };
+        let rmcp_params: CallToolRequestParam = convert_to_rmcp(params)?;
+        // rmcp attaches a progress token to every request it sends.
+        let handle = service
+            .send_request_with_option(
+                ClientRequest::CallToolRequest(CallToolRequest::new(rmcp_params)),
+                PeerRequestOptions::no_options(),
+            )
+            .await
+            .map_err(|err| anyhow!("tools/call failed: {err}"))?;
+        let token: ProgressToken = convert_to_mcp(handle.progress_token.clone())?;
+
+        if let Ok(mut guard) = self.progress_handlers.lock() {
+            guard.insert(token.clone(), on_progress);
+        }
+        let result = run_with_timeout(handle.await_response(), timeout, "tools/call").await;
+        if let Ok(mut guard) = self.progress_handlers.lock() {
+            guard.remove(&token);
+        }
+        match result? {
+            ServerResult::CallToolResult(result) => convert_call_tool_result(result),
+            other => Err(anyhow!("unexpected response to tools/call: {other:?}")),
+        }
+    }
+
+    pub async fn list_resources(
+        &self,
+        params: Option<ListResourcesRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListResourcesResult> {
+        let service = self.service().await?;
+        let rmcp_params = params
+            .map(convert_to_rmcp::<_, PaginatedRequestParam>)
+            .transpose()?;
+
+        let fut = service.list_resources(rmcp_params);
+        let result = run_with_timeout(fut, timeout, "resources/list").await?;
+        convert_to_mcp(result)
+    }
+
+    pub async fn read_resource(
+        &self,
+        params: ReadResourceRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<ReadResourceResult> {
+        let service = self.service().await?;
+        let rmcp_params: ReadResourceRequestParam = convert_to_rmcp(params)?;
+        let fut = service.read_resource(rmcp_params);
+        let result = run_with_timeout(fut, timeout, "resources/read").await?;
+        convert_to_mcp(result)
+    }
+
+    pub async fn list_prompts(
+        &self,
+        params: Option<ListPromptsRequestParams>,
+        timeout: Option<Duration>,
+    ) -> Result<ListPromptsResult> {
+        let service = self.service().await?;
+        let rmcp_params = params
+            .map(convert_to_rmcp::<_, PaginatedRequestParam>)
+            .transpose()?;
+
+        let fut = service.list_prompts(rmcp_params);
+        let result = run_with_timeout(fut, timeout, "prompts/list").await?;
+        convert_to_mcp(result)
+    }
+
+    pub async fn get_prompt(
+        &self,
+        params: GetPromptRequestParams,
+        timeout: Option<Duration>,
+    ) -> Result<GetPromptResult> {
+        let service = self.service().await?;
+        let rmcp_params: GetPromptRequestParam = convert_to_rmcp(params)?;
+        let fut = service.get_prompt(rmcp_params);
+        let result = run_with_timeout(fut, timeout, "prompts/get").await?;
+        convert_to_mcp(result)
+    }
+
+    /// Send `ping` to check that the server is alive and responsive.
+    pub async fn ping(&self, timeout: Option<Duration>) -> Result<()> {
+        let service = self.service().await?;
+        let fut = service.send_request(ClientRequest::PingRequest(PingRequest::default()));
+        run_with_timeout(fut, timeout, "ping").await?;
+        Ok(())
+    }
+
+    /// Tell the server that the list of roots changed.
+    pub async fn notify_roots_list_changed(&self) -> Result<()> {
+        let service = self.service().await?;
+        service
+            .notify_roots_list_changed()
+            .await
+            .map_err(|err| anyhow!("notifications/roots/list_changed failed: {err}"))
+    }
+
     async fn service(&self) -> Result<Arc<RunningService<RoleClient, LoggingClientHandler>>> {
         let guard = self.state.lock().await;
         match &*guard {
@@ -6,6 +6,9 @@ use anyhow::Context;
 use anyhow::Result;
 use anyhow::anyhow;
 use mcp_types::CallToolResult;
+use reqwest::header::HeaderMap;
+use reqwest::header::HeaderName;
+use reqwest::header::HeaderValue;
 use rmcp::model::CallToolResult as RmcpCallToolResult;
 use rmcp::service::ServiceError;
 use serde_json::Value;
@@ -78,6 +81,20 @@ pub(crate) fn create_env_for_mcp_server(
         .collect()
 }
 
+/// Convert the configured HTTP headers of a server into a [`HeaderMap`],
+/// rejecting names and values that are not valid in a header.
+pub(crate) fn build_default_headers(headers: HashMap<String, String>) -> Result<HeaderMap> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("invalid HTTP header name `{name}`"))?;
+        let header_value = HeaderValue::from_str(&value)
+            .with_context(|| format!("invalid value for HTTP header `{name}`"))?;
+        header_map.insert(header_name, header_value);
+    }
+    Ok(header_map)
+}
+
 #[cfg(unix)]
 pub(crate) const DEFAULT_ENV_VARS: &[&str] = &[
     "HOME",
@@ -119,6 +136,29 @@ mod tests {
         assert_eq!(env.get("TZ"), Some(&value));
     }
 
+    #[test]
+    fn build_default_headers_validates_names_and_values() -> Result<()> {
+        let headers = build_default_headers(HashMap::from([(
+            "X-Api-Key".to_string(),
+            "secret".to_string(),
+        )]))?;
+        assert_eq!(
+            headers.get("x-api-key").and_then(|v| v.to_str().ok()),
+            Some("secret")
+        );
+
+        assert!(
+            build_default_headers(HashMap::from([("bad header".to_string(), "v".to_string())]))
+                .is_err()
+        );
+        assert!(
+            build_default_headers(HashMap::from([("X-Ok".to_string(), "a\nb".to_string())]))
+                .is_err()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn convert_call_tool_result_defaults_missing_content() -> Result<()> {
         let structured_content = json!({ "key": "value" });
@@ -6,6 +6,7 @@ use crate::chatwidget::ChatWidget;
 use crate::diff_render::DiffSummary;
 use crate::exec_command::strip_bash_lc_and_escape;
 use crate::file_search::FileSearchManager;
+use crate::history_cell;
 use crate::history_cell::HistoryCell;
 use crate::pager_overlay::Overlay;
 use crate::render::highlight::highlight_bash_to_lines;
@@ -17,6 +18,7 @@ use codex_core::AuthManager;
 use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::persist_model_selection;
+use codex_core::doctor::startup_report;
 use codex_core::model_family::find_family_for_model;
 use codex_core::protocol::SessionSource;
 use codex_core::protocol::TokenUsage;
@@ -154,6 +156,14 @@ impl App {
             backtrack: BacktrackState::default(),
         };
 
+        // Show the configuration problems found without going to the network;
+        // `codex doctor` runs every check.
+        for problem in startup_report(&app.config).problems() {
+            app.app_event_tx.send(AppEvent::InsertHistoryCell(Box::new(
+                history_cell::new_warning_event(problem.to_string()),
+            )));
+        }
+
         let tui_events = tui.event_stream();
         tokio::pin!(tui_events);
 
@@ -0,0 +1,530 @@
+//! Form shown when an MCP server asks the user for structured input
+//! (`elicitation/create`). Each property of the requested schema becomes one
+//! field; the answer is sent back with `Op::ResolveElicitation`.
+
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+use ratatui::widgets::Wrap;
+use serde_json::Map;
+use serde_json::Value;
+
+use codex_core::protocol::ElicitationAction;
+use codex_core::protocol::Op;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+use crate::render::renderable::Renderable;
+
+use super::CancellationEvent;
+use super::bottom_pane_view::BottomPaneView;
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldKind {
+    Text,
+    Number {
+        integer: bool,
+    },
+    Boolean,
+    /// One of the values of the property's `enum`.
+    Choice(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FormField {
+    name: String,
+    label: String,
+    description: Option<String>,
+    required: bool,
+    kind: FieldKind,
+    /// Input of text and number fields.
+    text: String,
+    /// Value of boolean fields.
+    checked: bool,
+    /// Selected value of choice fields.
+    choice: usize,
+}
+
+impl FormField {
+    fn toggle(&mut self, forward: bool) {
+        match &self.kind {
+            FieldKind::Boolean => self.checked = !self.checked,
+            FieldKind::Choice(values) if !values.is_empty() => {
+                let len = values.len();
+                self.choice = if forward {
+                    (self.choice + 1) % len
+                } else {
+                    (self.choice + len - 1) % len
+                };
+            }
+            FieldKind::Text | FieldKind::Number { .. } | FieldKind::Choice(_) => {}
+        }
+    }
+
+    fn takes_text(&self) -> bool {
+        matches!(self.kind, FieldKind::Text | FieldKind::Number { .. })
+    }
+
+    /// The value sent to the server, `None` for an empty optional field.
+    fn value(&self) -> Result<Option<Value>, String> {
+        let text = self.text.trim();
+        match &self.kind {
+            FieldKind::Boolean => Ok(Some(Value::Bool(self.checked))),
+            FieldKind::Choice(values) => Ok(values
+                .get(self.choice)
+                .map(|value| Value::String(value.clone()))),
+            FieldKind::Text | FieldKind::Number { .. } if text.is_empty() => {
+                if self.required {
+                    Err(format!("{} is required", self.label))
+                } else {
+                    Ok(None)
+                }
+            }
+            FieldKind::Text => Ok(Some(Value::String(self.text.clone()))),
+            FieldKind::Number { integer: true } => text
+                .parse::<i64>()
+                .map(|n| Some(Value::from(n)))
+                .map_err(|_| format!("{} must be a whole number", self.label)),
+            FieldKind::Number { integer: false } => text
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(|n| Some(Value::Number(n)))
+                .ok_or_else(|| format!("{} must be a number", self.label)),
+        }
+    }
+
+    fn display_value(&self, selected: bool) -> Vec<Span<'static>> {
+        match &self.kind {
+            FieldKind::Boolean => {
+                vec![if self.checked { "[x]" } else { "[ ]" }.into()]
+            }
+            FieldKind::Choice(values) => {
+                let value = values.get(self.choice).cloned().unwrap_or_default();
+                if selected {
+                    vec!["‹ ".dim(), value.into(), " ›".dim()]
+                } else {
+                    vec![value.into()]
+                }
+            }
+            FieldKind::Text | FieldKind::Number { .. } => {
+                let mut spans = Vec::new();
+                if self.text.is_empty() && !selected {
+                    if let Some(description) = &self.description {
+                        spans.push(description.clone().dim());
+                    }
+                } else {
+                    spans.push(self.text.clone().into());
+                }
+                if selected {
+                    spans.push(" ".reversed());
+                }
+                spans
+            }
+        }
+    }
+}
+
+/// Fields for the top-level properties of `schema`, in the order the schema
+/// lists them.
+fn fields_from_schema(schema: &Value) -> Vec<FormField> {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let choices: Option<Vec<String>> =
+                property
+                    .get("enum")
+                    .and_then(Value::as_array)
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .map(str::to_string)
+                            .collect()
+                    });
+            let kind = match (choices, property.get("type").and_then(Value::as_str)) {
+                (Some(values), _) => FieldKind::Choice(values),
+                (None, Some("boolean")) => FieldKind::Boolean,
+                (None, Some("integer")) => FieldKind::Number { integer: true },
+                (None, Some("number")) => FieldKind::Number { integer: false },
+                (None, _) => FieldKind::Text,
+            };
+            let default = property.get("default");
+            let text = match default {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Number(n)) => n.to_string(),
+                _ => String::new(),
+            };
+            let choice = match (&kind, default.and_then(Value::as_str)) {
+                (FieldKind::Choice(values), Some(default)) => {
+                    values.iter().position(|v| v == default).unwrap_or(0)
+                }
+                _ => 0,
+            };
+            FormField {
+                name: name.clone(),
+                label: property
+                    .get("title")
+                    .and_then(Value::as_str)
+                    .unwrap_or(name)
+                    .to_string(),
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                required: required.contains(&name.as_str()),
+                kind,
+                text,
+                checked: default.and_then(Value::as_bool).unwrap_or(false),
+                choice,
+            }
+        })
+        .collect()
+}
+
+/// The content of an accepted form, or the first problem with its fields.
+fn form_content(fields: &[FormField]) -> Result<Value, String> {
+    let mut content = Map::new();
+    for field in fields {
+        if let Some(value) = field.value()? {
+            content.insert(field.name.clone(), value);
+        }
+    }
+    Ok(Value::Object(content))
+}
+
+/// Form answering one elicitation request.
+pub(crate) struct ElicitationFormView {
+    id: String,
+    server: String,
+    message: String,
+    fields: Vec<FormField>,
+    selected: usize,
+    error: Option<String>,
+    app_event_tx: AppEventSender,
+    complete: bool,
+}
+
+impl ElicitationFormView {
+    pub(crate) fn new(
+        id: String,
+        server: String,
+        message: String,
+        requested_schema: &Value,
+        app_event_tx: AppEventSender,
+    ) -> Self {
+        Self {
+            id,
+            server,
+            message,
+            fields: fields_from_schema(requested_schema),
+            selected: 0,
+            error: None,
+            app_event_tx,
+            complete: false,
+        }
+    }
+
+    fn resolve(&mut self, action: ElicitationAction, content: Option<Value>) {
+        self.app_event_tx
+            .send(AppEvent::CodexOp(Op::ResolveElicitation {
+                id: self.id.clone(),
+                action,
+                content,
+            }));
+        self.complete = true;
+    }
+
+    fn submit(&mut self) {
+        match form_content(&self.fields) {
+            Ok(content) => self.resolve(ElicitationAction::Accept, Some(content)),
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn move_selection(&mut self, forward: bool) {
+        let len = self.fields.len();
+        if len == 0 {
+            return;
+        }
+        self.selected = if forward {
+            (self.selected + 1) % len
+        } else {
+            (self.selected + len - 1) % len
+        };
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(vec![
+                gutter(),
+                format!("MCP server `{}` asks for input", self.server).bold(),
+            ]),
+            Line::from(vec![gutter(), self.message.clone().into()]),
+            Line::from(""),
+        ];
+        for (idx, field) in self.fields.iter().enumerate() {
+            let selected = idx == self.selected;
+            let mut spans = vec![
+                if selected { "› ".cyan() } else { "  ".into() },
+                if selected {
+                    field.label.clone().cyan()
+                } else {
+                    field.label.clone().into()
+                },
+            ];
+            if field.required {
+                spans.push("*".red());
+            }
+            spans.push(": ".into());
+            spans.extend(field.display_value(selected));
+            lines.push(Line::from(spans));
+        }
+        if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone().red()));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "Enter to submit · Esc to decline · Tab to move between fields".dim(),
+        ));
+        lines
+    }
+}
+
+impl BottomPaneView for ElicitationFormView {
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if key_event.kind == KeyEventKind::Release {
+            return;
+        }
+        let takes_text = self
+            .fields
+            .get(self.selected)
+            .is_some_and(FormField::takes_text);
+        match key_event.code {
+            KeyCode::Esc => self.resolve(ElicitationAction::Decline, None),
+            KeyCode::Enter => self.submit(),
+            KeyCode::Tab | KeyCode::Down => self.move_selection(true),
+            KeyCode::BackTab | KeyCode::Up => self.move_selection(false),
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if !takes_text => {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.toggle(key_event.code != KeyCode::Left);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.text.pop();
+                }
+            }
+            KeyCode::Char(c)
+                if takes_text
+                    && !key_event
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                if let Some(field) = self.fields.get_mut(self.selected) {
+                    field.text.push(c);
+                    self.error = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_ctrl_c(&mut self) -> CancellationEvent {
+        self.resolve(ElicitationAction::Cancel, None);
+        CancellationEvent::Handled
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn handle_paste(&mut self, pasted: String) -> bool {
+        match self.fields.get_mut(self.selected) {
+            Some(field) if field.takes_text() => {
+                field.text.push_str(&pasted.replace(['\r', '\n'], " "));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Renderable for ElicitationFormView {
+    fn desired_height(&self, width: u16) -> u16 {
+        Paragraph::new(self.lines())
+            .wrap(Wrap { trim: false })
+            .line_count(width)
+            .try_into()
+            .unwrap_or(u16::MAX)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 || area.width == 0 {
+            return;
+        }
+        Clear.render(area, buf);
+        Paragraph::new(self.lines())
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+fn gutter() -> Span<'static> {
+    "▌ ".cyan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "title": "Name"},
+                "count": {"type": "integer", "default": 3},
+                "confirm": {"type": "boolean"},
+                "color": {"type": "string", "enum": ["red", "green"], "default": "green"}
+            },
+            "required": ["name"]
+        })
+    }
+
+    fn view(tx: AppEventSender) -> ElicitationFormView {
+        ElicitationFormView::new(
+            "elicitation-1".to_string(),
+            "docs".to_string(),
+            "Who are you?".to_string(),
+            &schema(),
+            tx,
+        )
+    }
+
+    fn press(view: &mut ElicitationFormView, code: KeyCode) {
+        view.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn parses_schema_properties_into_fields() {
+        let fields = fields_from_schema(&schema());
+        let kinds: Vec<(&str, &FieldKind, bool)> = fields
+            .iter()
+            .map(|f| (f.name.as_str(), &f.kind, f.required))
+            .collect();
+        let choice = FieldKind::Choice(vec!["red".to_string(), "green".to_string()]);
+        let mut expected = vec![
+            ("name", &FieldKind::Text, true),
+            ("count", &FieldKind::Number { integer: true }, false),
+            ("confirm", &FieldKind::Boolean, false),
+            ("color", &choice, false),
+        ];
+        let mut actual = kinds;
+        expected.sort_by_key(|(name, _, _)| *name);
+        actual.sort_by_key(|(name, _, _)| *name);
+        assert_eq!(actual, expected);
+
+        let content = form_content(
+            &fields
+                .into_iter()
+                .map(|mut f| {
+                    if f.name == "name" {
+                        f.text = "codex".to_string();
+                    }
+                    f
+                })
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            content,
+            Ok(json!({"name": "codex", "count": 3, "confirm": false, "color": "green"}))
+        );
+    }
+
+    #[test]
+    fn invalid_fields_block_submission() {
+        let mut fields = fields_from_schema(&schema());
+        assert_eq!(form_content(&fields), Err("Name is required".to_string()));
+        for field in &mut fields {
+            match field.name.as_str() {
+                "name" => field.text = "codex".to_string(),
+                "count" => field.text = "many".to_string(),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            form_content(&fields),
+            Err("count must be a whole number".to_string())
+        );
+    }
+
+    #[test]
+    fn enter_accepts_and_esc_declines() {
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let mut form = view(AppEventSender::new(tx));
+        let name = form
+            .fields
+            .iter()
+            .position(|f| f.name == "name")
+            .expect("name field");
+        form.selected = name;
+
+        press(&mut form, KeyCode::Enter);
+        assert!(!form.is_complete());
+        assert_eq!(form.error.as_deref(), Some("Name is required"));
+
+        for c in "ada".chars() {
+            press(&mut form, KeyCode::Char(c));
+        }
+        press(&mut form, KeyCode::Enter);
+        assert!(form.is_complete());
+        match rx.try_recv() {
+            Ok(AppEvent::CodexOp(Op::ResolveElicitation {
+                id,
+                action,
+                content,
+            })) => {
+                assert_eq!(id, "elicitation-1");
+                assert_eq!(action, ElicitationAction::Accept);
+                assert_eq!(
+                    content.and_then(|c| c.get("name").cloned()),
+                    Some(json!("ada"))
+                );
+            }
+            other => panic!("expected ResolveElicitation, got {other:?}"),
+        }
+
+        let (tx, mut rx) = unbounded_channel::<AppEvent>();
+        let mut form = view(AppEventSender::new(tx));
+        press(&mut form, KeyCode::Esc);
+        assert!(form.is_complete());
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(AppEvent::CodexOp(Op::ResolveElicitation {
+                action: ElicitationAction::Decline,
+                content: None,
+                ..
+            }))
+        ));
+    }
+}
@@ -18,6 +18,17 @@ pub(crate) struct FooterProps {
     pub(crate) use_shift_enter_hint: bool,
     pub(crate) is_task_running: bool,
     pub(crate) context_window_percent: Option<u8>,
+    pub(crate) session_cost: Option<SessionCost>,
+}
+
+/// Estimated cost of the session so far.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SessionCost {
+    /// In US dollars, of the turns whose model has a known price.
+    pub(crate) usd: f64,
+    pub(crate) turns: u32,
+    /// Turns left out of `usd` because their model has no known price.
+    pub(crate) unpriced_turns: u32,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -71,20 +82,34 @@ pub(crate) fn render_footer(area: Rect, buf: &mut Buffer, props: FooterProps) {
     .render(area, buf);
 }
 
+fn session_cost_label(cost: SessionCost) -> String {
+    let usd = cost.usd;
+    match cost.unpriced_turns {
+        0 => format!("${usd:.2}"),
+        n if n == cost.turns => "cost unavailable".to_string(),
+        1 => format!("${usd:.2}, 1 turn unpriced"),
+        n => format!("${usd:.2}, {n} turns unpriced"),
+    }
+}
+
 fn footer_lines(props: FooterProps) -> Vec<Line<'static>> {
     match props.mode {
         FooterMode::CtrlCReminder => vec![ctrl_c_reminder_line(CtrlCReminderState {
             is_task_running: props.is_task_running,
         })],
         FooterMode::ShortcutPrompt => {
-            if props.is_task_running {
-                vec![context_window_line(props.context_window_percent)]
+            let mut line = if props.is_task_running {
+                context_window_line(props.context_window_percent)
             } else {
-                vec![Line::from(vec![
+                Line::from(vec![
                     key_hint::plain(KeyCode::Char('?')).into(),
                     " for shortcuts".dim(),
-                ])]
+                ])
+            };
+            if let Some(cost) = props.session_cost {
+                line.push_span(format!(" · {}", session_cost_label(cost)).dim());
             }
+            vec![line]
         }
         FooterMode::ShortcutOverlay => shortcut_overlay_lines(ShortcutsState {
             use_shift_enter_hint: props.use_shift_enter_hint,
@@ -382,6 +407,7 @@ const SHORTCUTS: &[ShortcutDescriptor] = &[
 mod tests {
     use super::*;
     use insta::assert_snapshot;
+    use pretty_assertions::assert_eq;
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
 
@@ -407,6 +433,7 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: false,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -418,6 +445,7 @@ mod tests {
                 use_shift_enter_hint: true,
                 is_task_running: false,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -429,6 +457,7 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: false,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -440,6 +469,7 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: true,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -451,6 +481,7 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: false,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -462,6 +493,7 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: false,
                 context_window_percent: None,
+                session_cost: None,
             },
         );
 
@@ -473,7 +505,47 @@ mod tests {
                 use_shift_enter_hint: false,
                 is_task_running: true,
                 context_window_percent: Some(72),
+                session_cost: None,
             },
         );
     }
+
+    #[test]
+    fn shows_the_session_cost_next_to_the_prompt() {
+        let text = |is_task_running| {
+            footer_lines(FooterProps {
+                mode: FooterMode::ShortcutPrompt,
+                esc_backtrack_hint: false,
+                use_shift_enter_hint: false,
+                is_task_running,
+                context_window_percent: Some(72),
+                session_cost: Some(SessionCost {
+                    usd: 0.4213,
+                    turns: 3,
+                    unpriced_turns: 0,
+                }),
+            })
+            .iter()
+            .flat_map(|line| line.spans.iter().map(|span| span.content.to_string()))
+            .collect::<String>()
+        };
+
+        assert_eq!(text(false), "? for shortcuts · $0.42");
+        assert_eq!(text(true), "72% context left · $0.42");
+    }
+
+    #[test]
+    fn says_when_the_cost_of_turns_is_unknown() {
+        let label = |unpriced_turns| {
+            session_cost_label(SessionCost {
+                usd: 0.4213,
+                turns: 3,
+                unpriced_turns,
+            })
+        };
+
+        assert_eq!(label(1), "$0.42, 1 turn unpriced");
+        assert_eq!(label(2), "$0.42, 2 turns unpriced");
+        assert_eq!(label(3), "cost unavailable");
+    }
 }
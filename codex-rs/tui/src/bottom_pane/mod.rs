@@ -17,11 +17,13 @@ use std::time::Duration;
 mod approval_overlay;
 pub(crate) use approval_overlay::ApprovalOverlay;
 pub(crate) use approval_overlay::ApprovalRequest;
+pub(crate) use elicitation_form_view::ElicitationFormView;
 mod bottom_pane_view;
 mod chat_composer;
 mod chat_composer_history;
 mod command_popup;
 pub mod custom_prompt_view;
+mod elicitation_form_view;
 mod file_search_popup;
 mod footer;
 mod list_selection_view;
@@ -42,7 +44,11 @@ pub(crate) enum CancellationEvent {
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::McpPrompt;
+pub(crate) use footer::SessionCost;
+pub(crate) use prompt_args::McpServerCommand;
 
+use crate::status_indicator_widget::PlanProgress;
 use crate::status_indicator_widget::StatusIndicatorWidget;
 pub(crate) use list_selection_view::SelectionAction;
 pub(crate) use list_selection_view::SelectionItem;
@@ -69,7 +75,9 @@ pub(crate) struct BottomPane {
     /// Queued user messages to show under the status indicator.
     queued_user_messages: Vec<String>,
     context_window_percent: Option<u8>,
+    session_cost: Option<SessionCost>,
     background_process_count: usize,
+    plan_progress: Option<PlanProgress>,
 }
 
 pub(crate) struct BottomPaneParams {
@@ -103,7 +111,9 @@ impl BottomPane {
             queued_user_messages: Vec::new(),
             esc_backtrack_hint: false,
             context_window_percent: None,
+            session_cost: None,
             background_process_count: 0,
+            plan_progress: None,
         }
     }
 
@@ -338,6 +348,7 @@ impl BottomPane {
             if let Some(status) = self.status.as_mut() {
                 status.set_queued_messages(self.queued_user_messages.clone());
                 status.set_background_process_count(self.background_process_count);
+                status.set_plan_progress(self.plan_progress.clone());
             }
             self.request_redraw();
         } else {
@@ -363,6 +374,17 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Show the estimated cost of the session in the footer.
+    pub(crate) fn set_session_cost(&mut self, cost: Option<SessionCost>) {
+        if self.session_cost == cost {
+            return;
+        }
+
+        self.session_cost = cost;
+        self.composer.set_session_cost(cost);
+        self.request_redraw();
+    }
+
     pub(crate) fn set_background_process_count(&mut self, count: usize) {
         if self.background_process_count == count {
             return;
@@ -374,6 +396,18 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update the plan progress shown under the status header.
+    pub(crate) fn set_plan_progress(&mut self, progress: Option<PlanProgress>) {
+        if self.plan_progress == progress {
+            return;
+        }
+        self.plan_progress = progress.clone();
+        if let Some(status) = self.status.as_mut() {
+            status.set_plan_progress(progress);
+        }
+        self.request_redraw();
+    }
+
     /// Show a generic list selection view with the provided items.
     pub(crate) fn show_selection_view(&mut self, params: list_selection_view::SelectionViewParams) {
         let view = list_selection_view::ListSelectionView::new(params, self.app_event_tx.clone());
@@ -396,6 +430,12 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Update the MCP server prompts available for the slash popup.
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPrompt>) {
+        self.composer.set_mcp_prompts(prompts);
+        self.request_redraw();
+    }
+
     pub(crate) fn composer_is_empty(&self) -> bool {
         self.composer.is_empty()
     }
@@ -1,4 +1,7 @@
+use crate::slash_command::SlashCommand;
 use codex_protocol::custom_prompts::CustomPrompt;
+use codex_protocol::custom_prompts::MCP_PROMPTS_CMD_PREFIX;
+use codex_protocol::custom_prompts::McpPrompt;
 use codex_protocol::custom_prompts::PROMPTS_CMD_PREFIX;
 use lazy_static::lazy_static;
 use regex_lite::Regex;
@@ -192,6 +195,99 @@ pub fn expand_custom_prompt(
     Ok(Some(expanded))
 }
 
+/// An MCP prompt invocation parsed from `/mcp:server:prompt KEY=value …`. The
+/// server substitutes the arguments when the prompt is expanded.
+#[derive(Debug, PartialEq)]
+pub struct McpPromptInvocation {
+    pub server: String,
+    pub name: String,
+    pub arguments: HashMap<String, String>,
+}
+
+/// The slash command token of an MCP prompt, without the leading '/'.
+pub fn mcp_prompt_command(prompt: &McpPrompt) -> String {
+    format!("{MCP_PROMPTS_CMD_PREFIX}:{}:{}", prompt.server, prompt.name)
+}
+
+/// Parses a message of the form `/mcp:server:prompt KEY=value …` naming one of
+/// `mcp_prompts`.
+///
+/// Returns `Ok(None)` when the text is not such a command, and an error when
+/// the arguments cannot be parsed or a required one is missing.
+pub fn parse_mcp_prompt_invocation(
+    text: &str,
+    mcp_prompts: &[McpPrompt],
+) -> Result<Option<McpPromptInvocation>, PromptExpansionError> {
+    let Some((name, rest)) = parse_slash_name(text) else {
+        return Ok(None);
+    };
+    let Some(prompt) = mcp_prompts.iter().find(|p| mcp_prompt_command(p) == name) else {
+        return Ok(None);
+    };
+    let arguments = parse_prompt_inputs(rest).map_err(|error| PromptExpansionError::Args {
+        command: format!("/{name}"),
+        error,
+    })?;
+    let missing: Vec<String> = prompt
+        .arguments
+        .iter()
+        .filter(|arg| arg.required && !arguments.contains_key(&arg.name))
+        .map(|arg| arg.name.clone())
+        .collect();
+    if !missing.is_empty() {
+        return Err(PromptExpansionError::MissingArgs {
+            command: format!("/{name}"),
+            missing,
+        });
+    }
+    Ok(Some(McpPromptInvocation {
+        server: prompt.server.clone(),
+        name: prompt.name.clone(),
+        arguments,
+    }))
+}
+
+/// A `/mcp connect <server>` or `/mcp disconnect <server>` command.
+#[derive(Debug, PartialEq)]
+pub enum McpServerCommand {
+    Connect(String),
+    Disconnect(String),
+}
+
+/// Parses `/mcp connect <server>` and `/mcp disconnect <server>`. Returns
+/// `None` for any other text, including a bare `/mcp`.
+pub fn parse_mcp_server_command(text: &str) -> Option<McpServerCommand> {
+    let (name, rest) = parse_slash_name(text)?;
+    if name != SlashCommand::Mcp.command() {
+        return None;
+    }
+    let mut words = rest.split_whitespace();
+    let (action, server) = (words.next()?, words.next()?.to_string());
+    if words.next().is_some() {
+        return None;
+    }
+    match action {
+        "connect" => Some(McpServerCommand::Connect(server)),
+        "disconnect" => Some(McpServerCommand::Disconnect(server)),
+        _ => None,
+    }
+}
+
+/// Parses `/profile <name>` into the name of the profile to switch to. Returns
+/// `None` for any other text, including a bare `/profile`.
+pub fn parse_profile_command(text: &str) -> Option<String> {
+    let (name, rest) = parse_slash_name(text)?;
+    if name != SlashCommand::Profile.command() {
+        return None;
+    }
+    let mut words = rest.split_whitespace();
+    let profile = words.next()?.to_string();
+    if words.next().is_some() {
+        return None;
+    }
+    Some(profile)
+}
+
 /// Detect whether `content` contains numeric placeholders ($1..$9) or `$ARGUMENTS`.
 pub fn prompt_has_numeric_placeholders(content: &str) -> bool {
     if content.contains("$ARGUMENTS") {
@@ -299,7 +395,17 @@ pub fn expand_numeric_placeholders(content: &str, args: &[String]) -> String {
 /// Constructs a command text for a custom prompt with arguments.
 /// Returns the text and the cursor position (inside the first double quote).
 pub fn prompt_command_with_arg_placeholders(name: &str, args: &[String]) -> (String, usize) {
-    let mut text = format!("/{PROMPTS_CMD_PREFIX}:{name}");
+    command_with_arg_placeholders(format!("/{PROMPTS_CMD_PREFIX}:{name}"), args)
+}
+
+/// Constructs the command text of an MCP prompt with its arguments.
+/// Returns the text and the cursor position (inside the first double quote).
+pub fn mcp_prompt_command_with_arg_placeholders(prompt: &McpPrompt) -> (String, usize) {
+    let args: Vec<String> = prompt.arguments.iter().map(|a| a.name.clone()).collect();
+    command_with_arg_placeholders(format!("/{}", mcp_prompt_command(prompt)), &args)
+}
+
+fn command_with_arg_placeholders(mut text: String, args: &[String]) -> (String, usize) {
     let mut cursor: usize = text.len();
     for (i, arg) in args.iter().enumerate() {
         text.push_str(format!(" {arg}=\"\"").as_str());
@@ -313,6 +419,62 @@ pub fn prompt_command_with_arg_placeholders(name: &str, args: &[String]) -> (Str
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::custom_prompts::McpPromptArgument;
+
+    fn review_mcp_prompt() -> McpPrompt {
+        McpPrompt {
+            server: "team".to_string(),
+            name: "review".to_string(),
+            title: None,
+            description: None,
+            arguments: vec![
+                McpPromptArgument {
+                    name: "path".to_string(),
+                    description: None,
+                    required: true,
+                },
+                McpPromptArgument {
+                    name: "focus".to_string(),
+                    description: None,
+                    required: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn parse_mcp_prompt_invocation_collects_arguments() {
+        let prompts = vec![review_mcp_prompt()];
+
+        let invocation =
+            parse_mcp_prompt_invocation("/mcp:team:review path=\"src/main.rs\"", &prompts).unwrap();
+        assert_eq!(
+            invocation,
+            Some(McpPromptInvocation {
+                server: "team".to_string(),
+                name: "review".to_string(),
+                arguments: HashMap::from([("path".to_string(), "src/main.rs".to_string())]),
+            })
+        );
+        assert_eq!(
+            parse_mcp_prompt_invocation("/prompts:review path=a", &prompts).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_mcp_prompt_invocation_reports_missing_required_args() {
+        let prompts = vec![review_mcp_prompt()];
+
+        let err = parse_mcp_prompt_invocation("/mcp:team:review focus=tests", &prompts)
+            .unwrap_err()
+            .user_message();
+        assert!(err.contains("Missing required args for /mcp:team:review: path"));
+
+        let (text, cursor) = mcp_prompt_command_with_arg_placeholders(&prompts[0]);
+        assert_eq!(text, "/mcp:team:review path=\"\" focus=\"\"");
+        assert_eq!(cursor, "/mcp:team:review path=\"".len());
+    }
 
     #[test]
     fn expand_arguments_basic() {
@@ -403,4 +565,31 @@ mod tests {
         let out = expand_custom_prompt("/prompts:my-prompt", &prompts).unwrap();
         assert_eq!(out, Some("literal $$USER".to_string()));
     }
+
+    #[test]
+    fn parses_mcp_server_commands() {
+        assert_eq!(
+            parse_mcp_server_command("/mcp connect github"),
+            Some(McpServerCommand::Connect("github".to_string()))
+        );
+        assert_eq!(
+            parse_mcp_server_command("/mcp disconnect github"),
+            Some(McpServerCommand::Disconnect("github".to_string()))
+        );
+        assert_eq!(parse_mcp_server_command("/mcp"), None);
+        assert_eq!(parse_mcp_server_command("/mcp connect"), None);
+        assert_eq!(parse_mcp_server_command("/mcp connect a b"), None);
+        assert_eq!(parse_mcp_server_command("/mcp restart github"), None);
+    }
+
+    #[test]
+    fn parses_profile_commands() {
+        assert_eq!(
+            parse_profile_command("/profile deep-reasoning"),
+            Some("deep-reasoning".to_string())
+        );
+        assert_eq!(parse_profile_command("/profile"), None);
+        assert_eq!(parse_profile_command("/profile a b"), None);
+        assert_eq!(parse_profile_command("/model gpt-5"), None);
+    }
 }
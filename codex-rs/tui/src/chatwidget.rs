@@ -4,7 +4,9 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use codex_core::config::Config;
+use codex_core::config_profile::SessionProfile;
 use codex_core::config_types::Notifications;
+use codex_core::config_types::ToolFilter;
 use codex_core::git_info::current_branch_name;
 use codex_core::git_info::local_git_branches;
 use codex_core::protocol::AgentMessageDeltaEvent;
@@ -14,8 +16,18 @@ use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalBatchRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::BackgroundProcessStatusEvent;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::CheckpointCreatedEvent;
+use codex_core::protocol::CheckpointRestoredEvent;
+use codex_core::protocol::ConfigReloadedEvent;
+use codex_core::protocol::ContextBudgetEvent;
+use codex_core::protocol::ContextCompactedEvent;
+use codex_core::protocol::CostSummaryEvent;
+use codex_core::protocol::ElicitationRequestEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
@@ -26,24 +38,37 @@ use codex_core::protocol::ExitedReviewModeEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
+use codex_core::protocol::McpGetPromptResponseEvent;
+use codex_core::protocol::McpListPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpServerConnectionEvent;
+use codex_core::protocol::McpServerHealthEvent;
+use codex_core::protocol::McpServerHealthStatus;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::McpToolCallProgressEvent;
+use codex_core::protocol::ModelFailoverEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubAgentBeginEvent;
+use codex_core::protocol::SubAgentEndEvent;
+use codex_core::protocol::SubAgentOutcome;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
+use codex_core::protocol::ToolCallArgumentsDeltaEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
+use codex_core::protocol::TurnUndoneEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::ViewImageToolCallEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
 use codex_protocol::ConversationId;
+use codex_protocol::custom_prompts::MCP_PROMPTS_CMD_PREFIX;
 use codex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -65,10 +90,13 @@ use crate::bottom_pane::ApprovalRequest;
 use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
+use crate::bottom_pane::ElicitationFormView;
 use crate::bottom_pane::InputResult;
+use crate::bottom_pane::McpServerCommand;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
 use crate::bottom_pane::SelectionViewParams;
+use crate::bottom_pane::SessionCost;
 use crate::bottom_pane::custom_prompt_view::CustomPromptView;
 use crate::bottom_pane::popup_consts::standard_popup_hint_line;
 use crate::clipboard_paste::paste_image_to_temp_png;
@@ -84,6 +112,7 @@ use crate::history_cell::McpToolCallCell;
 use crate::markdown::append_markdown;
 use crate::slash_command::SlashCommand;
 use crate::status::RateLimitSnapshotDisplay;
+use crate::status_indicator_widget::PlanProgress;
 use crate::text_formatting::truncate_text;
 use crate::tui::FrameRequester;
 mod interrupts;
@@ -104,7 +133,11 @@ use codex_common::model_presets::builtin_model_presets;
 use codex_core::AuthManager;
 use codex_core::ConversationManager;
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxDeniedEvent;
+use codex_core::protocol::SandboxOperation;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::SessionBusyEvent;
+use codex_core::protocol::SessionSettingsUpdatedEvent;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_file_search::FileMatch;
 use codex_git_tooling::CreateGhostCommitOptions;
@@ -124,6 +157,10 @@ struct RunningCommand {
 
 const RATE_LIMIT_WARNING_THRESHOLDS: [f64; 3] = [75.0, 90.0, 95.0];
 
+/// Share of the context window left at which the user is warned that the
+/// conversation is about to be compacted.
+const CONTEXT_WARNING_PERCENT_REMAINING: u8 = 10;
+
 #[derive(Default)]
 struct RateLimitWarningState {
     secondary_index: usize,
@@ -230,6 +267,9 @@ pub(crate) struct ChatWidget {
     token_info: Option<TokenUsageInfo>,
     rate_limit_snapshot: Option<RateLimitSnapshotDisplay>,
     rate_limit_warnings: RateLimitWarningState,
+    // Whether the nearly full context window has been reported since it last
+    // had room.
+    context_budget_warned: bool,
     // Stream lifecycle controller
     stream_controller: Option<StreamController>,
     running_commands: HashMap<String, RunningCommand>,
@@ -319,8 +359,9 @@ impl ChatWidget {
         if let Some(messages) = initial_messages {
             self.replay_initial_messages(messages);
         }
-        // Ask codex-core to enumerate custom prompts for this session.
+        // Ask codex-core to enumerate custom and MCP prompts for this session.
         self.submit_op(Op::ListCustomPrompts);
+        self.submit_op(Op::ListMcpPrompts);
         if let Some(user_message) = self.initial_user_message.take() {
             self.submit_user_message(user_message);
         }
@@ -344,6 +385,16 @@ impl ChatWidget {
         self.handle_streaming_delta(delta);
     }
 
+    fn on_tool_call_arguments_delta(&mut self, ev: ToolCallArgumentsDeltaEvent) {
+        // Large patches can take a while to generate; show progress in the
+        // status indicator before the call itself is complete.
+        if ev.tool_name == "apply_patch" {
+            self.bottom_pane
+                .update_status_header("Writing patch".to_string());
+            self.request_redraw();
+        }
+    }
+
     fn on_agent_reasoning_delta(&mut self, delta: String) {
         // For reasoning deltas, do not stream to history. Accumulate the
         // current reasoning block and extract the first bold element
@@ -510,6 +561,8 @@ impl ChatWidget {
     }
 
     fn on_plan_update(&mut self, update: codex_core::plan_tool::UpdatePlanArgs) {
+        self.bottom_pane
+            .set_plan_progress(PlanProgress::from_plan(&update));
         self.add_to_history(history_cell::new_plan_update(update));
     }
 
@@ -531,6 +584,14 @@ impl ChatWidget {
         );
     }
 
+    fn on_elicitation_request(&mut self, ev: ElicitationRequestEvent) {
+        let ev2 = ev.clone();
+        self.defer_or_handle(
+            |q| q.push_elicitation(ev),
+            |s| s.handle_elicitation_now(ev2),
+        );
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -578,6 +639,15 @@ impl ChatWidget {
         self.defer_or_handle(|q| q.push_mcp_begin(ev), |s| s.handle_mcp_begin_now(ev2));
     }
 
+    fn on_mcp_tool_call_progress(&mut self, ev: McpToolCallProgressEvent) {
+        // Long-running MCP tools report what they are doing in the status
+        // indicator; streamed output shows its first line.
+        let status = ev.to_string();
+        let status = status.lines().next().unwrap_or_default();
+        self.bottom_pane.update_status_header(status.to_string());
+        self.request_redraw();
+    }
+
     fn on_mcp_tool_call_end(&mut self, ev: McpToolCallEndEvent) {
         let ev2 = ev.clone();
         self.defer_or_handle(|q| q.push_mcp_end(ev), |s| s.handle_mcp_end_now(ev2));
@@ -631,12 +701,223 @@ impl ChatWidget {
         }
     }
 
+    fn on_context_compacted(&mut self, ev: ContextCompactedEvent) {
+        let mut details = Vec::new();
+        if ev.turns_summarized > 0 {
+            details.push(format!("{} turns summarized", ev.turns_summarized));
+        }
+        if ev.tool_outputs_dropped > 0 {
+            details.push(format!("{} tool outputs dropped", ev.tool_outputs_dropped));
+        }
+        if ev.pinned_items_kept > 0 {
+            details.push(format!("{} pinned items kept", ev.pinned_items_kept));
+        }
+        let hint = (!details.is_empty()).then(|| details.join(", "));
+        self.add_to_history(history_cell::new_info_event(
+            format!("Context compacted ({})", ev.strategy),
+            hint,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_sub_agent_begin(&mut self, ev: SubAgentBeginEvent) {
+        self.add_to_history(history_cell::new_info_event(
+            "Delegated task to a sub-agent".to_string(),
+            Some(ev.task),
+        ));
+        self.request_redraw();
+    }
+
+    fn on_sub_agent_end(&mut self, ev: SubAgentEndEvent) {
+        let status = match ev.outcome {
+            SubAgentOutcome::Completed => "finished",
+            SubAgentOutcome::BudgetExhausted => "ran out of budget",
+            SubAgentOutcome::Failed => "failed",
+        };
+        self.add_to_history(history_cell::new_info_event(
+            format!("Sub-agent {status}"),
+            Some(format!("Transcript: {}", ev.rollout_path.display())),
+        ));
+        self.request_redraw();
+    }
+
+    fn on_checkpoint_created(&mut self, ev: CheckpointCreatedEvent) {
+        let hint = (!ev.files_captured)
+            .then(|| "Not in a git repository; only the conversation was saved.".to_string());
+        self.add_to_history(history_cell::new_info_event(
+            format!("Saved checkpoint {}", ev.name),
+            hint,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_checkpoint_restored(&mut self, ev: CheckpointRestoredEvent) {
+        self.add_to_history(history_cell::new_info_event(
+            format!("Rolled back to checkpoint {}", ev.name),
+            None,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_session_busy(&mut self, ev: SessionBusyEvent) {
+        let message = if ev.queued > 0 {
+            format!(
+                "Another turn is running with {} more queued; this submission was not run.",
+                ev.queued
+            )
+        } else {
+            "Another turn is running; this submission was not run.".to_string()
+        };
+        self.add_to_history(history_cell::new_error_event(message));
+        self.request_redraw();
+    }
+
+    fn on_budget_exceeded(&mut self, id: String, ev: BudgetExceededEvent) {
+        self.add_to_history(history_cell::new_error_event(format!(
+            "Session budget reached: {ev}."
+        )));
+        let item = |name: &str, description: &str, decision: BudgetDecision| {
+            let id = id.clone();
+            let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
+                tx.send(AppEvent::CodexOp(Op::BudgetDecision {
+                    id: id.clone(),
+                    decision,
+                }));
+            })];
+            SelectionItem {
+                name: name.to_string(),
+                description: Some(description.to_string()),
+                actions,
+                dismiss_on_select: true,
+                ..Default::default()
+            }
+        };
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: Some("Session budget reached".to_string()),
+            subtitle: Some("The turn is paused. Continue with a fresh budget?".to_string()),
+            footer_hint: Some(standard_popup_hint_line()),
+            items: vec![
+                item(
+                    "Continue",
+                    "Resume the turn; the budget starts over from here",
+                    BudgetDecision::Continue,
+                ),
+                item("Stop", "End the turn", BudgetDecision::Stop),
+            ],
+            ..Default::default()
+        });
+        self.request_redraw();
+    }
+
+    fn on_turn_undone(&mut self, ev: TurnUndoneEvent) {
+        let TurnUndoneEvent { restored, removed } = ev;
+        let files = |n: usize| {
+            if n == 1 {
+                "1 file".to_string()
+            } else {
+                format!("{n} files")
+            }
+        };
+        self.add_to_history(history_cell::new_info_event(
+            "Undid the file changes of the last turn".to_string(),
+            Some(format!(
+                "Restored {}, removed {}.",
+                files(restored.len()),
+                files(removed.len())
+            )),
+        ));
+        self.request_redraw();
+    }
+
+    fn on_sandbox_denied(&mut self, ev: SandboxDeniedEvent) {
+        let SandboxDeniedEvent {
+            operation,
+            path,
+            host,
+            ..
+        } = ev;
+        let message = match (operation, path, host) {
+            (SandboxOperation::FileWrite, Some(path), _) => {
+                format!("Sandbox blocked writing to {}", path.display())
+            }
+            (SandboxOperation::Network, _, Some(host)) => {
+                format!("Sandbox blocked network access to {host}")
+            }
+            (SandboxOperation::FileWrite, None, _) => "Sandbox blocked a file write".to_string(),
+            (SandboxOperation::Network, _, None) => "Sandbox blocked network access".to_string(),
+        };
+        self.add_to_history(history_cell::new_warning_event(message));
+        self.request_redraw();
+    }
+
+    fn on_session_settings_updated(&mut self, ev: SessionSettingsUpdatedEvent) {
+        let SessionSettingsUpdatedEvent {
+            model,
+            approval_policy,
+            sandbox_policy,
+            reasoning_effort,
+            profile,
+        } = ev;
+        self.set_model(&model);
+        self.set_approval_policy(approval_policy);
+        self.set_sandbox_policy(sandbox_policy);
+        self.set_reasoning_effort(reasoning_effort);
+        if let Some(profile) = profile {
+            self.add_info_message(
+                format!("Switched to profile {profile} (model {model})"),
+                None,
+            );
+            self.config.active_profile = Some(profile);
+        }
+        self.request_redraw();
+    }
+
     fn on_stream_error(&mut self, message: String) {
         // Show stream errors in the transcript so users see retry/backoff info.
         self.add_to_history(history_cell::new_stream_error_event(message));
         self.request_redraw();
     }
 
+    fn on_cost_summary(&mut self, ev: CostSummaryEvent) {
+        let cost = SessionCost {
+            usd: ev.session_cost_usd(),
+            turns: ev.session.iter().map(|cost| cost.turns).sum(),
+            unpriced_turns: ev.session.iter().map(|cost| cost.unpriced_turns).sum(),
+        };
+        self.bottom_pane
+            .set_session_cost((cost.turns > 0).then_some(cost));
+    }
+
+    fn on_context_budget(&mut self, ev: ContextBudgetEvent) {
+        let percent = ev.percent_remaining();
+        if percent.is_some() {
+            self.bottom_pane.set_context_window_percent(percent);
+        }
+        match percent {
+            Some(percent) if percent <= CONTEXT_WARNING_PERCENT_REMAINING => {
+                if !self.context_budget_warned {
+                    self.context_budget_warned = true;
+                    self.add_to_history(history_cell::new_warning_event(format!(
+                        "Only {percent}% of the context window is left; Codex will compact the conversation before it overflows."
+                    )));
+                    self.request_redraw();
+                }
+            }
+            _ => self.context_budget_warned = false,
+        }
+    }
+
+    fn on_model_failover(&mut self, ev: ModelFailoverEvent) {
+        self.add_to_history(history_cell::new_info_event(
+            format!(
+                "Falling back to {} ({}) for this turn",
+                ev.model, ev.model_provider
+            ),
+            Some(ev.reason),
+        ));
+        self.request_redraw();
+    }
+
     /// Periodic tick to commit at most one queued line to history with a small delay,
     /// animating the output.
     pub(crate) fn on_commit_tick(&mut self) {
@@ -799,6 +1080,22 @@ impl ChatWidget {
         });
     }
 
+    pub(crate) fn handle_elicitation_now(&mut self, ev: ElicitationRequestEvent) {
+        self.flush_answer_stream_with_separator();
+        self.notify(Notification::ElicitationRequested {
+            server: ev.server.clone(),
+        });
+        self.bottom_pane
+            .show_view(Box::new(ElicitationFormView::new(
+                ev.id,
+                ev.server,
+                ev.message,
+                &ev.requested_schema,
+                self.app_event_tx.clone(),
+            )));
+        self.request_redraw();
+    }
+
     pub(crate) fn handle_exec_begin_now(&mut self, ev: ExecCommandBeginEvent) {
         // Ensure the status indicator is visible while the command runs.
         self.running_commands.insert(
@@ -936,6 +1233,7 @@ impl ChatWidget {
             token_info: None,
             rate_limit_snapshot: None,
             rate_limit_warnings: RateLimitWarningState::default(),
+            context_budget_warned: false,
             stream_controller: None,
             running_commands: HashMap::new(),
             task_complete_pending: false,
@@ -999,6 +1297,7 @@ impl ChatWidget {
             token_info: None,
             rate_limit_snapshot: None,
             rate_limit_warnings: RateLimitWarningState::default(),
+            context_budget_warned: false,
             stream_controller: None,
             running_commands: HashMap::new(),
             task_complete_pending: false,
@@ -1091,6 +1390,30 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::McpPrompt(invocation) => {
+                        // The expanded text is submitted once it arrives.
+                        self.submit_op(Op::GetMcpPrompt {
+                            server: invocation.server,
+                            name: invocation.name,
+                            arguments: invocation.arguments,
+                        });
+                    }
+                    InputResult::McpServer(command) => {
+                        self.submit_op(match command {
+                            McpServerCommand::Connect(name) => Op::ConnectMcpServer { name },
+                            McpServerCommand::Disconnect(name) => Op::DisconnectMcpServer { name },
+                        });
+                    }
+                    InputResult::SwitchProfile(name) => {
+                        if self.bottom_pane.is_task_running() {
+                            self.add_to_history(history_cell::new_error_event(
+                                "'/profile' is disabled while a task is in progress.".to_string(),
+                            ));
+                            self.request_redraw();
+                        } else {
+                            self.submit_op(Op::SwitchProfile { name });
+                        }
+                    }
                     InputResult::None => {}
                 }
             }
@@ -1143,6 +1466,9 @@ impl ChatWidget {
             SlashCommand::Approvals => {
                 self.open_approvals_popup();
             }
+            SlashCommand::Profile => {
+                self.open_profile_popup();
+            }
             SlashCommand::Quit => {
                 self.app_event_tx.send(AppEvent::ExitRequest);
             }
@@ -1340,6 +1666,12 @@ impl ChatWidget {
     }
 
     fn undo_last_snapshot(&mut self) {
+        // Without ghost snapshots, fall back to the file changes Codex
+        // recorded for each turn.
+        if self.ghost_snapshots_disabled {
+            self.submit_op(Op::UndoTurn);
+            return;
+        }
         let Some(commit) = self.ghost_snapshots.pop() else {
             self.add_info_message("No snapshot available to undo.".to_string(), None);
             return;
@@ -1384,6 +1716,8 @@ impl ChatWidget {
         match msg {
             EventMsg::AgentMessageDelta(_)
             | EventMsg::AgentReasoningDelta(_)
+            | EventMsg::ToolCallArgumentsDelta(_)
+            | EventMsg::PayloadFrame(_)
             | EventMsg::ExecCommandOutputDelta(_) => {}
             _ => {
                 tracing::trace!("handle_codex_event: {:?}", msg);
@@ -1396,6 +1730,7 @@ impl ChatWidget {
             EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
                 self.on_agent_message_delta(delta)
             }
+            EventMsg::ToolCallArgumentsDelta(ev) => self.on_tool_call_arguments_delta(ev),
             EventMsg::AgentReasoningDelta(AgentReasoningDeltaEvent { delta })
             | EventMsg::AgentReasoningRawContentDelta(AgentReasoningRawContentDeltaEvent {
                 delta,
@@ -1431,6 +1766,13 @@ impl ChatWidget {
                 // For replayed events, synthesize an empty id (these should not occur).
                 self.on_exec_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::ApprovalBatchRequest(ApprovalBatchRequestEvent { items }) => {
+                // Queue each item as its own approval; answering with the
+                // item's call_id resolves that entry of the batch.
+                for ev in items {
+                    self.on_exec_approval_request(ev.call_id.clone(), ev);
+                }
+            }
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
@@ -1441,12 +1783,19 @@ impl ChatWidget {
             EventMsg::ExecCommandEnd(ev) => self.on_exec_command_end(ev),
             EventMsg::ViewImageToolCall(ev) => self.on_view_image_tool_call(ev),
             EventMsg::McpToolCallBegin(ev) => self.on_mcp_tool_call_begin(ev),
+            EventMsg::McpToolCallProgress(ev) => self.on_mcp_tool_call_progress(ev),
             EventMsg::McpToolCallEnd(ev) => self.on_mcp_tool_call_end(ev),
+            EventMsg::ElicitationRequest(ev) => self.on_elicitation_request(ev),
             EventMsg::WebSearchBegin(ev) => self.on_web_search_begin(ev),
             EventMsg::WebSearchEnd(ev) => self.on_web_search_end(ev),
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::McpListPromptsResponse(ev) => self.on_list_mcp_prompts(ev),
+            EventMsg::McpGetPromptResponse(ev) => self.on_mcp_prompt_expanded(ev),
+            EventMsg::McpServerHealth(ev) => self.on_mcp_server_health(ev),
+            EventMsg::McpServerConnection(ev) => self.on_mcp_server_connection(ev),
+            EventMsg::ConfigReloaded(ev) => self.on_config_reloaded(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
             EventMsg::BackgroundProcessStatus(BackgroundProcessStatusEvent { running }) => {
@@ -1456,6 +1805,40 @@ impl ChatWidget {
                 self.on_background_event(message)
             }
             EventMsg::StreamError(StreamErrorEvent { message }) => self.on_stream_error(message),
+            EventMsg::ModelFailover(ev) => self.on_model_failover(ev),
+            EventMsg::ContextBudget(ev) => self.on_context_budget(ev),
+            EventMsg::SandboxDenied(ev) => self.on_sandbox_denied(ev),
+            EventMsg::SessionSettingsUpdated(ev) => self.on_session_settings_updated(ev),
+            EventMsg::UserInputQueue(_) => {
+                // The TUI keeps its own queue and submits with `Op::UserInput`.
+            }
+            EventMsg::SessionBusy(ev) => self.on_session_busy(ev),
+            EventMsg::BudgetExceeded(ev) => self.on_budget_exceeded(id.unwrap_or_default(), ev),
+            EventMsg::ContextCompacted(ev) => self.on_context_compacted(ev),
+            EventMsg::CheckpointCreated(ev) => self.on_checkpoint_created(ev),
+            EventMsg::CheckpointRestored(ev) => self.on_checkpoint_restored(ev),
+            EventMsg::TurnUndone(ev) => self.on_turn_undone(ev),
+            EventMsg::CostSummary(ev) => self.on_cost_summary(ev),
+            // Turn accounting is surfaced through `codex export` and the API.
+            EventMsg::TurnStats(_)
+            | EventMsg::TurnStatsResponse(_)
+            | EventMsg::TurnGitState(_)
+            | EventMsg::PinnedContext(_) => {}
+            // Only read back when the session is resumed.
+            EventMsg::WorkspaceState(_) => {}
+            // Shown by the resume picker.
+            EventMsg::SessionTitle(_) | EventMsg::SessionTags(_) => {}
+            // Only sent in reply to `Op::UpdateSessionEnv`, which the TUI does not submit.
+            EventMsg::SessionEnv(_) => {}
+            // Only sent in reply to `Op::GetSessionDiff` and `Op::ResolveHunks`,
+            // which the TUI does not submit.
+            EventMsg::SessionDiff(_) => {}
+            // Only sent in reply to `Op::BackgroundProcess`, which the TUI does not submit.
+            EventMsg::BackgroundProcessResponse(_) => {}
+            EventMsg::SubAgentBegin(ev) => self.on_sub_agent_begin(ev),
+            EventMsg::SubAgentEnd(ev) => self.on_sub_agent_end(ev),
+            // The TUI does not enable payload framing, so tool output stays inline.
+            EventMsg::PayloadFrame(_) => {}
             EventMsg::UserMessage(ev) => {
                 if from_replay {
                     self.on_user_message_event(ev);
@@ -1832,6 +2215,69 @@ impl ChatWidget {
         });
     }
 
+    /// Open a popup listing the profiles of the config to switch to.
+    pub(crate) fn open_profile_popup(&mut self) {
+        if self.config.profiles.is_empty() {
+            self.add_info_message(
+                "No profiles are configured".to_string(),
+                Some("Define profiles under [profiles] in config.toml".to_string()),
+            );
+            return;
+        }
+        let items: Vec<SelectionItem> = self
+            .config
+            .profiles
+            .iter()
+            .map(|(name, profile)| {
+                let is_current = self.config.active_profile.as_deref() == Some(name.as_str());
+                let description = Self::profile_description(profile);
+                let name = name.clone();
+                let profile_name = name.clone();
+                let actions: Vec<SelectionAction> = vec![Box::new(move |tx| {
+                    tx.send(AppEvent::CodexOp(Op::SwitchProfile {
+                        name: profile_name.clone(),
+                    }));
+                })];
+                SelectionItem {
+                    name,
+                    description,
+                    is_current,
+                    actions,
+                    dismiss_on_select: true,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        self.bottom_pane.show_selection_view(SelectionViewParams {
+            title: Some("Select Profile".to_string()),
+            subtitle: Some("Switch settings for the rest of this session".to_string()),
+            footer_hint: Some(standard_popup_hint_line()),
+            items,
+            ..Default::default()
+        });
+    }
+
+    fn profile_description(profile: &SessionProfile) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(model) = &profile.model {
+            parts.push(match profile.model_reasoning_effort {
+                Some(effort) => format!("{model} {effort}"),
+                None => model.clone(),
+            });
+        }
+        if let Some(approval_policy) = profile.approval_policy {
+            parts.push(format!("approvals {approval_policy}"));
+        }
+        if let Some(sandbox_policy) = &profile.sandbox_policy {
+            parts.push(format!("sandbox {sandbox_policy}"));
+        }
+        if profile.tool_filter != ToolFilter::default() {
+            parts.push("custom tools".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
     /// Set the approval policy in the widget's config copy.
     pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
         self.config.approval_policy = policy;
@@ -1938,6 +2384,59 @@ impl ChatWidget {
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
     }
 
+    fn on_list_mcp_prompts(&mut self, ev: McpListPromptsResponseEvent) {
+        debug!("received {} MCP prompts", ev.prompts.len());
+        self.bottom_pane.set_mcp_prompts(ev.prompts);
+    }
+
+    fn on_mcp_prompt_expanded(&mut self, ev: McpGetPromptResponseEvent) {
+        match ev.result {
+            Ok(text) => {
+                let user_message = UserMessage {
+                    text,
+                    image_paths: Vec::new(),
+                };
+                if self.bottom_pane.is_task_running() {
+                    self.queued_user_messages.push_back(user_message);
+                    self.refresh_queued_user_messages();
+                } else {
+                    self.submit_user_message(user_message);
+                }
+            }
+            Err(message) => {
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "Failed to expand /{MCP_PROMPTS_CMD_PREFIX}:{}:{}: {message}",
+                    ev.server, ev.name
+                )));
+            }
+        }
+        self.request_redraw();
+    }
+
+    fn on_mcp_server_health(&mut self, ev: McpServerHealthEvent) {
+        let message = format!("{ev}.");
+        match ev.status {
+            McpServerHealthStatus::Reconnected { .. } => self.add_info_message(message, None),
+            McpServerHealthStatus::Unresponsive { .. } | McpServerHealthStatus::Failed { .. } => {
+                self.add_error_message(message)
+            }
+        }
+    }
+
+    fn on_mcp_server_connection(&mut self, ev: McpServerConnectionEvent) {
+        self.add_info_message(format!("{ev}."), None);
+    }
+
+    fn on_config_reloaded(&mut self, ev: ConfigReloadedEvent) {
+        if let Some(approval_policy) = ev.approval_policy {
+            self.set_approval_policy(approval_policy);
+        }
+        if ev.applied.is_empty() && ev.requires_restart.is_empty() {
+            return;
+        }
+        self.add_info_message(format!("{ev}."), None);
+    }
+
     pub(crate) fn open_review_popup(&mut self) {
         let mut items: Vec<SelectionItem> = Vec::new();
 
@@ -2161,6 +2660,7 @@ enum Notification {
     AgentTurnComplete { response: String },
     ExecApprovalRequested { command: String },
     EditApprovalRequested { cwd: PathBuf, changes: Vec<PathBuf> },
+    ElicitationRequested { server: String },
 }
 
 impl Notification {
@@ -2173,6 +2673,9 @@ impl Notification {
             Notification::ExecApprovalRequested { command } => {
                 format!("Approval requested: {}", truncate_text(command, 30))
             }
+            Notification::ElicitationRequested { server } => {
+                format!("MCP server {server} asks for input")
+            }
             Notification::EditApprovalRequested { cwd, changes } => {
                 format!(
                     "Codex wants to edit {}",
@@ -2191,7 +2694,8 @@ impl Notification {
         match self {
             Notification::AgentTurnComplete { .. } => "agent-turn-complete",
             Notification::ExecApprovalRequested { .. }
-            | Notification::EditApprovalRequested { .. } => "approval-requested",
+            | Notification::EditApprovalRequested { .. }
+            | Notification::ElicitationRequested { .. } => "approval-requested",
         }
     }
 
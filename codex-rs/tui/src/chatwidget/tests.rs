@@ -18,6 +18,10 @@ use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::BackgroundProcessStatusEvent;
+use codex_core::protocol::BudgetDecision;
+use codex_core::protocol::BudgetExceededEvent;
+use codex_core::protocol::BudgetKind;
+use codex_core::protocol::ContextBudgetEvent;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
@@ -44,6 +48,7 @@ use crossterm::event::KeyEvent;
 use crossterm::event::KeyModifiers;
 use insta::assert_snapshot;
 use pretty_assertions::assert_eq;
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
@@ -270,6 +275,7 @@ fn make_chatwidget_manual() -> (
         token_info: None,
         rate_limit_snapshot: None,
         rate_limit_warnings: RateLimitWarningState::default(),
+        context_budget_warned: false,
         stream_controller: None,
         running_commands: HashMap::new(),
         task_complete_pending: false,
@@ -376,6 +382,32 @@ fn test_rate_limit_warnings_monthly() {
     );
 }
 
+#[test]
+fn context_budget_warns_once_when_nearly_full() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    let budget = |estimated_prompt_tokens| Event {
+        id: "sub-1".into(),
+        msg: EventMsg::ContextBudget(ContextBudgetEvent {
+            estimated_prompt_tokens,
+            model_context_window: Some(112_000),
+            remaining_tokens: Some(112_000 - estimated_prompt_tokens),
+        }),
+    };
+
+    chat.handle_codex_event(budget(50_000));
+    assert!(drain_insert_history(&mut rx).is_empty());
+
+    chat.handle_codex_event(budget(105_000));
+    chat.handle_codex_event(budget(106_000));
+    let cells = drain_insert_history(&mut rx);
+    assert_eq!(cells.len(), 1, "expected a single warning: {cells:?}");
+    assert!(lines_to_single_string(&cells[0]).contains("7% of the context window is left"));
+
+    chat.handle_codex_event(budget(20_000));
+    chat.handle_codex_event(budget(108_000));
+    assert_eq!(drain_insert_history(&mut rx).len(), 1);
+}
+
 // (removed experimental resize snapshot test)
 
 #[test]
@@ -514,6 +546,7 @@ fn begin_exec(chat: &mut ChatWidget, call_id: &str, raw_cmd: &str) {
             command,
             cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
             parsed_cmd,
+            session_env: BTreeMap::new(),
         }),
     });
 }
@@ -1240,6 +1273,7 @@ async fn binary_size_transcript_snapshot() {
                                         .into_iter()
                                         .map(std::convert::Into::into)
                                         .collect(),
+                                    session_env: BTreeMap::new(),
                                 }),
                             }
                         }
@@ -1877,6 +1911,33 @@ fn apply_patch_approval_sends_op_with_submission_id() {
     assert!(found, "expected PatchApproval op to be sent");
 }
 
+#[test]
+fn budget_exceeded_popup_sends_decision_with_submission_id() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    chat.handle_codex_event(Event {
+        id: "sub-7".into(),
+        msg: EventMsg::BudgetExceeded(BudgetExceededEvent {
+            budget: BudgetKind::ToolCalls,
+            limit: 20.0,
+            used: 20.0,
+        }),
+    });
+
+    // The first option continues the turn.
+    chat.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+    let mut found = false;
+    while let Ok(app_ev) = rx.try_recv() {
+        if let AppEvent::CodexOp(Op::BudgetDecision { id, decision }) = app_ev {
+            assert_eq!(id, "sub-7");
+            assert_eq!(decision, BudgetDecision::Continue);
+            found = true;
+            break;
+        }
+    }
+    assert!(found, "expected BudgetDecision op to be sent");
+}
+
 #[test]
 fn apply_patch_full_flow_integration_like() {
     let (mut chat, mut rx, mut op_rx) = make_chatwidget_manual();
@@ -2285,6 +2346,7 @@ fn chatwidget_exec_and_status_layout_vt100_snapshot() {
                 }
                 .into(),
             ],
+            session_env: BTreeMap::new(),
         }),
     });
     chat.handle_codex_event(Event {
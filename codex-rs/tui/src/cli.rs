@@ -72,6 +72,11 @@ pub struct Cli {
     #[arg(long = "search", default_value_t = false)]
     pub web_search: bool,
 
+    /// Tag the session, e.g. `--tag ticket=ABC-123`. May be repeated. With
+    /// `resume --last`, only sessions carrying these tags are considered.
+    #[arg(long = "tag", value_name = "KEY=VALUE")]
+    pub tags: Vec<codex_common::SessionTagCliArg>,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 }
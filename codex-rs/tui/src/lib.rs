@@ -146,10 +146,11 @@ pub async fn run_main(
         include_view_image_tool: None,
         show_raw_agent_reasoning: cli.oss.then_some(true),
         tools_web_search_request: cli.web_search.then_some(true),
+        client_capabilities: None,
     };
     let raw_overrides = cli.config_overrides.raw_overrides.clone();
     let overrides_cli = codex_common::CliConfigOverrides { raw_overrides };
-    let cli_kv_overrides = match overrides_cli.parse_overrides() {
+    let mut cli_kv_overrides = match overrides_cli.parse_overrides() {
         Ok(v) => v,
         #[allow(clippy::print_stderr)]
         Err(e) => {
@@ -157,12 +158,17 @@ pub async fn run_main(
             std::process::exit(1);
         }
     };
+    cli_kv_overrides.extend(
+        cli.tags
+            .iter()
+            .map(codex_common::SessionTagCliArg::to_config_override),
+    );
 
     let mut config = {
         // Load configuration and support CLI overrides.
 
         #[allow(clippy::print_stderr)]
-        match Config::load_with_cli_overrides(cli_kv_overrides.clone(), overrides) {
+        match Config::load_with_cli_overrides(cli_kv_overrides.clone(), overrides.clone()) {
             Ok(config) => config,
             Err(err) => {
                 eprintln!("Error loading configuration: {err}");
@@ -182,7 +188,7 @@ pub async fn run_main(
             }
         };
 
-        match load_config_as_toml_with_cli_overrides(&codex_home, cli_kv_overrides) {
+        match load_config_as_toml_with_cli_overrides(&codex_home, cli_kv_overrides.clone()) {
             Ok(config_toml) => config_toml,
             Err(err) => {
                 eprintln!("Error loading config.toml: {err}");
@@ -269,9 +275,19 @@ pub async fn run_main(
         let _ = tracing_subscriber::registry().with(file_layer).try_init();
     };
 
-    run_ratatui_app(cli, config, active_profile, should_show_trust_screen)
-        .await
-        .map_err(|err| std::io::Error::other(err.to_string()))
+    // Trusting the folder on the trust screen applies its project config,
+    // which takes loading the configuration again.
+    let reload_config = move || Config::load_with_cli_overrides(cli_kv_overrides, overrides);
+
+    run_ratatui_app(
+        cli,
+        config,
+        active_profile,
+        should_show_trust_screen,
+        reload_config,
+    )
+    .await
+    .map_err(|err| std::io::Error::other(err.to_string()))
 }
 
 async fn run_ratatui_app(
@@ -279,6 +295,7 @@ async fn run_ratatui_app(
     config: Config,
     active_profile: Option<String>,
     should_show_trust_screen: bool,
+    reload_config: impl FnOnce() -> std::io::Result<Config>,
 ) -> color_eyre::Result<AppExitInfo> {
     let mut config = config;
     color_eyre::install()?;
@@ -364,13 +381,17 @@ async fn run_ratatui_app(
 
     let auth_manager = AuthManager::shared(config.codex_home.clone(), false);
     let login_status = get_login_status(&config);
-    let should_show_onboarding =
-        should_show_onboarding(login_status, &config, should_show_trust_screen);
+    let untrusted_project_config = config
+        .project_config
+        .as_ref()
+        .is_some_and(|file| !file.trusted);
+    let show_trust_screen = should_show_trust_screen || untrusted_project_config;
+    let should_show_onboarding = should_show_onboarding(login_status, &config, show_trust_screen);
     if should_show_onboarding {
         let directory_trust_decision = run_onboarding_app(
             OnboardingScreenArgs {
                 show_login_screen: should_show_login_screen(login_status, &config),
-                show_trust_screen: should_show_trust_screen,
+                show_trust_screen,
                 login_status,
                 auth_manager: auth_manager.clone(),
                 config: config.clone(),
@@ -379,8 +400,13 @@ async fn run_ratatui_app(
         )
         .await?;
         if let Some(TrustDirectorySelection::Trust) = directory_trust_decision {
-            config.approval_policy = AskForApproval::OnRequest;
-            config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+            if untrusted_project_config {
+                config = reload_config()?;
+            }
+            if should_show_trust_screen {
+                config.approval_policy = AskForApproval::OnRequest;
+                config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+            }
         }
     }
 
@@ -394,11 +420,17 @@ async fn run_ratatui_app(
             }
         }
     } else if cli.resume_last {
-        match RolloutRecorder::list_conversations(
+        let required_tags = cli
+            .tags
+            .iter()
+            .map(|tag| (tag.key.clone(), tag.value.clone()))
+            .collect();
+        match RolloutRecorder::list_conversations_with_tags(
             &config.codex_home,
             1,
             None,
             INTERACTIVE_SESSION_SOURCES,
+            &required_tags,
         )
         .await
         {
@@ -72,6 +72,10 @@ impl OnboardingScreen {
             config,
         } = args;
         let cwd = config.cwd.clone();
+        let project_config = config
+            .project_config
+            .filter(|file| !file.trusted)
+            .map(|file| file.path);
         let codex_home = config.codex_home;
         let mut steps: Vec<Step> = vec![Step::Welcome(WelcomeWidget::new(
             !matches!(login_status, LoginStatus::NotAuthenticated),
@@ -100,6 +104,7 @@ impl OnboardingScreen {
                 cwd,
                 codex_home,
                 is_git_repo,
+                project_config,
                 selection: None,
                 highlighted,
                 error: None,
@@ -24,6 +24,8 @@ pub(crate) struct TrustDirectoryWidget {
     pub codex_home: PathBuf,
     pub cwd: PathBuf,
     pub is_git_repo: bool,
+    /// The project config that is applied once the folder is trusted.
+    pub project_config: Option<PathBuf>,
     pub selection: Option<TrustDirectorySelection>,
     pub highlighted: TrustDirectorySelection,
     pub error: Option<String>,
@@ -57,6 +59,18 @@ impl WidgetRef for &TrustDirectoryWidget {
             );
             lines.push("  approval of all edits and commands.".into());
         }
+        if let Some(project_config) = &self.project_config {
+            lines.push("".into());
+            lines.push(Line::from(vec![
+                "  This folder has a project config at ".into(),
+                project_config.to_string_lossy().to_string().bold(),
+                ".".into(),
+            ]));
+            lines.push(
+                "  It can change the model, sandbox, tools and MCP servers, and is only".into(),
+            );
+            lines.push("  applied if you trust this folder.".into());
+        }
         lines.push("".into());
 
         let create_option =
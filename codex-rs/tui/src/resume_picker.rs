@@ -64,8 +64,9 @@ enum BackgroundEvent {
 }
 
 /// Interactive session picker that lists recorded rollout files with simple
-/// search and pagination. Shows the first user input as the preview, relative
-/// time (e.g., "5 seconds ago"), and the absolute path.
+/// search and pagination. Shows the session title, or the first user input
+/// for untitled sessions, as the preview, relative time (e.g., "5 seconds
+/// ago"), and the absolute path.
 pub async fn run_resume_picker(tui: &mut Tui, codex_home: &Path) -> Result<ResumeSelection> {
     let alt = AltScreenGuard::enter(tui);
     let (bg_tx, bg_rx) = mpsc::unbounded_channel();
@@ -586,10 +587,25 @@ fn head_to_row(item: &ConversationItem) -> Row {
         .and_then(parse_timestamp_str)
         .or(created_at);
 
-    let preview = preview_from_head(&item.head)
+    let mut preview = item
+        .title
+        .clone()
+        .or_else(|| preview_from_head(&item.head))
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
         .unwrap_or_else(|| String::from("(no message yet)"));
+    // Tags are part of the preview so searching for them finds the session.
+    if !item.tags.is_empty() {
+        let tags: Vec<String> = item
+            .tags
+            .iter()
+            .map(|(key, value)| match value.as_str() {
+                "" => key.clone(),
+                value => format!("{key}={value}"),
+            })
+            .collect();
+        preview.push_str(&format!(" [{}]", tags.join(", ")));
+    }
 
     Row {
         path: item.path.clone(),
@@ -934,6 +950,7 @@ mod tests {
     use crossterm::event::KeyModifiers;
     use insta::assert_snapshot;
     use serde_json::json;
+    use std::collections::BTreeMap;
     use std::future::Future;
     use std::path::PathBuf;
     use std::sync::Arc;
@@ -960,6 +977,8 @@ mod tests {
             tail: Vec::new(),
             created_at: Some(ts.to_string()),
             updated_at: Some(ts.to_string()),
+            title: None,
+            tags: BTreeMap::new(),
         }
     }
 
@@ -1022,6 +1041,8 @@ mod tests {
             tail: Vec::new(),
             created_at: Some("2025-01-01T00:00:00Z".into()),
             updated_at: Some("2025-01-01T00:00:00Z".into()),
+            title: None,
+            tags: BTreeMap::new(),
         };
         let b = ConversationItem {
             path: PathBuf::from("/tmp/b.jsonl"),
@@ -1029,6 +1050,8 @@ mod tests {
             tail: Vec::new(),
             created_at: Some("2025-01-02T00:00:00Z".into()),
             updated_at: Some("2025-01-02T00:00:00Z".into()),
+            title: None,
+            tags: BTreeMap::new(),
         };
         let rows = rows_from_items(vec![a, b]);
         assert_eq!(rows.len(), 2);
@@ -1057,6 +1080,8 @@ mod tests {
             tail,
             created_at: Some("2025-01-01T00:00:00Z".into()),
             updated_at: Some("2025-01-01T01:00:00Z".into()),
+            title: None,
+            tags: BTreeMap::new(),
         };
 
         let row = head_to_row(&item);
@@ -1071,6 +1096,34 @@ mod tests {
         assert_eq!(row.updated_at, Some(expected_updated));
     }
 
+    #[test]
+    fn row_prefers_session_title_over_first_message() {
+        let item = ConversationItem {
+            title: Some("Fix flaky websocket test".into()),
+            ..make_item(
+                "/tmp/a.jsonl",
+                "2025-01-01T00:00:00Z",
+                "why does ws_test fail?",
+            )
+        };
+        assert_eq!(head_to_row(&item).preview, "Fix flaky websocket test");
+    }
+
+    #[test]
+    fn row_preview_lists_session_tags() {
+        let item = ConversationItem {
+            tags: BTreeMap::from([
+                ("ticket".to_string(), "ABC-1".to_string()),
+                ("urgent".to_string(), String::new()),
+            ]),
+            ..make_item("/tmp/a.jsonl", "2025-01-01T00:00:00Z", "fix the upload")
+        };
+        assert_eq!(
+            head_to_row(&item).preview,
+            "fix the upload [ticket=ABC-1, urgent]"
+        );
+    }
+
     #[test]
     fn resume_table_snapshot() {
         use crate::custom_terminal::Terminal;
@@ -4,6 +4,8 @@
 use std::time::Duration;
 use std::time::Instant;
 
+use codex_core::plan_tool::StepStatus;
+use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::protocol::Op;
 use crossterm::event::KeyCode;
 use ratatui::buffer::Buffer;
@@ -28,6 +30,8 @@ pub(crate) struct StatusIndicatorWidget {
     queued_messages: Vec<String>,
     /// Count of currently running background processes.
     background_process_count: usize,
+    /// Progress through the model's plan, shown under the status line.
+    plan_progress: Option<PlanProgress>,
 
     elapsed_running: Duration,
     last_resume_at: Instant,
@@ -36,6 +40,38 @@ pub(crate) struct StatusIndicatorWidget {
     frame_requester: FrameRequester,
 }
 
+/// How far the model has come through its plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PlanProgress {
+    pub(crate) completed: usize,
+    pub(crate) total: usize,
+    /// The step in progress, if any.
+    pub(crate) current_step: Option<String>,
+}
+
+impl PlanProgress {
+    /// `None` for an empty or finished plan.
+    pub(crate) fn from_plan(update: &UpdatePlanArgs) -> Option<Self> {
+        let completed = update
+            .plan
+            .iter()
+            .filter(|item| matches!(item.status, StepStatus::Completed))
+            .count();
+        if completed == update.plan.len() {
+            return None;
+        }
+        Some(Self {
+            completed,
+            total: update.plan.len(),
+            current_step: update
+                .plan
+                .iter()
+                .find(|item| matches!(item.status, StepStatus::InProgress))
+                .map(|item| item.step.clone()),
+        })
+    }
+}
+
 // Format elapsed seconds into a compact human-friendly form used by the status line.
 // Examples: 0s, 59s, 1m 00s, 59m 59s, 1h 00m 00s, 2h 03m 09s
 pub fn fmt_elapsed_compact(elapsed_secs: u64) -> String {
@@ -59,6 +95,7 @@ impl StatusIndicatorWidget {
             header: String::from("Working"),
             queued_messages: Vec::new(),
             background_process_count: 0,
+            plan_progress: None,
             elapsed_running: Duration::ZERO,
             last_resume_at: Instant::now(),
             is_paused: false,
@@ -69,10 +106,13 @@ impl StatusIndicatorWidget {
     }
 
     pub fn desired_height(&self, width: u16) -> u16 {
-        // Status line + optional blank line + wrapped queued messages (up to 3 lines per message)
+        // Status line + optional plan line + optional blank line + wrapped queued messages (up to 3 lines per message)
         // + optional ellipsis line per truncated message + 1 spacer line
         let inner_width = width.max(1) as usize;
         let mut total: u16 = 1; // status line
+        if self.plan_progress.is_some() {
+            total = total.saturating_add(1); // plan line
+        }
         if !self.queued_messages.is_empty() {
             total = total.saturating_add(1); // blank line between status and queued messages
         }
@@ -121,6 +161,13 @@ impl StatusIndicatorWidget {
         }
     }
 
+    pub(crate) fn set_plan_progress(&mut self, progress: Option<PlanProgress>) {
+        if self.plan_progress != progress {
+            self.plan_progress = progress;
+            self.frame_requester.schedule_frame();
+        }
+    }
+
     pub(crate) fn pause_timer(&mut self) {
         self.pause_timer_at(Instant::now());
     }
@@ -183,6 +230,17 @@ impl WidgetRef for StatusIndicatorWidget {
         // Build lines: status, then queued messages, then spacer.
         let mut lines: Vec<Line<'static>> = Vec::new();
         lines.push(Line::from(spans));
+        if let Some(progress) = &self.plan_progress {
+            let mut plan = vec![
+                " ".repeat(LIVE_PREFIX_COLS as usize).into(),
+                format!("Plan {}/{}", progress.completed, progress.total).dim(),
+            ];
+            if let Some(step) = &progress.current_step {
+                plan.push(" · ".dim());
+                plan.push(step.clone().into());
+            }
+            lines.push(Line::from(plan));
+        }
         if !self.queued_messages.is_empty() {
             lines.push(Line::from(""));
         }
@@ -221,6 +279,7 @@ mod tests {
     use super::*;
     use crate::app_event::AppEvent;
     use crate::app_event_sender::AppEventSender;
+    use codex_core::plan_tool::PlanItemArg;
     use ratatui::Terminal;
     use ratatui::backend::TestBackend;
     use std::time::Duration;
@@ -286,6 +345,73 @@ mod tests {
         insta::assert_snapshot!(terminal.backend());
     }
 
+    #[test]
+    fn renders_with_plan_progress() {
+        let (tx_raw, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let mut w = StatusIndicatorWidget::new(tx, crate::tui::FrameRequester::test_dummy());
+        w.set_plan_progress(Some(PlanProgress {
+            completed: 1,
+            total: 3,
+            current_step: Some("Write the tests".to_string()),
+        }));
+
+        // Render into a fixed-size test terminal and snapshot the backend.
+        let mut terminal = Terminal::new(TestBackend::new(80, 3)).expect("terminal");
+        terminal
+            .draw(|f| w.render_ref(f.area(), f.buffer_mut()))
+            .expect("draw");
+        insta::assert_snapshot!(terminal.backend());
+    }
+
+    #[test]
+    fn plan_progress_counts_steps_until_finished() {
+        let update = UpdatePlanArgs {
+            explanation: None,
+            plan: vec![
+                PlanItemArg {
+                    step: "Read the parser".to_string(),
+                    status: StepStatus::Completed,
+                },
+                PlanItemArg {
+                    step: "Write the tests".to_string(),
+                    status: StepStatus::InProgress,
+                },
+                PlanItemArg {
+                    step: "Fix the bug".to_string(),
+                    status: StepStatus::Pending,
+                },
+            ],
+        };
+        assert_eq!(
+            PlanProgress::from_plan(&update),
+            Some(PlanProgress {
+                completed: 1,
+                total: 3,
+                current_step: Some("Write the tests".to_string()),
+            })
+        );
+        assert_eq!(
+            PlanProgress::from_plan(&UpdatePlanArgs {
+                explanation: None,
+                plan: Vec::new(),
+            }),
+            None
+        );
+        let finished = UpdatePlanArgs {
+            explanation: None,
+            plan: update
+                .plan
+                .into_iter()
+                .map(|item| PlanItemArg {
+                    status: StepStatus::Completed,
+                    ..item
+                })
+                .collect(),
+        };
+        assert_eq!(PlanProgress::from_plan(&finished), None);
+    }
+
     #[test]
     fn timer_pauses_when_requested() {
         let (tx_raw, _rx) = unbounded_channel::<AppEvent>();